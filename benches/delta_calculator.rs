@@ -0,0 +1,44 @@
+//! DeltaCalculator update/calculate with a full 24h history.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rust_test::backtest::DeltaCalculator;
+use rust_test::testing::ScenarioBuilder;
+
+const DAY_SECS: usize = 24 * 3_600;
+
+fn bench_delta_calculator(c: &mut Criterion) {
+    // 24h истории при шаге 1с + следующий час для замера update
+    let mut day = ScenarioBuilder::new("BTC_USDT", 40_000.0)
+        .step_ms(1_000)
+        .choppy(2.0, 25 * 3_600_000, 3_600_000)
+        .build();
+    let next_hour = day.split_off(DAY_SECS);
+
+    let mut calculator = DeltaCalculator::new();
+    for tick in &day {
+        calculator.update(tick, tick.timestamp);
+    }
+    let last = day.last().unwrap();
+
+    let mut group = c.benchmark_group("delta_calculator_24h");
+    group.bench_function("calculate_deltas", |b| {
+        b.iter(|| black_box(calculator.calculate_deltas(last.price, last.timestamp)))
+    });
+    group.throughput(Throughput::Elements(next_hour.len() as u64));
+    group.bench_function("update_1h", |b| {
+        b.iter_batched(
+            || calculator.clone(),
+            |mut calculator| {
+                for tick in &next_hour {
+                    calculator.update(tick, tick.timestamp);
+                }
+                calculator
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_delta_calculator);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+//! on_tick throughput for Hook and MStrike on a choppy market without detections
+//! (the steady-state path every tick goes through).
+
+use chrono::Duration;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_test::strategy::moon_strategies::mshot::Deltas;
+use rust_test::strategy::moon_strategies::{HookConfig, HookStrategy, MStrikeConfig, MStrikeStrategy};
+use rust_test::testing::ScenarioBuilder;
+
+const STEP_MS: i64 = 100;
+
+fn bench_hook(c: &mut Criterion) {
+    let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+        .step_ms(STEP_MS)
+        .choppy(0.5, 600_000, 5_000)
+        .build();
+    let deltas = Deltas::default();
+
+    let mut group = c.benchmark_group("hook_on_tick");
+    group.throughput(Throughput::Elements(ticks.len() as u64));
+    for window_ms in [2_000i64, 30_000, 300_000] {
+        group.bench_with_input(
+            BenchmarkId::new("window_ticks", window_ms / STEP_MS),
+            &window_ms,
+            |b, &window_ms| {
+                b.iter(|| {
+                    let mut strategy = HookStrategy::new(HookConfig {
+                        hook_time_frame: Duration::milliseconds(window_ms),
+                        ..Default::default()
+                    });
+                    for tick in &ticks {
+                        black_box(strategy.on_tick(tick, &deltas));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_mstrike(c: &mut Criterion) {
+    let deltas = Deltas::default();
+
+    let mut group = c.benchmark_group("mstrike_on_tick");
+    for duration_ms in [60_000i64, 600_000] {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .step_ms(STEP_MS)
+            .choppy(0.5, duration_ms, 5_000)
+            .build();
+        group.throughput(Throughput::Elements(ticks.len() as u64));
+        group.bench_with_input(BenchmarkId::new("ticks", ticks.len()), &ticks, |b, ticks| {
+            b.iter(|| {
+                let mut strategy = MStrikeStrategy::new(MStrikeConfig::default());
+                for tick in ticks {
+                    black_box(strategy.on_tick(tick, &deltas));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_hook, bench_mstrike);
+criterion_main!(benches);
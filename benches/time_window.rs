@@ -0,0 +1,60 @@
+//! TimeWindow push/evict with incremental aggregates against a naive rescan.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rust_test::strategy::moon_strategies::{Extremes, Sum, TimeWindow};
+use std::collections::VecDeque;
+
+const POINTS: usize = 100_000;
+
+fn points() -> Vec<(DateTime<Utc>, f64)> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    // Пила с шумом: экстремумы окна постоянно меняются
+    (0..POINTS)
+        .map(|i| (start + Duration::milliseconds(i as i64 * 100), 100.0 + (i % 997) as f64 * 0.01 - (i % 13) as f64 * 0.05))
+        .collect()
+}
+
+fn bench_time_window(c: &mut Criterion) {
+    let points = points();
+    // Окно 60с при шаге 100мс — 600 точек, как HookTimeFrame на активном символе
+    let span = Duration::seconds(60);
+
+    let mut group = c.benchmark_group("time_window_60s");
+    group.throughput(Throughput::Elements(points.len() as u64));
+    group.bench_function("push_sum_extremes", |b| {
+        b.iter(|| {
+            let mut window: TimeWindow<f64, (Sum, Extremes)> = TimeWindow::new();
+            let mut acc = 0.0;
+            for &(t, v) in &points {
+                window.push(t, v);
+                window.evict_before(t - span);
+                let (sum, extremes) = window.aggregate();
+                acc += sum.sum() + extremes.max().unwrap_or(0.0) - extremes.min().unwrap_or(0.0);
+            }
+            black_box(acc)
+        })
+    });
+    group.bench_function("naive_rescan", |b| {
+        b.iter(|| {
+            let mut window: VecDeque<(DateTime<Utc>, f64)> = VecDeque::new();
+            let mut acc = 0.0;
+            for &(t, v) in &points {
+                window.push_back((t, v));
+                while window.front().is_some_and(|&(front, _)| front < t - span) {
+                    window.pop_front();
+                }
+                let values = window.iter().map(|&(_, v)| v);
+                let sum: f64 = values.clone().sum();
+                let max = values.clone().fold(f64::MIN, f64::max);
+                let min = values.fold(f64::MAX, f64::min);
+                acc += sum + max - min;
+            }
+            black_box(acc)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_time_window);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+//! Build metadata: the git commit the binary was built from, stamped into stored
+//! orders and journal lines (see `database::versioning`)
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    // Builds without a checkout (Docker context, source tarball) pass GIT_SHA instead
+    let sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(git_sha)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_SHA={}", sha);
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+fn git_sha() -> Option<String> {
+    let git_dir = git(&["rev-parse", "--git-dir"])?;
+    // A commit or checkout moves HEAD or a branch ref
+    for path in ["HEAD", "refs/heads", "packed-refs"] {
+        println!("cargo:rerun-if-changed={}/{}", git_dir, path);
+    }
+    git(&["rev-parse", "--short=12", "HEAD"])
+}
@@ -0,0 +1,53 @@
+-- =================================================================
+-- Live trading state shared by the SQLite and PostgreSQL backends
+-- Portable SQL only: no SERIAL, no TIMESTAMPTZ, timestamps are unix ms.
+-- Every row carries the bot instance so several instances can share one database.
+-- =================================================================
+
+CREATE TABLE IF NOT EXISTS state_positions (
+    instance TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    -- Signed base size: positive long, negative short, zero flat
+    size DOUBLE PRECISION NOT NULL,
+    entry_price DOUBLE PRECISION NOT NULL,
+    liq_price DOUBLE PRECISION,
+    realised_pnl DOUBLE PRECISION NOT NULL,
+    updated_ms BIGINT NOT NULL,
+    PRIMARY KEY (instance, symbol)
+);
+
+CREATE TABLE IF NOT EXISTS state_orders (
+    instance TEXT NOT NULL,
+    exchange_order_id TEXT NOT NULL,
+    client_order_id TEXT,
+    symbol TEXT NOT NULL,
+    side TEXT NOT NULL CHECK (side IN ('buy', 'sell')),
+    status TEXT NOT NULL,
+    price DOUBLE PRECISION NOT NULL,
+    size DOUBLE PRECISION NOT NULL,
+    filled DOUBLE PRECISION NOT NULL,
+    avg_fill_price DOUBLE PRECISION,
+    updated_ms BIGINT NOT NULL,
+    PRIMARY KEY (instance, exchange_order_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_state_orders_status ON state_orders (instance, status);
+
+CREATE TABLE IF NOT EXISTS state_journal (
+    instance TEXT NOT NULL,
+    seq BIGINT NOT NULL,
+    ts_ms BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    symbol TEXT,
+    message TEXT NOT NULL,
+    PRIMARY KEY (instance, seq)
+);
+
+CREATE TABLE IF NOT EXISTS state_equity (
+    instance TEXT NOT NULL,
+    ts_ms BIGINT NOT NULL,
+    equity DOUBLE PRECISION NOT NULL,
+    balance DOUBLE PRECISION NOT NULL,
+    unrealised_pnl DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (instance, ts_ms)
+);
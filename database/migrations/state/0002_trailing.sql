@@ -0,0 +1,19 @@
+-- =================================================================
+-- Client-side trailing stops: the watermark must survive a restart,
+-- otherwise the trail restarts from the current price and gives back profit.
+-- =================================================================
+
+CREATE TABLE IF NOT EXISTS state_trailing (
+    instance TEXT NOT NULL,
+    trail_key TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    -- Side of the closing order
+    side TEXT NOT NULL CHECK (side IN ('buy', 'sell')),
+    size DOUBLE PRECISION NOT NULL,
+    trail DOUBLE PRECISION NOT NULL,
+    activation DOUBLE PRECISION,
+    -- NULL until the activation price is reached
+    watermark DOUBLE PRECISION,
+    updated_ms BIGINT NOT NULL,
+    PRIMARY KEY (instance, trail_key)
+);
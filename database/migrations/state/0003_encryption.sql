@@ -0,0 +1,21 @@
+-- =================================================================
+-- Optional encryption at rest: sensitive fields of a row are sealed
+-- into `sealed` and their plaintext columns hold zeros / NULL.
+-- Rows written before encryption was enabled keep `sealed` NULL.
+-- =================================================================
+
+CREATE TABLE IF NOT EXISTS state_encryption (
+    instance TEXT PRIMARY KEY,
+    -- Argon2id parameters, JSON
+    kdf TEXT NOT NULL,
+    salt TEXT NOT NULL,
+    -- Known plaintext sealed with the key, to reject a wrong passphrase at open
+    check_value TEXT NOT NULL,
+    created_ms BIGINT NOT NULL
+);
+
+ALTER TABLE state_positions ADD COLUMN sealed TEXT;
+ALTER TABLE state_orders ADD COLUMN sealed TEXT;
+ALTER TABLE state_journal ADD COLUMN sealed TEXT;
+ALTER TABLE state_trailing ADD COLUMN sealed TEXT;
+ALTER TABLE state_equity ADD COLUMN sealed TEXT;
@@ -0,0 +1,22 @@
+-- =================================================================
+-- Append-only audit of configuration changes: one row per changed key.
+-- The store never updates or deletes these rows, compaction included.
+-- =================================================================
+
+CREATE TABLE IF NOT EXISTS state_config_audit (
+    instance TEXT NOT NULL,
+    seq BIGINT NOT NULL,
+    ts_ms BIGINT NOT NULL,
+    -- file, api, telegram or cli
+    source TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    target TEXT NOT NULL,
+    change_key TEXT NOT NULL,
+    -- JSON, NULL when the key did not exist before or was removed
+    old_value TEXT,
+    new_value TEXT,
+    sealed TEXT,
+    PRIMARY KEY (instance, seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_state_config_audit_ts ON state_config_audit (instance, ts_ms);
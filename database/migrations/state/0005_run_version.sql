@@ -0,0 +1,13 @@
+-- =================================================================
+-- Config hash and code version that produced each order and journal line,
+-- so results can be compared across tuning iterations and releases.
+-- NULL for rows written before the store was given a run version.
+-- =================================================================
+
+ALTER TABLE state_orders ADD COLUMN config_hash TEXT;
+ALTER TABLE state_orders ADD COLUMN code_version TEXT;
+ALTER TABLE state_journal ADD COLUMN config_hash TEXT;
+ALTER TABLE state_journal ADD COLUMN code_version TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_state_orders_config ON state_orders (instance, config_hash);
+CREATE INDEX IF NOT EXISTS idx_state_journal_config ON state_journal (instance, config_hash);
@@ -2,6 +2,7 @@
 //! Совместимо с MoonBot форматом
 
 use crate::backtest::market::{TradeTick, TradeSide};
+use crate::base_classes::symbol::Symbol;
 use chrono::{DateTime, Utc, NaiveDateTime};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
@@ -17,7 +18,7 @@ pub struct TradeRecord {
 
 pub struct BinFileReader {
     file: BufReader<File>,
-    symbol: String,
+    symbol: Symbol,
 }
 
 impl BinFileReader {
@@ -29,7 +30,7 @@ impl BinFileReader {
         let filename = path.as_ref().file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("UNKNOWN");
-        let symbol = filename.split('_').next().unwrap_or("UNKNOWN").to_string();
+        let symbol = Symbol::new(filename.split('_').next().unwrap_or("UNKNOWN"));
         
         Ok(Self { 
             file: reader,
@@ -71,7 +72,7 @@ impl BinFileReader {
                     
                     trades.push(TradeTick {
                         timestamp,
-                        symbol: self.symbol.clone(),
+                        symbol: self.symbol,
                         price,
                         volume,
                         side: if side { TradeSide::Buy } else { TradeSide::Sell },
@@ -0,0 +1,132 @@
+//! Кэш результатов перебора на диске
+//!
+//! Ключ комбинации — SHA-256 от хэша данных, стратегии с итоговыми слоями конфига,
+//! настроек движка и `ENGINE_VERSION`. Повторный перебор после небольшого изменения
+//! сетки считает только новые комбинации; смена данных, seed или версии движка
+//! даёт новые ключи, и старые записи просто не находятся.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use super::engine::{BacktestSettings, ENGINE_VERSION};
+use super::market::{TradeSide, TradeStream};
+use super::optimize::{OptimizeRun, StrategyKind};
+use crate::strategy::moon_strategies::ConfigLayers;
+
+/// Каталог с файлами `<ключ>.json`, по одному на комбинацию
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir).with_context(|| format!("create result cache {}", dir.display()))?;
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    pub fn key(data_hash: &str, kind: StrategyKind, layers: &ConfigLayers, settings: &BacktestSettings) -> Result<String> {
+        let material = json!({
+            "engine": ENGINE_VERSION,
+            "data": data_hash,
+            "strategy": format!("{:?}", kind),
+            "layers": serde_json::to_value(layers)?,
+            "settings": format!("{:?}", settings),
+        });
+        Ok(hex(&Sha256::digest(material.to_string().as_bytes())))
+    }
+
+    /// Битая запись — ошибка, а не промах: молча пересчитывать её незачем
+    pub fn get(&self, key: &str) -> Result<Option<OptimizeRun>> {
+        let path = self.path(key);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&path).with_context(|| format!("read cached result {}", path.display()))?;
+        let run = serde_json::from_str(&text).with_context(|| format!("parse cached result {}", path.display()))?;
+        Ok(Some(run))
+    }
+
+    pub fn put(&self, key: &str, run: &OptimizeRun) -> Result<()> {
+        let path = self.path(key);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(run)?).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("store cached result {}", path.display()))?;
+        Ok(())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+/// Хэш всех сделок всех потоков в порядке подачи
+pub fn data_hash(streams: &[TradeStream]) -> String {
+    let mut hasher = Sha256::new();
+    for stream in streams {
+        hasher.update(stream.symbol.to_string().as_bytes());
+        hasher.update((stream.trades.len() as u64).to_le_bytes());
+        for trade in &stream.trades {
+            hasher.update(trade.timestamp.timestamp_micros().to_le_bytes());
+            hasher.update(trade.price.to_bits().to_le_bytes());
+            hasher.update(trade.volume.to_bits().to_le_bytes());
+            hasher.update([matches!(trade.side, TradeSide::Buy) as u8]);
+        }
+    }
+    hex(&hasher.finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::symbol::Symbol;
+    use serde_json::Value;
+
+    #[test]
+    fn test_keys_track_data_config_and_settings() {
+        let ticks = crate::testing::ScenarioBuilder::new("ETH_USDT", 100.0).flat(2_000).build();
+        let streams = [TradeStream::new(Symbol::new("ETH_USDT"), ticks.clone())];
+        let mut shifted = ticks;
+        shifted[0].price += 0.01;
+        let other_data = [TradeStream::new(Symbol::new("ETH_USDT"), shifted)];
+        assert_eq!(data_hash(&streams), data_hash(&streams));
+        assert_ne!(data_hash(&streams), data_hash(&other_data));
+
+        let settings = BacktestSettings { random_seed: Some(1), ..BacktestSettings::default() };
+        let layers = ConfigLayers::default();
+        let mut tuned = layers.clone();
+        tuned.base.insert("hook_detect_depth".into(), Value::from(3.0));
+        let data = data_hash(&streams);
+        let key = ResultCache::key(&data, StrategyKind::Hook, &layers, &settings).unwrap();
+        assert_eq!(key, ResultCache::key(&data, StrategyKind::Hook, &layers, &settings).unwrap());
+        assert_ne!(key, ResultCache::key(&data, StrategyKind::MShot, &layers, &settings).unwrap());
+        assert_ne!(key, ResultCache::key(&data, StrategyKind::Hook, &tuned, &settings).unwrap());
+        let reseeded = BacktestSettings { random_seed: Some(2), ..settings.clone() };
+        assert_ne!(key, ResultCache::key(&data, StrategyKind::Hook, &layers, &reseeded).unwrap());
+        assert_ne!(key, ResultCache::key(&data_hash(&other_data), StrategyKind::Hook, &layers, &settings).unwrap());
+
+        let dir = std::env::temp_dir().join(format!("result_cache_{}", std::process::id()));
+        let cache = ResultCache::open(&dir).unwrap();
+        assert!(cache.get(&key).unwrap().is_none());
+        let run = OptimizeRun {
+            params: Default::default(),
+            pnl: 1.5,
+            trades: 2,
+            sharpe: 0.3,
+            gross_profit: 2.0,
+            gross_loss: 0.5,
+            max_drawdown: 1.0,
+        };
+        cache.put(&key, &run).unwrap();
+        assert_eq!(cache.get(&key).unwrap(), Some(run));
+        std::fs::write(dir.join(format!("{}.json", key)), "{").unwrap();
+        assert!(cache.get(&key).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
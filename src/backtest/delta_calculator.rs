@@ -2,26 +2,27 @@
 //! Используется стратегиями MShot, MStrike, Hook для модификации параметров
 
 use crate::strategy::moon_strategies::mshot::Deltas;
+use crate::strategy::moon_strategies::{RollingMinMax, TimeWindow};
 use crate::backtest::market::TradeTick;
 use chrono::{DateTime, Utc, Duration};
-use std::collections::VecDeque;
-
-#[derive(Debug, Clone)]
-struct PricePoint {
-    timestamp: DateTime<Utc>,
-    price: f64,
-}
 
 /// Калькулятор дельт на основе истории тиков
+#[derive(Clone)]
 pub struct DeltaCalculator {
     /// История цен для текущего символа
-    price_history: VecDeque<PricePoint>,
+    price_history: TimeWindow<f64>,
     
     /// История цен для BTC (для delta_btc)
-    btc_price_history: VecDeque<PricePoint>,
+    btc_price_history: TimeWindow<f64>,
+    
+    /// Min/max BTC за последние 5 минут (для delta_btc_5m)
+    btc_extremes_5m: RollingMinMax,
     
     /// История цен для маркета (для delta_market)
-    market_price_history: VecDeque<PricePoint>,
+    market_price_history: TimeWindow<f64>,
+    
+    /// Отсчёты открытого интереса (для delta_oi_5m)
+    open_interest_history: TimeWindow<f64>,
     
     /// Максимальное время хранения истории (для очистки)
     max_history_duration: Duration,
@@ -30,9 +31,11 @@ pub struct DeltaCalculator {
 impl DeltaCalculator {
     pub fn new() -> Self {
         Self {
-            price_history: VecDeque::new(),
-            btc_price_history: VecDeque::new(),
-            market_price_history: VecDeque::new(),
+            price_history: TimeWindow::new(),
+            btc_price_history: TimeWindow::new(),
+            btc_extremes_5m: RollingMinMax::new(),
+            market_price_history: TimeWindow::new(),
+            open_interest_history: TimeWindow::new(),
             max_history_duration: Duration::hours(24), // Храним 24 часа
         }
     }
@@ -40,29 +43,37 @@ impl DeltaCalculator {
     /// Обновить историю цен новым тиком
     pub fn update(&mut self, tick: &TradeTick, current_time: DateTime<Utc>) {
         // Обновляем историю для текущего символа
-        self.price_history.push_back(PricePoint {
-            timestamp: tick.timestamp,
-            price: tick.price,
-        });
+        self.price_history.push(tick.timestamp, tick.price);
         
         // Обновляем BTC историю если это BTC пара
         if tick.symbol.contains("BTC") || tick.symbol == "BTC_USDT" {
-            self.btc_price_history.push_back(PricePoint {
-                timestamp: tick.timestamp,
-                price: tick.price,
-            });
+            self.btc_price_history.push(tick.timestamp, tick.price);
+            self.btc_extremes_5m.push(tick.timestamp, tick.price);
         }
         
         // Обновляем маркет историю (упрощенно - для всех символов)
-        self.market_price_history.push_back(PricePoint {
-            timestamp: tick.timestamp,
-            price: tick.price,
-        });
+        self.market_price_history.push(tick.timestamp, tick.price);
         
         // Очищаем старую историю
         self.cleanup(current_time);
     }
     
+    /// Новый отсчёт открытого интереса (стрим тикеров или опрос contract_stats)
+    pub fn update_open_interest(&mut self, timestamp: DateTime<Utc>, open_interest: f64) {
+        if !(open_interest >= 0.0 && open_interest.is_finite()) {
+            log::warn!("open interest {} at {} ignored", open_interest, timestamp);
+            return;
+        }
+        self.open_interest_history.push(timestamp, open_interest);
+        let cutoff = timestamp - Duration::minutes(5);
+        // Последний отсчёт старше окна нужен как точка отсчёта, пока не придёт следующий
+        while self.open_interest_history.len() > 1
+            && self.open_interest_history.get(1).is_some_and(|&(t, _)| t <= cutoff)
+        {
+            self.open_interest_history.pop_front();
+        }
+    }
+    
     /// Вычислить дельты для текущего символа
     pub fn calculate_deltas(&self, current_price: f64, current_time: DateTime<Utc>) -> Deltas {
         let delta_15min = self.calculate_delta_percent(
@@ -90,7 +101,7 @@ impl DeltaCalculator {
         let delta_btc = if !self.btc_price_history.is_empty() {
             self.calculate_delta_percent(
                 &self.btc_price_history,
-                self.btc_price_history.back().map(|p| p.price).unwrap_or(current_price),
+                self.btc_price_history.back().map_or(current_price, |&(_, price)| price),
                 current_time,
                 Duration::hours(1),
             )
@@ -98,29 +109,29 @@ impl DeltaCalculator {
             0.0
         };
         
-        let delta_btc_5m = if !self.btc_price_history.is_empty() {
-            // Для 5м дельты BTC берем минимум/максимум за последние 5 минут
-            let cutoff = current_time - Duration::minutes(5);
-            let prices_5m: Vec<f64> = self.btc_price_history
-                .iter()
-                .filter(|p| p.timestamp >= cutoff)
-                .map(|p| p.price)
-                .collect();
-            
-            if !prices_5m.is_empty() {
-                let min = prices_5m.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-                let max = prices_5m.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-                ((max - min) / min) * 100.0
-            } else {
-                0.0
-            }
-        } else {
-            0.0
+        // Для 5м дельты BTC берем минимум/максимум за последние 5 минут
+        let cutoff_5m = current_time - Duration::minutes(5);
+        let delta_btc_5m = match (
+            self.btc_extremes_5m.min_since(cutoff_5m),
+            self.btc_extremes_5m.max_since(cutoff_5m),
+        ) {
+            (Some(min), Some(max)) => ((max - min) / min) * 100.0,
+            _ => 0.0,
         };
         
         // Маркет дельта (упрощенно - используем текущий символ)
         let delta_market = delta_hourly; // Можно заменить на реальный расчет маркета
         
+        let delta_oi_5m = match self.open_interest_history.back() {
+            Some(&(_, last)) => self.calculate_delta_percent(
+                &self.open_interest_history,
+                last,
+                current_time,
+                Duration::minutes(5),
+            ),
+            None => 0.0,
+        };
+        
         Deltas {
             delta_3h,
             delta_hourly,
@@ -128,28 +139,28 @@ impl DeltaCalculator {
             delta_market,
             delta_btc,
             delta_btc_5m,
+            delta_oi_5m,
         }
     }
     
     /// Вычислить процентное изменение цены за период
     fn calculate_delta_percent(
         &self,
-        history: &VecDeque<PricePoint>,
+        history: &TimeWindow<f64>,
         current_price: f64,
         current_time: DateTime<Utc>,
         window: Duration,
     ) -> f64 {
         let cutoff = current_time - window;
         
-        // Находим цену в начале окна
+        // Находим цену в начале окна (история отсортирована по времени - бинарный поиск)
         let start_price = history
-            .iter()
-            .find(|p| p.timestamp >= cutoff)
-            .map(|p| p.price)
-            .unwrap_or_else(|| {
+            .first_since(cutoff)
+            .or_else(|| {
                 // Если не нашли в окне, берем самую старую цену
-                history.front().map(|p| p.price).unwrap_or(current_price)
-            });
+                history.front()
+            })
+            .map_or(current_price, |&(_, price)| price);
         
         if start_price > 0.0 {
             ((current_price - start_price) / start_price) * 100.0
@@ -162,29 +173,12 @@ impl DeltaCalculator {
     fn cleanup(&mut self, current_time: DateTime<Utc>) {
         let cutoff = current_time - self.max_history_duration;
         
-        while let Some(front) = self.price_history.front() {
-            if front.timestamp < cutoff {
-                self.price_history.pop_front();
-            } else {
-                break;
-            }
-        }
+        self.price_history.evict_before(cutoff);
+        self.btc_price_history.evict_before(cutoff);
         
-        while let Some(front) = self.btc_price_history.front() {
-            if front.timestamp < cutoff {
-                self.btc_price_history.pop_front();
-            } else {
-                break;
-            }
-        }
+        self.btc_extremes_5m.evict_before(current_time - Duration::minutes(5));
         
-        while let Some(front) = self.market_price_history.front() {
-            if front.timestamp < cutoff {
-                self.market_price_history.pop_front();
-            } else {
-                break;
-            }
-        }
+        self.market_price_history.evict_before(cutoff);
     }
 }
 
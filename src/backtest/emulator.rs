@@ -2,13 +2,65 @@
 
 use crate::backtest::market::{TradeTick, TradeSide};
 use crate::backtest::metrics::BacktestMetrics;
+use crate::base_classes::symbol::Symbol;
 use chrono::{DateTime, Utc};
 use rand::Rng;
 use std::collections::HashMap;
 
+/// Когда сделка на ленте исполняет наш лимитный ордер. Результаты Hook сильно
+/// зависят от этого допущения, поэтому модель выбирается на каждый бэктест.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitFillModel {
+    /// Только сделка строго сквозь цену ордера; исполняется до объёма сделки
+    Conservative,
+    /// Касание цены исполняет ордер целиком
+    Optimistic,
+    /// Касание исполняет с вероятностью `fill_probability` долю `volume_share` объёма сделки
+    Probabilistic { fill_probability: f64, volume_share: f64 },
+}
+
+impl Default for LimitFillModel {
+    fn default() -> Self {
+        LimitFillModel::Probabilistic {
+            fill_probability: 0.95, // 95% вероятность заполнения при подходящей цене
+            volume_share: 0.1,      // Примерно 10% объема тика
+        }
+    }
+}
+
+impl LimitFillModel {
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "conservative" => Ok(Self::Conservative),
+            "optimistic" => Ok(Self::Optimistic),
+            "probabilistic" => Ok(Self::default()),
+            other => anyhow::bail!("unknown fill model '{}', expected conservative, optimistic or probabilistic", other),
+        }
+    }
+
+    /// Сколько из `remaining` исполняет сделка объёмом `volume`, если рынок для нашей
+    /// стороны на `market`, а ордер стоит по `price`
+    fn fill_size<R: Rng>(&self, market: f64, volume: f64, price: f64, is_buy: bool, remaining: f64, rng: &mut R) -> f64 {
+        let through = if is_buy { market < price } else { market > price };
+        let touched = through || market == price;
+        match *self {
+            LimitFillModel::Conservative if through => remaining.min(volume),
+            LimitFillModel::Optimistic if touched => remaining,
+            // Применяем вероятность заполнения (не всегда заполняется!)
+            LimitFillModel::Probabilistic { fill_probability, volume_share }
+                if touched && rng.gen_range(0.0f64..1.0f64) < fill_probability =>
+            {
+                remaining.min(volume * volume_share)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EmulatorSettings {
-    pub fill_probability: f64,      // Вероятность заполнения лимитного ордера (0.0-1.0)
+    pub fill_model: LimitFillModel,  // Модель исполнения лимитных ордеров
+    pub spread_aware: bool,         // Покупки об аск, продажи об бид (если в тике есть стакан)
     pub slippage_percent: f64,      // Скольжение цены (%)
     pub max_active_orders: usize,  // Максимум активных ордеров
 }
@@ -16,7 +68,8 @@ pub struct EmulatorSettings {
 impl Default for EmulatorSettings {
     fn default() -> Self {
         EmulatorSettings {
-            fill_probability: 0.95, // 95% вероятность заполнения при подходящей цене
+            fill_model: LimitFillModel::default(),
+            spread_aware: true,
             slippage_percent: 0.1,  // 0.1% скольжение
             max_active_orders: 30,   // Как в MoonBot
         }
@@ -26,7 +79,7 @@ impl Default for EmulatorSettings {
 #[derive(Debug, Clone)]
 pub struct Order {
     pub id: u64,
-    pub symbol: String,
+    pub symbol: Symbol,
     pub price: f64,
     pub size: f64,
     pub filled: f64,
@@ -43,8 +96,12 @@ pub struct MarketEmulator {
 
 impl MarketEmulator {
     pub fn new() -> Self {
+        Self::with_settings(EmulatorSettings::default())
+    }
+
+    pub fn with_settings(settings: EmulatorSettings) -> Self {
         Self {
-            settings: EmulatorSettings::default(),
+            settings,
             active_orders: HashMap::new(),
             next_order_id: 1,
         }
@@ -53,7 +110,7 @@ impl MarketEmulator {
     /// Разместить лимитный ордер
     pub fn place_limit_order(
         &mut self,
-        symbol: &str,
+        symbol: Symbol,
         price: f64,
         size: f64,
         is_buy: bool,
@@ -69,7 +126,7 @@ impl MarketEmulator {
         
         let order = Order {
             id: order_id,
-            symbol: symbol.to_string(),
+            symbol,
             price,
             size,
             filled: 0.0,
@@ -100,56 +157,60 @@ impl MarketEmulator {
                     continue;
                 }
                 
-                // Проверка условия заполнения лимитного ордера
-                let should_fill = if order.is_buy {
-                    // Buy ордер заполняется если цена трейда <= цене ордера
-                    tick.price <= order.price
+                // Проверка условия заполнения лимитного ордера по модели исполнения
+                let market = if self.settings.spread_aware {
+                    tick.execution_price(order.is_buy)
                 } else {
-                    // Sell ордер заполняется если цена трейда >= цене ордера
-                    tick.price >= order.price
+                    tick.price
                 };
-                
-                if should_fill {
-                    // Применяем вероятность заполнения (не всегда заполняется!)
-                    if rng.gen_range(0.0f64..1.0f64) < self.settings.fill_probability {
-                        // Применяем скольжение
-                        let execution_price = if order.is_buy {
-                            tick.price * (1.0 + self.settings.slippage_percent / 100.0)
+                let fill_size = self.settings.fill_model.fill_size(
+                    market,
+                    tick.volume,
+                    order.price,
+                    order.is_buy,
+                    order.size - order.filled,
+                    rng,
+                );
+
+                if fill_size > 0.0 {
+                    // Применяем скольжение
+                    let execution_price = if order.is_buy {
+                        market * (1.0 + self.settings.slippage_percent / 100.0)
+                    } else {
+                        market * (1.0 - self.settings.slippage_percent / 100.0)
+                    };
+                    
+                    // Издержки на спред: расстояние до середины, учитываются отдельно от P&L
+                    if self.settings.spread_aware && let Some(mid) = tick.mid() {
+                        metrics.spread_cost += (market - mid).abs() * fill_size;
+                    }
+                    
+                    order.filled += fill_size;
+                    
+                    if order.filled >= order.size {
+                        order.filled_at = Some(tick.timestamp);
+                        
+                        // Обновляем метрики
+                        let pnl = if order.is_buy {
+                            // Продали по execution_price, купили по order.price
+                            (execution_price - order.price) * order.size
                         } else {
-                            tick.price * (1.0 - self.settings.slippage_percent / 100.0)
+                            // Продали по order.price, купили по execution_price
+                            (order.price - execution_price) * order.size
                         };
                         
-                        // Исполняем ордер (полностью или частично)
-                        let remaining = order.size - order.filled;
-                        let fill_size = remaining.min(tick.volume * 0.1); // Примерно 10% объема тика
-                        
-                        order.filled += fill_size;
+                        metrics.record_trade(
+                            tick.symbol.to_string(),
+                            order.price,
+                            execution_price,
+                            order.size,
+                            order.is_buy,
+                            pnl,
+                            tick.timestamp,
+                        );
                         
-                        if order.filled >= order.size {
-                            order.filled_at = Some(tick.timestamp);
-                            
-                            // Обновляем метрики
-                            let pnl = if order.is_buy {
-                                // Продали по execution_price, купили по order.price
-                                (execution_price - order.price) * order.size
-                            } else {
-                                // Продали по order.price, купили по execution_price
-                                (order.price - execution_price) * order.size
-                            };
-                            
-                            metrics.record_trade(
-                                tick.symbol.clone(),
-                                order.price,
-                                execution_price,
-                                order.size,
-                                order.is_buy,
-                                pnl,
-                                tick.timestamp,
-                            );
-                            
-                            // Удаляем исполненный ордер
-                            self.active_orders.remove(&order_id);
-                        }
+                        // Удаляем исполненный ордер
+                        self.active_orders.remove(&order_id);
                     }
                 }
             }
@@ -203,3 +264,86 @@ impl MarketEmulator {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::TradeSide;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn quoted(price: f64, bid: f64, ask: f64) -> TradeTick {
+        TradeTick { best_bid: Some(bid), best_ask: Some(ask), ..tick(price, 4.0) }
+    }
+
+    fn tick(price: f64, volume: f64) -> TradeTick {
+        TradeTick {
+            timestamp: Utc::now(),
+            symbol: Symbol::new("ETH_USDT"),
+            price,
+            volume,
+            side: TradeSide::Sell,
+            trade_id: String::new(),
+            best_bid: None,
+            best_ask: None,
+        }
+    }
+
+    /// Сколько объёма buy-ордера 1.0 по 100 исполняют сделки `prices` (объём 4.0 каждая)
+    fn filled(model: LimitFillModel, prices: &[f64]) -> f64 {
+        let settings = EmulatorSettings { fill_model: model, ..EmulatorSettings::default() };
+        let mut emulator = MarketEmulator::with_settings(settings);
+        let mut metrics = BacktestMetrics::new();
+        let mut rng = StdRng::seed_from_u64(1);
+        let id = emulator.place_limit_order(Symbol::new("ETH_USDT"), 100.0, 1.0, true, Utc::now());
+        for &price in prices {
+            emulator.process_tick(&tick(price, 4.0), &mut metrics, &mut rng);
+        }
+        emulator.get_active_orders().get(&id).map_or(1.0, |o| o.filled)
+    }
+
+    #[test]
+    fn test_fill_models_differ_on_touch_and_through() {
+        assert_eq!(filled(LimitFillModel::Conservative, &[100.0, 100.5]), 0.0);
+        assert_eq!(filled(LimitFillModel::Conservative, &[99.9]), 1.0);
+        assert_eq!(filled(LimitFillModel::Optimistic, &[100.5]), 0.0);
+        assert_eq!(filled(LimitFillModel::Optimistic, &[100.0]), 1.0);
+
+        let share = LimitFillModel::Probabilistic { fill_probability: 1.0, volume_share: 0.1 };
+        assert!((filled(share, &[100.0]) - 0.4).abs() < 1e-12);
+        assert_eq!(filled(share, &[100.0, 99.0, 100.0]), 1.0);
+        let never = LimitFillModel::Probabilistic { fill_probability: 0.0, volume_share: 1.0 };
+        assert_eq!(filled(never, &[99.0, 100.0]), 0.0);
+
+        assert_eq!(LimitFillModel::parse("Conservative").unwrap(), LimitFillModel::Conservative);
+        assert_eq!(LimitFillModel::parse("probabilistic").unwrap(), LimitFillModel::default());
+        assert!(LimitFillModel::parse("mid").is_err());
+    }
+
+    #[test]
+    fn test_quotes_set_fill_and_spread_cost() {
+        let settings = EmulatorSettings {
+            fill_model: LimitFillModel::Optimistic,
+            slippage_percent: 0.0,
+            ..EmulatorSettings::default()
+        };
+        let mut emulator = MarketEmulator::with_settings(settings.clone());
+        let mut metrics = BacktestMetrics::new();
+        let mut rng = StdRng::seed_from_u64(1);
+        emulator.place_limit_order(Symbol::new("ETH_USDT"), 100.0, 1.0, true, Utc::now());
+        // Сделка прошла ниже, но аск выше цены ордера: покупке не об кого исполниться
+        emulator.process_tick(&quoted(99.5, 99.4, 100.2), &mut metrics, &mut rng);
+        assert_eq!(metrics.total_trades, 0);
+        emulator.process_tick(&quoted(99.9, 99.8, 100.0), &mut metrics, &mut rng);
+        assert_eq!(metrics.total_trades, 1);
+        assert_eq!(metrics.trades[0].exit_price, 100.0);
+        assert!((metrics.spread_cost - 0.1).abs() < 1e-9);
+
+        // Без учёта стакана исполняется по цене сделки и спред не считается
+        let mut emulator = MarketEmulator::with_settings(EmulatorSettings { spread_aware: false, ..settings });
+        let mut metrics = BacktestMetrics::new();
+        emulator.place_limit_order(Symbol::new("ETH_USDT"), 100.0, 1.0, true, Utc::now());
+        emulator.process_tick(&quoted(99.5, 99.4, 100.2), &mut metrics, &mut rng);
+        assert_eq!(metrics.trades[0].exit_price, 99.5);
+        assert_eq!(metrics.spread_cost, 0.0);
+    }
+}
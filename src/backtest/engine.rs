@@ -13,14 +13,21 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 
 use super::market::{MarketState, TradeStream};
-use super::emulator::MarketEmulator;
+use super::emulator::{EmulatorSettings, LimitFillModel, MarketEmulator};
 use super::metrics::{BacktestMetrics, BacktestResult};
 use super::delta_calculator::DeltaCalculator;
+use super::tick_guard::{TickGuard, TickGuardConfig, TickGuardStats};
 #[cfg(feature = "gate_exec")]
 use super::strategy_adapter::{StrategyAdapter, StrategyAction};
 #[cfg(feature = "gate_exec")]
+use crate::base_classes::types::Side;
+#[cfg(feature = "gate_exec")]
 use crate::strategy::moon_strategies::mshot::Deltas;
 
+/// Версия логики движка для кэша результатов: поднимать, когда те же данные, конфиг
+/// и seed начинают давать другой результат
+pub const ENGINE_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExecutionMode {
@@ -62,6 +69,15 @@ pub struct BacktestSettings {
     
     /// Защита от реальных ордеров в режиме эмулятора
     pub enforce_emulator_mode: bool,
+
+    /// Когда сделка на ленте исполняет лимитный ордер
+    pub fill_model: LimitFillModel,
+
+    /// Исполнять по записанным best bid/ask вместо цены сделки
+    pub spread_aware: bool,
+
+    /// Отбраковка битых тиков до стратегий и калькулятора дельт
+    pub tick_guard: TickGuardConfig,
 }
 
 impl Default for BacktestSettings {
@@ -77,6 +93,9 @@ impl Default for BacktestSettings {
             missed_trade_probability: 0.0,
             mode: ExecutionMode::Emulator,
             enforce_emulator_mode: true,
+            fill_model: LimitFillModel::default(),
+            spread_aware: true,
+            tick_guard: TickGuardConfig::default(),
         }
     }
 }
@@ -116,8 +135,21 @@ pub struct BacktestEngine {
     
     /// Калькулятор дельт для стратегий
     delta_calculator: DeltaCalculator, 
+
+    /// Сторож входящих тиков
+    tick_guard: TickGuard,
+
+    /// Экспорт признаков детектов для ML
+    #[cfg(feature = "ml_export")]
+    features: Option<super::features::FeatureExporter>,
+
+    /// Колбэк прогресса и его шаг в тиках; пока он задан, движок молчит в stdout
+    progress: Option<(u64, ProgressCallback)>,
 }
 
+/// Получает число обработанных тиков и текущий P&L
+pub type ProgressCallback = Box<dyn FnMut(u64, f64) + Send>;
+
 #[derive(Debug, Clone)]
 enum DelayedEvent {
     OrderExecution {
@@ -156,6 +188,12 @@ impl BacktestEngine {
         
         let mut final_settings = settings;
         final_settings.mode = mode;
+        let tick_guard = TickGuard::new(final_settings.tick_guard);
+        let emulator = MarketEmulator::with_settings(EmulatorSettings {
+            fill_model: final_settings.fill_model,
+            spread_aware: final_settings.spread_aware,
+            ..EmulatorSettings::default()
+        });
         
         Self {
             settings: final_settings,
@@ -163,7 +201,7 @@ impl BacktestEngine {
             rng,
             streams: Vec::new(),
             market_state: MarketState::new(),
-            emulator: MarketEmulator::new(),
+            emulator,
             current_time: Utc::now(),
             last_recalculation_time: Utc::now(),
             metrics: BacktestMetrics::new(),
@@ -172,6 +210,10 @@ impl BacktestEngine {
             #[cfg(feature = "gate_exec")]
             strategies: Vec::new(),
             delta_calculator: DeltaCalculator::new(),
+            tick_guard,
+            #[cfg(feature = "ml_export")]
+            features: None,
+            progress: None,
         }
     }
     
@@ -186,6 +228,23 @@ impl BacktestEngine {
         self.strategies.push(Box::new(adapter));
     }
     
+    /// Адаптер, уже упакованный (например, `strategy_adapter::with_entry_model`)
+    #[cfg(feature = "gate_exec")]
+    pub fn add_boxed_strategy_adapter(&mut self, adapter: Box<dyn StrategyAdapter + Send>) {
+        self.strategies.push(adapter);
+    }
+    
+    /// Писать признаки каждого детекта в Parquet; файл закрывается в конце `run`
+    #[cfg(feature = "ml_export")]
+    pub fn set_feature_export(&mut self, exporter: super::features::FeatureExporter) {
+        self.features = Some(exporter);
+    }
+
+    /// Сообщать прогресс каждые `every_ticks` тиков и в конце прогона вместо печати в stdout
+    pub fn set_progress(&mut self, every_ticks: u64, callback: ProgressCallback) {
+        self.progress = Some((every_ticks.max(1), callback));
+    }
+
     /// Запуск бэктеста
     pub fn run(&mut self) -> anyhow::Result<BacktestResult> {
         if self.streams.is_empty() {
@@ -199,15 +258,17 @@ impl BacktestEngine {
             ));
         }
         
-        println!("🚀 Starting backtest with seed: {:?}", self.settings.random_seed);
-        println!("📊 Streams: {}", self.streams.len());
+        if self.progress.is_none() {
+            println!("🚀 Starting backtest with seed: {:?}", self.settings.random_seed);
+            println!("📊 Streams: {}", self.streams.len());
+        }
         
         // Инициализация времени
         self.current_time = self.get_earliest_timestamp();
         self.last_recalculation_time = self.current_time;
         
         // Основной цикл симуляции
-        let mut tick_count = 0;
+        let mut tick_count: u64 = 0;
         while !self.stopped && self.has_more_data() {
             // Получаем следующий тик с учетом случайных задержек
             if let Some(next_tick) = self.get_next_tick_with_lag() {
@@ -224,6 +285,11 @@ impl BacktestEngine {
                 // Обновляем время симуляции
                 self.current_time = next_tick.timestamp;
                 
+                // Битый тик не доходит ни до стратегий, ни до эмулятора
+                if self.tick_guard.check(&next_tick).is_err() {
+                    continue;
+                }
+                
                 // Проверяем, не пропустили ли мы этот трейд (случайность)
                 if self.should_miss_trade() {
                     continue; // Пропускаем этот трейд
@@ -246,6 +312,11 @@ impl BacktestEngine {
                 
                 // Обновляем калькулятор дельт
                 self.delta_calculator.update(&next_tick, adjusted_time);
+
+                #[cfg(feature = "ml_export")]
+                if let Some(features) = &mut self.features {
+                    features.on_tick(&next_tick)?;
+                }
                 
                 // Эмулируем исполнение ордеров
                 // Сначала сохраняем активные ордера до обработки
@@ -285,13 +356,16 @@ impl BacktestEngine {
                                         match action {
                                             StrategyAction::PlaceSell { price: sell_price, size } => {
                                                 let _ = self.emulator.place_limit_order(
-                                                    &next_tick.symbol,
+                                                    next_tick.symbol,
                                                     sell_price,
                                                     size,
                                                     false,
                                                     adjusted_time,
                                                 );
                                             }
+                                            StrategyAction::Error(err) => {
+                                                log::warn!("[{}] Strategy {} rejected buy fill: {}", next_tick.symbol, adapter.get_name(), err);
+                                            }
                                             _ => {}
                                         }
                                     }
@@ -303,17 +377,44 @@ impl BacktestEngine {
                 
                 tick_count += 1;
                 
-                // Прогресс каждые 10000 тиков
-                if tick_count % 10000 == 0 {
-                    println!("⏳ Progress: {} ticks processed, P&L: {:.2}", 
-                        tick_count, self.metrics.total_pnl);
+                // Прогресс каждые 10000 тиков (или с шагом колбэка)
+                match &mut self.progress {
+                    Some((every, callback)) if tick_count.is_multiple_of(*every) => {
+                        callback(tick_count, self.metrics.total_pnl)
+                    }
+                    Some(_) => {}
+                    None if tick_count.is_multiple_of(10000) => {
+                        println!("⏳ Progress: {} ticks processed, P&L: {:.2}",
+                            tick_count, self.metrics.total_pnl);
+                    }
+                    None => {}
                 }
             } else {
                 break;
             }
         }
         
-        println!("✅ Backtest completed: {} ticks", tick_count);
+        match &mut self.progress {
+            Some((_, callback)) => callback(tick_count, self.metrics.total_pnl),
+            None => println!("✅ Backtest completed: {} ticks", tick_count),
+        }
+        let guard = self.tick_guard.stats();
+        if guard.rejected() > 0 {
+            log::warn!(
+                "tick guard rejected {} ticks: price {}, volume {}, quote {}, jumps {}",
+                guard.rejected(),
+                guard.bad_price,
+                guard.bad_volume,
+                guard.bad_quote,
+                guard.jumps
+            );
+        }
+
+        #[cfg(feature = "ml_export")]
+        if let Some(features) = self.features.take() {
+            let rows = features.finish()?;
+            println!("🧠 Detection features exported: {} rows", rows);
+        }
         
         Ok(self.metrics.to_result())
     }
@@ -417,17 +518,35 @@ impl BacktestEngine {
             // Вычисляем реальные дельты из истории
             let deltas = self.delta_calculator.calculate_deltas(tick.price, adjusted_time);
             for adapter in &mut self.strategies {
-                match adapter.on_tick(tick, &deltas) {
+                let action = adapter.on_tick(tick, &deltas);
+                // Детект пишется по сигналу; вход помечает его сработавшим
+                #[cfg(feature = "ml_export")]
+                if let Some(features) = &mut self.features {
+                    match &action {
+                        StrategyAction::DetectSignal { .. } => {
+                            features.record(tick, adapter.get_name(), false, None, &deltas, None)
+                        }
+                        StrategyAction::PlaceBuy { .. } if !features.mark_fired(tick.symbol, adapter.get_name()) => {
+                            features.record(tick, adapter.get_name(), true, None, &deltas, None)
+                        }
+                        _ => {}
+                    }
+                }
+                match action {
                     StrategyAction::NoAction => {}
                     StrategyAction::PlaceBuy { price, size } => {
-                        let id = self.emulator.place_limit_order(&tick.symbol, price, size, true, adjusted_time);
+                        let id = self.emulator.place_limit_order(tick.symbol, price, size, true, adjusted_time);
                         if id > 0 {
+                            adapter.on_order_placed(Side::Bid, id);
                             println!("📊 [{}] Strategy {} placed BUY order: price={:.8}, size={:.2}, id={}", 
                                 tick.symbol, adapter.get_name(), price, size, id);
+                        } else {
+                            // Эмулятор не принял ордер: стратегия не должна ждать его фила
+                            adapter.on_buy_declined();
                         }
                     }
                     StrategyAction::PlaceSell { price, size } => {
-                        let _id = self.emulator.place_limit_order(&tick.symbol, price, size, false, adjusted_time);
+                        let _id = self.emulator.place_limit_order(tick.symbol, price, size, false, adjusted_time);
                     }
                     StrategyAction::ReplaceBuy { new_price } => {
                         // Переставление: выберем любой активный ордер по символу (упрощенно)
@@ -439,6 +558,9 @@ impl BacktestEngine {
                         let _ = self.emulator.cancel_order(order_id);
                     }
                     StrategyAction::DetectSignal { .. } => {}
+                    StrategyAction::Error(err) => {
+                        log::error!("[{}] Strategy {} failed: {}", tick.symbol, adapter.get_name(), err);
+                    }
                 }
             }
         }
@@ -459,6 +581,11 @@ impl BacktestEngine {
         }
     }
     
+    /// Счётчики отбракованных тиков
+    pub fn tick_guard_stats(&self) -> TickGuardStats {
+        self.tick_guard.stats()
+    }
+    
     /// Остановка бэктеста
     pub fn stop(&mut self) {
         self.stopped = true;
@@ -0,0 +1,300 @@
+//! Экспорт признаков детектов для обучения ML-фильтра
+//!
+//! На каждый детект (с входом или без: отсеян фильтром, не дождался условий входа)
+//! пишутся его признаки (`DetectionFeatures`). Метка - исход по следующим тикам того же
+//! символа (тройной барьер): 1, если цена дошла до +`take_profit_pct` раньше, чем
+//! до -`stop_pct`; 0 - наоборот; -1 - ни то ни другое за `horizon`. Так размечаются и отсеянные детекты, по которым сделки не было.
+//! Размеченные строки копятся и пишутся в Parquet группами по `batch_rows`.
+
+use super::{BookSnapshot, DetectionFeatures, FeatureTracker};
+use crate::backtest::market::TradeTick;
+use crate::base_classes::symbol::Symbol;
+use crate::strategy::moon_strategies::mshot::Deltas;
+use anyhow::{Context, Result};
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, Int8Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use chrono::Duration;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct FeatureExportConfig {
+    pub take_profit_pct: f64,
+    pub stop_pct: f64,
+    pub horizon: Duration,
+    /// Окно профиля объёма и глубины прострела
+    pub volume_window: Duration,
+    pub batch_rows: usize,
+}
+
+impl Default for FeatureExportConfig {
+    fn default() -> Self {
+        Self {
+            take_profit_pct: 1.0,
+            stop_pct: 1.0,
+            horizon: Duration::minutes(10),
+            volume_window: Duration::minutes(5),
+            batch_rows: 4096,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DetectionRow {
+    symbol: Symbol,
+    strategy: String,
+    fired: bool,
+    filter_reason: Option<String>,
+    features: DetectionFeatures,
+    max_up_pct: f64,
+    max_down_pct: f64,
+    last_price: f64,
+    label: Option<i8>,
+}
+
+pub struct FeatureExporter {
+    config: FeatureExportConfig,
+    writer: ArrowWriter<File>,
+    schema: Arc<Schema>,
+    tracker: FeatureTracker,
+    /// Детекты, ждущие исхода
+    pending: Vec<DetectionRow>,
+    ready: Vec<DetectionRow>,
+    written: usize,
+}
+
+fn schema() -> Schema {
+    let f64_field = |name: &str, nullable| Field::new(name, DataType::Float64, nullable);
+    Schema::new(vec![
+        Field::new("ts_ms", DataType::Int64, false),
+        Field::new("symbol", DataType::Utf8, false),
+        Field::new("strategy", DataType::Utf8, false),
+        Field::new("fired", DataType::Boolean, false),
+        Field::new("filter_reason", DataType::Utf8, true),
+        f64_field("price", false),
+        f64_field("depth_pct", false),
+        f64_field("delta_15m", false),
+        f64_field("delta_1h", false),
+        f64_field("delta_3h", false),
+        f64_field("delta_market", false),
+        f64_field("delta_btc", false),
+        f64_field("delta_btc_5m", false),
+        f64_field("volume_1m", false),
+        f64_field("volume_window", false),
+        f64_field("buy_share_1m", false),
+        Field::new("trades_1m", DataType::Int64, false),
+        f64_field("spread_bps", true),
+        f64_field("book_imbalance", true),
+        Field::new("minute_of_day", DataType::Int32, false),
+        Field::new("day_of_week", DataType::Int32, false),
+        Field::new("label", DataType::Int8, false),
+        f64_field("forward_return_pct", false),
+        f64_field("max_up_pct", false),
+        f64_field("max_down_pct", false),
+    ])
+}
+
+impl FeatureExporter {
+    pub fn create(path: impl AsRef<Path>, config: FeatureExportConfig) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).with_context(|| format!("create {}", path.display()))?;
+        let schema = Arc::new(schema());
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(props)).context("parquet writer")?;
+        Ok(Self {
+            tracker: FeatureTracker::new(config.volume_window),
+            config,
+            writer,
+            schema,
+            pending: Vec::new(),
+            ready: Vec::new(),
+            written: 0,
+        })
+    }
+
+    /// Тик рынка: обновляет профиль объёма и исходы ждущих детектов символа
+    pub fn on_tick(&mut self, tick: &TradeTick) -> Result<()> {
+        self.tracker.on_tick(tick);
+
+        let (take, stop, horizon) = (self.config.take_profit_pct, self.config.stop_pct, self.config.horizon);
+        let mut i = 0;
+        while i < self.pending.len() {
+            let row = &mut self.pending[i];
+            if row.symbol == tick.symbol && tick.timestamp >= row.features.ts {
+                let change = (tick.price - row.features.price) / row.features.price * 100.0;
+                row.max_up_pct = row.max_up_pct.max(change);
+                row.max_down_pct = row.max_down_pct.min(change);
+                row.last_price = tick.price;
+                if change >= take {
+                    row.label = Some(1);
+                } else if change <= -stop {
+                    row.label = Some(0);
+                } else if tick.timestamp - row.features.ts >= horizon {
+                    row.label = Some(-1);
+                }
+            }
+            if self.pending[i].label.is_some() {
+                self.ready.push(self.pending.swap_remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        if self.ready.len() >= self.config.batch_rows {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Детект стратегии на тике `tick`; `filter_reason` - фильтр, отсеявший вход, если известен
+    pub fn record(
+        &mut self,
+        tick: &TradeTick,
+        strategy: &str,
+        fired: bool,
+        filter_reason: Option<&str>,
+        deltas: &Deltas,
+        book: Option<BookSnapshot<'_>>,
+    ) {
+        self.pending.push(DetectionRow {
+            symbol: tick.symbol,
+            strategy: strategy.to_string(),
+            fired,
+            filter_reason: filter_reason.map(str::to_string),
+            features: self.tracker.features(tick, deltas, book),
+            max_up_pct: 0.0,
+            max_down_pct: 0.0,
+            last_price: tick.price,
+            label: None,
+        });
+    }
+
+    /// По последнему ждущему детекту стратегии на символе выставлен вход;
+    /// false, если такого детекта нет
+    pub fn mark_fired(&mut self, symbol: Symbol, strategy: &str) -> bool {
+        match self.pending.iter_mut().rev().find(|r| r.symbol == symbol && r.strategy == strategy) {
+            Some(row) => {
+                row.fired = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.ready.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.ready);
+        let f64_col = |f: &dyn Fn(&DetectionRow) -> f64| -> ArrayRef { Arc::new(Float64Array::from_iter_values(rows.iter().map(f))) };
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.features.ts.timestamp_millis()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.symbol.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.strategy.as_str()))),
+            Arc::new(BooleanArray::from(rows.iter().map(|r| r.fired).collect::<Vec<_>>())),
+            Arc::new(StringArray::from(rows.iter().map(|r| r.filter_reason.as_deref()).collect::<Vec<_>>())),
+            f64_col(&|r| r.features.price),
+            f64_col(&|r| r.features.depth_pct),
+            f64_col(&|r| r.features.deltas.delta_15min),
+            f64_col(&|r| r.features.deltas.delta_hourly),
+            f64_col(&|r| r.features.deltas.delta_3h),
+            f64_col(&|r| r.features.deltas.delta_market),
+            f64_col(&|r| r.features.deltas.delta_btc),
+            f64_col(&|r| r.features.deltas.delta_btc_5m),
+            f64_col(&|r| r.features.volume_1m),
+            f64_col(&|r| r.features.volume_window),
+            f64_col(&|r| r.features.buy_share_1m),
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.features.trades_1m))),
+            Arc::new(Float64Array::from(rows.iter().map(|r| r.features.spread_bps).collect::<Vec<_>>())),
+            Arc::new(Float64Array::from(rows.iter().map(|r| r.features.book_imbalance).collect::<Vec<_>>())),
+            Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.features.minute_of_day()))),
+            Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.features.day_of_week()))),
+            Arc::new(Int8Array::from_iter_values(rows.iter().map(|r| r.label.unwrap_or(-1)))),
+            f64_col(&|r| (r.last_price - r.features.price) / r.features.price * 100.0),
+            f64_col(&|r| r.max_up_pct),
+            f64_col(&|r| r.max_down_pct),
+        ];
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).context("feature batch")?;
+        self.writer.write(&batch).context("write feature batch")?;
+        self.written += rows.len();
+        Ok(())
+    }
+
+    /// Дописывает всё, включая не дождавшиеся исхода детекты (метка -1), и закрывает файл
+    pub fn finish(mut self) -> Result<usize> {
+        for mut row in std::mem::take(&mut self.pending) {
+            row.label = Some(-1);
+            self.ready.push(row);
+        }
+        self.flush()?;
+        self.writer.close().context("close parquet")?;
+        Ok(self.written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::TradeSide;
+    use chrono::{DateTime, Utc};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn tick(symbol: Symbol, at: DateTime<Utc>, price: f64, side: TradeSide) -> TradeTick {
+        TradeTick {
+            timestamp: at,
+            symbol,
+            price,
+            volume: 10.0,
+            side,
+            trade_id: String::new(),
+            best_bid: Some(price - 0.01),
+            best_ask: Some(price + 0.01),
+        }
+    }
+
+    #[test]
+    fn test_labels_follow_first_barrier_and_file_has_rows() {
+        let dir = std::env::temp_dir().join(format!("features_{}.parquet", std::process::id()));
+        let mut exporter = FeatureExporter::create(&dir, FeatureExportConfig::default()).unwrap();
+        let (btc, eth) = (Symbol::new("BTC_USDT"), Symbol::new("ETH_USDT"));
+        let t0 = Utc::now();
+
+        exporter.on_tick(&tick(btc, t0, 100.0, TradeSide::Buy)).unwrap();
+        let shot = tick(btc, t0 + Duration::seconds(5), 96.0, TradeSide::Sell);
+        exporter.on_tick(&shot).unwrap();
+        exporter.record(&shot, "Hook", false, None, &Deltas::default(), None);
+        assert!(exporter.mark_fired(btc, "Hook"));
+        let eth_tick = tick(eth, t0 + Duration::seconds(5), 50.0, TradeSide::Sell);
+        exporter.on_tick(&eth_tick).unwrap();
+        let book = BookSnapshot { bids: &[(49.9, 30.0)], asks: &[(50.1, 10.0)] };
+        exporter.record(&eth_tick, "MStrike", false, Some("min_volume"), &Deltas::default(), Some(book));
+        assert!(!exporter.mark_fired(eth, "Hook"));
+        assert_eq!(exporter.pending(), 2);
+
+        // BTC отскочил на +1% - метка 1; ETH не размечается чужими тиками
+        exporter.on_tick(&tick(btc, t0 + Duration::seconds(30), 97.0, TradeSide::Buy)).unwrap();
+        assert_eq!(exporter.pending(), 1);
+        assert_eq!(exporter.ready[0].label, Some(1));
+        assert!(exporter.ready[0].fired);
+        assert!((exporter.ready[0].features.depth_pct - 4.0).abs() < 1e-9);
+        assert!((exporter.ready[0].features.buy_share_1m - 0.5).abs() < 1e-9);
+
+        assert_eq!(exporter.finish().unwrap(), 2);
+        let reader = SerializedFileReader::new(File::open(&dir).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        std::fs::remove_file(&dir).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+//! Признаки детектов для ML-фильтра
+//!
+//! Один и тот же вектор признаков пишется в Parquet для обучения (`export`, фича
+//! `ml_export`) и подаётся в обученную модель перед входом (`model`, фича `onnx`):
+//! глубина прострела, дельты, профиль объёма, спред, дисбаланс стакана, время суток.
+
+#[cfg(feature = "ml_export")]
+pub mod export;
+#[cfg(feature = "onnx")]
+pub mod model;
+
+use super::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use crate::strategy::moon_strategies::mshot::Deltas;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use std::collections::{HashMap, VecDeque};
+
+#[cfg(feature = "ml_export")]
+pub use export::{FeatureExportConfig, FeatureExporter};
+#[cfg(feature = "onnx")]
+pub use model::{EntryModel, ModelFilteredAdapter};
+
+/// Порядок признаков во входе модели - как колонки Parquet-экспорта
+pub const FEATURE_NAMES: [&str; 15] = [
+    "depth_pct",
+    "delta_15m",
+    "delta_1h",
+    "delta_3h",
+    "delta_market",
+    "delta_btc",
+    "delta_btc_5m",
+    "volume_1m",
+    "volume_window",
+    "buy_share_1m",
+    "trades_1m",
+    "spread_bps",
+    "book_imbalance",
+    "minute_of_day",
+    "day_of_week",
+];
+
+/// Стакан на момент детекта: уровни (цена, объём), лучшие первыми
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BookSnapshot<'a> {
+    pub bids: &'a [(f64, f64)],
+    pub asks: &'a [(f64, f64)],
+}
+
+impl BookSnapshot<'_> {
+    /// (bid - ask) / (bid + ask) по объёму видимых уровней, от -1 до 1
+    pub fn imbalance(&self) -> Option<f64> {
+        let bid: f64 = self.bids.iter().map(|&(_, size)| size).sum();
+        let ask: f64 = self.asks.iter().map(|&(_, size)| size).sum();
+        (bid + ask > 0.0).then(|| (bid - ask) / (bid + ask))
+    }
+}
+
+/// Признаки одного детекта
+#[derive(Debug, Clone)]
+pub struct DetectionFeatures {
+    pub ts: DateTime<Utc>,
+    pub price: f64,
+    /// Падение от максимума окна объёма, %
+    pub depth_pct: f64,
+    pub deltas: Deltas,
+    pub volume_1m: f64,
+    pub volume_window: f64,
+    pub buy_share_1m: f64,
+    pub trades_1m: i64,
+    pub spread_bps: Option<f64>,
+    pub book_imbalance: Option<f64>,
+}
+
+impl DetectionFeatures {
+    pub fn minute_of_day(&self) -> i32 {
+        (self.ts.hour() * 60 + self.ts.minute()) as i32
+    }
+
+    pub fn day_of_week(&self) -> i32 {
+        self.ts.weekday().num_days_from_monday() as i32
+    }
+
+    /// Вход модели в порядке `FEATURE_NAMES`; нет стакана или спреда - NaN
+    pub fn to_vector(&self) -> [f32; FEATURE_NAMES.len()] {
+        [
+            self.depth_pct,
+            self.deltas.delta_15min,
+            self.deltas.delta_hourly,
+            self.deltas.delta_3h,
+            self.deltas.delta_market,
+            self.deltas.delta_btc,
+            self.deltas.delta_btc_5m,
+            self.volume_1m,
+            self.volume_window,
+            self.buy_share_1m,
+            self.trades_1m as f64,
+            self.spread_bps.unwrap_or(f64::NAN),
+            self.book_imbalance.unwrap_or(f64::NAN),
+            self.minute_of_day() as f64,
+            self.day_of_week() as f64,
+        ]
+        .map(|x| x as f32)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RecentTrade {
+    ts: DateTime<Utc>,
+    price: f64,
+    volume: f64,
+    buy: bool,
+}
+
+/// Скользящее окно сделок по символам, из которого считаются признаки
+#[derive(Debug, Clone)]
+pub struct FeatureTracker {
+    window: Duration,
+    recent: HashMap<Symbol, VecDeque<RecentTrade>>,
+}
+
+impl FeatureTracker {
+    /// `window` - окно профиля объёма и глубины прострела, не меньше минуты
+    pub fn new(window: Duration) -> Self {
+        Self { window: window.max(Duration::minutes(1)), recent: HashMap::new() }
+    }
+
+    pub fn on_tick(&mut self, tick: &TradeTick) {
+        let recent = self.recent.entry(tick.symbol).or_default();
+        recent.push_back(RecentTrade {
+            ts: tick.timestamp,
+            price: tick.price,
+            volume: tick.volume,
+            buy: tick.side == TradeSide::Buy,
+        });
+        while recent.front().is_some_and(|t| tick.timestamp - t.ts > self.window) {
+            recent.pop_front();
+        }
+    }
+
+    pub fn features(&self, tick: &TradeTick, deltas: &Deltas, book: Option<BookSnapshot<'_>>) -> DetectionFeatures {
+        let recent = self.recent.get(&tick.symbol);
+        let trades = || recent.into_iter().flatten();
+        let minute_ago = tick.timestamp - Duration::minutes(1);
+        let last_minute = || trades().filter(|t| t.ts >= minute_ago);
+        let volume_1m: f64 = last_minute().map(|t| t.volume).sum();
+        let buy_1m: f64 = last_minute().filter(|t| t.buy).map(|t| t.volume).sum();
+        let high = trades().map(|t| t.price).fold(tick.price, f64::max);
+        let spread_bps = match (tick.best_bid, tick.best_ask) {
+            (Some(bid), Some(ask)) if bid > 0.0 && ask >= bid => Some((ask - bid) / ((ask + bid) / 2.0) * 10_000.0),
+            _ => None,
+        };
+        DetectionFeatures {
+            ts: tick.timestamp,
+            price: tick.price,
+            depth_pct: (high - tick.price) / high * 100.0,
+            deltas: deltas.clone(),
+            volume_1m,
+            volume_window: trades().map(|t| t.volume).sum(),
+            buy_share_1m: if volume_1m > 0.0 { buy_1m / volume_1m } else { 0.0 },
+            trades_1m: last_minute().count() as i64,
+            spread_bps,
+            book_imbalance: book.and_then(|b| b.imbalance()),
+        }
+    }
+}
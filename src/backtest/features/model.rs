@@ -0,0 +1,340 @@
+//! Инференс ONNX-модели входов (tract)
+//!
+//! Вход модели - тензор f32 `[1, FEATURE_NAMES.len()]`. Выход - вероятность удачного
+//! исхода: `[1, 1]`, либо вероятности классов `[1, 2]` (берётся класс 1). У моделей
+//! из skl2onnx несколько выходов (метка и вероятности) - берётся последний выход f32,
+//! ZipMap при конвертации нужно отключить.
+
+use super::{BookSnapshot, DetectionFeatures, FeatureTracker, FEATURE_NAMES};
+use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::{
+    StrategyAction, StrategyAdapter, StrategySnapshot, StrategyView, WarmupRequirements,
+};
+use crate::base_classes::liquidations::Liquidation;
+use crate::base_classes::types::Side;
+use crate::execution::TimeInForce;
+use crate::strategy::moon_strategies::{mshot::Deltas, EntryModelConfig, OrderIntent};
+use anyhow::{anyhow, ensure, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use tract_onnx::prelude::*;
+
+type Plan = TypedRunnableModel<TypedModel>;
+
+pub struct EntryModel {
+    plan: Plan,
+    pub threshold: f64,
+}
+
+impl EntryModel {
+    /// Загружает и оптимизирует модель; ошибки конфига и модели - на старте
+    pub fn load(config: &EntryModelConfig) -> Result<Self> {
+        let model = tract_onnx::onnx()
+            .model_for_path(&config.path)
+            .map_err(|err| anyhow!("load entry model {}: {:#}", config.path.display(), err))?;
+        Self::from_model(model, config.threshold)
+    }
+
+    fn from_model(model: InferenceModel, threshold: f64) -> Result<Self> {
+        ensure!((0.0..=1.0).contains(&threshold), "entry model threshold {} outside 0..1", threshold);
+        let plan = model
+            .with_input_fact(0, f32::fact([1, FEATURE_NAMES.len()]).into())
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|err| anyhow!("prepare entry model: {:#}", err))?;
+        Ok(Self { plan, threshold })
+    }
+
+    /// Вероятность удачного исхода детекта
+    pub fn score(&self, features: &DetectionFeatures) -> Result<f64> {
+        let input = Tensor::from_shape(&[1, FEATURE_NAMES.len()], &features.to_vector())
+            .map_err(|err| anyhow!("entry model input: {:#}", err))?;
+        let outputs = self
+            .plan
+            .run(tvec!(input.into()))
+            .map_err(|err| anyhow!("entry model run: {:#}", err))?;
+        let probs = outputs
+            .iter()
+            .rev()
+            .find_map(|output| output.as_slice::<f32>().ok())
+            .context("entry model has no f32 output")?;
+        match probs {
+            [p] | [_, p] => Ok(*p as f64),
+            other => Err(anyhow!("entry model output has {} values, expected 1 or 2", other.len())),
+        }
+    }
+}
+
+/// Адаптер стратегии с ML-фильтром: PlaceBuy с оценкой ниже порога заменяется на
+/// NoAction, а стратегия получает `on_buy_declined`, как при отказе арбитра.
+/// Ошибка инференса тоже пропускает вход.
+pub struct ModelFilteredAdapter<A> {
+    inner: A,
+    model: EntryModel,
+    tracker: FeatureTracker,
+    bids: Vec<(f64, f64)>,
+    asks: Vec<(f64, f64)>,
+    skipped: u64,
+}
+
+impl<A: StrategyAdapter> ModelFilteredAdapter<A> {
+    pub fn new(inner: A, model: EntryModel) -> Self {
+        Self {
+            inner,
+            model,
+            tracker: FeatureTracker::new(Duration::minutes(5)),
+            bids: Vec::new(),
+            asks: Vec::new(),
+            skipped: 0,
+        }
+    }
+
+    /// Сколько входов отсеяно моделью
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    fn decline(&mut self) -> StrategyAction {
+        self.skipped += 1;
+        self.inner.on_buy_declined();
+        StrategyAction::NoAction
+    }
+}
+
+impl<A: StrategyAdapter> StrategyAdapter for ModelFilteredAdapter<A> {
+    fn on_tick(&mut self, tick: &TradeTick, deltas: &Deltas) -> StrategyAction {
+        self.tracker.on_tick(tick);
+        let action = self.inner.on_tick(tick, deltas);
+        if !matches!(action, StrategyAction::PlaceBuy { .. }) {
+            return action;
+        }
+        let book = (!self.bids.is_empty() || !self.asks.is_empty())
+            .then(|| BookSnapshot { bids: &self.bids, asks: &self.asks });
+        let features = self.tracker.features(tick, deltas, book);
+        match self.model.score(&features) {
+            Ok(p) if p >= self.model.threshold => action,
+            Ok(p) => {
+                log::info!(
+                    "{} {} entry skipped by model: p={:.3} < {:.3}",
+                    self.inner.get_name(),
+                    tick.symbol,
+                    p,
+                    self.model.threshold
+                );
+                self.decline()
+            }
+            Err(err) => {
+                log::error!("{} {} entry skipped, model failed: {:#}", self.inner.get_name(), tick.symbol, err);
+                self.decline()
+            }
+        }
+    }
+
+    fn warm_up(&mut self, tick: &TradeTick, deltas: &Deltas) {
+        self.tracker.on_tick(tick);
+        self.inner.warm_up(tick, deltas);
+    }
+
+    fn on_liquidation(&mut self, liquidation: &Liquidation) {
+        self.inner.on_liquidation(liquidation)
+    }
+
+    fn on_stale(&mut self, widen_stops_pct: Option<f64>) {
+        self.inner.on_stale(widen_stops_pct)
+    }
+
+    fn on_timer(&mut self, now: DateTime<Utc>) -> StrategyAction {
+        let action = self.inner.on_timer(now);
+        if !matches!(action, StrategyAction::PlaceBuy { .. }) {
+            return action;
+        }
+        // Вход без тика оценить нечем
+        log::info!("{} entry from timer skipped: no tick to score", self.inner.get_name());
+        self.decline()
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn time_in_force(&self, side: Side) -> TimeInForce {
+        self.inner.time_in_force(side)
+    }
+
+    fn warmup_requirements(&self) -> WarmupRequirements {
+        self.inner.warmup_requirements()
+    }
+
+    fn indicator_ready(&self, indicator: &str) -> bool {
+        self.inner.indicator_ready(indicator)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    fn on_buy_filled(&mut self, price: f64, size: f64) -> Option<StrategyAction> {
+        self.inner.on_buy_filled(price, size)
+    }
+
+    fn on_sell_filled(&mut self, price: f64, size: f64) {
+        self.inner.on_sell_filled(price, size)
+    }
+
+    fn on_buy_declined(&mut self) {
+        self.inner.on_buy_declined()
+    }
+
+    fn on_order_placed(&mut self, side: Side, order_id: u64) {
+        self.inner.on_order_placed(side, order_id)
+    }
+
+    fn on_book(&mut self, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        self.bids.clear();
+        self.bids.extend_from_slice(bids);
+        self.asks.clear();
+        self.asks.extend_from_slice(asks);
+        self.inner.on_book(bids, asks)
+    }
+
+    fn calculate_sell_price(&self, buy_price: f64, current_price: f64) -> Option<f64> {
+        self.inner.calculate_sell_price(buy_price, current_price)
+    }
+
+    fn snapshot(&self) -> Result<StrategySnapshot> {
+        self.inner.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &StrategySnapshot) -> Result<()> {
+        self.inner.restore(snapshot)
+    }
+
+    fn view(&self) -> StrategyView {
+        self.inner.view()
+    }
+
+    fn pending_orders(&self) -> Vec<OrderIntent> {
+        self.inner.pending_orders()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::TradeSide;
+    use crate::backtest::strategy_adapter::HookAdapter;
+    use crate::strategy::moon_strategies::HookConfig;
+    use crate::base_classes::symbol::Symbol;
+    use chrono::{DateTime, Utc};
+    use tract_onnx::pb::tensor_shape_proto::{dimension, Dimension};
+    use tract_onnx::pb::{
+        tensor_proto::DataType, type_proto, GraphProto, ModelProto, NodeProto, OperatorSetIdProto, TensorProto,
+        TensorShapeProto, TypeProto, ValueInfoProto,
+    };
+
+    /// p = sigmoid(depth_pct - min_depth): прострелы мельче `min_depth` отсеиваются
+    fn depth_model(min_depth: f32) -> InferenceModel {
+        let mut weights = vec![0.0f32; FEATURE_NAMES.len()];
+        weights[0] = 1.0;
+        let tensor = |name: &str, dims: Vec<i64>, float_data: Vec<f32>| TensorProto {
+            name: name.to_string(),
+            dims,
+            data_type: DataType::Float as i32,
+            float_data,
+            ..Default::default()
+        };
+        let node = |op: &str, input: &[&str], output: &str| NodeProto {
+            op_type: op.to_string(),
+            input: input.iter().map(|s| s.to_string()).collect(),
+            output: vec![output.to_string()],
+            ..Default::default()
+        };
+        let value = |name: &str, dims: [i64; 2]| ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(TypeProto {
+                value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                    elem_type: DataType::Float as i32,
+                    shape: Some(TensorShapeProto {
+                        dim: dims
+                            .iter()
+                            .map(|&d| Dimension { value: Some(dimension::Value::DimValue(d)), ..Default::default() })
+                            .collect(),
+                    }),
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let proto = ModelProto {
+            ir_version: 8,
+            opset_import: vec![OperatorSetIdProto { domain: String::new(), version: 13 }],
+            graph: Some(GraphProto {
+                node: vec![
+                    node("MatMul", &["features", "w"], "logit"),
+                    node("Add", &["logit", "b"], "shifted"),
+                    node("Sigmoid", &["shifted"], "probability"),
+                ],
+                initializer: vec![
+                    tensor("w", vec![FEATURE_NAMES.len() as i64, 1], weights),
+                    tensor("b", vec![1], vec![-min_depth]),
+                ],
+                input: vec![value("features", [1, FEATURE_NAMES.len() as i64])],
+                output: vec![value("probability", [1, 1])],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        tract_onnx::onnx().model_for_proto_model(&proto).unwrap()
+    }
+
+    fn tick(at: DateTime<Utc>, price: f64) -> TradeTick {
+        TradeTick {
+            timestamp: at,
+            symbol: Symbol::new("BTC_USDT"),
+            price,
+            volume: 1000.0,
+            side: TradeSide::Sell,
+            trade_id: String::new(),
+            best_bid: Some(price),
+            best_ask: Some(price),
+        }
+    }
+
+    /// Прострел на 10% за полсекунды; возвращает действие на втором тике
+    fn crash(adapter: &mut impl StrategyAdapter) -> StrategyAction {
+        let base = Utc::now();
+        let deltas = Deltas::default();
+        adapter.on_book(&[(100.0, 5.0)], &[(100.1, 5.0)]);
+        adapter.on_tick(&tick(base, 100.0), &deltas);
+        adapter.on_tick(&tick(base + Duration::milliseconds(500), 90.0), &deltas)
+    }
+
+    #[test]
+    fn test_model_scores_depth() {
+        let model = EntryModel::from_model(depth_model(3.0), 0.5).unwrap();
+        let mut tracker = FeatureTracker::new(Duration::minutes(5));
+        let base = Utc::now();
+        tracker.on_tick(&tick(base, 100.0));
+        let shot = tick(base + Duration::seconds(1), 96.0);
+        let book = BookSnapshot { bids: &[(95.9, 5.0)], asks: &[(96.1, 5.0)] };
+        let features = tracker.features(&shot, &Deltas::default(), Some(book));
+        assert!((features.depth_pct - 4.0).abs() < 1e-9);
+        let p = model.score(&features).unwrap();
+        assert!((p - 1.0 / (1.0 + (-1.0f64).exp())).abs() < 1e-5, "{}", p);
+        assert!(EntryModel::from_model(depth_model(3.0), 1.5).is_err());
+    }
+
+    #[test]
+    fn test_entries_below_threshold_are_skipped() {
+        let model = |min_depth| EntryModel::from_model(depth_model(min_depth), 0.5).unwrap();
+        let hook = || HookAdapter::new(HookConfig { hook_roll_back_wait: 0, ..Default::default() });
+        let mut filtered = ModelFilteredAdapter::new(hook(), model(12.0));
+        assert!(matches!(crash(&mut filtered), StrategyAction::NoAction));
+        assert_eq!(filtered.skipped(), 1);
+        // Стратегия получила отказ и не ждёт исполнения
+        assert!(filtered.pending_orders().is_empty());
+
+        let mut filtered = ModelFilteredAdapter::new(hook(), model(3.0));
+        assert!(matches!(crash(&mut filtered), StrategyAction::PlaceBuy { .. }));
+        assert_eq!(filtered.skipped(), 0);
+    }
+}
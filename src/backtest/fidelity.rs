@@ -0,0 +1,208 @@
+//! Сверка симуляции с реальностью
+//!
+//! Записанные тики живой сессии прогоняются через движок с тем же конфигом, а
+//! смоделированные исполнения сопоставляются с реальными из журнала сделок: по
+//! стратегии, символу и стороне, ближайшее по времени в пределах окна. Итог по
+//! каждой паре стратегия/символ — сколько реальных исполнений симуляция пропустила,
+//! сколько придумала лишних, насколько разошлась по времени и цене.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::metrics::BacktestResult;
+use crate::base_classes::symbol::Symbol;
+
+/// Одно исполнение, реальное или смоделированное
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub ts_ms: i64,
+    pub symbol: Symbol,
+    pub strategy: String,
+    pub is_buy: bool,
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Реальные исполнения из CSV `ts_ms,symbol,strategy,side,price,size` (с заголовком)
+pub fn load_fills_csv(path: &Path) -> Result<Vec<Fill>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read fills {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| parse_fill(line).with_context(|| format!("{}:{}", path.display(), n + 1)))
+        .collect()
+}
+
+fn parse_fill(line: &str) -> Result<Fill> {
+    let cols: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [ts, symbol, strategy, side, price, size] = cols.as_slice() else {
+        bail!("expected 6 columns, got {}", cols.len());
+    };
+    let is_buy = match side.to_ascii_lowercase().as_str() {
+        "buy" => true,
+        "sell" => false,
+        other => bail!("side '{}' is neither buy nor sell", other),
+    };
+    Ok(Fill {
+        ts_ms: ts.parse().with_context(|| format!("bad timestamp '{}'", ts))?,
+        symbol: Symbol::new(symbol),
+        strategy: strategy.to_ascii_lowercase(),
+        is_buy,
+        price: price.parse().with_context(|| format!("bad price '{}'", price))?,
+        size: size.parse().with_context(|| format!("bad size '{}'", size))?,
+    })
+}
+
+/// Вход и выход каждой смоделированной сделки как два исполнения
+pub fn simulated_fills(strategy: &str, results: &[(Symbol, BacktestResult)]) -> Vec<Fill> {
+    let strategy = strategy.to_ascii_lowercase();
+    results
+        .iter()
+        .flat_map(|(symbol, result)| result.trades.iter().map(move |t| (*symbol, t)))
+        .flat_map(|(symbol, t)| {
+            let fill = |ts: i64, is_buy: bool, price: f64| Fill {
+                ts_ms: ts,
+                symbol,
+                strategy: strategy.clone(),
+                is_buy,
+                price,
+                size: t.size,
+            };
+            [
+                fill(t.entry_time.timestamp_millis(), t.is_buy, t.entry_price),
+                fill(t.exit_time.timestamp_millis(), !t.is_buy, t.exit_price),
+            ]
+        })
+        .collect()
+}
+
+/// Точность симуляции для одной стратегии на одном символе
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FidelityReport {
+    pub strategy: String,
+    pub symbol: String,
+    pub real: usize,
+    pub simulated: usize,
+    pub matched: usize,
+    /// Реальные исполнения без пары в симуляции
+    pub missed: usize,
+    /// Смоделированные исполнения, которых не было в реальности
+    pub phantom: usize,
+    /// Среднее (симуляция − реальность) по времени у сопоставленных
+    pub mean_skew_ms: f64,
+    pub max_abs_skew_ms: i64,
+    /// Насколько цена симуляции лучше реальной; больше нуля — симуляция оптимистична
+    pub sim_edge_bps: f64,
+}
+
+impl FidelityReport {
+    /// Доля реальных исполнений, воспроизведённых симуляцией
+    pub fn recall(&self) -> f64 {
+        if self.real == 0 { 1.0 } else { self.matched as f64 / self.real as f64 }
+    }
+
+    /// Доля смоделированных исполнений, случившихся и в реальности
+    pub fn precision(&self) -> f64 {
+        if self.simulated == 0 { 1.0 } else { self.matched as f64 / self.simulated as f64 }
+    }
+}
+
+/// Сопоставляет исполнения в пределах `window_ms`; отчёты по стратегии и символу
+pub fn compare(real: &[Fill], simulated: &[Fill], window_ms: i64) -> Vec<FidelityReport> {
+    // (стратегия, символ) -> (реальные, смоделированные)
+    type Group<'a> = (Vec<&'a Fill>, Vec<&'a Fill>);
+    let mut groups: BTreeMap<(String, String), Group> = BTreeMap::new();
+    for fill in real {
+        groups.entry((fill.strategy.clone(), fill.symbol.to_string())).or_default().0.push(fill);
+    }
+    for fill in simulated {
+        groups.entry((fill.strategy.clone(), fill.symbol.to_string())).or_default().1.push(fill);
+    }
+
+    groups
+        .into_iter()
+        .map(|((strategy, symbol), (mut real, mut sim))| {
+            real.sort_by_key(|f| f.ts_ms);
+            sim.sort_by_key(|f| f.ts_ms);
+            let mut taken = vec![false; sim.len()];
+            let (mut skew_sum, mut max_abs_skew, mut edge_sum) = (0i64, 0i64, 0.0);
+            let mut matched = 0;
+            for fill in &real {
+                let from = sim.partition_point(|s| s.ts_ms < fill.ts_ms - window_ms);
+                let best = (from..sim.len())
+                    .take_while(|&i| sim[i].ts_ms <= fill.ts_ms + window_ms)
+                    .filter(|&i| !taken[i] && sim[i].is_buy == fill.is_buy)
+                    .min_by_key(|&i| (sim[i].ts_ms - fill.ts_ms).abs());
+                let Some(i) = best else { continue };
+                taken[i] = true;
+                matched += 1;
+                let skew = sim[i].ts_ms - fill.ts_ms;
+                skew_sum += skew;
+                max_abs_skew = max_abs_skew.max(skew.abs());
+                if fill.price > 0.0 {
+                    let edge = (fill.price - sim[i].price) / fill.price * 10_000.0;
+                    edge_sum += if fill.is_buy { edge } else { -edge };
+                }
+            }
+            FidelityReport {
+                strategy,
+                symbol,
+                real: real.len(),
+                simulated: sim.len(),
+                matched,
+                missed: real.len() - matched,
+                phantom: sim.len() - matched,
+                mean_skew_ms: if matched > 0 { skew_sum as f64 / matched as f64 } else { 0.0 },
+                max_abs_skew_ms: max_abs_skew,
+                sim_edge_bps: if matched > 0 { edge_sum / matched as f64 } else { 0.0 },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(ts_ms: i64, is_buy: bool, price: f64) -> Fill {
+        Fill { ts_ms, symbol: Symbol::new("ETH_USDT"), strategy: "hook".into(), is_buy, price, size: 1.0 }
+    }
+
+    #[test]
+    fn test_compare_counts_missed_and_phantom_fills() {
+        let real = [fill(1_000, true, 100.0), fill(5_000, false, 102.0), fill(20_000, true, 99.0)];
+        let sim = [
+            fill(1_300, true, 99.9),
+            fill(4_800, false, 102.0),
+            // Сторона не та: не пара реальной покупке на 20 000
+            fill(20_100, false, 99.0),
+            fill(40_000, true, 98.0),
+        ];
+        let reports = compare(&real, &sim, 1_000);
+        assert_eq!(reports.len(), 1);
+        let r = &reports[0];
+        assert_eq!((r.real, r.simulated, r.matched, r.missed, r.phantom), (3, 4, 2, 1, 2));
+        assert_eq!(r.mean_skew_ms, 50.0);
+        assert_eq!(r.max_abs_skew_ms, 300);
+        assert!((r.sim_edge_bps - 5.0).abs() < 1e-9);
+        assert!((r.recall() - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(r.precision(), 0.5);
+
+        let other = Fill { strategy: "mshot".into(), ..fill(1_000, true, 100.0) };
+        let reports = compare(&[other], &sim, 1_000);
+        assert_eq!(reports.iter().map(|r| r.strategy.as_str()).collect::<Vec<_>>(), ["hook", "mshot"]);
+        assert_eq!(reports[1].missed, 1);
+
+        assert_eq!(parse_fill("1000, ETH_USDT, Hook, SELL, 101.5, 2").unwrap(), Fill {
+            is_buy: false,
+            price: 101.5,
+            size: 2.0,
+            ..fill(1_000, true, 0.0)
+        });
+        assert!(parse_fill("1000,ETH_USDT,hook,hold,1,1").is_err());
+        assert!(parse_fill("1000,ETH_USDT,hook,buy,1").is_err());
+    }
+}
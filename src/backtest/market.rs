@@ -1,12 +1,13 @@
 //! Состояние рынка и потоки данных
 
+use crate::base_classes::symbol::Symbol;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeTick {
     pub timestamp: DateTime<Utc>,
-    pub symbol: String,
+    pub symbol: Symbol,
     pub price: f64,
     pub volume: f64,
     pub side: TradeSide, // true = buy (taker buy), false = sell (taker sell)
@@ -15,6 +16,23 @@ pub struct TradeTick {
     pub best_ask: Option<f64>, // Лучшая цена продажи из стакана
 }
 
+impl TradeTick {
+    /// Цена для нашей стороны: покупка исполняется об аск, продажа — об бид;
+    /// без записанного стакана — цена сделки
+    pub fn execution_price(&self, is_buy: bool) -> f64 {
+        let quote = if is_buy { self.best_ask } else { self.best_bid };
+        quote.filter(|p| *p > 0.0 && p.is_finite()).unwrap_or(self.price)
+    }
+
+    /// Середина спреда, если записаны обе стороны и стакан не перевёрнут
+    pub fn mid(&self) -> Option<f64> {
+        match (self.best_bid, self.best_ask) {
+            (Some(bid), Some(ask)) if bid > 0.0 && ask >= bid => Some((bid + ask) / 2.0),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TradeSide {
     Buy,  // Taker buy (hit the ask)
@@ -29,13 +47,13 @@ impl From<bool> for TradeSide {
 
 #[derive(Debug, Clone)]
 pub struct TradeStream {
-    pub symbol: String,
+    pub symbol: Symbol,
     pub trades: Vec<TradeTick>,
     pub current_index: Option<usize>,
 }
 
 impl TradeStream {
-    pub fn new(symbol: String, trades: Vec<TradeTick>) -> Self {
+    pub fn new(symbol: Symbol, trades: Vec<TradeTick>) -> Self {
         Self {
             symbol,
             trades,
@@ -66,7 +84,7 @@ impl TradeStream {
 
 #[derive(Debug, Clone)]
 pub struct MarketState {
-    pub symbol: String,
+    pub symbol: Symbol,
     pub current_price: f64,
     pub best_bid: Option<f64>,
     pub best_ask: Option<f64>,
@@ -77,7 +95,7 @@ pub struct MarketState {
 impl MarketState {
     pub fn new() -> Self {
         Self {
-            symbol: String::new(),
+            symbol: Symbol::default(),
             current_price: 0.0,
             best_bid: None,
             best_ask: None,
@@ -87,7 +105,7 @@ impl MarketState {
     }
     
     pub fn update_from_tick(&mut self, tick: &TradeTick) {
-        self.symbol = tick.symbol.clone();
+        self.symbol = tick.symbol;
         self.current_price = tick.price;
         self.last_update = tick.timestamp;
         
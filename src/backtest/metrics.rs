@@ -1,8 +1,10 @@
 //! Метрики бэктеста
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::risk::profiles::ProfileWindow;
+
 #[derive(Debug, Clone, Default)]
 pub struct BacktestMetrics {
     pub total_pnl: f64,
@@ -13,6 +15,8 @@ pub struct BacktestMetrics {
     pub max_profit: f64,
     pub equity_curve: Vec<(DateTime<Utc>, f64)>,
     pub trades: Vec<TradeRecord>,
+    /// Сумма |цена исполнения − середина спреда| × объём по тикам со стаканом
+    pub spread_cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +60,8 @@ pub struct BacktestResult {
     pub rating: StrategyRating,     // Рейтинг стратегии
     pub trades: Vec<TradeRecord>,   // Список всех сделок
     pub equity_curve: Vec<(DateTime<Utc>, f64)>, // Кривая equity по времени
+    #[serde(default)]
+    pub spread_cost: f64,           // Издержки на спред, отдельно от P&L
 }
 
 impl BacktestMetrics {
@@ -128,6 +134,7 @@ impl BacktestMetrics {
             max_profit: 0.0,
             equity_curve: Vec::new(),
             trades: Vec::new(),
+            spread_cost: 0.0,
         }
     }
     
@@ -285,7 +292,148 @@ impl BacktestMetrics {
             rating,
             trades: self.trades.clone(),
             equity_curve: self.equity_curve.clone(),
+            spread_cost: self.spread_cost,
+        }
+    }
+}
+
+
+/// P&L и число сделок по дню недели (пн = 0) и часу суток UTC, по времени входа.
+/// Показывает, в какие часы конфиг зарабатывает: отсюда окна `ProfileWindow`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeHeatmap {
+    pub pnl: [[f64; 24]; 7],
+    pub trades: [[usize; 24]; 7],
+}
+
+impl Default for TimeHeatmap {
+    fn default() -> Self {
+        Self { pnl: [[0.0; 24]; 7], trades: [[0; 24]; 7] }
+    }
+}
+
+impl TimeHeatmap {
+    pub fn from_trades(trades: &[TradeRecord]) -> Self {
+        let mut heatmap = Self::default();
+        for trade in trades {
+            heatmap.add(trade.entry_time, trade.pnl);
+        }
+        heatmap
+    }
+
+    pub fn add(&mut self, at: DateTime<Utc>, pnl: f64) {
+        let (day, hour) = (at.weekday().num_days_from_monday() as usize, at.hour() as usize);
+        self.pnl[day][hour] += pnl;
+        self.trades[day][hour] += 1;
+    }
+
+    pub fn merge(&mut self, other: &TimeHeatmap) {
+        for day in 0..7 {
+            for hour in 0..24 {
+                self.pnl[day][hour] += other.pnl[day][hour];
+                self.trades[day][hour] += other.trades[day][hour];
+            }
+        }
+    }
+
+    /// Сумма по всем дням недели: (P&L, сделок) на каждый час
+    pub fn by_hour(&self) -> [(f64, usize); 24] {
+        let mut totals = [(0.0, 0); 24];
+        for day in 0..7 {
+            for (hour, total) in totals.iter_mut().enumerate() {
+                total.0 += self.pnl[day][hour];
+                total.1 += self.trades[day][hour];
+            }
+        }
+        totals
+    }
+
+    /// Сумма по всем часам: (P&L, сделок) на каждый день недели
+    pub fn by_weekday(&self) -> [(f64, usize); 7] {
+        std::array::from_fn(|day| (self.pnl[day].iter().sum(), self.trades[day].iter().sum()))
+    }
+
+    /// Длинный формат `weekday,hour,trades,pnl`, только непустые ячейки
+    pub fn to_csv(&self) -> String {
+        const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+        let mut csv = String::from("weekday,hour,trades,pnl\n");
+        for (day, name) in DAYS.iter().enumerate() {
+            for hour in 0..24 {
+                if self.trades[day][hour] > 0 {
+                    csv.push_str(&format!("{},{},{},{}\n", name, hour, self.trades[day][hour], self.pnl[day][hour]));
+                }
+            }
+        }
+        csv
+    }
+
+    /// Окна из подряд идущих часов с положительным P&L и не меньше `min_trades` сделок
+    /// (по всем дням недели) — заготовка расписания для `RiskProfileBook`. Окно через
+    /// полночь склеивается, весь день — одно окно 0..24
+    pub fn profitable_windows(&self, profile: &str, min_trades: usize) -> Vec<ProfileWindow> {
+        let good: Vec<bool> = self.by_hour().iter().map(|&(pnl, n)| n >= min_trades.max(1) && pnl > 0.0).collect();
+        if good.iter().all(|&g| g) {
+            return vec![ProfileWindow { from_hour: 0, to_hour: 24, profile: profile.to_string() }];
+        }
+        let mut windows: Vec<(u32, u32)> = Vec::new();
+        let mut hour = 0;
+        while hour < 24 {
+            if !good[hour] {
+                hour += 1;
+                continue;
+            }
+            let from = hour;
+            while hour < 24 && good[hour] {
+                hour += 1;
+            }
+            windows.push((from as u32, hour as u32));
+        }
+        if windows.len() > 1 && windows[0].0 == 0 && windows[windows.len() - 1].1 == 24 {
+            let (_, to) = windows.remove(0);
+            windows.last_mut().expect("checked len").1 = to;
         }
+        windows
+            .into_iter()
+            .map(|(from_hour, to_hour)| ProfileWindow { from_hour, to_hour, profile: profile.to_string() })
+            .collect()
+    }
+}
+
+impl BacktestResult {
+    pub fn heatmap(&self) -> TimeHeatmap {
+        TimeHeatmap::from_trades(&self.trades)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_heatmap_cells_and_windows() {
+        let mut metrics = BacktestMetrics::new();
+        // 2024-01-01 — понедельник
+        let at = |day: u32, hour: u32| Utc.with_ymd_and_hms(2024, 1, day, hour, 30, 0).unwrap();
+        for (time, pnl) in [(at(1, 23), 2.0), (at(2, 0), 1.0), (at(3, 0), 1.0), (at(1, 9), -3.0), (at(6, 14), 5.0)] {
+            metrics.record_trade("ETH_USDT".into(), 100.0, 100.0, 1.0, true, pnl, time);
+        }
+        let heatmap = metrics.to_result().heatmap();
+        assert_eq!((heatmap.trades[0][23], heatmap.pnl[0][23]), (1, 2.0));
+        assert_eq!(heatmap.by_hour()[0], (2.0, 2));
+        assert_eq!(heatmap.by_weekday()[5], (5.0, 1));
+        assert!(heatmap.to_csv().lines().any(|l| l == "sat,14,1,5"));
+
+        let windows = heatmap.profitable_windows("day", 1);
+        let spans: Vec<_> = windows.iter().map(|w| (w.from_hour, w.to_hour)).collect();
+        assert_eq!(spans, [(14, 15), (23, 1)]);
+        assert!(windows[1].contains(at(4, 0)));
+        assert!(heatmap.profitable_windows("day", 2).iter().all(|w| w.from_hour == 0));
+
+        let mut doubled = heatmap.clone();
+        doubled.merge(&heatmap);
+        assert_eq!(doubled.by_hour()[0], (4.0, 4));
+        let json = serde_json::to_string(&heatmap).unwrap();
+        assert_eq!(serde_json::from_str::<TimeHeatmap>(&json).unwrap(), heatmap);
+    }
+}
@@ -10,16 +10,41 @@ pub mod bin_format;
 pub mod orderbook;
 pub mod filters;
 pub mod delta_calculator;
+pub mod regimes;
+pub mod tick_guard;
 #[cfg(feature = "gate_exec")]
 pub mod strategy_adapter;
+#[cfg(feature = "gate_exec")]
+pub mod features;
+#[cfg(feature = "gate_exec")]
+pub mod optimize;
+#[cfg(feature = "gate_exec")]
+pub mod cache;
+#[cfg(feature = "gate_exec")]
+pub mod fidelity;
 
-pub use engine::{BacktestEngine, BacktestSettings, ExecutionMode};
-pub use emulator::{MarketEmulator, EmulatorSettings};
+pub use engine::{BacktestEngine, BacktestSettings, ExecutionMode, ENGINE_VERSION};
+pub use emulator::{MarketEmulator, EmulatorSettings, LimitFillModel};
 pub use market::{MarketState, TradeStream, TradeTick};
 pub use replay::{ReplayEngine, ReplaySettings};
-pub use metrics::{BacktestMetrics, BacktestResult};
+pub use metrics::{BacktestMetrics, BacktestResult, TimeHeatmap};
 pub use bin_format::{BinFileReader, BinFileWriter, TradeRecord};
 pub use orderbook::{OrderBook, OrderLevel, FillModel};
 pub use filters::{MarketFilters, MarketSelector, SortCriterion};
 pub use delta_calculator::DeltaCalculator;
+pub use regimes::RegimeStats;
+pub use tick_guard::{TickGuard, TickGuardConfig, TickGuardStats, TickRejection};
+#[cfg(feature = "gate_exec")]
+pub use optimize::{
+    grid, optimize, optimize_with, run_backtest, run_backtest_with, Objective, OptimizeOptions, OptimizeRun, ParamRange,
+    Progress, StrategyKind,
+};
+#[cfg(feature = "gate_exec")]
+pub use cache::{data_hash, ResultCache};
+#[cfg(feature = "gate_exec")]
+pub use fidelity::{compare, load_fills_csv, simulated_fills, FidelityReport, Fill};
+#[cfg(feature = "gate_exec")]
+pub use features::{BookSnapshot, DetectionFeatures, FeatureTracker};
+#[cfg(feature = "ml_export")]
+pub use features::{FeatureExportConfig, FeatureExporter};
 
@@ -0,0 +1,605 @@
+//! Прогон бэктеста по слоям конфига и перебор параметров по сетке
+//!
+//! Каждый файл данных (поток одного символа) гоняется в своём движке: стратегия движка
+//! видит все его потоки, а конфиг собирается на символ из `ConfigLayers`. Перебор
+//! добавляет поверх базы слой с очередной комбинацией параметров, прогоняет все потоки
+//! с одним seed и ранжирует комбинации по выбранной цели.
+//!
+//! Долгие прогоны шлют `Progress` в канал (тики, ETA, лучший результат), а перебор
+//! после каждой комбинации сохраняет контрольную точку, с которой прерванный прогон
+//! продолжается без повторного счёта. Кэш результатов (`cache::ResultCache`) переживает
+//! и смену сетки: считаются только комбинации, которых в нём ещё нет.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use super::cache::{data_hash, ResultCache};
+use super::engine::{BacktestEngine, BacktestSettings};
+use super::market::TradeStream;
+use super::metrics::BacktestResult;
+use super::strategy_adapter::{with_entry_model, HookAdapter, MShotAdapter, MStrikeAdapter, StrategyAdapter};
+use crate::base_classes::symbol::Symbol;
+use crate::strategy::moon_strategies::{ConfigLayers, HookConfig, MShotConfig, MStrikeConfig};
+
+/// Больше комбинаций перебор не запускает: сетка почти наверняка задана по ошибке
+pub const MAX_GRID: usize = 10_000;
+
+/// Шаг отчёта о прогрессе внутри движка
+const PROGRESS_EVERY_TICKS: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyKind {
+    Hook,
+    MShot,
+    MStrike,
+}
+
+impl StrategyKind {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hook" => Ok(Self::Hook),
+            "mshot" => Ok(Self::MShot),
+            "mstrike" => Ok(Self::MStrike),
+            other => bail!("unknown strategy '{}', expected hook, mshot or mstrike", other),
+        }
+    }
+
+    pub fn validate(self, layers: &ConfigLayers) -> Result<()> {
+        match self {
+            Self::Hook => layers.validate::<HookConfig>(),
+            Self::MShot => layers.validate::<MShotConfig>(),
+            Self::MStrike => layers.validate::<MStrikeConfig>(),
+        }
+    }
+
+    /// Адаптер стратегии с конфигом символа
+    pub fn adapter(self, layers: &ConfigLayers, symbol: Symbol) -> Result<Box<dyn StrategyAdapter + Send>> {
+        match self {
+            Self::Hook => {
+                let config: HookConfig = layers.resolve(symbol)?;
+                let model = config.entry_model.clone();
+                with_entry_model(HookAdapter::new(config), model.as_ref())
+            }
+            Self::MShot => {
+                let config: MShotConfig = layers.resolve(symbol)?;
+                let model = config.entry_model.clone();
+                with_entry_model(MShotAdapter::new(config), model.as_ref())
+            }
+            Self::MStrike => Ok(Box::new(MStrikeAdapter::new(layers.resolve(symbol)?))),
+        }
+    }
+}
+
+/// Значения одного параметра: `name=lo:hi:step` или `name=a,b,c`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamRange {
+    pub name: String,
+    pub values: Vec<Value>,
+}
+
+impl ParamRange {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, values) = spec.split_once('=').ok_or_else(|| anyhow!("parameter '{}' is not name=values", spec))?;
+        let name = name.trim().to_string();
+        let values = values.trim();
+        let parsed = match values.split(':').collect::<Vec<_>>().as_slice() {
+            [lo, hi, step] => {
+                let num = |s: &str| s.trim().parse::<f64>().with_context(|| format!("{}: '{}' is not a number", name, s));
+                let (lo, hi, step) = (num(lo)?, num(hi)?, num(step)?);
+                if !(step > 0.0 && lo <= hi) {
+                    bail!("{}: range {}:{}:{} needs lo <= hi and a positive step", name, lo, hi, step);
+                }
+                let count = ((hi - lo) / step + 1e-9).floor() as usize + 1;
+                (0..count).map(|i| Value::from(lo + step * i as f64)).collect()
+            }
+            [_] => values.split(',').map(|v| scalar(v.trim())).collect(),
+            _ => bail!("{}: '{}' is neither lo:hi:step nor a comma list", name, values),
+        };
+        Ok(Self { name, values: parsed })
+    }
+}
+
+fn scalar(text: &str) -> Value {
+    if let Ok(b) = text.parse::<bool>() {
+        return Value::from(b);
+    }
+    if let Ok(n) = text.parse::<f64>() {
+        return Value::from(n);
+    }
+    Value::from(text)
+}
+
+/// Декартово произведение параметров
+pub fn grid(params: &[ParamRange]) -> Result<Vec<Map<String, Value>>> {
+    let size = params.iter().try_fold(1usize, |acc, p| acc.checked_mul(p.values.len()));
+    match size {
+        Some(0) => bail!("parameter grid is empty"),
+        Some(n) if n <= MAX_GRID => {}
+        _ => bail!("parameter grid has more than {} combinations", MAX_GRID),
+    }
+    let mut combos = vec![Map::new()];
+    for param in params {
+        combos = combos
+            .into_iter()
+            .flat_map(|combo| {
+                param.values.iter().map(move |value| {
+                    let mut next = combo.clone();
+                    next.insert(param.name.clone(), value.clone());
+                    next
+                })
+            })
+            .collect();
+    }
+    Ok(combos)
+}
+
+/// Гоняет каждый поток в отдельном движке с конфигом его символа
+pub fn run_backtest(
+    kind: StrategyKind,
+    layers: &ConfigLayers,
+    streams: &[TradeStream],
+    settings: &BacktestSettings,
+) -> Result<Vec<(Symbol, BacktestResult)>> {
+    run_backtest_with(kind, layers, streams, settings, None)
+}
+
+/// `run_backtest` с отчётами о прогрессе в канал
+pub fn run_backtest_with(
+    kind: StrategyKind,
+    layers: &ConfigLayers,
+    streams: &[TradeStream],
+    settings: &BacktestSettings,
+    progress: Option<Sender<Progress>>,
+) -> Result<Vec<(Symbol, BacktestResult)>> {
+    let tracker = progress.map(|tx| Tracker::new(tx, 1, ticks_in(streams)));
+    run_streams(kind, layers, streams, settings, tracker.as_ref())
+}
+
+fn run_streams(
+    kind: StrategyKind,
+    layers: &ConfigLayers,
+    streams: &[TradeStream],
+    settings: &BacktestSettings,
+    tracker: Option<&Tracker>,
+) -> Result<Vec<(Symbol, BacktestResult)>> {
+    if streams.is_empty() {
+        bail!("no trade streams to backtest");
+    }
+    let mut offset = 0;
+    streams
+        .iter()
+        .map(|stream| {
+            let mut engine = BacktestEngine::new(settings.clone());
+            engine.add_stream(stream.clone());
+            engine.add_boxed_strategy_adapter(kind.adapter(layers, stream.symbol)?);
+            if let Some(tracker) = tracker {
+                engine.set_progress(PROGRESS_EVERY_TICKS, tracker.callback(offset));
+            }
+            let result = engine.run().with_context(|| format!("backtest of {}", stream.symbol))?;
+            offset += stream.trades.len() as u64;
+            Ok((stream.symbol, result))
+        })
+        .collect()
+}
+
+fn ticks_in(streams: &[TradeStream]) -> u64 {
+    streams.iter().map(|s| s.trades.len() as u64).sum()
+}
+
+/// Снимок прогресса прогона; тики и ETA считаются только по работе текущего запуска,
+/// комбинации из контрольной точки входят в `combos_done`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub ticks_done: u64,
+    pub ticks_total: u64,
+    pub combos_done: usize,
+    pub combos_total: usize,
+    pub elapsed: Duration,
+    /// `None`, пока не обработано ни одного тика
+    pub eta: Option<Duration>,
+    /// Лучший результат среди завершённых комбинаций
+    pub best: Option<(f64, Map<String, Value>)>,
+}
+
+/// Общее состояние для колбэков движков: база прогресса обновляется между комбинациями
+struct Tracker {
+    tx: Sender<Progress>,
+    started: Instant,
+    base: Progress,
+}
+
+impl Tracker {
+    fn new(tx: Sender<Progress>, combos_total: usize, ticks_total: u64) -> Self {
+        let base = Progress {
+            ticks_done: 0,
+            ticks_total,
+            combos_done: 0,
+            combos_total,
+            elapsed: Duration::ZERO,
+            eta: None,
+            best: None,
+        };
+        Self { tx, started: Instant::now(), base }
+    }
+
+    /// Колбэк движка, чьи тики начинаются с `offset` в счёте прогона
+    fn callback(&self, offset: u64) -> crate::backtest::engine::ProgressCallback {
+        let (tx, started, base) = (self.tx.clone(), self.started, self.base.clone());
+        let offset = base.ticks_done + offset;
+        Box::new(move |ticks, _pnl| {
+            let _ = tx.send(snapshot(&base, started, offset + ticks));
+        })
+    }
+
+    fn advance(&mut self, ticks: u64, best: Option<(f64, Map<String, Value>)>) {
+        self.base.ticks_done += ticks;
+        self.base.combos_done += 1;
+        self.base.best = best;
+        let _ = self.tx.send(snapshot(&self.base, self.started, self.base.ticks_done));
+    }
+}
+
+fn snapshot(base: &Progress, started: Instant, ticks_done: u64) -> Progress {
+    let elapsed = started.elapsed();
+    let ticks_done = ticks_done.min(base.ticks_total);
+    let eta = (ticks_done > 0)
+        .then(|| elapsed.mul_f64((base.ticks_total - ticks_done) as f64 / ticks_done as f64));
+    Progress { ticks_done, elapsed, eta, ..base.clone() }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    Pnl,
+    Sharpe,
+    ProfitFactor,
+}
+
+impl Objective {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "pnl" => Ok(Self::Pnl),
+            "sharpe" => Ok(Self::Sharpe),
+            "profit-factor" | "pf" => Ok(Self::ProfitFactor),
+            other => bail!("unknown objective '{}', expected pnl, sharpe or profit-factor", other),
+        }
+    }
+}
+
+/// Итог одной комбинации по всем символам
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptimizeRun {
+    pub params: Map<String, Value>,
+    pub pnl: f64,
+    pub trades: usize,
+    /// Средний Sharpe по символам
+    pub sharpe: f64,
+    /// Сумма прибыльных и модуль суммы убыточных сделок
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    /// Худшая просадка среди символов
+    pub max_drawdown: f64,
+}
+
+impl OptimizeRun {
+    fn from_results(params: Map<String, Value>, results: &[(Symbol, BacktestResult)]) -> Self {
+        let (wins, losses) = results.iter().flat_map(|(_, r)| r.trades.iter()).fold((0.0, 0.0), |(w, l), t| {
+            if t.pnl > 0.0 { (w + t.pnl, l) } else { (w, l - t.pnl) }
+        });
+        Self {
+            params,
+            pnl: results.iter().map(|(_, r)| r.total_pnl).sum(),
+            trades: results.iter().map(|(_, r)| r.total_trades).sum(),
+            sharpe: results.iter().map(|(_, r)| r.sharpe_ratio).sum::<f64>() / results.len() as f64,
+            gross_profit: wins,
+            gross_loss: losses,
+            max_drawdown: results.iter().map(|(_, r)| r.max_drawdown).fold(0.0, f64::max),
+        }
+    }
+
+    pub fn profit_factor(&self) -> f64 {
+        if self.gross_loss > 0.0 {
+            self.gross_profit / self.gross_loss
+        } else if self.gross_profit > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+
+    pub fn score(&self, objective: Objective) -> f64 {
+        match objective {
+            Objective::Pnl => self.pnl,
+            Objective::Sharpe => self.sharpe,
+            Objective::ProfitFactor => self.profit_factor(),
+        }
+    }
+}
+
+/// Перебирает сетку поверх базы `layers`; результаты от лучшего к худшему.
+/// Без seed в `settings` комбинации сравнивались бы на разном шуме эмулятора.
+pub fn optimize(
+    kind: StrategyKind,
+    layers: &ConfigLayers,
+    params: &[ParamRange],
+    streams: &[TradeStream],
+    settings: &BacktestSettings,
+    objective: Objective,
+) -> Result<Vec<OptimizeRun>> {
+    optimize_with(kind, layers, params, streams, settings, objective, &OptimizeOptions::default())
+}
+
+/// Прогресс, контрольная точка и кэш перебора
+#[derive(Debug, Clone, Default)]
+pub struct OptimizeOptions {
+    /// JSON с завершёнными комбинациями: перезаписывается после каждой, при повторном
+    /// запуске с теми же входными данными готовые комбинации не пересчитываются
+    pub checkpoint: Option<PathBuf>,
+    /// Каталог кэша результатов, общий для всех переборов
+    pub cache: Option<PathBuf>,
+    pub progress: Option<Sender<Progress>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Checkpoint {
+    /// Стратегия, слои, сетка, seed и данные: с другими входами точка не годится
+    fingerprint: Value,
+    runs: Vec<OptimizeRun>,
+}
+
+impl Checkpoint {
+    fn load(path: &Path, fingerprint: &Value) -> Result<Vec<OptimizeRun>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let text = std::fs::read_to_string(path).with_context(|| format!("read checkpoint {}", path.display()))?;
+        let saved: Checkpoint =
+            serde_json::from_str(&text).with_context(|| format!("parse checkpoint {}", path.display()))?;
+        if saved.fingerprint != *fingerprint {
+            bail!(
+                "checkpoint {} belongs to a different optimization (strategy, layers, grid, seed or data changed); \
+                 remove it or pass another path",
+                path.display()
+            );
+        }
+        Ok(saved.runs)
+    }
+
+    fn save(path: &Path, fingerprint: &Value, runs: &[OptimizeRun]) -> Result<()> {
+        let body = json!({ "fingerprint": fingerprint, "runs": runs });
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&body)?).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("replace checkpoint {}", path.display()))?;
+        Ok(())
+    }
+}
+
+fn fingerprint(
+    kind: StrategyKind,
+    layers: &ConfigLayers,
+    combos: &[Map<String, Value>],
+    streams: &[TradeStream],
+    settings: &BacktestSettings,
+) -> Result<Value> {
+    let data: Vec<Value> = streams
+        .iter()
+        .map(|s| {
+            json!({
+                "symbol": s.symbol.to_string(),
+                "trades": s.trades.len(),
+                "first": s.trades.first().map(|t| t.timestamp.timestamp_millis()),
+                "last": s.trades.last().map(|t| t.timestamp.timestamp_millis()),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "strategy": format!("{:?}", kind),
+        "layers": serde_json::to_value(layers)?,
+        "grid": combos,
+        "seed": settings.random_seed,
+        "data": data,
+    }))
+}
+
+pub fn optimize_with(
+    kind: StrategyKind,
+    layers: &ConfigLayers,
+    params: &[ParamRange],
+    streams: &[TradeStream],
+    settings: &BacktestSettings,
+    objective: Objective,
+    options: &OptimizeOptions,
+) -> Result<Vec<OptimizeRun>> {
+    if settings.random_seed.is_none() {
+        bail!("optimization needs a fixed random seed");
+    }
+    let combos = grid(params)?;
+    let fingerprint = fingerprint(kind, layers, &combos, streams, settings)?;
+    let mut runs = match &options.checkpoint {
+        Some(path) => Checkpoint::load(path, &fingerprint)?,
+        None => Vec::new(),
+    };
+    let cache = options.cache.as_deref().map(ResultCache::open).transpose()?;
+    let data = cache.as_ref().map(|_| data_hash(streams));
+    let fresh: Vec<_> = combos.into_iter().filter(|combo| !runs.iter().any(|r| r.params == *combo)).collect();
+    let mut pending = Vec::new();
+    for combo in fresh {
+        let mut candidate = layers.clone();
+        candidate.base.extend(combo.clone());
+        kind.validate(&candidate).with_context(|| format!("parameters {}", Value::Object(combo.clone())))?;
+        let key = match (&cache, &data) {
+            (Some(cache), Some(data)) => {
+                let key = ResultCache::key(data, kind, &candidate, settings)?;
+                if let Some(cached) = cache.get(&key)? {
+                    runs.push(OptimizeRun { params: combo, ..cached });
+                    continue;
+                }
+                Some(key)
+            }
+            _ => None,
+        };
+        pending.push((combo, candidate, key));
+    }
+
+    let per_combo = ticks_in(streams);
+    let mut tracker = options.progress.clone().map(|tx| {
+        let mut tracker = Tracker::new(tx, runs.len() + pending.len(), per_combo * pending.len() as u64);
+        tracker.base.combos_done = runs.len();
+        tracker.base.best = best_of(&runs, objective);
+        tracker
+    });
+    for (combo, candidate, key) in pending {
+        let results = run_streams(kind, &candidate, streams, settings, tracker.as_ref())?;
+        let run = OptimizeRun::from_results(combo, &results);
+        if let (Some(cache), Some(key)) = (&cache, &key) {
+            cache.put(key, &run)?;
+        }
+        runs.push(run);
+        if let Some(path) = &options.checkpoint {
+            Checkpoint::save(path, &fingerprint, &runs)?;
+        }
+        if let Some(tracker) = &mut tracker {
+            tracker.advance(per_combo, best_of(&runs, objective));
+        }
+    }
+    runs.sort_by(|a, b| b.score(objective).total_cmp(&a.score(objective)));
+    Ok(runs)
+}
+
+fn best_of(runs: &[OptimizeRun], objective: Objective) -> Option<(f64, Map<String, Value>)> {
+    // При равенстве — первая, как после устойчивой сортировки в `optimize_with`
+    runs.iter()
+        .rev()
+        .max_by(|a, b| a.score(objective).total_cmp(&b.score(objective)))
+        .map(|r| (r.score(objective), r.params.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param_ranges_and_grid() {
+        let depth = ParamRange::parse("hook_detect_depth=3:5:0.5").unwrap();
+        assert_eq!(depth.values, [3.0, 3.5, 4.0, 4.5, 5.0].map(Value::from));
+        let pump = ParamRange::parse("hook_anti_pump=true,false").unwrap();
+        assert_eq!(pump.values, [Value::from(true), Value::from(false)]);
+
+        let combos = grid(&[depth, pump]).unwrap();
+        assert_eq!(combos.len(), 10);
+        assert_eq!(combos[1]["hook_detect_depth"], 3.0);
+        assert_eq!(combos[1]["hook_anti_pump"], false);
+
+        assert!(ParamRange::parse("hook_detect_depth").is_err());
+        assert!(ParamRange::parse("hook_detect_depth=5:3:1").is_err());
+        assert!(ParamRange::parse("hook_detect_depth=1:2:0").is_err());
+        let huge = ParamRange::parse("order_size=1:100000:1").unwrap();
+        assert!(grid(&[huge]).is_err());
+        assert_eq!(Objective::parse("profit_factor").unwrap(), Objective::ProfitFactor);
+        assert!(StrategyKind::parse("grid").is_err());
+    }
+
+    #[test]
+    fn test_optimize_ranks_combinations_on_the_same_data() {
+        let ticks = crate::testing::ScenarioBuilder::new("ETH_USDT", 100.0)
+            .flat(5_000)
+            .crash(8.0, 1_000)
+            .flat(5_000)
+            .build();
+        let streams = [TradeStream::new(Symbol::new("ETH_USDT"), ticks)];
+        let settings = BacktestSettings { random_seed: Some(7), ..BacktestSettings::default() };
+        let params = [ParamRange::parse("hook_detect_depth=2,4").unwrap()];
+        let runs = optimize(StrategyKind::Hook, &ConfigLayers::default(), &params, &streams, &settings, Objective::Pnl).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs[0].pnl >= runs[1].pnl);
+
+        let unseeded = BacktestSettings::default();
+        assert!(optimize(StrategyKind::Hook, &ConfigLayers::default(), &params, &streams, &unseeded, Objective::Pnl).is_err());
+        let bad = [ParamRange::parse("hook_detect_depth=-1").unwrap()];
+        assert!(optimize(StrategyKind::Hook, &ConfigLayers::default(), &bad, &streams, &settings, Objective::Pnl).is_err());
+    }
+
+    #[test]
+    fn test_interrupted_optimization_resumes_from_checkpoint() {
+        let ticks = crate::testing::ScenarioBuilder::new("ETH_USDT", 100.0)
+            .flat(5_000)
+            .crash(8.0, 1_000)
+            .flat(5_000)
+            .build();
+        let streams = [TradeStream::new(Symbol::new("ETH_USDT"), ticks)];
+        let settings = BacktestSettings { random_seed: Some(7), ..BacktestSettings::default() };
+        let params = [ParamRange::parse("hook_detect_depth=2,3,4").unwrap()];
+        let layers = ConfigLayers::default();
+        let path = std::env::temp_dir().join(format!("optimize_checkpoint_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let options = OptimizeOptions { checkpoint: Some(path.clone()), progress: Some(tx), cache: None };
+        let full = optimize_with(StrategyKind::Hook, &layers, &params, &streams, &settings, Objective::Pnl, &options).unwrap();
+        drop(options);
+        let reports: Vec<Progress> = rx.iter().collect();
+        let last = reports.last().unwrap();
+        assert_eq!((last.combos_done, last.combos_total), (3, 3));
+        assert_eq!(last.ticks_done, last.ticks_total);
+        assert_eq!(last.best.as_ref().unwrap().1, full[0].params);
+        assert!(reports.windows(2).all(|w| w[0].ticks_done <= w[1].ticks_done));
+
+        // Прерывание после первой комбинации: в точке остаётся одна
+        let mut saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        saved["runs"].as_array_mut().unwrap().truncate(1);
+        std::fs::write(&path, saved.to_string()).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let options = OptimizeOptions { checkpoint: Some(path.clone()), progress: Some(tx), cache: None };
+        let resumed = optimize_with(StrategyKind::Hook, &layers, &params, &streams, &settings, Objective::Pnl, &options).unwrap();
+        drop(options);
+        assert_eq!(resumed, full);
+        let reports: Vec<Progress> = rx.iter().collect();
+        assert_eq!(reports[0].combos_done, 1);
+        assert_eq!(reports[0].ticks_total, 2 * streams[0].trades.len() as u64);
+
+        // Другой seed — другая задача: точка не подходит
+        let other = BacktestSettings { random_seed: Some(8), ..settings };
+        let options = OptimizeOptions { checkpoint: Some(path.clone()), ..OptimizeOptions::default() };
+        let err = optimize_with(StrategyKind::Hook, &layers, &params, &streams, &other, Objective::Pnl, &options).unwrap_err();
+        assert!(err.to_string().contains("different optimization"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_widened_grid_only_runs_new_combinations() {
+        let ticks = crate::testing::ScenarioBuilder::new("ETH_USDT", 100.0)
+            .flat(5_000)
+            .crash(8.0, 1_000)
+            .flat(5_000)
+            .build();
+        let streams = [TradeStream::new(Symbol::new("ETH_USDT"), ticks)];
+        let settings = BacktestSettings { random_seed: Some(7), ..BacktestSettings::default() };
+        let layers = ConfigLayers::default();
+        let dir = std::env::temp_dir().join(format!("optimize_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let options = |tx| OptimizeOptions { cache: Some(dir.clone()), progress: Some(tx), checkpoint: None };
+
+        let narrow = [ParamRange::parse("hook_detect_depth=2,3").unwrap()];
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let first = optimize_with(StrategyKind::Hook, &layers, &narrow, &streams, &settings, Objective::Pnl, &options(tx)).unwrap();
+
+        let wide = [ParamRange::parse("hook_detect_depth=2,3,4").unwrap()];
+        let (tx, rx) = std::sync::mpsc::channel();
+        let second = optimize_with(StrategyKind::Hook, &layers, &wide, &streams, &settings, Objective::Pnl, &options(tx)).unwrap();
+        let reports: Vec<Progress> = rx.iter().collect();
+        assert_eq!(reports[0].combos_done, 2);
+        assert_eq!(reports[0].ticks_total, streams[0].trades.len() as u64);
+        assert_eq!(second.len(), 3);
+        for run in &first {
+            assert!(second.contains(run));
+        }
+
+        let uncached = optimize(StrategyKind::Hook, &layers, &wide, &streams, &settings, Objective::Pnl).unwrap();
+        assert_eq!(second, uncached);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,131 @@
+//! Разбивка результата бэктеста по режимам рынка
+//!
+//! Режим берётся из `risk::regime` по тем же тикам, что видел движок; сделка относится
+//! к режиму на момент входа. Так видно, что конфиг, например, зарабатывает только
+//! на волатильном рынке, а на спокойном медленно теряет.
+
+use super::market::TradeTick;
+use super::metrics::BacktestResult;
+use crate::risk::regime::{Regime, RegimeConfig, RegimeTimeline};
+
+/// Метрики сделок одного режима; `regime: None` — разогрев классификатора
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegimeStats {
+    pub regime: Option<Regime>,
+    pub trades: usize,
+    pub wins: usize,
+    pub pnl: f64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    /// Сколько времени рынок провёл в режиме, мс
+    pub duration_ms: i64,
+}
+
+impl RegimeStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.trades == 0 { 0.0 } else { self.wins as f64 / self.trades as f64 * 100.0 }
+    }
+
+    pub fn profit_factor(&self) -> f64 {
+        if self.gross_loss > 0.0 {
+            self.gross_profit / self.gross_loss
+        } else if self.gross_profit > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+
+    /// P&L на час рынка в этом режиме: сравнимо между режимами разной длины
+    pub fn pnl_per_hour(&self) -> f64 {
+        if self.duration_ms > 0 { self.pnl / (self.duration_ms as f64 / 3_600_000.0) } else { 0.0 }
+    }
+
+    fn merge(&mut self, other: &RegimeStats) {
+        self.trades += other.trades;
+        self.wins += other.wins;
+        self.pnl += other.pnl;
+        self.gross_profit += other.gross_profit;
+        self.gross_loss += other.gross_loss;
+        self.duration_ms += other.duration_ms;
+    }
+}
+
+/// Режимы тиков потока
+pub fn timeline(ticks: &[TradeTick], config: &RegimeConfig) -> RegimeTimeline {
+    RegimeTimeline::classify(ticks.iter().map(|t| (t.timestamp.timestamp_millis(), t.price)), config)
+}
+
+/// Метрики по режимам, упорядочены: разогрев, спокойный, волатильный, трендовый
+pub fn breakdown(result: &BacktestResult, timeline: &RegimeTimeline) -> Vec<RegimeStats> {
+    let mut stats: Vec<RegimeStats> = timeline
+        .durations()
+        .into_iter()
+        .map(|(regime, duration_ms)| RegimeStats { regime, duration_ms, ..RegimeStats::default() })
+        .collect();
+    for trade in &result.trades {
+        let regime = timeline.at(trade.entry_time.timestamp_millis());
+        let idx = match stats.iter().position(|s| s.regime == regime) {
+            Some(idx) => idx,
+            None => {
+                stats.push(RegimeStats { regime, ..RegimeStats::default() });
+                stats.len() - 1
+            }
+        };
+        let entry = &mut stats[idx];
+        entry.trades += 1;
+        entry.pnl += trade.pnl;
+        if trade.pnl > 0.0 {
+            entry.wins += 1;
+            entry.gross_profit += trade.pnl;
+        } else {
+            entry.gross_loss -= trade.pnl;
+        }
+    }
+    stats.sort_by_key(|s| s.regime);
+    stats
+}
+
+/// Сводит разбивки нескольких символов в одну
+pub fn merge(parts: &[Vec<RegimeStats>]) -> Vec<RegimeStats> {
+    let mut total: Vec<RegimeStats> = Vec::new();
+    for stats in parts.iter().flatten() {
+        match total.iter_mut().find(|s| s.regime == stats.regime) {
+            Some(existing) => existing.merge(stats),
+            None => total.push(stats.clone()),
+        }
+    }
+    total.sort_by_key(|s| s.regime);
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::metrics::BacktestMetrics;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_trades_split_by_regime_at_entry() {
+        let timeline = RegimeTimeline::new(
+            vec![(0, None), (1_000, Some(Regime::Quiet)), (5_000, Some(Regime::Volatile))],
+            9_000,
+        );
+        let mut metrics = BacktestMetrics::new();
+        for (ts, pnl) in [(500, 1.0), (2_000, -2.0), (3_000, 1.0), (6_000, 4.0), (8_000, -1.0)] {
+            metrics.record_trade("ETH_USDT".into(), 100.0, 100.0, 1.0, true, pnl, Utc.timestamp_millis_opt(ts).unwrap());
+        }
+        let stats = breakdown(&metrics.to_result(), &timeline);
+        assert_eq!(stats.iter().map(|s| s.regime).collect::<Vec<_>>(), [None, Some(Regime::Quiet), Some(Regime::Volatile)]);
+        let quiet = &stats[1];
+        assert_eq!((quiet.trades, quiet.wins, quiet.pnl, quiet.duration_ms), (2, 1, -1.0, 4_000));
+        assert_eq!(quiet.profit_factor(), 0.5);
+        let volatile = &stats[2];
+        assert_eq!((volatile.trades, volatile.pnl), (2, 3.0));
+        assert_eq!(volatile.pnl_per_hour(), 3.0 * 900.0);
+
+        let total = merge(&[stats.clone(), stats]);
+        assert_eq!(total.len(), 3);
+        assert_eq!((total[2].trades, total[2].duration_ms), (4, 8_000));
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::backtest::market::{TradeStream, TradeTick};
 use crate::backtest::bin_format::BinFileReader;
+use crate::base_classes::symbol::Symbol;
 use chrono::{DateTime, Utc};
 use std::path::Path;
 
@@ -64,9 +65,9 @@ impl ReplayEngine {
         
         // Определяем символ из первого трейда
         let symbol = if !filtered_trades.is_empty() && !filtered_trades[0].symbol.is_empty() {
-            filtered_trades[0].symbol.clone()
+            filtered_trades[0].symbol
         } else {
-            "UNKNOWN".to_string()
+            Symbol::new("UNKNOWN")
         };
         
         let stream = TradeStream::new(symbol, filtered_trades);
@@ -3,12 +3,21 @@
 #![cfg(feature = "gate_exec")]
 
 use crate::backtest::market::TradeTick;
+use crate::base_classes::liquidations::Liquidation;
+use crate::base_classes::types::Side;
+use crate::error::Error;
+use crate::execution::TimeInForce;
 use crate::strategy::moon_strategies::{
-    MShotStrategy, MShotConfig, MShotSignal,
-    MStrikeStrategy, MStrikeConfig, MStrikeSignal,
-    HookStrategy, HookConfig, HookSignal,
+    MShotStrategy, MShotConfig, MShotSignal, MShotState,
+    MStrikeStrategy, MStrikeConfig, MStrikeSignal, MStrikeState,
+    HookStrategy, HookConfig, HookSignal, HookState,
+    MShotView, MStrikeView, HookView, OrderIntent, EntryModelConfig,
     mshot::Deltas,
 };
+use crate::strategy::external_signal::ExternalSignalView;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// Трейт для унификации работы со стратегиями в бэктестере
 pub trait StrategyAdapter {
@@ -17,8 +26,141 @@ pub trait StrategyAdapter {
     fn reset(&mut self);
     /// Вызывается когда buy ордер исполнился
     fn on_buy_filled(&mut self, price: f64, size: f64) -> Option<StrategyAction>;
+    /// Вызывается когда sell ордер исполнился (позиция закрыта)
+    fn on_sell_filled(&mut self, price: f64, size: f64);
+    /// Вызывается когда buy не ушёл на биржу (вход забрала другая стратегия символа
+    /// или его отсеял ML-фильтр)
+    fn on_buy_declined(&mut self);
+    /// Заявка стратегии принята под `order_id` - этот id стратегия указывает в `CancelOrder`.
+    /// По умолчанию стратегии id не нужен
+    fn on_order_placed(&mut self, _side: Side, _order_id: u64) {}
+    /// Обновление стакана: bids по убыванию, asks по возрастанию цены
+    fn on_book(&mut self, bids: &[(f64, f64)], asks: &[(f64, f64)]);
     /// Вызывается когда нужно вычислить цену продажи
     fn calculate_sell_price(&self, buy_price: f64, current_price: f64) -> Option<f64>;
+    /// Снимок состояния стратегии для сохранения между рестартами
+    fn snapshot(&self) -> Result<StrategySnapshot>;
+    /// Восстановление состояния из снимка, сделанного той же стратегией
+    fn restore(&mut self, snapshot: &StrategySnapshot) -> Result<()>;
+    /// Read-model внутреннего состояния для дашборда и тестов
+    fn view(&self) -> StrategyView;
+    /// Ордера, которые стратегия держит на рынке
+    fn pending_orders(&self) -> Vec<OrderIntent>;
+    /// Исторический тик прогрева перед живым потоком: только накопить индикаторы,
+    /// не торговать. По умолчанию стратегии история не нужна.
+    fn warm_up(&mut self, _tick: &TradeTick, _deltas: &Deltas) {}
+    /// Ликвидация по символу из ленты биржи (forceOrder, public_liquidates).
+    /// По умолчанию стратегии лента не нужна.
+    fn on_liquidation(&mut self, _liquidation: &Liquidation) {}
+    /// Поток символа замолчал, пока остальные идут (`Some(%)` - на сколько расширить
+    /// стопы), или вернулся (`None`). По умолчанию стопы стратегии не расширяются.
+    fn on_stale(&mut self, _widen_stops_pct: Option<f64>) {}
+    /// Таймер рантайма: таймауты, которые должны сработать и без тиков символа
+    /// (`now` - время рантайма, а не биржи). По умолчанию стратегии таймер не нужен.
+    fn on_timer(&mut self, _now: DateTime<Utc>) -> StrategyAction {
+        StrategyAction::NoAction
+    }
+    /// Time-in-force заявок стратегии на этой стороне; по умолчанию GTC
+    fn time_in_force(&self, _side: Side) -> TimeInForce {
+        TimeInForce::Gtc
+    }
+    /// Что стратегия должна увидеть до первого входа; рантайм держит её входы, пока
+    /// требования не выполнены. По умолчанию стратегия готова с первого тика
+    fn warmup_requirements(&self) -> WarmupRequirements {
+        WarmupRequirements::default()
+    }
+    /// Посчитан ли индикатор из `WarmupRequirements::indicators`
+    fn indicator_ready(&self, _indicator: &str) -> bool {
+        true
+    }
+}
+
+/// Требования стратегии к истории символа (тики прогрева и живые)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WarmupRequirements {
+    pub min_ticks: u64,
+    /// От первого до последнего тика, мс
+    pub min_history_ms: u64,
+    /// Имена индикаторов, которые должны посчитаться (`StrategyAdapter::indicator_ready`)
+    pub indicators: Vec<&'static str>,
+}
+
+impl WarmupRequirements {
+    pub const DELTA_15M_MS: u64 = 15 * 60_000;
+    pub const DELTA_1H_MS: u64 = 3_600_000;
+    pub const DELTA_3H_MS: u64 = 3 * 3_600_000;
+
+    /// Самое длинное окно дельт символа, модификатор которого включён (коэффициент не 0).
+    /// Дельты маркета и BTC считаются по другим символам и сюда не входят
+    pub fn delta_history_ms(windows: &[(f64, u64)]) -> u64 {
+        windows.iter().filter(|(coefficient, _)| *coefficient != 0.0).map(|(_, ms)| *ms).max().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min_ticks == 0 && self.min_history_ms == 0 && self.indicators.is_empty()
+    }
+}
+
+/// Оборачивает адаптер ML-фильтром входов, если в конфиге стратегии задана модель.
+/// Модель задана, а сборка без фичи `onnx` - ошибка, а не молчаливый вход без фильтра.
+pub fn with_entry_model<A: StrategyAdapter + Send + 'static>(
+    adapter: A,
+    entry_model: Option<&EntryModelConfig>,
+) -> Result<Box<dyn StrategyAdapter + Send>> {
+    let Some(config) = entry_model else {
+        return Ok(Box::new(adapter));
+    };
+    #[cfg(feature = "onnx")]
+    {
+        let model = crate::backtest::features::EntryModel::load(config)?;
+        Ok(Box::new(crate::backtest::features::ModelFilteredAdapter::new(adapter, model)))
+    }
+    #[cfg(not(feature = "onnx"))]
+    bail!(
+        "{}: entry model {} configured, but the build has no `onnx` feature",
+        adapter.get_name(),
+        config.path.display()
+    )
+}
+
+/// Состояние конкретной стратегии (коридор, детект, ордера)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "strategy", content = "view")]
+pub enum StrategyView {
+    MShot(MShotView),
+    MStrike(MStrikeView),
+    Hook(HookView),
+    External(ExternalSignalView),
+}
+
+/// Сериализованное состояние стратегии (детекты, коридоры, повторные ордера)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategySnapshot {
+    pub strategy: String,
+    pub taken_at: DateTime<Utc>,
+    pub state: serde_json::Value,
+}
+
+impl StrategySnapshot {
+    pub fn new<T: Serialize>(strategy: &str, state: &T) -> Result<Self> {
+        Ok(Self {
+            strategy: strategy.to_string(),
+            taken_at: Utc::now(),
+            state: serde_json::to_value(state)?,
+        })
+    }
+    
+    /// Декодирует состояние, проверяя что снимок сделан той же стратегией
+    pub fn decode<T: DeserializeOwned>(&self, expected_strategy: &str) -> Result<T> {
+        if self.strategy != expected_strategy {
+            bail!(
+                "snapshot belongs to strategy '{}', cannot restore into '{}'",
+                self.strategy,
+                expected_strategy
+            );
+        }
+        Ok(serde_json::from_value(self.state.clone())?)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +171,8 @@ pub enum StrategyAction {
     ReplaceBuy { new_price: f64 },
     CancelOrder { order_id: u64 },
     DetectSignal { message: String },
+    /// Стратегия не смогла обработать событие; рантайм считает и логирует, ордеров нет
+    Error(Error),
 }
 
 /// Адаптер для MShot стратегии
@@ -80,9 +224,43 @@ impl StrategyAdapter for MShotAdapter {
         })
     }
     
+    fn on_sell_filled(&mut self, _price: f64, _size: f64) {
+        // MShot не отслеживает позицию после покупки
+    }
+    
+    fn on_buy_declined(&mut self) {
+        self.strategy.on_buy_declined();
+    }
+    
+    fn on_book(&mut self, _bids: &[(f64, f64)], _asks: &[(f64, f64)]) {
+        // MShot считает цены от бида/аска тика, стакан не нужен
+    }
+    
     fn calculate_sell_price(&self, buy_price: f64, current_price: f64) -> Option<f64> {
         Some(self.strategy.calculate_sell_price(buy_price, Some(current_price)))
     }
+    
+    fn snapshot(&self) -> Result<StrategySnapshot> {
+        StrategySnapshot::new(self.get_name(), &self.strategy.snapshot())
+    }
+    
+    fn restore(&mut self, snapshot: &StrategySnapshot) -> Result<()> {
+        let state: MShotState = snapshot.decode(self.get_name())?;
+        self.strategy.restore(state);
+        Ok(())
+    }
+    
+    fn view(&self) -> StrategyView {
+        StrategyView::MShot(self.strategy.view())
+    }
+    
+    fn warmup_requirements(&self) -> WarmupRequirements {
+        self.strategy.warmup_requirements()
+    }
+    
+    fn pending_orders(&self) -> Vec<OrderIntent> {
+        self.strategy.pending_orders()
+    }
 }
 
 /// Адаптер для MStrike стратегии
@@ -104,25 +282,34 @@ impl MStrikeAdapter {
     }
 }
 
-impl StrategyAdapter for MStrikeAdapter {
-    fn on_tick(&mut self, tick: &TradeTick, deltas: &Deltas) -> StrategyAction {
-        match self.strategy.on_tick(tick, deltas) {
-            MStrikeSignal::NoAction => StrategyAction::NoAction,
-            MStrikeSignal::DetectStrike { depth, volume, min_price } => {
-                StrategyAction::DetectSignal {
-                    message: format!("MStrike: depth={:.2}%, volume={:.2}, min={:.8}", depth, volume, min_price),
-                }
-            }
-            MStrikeSignal::PlaceBuy { price, size, reason: _ } => {
-                StrategyAction::PlaceBuy { price, size }
-            }
-            MStrikeSignal::PlaceSell { price, size } => {
-                StrategyAction::PlaceSell { price, size }
-            }
-            MStrikeSignal::CancelOrder { order_id } => {
-                StrategyAction::CancelOrder { order_id }
+fn mstrike_action(signal: MStrikeSignal) -> StrategyAction {
+    match signal {
+        MStrikeSignal::NoAction => StrategyAction::NoAction,
+        MStrikeSignal::DetectStrike { depth, volume, min_price } => {
+            StrategyAction::DetectSignal {
+                message: format!("MStrike: depth={:.2}%, volume={:.2}, min={:.8}", depth, volume, min_price),
             }
         }
+        MStrikeSignal::PlaceBuy { price, size, reason: _ } => {
+            StrategyAction::PlaceBuy { price, size }
+        }
+        MStrikeSignal::PlaceSell { price, size } => {
+            StrategyAction::PlaceSell { price, size }
+        }
+        MStrikeSignal::CancelOrder { order_id } => {
+            StrategyAction::CancelOrder { order_id }
+        }
+        MStrikeSignal::Error(err) => StrategyAction::Error(err),
+    }
+}
+
+impl StrategyAdapter for MStrikeAdapter {
+    fn on_tick(&mut self, tick: &TradeTick, deltas: &Deltas) -> StrategyAction {
+        mstrike_action(self.strategy.on_tick(tick, deltas))
+    }
+    
+    fn on_timer(&mut self, now: DateTime<Utc>) -> StrategyAction {
+        mstrike_action(self.strategy.on_timer(now))
     }
     
     fn get_name(&self) -> &str {
@@ -134,14 +321,68 @@ impl StrategyAdapter for MStrikeAdapter {
     }
     
     fn on_buy_filled(&mut self, price: f64, size: f64) -> Option<StrategyAction> {
-        self.strategy.on_buy_filled(price, size);
-        None // MStrike сам управляет sell через on_tick
+        // MStrike сам управляет sell через on_tick
+        self.strategy.on_buy_filled(price, size).err().map(|err| StrategyAction::Error(err.into()))
+    }
+    
+    fn on_sell_filled(&mut self, _price: f64, size: f64) {
+        if let Err(err) = self.strategy.on_sell_filled(size) {
+            log::error!("MStrike sell fill ignored: {}", err);
+        }
+    }
+    
+    fn on_buy_declined(&mut self) {
+        if let Err(err) = self.strategy.on_buy_declined() {
+            log::error!("MStrike buy decline ignored: {}", err);
+        }
+    }
+    
+    fn on_book(&mut self, _bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        self.strategy.on_book(asks);
+    }
+    
+    fn on_liquidation(&mut self, liquidation: &Liquidation) {
+        self.strategy.on_liquidation(liquidation);
+    }
+    
+    fn on_stale(&mut self, widen_stops_pct: Option<f64>) {
+        self.strategy.set_stop_widening(widen_stops_pct);
     }
     
     fn calculate_sell_price(&self, buy_price: f64, current_price: f64) -> Option<f64> {
         // MStrike вычисляет sell_price в manage_position
         None
     }
+    
+    fn snapshot(&self) -> Result<StrategySnapshot> {
+        StrategySnapshot::new(self.get_name(), &self.strategy.snapshot())
+    }
+    
+    fn restore(&mut self, snapshot: &StrategySnapshot) -> Result<()> {
+        let state: MStrikeState = snapshot.decode(self.get_name())?;
+        self.strategy.restore(state);
+        Ok(())
+    }
+    
+    fn view(&self) -> StrategyView {
+        StrategyView::MStrike(self.strategy.view())
+    }
+    
+    fn pending_orders(&self) -> Vec<OrderIntent> {
+        self.strategy.pending_orders()
+    }
+
+    fn time_in_force(&self, side: Side) -> TimeInForce {
+        self.strategy.time_in_force(side)
+    }
+    
+    fn warmup_requirements(&self) -> WarmupRequirements {
+        self.strategy.warmup_requirements()
+    }
+    
+    fn indicator_ready(&self, indicator: &str) -> bool {
+        self.strategy.indicator_ready(indicator)
+    }
 }
 
 /// Адаптер для Hook стратегии
@@ -163,28 +404,37 @@ impl HookAdapter {
     }
 }
 
-impl StrategyAdapter for HookAdapter {
-    fn on_tick(&mut self, tick: &TradeTick, deltas: &Deltas) -> StrategyAction {
-        match self.strategy.on_tick(tick, deltas) {
-            HookSignal::NoAction => StrategyAction::NoAction,
-            HookSignal::DetectHook { depth, min_price, max_price } => {
-                StrategyAction::DetectSignal {
-                    message: format!("Hook: depth={:.2}%, min={:.8}, max={:.8}", depth, min_price, max_price),
-                }
-            }
-            HookSignal::PlaceBuy { price, size, reason: _ } => {
-                StrategyAction::PlaceBuy { price, size }
-            }
-            HookSignal::ReplaceBuy { new_price } => {
-                StrategyAction::ReplaceBuy { new_price }
-            }
-            HookSignal::PlaceSell { price, size } => {
-                StrategyAction::PlaceSell { price, size }
-            }
-            HookSignal::CancelOrder { order_id } => {
-                StrategyAction::CancelOrder { order_id }
+fn hook_action(signal: HookSignal) -> StrategyAction {
+    match signal {
+        HookSignal::NoAction => StrategyAction::NoAction,
+        HookSignal::DetectHook { depth, min_price, max_price } => {
+            StrategyAction::DetectSignal {
+                message: format!("Hook: depth={:.2}%, min={:.8}, max={:.8}", depth, min_price, max_price),
             }
         }
+        HookSignal::PlaceBuy { price, size, reason: _ } => {
+            StrategyAction::PlaceBuy { price, size }
+        }
+        HookSignal::ReplaceBuy { new_price } => {
+            StrategyAction::ReplaceBuy { new_price }
+        }
+        HookSignal::PlaceSell { price, size } => {
+            StrategyAction::PlaceSell { price, size }
+        }
+        HookSignal::CancelOrder { order_id } => {
+            StrategyAction::CancelOrder { order_id }
+        }
+        HookSignal::Error(err) => StrategyAction::Error(err),
+    }
+}
+
+impl StrategyAdapter for HookAdapter {
+    fn on_tick(&mut self, tick: &TradeTick, deltas: &Deltas) -> StrategyAction {
+        hook_action(self.strategy.on_tick(tick, deltas))
+    }
+    
+    fn on_timer(&mut self, now: DateTime<Utc>) -> StrategyAction {
+        hook_action(self.strategy.on_timer(now))
     }
     
     fn get_name(&self) -> &str {
@@ -196,13 +446,142 @@ impl StrategyAdapter for HookAdapter {
     }
     
     fn on_buy_filled(&mut self, price: f64, size: f64) -> Option<StrategyAction> {
-        self.strategy.on_buy_filled(price, size);
-        None // Hook сам управляет sell через on_tick
+        // Hook сам управляет sell через on_tick
+        self.strategy.on_buy_filled(price, size).err().map(|err| StrategyAction::Error(err.into()))
+    }
+    
+    fn on_sell_filled(&mut self, _price: f64, size: f64) {
+        if let Err(err) = self.strategy.on_sell_filled(size) {
+            log::error!("Hook sell fill ignored: {}", err);
+        }
+    }
+    
+    fn on_buy_declined(&mut self) {
+        if let Err(err) = self.strategy.on_buy_declined() {
+            log::error!("Hook buy decline ignored: {}", err);
+        }
+    }
+    
+    fn on_order_placed(&mut self, side: Side, order_id: u64) {
+        if side == Side::Bid {
+            self.strategy.on_buy_placed(order_id);
+        }
+    }
+    
+    fn on_book(&mut self, _bids: &[(f64, f64)], _asks: &[(f64, f64)]) {
+        // Hook строит коридор по трейдам, стакан не нужен
+    }
+    
+    fn on_stale(&mut self, widen_stops_pct: Option<f64>) {
+        self.strategy.set_stop_widening(widen_stops_pct);
     }
     
     fn calculate_sell_price(&self, buy_price: f64, current_price: f64) -> Option<f64> {
         // Hook вычисляет sell_price в manage_position
         None
     }
+    
+    fn snapshot(&self) -> Result<StrategySnapshot> {
+        StrategySnapshot::new(self.get_name(), &self.strategy.snapshot())
+    }
+    
+    fn restore(&mut self, snapshot: &StrategySnapshot) -> Result<()> {
+        let state: HookState = snapshot.decode(self.get_name())?;
+        self.strategy.restore(state);
+        Ok(())
+    }
+    
+    fn view(&self) -> StrategyView {
+        StrategyView::Hook(self.strategy.view())
+    }
+    
+    fn warmup_requirements(&self) -> WarmupRequirements {
+        self.strategy.warmup_requirements()
+    }
+    
+    fn pending_orders(&self) -> Vec<OrderIntent> {
+        self.strategy.pending_orders()
+    }
+
+    fn time_in_force(&self, side: Side) -> TimeInForce {
+        self.strategy.time_in_force(side)
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::TradeSide;
+    use crate::base_classes::symbol::Symbol;
+
+    fn tick(price: f64, offset_ms: i64, base: DateTime<Utc>) -> TradeTick {
+        TradeTick {
+            timestamp: base + chrono::Duration::milliseconds(offset_ms),
+            symbol: Symbol::new("BTC_USDT"),
+            price,
+            volume: 1000.0,
+            side: TradeSide::Sell,
+            trade_id: offset_ms.to_string(),
+            best_bid: Some(price),
+            best_ask: Some(price),
+        }
+    }
+
+    #[test]
+    fn test_hook_snapshot_roundtrip() {
+        let mut adapter = HookAdapter::default();
+        let now = Utc::now();
+        let deltas = Deltas::default();
+        adapter.on_tick(&tick(100.0, 0, now), &deltas);
+        adapter.on_tick(&tick(90.0, 500, now), &deltas);
+
+        let snapshot = adapter.snapshot().unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored_snapshot: StrategySnapshot = serde_json::from_str(&json).unwrap();
+
+        let mut restored = HookAdapter::default();
+        restored.restore(&restored_snapshot).unwrap();
+        assert_eq!(restored.snapshot().unwrap().state, snapshot.state);
+    }
+
+    #[test]
+    fn test_hook_view_exposes_corridor_and_pending_buy() {
+        let mut adapter = HookAdapter::new(HookConfig { hook_roll_back_wait: 0, ..Default::default() });
+        let now = Utc::now();
+        let deltas = Deltas::default();
+        adapter.on_tick(&tick(100.0, 0, now), &deltas);
+        let action = adapter.on_tick(&tick(90.0, 500, now), &deltas);
+
+        let price = match action {
+            StrategyAction::PlaceBuy { price, .. } => price,
+            other => panic!("expected PlaceBuy, got {:?}", other),
+        };
+        let view = match adapter.view() {
+            StrategyView::Hook(view) => view,
+            other => panic!("expected Hook view, got {:?}", other),
+        };
+        assert!(view.detected);
+        assert!((view.detection_depth.unwrap() - 10.0).abs() < 1e-9);
+        let corridor = view.corridor.expect("corridor must be set after detection");
+        assert!(corridor.upper >= corridor.lower);
+        assert!(corridor.contains(price));
+        assert_eq!(adapter.pending_orders().len(), 1);
+        assert_eq!(adapter.pending_orders()[0].price, price);
+    }
+
+    #[test]
+    fn test_restore_rejects_foreign_snapshot() {
+        let snapshot = MStrikeAdapter::default().snapshot().unwrap();
+        let mut hook = HookAdapter::default();
+        assert!(hook.restore(&snapshot).is_err());
+    }
+
+    #[test]
+    fn test_entry_model_is_not_silently_dropped() {
+        let adapter = with_entry_model(HookAdapter::default(), None).unwrap();
+        assert_eq!(adapter.get_name(), "Hook");
+        // Без фичи onnx - ошибка сборки, с ней - ошибка загрузки несуществующей модели
+        let config = EntryModelConfig { path: "missing/hook.onnx".into(), threshold: 0.6 };
+        assert!(with_entry_model(HookAdapter::default(), Some(&config)).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Проверка входящих тиков до стратегий и калькулятора дельт
+//!
+//! Один битый тик портит детект надолго: нулевая цена попадает в минимум окна Hook на
+//! весь HookTimeFrame, NaN — в EMA бидов MStrike, а скачок цены в десятки процентов от
+//! ошибки биржи или склейки потоков выглядит как прострел. Сторож общий для бэктеста и
+//! рантайма: отбрасывает неположительные и NaN-цены, отрицательный объём, битые bid/ask
+//! и скачки больше `max_jump_pct` % от последнего принятого тика символа. Каждый отказ
+//! пишется в лог и считается по причине.
+//!
+//! Настоящий сдвиг уровня (листинг, гэп после остановки торгов) не должен навсегда
+//! отрезать символ: скачок принимается, если следующий тик подтверждает новый уровень.
+
+use super::market::TradeTick;
+use crate::base_classes::symbol::Symbol;
+use crate::strategy::moon_strategies::numeric::{valid_price, valid_volume};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TickGuardConfig {
+    /// Максимальный скачок цены от последнего принятого тика символа, % (0 = не проверять)
+    pub max_jump_pct: f64,
+}
+
+impl Default for TickGuardConfig {
+    fn default() -> Self {
+        Self { max_jump_pct: 50.0 }
+    }
+}
+
+impl TickGuardConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.max_jump_pct >= 0.0 && self.max_jump_pct.is_finite()) {
+            bail!("tick_guard.max_jump_pct {} must be non-negative", self.max_jump_pct);
+        }
+        Ok(())
+    }
+}
+
+/// Причина отказа в тике
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TickRejection {
+    /// Цена сделки NaN, бесконечность, ноль или отрицательная
+    BadPrice(f64),
+    /// Объём NaN или отрицательный
+    BadVolume(f64),
+    /// Записанный bid/ask есть, но непригоден
+    BadQuote { bid: Option<f64>, ask: Option<f64> },
+    /// Скачок от последней принятой цены символа
+    Jump { from: f64, to: f64, pct: f64 },
+}
+
+impl fmt::Display for TickRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TickRejection::BadPrice(price) => write!(f, "bad price {}", price),
+            TickRejection::BadVolume(volume) => write!(f, "bad volume {}", volume),
+            TickRejection::BadQuote { bid, ask } => write!(f, "bad quote bid {:?} ask {:?}", bid, ask),
+            TickRejection::Jump { from, to, pct } => write!(f, "price jump {} -> {} ({:+.2}%)", from, to, pct),
+        }
+    }
+}
+
+/// Счётчики сторожа
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TickGuardStats {
+    pub accepted: u64,
+    pub bad_price: u64,
+    pub bad_volume: u64,
+    pub bad_quote: u64,
+    pub jumps: u64,
+    /// Скачки, подтверждённые следующим тиком и принятые как новый уровень
+    pub level_shifts: u64,
+}
+
+impl TickGuardStats {
+    pub fn rejected(&self) -> u64 {
+        self.bad_price + self.bad_volume + self.bad_quote + self.jumps
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SymbolGuard {
+    last_price: Option<f64>,
+    /// Цена отвергнутого скачка: если следующий тик рядом с ней, уровень сдвинулся
+    pending_jump: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TickGuard {
+    config: TickGuardConfig,
+    symbols: HashMap<Symbol, SymbolGuard>,
+    stats: TickGuardStats,
+}
+
+impl TickGuard {
+    pub fn new(config: TickGuardConfig) -> Self {
+        assert!(
+            config.max_jump_pct >= 0.0 && config.max_jump_pct.is_finite(),
+            "tick guard max jump must be non-negative, got {}",
+            config.max_jump_pct
+        );
+        Self { config, symbols: HashMap::new(), stats: TickGuardStats::default() }
+    }
+
+    pub fn config(&self) -> TickGuardConfig {
+        self.config
+    }
+
+    pub fn stats(&self) -> TickGuardStats {
+        self.stats
+    }
+
+    /// Принять тик или вернуть причину отказа; отказ уже записан в лог
+    pub fn check(&mut self, tick: &TradeTick) -> Result<(), TickRejection> {
+        let verdict = self.verdict(tick);
+        match verdict {
+            Ok(()) => self.stats.accepted += 1,
+            Err(rejection) => {
+                match rejection {
+                    TickRejection::BadPrice(_) => self.stats.bad_price += 1,
+                    TickRejection::BadVolume(_) => self.stats.bad_volume += 1,
+                    TickRejection::BadQuote { .. } => self.stats.bad_quote += 1,
+                    TickRejection::Jump { .. } => self.stats.jumps += 1,
+                }
+                log::warn!("{} tick {} at {} rejected: {}", tick.symbol, tick.trade_id, tick.timestamp, rejection);
+            }
+        }
+        verdict
+    }
+
+    fn verdict(&mut self, tick: &TradeTick) -> Result<(), TickRejection> {
+        if !valid_price(tick.price) {
+            return Err(TickRejection::BadPrice(tick.price));
+        }
+        if !valid_volume(tick.volume) {
+            return Err(TickRejection::BadVolume(tick.volume));
+        }
+        if tick.best_bid.is_some_and(|bid| !valid_price(bid)) || tick.best_ask.is_some_and(|ask| !valid_price(ask)) {
+            return Err(TickRejection::BadQuote { bid: tick.best_bid, ask: tick.best_ask });
+        }
+
+        let max_jump_pct = self.config.max_jump_pct;
+        let entry = self.symbols.entry(tick.symbol).or_default();
+        if max_jump_pct > 0.0
+            && let Some(last) = entry.last_price
+        {
+            let pct = (tick.price - last) / last * 100.0;
+            if pct.abs() > max_jump_pct {
+                let confirmed = entry
+                    .pending_jump
+                    .is_some_and(|pending| ((tick.price - pending) / pending * 100.0).abs() <= max_jump_pct);
+                if !confirmed {
+                    entry.pending_jump = Some(tick.price);
+                    return Err(TickRejection::Jump { from: last, to: tick.price, pct });
+                }
+                self.stats.level_shifts += 1;
+                log::warn!("{}: price level shifted {} -> {} ({:+.2}%)", tick.symbol, last, tick.price, pct);
+            }
+        }
+        entry.last_price = Some(tick.price);
+        entry.pending_jump = None;
+        Ok(())
+    }
+}
+
+impl Default for TickGuard {
+    fn default() -> Self {
+        Self::new(TickGuardConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::TradeSide;
+    use chrono::{TimeZone, Utc};
+
+    fn tick(price: f64, volume: f64) -> TradeTick {
+        TradeTick {
+            timestamp: Utc.timestamp_millis_opt(1_700_000_000_000).unwrap(),
+            symbol: Symbol::new("ETH_USDT"),
+            price,
+            volume,
+            side: TradeSide::Sell,
+            trade_id: "1".into(),
+            best_bid: None,
+            best_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_rejects_garbage_and_confirms_level_shifts() {
+        let mut guard = TickGuard::new(TickGuardConfig { max_jump_pct: 20.0 });
+        assert_eq!(guard.check(&tick(100.0, 1.0)), Ok(()));
+        assert_eq!(guard.check(&tick(0.0, 1.0)), Err(TickRejection::BadPrice(0.0)));
+        assert!(matches!(guard.check(&tick(f64::NAN, 1.0)), Err(TickRejection::BadPrice(_))));
+        assert_eq!(guard.check(&tick(100.0, -1.0)), Err(TickRejection::BadVolume(-1.0)));
+        let quoted = TradeTick { best_bid: Some(0.0), ..tick(100.0, 1.0) };
+        assert!(matches!(guard.check(&quoted), Err(TickRejection::BadQuote { .. })));
+
+        // Одиночный выброс отвергается, цена возвращается - тик принят
+        assert!(matches!(guard.check(&tick(1_000.0, 1.0)), Err(TickRejection::Jump { .. })));
+        assert_eq!(guard.check(&tick(101.0, 1.0)), Ok(()));
+        // Новый уровень держится второй тик - принимаем
+        assert!(guard.check(&tick(40.0, 1.0)).is_err());
+        assert_eq!(guard.check(&tick(41.0, 1.0)), Ok(()));
+        assert_eq!(guard.check(&tick(40.5, 1.0)), Ok(()));
+
+        let stats = guard.stats();
+        assert_eq!((stats.accepted, stats.rejected(), stats.jumps, stats.level_shifts), (4, 6, 2, 1));
+        assert!(TickGuardConfig { max_jump_pct: -1.0 }.validate().is_err());
+    }
+}
@@ -26,7 +26,7 @@ use crate::exchanges::gate::{GateFrame, GateHandler, canonical_contract_symbol};
 use crate::exchanges::okx::{OkxFrame, OkxHandler};
 
 #[cfg(feature = "gate_exec")]
-use crate::execution::{GateWsConfig, GateWsGateway};
+use crate::execution::{GateCredentials, GateWsConfig, GateWsGateway};
 #[cfg(feature = "gate_exec")]
 use futures_util::future::pending;
 #[cfg(feature = "gate_exec")]
@@ -326,12 +326,12 @@ fn spawn_gate_user_trades_listener(
             };
 
             let cfg = GateWsConfig {
-                api_key,
-                api_secret,
+                credentials: GateCredentials::new(api_key, api_secret),
                 symbol: contract,
                 settle: Some(settle),
                 ws_url: None,
                 contract_size: Some(contract_size),
+                clock: None,
             };
 
             match rt.block_on(GateWsGateway::connect(cfg)) {
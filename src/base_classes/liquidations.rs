@@ -0,0 +1,151 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Which position the exchange closed by force
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LiquidationSide {
+    /// A long was liquidated: the forced order sells into the book
+    Long,
+    /// A short was liquidated: the forced order buys
+    Short,
+}
+
+/// One forced liquidation order from a public stream
+#[derive(Clone, Debug, PartialEq)]
+pub struct Liquidation {
+    pub symbol: String,
+    pub side: LiquidationSide,
+    pub price: f64,
+    /// Exchange units: base quantity on Binance, contracts on Gate
+    pub qty: f64,
+    pub ts_ms: u64,
+}
+
+impl Liquidation {
+    #[inline(always)]
+    pub fn notional(&self) -> f64 {
+        self.price * self.qty
+    }
+}
+
+/// Liquidation totals over a window, split by the liquidated side
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct LiquidationStats {
+    pub long_qty: f64,
+    pub long_notional: f64,
+    pub long_count: u32,
+    pub short_qty: f64,
+    pub short_notional: f64,
+    pub short_count: u32,
+}
+
+impl LiquidationStats {
+    fn add(&mut self, liquidation: &Liquidation) {
+        match liquidation.side {
+            LiquidationSide::Long => {
+                self.long_qty += liquidation.qty;
+                self.long_notional += liquidation.notional();
+                self.long_count += 1;
+            }
+            LiquidationSide::Short => {
+                self.short_qty += liquidation.qty;
+                self.short_notional += liquidation.notional();
+                self.short_count += 1;
+            }
+        }
+    }
+}
+
+/// Liquidations of one symbol over a sliding time window
+#[derive(Clone, Debug)]
+pub struct LiquidationWindow {
+    window_ms: u64,
+    events: VecDeque<Liquidation>,
+}
+
+impl LiquidationWindow {
+    pub fn new(window_ms: u64) -> Self {
+        Self { window_ms, events: VecDeque::new() }
+    }
+
+    /// Events arrive roughly in time order; the window is trimmed against the newest one
+    pub fn push(&mut self, liquidation: Liquidation) {
+        let newest = self.events.back().map_or(liquidation.ts_ms, |last| last.ts_ms.max(liquidation.ts_ms));
+        self.events.push_back(liquidation);
+        let cutoff = newest.saturating_sub(self.window_ms);
+        while self.events.front().is_some_and(|l| l.ts_ms < cutoff) {
+            self.events.pop_front();
+        }
+    }
+
+    /// Totals of events within `window_ms` before `now_ms`
+    pub fn stats(&self, now_ms: u64) -> LiquidationStats {
+        let cutoff = now_ms.saturating_sub(self.window_ms);
+        let mut stats = LiquidationStats::default();
+        for liquidation in self.events.iter().filter(|l| l.ts_ms >= cutoff && l.ts_ms <= now_ms) {
+            stats.add(liquidation);
+        }
+        stats
+    }
+
+    pub fn last(&self) -> Option<&Liquidation> {
+        self.events.back()
+    }
+}
+
+/// Per-symbol liquidation windows fed by the stream collectors
+#[derive(Debug)]
+pub struct LiquidationStore {
+    window_ms: u64,
+    entries: HashMap<String, LiquidationWindow>,
+}
+
+impl LiquidationStore {
+    pub fn new(window_ms: u64) -> Self {
+        Self { window_ms, entries: HashMap::new() }
+    }
+
+    pub fn push(&mut self, liquidation: Liquidation) {
+        let window_ms = self.window_ms;
+        self.entries
+            .entry(liquidation.symbol.clone())
+            .or_insert_with(|| LiquidationWindow::new(window_ms))
+            .push(liquidation);
+    }
+
+    pub fn stats(&self, symbol: &str, now_ms: u64) -> LiquidationStats {
+        self.entries.get(symbol).map(|w| w.stats(now_ms)).unwrap_or_default()
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&LiquidationWindow> {
+        self.entries.get(symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn liq(side: LiquidationSide, price: f64, qty: f64, ts_ms: u64) -> Liquidation {
+        Liquidation { symbol: "BTCUSDT".to_string(), side, price, qty, ts_ms }
+    }
+
+    #[test]
+    fn test_store_aggregates_by_side_within_window() {
+        let mut store = LiquidationStore::new(1_000);
+        store.push(liq(LiquidationSide::Long, 100.0, 2.0, 0));
+        store.push(liq(LiquidationSide::Long, 99.0, 1.0, 600));
+        store.push(liq(LiquidationSide::Short, 101.0, 3.0, 900));
+
+        let stats = store.stats("BTCUSDT", 900);
+        assert_eq!((stats.long_count, stats.long_qty, stats.long_notional), (2, 3.0, 299.0));
+        assert_eq!((stats.short_count, stats.short_notional), (1, 303.0));
+
+        // The first long left the window
+        let stats = store.stats("BTCUSDT", 1_500);
+        assert_eq!((stats.long_count, stats.short_count), (1, 1));
+        assert_eq!(store.stats("ETHUSDT", 1_500), LiquidationStats::default());
+
+        store.push(liq(LiquidationSide::Long, 98.0, 1.0, 1_700));
+        assert_eq!(store.get("BTCUSDT").unwrap().events.len(), 2);
+    }
+}
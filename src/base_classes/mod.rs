@@ -4,12 +4,15 @@ pub mod demean;
 pub mod engine;
 pub mod feed_config;
 pub mod feed_gate;
+pub mod liquidations;
 pub mod order_book;
 pub mod orderbook_trait;
 pub mod reference;
 pub mod reference_publisher;
 pub mod ring_buffer;
 pub mod state;
+pub mod subscriptions;
+pub mod symbol;
 pub mod tickers;
 pub mod trades;
 pub mod types;
@@ -0,0 +1,322 @@
+//! Spreads market-data subscriptions across several WebSocket connections.
+//!
+//! Venues cap how many streams one connection may carry, and how many connections one
+//! IP may hold open. [`ConnectionManager`] plans which connection carries which symbol
+//! so neither cap is hit:
+//!
+//! - all streams of a symbol share one connection, so a dropped connection silences
+//!   whole symbols (what `runtime::stale` expects) rather than half of each
+//! - a new symbol goes to the least loaded connection with room; a connection is
+//!   opened only when none has room
+//! - when a connection drops, its symbols move to live connections with spare room,
+//!   and the rest wait for the reconnect; `rebalance` evens out the load afterwards
+//! - [`ConnectionManager::symbol_capacity`] tells `UniverseScanner` how many symbols
+//!   still fit, so a scan never picks more symbols than the venue lets us stream
+//!
+//! The manager only plans: the caller turns each [`SubscriptionUpdate`] into the venue's
+//! subscribe/unsubscribe frames on the matching socket.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_classes::symbol::Symbol;
+
+/// Venue limits on streaming
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionLimits {
+    pub streams_per_connection: usize,
+    pub max_connections: usize,
+}
+
+impl Default for SubscriptionLimits {
+    fn default() -> Self {
+        // Binance USD-M futures: 200 streams per connection
+        Self { streams_per_connection: 200, max_connections: 5 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SubscriptionError {
+    #[error("{symbol}: {streams} streams do not fit one connection (max {max})")]
+    TooManyStreams { symbol: Symbol, streams: usize, max: usize },
+    #[error("{symbol}: no room for {streams} streams, all {connections} connections are full")]
+    BudgetExhausted { symbol: Symbol, streams: usize, connections: usize },
+}
+
+/// Streams to (un)subscribe on one connection
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubscriptionUpdate {
+    pub connection: usize,
+    pub subscribe: Vec<String>,
+    pub unsubscribe: Vec<String>,
+}
+
+/// Usage against the venue limits, for logs and the dashboard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SubscriptionBudget {
+    pub symbols: usize,
+    pub streams: usize,
+    pub capacity: usize,
+    pub connections_open: usize,
+    pub connections_up: usize,
+}
+
+fn update_for(updates: &mut BTreeMap<usize, SubscriptionUpdate>, connection: usize) -> &mut SubscriptionUpdate {
+    updates.entry(connection).or_insert_with(|| SubscriptionUpdate { connection, ..Default::default() })
+}
+
+#[derive(Debug, Clone, Default)]
+struct Connection {
+    symbols: BTreeMap<Symbol, Vec<String>>,
+    streams: usize,
+    up: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectionManager {
+    limits: SubscriptionLimits,
+    connections: Vec<Connection>,
+    /// Connection carrying each symbol
+    assigned: BTreeMap<Symbol, usize>,
+}
+
+impl ConnectionManager {
+    pub fn new(limits: SubscriptionLimits) -> Self {
+        assert!(limits.streams_per_connection > 0, "streams_per_connection must be positive");
+        assert!(limits.max_connections > 0, "max_connections must be positive");
+        Self { limits, connections: Vec::new(), assigned: BTreeMap::new() }
+    }
+
+    pub fn limits(&self) -> SubscriptionLimits {
+        self.limits
+    }
+
+    /// Connections planned so far; the caller opens a socket for each index
+    pub fn connections(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn connection_of(&self, symbol: Symbol) -> Option<usize> {
+        self.assigned.get(&symbol).copied()
+    }
+
+    /// Every stream a connection carries, e.g. for the subscribe frames after connect
+    pub fn streams(&self, connection: usize) -> Vec<String> {
+        self.connections[connection].symbols.values().flatten().cloned().collect()
+    }
+
+    /// How many more symbols costing `streams_per_symbol` streams fit, counting
+    /// connections not opened yet
+    pub fn symbol_capacity(&self, streams_per_symbol: usize) -> usize {
+        if streams_per_symbol == 0 {
+            return usize::MAX;
+        }
+        let per_connection = self.limits.streams_per_connection;
+        let unopened = self.limits.max_connections.saturating_sub(self.connections.len());
+        let open: usize = self
+            .connections
+            .iter()
+            .map(|conn| per_connection.saturating_sub(conn.streams) / streams_per_symbol)
+            .sum();
+        open + unopened * (per_connection / streams_per_symbol)
+    }
+
+    /// Symbols the venue lets us stream in total: those placed plus those that still fit
+    pub fn symbol_limit(&self, streams_per_symbol: usize) -> usize {
+        self.assigned.len().saturating_add(self.symbol_capacity(streams_per_symbol))
+    }
+
+    pub fn budget(&self) -> SubscriptionBudget {
+        SubscriptionBudget {
+            symbols: self.assigned.len(),
+            streams: self.connections.iter().map(|conn| conn.streams).sum(),
+            capacity: self.limits.streams_per_connection * self.limits.max_connections,
+            connections_open: self.connections.len(),
+            connections_up: self.connections.iter().filter(|conn| conn.up).count(),
+        }
+    }
+
+    /// Places a symbol's streams. A symbol already placed is moved as a whole if its
+    /// stream list changed
+    pub fn subscribe(&mut self, symbol: Symbol, streams: Vec<String>) -> Result<SubscriptionUpdate, SubscriptionError> {
+        if streams.len() > self.limits.streams_per_connection {
+            return Err(SubscriptionError::TooManyStreams {
+                symbol,
+                streams: streams.len(),
+                max: self.limits.streams_per_connection,
+            });
+        }
+        let mut update = SubscriptionUpdate::default();
+        let previous = self.assigned.get(&symbol).copied();
+        if let Some(connection) = previous {
+            if self.connections[connection].symbols[&symbol] == streams {
+                update.connection = connection;
+                return Ok(update);
+            }
+            update.unsubscribe = self.remove(symbol, connection);
+        }
+        let Some(connection) = self.pick(streams.len(), None) else {
+            // The old streams keep flowing where they were
+            if let Some(connection) = previous {
+                self.place(symbol, std::mem::take(&mut update.unsubscribe), connection);
+            }
+            return Err(SubscriptionError::BudgetExhausted {
+                symbol,
+                streams: streams.len(),
+                connections: self.connections.len(),
+            });
+        };
+        update.connection = connection;
+        update.subscribe = streams.clone();
+        self.place(symbol, streams, connection);
+        Ok(update)
+    }
+
+    pub fn unsubscribe(&mut self, symbol: Symbol) -> Option<SubscriptionUpdate> {
+        let connection = self.assigned.get(&symbol).copied()?;
+        let unsubscribe = self.remove(symbol, connection);
+        Some(SubscriptionUpdate { connection, subscribe: Vec::new(), unsubscribe })
+    }
+
+    /// The socket for `connection` is connected and has sent `streams(connection)`
+    pub fn on_connected(&mut self, connection: usize) {
+        self.connections[connection].up = true;
+    }
+
+    /// The socket dropped. Its symbols move to live connections with room, largest
+    /// first; the rest stay and are resubscribed by the reconnect
+    pub fn on_disconnect(&mut self, connection: usize) -> Vec<SubscriptionUpdate> {
+        self.connections[connection].up = false;
+        let mut moving: Vec<(Symbol, usize)> =
+            self.connections[connection].symbols.iter().map(|(symbol, streams)| (*symbol, streams.len())).collect();
+        moving.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let mut updates: BTreeMap<usize, SubscriptionUpdate> = BTreeMap::new();
+        for (symbol, cost) in moving {
+            let Some(target) = self.pick(cost, Some(connection)).filter(|&target| self.connections[target].up) else {
+                continue;
+            };
+            let streams = self.connections[connection].symbols[&symbol].clone();
+            // Nothing to unsubscribe on a dead socket
+            self.remove(symbol, connection);
+            update_for(&mut updates, target).subscribe.extend(streams.iter().cloned());
+            self.place(symbol, streams, target);
+        }
+        let left = self.connections[connection].symbols.len();
+        if left > 0 {
+            log::warn!("ws connection {} down: {} symbols wait for its reconnect", connection, left);
+        }
+        updates.into_values().collect()
+    }
+
+    /// Moves symbols from the most to the least loaded live connection until their
+    /// loads differ by no more than one symbol's streams
+    pub fn rebalance(&mut self) -> Vec<SubscriptionUpdate> {
+        let mut updates: BTreeMap<usize, SubscriptionUpdate> = BTreeMap::new();
+        loop {
+            let live: Vec<usize> = (0..self.connections.len()).filter(|&idx| self.connections[idx].up).collect();
+            let (Some(&from), Some(&to)) = (
+                live.iter().max_by_key(|&&idx| (self.connections[idx].streams, std::cmp::Reverse(idx))),
+                live.iter().min_by_key(|&&idx| (self.connections[idx].streams, idx)),
+            ) else {
+                break;
+            };
+            let gap = self.connections[from].streams - self.connections[to].streams;
+            // Smallest symbol whose move narrows the gap
+            let candidate = self.connections[from]
+                .symbols
+                .iter()
+                .map(|(symbol, streams)| (*symbol, streams.len()))
+                .filter(|&(_, cost)| cost > 0 && cost * 2 <= gap)
+                .min_by_key(|&(symbol, cost)| (cost, symbol));
+            let Some((symbol, _)) = candidate else {
+                break;
+            };
+            let streams = self.remove(symbol, from);
+            update_for(&mut updates, from).unsubscribe.extend(streams.iter().cloned());
+            update_for(&mut updates, to).subscribe.extend(streams.iter().cloned());
+            self.place(symbol, streams, to);
+        }
+        updates.into_values().collect()
+    }
+
+    /// Least loaded connection with room for `cost` streams, opening one if allowed
+    fn pick(&mut self, cost: usize, except: Option<usize>) -> Option<usize> {
+        let room = self.limits.streams_per_connection;
+        let best = (0..self.connections.len())
+            .filter(|&idx| Some(idx) != except && self.connections[idx].streams + cost <= room)
+            .min_by_key(|&idx| (self.connections[idx].streams, idx));
+        if best.is_some() || except.is_some() || self.connections.len() >= self.limits.max_connections {
+            return best;
+        }
+        self.connections.push(Connection::default());
+        Some(self.connections.len() - 1)
+    }
+
+    fn place(&mut self, symbol: Symbol, streams: Vec<String>, connection: usize) {
+        let conn = &mut self.connections[connection];
+        conn.streams += streams.len();
+        conn.symbols.insert(symbol, streams);
+        self.assigned.insert(symbol, connection);
+    }
+
+    fn remove(&mut self, symbol: Symbol, connection: usize) -> Vec<String> {
+        let conn = &mut self.connections[connection];
+        let streams = conn.symbols.remove(&symbol).unwrap_or_default();
+        conn.streams -= streams.len();
+        self.assigned.remove(&symbol);
+        streams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streams(symbol: &str) -> Vec<String> {
+        vec![format!("{symbol}@aggTrade"), format!("{symbol}@bookTicker")]
+    }
+
+    #[test]
+    fn test_spreads_symbols_and_moves_them_off_a_dropped_connection() {
+        let mut manager = ConnectionManager::new(SubscriptionLimits { streams_per_connection: 4, max_connections: 2 });
+        assert_eq!(manager.symbol_capacity(2), 4);
+        let names = ["a", "b", "c", "d"];
+        for name in names {
+            manager.subscribe(Symbol::new(name), streams(name)).unwrap();
+        }
+        // First connection fills up before the second is opened
+        assert_eq!(manager.connection_of(Symbol::new("b")), Some(0));
+        assert_eq!(manager.connection_of(Symbol::new("c")), Some(1));
+        assert_eq!(manager.symbol_capacity(2), 0);
+        assert_eq!(manager.symbol_limit(2), 4);
+        let err = manager.subscribe(Symbol::new("e"), streams("e")).unwrap_err();
+        assert_eq!(err.to_string(), "E: no room for 2 streams, all 2 connections are full");
+        let wide: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        assert!(matches!(manager.subscribe(Symbol::new("e"), wide), Err(SubscriptionError::TooManyStreams { .. })));
+
+        manager.on_connected(0);
+        manager.on_connected(1);
+        manager.unsubscribe(Symbol::new("d")).unwrap();
+        // Connection 1 has room for one symbol of the dropped connection 0
+        let updates = manager.on_disconnect(0);
+        assert_eq!(updates, vec![SubscriptionUpdate { connection: 1, subscribe: streams("a"), unsubscribe: Vec::new() }]);
+        assert_eq!(manager.streams(0), streams("b"));
+        assert_eq!(manager.budget().connections_up, 1);
+
+        // Back up with one symbol against two: nothing narrows the gap by moving
+        manager.on_connected(0);
+        assert!(manager.rebalance().is_empty());
+        manager.unsubscribe(Symbol::new("b"));
+        let updates = manager.rebalance();
+        assert_eq!(updates[0], SubscriptionUpdate { connection: 0, subscribe: streams("a"), unsubscribe: Vec::new() });
+        assert_eq!((updates[1].connection, &updates[1].unsubscribe), (1, &streams("a")));
+        assert_eq!(manager.budget(), SubscriptionBudget {
+            symbols: 2,
+            streams: 4,
+            capacity: 8,
+            connections_open: 2,
+            connections_up: 2,
+        });
+    }
+}
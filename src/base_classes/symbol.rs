@@ -0,0 +1,207 @@
+//! Interned trading symbols.
+//!
+//! A `Symbol` is a `Copy` handle to a canonical, process-wide interned string in
+//! `BASE_QUOTE` form. Venue spellings (`BTCUSDT`, `btc-usdt`, `BTC/USDT`) normalize to
+//! the same `Symbol`, so comparing two symbols never depends on where they came from,
+//! and cloning one on the tick path does not allocate.
+//!
+//! Resolving a spelling goes through a per-thread cache first, so parsers calling
+//! `Symbol::new` per message neither allocate nor take the global interner lock once a
+//! spelling has been seen on that thread.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(feature = "gate_exec")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Quote currencies recognised when splitting a symbol written without a separator.
+/// Longer suffixes come first so `FDUSD` is not read as `FD` + `USD`.
+const KNOWN_QUOTES: &[&str] = &["FDUSD", "USDT", "USDC", "BUSD", "USD", "EUR", "BTC", "ETH", "BNB"];
+
+fn interner() -> &'static Mutex<HashSet<&'static str>> {
+    static INTERNER: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    INTERNER.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+thread_local! {
+    /// Venue spelling -> symbol, filled from the interner on first sight per thread.
+    static RESOLVED: RefCell<HashMap<Box<str>, Symbol>> = RefCell::new(HashMap::new());
+}
+
+fn intern(canonical: Cow<'_, str>) -> &'static str {
+    let mut set = interner().lock().expect("symbol interner poisoned");
+    if let Some(existing) = set.get(canonical.as_ref()) {
+        return existing;
+    }
+    // Symbols are few and live for the whole process, so leaking is the interner.
+    let leaked: &'static str = Box::leak(canonical.into_owned().into_boxed_str());
+    set.insert(leaked);
+    leaked
+}
+
+/// Already in `BASE_QUOTE` form: uppercase ASCII letters and digits around a `_`.
+fn is_canonical(raw: &str) -> bool {
+    raw.contains('_') && raw.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// Canonical `BASE_QUOTE` spelling of a venue symbol; borrows input that already is one.
+pub fn normalize(raw: &str) -> Cow<'_, str> {
+    if is_canonical(raw) {
+        return Cow::Borrowed(raw);
+    }
+    let upper: String = raw
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '-' | '/' | ':' => '_',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect();
+    if upper.contains('_') {
+        return Cow::Owned(upper);
+    }
+    for quote in KNOWN_QUOTES {
+        if upper.len() > quote.len() && upper.ends_with(quote) {
+            let base = &upper[..upper.len() - quote.len()];
+            return Cow::Owned(format!("{}_{}", base, quote));
+        }
+    }
+    Cow::Owned(upper)
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(&'static str);
+
+impl Symbol {
+    /// Interns `raw` after normalizing it to `BASE_QUOTE`. A spelling this thread has
+    /// resolved before is a cache hit: no allocation, no interner lock.
+    pub fn new(raw: &str) -> Self {
+        RESOLVED.with(|resolved| {
+            if let Some(symbol) = resolved.borrow().get(raw) {
+                return *symbol;
+            }
+            let symbol = Self(intern(normalize(raw)));
+            resolved.borrow_mut().insert(raw.into(), symbol);
+            symbol
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+
+    pub fn base(&self) -> Option<&'static str> {
+        self.0.split_once('_').map(|(base, _)| base)
+    }
+
+    pub fn quote(&self) -> Option<&'static str> {
+        self.0.split_once('_').map(|(_, quote)| quote)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(raw: &str) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(raw: String) -> Self {
+        Self::new(&raw)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(feature = "gate_exec")]
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+#[cfg(feature = "gate_exec")]
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+        Ok(Self::new(&raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn venue_spellings_intern_to_one_symbol() {
+        let a = Symbol::new("BTC_USDT");
+        let b = Symbol::new("BTCUSDT");
+        let c = Symbol::new("btc-usdt");
+        let d = Symbol::new("BTC/USDT");
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        assert_eq!(a, d);
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert_eq!(a, "BTC_USDT");
+        assert_eq!(a.base(), Some("BTC"));
+        assert_eq!(a.quote(), Some("USDT"));
+    }
+
+    #[test]
+    fn canonical_spelling_is_borrowed() {
+        assert!(matches!(normalize("BTC_USDT"), Cow::Borrowed("BTC_USDT")));
+        assert!(matches!(normalize("btc_usdt"), Cow::Owned(_)));
+        assert!(matches!(normalize(" BTC_USDT"), Cow::Owned(_)));
+        // Cache hits on this thread resolve to the interned string
+        let first = Symbol::new("sol-usdt");
+        assert!(std::ptr::eq(Symbol::new("sol-usdt").as_str(), first.as_str()));
+        assert!(std::ptr::eq(Symbol::new("SOL_USDT").as_str(), first.as_str()));
+    }
+
+    #[test]
+    fn longest_quote_wins() {
+        assert_eq!(Symbol::new("ETHFDUSD").as_str(), "ETH_FDUSD");
+        assert_eq!(Symbol::new("ETHBTC").as_str(), "ETH_BTC");
+        assert_eq!(Symbol::new("UNKNOWN").as_str(), "UNKNOWN");
+    }
+}
@@ -77,6 +77,14 @@ pub trait ExchangeHandler: Send + Sync + 'static {
         None
     }
 
+    // Optional: handshake run before every (re)connect, for venues that hand out a
+    // short-lived token and socket endpoint over REST (e.g. KuCoin bullet). Runs on the
+    // WS thread; an Err is logged and retried with the reconnect backoff. `None` keeps
+    // `url()` and the handler's own heartbeat interval.
+    fn prepare_connection(&self) -> Result<Option<ConnectionInfo>, String> {
+        Ok(None)
+    }
+
     // Human-readable label for logging (e.g., "binance:BTCUSDT").
     fn label(&self) -> String {
         self.url().to_string()
@@ -128,6 +136,28 @@ impl<const M: usize> SequenceGate<M> {
     }
 }
 
+/// Per-session connection parameters returned by `ExchangeHandler::prepare_connection`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// Full socket URL including any token query
+    pub url: String,
+    /// Overrides `app_heartbeat_interval` for this session (server-dictated ping interval)
+    pub app_heartbeat_interval_secs: Option<u64>,
+}
+
+impl ConnectionInfo {
+    /// URL and heartbeat interval for a session, falling back to the handler's static ones.
+    fn resolve<E: ExchangeHandler>(handler: &E, session: Option<ConnectionInfo>) -> (String, Option<u64>) {
+        match session {
+            Some(info) => {
+                let interval = info.app_heartbeat_interval_secs.or_else(|| handler.app_heartbeat_interval());
+                (info.url, interval)
+            }
+            None => (handler.url().to_string(), handler.app_heartbeat_interval()),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum HeartbeatPayload {
     Text(String),
@@ -284,13 +314,25 @@ fn run_ws_tungstenite<E, const N: usize>(
 {
     use std::time::{Duration, Instant};
     use tungstenite::{Message, connect};
-    let url = handler.url().to_string();
     let label = handler.label();
     let initial_backoff = Duration::from_millis(250);
     let max_backoff = Duration::from_millis(3_000);
     let mut backoff = initial_backoff;
     loop {
-        // connect
+        // per-session handshake (token endpoints), then connect
+        let session = match handler.prepare_connection() {
+            Ok(session) => session,
+            Err(err) => {
+                eprintln!(
+                    "WS[{label}] handshake error: {err}; retrying in {}ms",
+                    backoff.as_millis()
+                );
+                std::thread::sleep(backoff);
+                backoff = std::cmp::min(backoff * 2, max_backoff);
+                continue;
+            }
+        };
+        let (url, dyn_app_hb_interval) = ConnectionInfo::resolve(&handler, session);
         let (mut socket, _response) = match connect(url::Url::parse(&url).unwrap()) {
             Ok(ok) => ok,
             Err(err) => {
@@ -323,7 +365,6 @@ fn run_ws_tungstenite<E, const N: usize>(
         last_hb = Instant::now();
 
         let static_app_hb = handler.app_heartbeat();
-        let dyn_app_hb_interval = handler.app_heartbeat_interval();
         let mut last_app_hb = Instant::now();
         if let Some(_) = dyn_app_hb_interval {
             if let Some(payload) = handler.build_app_heartbeat() {
@@ -462,8 +503,6 @@ where
     use tokio::runtime::Builder as RtBuilder;
     use tokio::time::{Duration, Instant, interval};
 
-    let url = handler.url().to_string();
-
     let rt = RtBuilder::new_multi_thread()
         .enable_all()
         .build()
@@ -476,6 +515,22 @@ where
             const SEQ_SLOTS: usize = 256;
             let mut seq_gate: SequenceGate<SEQ_SLOTS> = SequenceGate::new();
 
+            // per-session handshake (token endpoints); may block on REST
+            let session = match tokio::task::block_in_place(|| handler.prepare_connection()) {
+                Ok(session) => session,
+                Err(e) => {
+                    eprintln!(
+                        "ws[{label}] handshake: {e}; retrying in {}ms",
+                        backoff.as_millis()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, max_backoff);
+                    backoff += Duration::from_millis(25);
+                    continue;
+                }
+            };
+            let (url, dyn_app_hb_interval) = ConnectionInfo::resolve(&handler, session);
+
             let req: Request<()> = url.clone().into_client_request().unwrap();
             let uri = req.uri().clone();
             let host = uri.host().unwrap().to_string();
@@ -535,7 +590,6 @@ where
             // Heartbeat timers
             let mut hb = interval(Duration::from_secs(20));
             let static_app_hb = handler.app_heartbeat();
-            let dyn_app_hb_interval = handler.app_heartbeat_interval();
             let mut app_hb_timer = dyn_app_hb_interval.map(|secs| interval(Duration::from_secs(secs)))
                 .or_else(|| static_app_hb.as_ref().map(|hb| interval(Duration::from_secs(hb.interval_secs))));
 
@@ -0,0 +1,543 @@
+//! Единая точка входа: `bot run | backtest | optimize | validate | download-data | synth | report`
+
+#![cfg(feature = "gate_exec")]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command as Process;
+use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand};
+use rust_test::analytics::{LogAnalyzer, PerformanceMetrics};
+use rust_test::backtest::market::{TradeSide, TradeStream, TradeTick};
+use rust_test::backtest::{
+    BacktestResult, BacktestSettings, BinFileWriter, LimitFillModel, Objective, OptimizeOptions, ParamRange, Progress, ReplayEngine,
+    ReplaySettings, StrategyKind, TimeHeatmap, compare, load_fills_csv, optimize_with, run_backtest_with, simulated_fills,
+};
+use rust_test::backtest::regimes;
+use rust_test::base_classes::symbol::Symbol;
+use rust_test::exchanges::endpoints::{GateioGet, Network};
+use rust_test::exchanges::gate::rest;
+use rust_test::risk::RegimeConfig;
+use rust_test::strategy::moon_strategies::ConfigLayers;
+use rust_test::testing::{SyntheticConfig, SyntheticEvent, SyntheticGenerator};
+
+#[derive(Debug, Parser)]
+#[command(name = "bot", about = "Trading bot: live runner, backtests, optimization, data and reports")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Start the live runner (the `gate_runner` binary next to this one)
+    Run {
+        #[arg(long, default_value = "config/gate_mvp.yaml")]
+        config: PathBuf,
+        #[arg(long)]
+        testnet: bool,
+    },
+    /// Replay recorded trades through a strategy
+    Backtest {
+        #[command(flatten)]
+        setup: BacktestArgs,
+        /// Write per-symbol results as JSON
+        #[arg(long)]
+        json: Option<PathBuf>,
+        /// Write the hour-of-day x weekday P&L heatmap of all symbols (.json or .csv)
+        #[arg(long)]
+        heatmap: Option<PathBuf>,
+    },
+    /// Grid-search strategy parameters over recorded trades
+    Optimize {
+        #[command(flatten)]
+        setup: BacktestArgs,
+        /// `name=lo:hi:step` or `name=a,b,c`; repeat for more parameters
+        #[arg(long = "param", required = true)]
+        params: Vec<String>,
+        /// pnl, sharpe or profit-factor
+        #[arg(long, default_value = "pnl")]
+        objective: String,
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Save finished combinations here and resume from it after an interruption
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+        /// Per-combination result cache shared by all optimizations
+        #[arg(long, default_value = "data/backtest_cache")]
+        cache: PathBuf,
+        /// Recompute every combination and leave the cache untouched
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Replay a live session's ticks and diff simulated fills against the real ones
+    Validate {
+        #[command(flatten)]
+        setup: BacktestArgs,
+        /// Real fills of the session: ts_ms,symbol,strategy,side,price,size
+        #[arg(long)]
+        fills: PathBuf,
+        /// A simulated fill pairs with a real one at most this far apart
+        #[arg(long, default_value_t = 2_000)]
+        window_ms: i64,
+        /// Write the per-symbol reports as JSON
+        #[arg(long)]
+        json: Option<PathBuf>,
+    },
+    /// Download public futures trades from Gate into a .bin file
+    DownloadData {
+        /// Gate contract, e.g. BTC_USDT
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value_t = 1)]
+        days: u32,
+        /// Defaults to data/<symbol>_trades.bin
+        #[arg(long)]
+        out: Option<PathBuf>,
+        #[arg(long)]
+        testnet: bool,
+    },
+    /// Generate a synthetic stress-test tick stream into a .bin file
+    Synth {
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value_t = 100.0)]
+        price: f64,
+        #[arg(long, default_value_t = 24)]
+        hours: u32,
+        /// calm, clustered, crashes, spoofing or stress
+        #[arg(long, default_value = "stress")]
+        preset: String,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        /// Defaults to data/<symbol>_synth_<preset>.bin
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Summarize closed trades of one UTC day
+    Report {
+        /// YYYY-MM-DD
+        #[arg(long)]
+        day: NaiveDate,
+        /// Trade log CSV: entry_time,entry_price,exit_time,exit_price,side,size[,pnl]
+        #[arg(long, default_value = "data/trades.csv")]
+        trades: PathBuf,
+    },
+}
+
+#[derive(Debug, Args)]
+struct BacktestArgs {
+    /// Recorded trades (.bin); one symbol per file
+    #[arg(long, required = true, num_args = 1..)]
+    data: Vec<PathBuf>,
+    /// hook, mshot or mstrike
+    #[arg(long)]
+    strategy: String,
+    /// Layered strategy config (YAML: base, groups, symbols)
+    #[arg(long)]
+    layers: Option<PathBuf>,
+    /// Seed for the emulator's randomness; random when omitted (optimize defaults to 42)
+    #[arg(long)]
+    seed: Option<u64>,
+    /// When a tape trade fills a resting limit order: conservative (trade through the price),
+    /// optimistic (touch) or probabilistic (share of the touching trade's volume)
+    #[arg(long, default_value = "probabilistic")]
+    fill_model: String,
+    /// Fill at trade prices even when ticks carry best bid/ask
+    #[arg(long)]
+    ignore_quotes: bool,
+}
+
+impl BacktestArgs {
+    fn settings(&self, seed: Option<u64>) -> Result<BacktestSettings> {
+        let fill_model = LimitFillModel::parse(&self.fill_model)?;
+        Ok(BacktestSettings {
+            random_seed: seed,
+            fill_model,
+            spread_aware: !self.ignore_quotes,
+            ..BacktestSettings::default()
+        })
+    }
+
+    fn load(&self) -> Result<(StrategyKind, ConfigLayers, Vec<TradeStream>)> {
+        let kind = StrategyKind::parse(&self.strategy)?;
+        let layers = match &self.layers {
+            Some(path) => {
+                let text = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+                serde_yaml::from_str(&text).with_context(|| format!("parse {}", path.display()))?
+            }
+            None => ConfigLayers::default(),
+        };
+        kind.validate(&layers)?;
+        let mut replay = ReplayEngine::new(ReplaySettings::default());
+        for path in &self.data {
+            replay.load_bin_file(path).with_context(|| format!("load {}", path.display()))?;
+        }
+        let streams = replay.take_streams();
+        for stream in &streams {
+            println!(
+                "{}: {} trades, layers {}",
+                stream.symbol,
+                stream.trades.len(),
+                layers.layers_for(stream.symbol).join(" < ")
+            );
+        }
+        Ok((kind, layers, streams))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    env_logger::init();
+    match Cli::parse().command {
+        Command::Run { config, testnet } => run(&config, testnet),
+        Command::Backtest { setup, json, heatmap } => backtest(&setup, json.as_deref(), heatmap.as_deref()),
+        Command::Optimize { setup, params, objective, top, checkpoint, cache, no_cache } => {
+            let options = OptimizeOptions { checkpoint, cache: (!no_cache).then_some(cache), progress: None };
+            optimize_cmd(&setup, &params, &objective, top, options)
+        }
+        Command::Validate { setup, fills, window_ms, json } => validate(&setup, &fills, window_ms, json.as_deref()),
+        Command::DownloadData { symbol, days, out, testnet } => download(&symbol, days, out, testnet).await,
+        Command::Synth { symbol, price, hours, preset, seed, out } => synth(&symbol, price, hours, &preset, seed, out),
+        Command::Report { day, trades } => report(day, &trades),
+    }
+}
+
+fn run(config: &Path, testnet: bool) -> Result<()> {
+    let runner = std::env::current_exe()?.with_file_name(format!("gate_runner{}", std::env::consts::EXE_SUFFIX));
+    if !runner.exists() {
+        bail!(
+            "{} not found; build it with `cargo build --release --features gate_exec --bin gate_runner`",
+            runner.display()
+        );
+    }
+    let mut process = Process::new(&runner);
+    process.arg("--config").arg(config);
+    if testnet {
+        process.arg("--testnet");
+    }
+    let status = process.status().with_context(|| format!("start {}", runner.display()))?;
+    if !status.success() {
+        bail!("gate_runner exited with {}", status);
+    }
+    Ok(())
+}
+
+fn backtest(args: &BacktestArgs, json: Option<&Path>, heatmap: Option<&Path>) -> Result<()> {
+    // Reject a bad path before the replay, not after it
+    let heatmap = heatmap.map(|path| heatmap_is_csv(path).map(|csv| (path, csv))).transpose()?;
+    let (kind, layers, streams) = args.load()?;
+    let settings = args.settings(args.seed)?;
+    let (tx, bar) = progress_bar();
+    let results = run_backtest_with(kind, &layers, &streams, &settings, Some(tx));
+    bar.join().ok();
+    let results = results?;
+    println!(
+        "{:<14} {:>7} {:>12} {:>8} {:>8} {:>8} {:>10}",
+        "symbol", "trades", "pnl", "win %", "pf", "max dd", "spread"
+    );
+    for (symbol, result) in &results {
+        println!(
+            "{:<14} {:>7} {:>12.4} {:>8.1} {:>8.2} {:>8.2} {:>10.4}",
+            symbol.to_string(),
+            result.total_trades,
+            result.total_pnl,
+            result.win_rate,
+            result.profit_factor,
+            result.max_drawdown,
+            result.spread_cost
+        );
+    }
+    print_regimes(&streams, &results);
+    if let Some(path) = json {
+        let named: Vec<_> = results.iter().map(|(symbol, result)| (symbol.to_string(), result)).collect();
+        std::fs::write(path, serde_json::to_vec_pretty(&named)?).with_context(|| format!("write {}", path.display()))?;
+        println!("results written to {}", path.display());
+    }
+    if let Some((path, csv)) = heatmap {
+        write_heatmap(path, csv, &results)?;
+    }
+    Ok(())
+}
+
+fn heatmap_is_csv(path: &Path) -> Result<bool> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => Ok(true),
+        Some("json") => Ok(false),
+        _ => bail!("heatmap path {} must end in .json or .csv", path.display()),
+    }
+}
+
+/// Hour x weekday heatmap of all symbols plus the hours that paid off, as schedule windows
+fn write_heatmap(path: &Path, csv: bool, results: &[(Symbol, BacktestResult)]) -> Result<()> {
+    let mut heatmap = TimeHeatmap::default();
+    for (_, result) in results {
+        heatmap.merge(&result.heatmap());
+    }
+    let bytes = if csv { heatmap.to_csv().into_bytes() } else { serde_json::to_vec_pretty(&heatmap)? };
+    std::fs::write(path, bytes).with_context(|| format!("write {}", path.display()))?;
+    println!("heatmap written to {}", path.display());
+    let windows: Vec<String> = heatmap
+        .profitable_windows("default", 1)
+        .iter()
+        .map(|w| format!("{:02}-{:02}", w.from_hour, w.to_hour))
+        .collect();
+    println!("profitable UTC hours: {}", if windows.is_empty() { "none".to_string() } else { windows.join(", ") });
+    Ok(())
+}
+
+/// Trades of all symbols split by the market regime at entry
+fn print_regimes(streams: &[TradeStream], results: &[(Symbol, BacktestResult)]) {
+    let config = RegimeConfig::default();
+    let parts: Vec<_> = streams
+        .iter()
+        .zip(results)
+        .map(|(stream, (_, result))| regimes::breakdown(result, &regimes::timeline(&stream.trades, &config)))
+        .collect();
+    println!();
+    println!("{:<10} {:>8} {:>7} {:>12} {:>8} {:>8} {:>10}", "regime", "hours", "trades", "pnl", "win %", "pf", "pnl/hour");
+    for stats in regimes::merge(&parts) {
+        println!(
+            "{:<10} {:>8.1} {:>7} {:>12.4} {:>8.1} {:>8.2} {:>10.4}",
+            stats.regime.map_or_else(|| "warmup".to_string(), |r| r.to_string()),
+            stats.duration_ms as f64 / 3_600_000.0,
+            stats.trades,
+            stats.pnl,
+            stats.win_rate(),
+            stats.profit_factor(),
+            stats.pnl_per_hour()
+        );
+    }
+}
+
+fn optimize_cmd(
+    args: &BacktestArgs,
+    params: &[String],
+    objective: &str,
+    top: usize,
+    options: OptimizeOptions,
+) -> Result<()> {
+    let (kind, layers, streams) = args.load()?;
+    let objective = Objective::parse(objective)?;
+    let params = params.iter().map(|p| ParamRange::parse(p)).collect::<Result<Vec<_>>>()?;
+    let settings = args.settings(Some(args.seed.unwrap_or(42)))?;
+    let (tx, bar) = progress_bar();
+    let options = OptimizeOptions { progress: Some(tx), ..options };
+    let runs = optimize_with(kind, &layers, &params, &streams, &settings, objective, &options);
+    drop(options);
+    bar.join().ok();
+    let runs = runs?;
+    println!("{:>4} {:>7} {:>12} {:>8} {:>8} {:>8}  params", "rank", "trades", "pnl", "sharpe", "pf", "max dd");
+    for (rank, run) in runs.iter().take(top).enumerate() {
+        println!(
+            "{:>4} {:>7} {:>12.4} {:>8.2} {:>8.2} {:>8.2}  {}",
+            rank + 1,
+            run.trades,
+            run.pnl,
+            run.sharpe,
+            run.profit_factor(),
+            run.max_drawdown,
+            serde_json::Value::Object(run.params.clone())
+        );
+    }
+    Ok(())
+}
+
+fn validate(args: &BacktestArgs, fills: &Path, window_ms: i64, json: Option<&Path>) -> Result<()> {
+    let (kind, layers, streams) = args.load()?;
+    let strategy = args.strategy.to_ascii_lowercase();
+    let symbols: Vec<Symbol> = streams.iter().map(|s| s.symbol).collect();
+    let real: Vec<_> = load_fills_csv(fills)?
+        .into_iter()
+        .filter(|f| f.strategy == strategy && symbols.contains(&f.symbol))
+        .collect();
+    if real.is_empty() {
+        bail!("{} has no {} fills for the replayed symbols", fills.display(), strategy);
+    }
+    let settings = args.settings(args.seed)?;
+    let (tx, bar) = progress_bar();
+    let results = run_backtest_with(kind, &layers, &streams, &settings, Some(tx));
+    bar.join().ok();
+    let reports = compare(&real, &simulated_fills(&strategy, &results?), window_ms);
+
+    println!(
+        "{:<14} {:>6} {:>6} {:>7} {:>6} {:>7} {:>7} {:>9} {:>9} {:>8}",
+        "symbol", "real", "sim", "matched", "missed", "phantom", "recall", "skew ms", "max skew", "edge bp"
+    );
+    for r in &reports {
+        println!(
+            "{:<14} {:>6} {:>6} {:>7} {:>6} {:>7} {:>6.1}% {:>9.0} {:>9} {:>8.2}",
+            r.symbol,
+            r.real,
+            r.simulated,
+            r.matched,
+            r.missed,
+            r.phantom,
+            r.recall() * 100.0,
+            r.mean_skew_ms,
+            r.max_abs_skew_ms,
+            r.sim_edge_bps
+        );
+    }
+    if let Some(path) = json {
+        std::fs::write(path, serde_json::to_vec_pretty(&reports)?).with_context(|| format!("write {}", path.display()))?;
+        println!("reports written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Draws progress reports on stderr until every sender is dropped
+fn progress_bar() -> (Sender<Progress>, JoinHandle<()>) {
+    let (tx, rx) = mpsc::channel::<Progress>();
+    let handle = std::thread::spawn(move || {
+        let mut drawn: Option<(Instant, usize)> = None;
+        let mut last = None;
+        for progress in rx {
+            // Redraw at most a few times a second, but always when a combination finishes
+            let due = drawn.is_none_or(|(at, combos)| at.elapsed() >= Duration::from_millis(250) || combos != progress.combos_done);
+            if due {
+                eprint!("\r{}", render(&progress));
+                std::io::stderr().flush().ok();
+                drawn = Some((Instant::now(), progress.combos_done));
+            }
+            last = Some(progress);
+        }
+        if let Some(progress) = last {
+            eprintln!("\r{}", render(&progress));
+        }
+    });
+    (tx, handle)
+}
+
+fn render(p: &Progress) -> String {
+    const WIDTH: usize = 30;
+    let share = if p.ticks_total == 0 { 1.0 } else { p.ticks_done as f64 / p.ticks_total as f64 };
+    let filled = ((share * WIDTH as f64) as usize).min(WIDTH);
+    let mut line = format!(
+        "[{}{}] {:>5.1}%  {}/{} ticks",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        share * 100.0,
+        p.ticks_done,
+        p.ticks_total
+    );
+    if p.combos_total > 1 {
+        line.push_str(&format!("  combos {}/{}", p.combos_done, p.combos_total));
+    }
+    line.push_str(&format!("  elapsed {}", hms(p.elapsed)));
+    if let Some(eta) = p.eta {
+        line.push_str(&format!("  eta {}", hms(eta)));
+    }
+    if let Some((score, params)) = &p.best {
+        line.push_str(&format!("  best {:.4} {}", score, serde_json::Value::Object(params.clone())));
+    }
+    line
+}
+
+fn hms(d: Duration) -> String {
+    let s = d.as_secs();
+    format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+}
+
+async fn download(symbol: &str, days: u32, out: Option<PathBuf>, testnet: bool) -> Result<()> {
+    if days == 0 {
+        bail!("--days must be at least 1");
+    }
+    let contract = symbol.to_ascii_uppercase();
+    let meta = rest::fetch_contract_meta_async(&contract)
+        .await
+        .ok_or_else(|| anyhow!("failed to fetch Gate contract metadata for {}", contract))?;
+    let multiplier = meta
+        .quanto_multiplier
+        .filter(|m| m.is_finite() && *m > 0.0)
+        .ok_or_else(|| anyhow!("{} has no valid quanto_multiplier to convert contracts to base size", contract))?;
+
+    let to = Utc::now().timestamp();
+    let from = to - i64::from(days) * 86_400;
+    let base = GateioGet::base(if testnet { Network::Testnet } else { Network::Mainnet });
+    let client = reqwest::Client::builder().timeout(std::time::Duration::from_secs(15)).build()?;
+    let trades = rest::fetch_trades(&client, base, &contract, from, to).await?;
+    if trades.is_empty() {
+        bail!("no {} trades in the last {} days", contract, days);
+    }
+
+    let symbol = Symbol::new(&contract);
+    let ticks: Vec<TradeTick> = trades
+        .iter()
+        .map(|t| {
+            let timestamp = DateTime::from_timestamp_millis(t.time_ms)
+                .ok_or_else(|| anyhow!("trade {} has an invalid time {}", t.id, t.time_ms))?;
+            Ok(TradeTick {
+                timestamp,
+                symbol,
+                price: t.price,
+                volume: t.size.abs() * multiplier,
+                side: if t.size > 0.0 { TradeSide::Buy } else { TradeSide::Sell },
+                trade_id: t.id.to_string(),
+                best_bid: None,
+                best_ask: None,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    let path = out.unwrap_or_else(|| PathBuf::from(format!("data/{}_trades.bin", contract.replace('_', "").to_lowercase())));
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
+    BinFileWriter::new(&path)?.write_all(&ticks)?;
+    println!("{} trades of {} written to {}", ticks.len(), contract, path.display());
+    Ok(())
+}
+
+fn synth(symbol: &str, price: f64, hours: u32, preset: &str, seed: u64, out: Option<PathBuf>) -> Result<()> {
+    let config = SyntheticConfig { seed, ..SyntheticConfig::preset(preset)? };
+    let contract = symbol.to_ascii_uppercase();
+    let (ticks, events) = SyntheticGenerator::new(&contract, price, config)?.run(i64::from(hours) * 3_600_000).build();
+    let path = out.unwrap_or_else(|| {
+        PathBuf::from(format!("data/{}_synth_{}.bin", contract.replace('_', "").to_lowercase(), preset.to_lowercase()))
+    });
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
+    BinFileWriter::new(&path)?.write_all(&ticks)?;
+    let jumps = events.iter().filter(|e| matches!(e, SyntheticEvent::Jump { .. })).count();
+    println!(
+        "{} synthetic ticks of {} ({} jumps, {} wicks) written to {}",
+        ticks.len(),
+        contract,
+        jumps,
+        events.len() - jumps,
+        path.display()
+    );
+    Ok(())
+}
+
+fn report(day: NaiveDate, path: &Path) -> Result<()> {
+    let trades = LogAnalyzer::load_from_csv(&path.to_string_lossy())
+        .map_err(anyhow::Error::msg)
+        .with_context(|| format!("read trade log {}", path.display()))?;
+    let start = day.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc().timestamp() as u64;
+    let closed: Vec<_> = trades.into_iter().filter(|t| (start..start + 86_400).contains(&t.exit_time)).collect();
+    if closed.is_empty() {
+        println!("{}: no trades closed", day);
+        return Ok(());
+    }
+    let metrics = PerformanceMetrics::calculate(&closed);
+    let pnls = closed.iter().map(|t| t.pnl.unwrap_or(0.0));
+    let best = pnls.clone().fold(f64::NEG_INFINITY, f64::max);
+    let worst = pnls.fold(f64::INFINITY, f64::min);
+    println!("report for {} (UTC) from {}", day, path.display());
+    println!("  trades:         {} ({} won, {} lost)", metrics.total_trades, metrics.winning_trades, metrics.losing_trades);
+    println!("  pnl:            {:.4}", metrics.total_pnl);
+    println!("  profit factor:  {:.2}", metrics.profit_factor);
+    println!("  max drawdown:   {:.2}%", metrics.max_drawdown);
+    println!("  best / worst:   {:.4} / {:.4}", best, worst);
+    println!("  avg win / loss: {:.4} / {:.4}", metrics.avg_win, metrics.avg_loss);
+    Ok(())
+}
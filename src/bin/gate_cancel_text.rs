@@ -7,7 +7,7 @@ use clap::Parser;
 use futures_util::{SinkExt, StreamExt};
 use rust_test::exchanges::endpoints::GateioWs;
 use rust_test::exchanges::gate::rest;
-use rust_test::execution::GateWsConfig;
+use rust_test::execution::{GateCredentials, GateWsConfig};
 use serde::Deserialize;
 use serde_json::{Value, json};
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
@@ -66,12 +66,12 @@ async fn main() -> Result<()> {
     let settle = cli.settle.to_ascii_lowercase();
 
     let ws_config = GateWsConfig {
-        api_key,
-        api_secret,
+        credentials: GateCredentials::new(api_key, api_secret),
         symbol: cli.symbol.clone(),
         settle: Some(settle.clone()),
         ws_url: Some(cli.ws_url.clone()),
         contract_size: Some(contract_size),
+        clock: None,
     };
 
     let ws_endpoint = format!(
@@ -243,11 +243,12 @@ fn build_api_request(
         .transpose()?
         .unwrap_or_else(String::new);
 
-    let signature = sign_api(&cfg.api_secret, channel, &payload_str, ts);
+    let creds = cfg.credentials.current()?;
+    let signature = sign_api(creds.api_secret.expose(), channel, &payload_str, ts);
     let mut payload = json!({
         "req_id": req_id,
         "timestamp": ts.to_string(),
-        "api_key": cfg.api_key,
+        "api_key": creds.api_key,
         "signature": signature,
     });
 
@@ -11,15 +11,19 @@ use rust_test::base_classes::types::Side;
 use rust_test::config::runner::{
     RiskConfig, RunnerConfig, load_gate_credentials, load_runner_config,
 };
+use rust_test::exchanges::endpoints::{GateioGet, GateioWs, Network};
 use rust_test::exchanges::gate::rest;
 use rust_test::execution::{
-    ClientOrderId, DryRunGateway, ExecutionGateway, ExecutionReport, GateClient, GateCredentials,
-    GateWsConfig, GateWsGateway, InventoryReportOutcome, InventoryTracker, OrderAck, OrderManager,
-    OrderStatus, QuoteIntent,
+    ClientOrderId, ClockSync, DryRunGateway, ExecutionGateway, ExecutionReport, GateClient,
+    GateCredentials, GateWsConfig, GateWsGateway, InventoryReportOutcome, InventoryTracker,
+    OrderAck, OrderManager, OrderStatus, Price, QuoteIntent, ServerClock, SimulatedExchange,
+    SkewSample, TimeEndpoint, SUBMIT_LOOKUPS, SUBMIT_TIMEOUT,
 };
 use rust_test::logging::quote::{DebugLogger, QuoteLogHandle, format_f64};
+use rust_test::runtime::{EntryGate, ShutdownCoordinator, ShutdownPhase};
 use rust_test::strategy::{ReferenceMeta, SimpleQuoteStrategy};
-use tokio::sync::{Mutex, Semaphore, mpsc};
+use rust_test::risk::AutoStopManager;
+use tokio::sync::{Mutex, Semaphore, mpsc, watch};
 use tokio::time::{self, MissedTickBehavior, interval};
 
 #[derive(Debug, Parser)]
@@ -28,6 +32,9 @@ struct Cli {
     /// Path to YAML configuration
     #[arg(long, default_value = "config/gate_mvp.yaml")]
     config: String,
+    /// Route orders and credentials to Gate futures testnet (overrides `mode.network`)
+    #[arg(long)]
+    testnet: bool,
 }
 
 fn latency_debug_enabled() -> bool {
@@ -46,6 +53,8 @@ fn dur_us(d: Duration) -> u128 {
 const REF_WARN: Duration = Duration::from_millis(20);
 const STAGE_WARN: Duration = Duration::from_millis(5);
 const CANCEL_WARN: Duration = Duration::from_micros(500);
+/// How often the keystore is checked for rotated keys.
+const KEYSTORE_POLL: Duration = Duration::from_secs(10);
 
 struct CancelMessage {
     reference: ReferenceEvent,
@@ -57,8 +66,12 @@ async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
     let cli = Cli::parse();
     let mut config = load_runner_config(&cli.config)?;
+    if cli.testnet {
+        config.mode.network = Network::Testnet;
+    }
     configure_feed_overrides(config.feeds);
     let debug = DebugLogger::new(config.mode.debug_prints);
+    print_network_banner(&config);
 
     let contract_meta = rest::fetch_contract_meta_async(&config.strategy.symbol)
         .await
@@ -126,10 +139,32 @@ async fn main() -> Result<()> {
     } else {
         Some(load_gate_credentials(config.as_ref())?)
     };
-
-    let rest_client = credentials
+    // The handle keeps the store alive; without the watcher rotated keys never load
+    let _keystore_watcher = credentials
         .as_ref()
-        .map(|creds| Arc::new(GateClient::new(creds.clone())));
+        .and_then(|creds| creds.store())
+        .map(|store| store.clone().spawn_watcher(KEYSTORE_POLL));
+
+    // Signed requests use server-corrected time; dry runs sign nothing
+    let server_clock = ServerClock::new(config.clock.recv_window_ms);
+    let skew_rx = if config.mode.dry_run {
+        None
+    } else {
+        let (rx, _sync) = ClockSync::spawn(
+            TimeEndpoint::Gate,
+            config.mode.network,
+            config.clock.clone(),
+            server_clock.clone(),
+        );
+        Some(rx)
+    };
+
+    let rest_client = credentials.as_ref().map(|creds| {
+        Arc::new(
+            GateClient::for_network(creds.clone(), config.mode.network)
+                .with_clock(server_clock.clone()),
+        )
+    });
 
     let initial_contracts = if let Some(client) = rest_client.as_ref() {
         match client
@@ -215,19 +250,24 @@ async fn main() -> Result<()> {
     );
     debug.info(|| {
         format!(
-            "Gate runner started for {} (dry_run: {})",
-            config.strategy.symbol, config.mode.dry_run
+            "Gate runner started for {} (dry_run: {}, network: {})",
+            config.strategy.symbol, config.mode.dry_run, config.mode.network
         )
     });
 
-    let gateway: Arc<dyn ExecutionGateway> = if config.mode.dry_run {
+    let paper = (config.mode.dry_run && config.mode.paper_fills)
+        .then(|| Arc::new(SimulatedExchange::new(config.mode.matching.clone())));
+    let gateway: Arc<dyn ExecutionGateway> = if let Some(paper) = &paper {
+        debug.info(|| "Paper fills: dry-run orders match against the reference price".to_string());
+        paper.clone()
+    } else if config.mode.dry_run {
         Arc::new(DryRunGateway::new())
     } else {
         let creds = credentials
             .as_ref()
             .expect("credentials must exist for live mode")
             .clone();
-        Arc::new(setup_live_gateway(config.as_ref(), contract_size, &creds).await?)
+        Arc::new(setup_live_gateway(config.as_ref(), contract_size, &creds, &server_clock).await?)
     };
     let order_manager = Arc::new(OrderManager::new(gateway, Duration::from_secs(30)));
     let strategy = Arc::new(Mutex::new(SimpleQuoteStrategy::new(
@@ -243,6 +283,15 @@ async fn main() -> Result<()> {
 
     tokio::spawn(async move {
         while let Some(msg) = cancel_rx.recv().await {
+            if let Some(paper) = &paper {
+                // Resting quotes fill first, so the strategy's cancels cannot race the price
+                match Price::try_from_f64(msg.reference.price) {
+                    Ok(price) => {
+                        paper.on_trade(&cancel_config.strategy.symbol, price, None);
+                    }
+                    Err(err) => cancel_debug.error(|| format!("paper fill skipped: {:#}", err)),
+                }
+            }
             if let Err(err) = handle_market_update(
                 msg,
                 cancel_strategy.clone(),
@@ -282,8 +331,19 @@ async fn main() -> Result<()> {
     let quote_gate = Arc::new(Semaphore::new(1));
 
     tokio::select! {
-        _ = ctrl_c_notifier() => {
-            debug.info(|| "Received shutdown signal; exiting.".to_string());
+        signal = ShutdownCoordinator::wait_for_signal() => {
+            match signal {
+                Ok(name) => debug.info(|| format!("Received {}; shutting down.", name)),
+                Err(err) => debug.error(|| format!("signal listener failed: {:#}; shutting down.", err)),
+            }
+        }
+        sample = wait_for_clock_skew(skew_rx, config.clock.max_skew_ms) => {
+            debug.error(|| {
+                format!(
+                    "AutoStop: clock skew {}ms vs Gate exceeds {}ms (rtt {}ms); shutting down.",
+                    sample.offset_ms, config.clock.max_skew_ms, sample.rtt_ms
+                )
+            });
         }
         _ = async {
             use tokio::sync::mpsc::error::TryRecvError;
@@ -396,6 +456,27 @@ async fn main() -> Result<()> {
         } => {}
     }
 
+    // Wait for an in-flight quote task so nothing is submitted after the cancel sweep
+    let _quiesced = quote_gate.acquire().await?;
+    let mut shutdown = ShutdownCoordinator::new(config.shutdown.clone(), EntryGate::new());
+    shutdown.register(ShutdownPhase::Orders, order_manager.clone());
+    if let Some(logger) = logger.clone() {
+        shutdown.register(ShutdownPhase::Flush, Arc::new(logger));
+    }
+    let report = shutdown.shutdown().await;
+    for failed in report.failures() {
+        debug.error(|| {
+            format!(
+                "shutdown step '{}' failed: {}",
+                failed.name,
+                failed.error.as_deref().unwrap_or_default()
+            )
+        });
+    }
+    if !report.is_clean() {
+        bail!("shutdown finished with {} failed step(s)", report.failures().count());
+    }
+
     Ok(())
 }
 
@@ -551,7 +632,7 @@ async fn handle_quote_tick(
     let config_ref = config.as_ref();
 
     let plan_opt = match strategy.try_lock() {
-        Ok(mut guard) => guard.plan_quotes(now),
+        Ok(mut guard) => guard.plan_quotes(now)?,
         Err(_) => {
             if latency_debug_enabled() {
                 debug.latency(|| "latency-debug::quote skipped (strategy busy)".to_string());
@@ -689,7 +770,10 @@ async fn handle_quote_tick(
         let debug_clone = debug.clone();
         tokio::spawn(async move {
             let call_start = Instant::now();
-            match order_manager_clone.submit(intents_for_send.clone()).await {
+            match order_manager_clone
+                .submit_idempotent(intents_for_send.clone(), SUBMIT_TIMEOUT, SUBMIT_LOOKUPS)
+                .await
+            {
                 Ok(acks) => {
                     if latency_debug_enabled() {
                         let call_elapsed = call_start.elapsed();
@@ -811,7 +895,7 @@ fn filter_intents(
     let mut skipped = Vec::new();
 
     for intent in intents {
-        let contracts = (intent.size.abs() / contract_size).round() as i64;
+        let contracts = (intent.size.abs().to_f64() / contract_size).round() as i64;
         if contracts == 0 {
             bail!(
                 "intent {} size {:.8} is below contract size {}",
@@ -822,7 +906,7 @@ fn filter_intents(
         }
 
         let effective_size = contracts as f64 * contract_size;
-        let price = intent.price.abs();
+        let price = intent.price.abs().to_f64();
         let notional = price * effective_size;
         if notional > risk.max_order_notional {
             bail!(
@@ -879,10 +963,10 @@ fn log_submission(
     let (source, ts_ns) = reference_meta
         .map(|meta| (meta.source.as_str(), meta.ts_ns))
         .unwrap_or(("unknown", None));
-    let mode = if config.mode.dry_run {
-        "dry-run"
-    } else {
-        "live"
+    let mode = match (config.mode.dry_run, config.mode.network) {
+        (true, _) => "dry-run",
+        (false, Network::Testnet) => "testnet",
+        (false, Network::Mainnet) => "live",
     };
     for (intent, ack) in intents.iter().zip(acks.iter()) {
         debug.info(|| {
@@ -931,22 +1015,58 @@ fn log_reports(reports: &[ExecutionReport], config: &RunnerConfig, debug: &Debug
     }
 }
 
-async fn ctrl_c_notifier() {
-    let _ = tokio::signal::ctrl_c().await;
+/// Printed regardless of `debug_prints`: which deployment gets the orders must never be a guess.
+fn print_network_banner(config: &RunnerConfig) {
+    let network = config.mode.network;
+    let target = GateioGet::base(network);
+    match (network, config.mode.dry_run) {
+        (Network::Testnet, _) => eprintln!(
+            "==== TESTNET ==== orders/credentials -> {} (market data still from mainnet feeds)",
+            target
+        ),
+        (Network::Mainnet, true) => eprintln!("==== MAINNET (dry run) ==== no orders will be sent"),
+        (Network::Mainnet, false) => {
+            eprintln!("==== MAINNET LIVE ==== real funds, orders -> {}", target)
+        }
+    }
+}
+
+/// Resolves once AutoStop trips on clock skew; never resolves without a sync task.
+async fn wait_for_clock_skew(
+    skew_rx: Option<watch::Receiver<Option<SkewSample>>>,
+    max_skew_ms: u64,
+) -> SkewSample {
+    let Some(mut skew_rx) = skew_rx else {
+        return std::future::pending().await;
+    };
+    let mut auto_stop = AutoStopManager {
+        max_clock_skew_ms: max_skew_ms,
+        ..AutoStopManager::default()
+    };
+    while skew_rx.changed().await.is_ok() {
+        let sample = *skew_rx.borrow_and_update();
+        if let Some(sample) = sample
+            && auto_stop.check_clock_skew(sample.offset_ms)
+        {
+            return sample;
+        }
+    }
+    std::future::pending().await
 }
 
 async fn setup_live_gateway(
     config: &RunnerConfig,
     contract_size: f64,
     creds: &GateCredentials,
+    clock: &ServerClock,
 ) -> Result<GateWsGateway> {
     let ws_config = GateWsConfig {
-        api_key: creds.api_key.clone(),
-        api_secret: creds.api_secret.clone(),
+        credentials: creds.clone(),
         symbol: config.strategy.symbol.clone(),
         settle: config.settle.clone(),
-        ws_url: None,
+        ws_url: Some(GateioWs::root(config.mode.network).to_string()),
         contract_size: Some(contract_size),
+        clock: Some(clock.clone()),
     };
 
     GateWsGateway::connect(ws_config).await
@@ -7,8 +7,8 @@ use anyhow::{Context, Result, bail};
 use clap::Parser;
 use rust_test::base_classes::types::Side;
 use rust_test::execution::{
-    ClientOrderId, ExecutionGateway, GateWsConfig, GateWsGateway, OrderManager, QuoteIntent,
-    TimeInForce, Venue,
+    ClientOrderIdGenerator, ExecutionGateway, GateCredentials, GateWsConfig, GateWsGateway,
+    OrderManager, Price, Qty, QuoteIntent, TimeInForce, Venue, SUBMIT_LOOKUPS, SUBMIT_TIMEOUT,
 };
 use tokio::time::sleep;
 
@@ -52,12 +52,12 @@ async fn main() -> Result<()> {
 
     let gateway = Arc::new(
         GateWsGateway::connect(GateWsConfig {
-            api_key,
-            api_secret,
+            credentials: GateCredentials::new(api_key, api_secret),
             symbol: cli.symbol.clone(),
             settle: None,
             ws_url: None,
             contract_size: None,
+            clock: None,
         })
         .await?,
     );
@@ -69,17 +69,19 @@ async fn main() -> Result<()> {
         Venue::Gate,
         cli.symbol.clone(),
         Side::Bid,
-        cli.price,
-        cli.size,
+        Price::try_from_f64(cli.price)?,
+        Qty::try_from_f64(cli.size)?,
         tif,
-        ClientOrderId::new(format!("t-test-buy-{}", current_epoch_ms())),
+        ClientOrderIdGenerator::new("testbuy")?.next_id()?,
     );
 
     println!(
         "Submitting {} {:.6} @ {:.2} ({:?})",
         intent.symbol, intent.size, intent.price, intent.tif
     );
-    let acks = order_manager.submit(vec![intent.clone()]).await?;
+    let acks = order_manager
+        .submit_idempotent(vec![intent.clone()], SUBMIT_TIMEOUT, SUBMIT_LOOKUPS)
+        .await?;
     for ack in &acks {
         println!(
             "Ack: {} -> {:?}",
@@ -123,11 +125,3 @@ fn parse_tif(value: &str) -> Result<TimeInForce> {
         other => bail!("unsupported tif {other}"),
     }
 }
-
-fn current_epoch_ms() -> u128 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("system time before epoch")
-        .as_millis()
-}
@@ -24,10 +24,14 @@ use chrono::{Utc, Duration};
 #[cfg(feature = "database")]
 use rust_test::database::{DatabaseRepository, BacktestResult as DbBacktestResult};
 #[cfg(feature = "database")]
-use rust_test::backtest::{BacktestEngine, BacktestSettings, ExecutionMode, TradeStream};
+use rust_test::backtest::{
+    BacktestEngine, BacktestSettings, ExecutionMode, TickGuardConfig, TradeStream,
+};
 #[cfg(feature = "database")]
 use rust_test::backtest::market::{TradeTick, TradeSide};
 #[cfg(feature = "database")]
+use rust_test::base_classes::symbol::Symbol;
+#[cfg(feature = "database")]
 use rust_test::backtest::replay::ReplayEngine;
 #[cfg(feature = "database")]
 use rust_test::backtest::metrics::BacktestResult;
@@ -393,6 +397,9 @@ async fn run_backtest_task(
                         enforce_emulator_mode: true,
                         slippage_satoshi: 0,
                         random_seed: None,
+                        fill_model: Default::default(),
+                        spread_aware: true,
+                        tick_guard: TickGuardConfig::default(),
                     };
                     
                     let mut engine = BacktestEngine::new(settings);
@@ -575,7 +582,7 @@ async fn load_trade_data(symbol: &str) -> anyhow::Result<Vec<TradeStream>> {
             if !ticks.is_empty() {
                 let trade_ticks: Vec<TradeTick> = ticks.into_iter().map(|t| TradeTick {
                     timestamp: t.timestamp,
-                    symbol: t.symbol,
+                    symbol: Symbol::new(&t.symbol),
                     price: f64::try_from(t.price).unwrap_or(0.0),
                     volume: f64::try_from(t.quantity).unwrap_or(0.0),
                     side: if t.side == "BUY" { TradeSide::Buy } else { TradeSide::Sell },
@@ -585,7 +592,7 @@ async fn load_trade_data(symbol: &str) -> anyhow::Result<Vec<TradeStream>> {
                 }).collect();
                 
                 log::info!("✅ Загружено {} тиков из БД для {}", trade_ticks.len(), symbol);
-                return Ok(vec![TradeStream::new(symbol.to_string(), trade_ticks)]);
+                return Ok(vec![TradeStream::new(Symbol::new(symbol), trade_ticks)]);
             }
             }
             Err(e) => {
@@ -678,7 +685,7 @@ fn generate_synthetic_data(symbol: &str) -> anyhow::Result<Vec<TradeStream>> {
         
         synthetic_ticks.push(TradeTick {
             timestamp,
-            symbol: symbol.to_string(),
+            symbol: Symbol::new(symbol),
             price: current_price,
             volume: 0.5 + (i as f64 % 20.0) / 20.0, // Объем 0.5-1.5
             side: if i % 2 == 0 { TradeSide::Buy } else { TradeSide::Sell },
@@ -688,7 +695,7 @@ fn generate_synthetic_data(symbol: &str) -> anyhow::Result<Vec<TradeStream>> {
         });
     }
     
-    Ok(vec![TradeStream::new(symbol.to_string(), synthetic_ticks)])
+    Ok(vec![TradeStream::new(Symbol::new(symbol), synthetic_ticks)])
 }
 
 #[cfg(feature = "database")]
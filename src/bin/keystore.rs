@@ -0,0 +1,98 @@
+#![cfg(feature = "gate_exec")]
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Parser, Subcommand};
+use rust_test::config::credentials::{
+    ApiCredentials, CredentialKey, KdfParams, KeystoreFile, MAIN_ACCOUNT, Secret, mask_key,
+};
+use zeroize::Zeroizing;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "keystore",
+    about = "Manage the encrypted API keystore (passphrase is read from an env var)"
+)]
+struct Cli {
+    #[arg(long, default_value = "config/keystore.json")]
+    path: PathBuf,
+    #[arg(long, default_value = "KEYSTORE_PASSPHRASE")]
+    passphrase_env: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Add or rotate keys; reads api key, api secret and optional passphrase as lines on stdin
+    Set {
+        exchange: String,
+        #[arg(long, default_value = MAIN_ACCOUNT)]
+        account: String,
+    },
+    Remove {
+        exchange: String,
+        #[arg(long, default_value = MAIN_ACCOUNT)]
+        account: String,
+    },
+    /// List accounts with masked keys
+    List,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let passphrase = Zeroizing::new(
+        std::env::var(&cli.passphrase_env)
+            .map_err(|_| anyhow!("passphrase env var {} is not set", cli.passphrase_env))?,
+    );
+
+    let (mut entries, kdf) = if cli.path.exists() {
+        let file = KeystoreFile::read(&cli.path)?;
+        (file.decrypt(&passphrase)?, file.kdf)
+    } else {
+        (HashMap::new(), KdfParams::default())
+    };
+
+    match cli.command {
+        Command::Set { exchange, account } => {
+            let mut lines = std::io::stdin().lock().lines();
+            let mut next_line = |what: &str| -> Result<Option<Zeroizing<String>>> {
+                Ok(lines
+                    .next()
+                    .transpose()
+                    .with_context(|| format!("failed to read {} from stdin", what))?
+                    .map(|line| Zeroizing::new(line.trim().to_string()))
+                    .filter(|line| !line.is_empty()))
+            };
+            let api_key = next_line("api key")?.ok_or_else(|| anyhow!("api key is required"))?;
+            let api_secret =
+                next_line("api secret")?.ok_or_else(|| anyhow!("api secret is required"))?;
+            let mut creds = ApiCredentials::new(api_key.as_str(), api_secret.as_str());
+            creds.passphrase = next_line("passphrase")?.map(|p| Secret::new(p.as_str()));
+
+            let key = CredentialKey::new(&exchange, &account);
+            println!("set {} -> {}", key, mask_key(&creds.api_key));
+            entries.insert(key, creds);
+        }
+        Command::Remove { exchange, account } => {
+            let key = CredentialKey::new(&exchange, &account);
+            if entries.remove(&key).is_none() {
+                bail!("{} is not in the keystore", key);
+            }
+            println!("removed {}", key);
+        }
+        Command::List => {
+            let mut keys: Vec<_> = entries.iter().collect();
+            keys.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, creds) in keys {
+                println!("{}\t{}", key, mask_key(&creds.api_key));
+            }
+            return Ok(());
+        }
+    }
+
+    KeystoreFile::encrypt(&entries, &passphrase, kdf)?.write(&cli.path)
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "database")]
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use rust_test::database::backup::{create_backup, restore_backup, verify_backup};
+use rust_test::database::{BackupManifest, BackupSources};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "state_backup",
+    about = "Back up and restore bot config, state database and journals as one checksummed archive"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Write an archive; safe while the bot is running
+    Backup {
+        /// Defaults to backups/state-<utc time>.backup.gz
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Paths below are relative to this directory
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+        /// Config file or directory; repeat for more
+        #[arg(long, default_values = ["config"])]
+        config: Vec<PathBuf>,
+        /// SQLite state database
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Journal file or directory; repeat for more
+        #[arg(long)]
+        journal: Vec<PathBuf>,
+    },
+    /// Check every checksum without unpacking
+    Verify { archive: PathBuf },
+    /// Unpack into `dest`; stop the bot first
+    Restore {
+        archive: PathBuf,
+        #[arg(long, default_value = ".")]
+        dest: PathBuf,
+        /// Replace files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Backup { out, root, config, db, journal } => {
+            let out = out.unwrap_or_else(|| {
+                PathBuf::from(format!("backups/state-{}.backup.gz", chrono::Utc::now().format("%Y%m%d-%H%M%S")))
+            });
+            let sources = BackupSources { root, config, storage: db, journals: journal };
+            let manifest = create_backup(&sources, &out).await?;
+            print_manifest(&manifest);
+            println!("wrote {}", out.display());
+        }
+        Command::Verify { archive } => {
+            let manifest = verify_backup(&archive)?;
+            print_manifest(&manifest);
+            println!("{} is intact", archive.display());
+        }
+        Command::Restore { archive, dest, force } => {
+            let manifest = restore_backup(&archive, &dest, force)?;
+            print_manifest(&manifest);
+            println!("restored into {}", dest.display());
+        }
+    }
+    Ok(())
+}
+
+fn print_manifest(manifest: &BackupManifest) {
+    for entry in &manifest.entries {
+        println!("{:?}\t{}\t{}\t{}", entry.kind, entry.size, &entry.sha256[..12], entry.path);
+    }
+    println!("{} files, {} bytes", manifest.entries.len(), manifest.total_bytes());
+}
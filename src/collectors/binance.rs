@@ -1,4 +1,5 @@
 use crate::base_classes::bbo_store::BboStore;
+use crate::base_classes::liquidations::{Liquidation, LiquidationSide, LiquidationStore};
 use crate::base_classes::tickers::{TickerSnapshot, TickerStore};
 use crate::base_classes::trades::{FixedTrades, Trade};
 use crate::base_classes::types::{Price, Qty, Seq};
@@ -164,10 +165,73 @@ pub fn update_trades<const N: usize>(s: &str, trades: &mut FixedTrades<N>) -> us
     0
 }
 
+// Update liquidation store from forceOrder frames. A SELL order closes a long.
+pub fn update_liquidations(s: &str, store: &mut LiquidationStore) -> Option<Liquidation> {
+    let raw: Value = serde_json::from_str(s).ok()?;
+    let payload = event_payload(&raw);
+    if payload.get("e").and_then(|v| v.as_str()) != Some("forceOrder") {
+        return None;
+    }
+    let order = payload.get("o")?;
+    let side = match order.get("S").and_then(|v| v.as_str())? {
+        "SELL" => LiquidationSide::Long,
+        "BUY" => LiquidationSide::Short,
+        _ => return None,
+    };
+    // Average fill price and filled quantity when present, the order limit otherwise
+    let price = order
+        .get("ap")
+        .and_then(as_f64)
+        .filter(|p| *p > 0.0)
+        .or_else(|| order.get("p").and_then(as_f64))?;
+    let qty = order
+        .get("z")
+        .and_then(as_f64)
+        .filter(|q| *q > 0.0)
+        .or_else(|| order.get("q").and_then(as_f64))?;
+    let ts_ms = order
+        .get("T")
+        .and_then(as_u64)
+        .or_else(|| payload.get("E").and_then(as_u64))
+        .unwrap_or(0);
+    let liquidation = Liquidation {
+        symbol: order.get("s").and_then(|v| v.as_str())?.to_string(),
+        side,
+        price,
+        qty,
+        ts_ms,
+    };
+    store.push(liquidation.clone());
+    Some(liquidation)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_update_liquidations_binance_force_order() {
+        let json = r#"{
+            "stream":"btcusdt@forceOrder",
+            "data":{
+                "e":"forceOrder",
+                "E":1568014460893,
+                "o":{
+                    "s":"BTCUSDT", "S":"SELL", "o":"LIMIT", "f":"IOC",
+                    "q":"0.014", "p":"9910", "ap":"9910.5", "X":"FILLED",
+                    "l":"0.014", "z":"0.014", "T":1568014460800
+                }
+            }
+        }"#;
+
+        let mut store = LiquidationStore::new(60_000);
+        let liquidation = update_liquidations(json, &mut store).expect("liquidation parsed");
+        assert_eq!(liquidation.side, LiquidationSide::Long);
+        assert_eq!((liquidation.price, liquidation.qty, liquidation.ts_ms), (9910.5, 0.014, 1568014460800));
+        assert_eq!(store.stats("BTCUSDT", 1568014460900).long_count, 1);
+        assert!(update_liquidations(r#"{"e":"aggTrade","s":"BTCUSDT"}"#, &mut store).is_none());
+    }
+
     #[test]
     fn test_update_tickers_binance_mark_price() {
         let json = r#"{
@@ -1,4 +1,5 @@
 use crate::base_classes::bbo_store::BboStore;
+use crate::base_classes::liquidations::{Liquidation, LiquidationSide, LiquidationStore};
 use crate::base_classes::tickers::{TickerSnapshot, TickerStore};
 use crate::base_classes::trades::{FixedTrades, Trade};
 use crate::base_classes::types::{Price, Qty, Seq};
@@ -159,10 +160,70 @@ pub fn update_tickers(s: &str, store: &mut TickerStore) -> Option<(String, Ticke
     Some((symbol, stored))
 }
 
+// Update liquidation store from futures.public_liquidates. The size sign follows the
+// forced order like futures.trades: negative sells, i.e. a long was liquidated.
+pub fn update_liquidations(s: &str, store: &mut LiquidationStore) -> usize {
+    let Ok(raw) = serde_json::from_str::<Value>(s) else {
+        return 0;
+    };
+    if raw.get("channel").and_then(|v| v.as_str()) != Some("futures.public_liquidates") {
+        return 0;
+    }
+    let Some(entries) = raw.get("result").and_then(|res| res.as_array()) else {
+        return 0;
+    };
+    let mut inserted = 0usize;
+    for entry in entries {
+        let (Some(symbol), Some(price), Some(size)) = (
+            entry.get("contract").and_then(|v| v.as_str()),
+            entry.get("price").and_then(as_f64),
+            entry.get("size").and_then(as_f64),
+        ) else {
+            continue;
+        };
+        if size == 0.0 {
+            continue;
+        }
+        let ts_ms = entry
+            .get("time_ms")
+            .and_then(as_u64)
+            .or_else(|| raw.get("time_ms").and_then(as_u64))
+            .unwrap_or(0);
+        store.push(Liquidation {
+            symbol: symbol.to_string(),
+            side: if size < 0.0 { LiquidationSide::Long } else { LiquidationSide::Short },
+            price,
+            qty: size.abs(),
+            ts_ms,
+        });
+        inserted += 1;
+    }
+    inserted
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_update_liquidations_gate() {
+        let json = r#"{
+            "time":1700000000,
+            "channel":"futures.public_liquidates",
+            "event":"update",
+            "result":[
+                {"price":"43000.5","size":-120,"time_ms":1700000000100,"contract":"BTC_USDT"},
+                {"price":43100,"size":30,"time_ms":1700000000200,"contract":"BTC_USDT"}
+            ]
+        }"#;
+
+        let mut store = LiquidationStore::new(60_000);
+        assert_eq!(update_liquidations(json, &mut store), 2);
+        let stats = store.stats("BTC_USDT", 1700000000300);
+        assert_eq!((stats.long_count, stats.long_qty, stats.short_qty), (1, 120.0, 30.0));
+        assert_eq!(stats.long_notional, 43000.5 * 120.0);
+    }
+
     #[test]
     fn test_update_tickers_gate_basic() {
         let json = r#"{
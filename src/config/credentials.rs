@@ -0,0 +1,580 @@
+//! Exchange API credentials from an encrypted keystore and/or the environment.
+//!
+//! The keystore is a JSON file whose payload is encrypted with ChaCha20-Poly1305 under a
+//! key derived from a passphrase with Argon2id. Environment variables override keystore
+//! entries, so CI and containers can inject keys without a keystore at all.
+//!
+//! Secrets never appear in `Debug`/`Display` output and are zeroed on drop. Consumers that
+//! call [`CredentialStore::get`] per connection/session pick up rotated keys after a
+//! [`CredentialStore::reload`] without restarting the process.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result, anyhow, bail};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::exchanges::endpoints::Network;
+use crate::exchanges::gate::signing::hex_bytes;
+
+const KEYSTORE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Sub-account used when none is specified.
+pub const MAIN_ACCOUNT: &str = "main";
+
+/// Account name the main account maps to on testnet.
+pub const TESTNET_ACCOUNT: &str = "testnet";
+
+/// A string that is never printed and is wiped from memory on drop.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The only way to read the secret; keep the borrow as short as possible.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+/// API keys for one exchange sub-account.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiCredentials {
+    pub api_key: String,
+    pub api_secret: Secret,
+    /// Required by OKX and Bitget, unused elsewhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<Secret>,
+}
+
+impl ApiCredentials {
+    pub fn new(api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            api_secret: Secret::new(api_secret),
+            passphrase: None,
+        }
+    }
+}
+
+/// Shows only the first four characters of an API key.
+pub fn mask_key(key: &str) -> String {
+    let visible: String = key.chars().take(4).collect();
+    format!("{}***", visible)
+}
+
+impl fmt::Debug for ApiCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiCredentials")
+            .field("api_key", &mask_key(&self.api_key))
+            .field("api_secret", &self.api_secret)
+            .field("passphrase", &self.passphrase)
+            .finish()
+    }
+}
+
+/// `exchange/account` identifier, lowercased so `Gate/Main` and `gate/main` match.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CredentialKey {
+    pub exchange: String,
+    pub account: String,
+}
+
+impl CredentialKey {
+    pub fn new(exchange: &str, account: &str) -> Self {
+        Self {
+            exchange: exchange.trim().to_ascii_lowercase(),
+            account: account.trim().to_ascii_lowercase(),
+        }
+    }
+
+    pub fn main(exchange: &str) -> Self {
+        Self::new(exchange, MAIN_ACCOUNT)
+    }
+
+    /// Testnet keys live under their own account (`testnet`, `arb1_testnet`), so a
+    /// testnet run can never resolve mainnet keys, and vice versa.
+    pub fn for_network(exchange: &str, account: &str, network: Network) -> Self {
+        let key = Self::new(exchange, account);
+        match network {
+            Network::Mainnet => key,
+            Network::Testnet if key.account == MAIN_ACCOUNT => Self::new(exchange, TESTNET_ACCOUNT),
+            Network::Testnet => Self::new(exchange, &format!("{}_testnet", key.account)),
+        }
+    }
+
+    /// Env var prefix: `GATE` for the main account, `GATE_ARB1` for sub-account `arb1`.
+    pub fn env_prefix(&self) -> String {
+        let prefix = if self.account == MAIN_ACCOUNT {
+            self.exchange.clone()
+        } else {
+            format!("{}_{}", self.exchange, self.account)
+        };
+        prefix
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect()
+    }
+
+    fn parse(raw: &str) -> Result<Self> {
+        match raw.split_once('/') {
+            Some((exchange, account)) if !exchange.is_empty() && !account.is_empty() => {
+                Ok(Self::new(exchange, account))
+            }
+            _ => bail!("keystore entry '{}' must be named exchange/account", raw),
+        }
+    }
+}
+
+impl fmt::Display for CredentialKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.exchange, self.account)
+    }
+}
+
+/// Argon2id cost parameters stored alongside the keystore.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub m_cost_kib: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost_kib: 64 * 1024,
+            t_cost: 3,
+            p_cost: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    pub(crate) fn derive_key(&self, passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        let params = Params::new(self.m_cost_kib, self.t_cost, self.p_cost, Some(32))
+            .map_err(|err| anyhow!("invalid keystore KDF params: {}", err))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+            .map_err(|err| anyhow!("keystore key derivation failed: {}", err))?;
+        Ok(key)
+    }
+}
+
+/// On-disk keystore layout. Only `ciphertext` carries credentials.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeystoreFile {
+    pub version: u32,
+    pub kdf: KdfParams,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl KeystoreFile {
+    pub fn encrypt(
+        entries: &HashMap<CredentialKey, ApiCredentials>,
+        passphrase: &str,
+        kdf: KdfParams,
+    ) -> Result<Self> {
+        if passphrase.is_empty() {
+            bail!("refusing to encrypt keystore with an empty passphrase");
+        }
+        let named: HashMap<String, &ApiCredentials> =
+            entries.iter().map(|(key, creds)| (key.to_string(), creds)).collect();
+        let plaintext = Zeroizing::new(serde_json::to_vec(&named)?);
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let key = kdf.derive_key(passphrase, &salt)?;
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| anyhow!("keystore encryption failed"))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf,
+            salt: hex_bytes(salt),
+            nonce: hex_bytes(nonce),
+            ciphertext: hex_bytes(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<HashMap<CredentialKey, ApiCredentials>> {
+        if self.version != KEYSTORE_VERSION {
+            bail!(
+                "unsupported keystore version {} (expected {})",
+                self.version,
+                KEYSTORE_VERSION
+            );
+        }
+        let salt = decode_hex(&self.salt).context("keystore salt")?;
+        let nonce = decode_hex(&self.nonce).context("keystore nonce")?;
+        if nonce.len() != NONCE_LEN {
+            bail!("keystore nonce must be {} bytes, got {}", NONCE_LEN, nonce.len());
+        }
+        let ciphertext = decode_hex(&self.ciphertext).context("keystore ciphertext")?;
+
+        let key = self.kdf.derive_key(passphrase, &salt)?;
+        let plaintext = Zeroizing::new(
+            ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+                .map_err(|_| anyhow!("keystore decryption failed: wrong passphrase or corrupted file"))?,
+        );
+        let named: HashMap<String, ApiCredentials> = serde_json::from_slice(&plaintext)
+            .context("keystore payload is not valid credentials JSON")?;
+        named
+            .into_iter()
+            .map(|(name, creds)| Ok((CredentialKey::parse(&name)?, creds)))
+            .collect()
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read keystore at {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse keystore at {}", path.display()))
+    }
+
+    /// Writes via a temp file and rename so a crash never leaves a truncated keystore.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write keystore to {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("failed to move keystore into {}", path.display()))
+    }
+}
+
+pub(crate) fn decode_hex(raw: &str) -> Result<Vec<u8>> {
+    if !raw.len().is_multiple_of(2) {
+        bail!("odd-length hex string");
+    }
+    // Bytes, not str slices: a non-ASCII character must be an error, not a char-boundary panic
+    raw.as_bytes()
+        .chunks_exact(2)
+        .enumerate()
+        .map(|(pair, digits)| {
+            let nibble = |b: u8| (b as char).to_digit(16);
+            match (nibble(digits[0]), nibble(digits[1])) {
+                (Some(hi), Some(lo)) => Ok((hi * 16 + lo) as u8),
+                _ => Err(anyhow!("invalid hex at offset {}", pair * 2)),
+            }
+        })
+        .collect()
+}
+
+/// Where the store reads its keystore from.
+#[derive(Clone, Debug)]
+pub struct KeystoreSource {
+    pub path: PathBuf,
+    /// Env var holding the keystore passphrase.
+    pub passphrase_env: String,
+}
+
+type EnvLookup = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Live credentials: keystore contents plus environment overrides.
+pub struct CredentialStore {
+    source: Option<KeystoreSource>,
+    entries: RwLock<Arc<HashMap<CredentialKey, Arc<ApiCredentials>>>>,
+    loaded_mtime: RwLock<Option<SystemTime>>,
+    env: EnvLookup,
+}
+
+impl CredentialStore {
+    /// Environment only: `{PREFIX}_API_KEY`, `{PREFIX}_API_SECRET`, `{PREFIX}_API_PASSPHRASE`.
+    pub fn from_env() -> Self {
+        Self::with_env(None, Arc::new(|name| std::env::var(name).ok()))
+    }
+
+    /// Decrypts the keystore now; fails loudly if it cannot.
+    pub fn open(source: KeystoreSource) -> Result<Self> {
+        let store = Self::with_env(Some(source), Arc::new(|name| std::env::var(name).ok()));
+        store.reload()?;
+        Ok(store)
+    }
+
+    /// Keystore only, unlocked with `passphrase`: no environment overrides, so one
+    /// process can hold stores of several tenants without them seeing each other's keys.
+    pub fn open_isolated(path: &Path, passphrase: Secret) -> Result<Self> {
+        const PASSPHRASE: &str = "ISOLATED_KEYSTORE_PASSPHRASE";
+        let source = KeystoreSource { path: path.to_path_buf(), passphrase_env: PASSPHRASE.to_string() };
+        let store = Self::with_env(
+            Some(source),
+            Arc::new(move |name| (name == PASSPHRASE).then(|| passphrase.expose().to_string())),
+        );
+        store.reload()?;
+        Ok(store)
+    }
+
+    /// No keystore and no environment: every `get` fails.
+    pub fn empty() -> Self {
+        Self::with_env(None, Arc::new(|_| None))
+    }
+
+    fn with_env(source: Option<KeystoreSource>, env: EnvLookup) -> Self {
+        Self {
+            source,
+            entries: RwLock::new(Arc::new(HashMap::new())),
+            loaded_mtime: RwLock::new(None),
+            env,
+        }
+    }
+
+    /// Credentials for `exchange`/`account`; env overrides win over the keystore.
+    pub fn get(&self, exchange: &str, account: &str) -> Result<Arc<ApiCredentials>> {
+        let key = CredentialKey::new(exchange, account);
+        if let Some(creds) = self.env_override(&key)? {
+            return Ok(Arc::new(creds));
+        }
+        let entries = self.entries.read().expect("credential store poisoned").clone();
+        entries.get(&key).cloned().ok_or_else(|| {
+            anyhow!(
+                "no credentials for {} (keystore has {} entries, env prefix {}_API_KEY unset)",
+                key,
+                entries.len(),
+                key.env_prefix()
+            )
+        })
+    }
+
+    /// Accounts currently available from the keystore (env-only accounts are not listed).
+    pub fn accounts(&self) -> Vec<CredentialKey> {
+        let mut keys: Vec<_> = self
+            .entries
+            .read()
+            .expect("credential store poisoned")
+            .keys()
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Re-reads the keystore and swaps it in atomically. On error the previous keys stay active.
+    pub fn reload(&self) -> Result<usize> {
+        let Some(source) = self.source.as_ref() else {
+            return Ok(0);
+        };
+        let passphrase = Zeroizing::new((self.env)(&source.passphrase_env).ok_or_else(|| {
+            anyhow!("keystore passphrase env var {} is not set", source.passphrase_env)
+        })?);
+        let mtime = std::fs::metadata(&source.path).and_then(|m| m.modified()).ok();
+        let decrypted = KeystoreFile::read(&source.path)?.decrypt(&passphrase)?;
+        let count = decrypted.len();
+        let entries = decrypted
+            .into_iter()
+            .map(|(key, creds)| (key, Arc::new(creds)))
+            .collect();
+        *self.entries.write().expect("credential store poisoned") = Arc::new(entries);
+        *self.loaded_mtime.write().expect("credential store poisoned") = mtime;
+        Ok(count)
+    }
+
+    /// Polls the keystore mtime and reloads on change, so rotated keys apply without restart.
+    pub fn spawn_watcher(self: Arc<Self>, every: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                let Some(source) = self.source.as_ref() else {
+                    return;
+                };
+                let current = std::fs::metadata(&source.path).and_then(|m| m.modified()).ok();
+                if current == *self.loaded_mtime.read().expect("credential store poisoned") {
+                    continue;
+                }
+                match self.reload() {
+                    Ok(count) => log::info!(
+                        "keystore {} reloaded: {} accounts",
+                        source.path.display(),
+                        count
+                    ),
+                    Err(err) => log::error!(
+                        "keystore {} changed but reload failed, keeping previous keys: {:#}",
+                        source.path.display(),
+                        err
+                    ),
+                }
+            }
+        })
+    }
+
+    fn env_override(&self, key: &CredentialKey) -> Result<Option<ApiCredentials>> {
+        let prefix = key.env_prefix();
+        let api_key = (self.env)(&format!("{}_API_KEY", prefix));
+        let api_secret = (self.env)(&format!("{}_API_SECRET", prefix));
+        match (api_key, api_secret) {
+            (Some(api_key), Some(api_secret)) => Ok(Some(ApiCredentials {
+                api_key,
+                api_secret: Secret::new(api_secret),
+                passphrase: (self.env)(&format!("{}_API_PASSPHRASE", prefix)).map(Secret::new),
+            })),
+            (None, None) => Ok(None),
+            _ => bail!(
+                "{}_API_KEY and {}_API_SECRET must be set together",
+                prefix,
+                prefix
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::GateCredentials;
+
+    // Cheap KDF so tests don't spend seconds in Argon2
+    const TEST_KDF: KdfParams = KdfParams {
+        m_cost_kib: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    fn sample() -> HashMap<CredentialKey, ApiCredentials> {
+        let mut entries = HashMap::new();
+        entries.insert(CredentialKey::main("gate"), ApiCredentials::new("gate-key-1", "gate-secret-1"));
+        entries.insert(CredentialKey::new("binance", "arb1"), ApiCredentials::new("bn-key", "bn-secret"));
+        entries
+    }
+
+    fn store_with_env(path: PathBuf, vars: &[(&str, &str)]) -> CredentialStore {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        CredentialStore::with_env(
+            Some(KeystoreSource {
+                path,
+                passphrase_env: "KEYSTORE_PASSPHRASE".to_string(),
+            }),
+            Arc::new(move |name| vars.get(name).cloned()),
+        )
+    }
+
+    #[test]
+    fn test_keystore_roundtrip_and_wrong_passphrase() {
+        let file = KeystoreFile::encrypt(&sample(), "hunter2", TEST_KDF).unwrap();
+        assert!(!file.ciphertext.contains(&hex_bytes("gate-secret-1")));
+
+        let decrypted = file.decrypt("hunter2").unwrap();
+        assert_eq!(decrypted, sample());
+        assert!(file.decrypt("hunter3").is_err());
+    }
+
+    #[test]
+    fn test_decode_hex_rejects_bad_input() {
+        assert_eq!(decode_hex("00ff7A").unwrap(), vec![0x00, 0xff, 0x7a]);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+        // Multi-byte character straddling a digit pair
+        assert!(decode_hex("aé0").is_err());
+    }
+
+    #[test]
+    fn test_secrets_never_formatted() {
+        let creds = ApiCredentials::new("ABCDEFGH", "super-secret");
+        let debug = format!("{:?}", creds);
+        assert!(!debug.contains("super-secret"));
+        assert!(!debug.contains("EFGH"));
+        assert!(debug.contains("ABCD***"));
+        assert_eq!(format!("{}", creds.api_secret), "***");
+    }
+
+    #[test]
+    fn test_env_overrides_keystore_and_reload_rotates() {
+        let dir = std::env::temp_dir().join(format!("keystore-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("keys.json");
+        KeystoreFile::encrypt(&sample(), "pw", TEST_KDF).unwrap().write(&path).unwrap();
+
+        let store = Arc::new(store_with_env(
+            path.clone(),
+            &[
+                ("KEYSTORE_PASSPHRASE", "pw"),
+                ("BINANCE_ARB1_API_KEY", "env-key"),
+                ("BINANCE_ARB1_API_SECRET", "env-secret"),
+            ],
+        ));
+        assert_eq!(store.reload().unwrap(), 2);
+        let gate = GateCredentials::from_store(store.clone(), CredentialKey::main("gate"));
+        assert_eq!(store.get("Gate", "Main").unwrap().api_key, "gate-key-1");
+        assert_eq!(store.get("binance", "arb1").unwrap().api_key, "env-key");
+        assert!(store.get("okx", MAIN_ACCOUNT).is_err());
+
+        let mut rotated = sample();
+        rotated.insert(CredentialKey::main("gate"), ApiCredentials::new("gate-key-2", "gate-secret-2"));
+        KeystoreFile::encrypt(&rotated, "pw", TEST_KDF).unwrap().write(&path).unwrap();
+        store.reload().unwrap();
+        assert_eq!(store.get("gate", MAIN_ACCOUNT).unwrap().api_key, "gate-key-2");
+        // Handles given out before the rotation sign with the new keys
+        assert_eq!(gate.current().unwrap().api_secret.expose(), "gate-secret-2");
+
+        // A broken file must not wipe the working keys
+        std::fs::write(&path, "{}").unwrap();
+        assert!(store.reload().is_err());
+        assert_eq!(store.get("gate", MAIN_ACCOUNT).unwrap().api_key, "gate-key-2");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_half_configured_env_is_an_error() {
+        let store = store_with_env(PathBuf::from("/nonexistent"), &[("GATE_API_KEY", "k")]);
+        assert!(store.get("gate", MAIN_ACCOUNT).is_err());
+    }
+
+    #[test]
+    fn test_testnet_keys_are_separate_accounts() {
+        let main = CredentialKey::for_network("gate", MAIN_ACCOUNT, Network::Testnet);
+        assert_eq!(main.to_string(), "gate/testnet");
+        assert_eq!(main.env_prefix(), "GATE_TESTNET");
+        let sub = CredentialKey::for_network("Binance", "ARB1", Network::Testnet);
+        assert_eq!(sub.env_prefix(), "BINANCE_ARB1_TESTNET");
+        assert_eq!(
+            CredentialKey::for_network("gate", MAIN_ACCOUNT, Network::Mainnet),
+            CredentialKey::main("gate")
+        );
+
+        let store = store_with_env(
+            PathBuf::from("/nonexistent"),
+            &[("GATE_API_KEY", "live"), ("GATE_API_SECRET", "live-secret")],
+        );
+        assert!(store.get("gate", &main.account).is_err());
+    }
+}
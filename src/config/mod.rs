@@ -1,2 +1,5 @@
 #[cfg(feature = "gate_exec")]
 pub mod runner;
+
+#[cfg(feature = "gate_exec")]
+pub mod credentials;
@@ -1,11 +1,15 @@
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
 use crate::base_classes::feed_config::FeedToggles;
-use crate::execution::GateCredentials;
+use crate::config::credentials::{CredentialKey, CredentialStore, KeystoreSource, MAIN_ACCOUNT};
+use crate::exchanges::endpoints::Network;
+use crate::execution::{ClockConfig, GateCredentials, MatchingRules};
+use crate::runtime::ShutdownConfig;
 use crate::strategy::QuoteConfig;
 
 fn default_true() -> bool {
@@ -31,6 +35,15 @@ pub struct ModeConfig {
     pub log_fills: bool,
     #[serde(default)]
     pub debug_prints: bool,
+    /// `testnet` sends everything (REST, private WS, credentials) to the venue sandbox.
+    #[serde(default)]
+    pub network: Network,
+    /// Paper trading: in dry run, orders rest in an in-process matching engine and fill
+    /// when the reference price trades through them, instead of never filling.
+    #[serde(default)]
+    pub paper_fills: bool,
+    #[serde(default)]
+    pub matching: MatchingRules,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -65,6 +78,19 @@ pub struct CredentialsConfig {
     pub api_key_env: Option<String>,
     #[serde(default)]
     pub api_secret_env: Option<String>,
+    /// Env vars used instead of `api_key_env`/`api_secret_env` when `mode.network` is testnet.
+    #[serde(default)]
+    pub testnet_api_key_env: Option<String>,
+    #[serde(default)]
+    pub testnet_api_secret_env: Option<String>,
+    /// Encrypted keystore; when set, keys come from it instead of `api_key_env`/`api_secret_env`.
+    #[serde(default)]
+    pub keystore_path: Option<String>,
+    #[serde(default)]
+    pub keystore_passphrase_env: Option<String>,
+    /// Sub-account inside the keystore, `main` when unset.
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -80,6 +106,10 @@ pub struct RunnerConfig {
     pub settle: Option<String>,
     #[serde(default)]
     pub feeds: FeedToggles,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
+    pub clock: ClockConfig,
 }
 
 pub fn load_runner_config(path: &str) -> Result<RunnerConfig> {
@@ -92,18 +122,42 @@ pub fn load_runner_config(path: &str) -> Result<RunnerConfig> {
 
 pub fn load_gate_credentials(config: &RunnerConfig) -> Result<GateCredentials> {
     let creds = config.credentials.clone().unwrap_or_default();
-    let key_env = creds
-        .api_key_env
-        .unwrap_or_else(|| "GATEIO_API_KEY".to_string());
-    let secret_env = creds
-        .api_secret_env
-        .unwrap_or_else(|| "GATEIO_SECRET_KEY".to_string());
+    let network = config.mode.network;
+    if let Some(path) = creds.keystore_path.as_ref() {
+        let store = CredentialStore::open(KeystoreSource {
+            path: path.into(),
+            passphrase_env: creds
+                .keystore_passphrase_env
+                .clone()
+                .unwrap_or_else(|| "KEYSTORE_PASSPHRASE".to_string()),
+        })?;
+        let account = creds.account.as_deref().unwrap_or(MAIN_ACCOUNT);
+        let key = CredentialKey::for_network("gate", account, network);
+        // Fail at startup, not on the first signature
+        store.get(&key.exchange, &key.account)?;
+        return Ok(GateCredentials::from_store(Arc::new(store), key));
+    }
+    let (key_env, secret_env) = match network {
+        Network::Mainnet => (
+            creds
+                .api_key_env
+                .unwrap_or_else(|| "GATEIO_API_KEY".to_string()),
+            creds
+                .api_secret_env
+                .unwrap_or_else(|| "GATEIO_SECRET_KEY".to_string()),
+        ),
+        Network::Testnet => (
+            creds
+                .testnet_api_key_env
+                .unwrap_or_else(|| "GATEIO_TESTNET_API_KEY".to_string()),
+            creds
+                .testnet_api_secret_env
+                .unwrap_or_else(|| "GATEIO_TESTNET_SECRET_KEY".to_string()),
+        ),
+    };
 
     let api_key = std::env::var(&key_env).with_context(|| format!("missing env var {key_env}"))?;
     let api_secret =
         std::env::var(&secret_env).with_context(|| format!("missing env var {secret_env}"))?;
-    Ok(GateCredentials {
-        api_key,
-        api_secret,
-    })
+    Ok(GateCredentials::new(api_key, api_secret))
 }
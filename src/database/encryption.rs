@@ -0,0 +1,138 @@
+//! Application-level encryption of stored trading state
+//!
+//! SQLCipher would need its own SQLite build and does nothing for PostgreSQL, so the
+//! state store seals sensitive fields itself: ChaCha20-Poly1305 under a key derived
+//! with Argon2id from a passphrase, the same scheme as the credentials keystore. The
+//! salt and KDF parameters live next to the data, per instance, together with a
+//! sealed check value, so a wrong passphrase fails at open instead of on the first
+//! row. Every sealed value is bound to its instance name: rows copied between
+//! instances do not decrypt.
+
+use crate::config::credentials::{decode_hex, KdfParams, Secret};
+use crate::exchanges::gate::signing::hex_bytes;
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+pub const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Plaintext of the check value stored with the salt
+const CHECK: &[u8] = b"state-store";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Environment variable holding the passphrase
+    #[serde(default = "default_passphrase_env")]
+    pub passphrase_env: String,
+    /// Used when the instance is first encrypted; later opens use the stored parameters
+    #[serde(default)]
+    pub kdf: KdfParams,
+}
+
+fn default_passphrase_env() -> String {
+    "STORAGE_PASSPHRASE".to_string()
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self { passphrase_env: default_passphrase_env(), kdf: KdfParams::default() }
+    }
+}
+
+impl EncryptionConfig {
+    pub fn passphrase(&self) -> Result<Secret> {
+        let passphrase = std::env::var(&self.passphrase_env)
+            .map_err(|_| anyhow!("storage passphrase env var {} is not set", self.passphrase_env))?;
+        if passphrase.is_empty() {
+            bail!("storage passphrase env var {} is empty", self.passphrase_env);
+        }
+        Ok(Secret::new(passphrase))
+    }
+}
+
+/// Key material of an encrypted instance, as stored in `state_encryption`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRecord {
+    pub kdf: KdfParams,
+    /// Hex
+    pub salt: String,
+    /// Sealed `CHECK`, hex
+    pub check: String,
+}
+
+#[derive(Clone)]
+pub struct StorageCipher {
+    cipher: ChaCha20Poly1305,
+    instance: Vec<u8>,
+}
+
+impl std::fmt::Debug for StorageCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StorageCipher(***)")
+    }
+}
+
+impl StorageCipher {
+    /// Fresh salt for an instance that is encrypted for the first time
+    pub fn create(passphrase: &Secret, kdf: KdfParams, instance: &str) -> Result<(Self, KeyRecord)> {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let cipher = Self::derive(passphrase, kdf, &salt, instance)?;
+        let check = cipher.seal(CHECK)?;
+        Ok((cipher, KeyRecord { kdf, salt: hex_bytes(salt), check }))
+    }
+
+    /// Key for an already encrypted instance; a wrong passphrase is an error here
+    pub fn unlock(passphrase: &Secret, record: &KeyRecord, instance: &str) -> Result<Self> {
+        let salt = decode_hex(&record.salt).context("storage salt")?;
+        let cipher = Self::derive(passphrase, record.kdf, &salt, instance)?;
+        match cipher.open(&record.check) {
+            Ok(check) if check == CHECK => Ok(cipher),
+            _ => bail!("storage passphrase for instance {} is wrong", instance),
+        }
+    }
+
+    fn derive(passphrase: &Secret, kdf: KdfParams, salt: &[u8], instance: &str) -> Result<Self> {
+        if passphrase.expose().is_empty() {
+            bail!("refusing to encrypt storage with an empty passphrase");
+        }
+        let key = kdf.derive_key(passphrase.expose(), salt)?;
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key.as_ref())),
+            instance: instance.as_bytes().to_vec(),
+        })
+    }
+
+    /// Nonce followed by ciphertext, hex
+    pub fn seal(&self, plaintext: &[u8]) -> Result<String> {
+        Ok(hex_bytes(self.seal_bytes(plaintext)?))
+    }
+
+    pub fn open(&self, sealed: &str) -> Result<Vec<u8>> {
+        self.open_bytes(&decode_hex(sealed).context("sealed value is not hex")?)
+    }
+
+    pub fn seal_bytes(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: &self.instance })
+            .map_err(|_| anyhow!("storage encryption failed"))?;
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    pub fn open_bytes(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            bail!("sealed value is {} bytes, shorter than its nonce", sealed.len());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &self.instance })
+            .map_err(|_| anyhow!("sealed value does not decrypt: wrong key, another instance or corrupted"))
+    }
+}
@@ -1,9 +1,22 @@
 //! Database module for PostgreSQL integration
 //! Provides repository pattern for data persistence and retrieval
 
+pub mod backup;
+pub mod encryption;
 pub mod repository;
+pub mod state_store;
 pub mod types;
+pub mod versioning;
 
+pub use backup::{BackupEntry, BackupKind, BackupManifest, BackupSources};
+pub use encryption::EncryptionConfig;
 pub use repository::{DatabaseRepository, RepositoryError};
+#[cfg(feature = "dashboard")]
+pub use state_store::audit_routes;
+pub use state_store::{
+    AuditQuery, AuditRecord, CompactionReport, EquitySnapshot, JournalEntry, RetentionPolicy, StateStore,
+    StorageBackend, StorageConfig, VersionSummary,
+};
 pub use types::*;
+pub use versioning::{RunVersion, CODE_VERSION};
 
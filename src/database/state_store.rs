@@ -0,0 +1,1579 @@
+//! Live trading state storage: positions, orders, journal and equity snapshots
+//!
+//! One implementation over sqlx `Any`, so the backend is picked by config: embedded
+//! SQLite for a single bot, PostgreSQL when several instances centralize their state.
+//! Both run the same migrations from `database/migrations/state`, written in SQL that
+//! both engines accept. Every row is keyed by the instance name, so instances can
+//! share one PostgreSQL database without seeing each other's state.
+//!
+//! A long-running bot writes equity snapshots and journal lines forever, so
+//! [`StateStore::compact`] applies a [`RetentionPolicy`]: old equity snapshots are
+//! thinned to one per bucket, and old journal lines move to gzipped JSON-lines
+//! files before they are deleted. SQLite reuses the freed pages rather than
+//! shrinking the file, which is enough to keep it from growing.
+//!
+//! With `encryption` configured, amounts, prices and journal text are sealed per row
+//! (see [`super::encryption`]); instance, symbols of orders and positions, statuses and
+//! timestamps stay in plaintext, because the queries filter and thin by them.
+//!
+//! Once given a [`RunVersion`] ([`StateStore::set_run_version`]), the store stamps it on
+//! every order and journal line; [`StateStore::version_report`] then sums orders and
+//! fills per config hash and build. The stamp is plaintext in encrypted stores too.
+//!
+//! Configuration changes go to an append-only audit table ([`StateStore::append_config_change`]):
+//! there is no method to update or delete them and compaction leaves them alone, so a
+//! post-mortem can line PnL up against the parameters that were live at the time.
+
+use super::encryption::{EncryptionConfig, KeyRecord, StorageCipher};
+use super::versioning::RunVersion;
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::config::credentials::Secret;
+use crate::execution::trailing::{TrailChanges, TrailingState};
+use crate::execution::types::{ClientOrderId, ExchangeOrderId, OrderStatus};
+use crate::execution::user_stream::{OrderUpdate, PositionUpdate, UserEvent};
+use crate::runtime::audit::{ChangeSource, ConfigChange};
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::{Any, AnyPool, Decode, Row, Type, TypeInfo, ValueRef};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Schema migrations shared by both backends, applied in order
+const MIGRATIONS: &[(i64, &str, &str)] = &[
+    (1, "state", include_str!("../../database/migrations/state/0001_state.sql")),
+    (2, "trailing", include_str!("../../database/migrations/state/0002_trailing.sql")),
+    (3, "encryption", include_str!("../../database/migrations/state/0003_encryption.sql")),
+    (4, "config_audit", include_str!("../../database/migrations/state/0004_config_audit.sql")),
+    (5, "run_version", include_str!("../../database/migrations/state/0005_run_version.sql")),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Embedded database file, created on first open
+    Sqlite { path: String },
+    Postgres {
+        url: String,
+        #[serde(default = "default_max_connections")]
+        max_connections: u32,
+    },
+}
+
+fn default_max_connections() -> u32 {
+    5
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(flatten)]
+    pub backend: StorageBackend,
+    /// Instance name; rows of other instances in a shared database are never touched
+    pub instance: String,
+    /// Compaction policy for the caller to pass to [`StateStore::compact`]; none keeps everything
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+    /// Seal sensitive fields; an encrypted instance cannot be opened without it
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
+}
+
+const DAY_MS: i64 = 86_400_000;
+
+/// How much equity and journal history stays in the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Equity snapshots younger than this keep full resolution
+    #[serde(default = "default_equity_full_days")]
+    pub equity_full_days: u32,
+    /// Older snapshots are thinned to the last one in each bucket of this size
+    #[serde(default = "default_equity_bucket_ms")]
+    pub equity_bucket_ms: i64,
+    /// Journal lines older than this move to archive files
+    #[serde(default = "default_journal_days")]
+    pub journal_days: u32,
+    #[serde(default = "default_archive_dir")]
+    pub archive_dir: String,
+}
+
+fn default_equity_full_days() -> u32 {
+    7
+}
+
+fn default_equity_bucket_ms() -> i64 {
+    3_600_000
+}
+
+fn default_journal_days() -> u32 {
+    30
+}
+
+fn default_archive_dir() -> String {
+    "data/journal_archive".to_string()
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            equity_full_days: default_equity_full_days(),
+            equity_bucket_ms: default_equity_bucket_ms(),
+            journal_days: default_journal_days(),
+            archive_dir: default_archive_dir(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompactionReport {
+    pub equity_removed: u64,
+    pub journal_archived: u64,
+    /// Archive written by this run, if any journal lines were old enough
+    pub archive: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub seq: i64,
+    pub ts_ms: i64,
+    pub kind: String,
+    pub symbol: Option<Symbol>,
+    pub message: String,
+    /// Run version of the store when the line was written
+    pub version: Option<RunVersion>,
+}
+
+/// Orders and journal lines of one config hash on one build
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionSummary {
+    #[serde(flatten)]
+    pub version: RunVersion,
+    /// First and last update of its orders and journal lines
+    pub first_ms: i64,
+    pub last_ms: i64,
+    pub orders: u64,
+    /// Orders with any fill
+    pub filled_orders: u64,
+    /// Filled notional of buys and sells, quote currency
+    pub bought: f64,
+    pub sold: f64,
+    pub journal_lines: u64,
+}
+
+impl VersionSummary {
+    fn new(version: RunVersion, ts_ms: i64) -> Self {
+        Self {
+            version,
+            first_ms: ts_ms,
+            last_ms: ts_ms,
+            orders: 0,
+            filled_orders: 0,
+            bought: 0.0,
+            sold: 0.0,
+            journal_lines: 0,
+        }
+    }
+
+    /// Summary of `version` in `summaries`, added if missing, with `ts_ms` in its span
+    fn entry(summaries: &mut Vec<Self>, version: RunVersion, ts_ms: i64) -> &mut Self {
+        let at = match summaries.iter().position(|s| s.version == version) {
+            Some(at) => at,
+            None => {
+                summaries.push(Self::new(version, ts_ms));
+                summaries.len() - 1
+            }
+        };
+        let entry = &mut summaries[at];
+        entry.first_ms = entry.first_ms.min(ts_ms);
+        entry.last_ms = entry.last_ms.max(ts_ms);
+        entry
+    }
+
+    /// Sold minus bought notional: the gross PnL, before fees, of positions that were
+    /// both opened and closed under this version
+    pub fn net_flow(&self) -> f64 {
+        self.sold - self.bought
+    }
+}
+
+/// Filter of [`StateStore::config_changes`]; also the query string of `GET /audit/config`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default)]
+    pub from_ms: i64,
+    #[serde(default = "default_audit_to_ms")]
+    pub to_ms: i64,
+    /// Only this target; empty means all
+    #[serde(default)]
+    pub target: String,
+    #[serde(default = "default_audit_limit")]
+    pub limit: i64,
+}
+
+const MAX_AUDIT_LIMIT: i64 = 5_000;
+
+fn default_audit_to_ms() -> i64 {
+    i64::MAX
+}
+
+fn default_audit_limit() -> i64 {
+    500
+}
+
+impl Default for AuditQuery {
+    fn default() -> Self {
+        Self { from_ms: 0, to_ms: default_audit_to_ms(), target: String::new(), limit: default_audit_limit() }
+    }
+}
+
+/// Stored configuration change with its sequence number within the instance
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditRecord {
+    pub seq: i64,
+    #[serde(flatten)]
+    pub change: ConfigChange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquitySnapshot {
+    pub ts_ms: i64,
+    pub equity: f64,
+    pub balance: f64,
+    pub unrealised_pnl: f64,
+}
+
+/// Fields sealed when the store is encrypted; plaintext columns then hold the defaults
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PositionFields {
+    size: f64,
+    entry_price: f64,
+    liq_price: Option<f64>,
+    realised_pnl: f64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OrderFields {
+    price: f64,
+    size: f64,
+    filled: f64,
+    avg_fill_price: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JournalFields {
+    symbol: Option<String>,
+    message: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrailingFields {
+    size: f64,
+    trail: f64,
+    activation: Option<f64>,
+    watermark: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AuditFields {
+    old_value: Option<String>,
+    new_value: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct EquityFields {
+    equity: f64,
+    balance: f64,
+    unrealised_pnl: f64,
+}
+
+pub struct StateStore {
+    pool: AnyPool,
+    backend: &'static str,
+    instance: String,
+    journal_seq: AtomicI64,
+    audit_seq: AtomicI64,
+    cipher: Option<StorageCipher>,
+    version: RwLock<Option<RunVersion>>,
+}
+
+impl StateStore {
+    /// Connect and bring the schema up to date; the passphrase of an encrypted store
+    /// comes from the env var named in its config
+    pub async fn open(config: &StorageConfig) -> Result<Self> {
+        let passphrase = config.encryption.as_ref().map(EncryptionConfig::passphrase).transpose()?;
+        Self::open_with(config, passphrase.as_ref()).await
+    }
+
+    /// As `open`, with the passphrase supplied by the caller
+    pub async fn open_with(config: &StorageConfig, passphrase: Option<&Secret>) -> Result<Self> {
+        if config.instance.is_empty() {
+            bail!("storage instance name must not be empty");
+        }
+        sqlx::any::install_default_drivers();
+        let (url, max_connections, backend) = match &config.backend {
+            // One connection: SQLite serializes writers anyway, and `:memory:` is per connection
+            StorageBackend::Sqlite { path } => (format!("sqlite://{}?mode=rwc", path), 1, "sqlite"),
+            StorageBackend::Postgres { url, max_connections } => (url.clone(), *max_connections, "postgres"),
+        };
+        let pool = AnyPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(Duration::from_secs(30))
+            .connect(&url)
+            .await
+            .with_context(|| format!("Failed to open {} state storage", backend))?;
+        let mut store =
+            Self { pool, backend, instance: config.instance.clone(), journal_seq: AtomicI64::new(0),
+            audit_seq: AtomicI64::new(0),
+            cipher: None,
+            version: RwLock::new(None),
+        };
+        store.migrate().await?;
+        store.cipher = store.unlock(config.encryption.as_ref(), passphrase).await?;
+        // COALESCE: the Any driver cannot decode an untyped NULL aggregate, even into Option
+        let last: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(seq), 0) FROM state_journal WHERE instance = $1")
+            .bind(&store.instance)
+            .fetch_one(&store.pool)
+            .await
+            .context("Failed to read journal position")?;
+        store.journal_seq.store(last, Ordering::Relaxed);
+        let last: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(seq), 0) FROM state_config_audit WHERE instance = $1")
+            .bind(&store.instance)
+            .fetch_one(&store.pool)
+            .await
+            .context("Failed to read config audit position")?;
+        store.audit_seq.store(last, Ordering::Relaxed);
+        log::info!(
+            "state storage: {} as instance {}{}",
+            backend,
+            store.instance,
+            if store.cipher.is_some() { ", encrypted" } else { "" }
+        );
+        Ok(store)
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Key of an encrypted instance. Once an instance is encrypted, opening it without
+    /// encryption is refused: its sealed rows would read back as zeros
+    async fn unlock(&self, config: Option<&EncryptionConfig>, passphrase: Option<&Secret>) -> Result<Option<StorageCipher>> {
+        let row = sqlx::query("SELECT kdf, salt, check_value FROM state_encryption WHERE instance = $1")
+            .bind(&self.instance)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to read storage encryption record")?;
+        let stored = row
+            .map(|row| -> Result<KeyRecord> {
+                Ok(KeyRecord {
+                    kdf: serde_json::from_str(&row.try_get::<String, _>("kdf")?).context("stored KDF parameters")?,
+                    salt: row.try_get("salt")?,
+                    check: row.try_get("check_value")?,
+                })
+            })
+            .transpose()?;
+        let (config, passphrase) = match (config, passphrase, &stored) {
+            (Some(config), Some(passphrase), _) => (config, passphrase),
+            (Some(_), None, _) => bail!("storage encryption is configured but no passphrase was given"),
+            (None, _, Some(_)) => bail!("storage of instance {} is encrypted; configure `encryption` to open it", self.instance),
+            (None, _, None) => return Ok(None),
+        };
+        if let Some(record) = &stored {
+            return StorageCipher::unlock(passphrase, record, &self.instance).map(Some);
+        }
+        let (cipher, record) = StorageCipher::create(passphrase, config.kdf, &self.instance)?;
+        sqlx::query("INSERT INTO state_encryption (instance, kdf, salt, check_value, created_ms) VALUES ($1, $2, $3, $4, $5)")
+            .bind(&self.instance)
+            .bind(serde_json::to_string(&record.kdf)?)
+            .bind(&record.salt)
+            .bind(&record.check)
+            .bind(chrono::Utc::now().timestamp_millis())
+            .execute(&self.pool)
+            .await
+            .context("Failed to store storage encryption record")?;
+        log::warn!("state storage: instance {} is now encrypted; rows written before stay in plaintext", self.instance);
+        Ok(Some(cipher))
+    }
+
+    /// Stamp later orders and journal lines with `version`; call again after a config reload
+    pub fn set_run_version(&self, version: RunVersion) {
+        log::info!("state storage: stamping config {} on {}", version.config_hash, version.code_version);
+        *self.version.write().unwrap_or_else(|e| e.into_inner()) = Some(version);
+    }
+
+    pub fn run_version(&self) -> Option<RunVersion> {
+        self.version.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Values of the `config_hash` and `code_version` columns
+    fn stamp(&self) -> (Option<String>, Option<String>) {
+        match self.run_version() {
+            Some(version) => (Some(version.config_hash), Some(version.code_version)),
+            None => (None, None),
+        }
+    }
+
+    /// Sealed `fields` and the values for the plaintext columns
+    fn seal<T: Serialize + Default>(&self, fields: T) -> Result<(T, Option<String>)> {
+        match &self.cipher {
+            Some(cipher) => Ok((T::default(), Some(cipher.seal(&serde_json::to_vec(&fields)?)?))),
+            None => Ok((fields, None)),
+        }
+    }
+
+    /// Fields of a sealed row; `None` for a plaintext row
+    fn unseal<T: DeserializeOwned>(&self, row: &AnyRow) -> Result<Option<T>> {
+        let Some(sealed) = nullable::<String>(row, "sealed")? else {
+            return Ok(None);
+        };
+        let Some(cipher) = &self.cipher else {
+            bail!("row is encrypted but the storage has no key");
+        };
+        Ok(Some(serde_json::from_slice(&cipher.open(&sealed)?).context("sealed row payload")?))
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS state_migrations (version BIGINT PRIMARY KEY, name TEXT NOT NULL, applied_ms BIGINT NOT NULL)",
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to create migrations table")?;
+        for (version, name, sql) in MIGRATIONS {
+            let applied: Option<i64> = sqlx::query_scalar("SELECT version FROM state_migrations WHERE version = $1")
+                .bind(*version)
+                .fetch_optional(&self.pool)
+                .await?;
+            if applied.is_some() {
+                continue;
+            }
+            let mut tx = self.pool.begin().await?;
+            for statement in statements(sql) {
+                sqlx::query(&statement)
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Migration {} ({}) failed on {}", version, name, self.backend))?;
+            }
+            // Another instance may have migrated concurrently. CREATEs are idempotent; a
+            // lost race on an ALTER fails the open loudly and the next open finds it applied
+            sqlx::query("INSERT INTO state_migrations (version, name, applied_ms) VALUES ($1, $2, $3) ON CONFLICT (version) DO NOTHING")
+                .bind(*version)
+                .bind(*name)
+                .bind(chrono::Utc::now().timestamp_millis())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            log::info!("state storage: applied migration {} ({})", version, name);
+        }
+        Ok(())
+    }
+
+    /// Persist order and position updates from the user-data stream
+    pub async fn record(&self, event: &UserEvent) -> Result<()> {
+        match event {
+            UserEvent::Order(update) => self.upsert_order(update).await,
+            UserEvent::Position(position) => self.upsert_position(position).await,
+            UserEvent::Trade(_) | UserEvent::Balance(_) | UserEvent::StreamGap { .. } => Ok(()),
+        }
+    }
+
+    // =================================================================
+    // Positions
+    // =================================================================
+
+    pub async fn upsert_position(&self, position: &PositionUpdate) -> Result<()> {
+        let (fields, sealed) = self.seal(PositionFields {
+            size: position.size,
+            entry_price: position.entry_price,
+            liq_price: position.liq_price,
+            realised_pnl: position.realised_pnl,
+        })?;
+        sqlx::query(
+            r#"
+            INSERT INTO state_positions (instance, symbol, size, entry_price, liq_price, realised_pnl, updated_ms, sealed)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (instance, symbol) DO UPDATE SET
+                size = excluded.size, entry_price = excluded.entry_price, liq_price = excluded.liq_price,
+                realised_pnl = excluded.realised_pnl, updated_ms = excluded.updated_ms, sealed = excluded.sealed
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(position.symbol.as_str())
+        .bind(fields.size)
+        .bind(fields.entry_price)
+        .bind(fields.liq_price)
+        .bind(fields.realised_pnl)
+        .bind(position.ts_ms as i64)
+        .bind(sealed)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to store {} position", position.symbol))?;
+        Ok(())
+    }
+
+    /// Last known position per symbol, flat ones included
+    pub async fn positions(&self) -> Result<Vec<PositionUpdate>> {
+        let rows = sqlx::query(
+            "SELECT symbol, size, entry_price, liq_price, realised_pnl, updated_ms, sealed FROM state_positions WHERE instance = $1 ORDER BY symbol",
+        )
+        .bind(&self.instance)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load positions")?;
+        rows.iter()
+            .map(|row| {
+                let fields = match self.unseal(row)? {
+                    Some(fields) => fields,
+                    None => PositionFields {
+                        size: row.try_get("size")?,
+                        entry_price: row.try_get("entry_price")?,
+                        liq_price: nullable(row, "liq_price")?,
+                        realised_pnl: row.try_get("realised_pnl")?,
+                    },
+                };
+                Ok(PositionUpdate {
+                    symbol: Symbol::new(row.try_get::<String, _>("symbol")?.as_str()),
+                    size: fields.size,
+                    entry_price: fields.entry_price,
+                    liq_price: fields.liq_price,
+                    realised_pnl: fields.realised_pnl,
+                    ts_ms: row.try_get::<i64, _>("updated_ms")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    // =================================================================
+    // Orders
+    // =================================================================
+
+    pub async fn upsert_order(&self, order: &OrderUpdate) -> Result<()> {
+        let (fields, sealed) = self.seal(OrderFields {
+            price: order.price,
+            size: order.size,
+            filled: order.filled,
+            avg_fill_price: order.avg_fill_price,
+        })?;
+        let (config_hash, code_version) = self.stamp();
+        // The stamp is the version that placed the order: later updates keep it
+        sqlx::query(
+            r#"
+            INSERT INTO state_orders
+                (instance, exchange_order_id, client_order_id, symbol, side, status, price, size, filled, avg_fill_price,
+                 updated_ms, sealed, config_hash, code_version)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            ON CONFLICT (instance, exchange_order_id) DO UPDATE SET
+                status = excluded.status, filled = excluded.filled,
+                avg_fill_price = excluded.avg_fill_price, updated_ms = excluded.updated_ms, sealed = excluded.sealed
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(&order.exchange_order_id.0)
+        .bind(order.client_order_id.as_ref().map(|id| id.0.clone()))
+        .bind(order.symbol.as_str())
+        .bind(side_name(order.side))
+        .bind(status_name(&order.status))
+        .bind(fields.price)
+        .bind(fields.size)
+        .bind(fields.filled)
+        .bind(fields.avg_fill_price)
+        .bind(order.ts_ms as i64)
+        .bind(sealed)
+        .bind(config_hash)
+        .bind(code_version)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to store order {}", order.exchange_order_id.0))?;
+        Ok(())
+    }
+
+    /// Orders still resting on the exchange as of their last update
+    pub async fn open_orders(&self) -> Result<Vec<OrderUpdate>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT exchange_order_id, client_order_id, symbol, side, status, price, size, filled, avg_fill_price, updated_ms, sealed
+            FROM state_orders
+            WHERE instance = $1 AND status IN ('new', 'partially_filled')
+            ORDER BY updated_ms
+            "#,
+        )
+        .bind(&self.instance)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load open orders")?;
+        rows.iter().map(|row| self.order_from_row(row)).collect()
+    }
+
+    // =================================================================
+    // Journal
+    // =================================================================
+
+    /// Append a journal line; returns its sequence number within the instance
+    pub async fn append_journal(&self, ts_ms: i64, kind: &str, symbol: Option<Symbol>, message: &str) -> Result<i64> {
+        let seq = self.journal_seq.fetch_add(1, Ordering::Relaxed) + 1;
+        let (fields, sealed) =
+            self.seal(JournalFields { symbol: symbol.map(|s| s.as_str().to_string()), message: message.to_string() })?;
+        let (config_hash, code_version) = self.stamp();
+        sqlx::query(
+            r#"
+            INSERT INTO state_journal (instance, seq, ts_ms, kind, symbol, message, sealed, config_hash, code_version)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(seq)
+        .bind(ts_ms)
+        .bind(kind)
+        .bind(fields.symbol)
+        .bind(fields.message)
+        .bind(sealed)
+        .bind(config_hash)
+        .bind(code_version)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to append journal entry {}", seq))?;
+        Ok(seq)
+    }
+
+    /// Journal lines after `after_seq`, oldest first
+    pub async fn journal(&self, after_seq: i64, limit: i64) -> Result<Vec<JournalEntry>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT seq, ts_ms, kind, symbol, message, sealed, config_hash, code_version FROM state_journal
+            WHERE instance = $1 AND seq > $2 ORDER BY seq LIMIT $3
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(after_seq)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load journal")?;
+        rows.iter().map(|row| self.journal_from_row(row)).collect()
+    }
+
+    /// As `journal`, only lines written under `config_hash`
+    pub async fn journal_for_config(&self, config_hash: &str, after_seq: i64, limit: i64) -> Result<Vec<JournalEntry>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT seq, ts_ms, kind, symbol, message, sealed, config_hash, code_version FROM state_journal
+            WHERE instance = $1 AND config_hash = $2 AND seq > $3 ORDER BY seq LIMIT $4
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(config_hash)
+        .bind(after_seq)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("Failed to load journal of config {}", config_hash))?;
+        rows.iter().map(|row| self.journal_from_row(row)).collect()
+    }
+
+    // =================================================================
+    // Run versions
+    // =================================================================
+
+    /// Orders, fills and journal lines per run version, oldest version first; only
+    /// `config_hash` if given. Rows written before any version was set are left out
+    pub async fn version_report(&self, config_hash: Option<&str>) -> Result<Vec<VersionSummary>> {
+        let filter = config_hash.unwrap_or("");
+        let mut summaries: Vec<VersionSummary> = Vec::new();
+
+        let orders = sqlx::query(
+            r#"
+            SELECT exchange_order_id, client_order_id, symbol, side, status, price, size, filled, avg_fill_price, updated_ms,
+                   sealed, config_hash, code_version
+            FROM state_orders
+            WHERE instance = $1 AND config_hash IS NOT NULL AND ($2 = '' OR config_hash = $2)
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(filter)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load orders for the version report")?;
+        for row in &orders {
+            let Some(version) = version_from_row(row)? else { continue };
+            let order = self.order_from_row(row)?;
+            let entry = VersionSummary::entry(&mut summaries, version, order.ts_ms as i64);
+            entry.orders += 1;
+            if order.filled > 0.0 {
+                entry.filled_orders += 1;
+                let notional = order.filled * order.avg_fill_price.unwrap_or(order.price);
+                match order.side {
+                    Side::Bid => entry.bought += notional,
+                    Side::Ask => entry.sold += notional,
+                }
+            }
+        }
+
+        // Journal columns used here are plaintext: count in the database
+        let journal = sqlx::query(
+            r#"
+            SELECT config_hash, code_version, COUNT(*) AS lines, MIN(ts_ms) AS first_ms, MAX(ts_ms) AS last_ms
+            FROM state_journal
+            WHERE instance = $1 AND config_hash IS NOT NULL AND ($2 = '' OR config_hash = $2)
+            GROUP BY config_hash, code_version
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(filter)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to count journal lines for the version report")?;
+        for row in &journal {
+            let Some(version) = version_from_row(row)? else { continue };
+            let first_ms: i64 = row.try_get("first_ms")?;
+            let entry = VersionSummary::entry(&mut summaries, version, first_ms);
+            entry.last_ms = entry.last_ms.max(row.try_get("last_ms")?);
+            entry.journal_lines += row.try_get::<i64, _>("lines")? as u64;
+        }
+
+        summaries.sort_by_key(|s| s.first_ms);
+        Ok(summaries)
+    }
+
+    // =================================================================
+    // Trailing stops
+    // =================================================================
+
+    /// Write watermark changes from `TrailingStops::take_changes` in one transaction
+    pub async fn apply_trailing(&self, changes: &TrailChanges) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+        for state in &changes.upserts {
+            let (fields, sealed) = self.seal(TrailingFields {
+                size: state.size,
+                trail: state.trail,
+                activation: state.activation,
+                watermark: state.watermark,
+            })?;
+            sqlx::query(
+                r#"
+                INSERT INTO state_trailing (instance, trail_key, symbol, side, size, trail, activation, watermark, updated_ms, sealed)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (instance, trail_key) DO UPDATE SET
+                    symbol = excluded.symbol, side = excluded.side, size = excluded.size, trail = excluded.trail,
+                    activation = excluded.activation, watermark = excluded.watermark, updated_ms = excluded.updated_ms,
+                    sealed = excluded.sealed
+                "#,
+            )
+            .bind(&self.instance)
+            .bind(&state.key)
+            .bind(&state.symbol)
+            .bind(side_name(state.side))
+            .bind(fields.size)
+            .bind(fields.trail)
+            .bind(fields.activation)
+            .bind(fields.watermark)
+            .bind(state.updated_ms)
+            .bind(sealed)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to store trailing stop {}", state.key))?;
+        }
+        for key in &changes.removed {
+            sqlx::query("DELETE FROM state_trailing WHERE instance = $1 AND trail_key = $2")
+                .bind(&self.instance)
+                .bind(key)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to delete trailing stop {}", key))?;
+        }
+        tx.commit().await.context("Failed to commit trailing stops")?;
+        Ok(())
+    }
+
+    /// Trailing stops to hand to `TrailingStops::restore` on start
+    pub async fn trailing_stops(&self) -> Result<Vec<TrailingState>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT trail_key, symbol, side, size, trail, activation, watermark, updated_ms, sealed
+            FROM state_trailing WHERE instance = $1 ORDER BY trail_key
+            "#,
+        )
+        .bind(&self.instance)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load trailing stops")?;
+        rows.iter()
+            .map(|row| {
+                let fields = match self.unseal(row)? {
+                    Some(fields) => fields,
+                    None => TrailingFields {
+                        size: row.try_get("size")?,
+                        trail: row.try_get("trail")?,
+                        activation: nullable(row, "activation")?,
+                        watermark: nullable(row, "watermark")?,
+                    },
+                };
+                Ok(TrailingState {
+                    key: row.try_get("trail_key")?,
+                    symbol: row.try_get("symbol")?,
+                    side: side_from_name(&row.try_get::<String, _>("side")?)?,
+                    size: fields.size,
+                    trail: fields.trail,
+                    activation: fields.activation,
+                    watermark: fields.watermark,
+                    updated_ms: row.try_get("updated_ms")?,
+                })
+            })
+            .collect()
+    }
+
+    // =================================================================
+    // Equity snapshots
+    // =================================================================
+
+    pub async fn record_equity(&self, snapshot: &EquitySnapshot) -> Result<()> {
+        let (fields, sealed) = self.seal(EquityFields {
+            equity: snapshot.equity,
+            balance: snapshot.balance,
+            unrealised_pnl: snapshot.unrealised_pnl,
+        })?;
+        sqlx::query(
+            r#"
+            INSERT INTO state_equity (instance, ts_ms, equity, balance, unrealised_pnl, sealed)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (instance, ts_ms) DO UPDATE SET
+                equity = excluded.equity, balance = excluded.balance, unrealised_pnl = excluded.unrealised_pnl,
+                sealed = excluded.sealed
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(snapshot.ts_ms)
+        .bind(fields.equity)
+        .bind(fields.balance)
+        .bind(fields.unrealised_pnl)
+        .bind(sealed)
+        .execute(&self.pool)
+        .await
+        .context("Failed to store equity snapshot")?;
+        Ok(())
+    }
+
+    /// Snapshots with `from_ms <= ts_ms < to_ms`, oldest first
+    pub async fn equity(&self, from_ms: i64, to_ms: i64) -> Result<Vec<EquitySnapshot>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT ts_ms, equity, balance, unrealised_pnl, sealed FROM state_equity
+            WHERE instance = $1 AND ts_ms >= $2 AND ts_ms < $3
+            ORDER BY ts_ms
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(from_ms)
+        .bind(to_ms)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load equity snapshots")?;
+        rows.iter()
+            .map(|row| {
+                let fields = match self.unseal(row)? {
+                    Some(fields) => fields,
+                    None => EquityFields {
+                        equity: row.try_get("equity")?,
+                        balance: row.try_get("balance")?,
+                        unrealised_pnl: row.try_get("unrealised_pnl")?,
+                    },
+                };
+                Ok(EquitySnapshot {
+                    ts_ms: row.try_get("ts_ms")?,
+                    equity: fields.equity,
+                    balance: fields.balance,
+                    unrealised_pnl: fields.unrealised_pnl,
+                })
+            })
+            .collect()
+    }
+
+    // =================================================================
+    // Config audit
+    // =================================================================
+
+    /// Append one configuration change; returns its sequence number within the instance.
+    /// Old and new values are sealed when the store is encrypted, who and what stay readable
+    pub async fn append_config_change(&self, change: &ConfigChange) -> Result<i64> {
+        let seq = self.audit_seq.fetch_add(1, Ordering::Relaxed) + 1;
+        let (fields, sealed) = self.seal(AuditFields {
+            old_value: change.old.as_ref().map(|value| value.to_string()),
+            new_value: change.new.as_ref().map(|value| value.to_string()),
+        })?;
+        sqlx::query(
+            r#"
+            INSERT INTO state_config_audit
+                (instance, seq, ts_ms, source, actor, target, change_key, old_value, new_value, sealed)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(seq)
+        .bind(change.ts_ms)
+        .bind(change.source.as_str())
+        .bind(&change.actor)
+        .bind(&change.target)
+        .bind(&change.key)
+        .bind(fields.old_value)
+        .bind(fields.new_value)
+        .bind(sealed)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to append config change {} of {}", change.key, change.target))?;
+        Ok(seq)
+    }
+
+    /// Changes matching `query`, newest first
+    pub async fn config_changes(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT seq, ts_ms, source, actor, target, change_key, old_value, new_value, sealed FROM state_config_audit
+            WHERE instance = $1 AND ts_ms >= $2 AND ts_ms < $3 AND ($4 = '' OR target = $4)
+            ORDER BY seq DESC LIMIT $5
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(query.from_ms)
+        .bind(query.to_ms)
+        .bind(&query.target)
+        .bind(query.limit.clamp(0, MAX_AUDIT_LIMIT))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load config audit")?;
+        rows.iter()
+            .map(|row| {
+                let fields = match self.unseal(row)? {
+                    Some(fields) => fields,
+                    None => AuditFields { old_value: nullable(row, "old_value")?, new_value: nullable(row, "new_value")? },
+                };
+                let source: String = row.try_get("source")?;
+                let value = |text: Option<String>| -> Result<Option<serde_json::Value>> {
+                    text.map(|text| serde_json::from_str(&text).context("config audit value is not JSON")).transpose()
+                };
+                Ok(AuditRecord {
+                    seq: row.try_get("seq")?,
+                    change: ConfigChange {
+                        ts_ms: row.try_get("ts_ms")?,
+                        source: ChangeSource::parse(&source)
+                            .with_context(|| format!("unknown config change source {}", source))?,
+                        actor: row.try_get("actor")?,
+                        target: row.try_get("target")?,
+                        key: row.try_get("change_key")?,
+                        old: value(fields.old_value)?,
+                        new: value(fields.new_value)?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Drain a [`crate::runtime::ConfigAudit`] channel into the audit table until every
+    /// sender is dropped. A change that fails to store is logged and lost
+    pub fn spawn_audit_writer(self: Arc<Self>, mut rx: mpsc::UnboundedReceiver<ConfigChange>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(change) = rx.recv().await {
+                if let Err(err) = self.append_config_change(&change).await {
+                    log::error!(
+                        "config change {} {} by {} is NOT in the audit log: {:#}",
+                        change.target,
+                        change.key,
+                        change.actor,
+                        err
+                    );
+                }
+            }
+        })
+    }
+
+    // =================================================================
+    // Retention
+    // =================================================================
+
+    /// Thin old equity snapshots and archive old journal lines. Journal lines are
+    /// deleted only after their archive file is written and synced
+    pub async fn compact(&self, policy: &RetentionPolicy, now_ms: i64) -> Result<CompactionReport> {
+        if policy.equity_bucket_ms <= 0 {
+            bail!("retention equity_bucket_ms must be positive, got {}", policy.equity_bucket_ms);
+        }
+        let mut report = CompactionReport::default();
+
+        // Cutoff on a bucket boundary, so every thinned bucket is complete
+        let bucket = policy.equity_bucket_ms;
+        let equity_cutoff = (now_ms - policy.equity_full_days as i64 * DAY_MS).div_euclid(bucket) * bucket;
+        report.equity_removed = sqlx::query(
+            r#"
+            DELETE FROM state_equity WHERE instance = $1 AND ts_ms < $2 AND ts_ms NOT IN (
+                SELECT MAX(ts_ms) FROM state_equity WHERE instance = $1 AND ts_ms < $2 GROUP BY ts_ms / $3
+            )
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(equity_cutoff)
+        .bind(bucket)
+        .execute(&self.pool)
+        .await
+        .context("Failed to thin equity snapshots")?
+        .rows_affected();
+
+        // The newest line always stays: `open` resumes the sequence from it
+        let journal_cutoff = now_ms - policy.journal_days as i64 * DAY_MS;
+        let newest = self.journal_seq.load(Ordering::Relaxed);
+        let old = self.old_journal(journal_cutoff, newest).await?;
+        if let (Some(first), Some(last)) = (old.first(), old.last()) {
+            // An encrypted store seals its archives as a whole
+            let extension = if self.cipher.is_some() { "jsonl.gz.enc" } else { "jsonl.gz" };
+            let path = Path::new(&policy.archive_dir)
+                .join(format!("{}-journal-{}-{}.{}", self.instance, first.seq, last.seq, extension));
+            let last_seq = last.seq;
+            let count = old.len() as u64;
+            let target = path.clone();
+            let cipher = self.cipher.clone();
+            tokio::task::spawn_blocking(move || write_journal_archive(&target, &old, cipher.as_ref()))
+                .await
+                .context("Journal archive writer panicked")?
+                .with_context(|| format!("Failed to write journal archive {}", path.display()))?;
+            sqlx::query("DELETE FROM state_journal WHERE instance = $1 AND seq <= $2 AND ts_ms < $3 AND seq < $4")
+                .bind(&self.instance)
+                .bind(last_seq)
+                .bind(journal_cutoff)
+                .bind(newest)
+                .execute(&self.pool)
+                .await
+                .with_context(|| format!("Failed to delete journal lines archived to {}", path.display()))?;
+            report.journal_archived = count;
+            report.archive = Some(path);
+        }
+        if report.equity_removed > 0 || report.journal_archived > 0 {
+            log::info!(
+                "state storage: compacted {} equity snapshots, archived {} journal lines",
+                report.equity_removed,
+                report.journal_archived
+            );
+        }
+        Ok(report)
+    }
+
+    /// Journal lines of an archive written by `compact`; sealed archives need this
+    /// store's key
+    pub fn read_journal_archive(&self, path: &Path) -> Result<Vec<JournalEntry>> {
+        let mut data = std::fs::read(path).with_context(|| format!("Failed to read journal archive {}", path.display()))?;
+        if path.extension().is_some_and(|extension| extension == "enc") {
+            let Some(cipher) = &self.cipher else {
+                bail!("journal archive {} is encrypted but the storage has no key", path.display());
+            };
+            data = cipher.open_bytes(&data).with_context(|| format!("journal archive {}", path.display()))?;
+        }
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(data.as_slice())
+            .read_to_string(&mut text)
+            .with_context(|| format!("Failed to decompress journal archive {}", path.display()))?;
+        text.lines()
+            .map(|line| {
+                let line: ArchivedJournalLine = serde_json::from_str(line).context("journal archive line")?;
+                Ok(JournalEntry {
+                    seq: line.seq,
+                    ts_ms: line.ts_ms,
+                    kind: line.kind,
+                    symbol: line.symbol.map(|s| Symbol::new(s.as_str())),
+                    message: line.message,
+                    version: line.version,
+                })
+            })
+            .collect()
+    }
+
+    fn journal_from_row(&self, row: &AnyRow) -> Result<JournalEntry> {
+        let fields = match self.unseal(row)? {
+            Some(fields) => fields,
+            None => JournalFields { symbol: nullable(row, "symbol")?, message: row.try_get("message")? },
+        };
+        Ok(JournalEntry {
+            seq: row.try_get("seq")?,
+            ts_ms: row.try_get("ts_ms")?,
+            kind: row.try_get("kind")?,
+            symbol: fields.symbol.map(|s| Symbol::new(s.as_str())),
+            message: fields.message,
+            version: version_from_row(row)?,
+        })
+    }
+
+    fn order_from_row(&self, row: &AnyRow) -> Result<OrderUpdate> {
+        let side = side_from_name(&row.try_get::<String, _>("side")?).context("stored order")?;
+        let status: String = row.try_get("status")?;
+        let fields = match self.unseal(row)? {
+            Some(fields) => fields,
+            None => OrderFields {
+                price: row.try_get("price")?,
+                size: row.try_get("size")?,
+                filled: row.try_get("filled")?,
+                avg_fill_price: nullable(row, "avg_fill_price")?,
+            },
+        };
+        Ok(OrderUpdate {
+            symbol: Symbol::new(row.try_get::<String, _>("symbol")?.as_str()),
+            client_order_id: nullable::<String>(row, "client_order_id")?.map(ClientOrderId),
+            exchange_order_id: ExchangeOrderId(row.try_get("exchange_order_id")?),
+            status: serde_json::from_value(serde_json::Value::String(status.clone()))
+                .with_context(|| format!("stored order has status {:?}", status))?,
+            side,
+            price: fields.price,
+            size: fields.size,
+            filled: fields.filled,
+            avg_fill_price: fields.avg_fill_price,
+            ts_ms: row.try_get::<i64, _>("updated_ms")? as u64,
+        })
+    }
+
+    async fn old_journal(&self, before_ms: i64, below_seq: i64) -> Result<Vec<JournalEntry>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT seq, ts_ms, kind, symbol, message, sealed, config_hash, code_version FROM state_journal
+            WHERE instance = $1 AND ts_ms < $2 AND seq < $3 ORDER BY seq
+            "#,
+        )
+        .bind(&self.instance)
+        .bind(before_ms)
+        .bind(below_seq)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load journal lines to archive")?;
+        rows.iter().map(|row| self.journal_from_row(row)).collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedJournalLine {
+    seq: i64,
+    ts_ms: i64,
+    kind: String,
+    symbol: Option<String>,
+    message: String,
+    /// Absent in archives written before run versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<RunVersion>,
+}
+
+/// One JSON object per line, gzipped and sealed with `cipher` if any; written to a
+/// temp name and renamed into place
+fn write_journal_archive(path: &Path, entries: &[JournalEntry], cipher: Option<&StorageCipher>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    for entry in entries {
+        let line = ArchivedJournalLine {
+            seq: entry.seq,
+            ts_ms: entry.ts_ms,
+            kind: entry.kind.clone(),
+            symbol: entry.symbol.map(|s| s.as_str().to_string()),
+            message: entry.message.clone(),
+            version: entry.version.clone(),
+        };
+        serde_json::to_writer(&mut encoder, &line)?;
+        encoder.write_all(b"\n")?;
+    }
+    let mut data = encoder.finish()?;
+    if let Some(cipher) = cipher {
+        data = cipher.seal_bytes(&data)?;
+    }
+    let partial = path.with_extension("partial");
+    let mut file = std::fs::File::create(&partial)?;
+    file.write_all(&data)?;
+    file.sync_all()?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Splits a migration into statements, dropping `--` comment lines
+#[cfg(feature = "dashboard")]
+struct AuditApi {
+    store: Arc<StateStore>,
+    token: [u8; 32],
+}
+
+/// `GET /audit/config?from_ms=&to_ms=&target=&limit=` - configuration changes, newest
+/// first, for callers presenting `Authorization: Bearer <token>`
+#[cfg(feature = "dashboard")]
+pub fn audit_routes(store: Arc<StateStore>, token: &Secret) -> axum::Router {
+    use sha2::{Digest, Sha256};
+    assert!(!token.expose().is_empty(), "config audit API needs a token");
+    let api = Arc::new(AuditApi { store, token: Sha256::digest(token.expose().as_bytes()).into() });
+    axum::Router::new().route("/audit/config", axum::routing::get(audit_config)).with_state(api)
+}
+
+#[cfg(feature = "dashboard")]
+async fn audit_config(
+    axum::extract::State(api): axum::extract::State<Arc<AuditApi>>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<AuditQuery>,
+) -> axum::response::Response {
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use sha2::{Digest, Sha256};
+    let authorized = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .is_some_and(|token| <[u8; 32]>::from(Sha256::digest(token.as_bytes())) == api.token);
+    if !authorized {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    match api.store.config_changes(&query).await {
+        Ok(records) => axum::Json(records).into_response(),
+        Err(err) => {
+            log::error!("config audit query failed: {:#}", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, "config audit is unavailable").into_response()
+        }
+    }
+}
+
+fn statements(sql: &str) -> Vec<String> {
+    sql.split(';')
+        .map(|chunk| chunk.lines().filter(|line| !line.trim_start().starts_with("--")).collect::<Vec<_>>().join("\n"))
+        .filter(|statement| !statement.trim().is_empty())
+        .collect()
+}
+
+/// The Any driver refuses to decode NULL into `Option<T>` and its `is_null` is always
+/// false, so look at the value type instead
+/// Stamp columns of a row; `None` for rows written without a run version
+fn version_from_row(row: &AnyRow) -> Result<Option<RunVersion>> {
+    let config_hash = nullable::<String>(row, "config_hash")?;
+    let code_version = nullable::<String>(row, "code_version")?;
+    Ok(config_hash.map(|config_hash| RunVersion { config_hash, code_version: code_version.unwrap_or_default() }))
+}
+
+fn nullable<'r, T: Decode<'r, Any> + Type<Any>>(row: &'r AnyRow, column: &str) -> Result<Option<T>> {
+    if row.try_get_raw(column)?.type_info().name() == "NULL" {
+        return Ok(None);
+    }
+    Ok(Some(row.try_get(column)?))
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Bid => "buy",
+        Side::Ask => "sell",
+    }
+}
+
+fn side_from_name(name: &str) -> Result<Side> {
+    match name {
+        "buy" => Ok(Side::Bid),
+        "sell" => Ok(Side::Ask),
+        other => bail!("stored side {:?}", other),
+    }
+}
+
+fn status_name(status: &OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::New => "new",
+        OrderStatus::PartiallyFilled => "partially_filled",
+        OrderStatus::Filled => "filled",
+        OrderStatus::Canceled => "canceled",
+        OrderStatus::PartiallyCanceled => "partially_canceled",
+        OrderStatus::Rejected => "rejected",
+        OrderStatus::Unknown => "unknown",
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn open(path: &str, instance: &str) -> StateStore {
+        let config: StorageConfig =
+            serde_json::from_value(serde_json::json!({"backend": "sqlite", "path": path, "instance": instance})).unwrap();
+        StateStore::open(&config).await.unwrap()
+    }
+
+    fn order(id: &str, status: OrderStatus, filled: f64) -> OrderUpdate {
+        OrderUpdate {
+            symbol: Symbol::new("BTC_USDT"),
+            client_order_id: Some(ClientOrderId(format!("t-{}", id))),
+            exchange_order_id: ExchangeOrderId(id.to_string()),
+            status,
+            side: Side::Bid,
+            price: 64000.0,
+            size: 0.01,
+            filled,
+            avg_fill_price: None,
+            ts_ms: 1_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_survives_reopen_and_is_scoped_by_instance() {
+        let dir = std::env::temp_dir().join(format!("state-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.db");
+        let _ = std::fs::remove_file(&path);
+        let path = path.to_str().unwrap();
+
+        let store = open(path, "bot-1").await;
+        assert_eq!(store.backend(), "sqlite");
+        store.record(&UserEvent::Order(order("1", OrderStatus::New, 0.0))).await.unwrap();
+        store.record(&UserEvent::Order(order("2", OrderStatus::New, 0.0))).await.unwrap();
+        store.record(&UserEvent::Order(order("2", OrderStatus::Filled, 0.01))).await.unwrap();
+        let position = PositionUpdate {
+            symbol: Symbol::new("BTC_USDT"),
+            size: 0.01,
+            entry_price: 64000.0,
+            liq_price: None,
+            realised_pnl: 0.0,
+            ts_ms: 1_000,
+        };
+        store.record(&UserEvent::Position(position.clone())).await.unwrap();
+        assert_eq!(store.append_journal(1_000, "entry", Some(Symbol::new("BTC_USDT")), "bought").await.unwrap(), 1);
+        store.record_equity(&EquitySnapshot { ts_ms: 1_000, equity: 1000.0, balance: 1000.0, unrealised_pnl: 0.0 }).await.unwrap();
+        store.record_equity(&EquitySnapshot { ts_ms: 2_000, equity: 1005.0, balance: 1000.0, unrealised_pnl: 5.0 }).await.unwrap();
+        drop(store);
+
+        // Migrations run again on reopen without touching the data
+        let store = open(path, "bot-1").await;
+        let open_orders = store.open_orders().await.unwrap();
+        assert_eq!(open_orders, vec![order("1", OrderStatus::New, 0.0)]);
+        assert_eq!(store.positions().await.unwrap(), vec![position]);
+        assert_eq!(store.append_journal(2_000, "exit", None, "sold").await.unwrap(), 2);
+        let journal = store.journal(0, 10).await.unwrap();
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal[0].symbol, Some(Symbol::new("BTC_USDT")));
+        assert_eq!(store.equity(1_500, 3_000).await.unwrap().len(), 1);
+
+        let mut trails = crate::execution::TrailingStops::new();
+        trails
+            .add(TrailingState {
+                key: "btc-long".to_string(),
+                symbol: "BTC_USDT".to_string(),
+                side: Side::Ask,
+                size: 0.01,
+                trail: 0.02,
+                activation: None,
+                watermark: None,
+                updated_ms: 0,
+            })
+            .unwrap();
+        trails.on_price("BTC_USDT", 66000.0, 3_000);
+        store.apply_trailing(&trails.take_changes()).await.unwrap();
+        drop(store);
+        let store = open(path, "bot-1").await;
+        let restored = store.trailing_stops().await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].watermark, Some(66000.0));
+        trails.remove("btc-long");
+        store.apply_trailing(&trails.take_changes()).await.unwrap();
+        assert!(store.trailing_stops().await.unwrap().is_empty());
+
+        let other = open(path, "bot-2").await;
+        assert!(other.positions().await.unwrap().is_empty());
+        assert!(other.open_orders().await.unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compaction_thins_equity_and_archives_journal() {
+        let dir = std::env::temp_dir().join(format!("state-compact-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.db");
+        let store = open(path.to_str().unwrap(), "bot-1").await;
+
+        let hour = 3_600_000;
+        let now = 20 * DAY_MS;
+        // Old snapshots every 20 minutes over 3 hours, plus a recent one
+        for i in 0..9 {
+            let ts_ms = DAY_MS + i * hour / 3;
+            store.record_equity(&EquitySnapshot { ts_ms, equity: 1000.0 + i as f64, balance: 1000.0, unrealised_pnl: 0.0 }).await.unwrap();
+        }
+        store.record_equity(&EquitySnapshot { ts_ms: now - hour, equity: 1100.0, balance: 1100.0, unrealised_pnl: 0.0 }).await.unwrap();
+        store.append_journal(DAY_MS, "entry", Some(Symbol::new("BTC_USDT")), "bought").await.unwrap();
+        store.append_journal(2 * DAY_MS, "exit", None, "sold").await.unwrap();
+        store.append_journal(3 * DAY_MS, "entry", None, "flat").await.unwrap();
+        store.append_journal(now, "entry", None, "recent").await.unwrap();
+
+        let policy = RetentionPolicy {
+            journal_days: 10,
+            archive_dir: dir.join("archive").to_str().unwrap().to_string(),
+            ..RetentionPolicy::default()
+        };
+        let report = store.compact(&policy, now).await.unwrap();
+        assert_eq!(report.equity_removed, 6);
+        let kept: Vec<i64> = store.equity(0, now).await.unwrap().iter().map(|s| s.ts_ms - DAY_MS).collect();
+        assert_eq!(kept, vec![2 * hour / 3, 5 * hour / 3, 8 * hour / 3, 19 * DAY_MS - hour]);
+
+        assert_eq!(report.journal_archived, 3);
+        let archive = report.archive.unwrap();
+        assert!(archive.ends_with("bot-1-journal-1-3.jsonl.gz"));
+        let lines = store.read_journal_archive(&archive).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].symbol, Some(Symbol::new("BTC_USDT")));
+        assert_eq!(lines[1].message, "sold");
+        assert_eq!(store.journal(0, 10).await.unwrap().iter().map(|e| e.seq).collect::<Vec<_>>(), vec![4]);
+
+        // Nothing left to do on a second run
+        assert_eq!(store.compact(&policy, now).await.unwrap(), CompactionReport::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_config_changes_are_appended_and_queryable() {
+        use crate::runtime::audit::{ChangeOrigin, ConfigAudit};
+        use serde_json::json;
+        let dir = std::env::temp_dir().join(format!("state-audit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.db");
+        let store = Arc::new(open(path.to_str().unwrap(), "bot-1").await);
+
+        let (audit, rx) = ConfigAudit::channel();
+        let writer = store.clone().spawn_audit_writer(rx);
+        let api = ChangeOrigin::new(ChangeSource::Api, "tenant-3");
+        let telegram = ChangeOrigin::new(ChangeSource::Telegram, "chat:42");
+        audit.record(&api, "risk_profile", &json!({"max_positions": 3, "stop_pct": 1.0}), &json!({"max_positions": 5, "stop_pct": 1.0}));
+        audit.record(&telegram, "symbol_lists", &json!({"blacklist": []}), &json!({"blacklist": ["DOGE_USDT"], "paused": true}));
+        drop(audit);
+        writer.await.unwrap();
+
+        let all = store.config_changes(&AuditQuery::default()).await.unwrap();
+        assert_eq!(all.iter().map(|r| (r.seq, r.change.key.as_str())).collect::<Vec<_>>(), vec![(3, "paused"), (2, "blacklist"), (1, "max_positions")]);
+        assert_eq!((all[0].change.old.clone(), all[0].change.new.clone()), (None, Some(json!(true))));
+        assert_eq!(all[2].change.source, ChangeSource::Api);
+        assert_eq!(all[2].change.old, Some(json!(3)));
+
+        let risk = AuditQuery { target: "risk_profile".to_string(), ..AuditQuery::default() };
+        assert_eq!(store.config_changes(&risk).await.unwrap().len(), 1);
+        let late = AuditQuery { from_ms: chrono::Utc::now().timestamp_millis() + 1, ..AuditQuery::default() };
+        assert!(store.config_changes(&late).await.unwrap().is_empty());
+
+        // Sequence carries on after reopen; compaction leaves the audit alone
+        drop(all);
+        let store = open(path.to_str().unwrap(), "bot-1").await;
+        let change = ConfigChange { ts_ms: 5, source: ChangeSource::File, actor: "config.yaml".to_string(), target: "strategy:hook".to_string(), key: "enabled".to_string(), old: Some(json!(true)), new: Some(json!(false)) };
+        assert_eq!(store.append_config_change(&change).await.unwrap(), 4);
+        store.compact(&RetentionPolicy { archive_dir: dir.join("archive").to_str().unwrap().to_string(), ..RetentionPolicy::default() }, 20 * DAY_MS).await.unwrap();
+        assert_eq!(store.config_changes(&AuditQuery { limit: 2, ..AuditQuery::default() }).await.unwrap()[0].change, change);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_orders_and_journal_are_reported_per_run_version() {
+        let dir = std::env::temp_dir().join(format!("state-versions-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = open(dir.join("state.db").to_str().unwrap(), "bot-1").await;
+
+        store.append_journal(500, "note", None, "before any version").await.unwrap();
+        let tuned = RunVersion::of(&serde_json::json!({"take_profit": 1.0})).unwrap();
+        store.set_run_version(tuned.clone());
+        store.upsert_order(&order("1", OrderStatus::New, 0.0)).await.unwrap();
+        store.append_journal(1_000, "entry", Some(Symbol::new("BTC_USDT")), "bought").await.unwrap();
+
+        let retuned = RunVersion::of(&serde_json::json!({"take_profit": 1.5})).unwrap();
+        store.set_run_version(retuned.clone());
+        // A fill of an order placed by the previous config stays with that config
+        store.upsert_order(&OrderUpdate { ts_ms: 2_000, ..order("1", OrderStatus::Filled, 0.01) }).await.unwrap();
+        let sell = OrderUpdate { side: Side::Ask, avg_fill_price: Some(65000.0), ts_ms: 3_000, ..order("2", OrderStatus::Filled, 0.01) };
+        store.upsert_order(&sell).await.unwrap();
+        store.upsert_order(&OrderUpdate { ts_ms: 3_500, ..order("3", OrderStatus::New, 0.0) }).await.unwrap();
+        store.append_journal(3_000, "exit", Some(Symbol::new("BTC_USDT")), "sold").await.unwrap();
+
+        let report = store.version_report(None).await.unwrap();
+        assert_eq!(report.iter().map(|s| &s.version).collect::<Vec<_>>(), vec![&tuned, &retuned]);
+        assert_eq!((report[0].orders, report[0].filled_orders, report[0].journal_lines), (1, 1, 1));
+        assert_eq!((report[0].first_ms, report[0].last_ms), (1_000, 2_000));
+        assert!((report[0].bought - 640.0).abs() < 1e-9);
+        assert_eq!((report[1].orders, report[1].filled_orders, report[1].journal_lines), (2, 1, 1));
+        assert!((report[1].net_flow() - 650.0).abs() < 1e-9);
+
+        let only = store.version_report(Some(&retuned.config_hash)).await.unwrap();
+        assert_eq!(only, vec![report[1].clone()]);
+        let lines = store.journal_for_config(&tuned.config_hash, 0, 10).await.unwrap();
+        assert_eq!((lines.len(), lines[0].message.as_str(), lines[0].version.as_ref()), (1, "bought", Some(&tuned)));
+        assert_eq!(store.journal(0, 10).await.unwrap()[0].version, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_store_seals_rows_and_archives() {
+        let dir = std::env::temp_dir().join(format!("state-sealed-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.db");
+        let config = |encrypted: bool| StorageConfig {
+            backend: StorageBackend::Sqlite { path: path.to_str().unwrap().to_string() },
+            instance: "tenant-7".to_string(),
+            retention: None,
+            // Cheap KDF: the test is about sealing, not key stretching
+            encryption: encrypted.then(|| EncryptionConfig {
+                kdf: crate::config::credentials::KdfParams { m_cost_kib: 64, t_cost: 1, p_cost: 1 },
+                ..EncryptionConfig::default()
+            }),
+        };
+        let passphrase = Secret::new("correct horse");
+
+        let store = StateStore::open_with(&config(true), Some(&passphrase)).await.unwrap();
+        assert!(store.is_encrypted());
+        let position = PositionUpdate {
+            symbol: Symbol::new("BTC_USDT"),
+            size: 0.25,
+            entry_price: 64000.0,
+            liq_price: Some(51000.0),
+            realised_pnl: 12.5,
+            ts_ms: 1_000,
+        };
+        store.upsert_position(&position).await.unwrap();
+        store.upsert_order(&order("1", OrderStatus::New, 0.0)).await.unwrap();
+        store.append_journal(DAY_MS, "entry", Some(Symbol::new("BTC_USDT")), "bought 0.25 at 64000").await.unwrap();
+        store.append_journal(20 * DAY_MS, "note", None, "latest").await.unwrap();
+        store.record_equity(&EquitySnapshot { ts_ms: 1_000, equity: 25_000.0, balance: 24_000.0, unrealised_pnl: 1_000.0 }).await.unwrap();
+
+        // Plaintext columns hold nothing sensitive
+        let (size, message): (f64, String) = sqlx::query_as(
+            "SELECT p.size, j.message FROM state_positions p, state_journal j WHERE p.instance = $1 AND j.seq = 1",
+        )
+        .bind("tenant-7")
+        .fetch_one(&store.pool)
+        .await
+        .unwrap();
+        assert_eq!((size, message.as_str()), (0.0, ""));
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!raw.windows(6).any(|window| window == b"bought"));
+
+        // Reads decrypt transparently
+        assert_eq!(store.positions().await.unwrap(), vec![position.clone()]);
+        assert_eq!(store.open_orders().await.unwrap(), vec![order("1", OrderStatus::New, 0.0)]);
+        assert_eq!(store.journal(0, 10).await.unwrap()[0].message, "bought 0.25 at 64000");
+        assert_eq!(store.equity(0, 2_000).await.unwrap()[0].equity, 25_000.0);
+
+        let policy = RetentionPolicy {
+            journal_days: 10,
+            archive_dir: dir.join("archive").to_str().unwrap().to_string(),
+            ..RetentionPolicy::default()
+        };
+        let archive = store.compact(&policy, 20 * DAY_MS).await.unwrap().archive.unwrap();
+        assert!(archive.to_str().unwrap().ends_with(".jsonl.gz.enc"));
+        assert_eq!(store.read_journal_archive(&archive).unwrap()[0].message, "bought 0.25 at 64000");
+        drop(store);
+
+        let wrong = StateStore::open_with(&config(true), Some(&Secret::new("wrong"))).await.err().unwrap();
+        assert!(wrong.to_string().contains("passphrase for instance tenant-7 is wrong"), "{}", wrong);
+        let plain = StateStore::open_with(&config(false), None).await.err().unwrap();
+        assert!(plain.to_string().contains("is encrypted"), "{}", plain);
+        let store = StateStore::open_with(&config(true), Some(&passphrase)).await.unwrap();
+        assert_eq!(store.positions().await.unwrap(), vec![position]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! Which configuration and which build produced a stored order or journal line
+//!
+//! A tuning iteration changes the strategy config, a release changes the code; both
+//! change results. [`RunVersion`] pairs a short hash of the canonical JSON of the
+//! config with the crate version and git commit, and the state store stamps it on
+//! every order and journal line it writes, so the report of one iteration is a
+//! filter by hash rather than a guess by date.
+
+use crate::exchanges::gate::signing::hex_bytes;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// `<crate version>+<git commit>`; the commit is `unknown` for builds without a checkout
+/// that were not given `GIT_SHA`
+pub const CODE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+", env!("GIT_SHA"));
+
+/// Hex characters kept of the SHA-256: 64 bits, short enough to type into a filter
+const HASH_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RunVersion {
+    pub config_hash: String,
+    pub code_version: String,
+}
+
+impl RunVersion {
+    /// Version of `config` running on this build
+    pub fn of<T: Serialize>(config: &T) -> Result<Self> {
+        Ok(Self { config_hash: config_hash(config)?, code_version: CODE_VERSION.to_string() })
+    }
+}
+
+/// Hash of the config as JSON with object keys sorted, so field order, map iteration
+/// order and the file format (YAML or JSON) do not change it
+pub fn config_hash<T: Serialize>(config: &T) -> Result<String> {
+    let value = serde_json::to_value(config).context("config is not serializable for hashing")?;
+    let mut canonical = String::new();
+    write_canonical(&value, &mut canonical);
+    let mut hash = hex_bytes(Sha256::digest(canonical.as_bytes()));
+    hash.truncate(HASH_LEN);
+    Ok(hash)
+}
+
+fn write_canonical(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    struct Tuning {
+        take_profit: f64,
+        symbols: HashMap<String, u32>,
+    }
+
+    #[test]
+    fn test_config_hash_ignores_key_order_and_tracks_values() {
+        let symbols: HashMap<String, u32> = (0..20).map(|i| (format!("S{}", i), i)).collect();
+        let tuning = Tuning { take_profit: 1.5, symbols: symbols.clone() };
+        let hash = config_hash(&tuning).unwrap();
+        assert_eq!(hash.len(), HASH_LEN);
+
+        let reordered: serde_json::Value =
+            serde_json::from_str(&format!(r#"{{"symbols": {}, "take_profit": 1.5}}"#, serde_json::to_string(&symbols).unwrap()))
+                .unwrap();
+        assert_eq!(config_hash(&reordered).unwrap(), hash);
+        assert_ne!(config_hash(&Tuning { take_profit: 1.6, symbols }).unwrap(), hash);
+
+        let version = RunVersion::of(&tuning).unwrap();
+        assert!(version.code_version.starts_with(env!("CARGO_PKG_VERSION")));
+    }
+}
@@ -0,0 +1,114 @@
+//! Crate-wide typed errors.
+//!
+//! Each layer has its own enum: market data, execution, risk, storage and config.
+//! `Error` wraps them so the runtime can tell a bad feed from a broken strategy
+//! without parsing strings. Library paths the runtime has to survive return these
+//! instead of panicking; `anyhow` stays at the CLI and setup edges.
+//!
+//! The enums carry rendered context rather than source errors, so they are `Clone`
+//! and travel through the shard rings inside `StrategyAction::Error`.
+
+use thiserror::Error;
+
+/// Bad or missing input from an exchange feed
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MarketDataError {
+    #[error("{symbol}: invalid tick, {reason}")]
+    InvalidTick { symbol: String, reason: String },
+    #[error("{symbol}: {what} is {age_ms} ms old")]
+    Stale { symbol: String, what: &'static str, age_ms: u64 },
+    #[error("{feed}: malformed message, {reason}")]
+    Malformed { feed: String, reason: String },
+}
+
+/// A strategy or the order path reached a state it cannot act on
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ExecutionError {
+    /// State the code path relies on is not set, e.g. no buy price while managing a position
+    #[error("{strategy}: {field} is not set in {context}")]
+    MissingState { strategy: &'static str, field: &'static str, context: &'static str },
+    /// An event the current lifecycle phase does not accept, e.g. a sell fill with no position
+    #[error("{strategy}: {from} -> {to} is not allowed in {context}")]
+    IllegalTransition { strategy: &'static str, from: &'static str, to: &'static str, context: &'static str },
+    #[error("{symbol}: order rejected, {reason}")]
+    Rejected { symbol: String, reason: String },
+}
+
+/// A risk rule refused or cannot evaluate an action
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RiskError {
+    #[error("{symbol}: blocked by {rule}")]
+    Blocked { symbol: String, rule: &'static str },
+    #[error("{limit} {value} exceeds {max}")]
+    LimitBreached { limit: &'static str, value: f64, max: f64 },
+}
+
+/// Snapshots, archives, caches and the database
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum StorageError {
+    #[error("{path}: {reason}")]
+    Io { path: String, reason: String },
+    #[error("{what} is corrupt: {reason}")]
+    Corrupt { what: String, reason: String },
+}
+
+impl StorageError {
+    pub fn io(path: impl std::fmt::Display, err: &std::io::Error) -> Self {
+        StorageError::Io { path: path.to_string(), reason: err.to_string() }
+    }
+}
+
+/// Invalid or missing configuration
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConfigError {
+    #[error("{key}: {reason}")]
+    Invalid { key: String, reason: String },
+    #[error("{key} is required")]
+    Missing { key: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Error {
+    #[error(transparent)]
+    MarketData(#[from] MarketDataError),
+    #[error(transparent)]
+    Execution(#[from] ExecutionError),
+    #[error(transparent)]
+    Risk(#[from] RiskError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+impl Error {
+    /// Layer name for logs and counters
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::MarketData(_) => "market_data",
+            Error::Execution(_) => "execution",
+            Error::Risk(_) => "risk",
+            Error::Storage(_) => "storage",
+            Error::Config(_) => "config",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers_convert_into_error_and_keep_messages() {
+        let err: Error = ExecutionError::MissingState { strategy: "MStrike", field: "buy_price", context: "manage_position" }.into();
+        assert_eq!(err.kind(), "execution");
+        assert_eq!(err.to_string(), "MStrike: buy_price is not set in manage_position");
+
+        let err: Error = MarketDataError::InvalidTick { symbol: "ETH_USDT".into(), reason: "price 0".into() }.into();
+        assert!(matches!(err, Error::MarketData(MarketDataError::InvalidTick { .. })));
+        assert_eq!(err.to_string(), "ETH_USDT: invalid tick, price 0");
+
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(Error::from(StorageError::io("/tmp/x", &io)).to_string(), "/tmp/x: gone");
+    }
+}
@@ -3,6 +3,7 @@
 
 use crate::base_classes::types::Ts;
 use crate::base_classes::ws::ExchangeHandler;
+use crate::exchanges::endpoints::{BinanceWs, Network};
 use std::time::Instant;
 
 #[derive(Debug, Clone)]
@@ -109,6 +110,7 @@ pub struct BinanceParsedFrame {
 pub struct BinanceParsedHandler {
     symbol_lc: String,
     subs: Vec<String>,
+    network: Network,
 }
 
 impl BinanceParsedHandler {
@@ -121,9 +123,16 @@ impl BinanceParsedHandler {
         Self {
             symbol_lc,
             subs: vec![sub],
+            network: Network::Mainnet,
         }
     }
 
+    /// Point the stream at Binance futures testnet instead of mainnet
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
     #[inline(always)]
     fn parse_event(bytes: &[u8]) -> Option<BinanceEvent> {
         // ack result
@@ -168,7 +177,7 @@ impl BinanceParsedHandler {
 impl ExchangeHandler for BinanceParsedHandler {
     type Out = BinanceParsedFrame;
     fn url(&self) -> &str {
-        "wss://fstream.binance.com/ws"
+        BinanceWs::raw_url(self.network)
     }
     fn initial_subscriptions(&self) -> &[String] {
         &self.subs
@@ -2,6 +2,7 @@
 
 use crate::base_classes::types::Ts;
 use crate::base_classes::ws::ExchangeHandler;
+use crate::exchanges::endpoints::{BinanceWs, Network};
 use std::time::Instant;
 
 // Minimal frame wrapper. For prod, map to typed events.
@@ -15,6 +16,7 @@ pub struct BinanceFrame {
 pub struct BinanceHandler {
     symbol_lc: String,
     subs: Vec<String>,
+    network: Network,
 }
 
 impl BinanceHandler {
@@ -28,8 +30,15 @@ impl BinanceHandler {
         Self {
             symbol_lc,
             subs: vec![sub],
+            network: Network::Mainnet,
         }
     }
+
+    /// Point the stream at Binance futures testnet instead of mainnet
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
 }
 
 impl ExchangeHandler for BinanceHandler {
@@ -37,7 +46,7 @@ impl ExchangeHandler for BinanceHandler {
 
     #[inline(always)]
     fn url(&self) -> &str {
-        "wss://fstream.binance.com/ws"
+        BinanceWs::raw_url(self.network)
     }
 
     #[inline(always)]
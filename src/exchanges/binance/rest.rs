@@ -3,7 +3,7 @@
 #[cfg(feature = "binance_book")]
 use serde::Deserialize;
 
-use crate::exchanges::endpoints::BinanceGet as Endp;
+use crate::exchanges::endpoints::{BinanceGet as Endp, Network};
 
 #[cfg(feature = "binance_book")]
 #[derive(Debug, Clone, Deserialize)]
@@ -14,16 +14,70 @@ pub struct BinanceSnapshot {
     pub asks: Vec<[String; 2]>,
 }
 
+/// One entry of `/ticker/24hr`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binance24hTicker {
+    pub symbol: String,
+    pub last: f64,
+    pub high_24h: f64,
+    pub low_24h: f64,
+    /// Percent
+    pub change_pct_24h: f64,
+    pub quote_volume_24h: f64,
+    /// Number of trades over 24h
+    pub count_24h: u64,
+}
+
+/// Parses a `/ticker/24hr` response (all symbols). Entries without a price or trade count
+/// (settling or pre-launch symbols) are skipped; a non-array body is an error.
+pub fn parse_tickers_24h(value: &serde_json::Value) -> Result<Vec<Binance24hTicker>, String> {
+    let rows = value.as_array().ok_or_else(|| format!("ticker/24hr response is not an array: {}", value))?;
+    let f64_field = |row: &serde_json::Value, key: &str| match row.get(key)? {
+        serde_json::Value::String(s) => s.parse::<f64>().ok(),
+        serde_json::Value::Number(n) => n.as_f64(),
+        _ => None,
+    };
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(Binance24hTicker {
+                symbol: row.get("symbol")?.as_str()?.to_string(),
+                last: f64_field(row, "lastPrice").filter(|p| *p > 0.0)?,
+                high_24h: f64_field(row, "highPrice")?,
+                low_24h: f64_field(row, "lowPrice")?,
+                change_pct_24h: f64_field(row, "priceChangePercent").unwrap_or(0.0),
+                quote_volume_24h: f64_field(row, "quoteVolume").unwrap_or(0.0),
+                count_24h: row.get("count")?.as_u64()?,
+            })
+        })
+        .collect())
+}
+
+pub async fn get_tickers_24h(network: Network) -> anyhow::Result<Vec<Binance24hTicker>> {
+    let url = format!("{}{}", Endp::base(network), Endp::TICKER_24H);
+    let value: serde_json::Value = reqwest::Client::new().get(url).send().await?.error_for_status()?.json().await?;
+    parse_tickers_24h(&value).map_err(anyhow::Error::msg)
+}
+
 #[cfg(feature = "binance_book")]
 pub async fn get_orderbook_snapshot(
     symbol: &str,
     limit: usize,
+) -> Result<BinanceSnapshot, reqwest::Error> {
+    get_orderbook_snapshot_on(Network::Mainnet, symbol, limit).await
+}
+
+#[cfg(feature = "binance_book")]
+pub async fn get_orderbook_snapshot_on(
+    network: Network,
+    symbol: &str,
+    limit: usize,
 ) -> Result<BinanceSnapshot, reqwest::Error> {
     let symbol_uc = symbol.to_uppercase();
     // Binance REST endpoints expect symbols without underscore separators (e.g. BTCUSDT).
     let symbol_param = symbol_uc.replace('_', "");
     let path = format!("/depth?symbol={}&limit={}", symbol_param, limit);
-    let url = format!("{}{}", Endp::BASE, path);
+    let url = format!("{}{}", Endp::base(network), path);
     let resp = reqwest::Client::new()
         .get(url)
         .send()
@@ -32,3 +86,21 @@ pub async fn get_orderbook_snapshot(
     let snap = resp.json::<BinanceSnapshot>().await?;
     Ok(snap)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tickers_24h() {
+        let value = serde_json::json!([
+            {"symbol": "BTCUSDT", "priceChangePercent": "-0.812", "lastPrice": "65000.1", "highPrice": "66000",
+             "lowPrice": "64000", "quoteVolume": "9100000000.5", "count": 2345678},
+            {"symbol": "NEWUSDT", "lastPrice": "0", "highPrice": "0", "lowPrice": "0", "count": 0}
+        ]);
+        let tickers = parse_tickers_24h(&value).unwrap();
+        assert_eq!(tickers.len(), 1);
+        assert_eq!((tickers[0].change_pct_24h, tickers[0].count_24h), (-0.812, 2345678));
+        assert!(parse_tickers_24h(&serde_json::json!({"code": -1121})).is_err());
+    }
+}
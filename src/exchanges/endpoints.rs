@@ -3,6 +3,50 @@
 // Pure endpoint definitions and simple helpers.
 // Keep these zero-dep and allocation-light; use templates or small builders.
 
+// ---------------- Network ----------------
+/// Which venue deployment to talk to. Testnet keys and balances are separate from
+/// mainnet ones, so a stack pointed at `Testnet` cannot touch real funds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "gate_exec", derive(serde::Deserialize))]
+#[cfg_attr(feature = "gate_exec", serde(rename_all = "lowercase"))]
+pub enum Network {
+    #[default]
+    Mainnet,
+    /// Binance futures testnet, Bybit demo trading, Gate futures testnet
+    Testnet,
+}
+
+impl Network {
+    pub fn is_testnet(self) -> bool {
+        self == Network::Testnet
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+        }
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mainnet" | "main" | "prod" => Ok(Network::Mainnet),
+            "testnet" | "test" | "demo" | "sandbox" => Ok(Network::Testnet),
+            other => Err(format!("unknown network '{other}' (expected mainnet or testnet)")),
+        }
+    }
+}
+
 // ---------------- Bitget ----------------
 pub struct BitgetWs;
 impl BitgetWs {
@@ -39,6 +83,19 @@ impl BitgetWs {
 pub struct BybitWs;
 impl BybitWs {
     pub const BASE: &str = "wss://stream.bybit.com/v5/public/linear";
+    pub const PRIVATE_BASE: &str = "wss://stream.bybit.com/v5/private";
+    // Demo trading has its own private stream; market data stays on mainnet
+    pub const DEMO_PRIVATE_BASE: &str = "wss://stream-demo.bybit.com/v5/private";
+
+    pub fn public_base(_network: Network) -> &'static str {
+        Self::BASE
+    }
+    pub fn private_base(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::PRIVATE_BASE,
+            Network::Testnet => Self::DEMO_PRIVATE_BASE,
+        }
+    }
 
     // topic templates
     pub const ORDERBOOK_FMT: &str = "orderbook.{depth}.{symbol}";
@@ -60,16 +117,51 @@ impl BybitWs {
     }
 }
 
+pub struct BybitGet;
+impl BybitGet {
+    pub const BASE: &str = "https://api.bybit.com";
+    pub const DEMO_BASE: &str = "https://api-demo.bybit.com";
+    pub const SERVER_TIME: &str = "/v5/market/time";
+    pub const SYSTEM_STATUS: &str = "/v5/system/status";
+    pub const INSTRUMENTS_INFO_FMT: &str = "/v5/market/instruments-info?category=linear&symbol={symbol}";
+
+    pub fn base(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::BASE,
+            Network::Testnet => Self::DEMO_BASE,
+        }
+    }
+    pub fn instruments_info(symbol: &str) -> String {
+        format!("/v5/market/instruments-info?category=linear&symbol={symbol}")
+    }
+}
+
 // ---------------- Binance ----------------
 pub struct BinanceWs;
 impl BinanceWs {
     pub const BASE: &str = "wss://fstream.binance.com";
+    pub const TESTNET_BASE: &str = "wss://stream.binancefuture.com";
+
+    pub fn base(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::BASE,
+            Network::Testnet => Self::TESTNET_BASE,
+        }
+    }
+    // Raw-stream endpoint used by the per-symbol handlers
+    pub fn raw_url(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => "wss://fstream.binance.com/ws",
+            Network::Testnet => "wss://stream.binancefuture.com/ws",
+        }
+    }
 
     // stream templates (lowercase symbol, e.g., btcusdt)
     pub const ORDERBOOK_FMT: &str = "{symbol}@depth@100ms";
     pub const BBO_FMT: &str = "{symbol}@bookTicker";
     pub const TICKERS_FMT: &str = "{symbol}@markPrice@1s";
     pub const TRADES_FMT: &str = "{symbol}@aggTrade";
+    pub const FORCE_ORDER_FMT: &str = "{symbol}@forceOrder";
 
     pub fn orderbook(symbol: &str) -> String {
         format!("{symbol}@depth@100ms")
@@ -83,6 +175,10 @@ impl BinanceWs {
     pub fn trades(symbol: &str) -> String {
         format!("{symbol}@aggTrade")
     }
+    /// Liquidation orders; Binance pushes at most one per symbol per second
+    pub fn force_order(symbol: &str) -> String {
+        format!("{symbol}@forceOrder")
+    }
 
     // Combined streams example:
     // wss://fstream.binance.com/stream?streams=bnbusdt@aggTrade/btcusdt@markPrice
@@ -101,7 +197,20 @@ impl BinanceWs {
 pub struct BinanceGet;
 impl BinanceGet {
     pub const BASE: &str = "https://fapi.binance.com/fapi/v1";
+    pub const TESTNET_BASE: &str = "https://testnet.binancefuture.com/fapi/v1";
+
+    pub fn base(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::BASE,
+            Network::Testnet => Self::TESTNET_BASE,
+        }
+    }
     pub const ORDERBOOK_PATH_FMT: &str = "/depth?symbol={symbol}&limit=1000";
+    pub const SERVER_TIME: &str = "/time";
+    /// 24h rolling statistics of every symbol when called without `symbol`
+    pub const TICKER_24H: &str = "/ticker/24hr";
+    /// Lives on the spot API only and covers the whole venue; there is no testnet copy
+    pub const SYSTEM_STATUS_URL: &str = "https://api.binance.com/sapi/v1/system/status";
     pub fn orderbook(symbol: &str) -> String {
         format!("/depth?symbol={symbol}&limit=1000")
     }
@@ -111,19 +220,81 @@ impl BinanceGet {
 pub struct GateioGet;
 impl GateioGet {
     pub const BASE: &str = "https://api.gateio.ws";
+    pub const TESTNET_BASE: &str = "https://fx-api-testnet.gateio.ws";
+
+    pub fn base(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::BASE,
+            Network::Testnet => Self::TESTNET_BASE,
+        }
+    }
     pub const ORDERBOOK: &str = "/api/v4/futures/usdt/order_book";
     pub const ORDERBOOK_BTC: &str = "/api/v4/futures/btc/order_book";
     pub const FUTURES_TICKERS: &str = "/api/v4/futures/usdt/tickers";
+    pub const SERVER_TIME: &str = "/api/v4/spot/time";
     pub const GET_POSITIONS: &str = "/api/v4/futures/usdt/positions";
+    /// All contracts of the settle currency, with `create_time` for listing age
+    pub const CONTRACTS: &str = "/api/v4/futures/usdt/contracts";
     pub const SINGLE_CONTRACT_FMT: &str = "/api/v4/futures/usdt/contracts/{contract}";
     pub fn single_contract(contract: &str) -> String {
         format!("/api/v4/futures/usdt/contracts/{contract}")
     }
+    /// At most 2000 points per request; `from`/`to` are unix seconds
+    pub const CANDLESTICKS: &str = "/api/v4/futures/usdt/candlesticks";
+    pub fn candlesticks(contract: &str, interval: &str, from: i64, to: i64) -> String {
+        format!("/api/v4/futures/usdt/candlesticks?contract={contract}&interval={interval}&from={from}&to={to}")
+    }
+    /// Public futures trades, newest first; at most 1000 per request, `from`/`to` are unix seconds
+    pub const TRADES: &str = "/api/v4/futures/usdt/trades";
+    pub fn trades(contract: &str, from: i64, to: i64, limit: usize) -> String {
+        format!("/api/v4/futures/usdt/trades?contract={contract}&from={from}&to={to}&limit={limit}")
+    }
+    /// Best bid and ask only
+    pub fn order_book_top(contract: &str) -> String {
+        format!("/api/v4/futures/usdt/order_book?contract={contract}&limit=1")
+    }
+    /// Contract statistics history (open interest, liquidations); `interval` like `5m`
+    pub const CONTRACT_STATS: &str = "/api/v4/futures/usdt/contract_stats";
+    pub fn contract_stats(contract: &str, interval: &str, limit: usize) -> String {
+        format!("/api/v4/futures/usdt/contract_stats?contract={contract}&interval={interval}&limit={limit}")
+    }
+    /// Spot pair metadata: precisions, minimum sizes and trade status
+    pub const SPOT_CURRENCY_PAIRS: &str = "/api/v4/spot/currency_pairs";
+}
+
+/// Gate.io spot public stream. Unlike futures, one subscribe frame may carry many
+/// pairs in `payload`.
+pub struct GateioSpotWs;
+impl GateioSpotWs {
+    pub const BASE: &str = "wss://api.gateio.ws/ws/v4/";
+
+    pub const PING: &str = "spot.ping";
+    pub const PONG: &str = "spot.pong";
+
+    pub const BOOK_TICKER: &str = "spot.book_ticker";
+    pub const TRADES: &str = "spot.trades";
 }
 
 pub struct GateioWs;
 impl GateioWs {
     pub const BASE: &str = "wss://fx-ws.gateio.ws/v4/ws/usdt";
+    pub const TESTNET_BASE: &str = "wss://fx-ws-testnet.gateio.ws/v4/ws/usdt";
+    // Settle-less roots; the private gateway appends `/{settle}` itself
+    pub const ROOT: &str = "wss://fx-ws.gateio.ws/v4/ws";
+    pub const TESTNET_ROOT: &str = "wss://fx-ws-testnet.gateio.ws/v4/ws";
+
+    pub fn base(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::BASE,
+            Network::Testnet => Self::TESTNET_BASE,
+        }
+    }
+    pub fn root(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::ROOT,
+            Network::Testnet => Self::TESTNET_ROOT,
+        }
+    }
 
     // ping/pong
     pub const PING: &str = "futures.ping";
@@ -135,6 +306,7 @@ impl GateioWs {
     pub const TICKER: &str = "futures.tickers";
     pub const PUBLIC_TRADES: &str = "futures.trades";
     pub const CANDLESTICKS: &str = "futures.candlesticks";
+    pub const PUBLIC_LIQUIDATES: &str = "futures.public_liquidates";
 
     // private channels (for future use)
     pub const USER_ORDERS: &str = "futures.autoorders";
@@ -146,6 +318,76 @@ impl GateioWs {
     pub const CREATE_BATCH_ORDER: &str = "futures.order_batch_place";
     pub const LOGIN: &str = "futures.login";
     pub const CANCEL_BATCH_ORDER_IDS: &str = "futures.order_cancel_ids";
+    pub const ORDER_STATUS: &str = "futures.order_status";
+    pub const ORDER_AMEND: &str = "futures.order_amend";
+}
+
+// ---------------- Hyperliquid ----------------
+pub struct HyperliquidApi;
+impl HyperliquidApi {
+    pub const BASE: &str = "https://api.hyperliquid.xyz";
+    pub const TESTNET_BASE: &str = "https://api.hyperliquid-testnet.xyz";
+    pub const WS: &str = "wss://api.hyperliquid.xyz/ws";
+    pub const TESTNET_WS: &str = "wss://api.hyperliquid-testnet.xyz/ws";
+
+    /// POST; unsigned queries (`{"type":"meta"}`, ...)
+    pub const INFO: &str = "/info";
+    /// POST; signed actions (order, cancel)
+    pub const EXCHANGE: &str = "/exchange";
+
+    pub fn base(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::BASE,
+            Network::Testnet => Self::TESTNET_BASE,
+        }
+    }
+    pub fn ws(network: Network) -> &'static str {
+        match network {
+            Network::Mainnet => Self::WS,
+            Network::Testnet => Self::TESTNET_WS,
+        }
+    }
+}
+
+// ---------------- KuCoin ----------------
+pub struct KucoinGet;
+impl KucoinGet {
+    pub const BASE: &str = "https://api.kucoin.com";
+
+    /// POST; returns a token and the socket endpoint. Tokens only gate the
+    /// connect, so a fresh one is needed for every reconnect.
+    pub const BULLET_PUBLIC: &str = "/api/v1/bullet-public";
+    pub const BULLET_PRIVATE: &str = "/api/v1/bullet-private";
+}
+
+pub struct KucoinWs;
+impl KucoinWs {
+    pub const MATCH: &str = "/market/match";
+    pub const TICKER: &str = "/market/ticker";
+    /// Symbols per subscribe topic
+    pub const MAX_TOPIC_SYMBOLS: usize = 100;
+
+    pub fn subscribe(id: u64, topic: &str, symbols: &[&str]) -> String {
+        format!(
+            r#"{{"id":"{id}","type":"subscribe","topic":"{topic}:{}","privateChannel":false,"response":true}}"#,
+            symbols.join(",")
+        )
+    }
+}
+
+// ---------------- MEXC ----------------
+pub struct MexcGet;
+impl MexcGet {
+    pub const BASE: &str = "https://api.mexc.com";
+
+    pub const SERVER_TIME: &str = "/api/v3/time";
+    pub const EXCHANGE_INFO: &str = "/api/v3/exchangeInfo";
+    /// Signed; query by `symbol` + `origClientOrderId` or `orderId`
+    pub const ORDER: &str = "/api/v3/order";
+    /// Website calendar of upcoming listings; not part of the documented API
+    pub const NEW_COIN_CALENDAR: &str = "https://www.mexc.com/api/operation/new_coin_calendar";
+
+    pub const API_KEY_HEADER: &str = "X-MEXC-APIKEY";
 }
 
 // ---------------- OKX ----------------
@@ -8,6 +8,24 @@ pub struct GateContractMeta {
     pub rounding_precision: Option<f64>,
     pub order_price_round: Option<f64>,
     pub in_delisting: Option<bool>,
+    /// `trading`, `delisting` or `delisted`
+    pub status: Option<String>,
+    /// Delisting time announced for the contract, unix seconds
+    pub delisting_time: Option<u64>,
+}
+
+/// Parses a single `/futures/usdt/contracts/{contract}` object
+pub fn parse_contract_meta(value: &serde_json::Value) -> GateContractMeta {
+    GateContractMeta {
+        quanto_multiplier: get_f64(value, "quanto_multiplier"),
+        min_order_size: get_f64(value, "order_size_min"),
+        funding_interval: get_u64(value, "funding_interval"),
+        rounding_precision: get_f64(value, "order_price_round"),
+        order_price_round: get_f64(value, "order_price_round"),
+        in_delisting: get_bool(value, "in_delisting"),
+        status: value.get("status").and_then(|v| v.as_str()).map(str::to_string),
+        delisting_time: get_u64(value, "delisting_time").filter(|&t| t > 0),
+    }
 }
 
 pub fn fetch_contract_meta(contract: &str) -> Option<GateContractMeta> {
@@ -25,15 +43,7 @@ pub fn fetch_contract_meta(contract: &str) -> Option<GateContractMeta> {
             return None;
         }
         let value: serde_json::Value = resp.json().await.ok()?;
-
-        Some(GateContractMeta {
-            quanto_multiplier: get_f64(&value, "quanto_multiplier"),
-            min_order_size: get_f64(&value, "order_size_min"),
-            funding_interval: get_u64(&value, "funding_interval"),
-            rounding_precision: get_f64(&value, "order_price_round"),
-            order_price_round: get_f64(&value, "order_price_round"),
-            in_delisting: get_bool(&value, "in_delisting"),
-        })
+        Some(parse_contract_meta(&value))
     })
 }
 
@@ -51,15 +61,315 @@ pub async fn fetch_contract_meta_async(contract: &str) -> Option<GateContractMet
         return None;
     }
     let value: serde_json::Value = resp.json().await.ok()?;
+    Some(parse_contract_meta(&value))
+}
 
-    Some(GateContractMeta {
-        quanto_multiplier: get_f64(&value, "quanto_multiplier"),
-        min_order_size: get_f64(&value, "order_size_min"),
-        funding_interval: get_u64(&value, "funding_interval"),
-        rounding_precision: get_f64(&value, "order_price_round"),
-        order_price_round: get_f64(&value, "order_price_round"),
-        in_delisting: get_bool(&value, "in_delisting"),
-    })
+/// One futures candlestick; `t` is the bucket open time in unix seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateCandle {
+    pub t: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Size in contracts
+    pub volume: f64,
+}
+
+/// Parses a `/futures/usdt/candlesticks` response. A malformed candle is an error:
+/// silently skipping it would leave a hole in warm-start history.
+pub fn parse_candles(value: &serde_json::Value) -> Result<Vec<GateCandle>, String> {
+    let rows = value.as_array().ok_or_else(|| format!("candlesticks response is not an array: {}", value))?;
+    rows.iter()
+        .map(|row| {
+            let field = |key: &str| get_f64(row, key).ok_or_else(|| format!("candle without `{}`: {}", key, row));
+            Ok(GateCandle {
+                t: row.get("t").and_then(|t| t.as_i64()).ok_or_else(|| format!("candle without `t`: {}", row))?,
+                open: field("o")?,
+                high: field("h")?,
+                low: field("l")?,
+                close: field("c")?,
+                volume: get_f64(row, "v").unwrap_or(0.0),
+            })
+        })
+        .collect()
+}
+
+/// 24h statistics of one contract from `/futures/usdt/tickers`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateTicker {
+    pub contract: String,
+    pub last: f64,
+    pub high_24h: f64,
+    pub low_24h: f64,
+    /// 24h turnover in the quote currency
+    pub volume_24h_quote: f64,
+    pub highest_bid: Option<f64>,
+    pub lowest_ask: Option<f64>,
+    /// 24h price change, percent
+    pub change_pct_24h: Option<f64>,
+}
+
+/// Parses a `/futures/usdt/tickers` response. Tickers without a last price or 24h range
+/// (fresh or suspended contracts) are skipped; a non-array body is an error.
+pub fn parse_tickers(value: &serde_json::Value) -> Result<Vec<GateTicker>, String> {
+    let rows = value.as_array().ok_or_else(|| format!("tickers response is not an array: {}", value))?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(GateTicker {
+                contract: row.get("contract")?.as_str()?.to_string(),
+                last: get_f64(row, "last").filter(|p| *p > 0.0)?,
+                high_24h: get_f64(row, "high_24h")?,
+                low_24h: get_f64(row, "low_24h")?,
+                volume_24h_quote: get_f64(row, "volume_24h_quote").unwrap_or(0.0),
+                highest_bid: get_f64(row, "highest_bid").filter(|p| *p > 0.0),
+                lowest_ask: get_f64(row, "lowest_ask").filter(|p| *p > 0.0),
+                change_pct_24h: get_f64(row, "change_percentage"),
+            })
+        })
+        .collect())
+}
+
+/// 24h statistics of every contract
+#[cfg(feature = "gate_exec")]
+pub async fn fetch_tickers(client: &reqwest::Client, base: &str) -> anyhow::Result<Vec<GateTicker>> {
+    use anyhow::Context;
+
+    let url = format!("{}{}", base, GateioGet::FUTURES_TICKERS);
+    let resp = client.get(&url).send().await.with_context(|| format!("GET {}", url))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("GET {} failed with {}: {}", url, status, body);
+    }
+    let value: serde_json::Value = resp.json().await.with_context(|| format!("decode {}", url))?;
+    parse_tickers(&value).map_err(anyhow::Error::msg)
+}
+
+/// `(contract, create_time)` pairs from a `/futures/usdt/contracts` response, unix seconds
+pub fn parse_contract_ages(value: &serde_json::Value) -> Result<Vec<(String, u64)>, String> {
+    let rows = value.as_array().ok_or_else(|| format!("contracts response is not an array: {}", value))?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let name = row.get("name")?.as_str()?;
+            Some((name.to_string(), get_u64(row, "create_time").filter(|&t| t > 0)?))
+        })
+        .collect())
+}
+
+/// `(contract, meta)` pairs from a `/futures/usdt/contracts` response
+pub fn parse_contracts(value: &serde_json::Value) -> Result<Vec<(String, GateContractMeta)>, String> {
+    let rows = value.as_array().ok_or_else(|| format!("contracts response is not an array: {}", value))?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| Some((row.get("name")?.as_str()?.to_string(), parse_contract_meta(row))))
+        .collect())
+}
+
+/// Metadata of every contract in one request, including delisting status
+#[cfg(feature = "gate_exec")]
+pub async fn fetch_contracts(client: &reqwest::Client, base: &str) -> anyhow::Result<Vec<(String, GateContractMeta)>> {
+    use anyhow::Context;
+
+    let url = format!("{}{}", base, GateioGet::CONTRACTS);
+    let resp = client.get(&url).send().await.with_context(|| format!("GET {}", url))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("GET {} failed with {}: {}", url, status, body);
+    }
+    let value: serde_json::Value = resp.json().await.with_context(|| format!("decode {}", url))?;
+    parse_contracts(&value).map_err(anyhow::Error::msg)
+}
+
+/// Candlesticks with `from <= t <= to` (unix seconds), oldest first
+#[cfg(feature = "gate_exec")]
+pub async fn fetch_candles(
+    client: &reqwest::Client,
+    base: &str,
+    contract: &str,
+    interval: &str,
+    from: i64,
+    to: i64,
+) -> anyhow::Result<Vec<GateCandle>> {
+    use anyhow::Context;
+
+    let url = format!("{}{}", base, GateioGet::candlesticks(contract, interval, from, to));
+    let resp = client.get(&url).send().await.with_context(|| format!("GET {}", url))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("GET {} failed with {}: {}", url, status, body);
+    }
+    let value: serde_json::Value = resp.json().await.with_context(|| format!("decode {}", url))?;
+    let mut candles = parse_candles(&value).map_err(anyhow::Error::msg)?;
+    candles.sort_by_key(|c| c.t);
+    Ok(candles)
+}
+
+/// One public futures trade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateTrade {
+    pub id: u64,
+    pub time_ms: i64,
+    pub price: f64,
+    /// Contracts; negative for a taker sell
+    pub size: f64,
+}
+
+/// Parses a `/futures/usdt/trades` response. A malformed trade is an error, like a
+/// malformed candle: a downloaded history with holes would skew every backtest on it.
+pub fn parse_trades(value: &serde_json::Value) -> Result<Vec<GateTrade>, String> {
+    let rows = value.as_array().ok_or_else(|| format!("trades response is not an array: {}", value))?;
+    rows.iter()
+        .map(|row| {
+            let time_ms = get_f64(row, "create_time_ms")
+                .or_else(|| get_f64(row, "create_time").map(|secs| secs * 1_000.0))
+                .ok_or_else(|| format!("trade without `create_time`: {}", row))?;
+            Ok(GateTrade {
+                id: get_u64(row, "id").ok_or_else(|| format!("trade without `id`: {}", row))?,
+                time_ms: time_ms as i64,
+                price: get_f64(row, "price").ok_or_else(|| format!("trade without `price`: {}", row))?,
+                size: get_f64(row, "size").ok_or_else(|| format!("trade without `size`: {}", row))?,
+            })
+        })
+        .collect()
+}
+
+/// Trades with `from <= t < to` (unix seconds), oldest first. The endpoint caps a page at
+/// 1000 trades, so a window that comes back full is split in half until it fits.
+#[cfg(feature = "gate_exec")]
+pub async fn fetch_trades(
+    client: &reqwest::Client,
+    base: &str,
+    contract: &str,
+    from: i64,
+    to: i64,
+) -> anyhow::Result<Vec<GateTrade>> {
+    use anyhow::Context;
+
+    const PAGE: usize = 1000;
+    let mut trades = Vec::new();
+    let mut windows = vec![(from, to)];
+    while let Some((start, end)) = windows.pop() {
+        if start >= end {
+            continue;
+        }
+        // `to` is inclusive at the endpoint; windows are half-open here
+        let url = format!("{}{}", base, GateioGet::trades(contract, start, end - 1, PAGE));
+        let resp = client.get(&url).send().await.with_context(|| format!("GET {}", url))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            anyhow::bail!("GET {} failed with {}: {}", url, status, body);
+        }
+        let value: serde_json::Value = resp.json().await.with_context(|| format!("decode {}", url))?;
+        let page = parse_trades(&value).map_err(anyhow::Error::msg)?;
+        if page.len() >= PAGE {
+            if end - start <= 1 {
+                anyhow::bail!("{} has more than {} trades in the second {}; cannot page it", contract, PAGE, start);
+            }
+            let mid = start + (end - start) / 2;
+            windows.push((mid, end));
+            windows.push((start, mid));
+            continue;
+        }
+        trades.extend(page);
+    }
+    trades.sort_by_key(|t| (t.time_ms, t.id));
+    trades.dedup_by_key(|t| t.id);
+    Ok(trades)
+}
+
+/// Top of a `/futures/usdt/order_book` response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateBookTop {
+    pub bid: f64,
+    pub ask: f64,
+}
+
+/// Parses the best levels of an order book response. An empty side is an error: a
+/// quote without one side is not a price anyone can trade at.
+pub fn parse_book_top(value: &serde_json::Value) -> Result<GateBookTop, String> {
+    let best = |side: &str| {
+        value
+            .get(side)
+            .and_then(|levels| levels.as_array())
+            .and_then(|levels| levels.first())
+            .and_then(|level| get_f64(level, "p"))
+            .filter(|price| *price > 0.0)
+            .ok_or_else(|| format!("order book without {}: {}", side, value))
+    };
+    Ok(GateBookTop { bid: best("bids")?, ask: best("asks")? })
+}
+
+#[cfg(feature = "gate_exec")]
+pub async fn fetch_book_top(client: &reqwest::Client, base: &str, contract: &str) -> anyhow::Result<GateBookTop> {
+    use anyhow::Context;
+
+    let url = format!("{}{}", base, GateioGet::order_book_top(contract));
+    let resp = client.get(&url).send().await.with_context(|| format!("GET {}", url))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("GET {} failed with {}: {}", url, status, body);
+    }
+    let value: serde_json::Value = resp.json().await.with_context(|| format!("decode {}", url))?;
+    parse_book_top(&value).map_err(anyhow::Error::msg)
+}
+
+/// One `/futures/usdt/contract_stats` point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateContractStat {
+    /// Unix seconds
+    pub time: i64,
+    /// Open interest in contracts
+    pub open_interest: f64,
+    pub open_interest_usd: f64,
+    pub mark_price: Option<f64>,
+}
+
+/// Parses a `/futures/usdt/contract_stats` response. A point without open interest is an
+/// error: the cascade guard compares consecutive points and a gap would hide a drop.
+pub fn parse_contract_stats(value: &serde_json::Value) -> Result<Vec<GateContractStat>, String> {
+    let rows = value.as_array().ok_or_else(|| format!("contract_stats response is not an array: {}", value))?;
+    rows.iter()
+        .map(|row| {
+            Ok(GateContractStat {
+                time: row.get("time").and_then(|t| t.as_i64()).ok_or_else(|| format!("stat without `time`: {}", row))?,
+                open_interest: get_f64(row, "open_interest")
+                    .ok_or_else(|| format!("stat without `open_interest`: {}", row))?,
+                open_interest_usd: get_f64(row, "open_interest_usd").unwrap_or(0.0),
+                mark_price: get_f64(row, "mark_price").filter(|p| *p > 0.0),
+            })
+        })
+        .collect()
+}
+
+/// Latest `limit` contract statistics points, oldest first
+#[cfg(feature = "gate_exec")]
+pub async fn fetch_contract_stats(
+    client: &reqwest::Client,
+    base: &str,
+    contract: &str,
+    interval: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<GateContractStat>> {
+    use anyhow::Context;
+
+    let url = format!("{}{}", base, GateioGet::contract_stats(contract, interval, limit));
+    let resp = client.get(&url).send().await.with_context(|| format!("GET {}", url))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("GET {} failed with {}: {}", url, status, body);
+    }
+    let value: serde_json::Value = resp.json().await.with_context(|| format!("decode {}", url))?;
+    let mut stats = parse_contract_stats(&value).map_err(anyhow::Error::msg)?;
+    stats.sort_by_key(|s| s.time);
+    Ok(stats)
 }
 
 fn get_f64(value: &serde_json::Value, key: &str) -> Option<f64> {
@@ -94,3 +404,77 @@ fn get_bool(value: &serde_json::Value, key: &str) -> Option<bool> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_candles() {
+        let value = serde_json::json!([
+            {"t": 1700000000, "v": 120, "c": "100.5", "h": "101", "l": "99.5", "o": "100", "sum": "12050"},
+            {"t": 1700000060, "v": 80, "c": "100.2", "h": "100.6", "l": "100.1", "o": "100.5", "sum": "8030"}
+        ]);
+        let candles = parse_candles(&value).unwrap();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0], GateCandle { t: 1700000000, open: 100.0, high: 101.0, low: 99.5, close: 100.5, volume: 120.0 });
+        assert!(parse_candles(&serde_json::json!([{"t": 1, "o": "1"}])).is_err());
+        assert!(parse_candles(&serde_json::json!({"label": "INVALID_PARAM_VALUE"})).is_err());
+    }
+
+    #[test]
+    fn test_parse_tickers_and_contract_ages() {
+        let value = serde_json::json!([
+            {"contract": "BTC_USDT", "last": "65000", "high_24h": "66000", "low_24h": "64000",
+             "volume_24h_quote": "1200000000", "highest_bid": "64999.9", "lowest_ask": "65000", "change_percentage": "-1.25"},
+            {"contract": "NEW_USDT", "last": "", "high_24h": "0", "low_24h": "0"}
+        ]);
+        let tickers = parse_tickers(&value).unwrap();
+        assert_eq!(tickers.len(), 1);
+        assert_eq!((tickers[0].volume_24h_quote, tickers[0].lowest_ask), (1.2e9, Some(65000.0)));
+        assert_eq!(tickers[0].change_pct_24h, Some(-1.25));
+        assert!(parse_tickers(&serde_json::json!({"label": "X"})).is_err());
+
+        let ages = parse_contract_ages(&serde_json::json!([{"name": "BTC_USDT", "create_time": 1600000000}, {"name": "X_USDT"}]));
+        assert_eq!(ages.unwrap(), [("BTC_USDT".to_string(), 1600000000)]);
+
+        let contracts = parse_contracts(&serde_json::json!([
+            {"name": "BTC_USDT", "status": "trading", "in_delisting": false},
+            {"name": "LUNA_USDT", "status": "delisting", "in_delisting": true, "delisting_time": 1700000000}
+        ]))
+        .unwrap();
+        assert_eq!(contracts[1].0, "LUNA_USDT");
+        assert_eq!((contracts[1].1.in_delisting, contracts[1].1.delisting_time), (Some(true), Some(1700000000)));
+    }
+
+    #[test]
+    fn test_parse_trades() {
+        let value = serde_json::json!([
+            {"id": 2, "create_time": 1700000001.5, "create_time_ms": 1700000001500.0, "contract": "BTC_USDT", "size": -3, "price": "65000.1"},
+            {"id": 1, "create_time": 1700000000.25, "contract": "BTC_USDT", "size": 5, "price": "65000"}
+        ]);
+        let trades = parse_trades(&value).unwrap();
+        assert_eq!(trades[0], GateTrade { id: 2, time_ms: 1700000001500, price: 65000.1, size: -3.0 });
+        assert_eq!(trades[1].time_ms, 1700000000250);
+        assert!(parse_trades(&serde_json::json!([{"id": 3, "create_time": 1}])).is_err());
+    }
+
+    #[test]
+    fn test_parse_book_top() {
+        let value = serde_json::json!({"current": 1700000000.1, "asks": [{"p": "65000.2", "s": 10}], "bids": [{"p": "65000.1", "s": 4}]});
+        assert_eq!(parse_book_top(&value).unwrap(), GateBookTop { bid: 65000.1, ask: 65000.2 });
+        assert!(parse_book_top(&serde_json::json!({"asks": [], "bids": [{"p": "1", "s": 1}]})).is_err());
+    }
+
+    #[test]
+    fn test_parse_contract_stats() {
+        let value = serde_json::json!([
+            {"time": 1700000300, "open_interest": 120500, "open_interest_usd": 7832500.5, "mark_price": "65.0", "long_liq_usd": 0},
+            {"time": 1700000000, "open_interest": "121000", "open_interest_usd": "7865000"}
+        ]);
+        let stats = parse_contract_stats(&value).unwrap();
+        assert_eq!(stats[0], GateContractStat { time: 1700000300, open_interest: 120500.0, open_interest_usd: 7832500.5, mark_price: Some(65.0) });
+        assert_eq!((stats[1].open_interest, stats[1].mark_price), (121000.0, None));
+        assert!(parse_contract_stats(&serde_json::json!([{"time": 1}])).is_err());
+    }
+}
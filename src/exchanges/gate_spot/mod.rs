@@ -0,0 +1,16 @@
+//! Gate.io spot integration
+//!
+//! Gate lists many small altcoins that have no Binance or futures market, so spot
+//! is the only venue for them. This module provides the multi-pair public stream
+//! parser, pair metadata from REST, the precision/minimum-size normalizer and a
+//! rate limiter that follows Gate's per-endpoint limits and response headers.
+
+pub mod normalizer;
+pub mod parser;
+pub mod rate_limit;
+pub mod rest;
+
+pub use normalizer::*;
+pub use parser::*;
+pub use rate_limit::*;
+pub use rest::*;
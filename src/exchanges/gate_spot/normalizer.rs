@@ -0,0 +1,266 @@
+//! Pair precision and minimum-size rules for Gate.io spot orders
+//!
+//! Gate rejects spot orders whose price or amount carries more decimals than the
+//! pair allows (`INVALID_PRECISION`), whose amount is under `min_base_amount`, or
+//! whose notional is under `min_quote_amount` (usually 1-3 USDT). Micro-cap pairs
+//! quote with 8-12 price decimals, so values are rounded on a scaled integer grid
+//! and formatted as fixed-point strings, never through `f64` display.
+
+use crate::backtest::market::TradeSide;
+use crate::base_classes::symbol::Symbol;
+use std::collections::HashMap;
+use std::fmt;
+
+/// `trade_status` of a currency pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpotTradeStatus {
+    Tradable,
+    /// Only buy orders are accepted (e.g. right after listing)
+    Buyable,
+    /// Only sell orders are accepted (e.g. ahead of delisting)
+    Sellable,
+    Untradable,
+}
+
+impl SpotTradeStatus {
+    pub fn parse(raw: &str) -> Self {
+        match raw {
+            "tradable" => SpotTradeStatus::Tradable,
+            "buyable" => SpotTradeStatus::Buyable,
+            "sellable" => SpotTradeStatus::Sellable,
+            _ => SpotTradeStatus::Untradable,
+        }
+    }
+
+    pub fn allows(self, side: TradeSide) -> bool {
+        matches!(
+            (self, side),
+            (SpotTradeStatus::Tradable, _) | (SpotTradeStatus::Buyable, TradeSide::Buy) | (SpotTradeStatus::Sellable, TradeSide::Sell)
+        )
+    }
+}
+
+/// One entry of `GET /spot/currency_pairs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotPairMeta {
+    pub symbol: Symbol,
+    pub base: String,
+    pub quote: String,
+    /// Price decimals (`precision`)
+    pub price_decimals: u32,
+    /// Amount decimals (`amount_precision`)
+    pub amount_decimals: u32,
+    pub min_base_amount: f64,
+    pub min_quote_amount: f64,
+    /// Absent for most pairs
+    pub max_quote_amount: Option<f64>,
+    pub status: SpotTradeStatus,
+}
+
+impl SpotPairMeta {
+    pub fn is_leveraged_token(&self) -> bool {
+        is_leveraged_token(&self.base)
+    }
+}
+
+/// Gate ETF tokens (`BTC3L`, `ETH5S`, ...) rebalance daily and decay; they look
+/// like illiquid altcoins to a scanner but are not spot assets.
+pub fn is_leveraged_token(base: &str) -> bool {
+    let base = base.to_ascii_uppercase();
+    ["3L", "3S", "5L", "5S"].iter().any(|suffix| base.len() > suffix.len() && base.ends_with(suffix))
+}
+
+/// Order values that Gate will accept as-is
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotOrderSize {
+    pub price: f64,
+    pub amount: f64,
+    /// Fixed-point strings for the request body
+    pub price_str: String,
+    pub amount_str: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpotNormalizeError {
+    UnknownPair(Symbol),
+    NotTradable { symbol: Symbol, status: SpotTradeStatus, side: TradeSide },
+    BadInput { symbol: Symbol, price: f64, amount: f64 },
+    BelowMinAmount { symbol: Symbol, amount: f64, min: f64 },
+    BelowMinNotional { symbol: Symbol, notional: f64, min: f64 },
+    AboveMaxNotional { symbol: Symbol, notional: f64, max: f64 },
+}
+
+impl fmt::Display for SpotNormalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpotNormalizeError::UnknownPair(symbol) => write!(f, "{}: no gate spot pair metadata", symbol),
+            SpotNormalizeError::NotTradable { symbol, status, side } => {
+                write!(f, "{}: {:?} orders rejected, pair is {:?}", symbol, side, status)
+            }
+            SpotNormalizeError::BadInput { symbol, price, amount } => {
+                write!(f, "{}: invalid price {} or amount {}", symbol, price, amount)
+            }
+            SpotNormalizeError::BelowMinAmount { symbol, amount, min } => {
+                write!(f, "{}: amount {} below min_base_amount {}", symbol, amount, min)
+            }
+            SpotNormalizeError::BelowMinNotional { symbol, notional, min } => {
+                write!(f, "{}: notional {} below min_quote_amount {}", symbol, notional, min)
+            }
+            SpotNormalizeError::AboveMaxNotional { symbol, notional, max } => {
+                write!(f, "{}: notional {} above max_quote_amount {}", symbol, notional, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpotNormalizeError {}
+
+/// Round `value` to `decimals` on an integer grid. Values that are already on the
+/// grid up to float noise (`0.1 + 0.2`) are kept instead of floored a step down.
+fn to_grid(value: f64, decimals: u32, round_up: bool) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    let scaled = value * scale;
+    let nearest = scaled.round();
+    let steps = if (scaled - nearest).abs() <= 1e-9 * nearest.abs().max(1.0) {
+        nearest
+    } else if round_up {
+        scaled.ceil()
+    } else {
+        scaled.floor()
+    };
+    steps / scale
+}
+
+pub fn format_fixed(value: f64, decimals: u32) -> String {
+    format!("{:.*}", decimals as usize, value)
+}
+
+/// Pair metadata by symbol and order normalization against it
+#[derive(Debug, Clone, Default)]
+pub struct SpotNormalizer {
+    pairs: HashMap<Symbol, SpotPairMeta>,
+}
+
+impl SpotNormalizer {
+    pub fn new(pairs: impl IntoIterator<Item = SpotPairMeta>) -> Self {
+        Self { pairs: pairs.into_iter().map(|p| (p.symbol, p)).collect() }
+    }
+
+    pub fn get(&self, symbol: Symbol) -> Option<&SpotPairMeta> {
+        self.pairs.get(&symbol)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Fully tradable pairs in `quote`, leveraged tokens excluded, sorted by symbol
+    pub fn universe(&self, quote: &str) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = self
+            .pairs
+            .values()
+            .filter(|p| p.quote.eq_ignore_ascii_case(quote))
+            .filter(|p| p.status == SpotTradeStatus::Tradable && !p.is_leveraged_token())
+            .map(|p| p.symbol)
+            .collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Price is rounded away from the market (buy down, sell up) so rounding never
+    /// makes the order more aggressive than asked; amount is always rounded down.
+    pub fn normalize(&self, symbol: Symbol, side: TradeSide, price: f64, amount: f64) -> Result<SpotOrderSize, SpotNormalizeError> {
+        let meta = self.get(symbol).ok_or(SpotNormalizeError::UnknownPair(symbol))?;
+        if !meta.status.allows(side) {
+            return Err(SpotNormalizeError::NotTradable { symbol, status: meta.status, side });
+        }
+        if !(price.is_finite() && price > 0.0 && amount.is_finite() && amount > 0.0) {
+            return Err(SpotNormalizeError::BadInput { symbol, price, amount });
+        }
+
+        let price = to_grid(price, meta.price_decimals, side == TradeSide::Sell);
+        let amount = to_grid(amount, meta.amount_decimals, false);
+        if price <= 0.0 {
+            return Err(SpotNormalizeError::BadInput { symbol, price, amount });
+        }
+        if amount < meta.min_base_amount || amount <= 0.0 {
+            return Err(SpotNormalizeError::BelowMinAmount { symbol, amount, min: meta.min_base_amount });
+        }
+        let notional = price * amount;
+        if notional < meta.min_quote_amount {
+            return Err(SpotNormalizeError::BelowMinNotional { symbol, notional, min: meta.min_quote_amount });
+        }
+        if let Some(max) = meta.max_quote_amount.filter(|&max| notional > max) {
+            return Err(SpotNormalizeError::AboveMaxNotional { symbol, notional, max });
+        }
+        Ok(SpotOrderSize {
+            price,
+            amount,
+            price_str: format_fixed(price, meta.price_decimals),
+            amount_str: format_fixed(amount, meta.amount_decimals),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(name: &str, price_decimals: u32, amount_decimals: u32, status: SpotTradeStatus) -> SpotPairMeta {
+        let (base, quote) = name.split_once('_').unwrap();
+        SpotPairMeta {
+            symbol: Symbol::new(name),
+            base: base.to_string(),
+            quote: quote.to_string(),
+            price_decimals,
+            amount_decimals,
+            min_base_amount: 1.0,
+            min_quote_amount: 3.0,
+            max_quote_amount: Some(50_000.0),
+            status,
+        }
+    }
+
+    #[test]
+    fn test_normalize_rounds_to_pair_precision() {
+        let normalizer = SpotNormalizer::new([pair("PEPE2_USDT", 10, 0, SpotTradeStatus::Tradable)]);
+        let symbol = Symbol::new("PEPE2_USDT");
+
+        let buy = normalizer.normalize(symbol, TradeSide::Buy, 0.000_001_234_567_89, 3_000_000.7).unwrap();
+        assert_eq!(buy.price_str, "0.0000012345");
+        assert_eq!(buy.amount_str, "3000000");
+        let sell = normalizer.normalize(symbol, TradeSide::Sell, 0.000_001_234_567_89, 3_000_000.0).unwrap();
+        assert_eq!(sell.price_str, "0.0000012346");
+
+        // On-grid values survive float noise instead of dropping a step
+        assert_eq!(to_grid(0.1 + 0.2, 1, false), 0.3);
+
+        let err = normalizer.normalize(symbol, TradeSide::Buy, 0.000_001, 1_000.0).unwrap_err();
+        assert!(matches!(err, SpotNormalizeError::BelowMinNotional { .. }), "{}", err);
+        let err = normalizer.normalize(symbol, TradeSide::Buy, 1.0, 0.4).unwrap_err();
+        assert!(matches!(err, SpotNormalizeError::BelowMinAmount { .. }), "{}", err);
+        let err = normalizer.normalize(symbol, TradeSide::Buy, 0.01, 6_000_000.0).unwrap_err();
+        assert!(matches!(err, SpotNormalizeError::AboveMaxNotional { .. }), "{}", err);
+        assert!(normalizer.normalize(Symbol::new("NOPE_USDT"), TradeSide::Buy, 1.0, 10.0).is_err());
+    }
+
+    #[test]
+    fn test_trade_status_and_universe() {
+        let normalizer = SpotNormalizer::new([
+            pair("ALT_USDT", 6, 2, SpotTradeStatus::Tradable),
+            pair("NEW_USDT", 6, 2, SpotTradeStatus::Buyable),
+            pair("OLD_USDT", 6, 2, SpotTradeStatus::Sellable),
+            pair("BTC3L_USDT", 6, 2, SpotTradeStatus::Tradable),
+            pair("ALT_BTC", 8, 2, SpotTradeStatus::Tradable),
+        ]);
+        assert!(normalizer.normalize(Symbol::new("NEW_USDT"), TradeSide::Buy, 1.0, 10.0).is_ok());
+        assert!(normalizer.normalize(Symbol::new("NEW_USDT"), TradeSide::Sell, 1.0, 10.0).is_err());
+        assert!(normalizer.normalize(Symbol::new("OLD_USDT"), TradeSide::Buy, 1.0, 10.0).is_err());
+        assert!(is_leveraged_token("eth5s") && !is_leveraged_token("5L"));
+        assert_eq!(normalizer.universe("usdt"), vec![Symbol::new("ALT_USDT")]);
+    }
+}
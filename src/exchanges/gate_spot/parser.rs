@@ -0,0 +1,247 @@
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Ts;
+use crate::base_classes::ws::{ExchangeHandler, HeartbeatPayload};
+use crate::exchanges::endpoints::GateioSpotWs;
+use chrono::DateTime;
+use serde_json::Value;
+use std::time::Instant;
+
+/// Pairs per subscribe frame; Gate accepts long payloads, but a rejected frame
+/// drops every pair in it, so keep the blast radius small.
+const SUBSCRIBE_CHUNK: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotTrade {
+    pub symbol: Symbol,
+    pub id: u64,
+    pub price: f64,
+    pub amount: f64,
+    /// Taker side
+    pub side: TradeSide,
+    pub ts_ms: u64,
+}
+
+impl SpotTrade {
+    pub fn to_tick(&self, best: Option<&SpotBookTicker>) -> TradeTick {
+        TradeTick {
+            timestamp: DateTime::from_timestamp_millis(self.ts_ms as i64).unwrap_or_default(),
+            symbol: self.symbol,
+            price: self.price,
+            volume: self.amount,
+            side: self.side,
+            trade_id: self.id.to_string(),
+            best_bid: best.map(|b| b.bid),
+            best_ask: best.map(|b| b.ask),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotBookTicker {
+    pub symbol: Symbol,
+    pub bid: f64,
+    pub bid_size: f64,
+    pub ask: f64,
+    pub ask_size: f64,
+    pub update_id: u64,
+    pub ts_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpotEvent {
+    Trade(SpotTrade),
+    BookTicker(SpotBookTicker),
+}
+
+impl SpotEvent {
+    pub fn symbol(&self) -> Symbol {
+        match self {
+            SpotEvent::Trade(t) => t.symbol,
+            SpotEvent::BookTicker(b) => b.symbol,
+        }
+    }
+}
+
+/// Parse one spot stream frame. Subscribe acks and pongs yield `None`; error
+/// frames (unknown pair, delisted pair) are logged loudly and yield `None`.
+pub fn parse_spot_message(text: &str) -> Option<SpotEvent> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    if let Some(error) = value.get("error").filter(|e| !e.is_null()) {
+        log::error!(
+            "gate spot {} rejected: {}",
+            value.get("channel").and_then(Value::as_str).unwrap_or("(unknown)"),
+            error
+        );
+        return None;
+    }
+    if value.get("event").and_then(Value::as_str) != Some("update") {
+        return None;
+    }
+    let result = value.get("result")?;
+    match value.get("channel").and_then(Value::as_str)? {
+        GateioSpotWs::TRADES => parse_trade(result).map(SpotEvent::Trade),
+        GateioSpotWs::BOOK_TICKER => parse_book_ticker(result).map(SpotEvent::BookTicker),
+        _ => None,
+    }
+}
+
+fn parse_trade(result: &Value) -> Option<SpotTrade> {
+    let side = match result.get("side").and_then(Value::as_str)? {
+        "buy" => TradeSide::Buy,
+        "sell" => TradeSide::Sell,
+        _ => return None,
+    };
+    // Spot sends `create_time_ms` as a string with a fractional part
+    // ("1606292218213.4578"); futures send an integer.
+    let ts_ms = get_f64(result, "create_time_ms")
+        .map(|ms| ms as u64)
+        .or_else(|| get_f64(result, "create_time").map(|s| (s * 1_000.0) as u64))?;
+    Some(SpotTrade {
+        symbol: Symbol::new(result.get("currency_pair").and_then(Value::as_str)?),
+        id: get_f64(result, "id")? as u64,
+        price: get_f64(result, "price")?,
+        amount: get_f64(result, "amount")?,
+        side,
+        ts_ms,
+    })
+}
+
+fn parse_book_ticker(result: &Value) -> Option<SpotBookTicker> {
+    Some(SpotBookTicker {
+        symbol: Symbol::new(result.get("s").and_then(Value::as_str)?),
+        bid: get_f64(result, "b")?,
+        bid_size: get_f64(result, "B")?,
+        ask: get_f64(result, "a")?,
+        ask_size: get_f64(result, "A")?,
+        update_id: get_f64(result, "u").unwrap_or(0.0) as u64,
+        ts_ms: get_f64(result, "t")? as u64,
+    })
+}
+
+fn get_f64(value: &Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+fn subscribe_frame(channel: &str, pairs: &[&str]) -> String {
+    serde_json::json!({
+        "time": now_secs(),
+        "channel": channel,
+        "event": "subscribe",
+        "payload": pairs,
+    })
+    .to_string()
+}
+
+/// Trades and book ticker for many pairs over one spot connection.
+pub struct GateSpotHandler {
+    pairs: Vec<Symbol>,
+    subs: Vec<String>,
+}
+
+impl GateSpotHandler {
+    pub fn new(pairs: &[Symbol]) -> Self {
+        let names: Vec<&str> = pairs.iter().map(|s| s.as_str()).collect();
+        let mut subs = Vec::new();
+        for chunk in names.chunks(SUBSCRIBE_CHUNK) {
+            subs.push(subscribe_frame(GateioSpotWs::BOOK_TICKER, chunk));
+            subs.push(subscribe_frame(GateioSpotWs::TRADES, chunk));
+        }
+        Self { pairs: pairs.to_vec(), subs }
+    }
+
+    pub fn pairs(&self) -> &[Symbol] {
+        &self.pairs
+    }
+}
+
+impl ExchangeHandler for GateSpotHandler {
+    type Out = SpotEvent;
+
+    fn url(&self) -> &str {
+        GateioSpotWs::BASE
+    }
+
+    fn initial_subscriptions(&self) -> &[String] {
+        &self.subs
+    }
+
+    fn parse_text(&self, text: &str, _ts: Ts, _recv_instant: Instant) -> Option<Self::Out> {
+        parse_spot_message(text)
+    }
+
+    fn parse_binary(&self, data: &[u8], _ts: Ts, _recv_instant: Instant) -> Option<Self::Out> {
+        parse_spot_message(std::str::from_utf8(data).ok()?)
+    }
+
+    fn app_heartbeat_interval(&self) -> Option<u64> {
+        Some(30)
+    }
+
+    fn build_app_heartbeat(&self) -> Option<HeartbeatPayload> {
+        Some(HeartbeatPayload::Text(format!(
+            r#"{{"time":{},"channel":"{}"}}"#,
+            now_secs(),
+            GateioSpotWs::PING
+        )))
+    }
+
+    fn label(&self) -> String {
+        format!("gate_spot:{} pairs", self.pairs.len())
+    }
+}
+
+#[inline(always)]
+fn now_secs() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spot_trade_and_book_ticker() {
+        let trade = r#"{"time":1606292218,"time_ms":1606292218231,"channel":"spot.trades","event":"update","result":{"id":309143071,"create_time":1606292218,"create_time_ms":"1606292218213.4578","side":"sell","currency_pair":"GT_USDT","amount":"16.47","price":"0.4705"}}"#;
+        let Some(SpotEvent::Trade(trade)) = parse_spot_message(trade) else {
+            panic!("trade not parsed");
+        };
+        assert_eq!(trade.symbol, Symbol::new("GT_USDT"));
+        assert_eq!(trade.ts_ms, 1_606_292_218_213);
+        assert_eq!(trade.side, TradeSide::Sell);
+        assert_eq!((trade.price, trade.amount), (0.4705, 16.47));
+
+        let bbo = r#"{"time":1606293275,"time_ms":1606293275723,"channel":"spot.book_ticker","event":"update","result":{"t":1606293275123,"u":48733182,"s":"BTC_USDT","b":"19177.79","B":"0.0003341504","a":"19179.38","A":"0.09"}}"#;
+        let Some(SpotEvent::BookTicker(bbo)) = parse_spot_message(bbo) else {
+            panic!("book ticker not parsed");
+        };
+        assert_eq!(bbo.update_id, 48_733_182);
+        let tick = trade.to_tick(Some(&bbo));
+        assert_eq!(tick.best_ask, Some(19179.38));
+        assert_eq!(tick.trade_id, "309143071");
+
+        let ack = r#"{"time":1606292218,"channel":"spot.trades","event":"subscribe","result":{"status":"success"}}"#;
+        assert!(parse_spot_message(ack).is_none());
+        let rejected = r#"{"time":1606292218,"channel":"spot.trades","event":"subscribe","error":{"code":2,"message":"unknown currency pair FOO_USDT"},"result":null}"#;
+        assert!(parse_spot_message(rejected).is_none());
+    }
+
+    #[test]
+    fn test_subscriptions_batch_pairs() {
+        let pairs: Vec<Symbol> = (0..150).map(|i| Symbol::new(&format!("ALT{}_USDT", i))).collect();
+        let handler = GateSpotHandler::new(&pairs);
+        // Two chunks, two channels each
+        assert_eq!(handler.initial_subscriptions().len(), 4);
+        let first: Value = serde_json::from_str(&handler.initial_subscriptions()[0]).unwrap();
+        assert_eq!(first["channel"], GateioSpotWs::BOOK_TICKER);
+        assert_eq!(first["payload"].as_array().unwrap().len(), SUBSCRIBE_CHUNK);
+    }
+}
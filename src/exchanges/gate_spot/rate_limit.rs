@@ -0,0 +1,164 @@
+//! Client-side rate limiting for the Gate.io spot REST API
+//!
+//! Gate limits spot endpoints separately: public market data 200 requests per
+//! 10s per endpoint, order placement 10 per second per pair, cancels 200 per
+//! second, other private calls 200 per 10s. Exceeding a limit returns 429
+//! `TOO_MANY_REQUESTS` and the window restarts, so the limiter waits locally and
+//! also trusts the `X-Gate-RateLimit-*` headers, which count requests made by
+//! other processes on the same key.
+
+use crate::base_classes::symbol::Symbol;
+use crate::utils::time::current_unix_ms;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const HEADER_REMAIN: &str = "x-gate-ratelimit-requests-remain";
+pub const HEADER_RESET: &str = "x-gate-ratelimit-reset-timestamp";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GateSpotEndpoint {
+    Public,
+    /// Limited per pair
+    PlaceOrder,
+    CancelOrder,
+    Private,
+}
+
+impl GateSpotEndpoint {
+    /// (requests, window)
+    pub fn limit(self) -> (u32, Duration) {
+        match self {
+            GateSpotEndpoint::Public => (200, Duration::from_secs(10)),
+            GateSpotEndpoint::PlaceOrder => (10, Duration::from_secs(1)),
+            GateSpotEndpoint::CancelOrder => (200, Duration::from_secs(1)),
+            GateSpotEndpoint::Private => (200, Duration::from_secs(10)),
+        }
+    }
+
+    fn key(self, pair: Option<Symbol>) -> (GateSpotEndpoint, Option<Symbol>) {
+        match self {
+            GateSpotEndpoint::PlaceOrder => (self, pair),
+            _ => (self, None),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    updated: Instant,
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(endpoint: GateSpotEndpoint, now: Instant) -> Self {
+        let (requests, window) = endpoint.limit();
+        Self {
+            capacity: requests as f64,
+            tokens: requests as f64,
+            per_sec: requests as f64 / window.as_secs_f64(),
+            updated: now,
+            blocked_until: None,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+    }
+}
+
+/// Token buckets per endpoint class (and per pair for order placement)
+#[derive(Debug, Default)]
+pub struct GateSpotRateLimiter {
+    buckets: Mutex<HashMap<(GateSpotEndpoint, Option<Symbol>), Bucket>>,
+}
+
+impl GateSpotRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a token, or return how long to wait before retrying
+    pub fn try_acquire(&self, endpoint: GateSpotEndpoint, pair: Option<Symbol>, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().expect("gate spot rate limiter poisoned");
+        let bucket = buckets.entry(endpoint.key(pair)).or_insert_with(|| Bucket::new(endpoint, now));
+        if let Some(until) = bucket.blocked_until {
+            if now < until {
+                return Err(until - now);
+            }
+            bucket.blocked_until = None;
+        }
+        bucket.refill(now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.per_sec))
+        }
+    }
+
+    pub async fn acquire(&self, endpoint: GateSpotEndpoint, pair: Option<Symbol>) {
+        while let Err(wait) = self.try_acquire(endpoint, pair, Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Sync with the server's view: never hold more tokens than `remain`, and
+    /// block until the reset when the window is used up.
+    pub fn observe(&self, endpoint: GateSpotEndpoint, pair: Option<Symbol>, remain: Option<u32>, reset_in: Option<Duration>, now: Instant) {
+        let mut buckets = self.buckets.lock().expect("gate spot rate limiter poisoned");
+        let bucket = buckets.entry(endpoint.key(pair)).or_insert_with(|| Bucket::new(endpoint, now));
+        bucket.refill(now);
+        if let Some(remain) = remain {
+            bucket.tokens = bucket.tokens.min(remain as f64);
+            if remain == 0 {
+                let wait = reset_in.unwrap_or_else(|| endpoint.limit().1);
+                bucket.blocked_until = Some(now + wait);
+            }
+        }
+    }
+
+    /// Feed a response back. A 429 blocks the class for the full window (or until
+    /// the advertised reset) and is logged loudly.
+    pub fn observe_response(&self, endpoint: GateSpotEndpoint, pair: Option<Symbol>, status: u16, headers: &reqwest::header::HeaderMap) {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.trim().parse::<u64>().ok());
+        let reset_in = header(HEADER_RESET).map(|reset_ms| Duration::from_millis(reset_ms.saturating_sub(current_unix_ms())));
+        let mut remain = header(HEADER_REMAIN).map(|r| r.min(u32::MAX as u64) as u32);
+        if status == 429 {
+            log::error!("gate spot {:?} rate limited (429){}", endpoint, pair.map(|p| format!(" on {}", p)).unwrap_or_default());
+            remain = Some(0);
+        }
+        self.observe(endpoint, pair, remain, reset_in, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_bucket_is_per_pair_and_follows_headers() {
+        let limiter = GateSpotRateLimiter::new();
+        let now = Instant::now();
+        let alt = Some(Symbol::new("ALT_USDT"));
+        for _ in 0..10 {
+            limiter.try_acquire(GateSpotEndpoint::PlaceOrder, alt, now).unwrap();
+        }
+        let wait = limiter.try_acquire(GateSpotEndpoint::PlaceOrder, alt, now).unwrap_err();
+        assert!(wait <= Duration::from_millis(100) && wait > Duration::ZERO, "{:?}", wait);
+        // Another pair has its own budget; 100ms refills one order
+        assert!(limiter.try_acquire(GateSpotEndpoint::PlaceOrder, Some(Symbol::new("MOON_USDT")), now).is_ok());
+        assert!(limiter.try_acquire(GateSpotEndpoint::PlaceOrder, alt, now + Duration::from_millis(100)).is_ok());
+
+        // Server says the public window is exhausted by someone else
+        limiter.observe(GateSpotEndpoint::Public, None, Some(0), Some(Duration::from_secs(3)), now);
+        let wait = limiter.try_acquire(GateSpotEndpoint::Public, None, now + Duration::from_secs(1)).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(2));
+        assert!(limiter.try_acquire(GateSpotEndpoint::Public, None, now + Duration::from_secs(3)).is_ok());
+    }
+}
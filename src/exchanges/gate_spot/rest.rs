@@ -0,0 +1,86 @@
+use super::normalizer::{SpotPairMeta, SpotTradeStatus};
+use super::rate_limit::{GateSpotEndpoint, GateSpotRateLimiter};
+use crate::base_classes::symbol::Symbol;
+use crate::exchanges::endpoints::GateioGet;
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+/// Parse the `currency_pairs` array. Entries missing precision fields are
+/// skipped with a warning rather than guessed: a wrong precision means every
+/// order on that pair is rejected.
+pub fn parse_currency_pairs(value: &Value) -> Result<Vec<SpotPairMeta>, String> {
+    let items = value.as_array().ok_or_else(|| "expected an array of currency pairs".to_string())?;
+    let mut pairs = Vec::with_capacity(items.len());
+    for item in items {
+        let Some(id) = item.get("id").and_then(Value::as_str) else {
+            continue;
+        };
+        let (Some(price_decimals), Some(amount_decimals)) = (get_u32(item, "precision"), get_u32(item, "amount_precision")) else {
+            log::warn!("gate spot pair {} has no precision, skipped", id);
+            continue;
+        };
+        let (base, quote) = id.split_once('_').unwrap_or((id, ""));
+        pairs.push(SpotPairMeta {
+            symbol: Symbol::new(id),
+            base: item.get("base").and_then(Value::as_str).unwrap_or(base).to_string(),
+            quote: item.get("quote").and_then(Value::as_str).unwrap_or(quote).to_string(),
+            price_decimals,
+            amount_decimals,
+            min_base_amount: get_f64(item, "min_base_amount").unwrap_or(0.0),
+            min_quote_amount: get_f64(item, "min_quote_amount").unwrap_or(0.0),
+            max_quote_amount: get_f64(item, "max_quote_amount").filter(|&max| max > 0.0),
+            status: SpotTradeStatus::parse(item.get("trade_status").and_then(Value::as_str).unwrap_or("")),
+        });
+    }
+    Ok(pairs)
+}
+
+/// All spot pairs, including untradable ones
+pub async fn fetch_currency_pairs(client: &reqwest::Client, base: &str, limiter: &GateSpotRateLimiter) -> Result<Vec<SpotPairMeta>> {
+    limiter.acquire(GateSpotEndpoint::Public, None).await;
+    let url = format!("{}{}", base, GateioGet::SPOT_CURRENCY_PAIRS);
+    let resp = client.get(&url).send().await.with_context(|| format!("GET {}", url))?;
+    let status = resp.status();
+    limiter.observe_response(GateSpotEndpoint::Public, None, status.as_u16(), resp.headers());
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        bail!("GET {} failed with {}: {}", url, status, body);
+    }
+    let value: Value = resp.json().await.with_context(|| format!("decode {}", url))?;
+    parse_currency_pairs(&value).map_err(anyhow::Error::msg)
+}
+
+fn get_f64(value: &Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+fn get_u32(value: &Value, key: &str) -> Option<u32> {
+    get_f64(value, key).filter(|v| *v >= 0.0).map(|v| v as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_currency_pairs() {
+        let raw = serde_json::json!([
+            {"id":"ETH_USDT","base":"ETH","quote":"USDT","fee":"0.2","min_base_amount":"0.001","min_quote_amount":"1.0",
+             "max_quote_amount":"10000","amount_precision":3,"precision":6,"trade_status":"tradable"},
+            {"id":"MOON_USDT","base":"MOON","quote":"USDT","min_quote_amount":"3","amount_precision":0,"precision":10,
+             "trade_status":"sellable"},
+            {"id":"BROKEN_USDT","trade_status":"tradable"}
+        ]);
+        let pairs = parse_currency_pairs(&raw).unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].max_quote_amount, Some(10_000.0));
+        assert_eq!((pairs[1].price_decimals, pairs[1].amount_decimals), (10, 0));
+        assert_eq!(pairs[1].min_base_amount, 0.0);
+        assert_eq!(pairs[1].status, SpotTradeStatus::Sellable);
+        assert!(parse_currency_pairs(&serde_json::json!({})).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use super::signing::keccak256;
+use super::wire::Wire;
+use crate::base_classes::symbol::Symbol;
+use serde_json::Value;
+
+/// Perp prices carry at most 5 significant figures and `6 - szDecimals` decimals
+const PRICE_SIG_FIGS: i32 = 5;
+const PERP_MAX_DECIMALS: u32 = 6;
+
+/// One perp from `{"type":"meta"}`; `index` is the asset id used in actions
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlAsset {
+    pub coin: String,
+    pub index: u32,
+    pub sz_decimals: u32,
+    pub max_leverage: u32,
+    /// Delisted perps stay in the universe with `isDelisted: true`
+    pub delisted: bool,
+}
+
+impl HlAsset {
+    pub fn symbol(&self) -> Symbol {
+        hl_symbol(&self.coin)
+    }
+
+    /// Nearest valid price: 5 significant figures, then the decimal cap.
+    /// Integer prices are always valid.
+    pub fn round_price(&self, price: f64) -> f64 {
+        if price <= 0.0 || !price.is_finite() {
+            return price;
+        }
+        let magnitude = price.log10().floor() as i32;
+        let sig = if magnitude >= PRICE_SIG_FIGS - 1 {
+            price.round()
+        } else {
+            let factor = 10f64.powi(PRICE_SIG_FIGS - 1 - magnitude);
+            (price * factor).round() / factor
+        };
+        let decimals = PERP_MAX_DECIMALS.saturating_sub(self.sz_decimals) as i32;
+        let factor = 10f64.powi(decimals);
+        (sig * factor).round() / factor
+    }
+
+    /// Sizes are floored to `szDecimals` so rounding never enlarges a position
+    pub fn round_size(&self, size: f64) -> f64 {
+        let factor = 10f64.powi(self.sz_decimals as i32);
+        ((size * factor) + 1e-9).floor() / factor
+    }
+}
+
+/// Perps settle in USDC; the coin is the base. Coins are case-sensitive
+/// (`kPEPE`) while symbols are upper-cased, so map back through `find_asset`.
+pub fn hl_symbol(coin: &str) -> Symbol {
+    Symbol::new(&format!("{}_USDC", coin))
+}
+
+pub fn find_asset(assets: &[HlAsset], symbol: Symbol) -> Option<&HlAsset> {
+    assets.iter().find(|asset| asset.symbol() == symbol)
+}
+
+pub fn parse_meta(value: &Value) -> Result<Vec<HlAsset>, String> {
+    let universe = value
+        .get("universe")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "expected a universe array".to_string())?;
+    universe
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            Ok(HlAsset {
+                coin: item.get("name").and_then(Value::as_str).ok_or("asset without name")?.to_string(),
+                index: index as u32,
+                sz_decimals: item.get("szDecimals").and_then(Value::as_u64).ok_or("asset without szDecimals")? as u32,
+                max_leverage: item.get("maxLeverage").and_then(Value::as_u64).unwrap_or(1) as u32,
+                delisted: item.get("isDelisted").and_then(Value::as_bool).unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// Decimal string as the venue re-serializes it: at most 8 decimals, no
+/// trailing zeros. A value that does not survive that is a sizing bug upstream.
+pub fn float_to_wire(value: f64) -> Result<String, String> {
+    let rounded = format!("{:.8}", value);
+    if (rounded.parse::<f64>().unwrap_or(f64::NAN) - value).abs() >= 1e-12 {
+        return Err(format!("{} has more than 8 decimals", value));
+    }
+    let trimmed = if rounded.contains('.') { rounded.trim_end_matches('0').trim_end_matches('.') } else { &rounded };
+    Ok(match trimmed {
+        "-0" | "" => "0".to_string(),
+        other => other.to_string(),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HlTif {
+    Gtc,
+    Ioc,
+    /// Add-liquidity-only (post only)
+    Alo,
+}
+
+impl HlTif {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HlTif::Gtc => "Gtc",
+            HlTif::Ioc => "Ioc",
+            HlTif::Alo => "Alo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlOrder {
+    pub asset: u32,
+    pub is_buy: bool,
+    pub price: f64,
+    pub size: f64,
+    pub reduce_only: bool,
+    pub tif: HlTif,
+    /// 16-byte client order id as 0x-hex
+    pub cloid: Option<String>,
+}
+
+impl HlOrder {
+    fn to_wire(&self) -> Result<Wire, String> {
+        let mut fields = vec![
+            ("a", Wire::UInt(self.asset as u64)),
+            ("b", Wire::Bool(self.is_buy)),
+            ("p", Wire::Str(float_to_wire(self.price)?)),
+            ("s", Wire::Str(float_to_wire(self.size)?)),
+            ("r", Wire::Bool(self.reduce_only)),
+            ("t", Wire::Map(vec![("limit", Wire::Map(vec![("tif", Wire::str(self.tif.as_str()))]))])),
+        ];
+        if let Some(cloid) = &self.cloid {
+            fields.push(("c", Wire::str(cloid.clone())));
+        }
+        Ok(Wire::Map(fields))
+    }
+}
+
+pub fn order_action(orders: &[HlOrder]) -> Result<Wire, String> {
+    Ok(Wire::Map(vec![
+        ("type", Wire::str("order")),
+        ("orders", Wire::Array(orders.iter().map(HlOrder::to_wire).collect::<Result<_, _>>()?)),
+        ("grouping", Wire::str("na")),
+    ]))
+}
+
+/// Cancels by venue order id: `(asset, oid)`
+pub fn cancel_action(cancels: &[(u32, u64)]) -> Wire {
+    Wire::Map(vec![
+        ("type", Wire::str("cancel")),
+        (
+            "cancels",
+            Wire::Array(
+                cancels
+                    .iter()
+                    .map(|&(asset, oid)| Wire::Map(vec![("a", Wire::UInt(asset as u64)), ("o", Wire::UInt(oid))]))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+/// `keccak(msgpack(action) || nonce_be || vault flag [|| vault])`
+pub fn action_hash(action: &Wire, nonce: u64, vault: Option<&[u8; 20]>) -> [u8; 32] {
+    let mut data = action.to_msgpack();
+    data.extend_from_slice(&nonce.to_be_bytes());
+    match vault {
+        None => data.push(0x00),
+        Some(address) => {
+            data.push(0x01);
+            data.extend_from_slice(address);
+        }
+    }
+    keccak256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pepe_asset() -> HlAsset {
+        HlAsset { coin: "kPEPE".into(), index: 90, sz_decimals: 0, max_leverage: 10, delisted: false }
+    }
+
+    #[test]
+    fn test_price_and_size_rules() {
+        let eth = HlAsset { coin: "ETH".into(), index: 4, sz_decimals: 4, max_leverage: 50, delisted: false };
+        assert_eq!(eth.round_price(1670.123), 1670.1);
+        assert_eq!(eth.round_price(123_456.7), 123_457.0);
+        let pepe = pepe_asset();
+        assert_eq!(pepe.round_price(0.0123456789), 0.012346);
+        assert_eq!(pepe.round_size(1234.9), 1234.0);
+        assert_eq!(eth.round_size(0.1 + 0.2), 0.3);
+
+        assert_eq!(float_to_wire(1670.1).unwrap(), "1670.1");
+        assert_eq!(float_to_wire(100.0).unwrap(), "100");
+        assert_eq!(float_to_wire(-0.0).unwrap(), "0");
+        assert!(float_to_wire(0.123456789).is_err());
+    }
+
+    #[test]
+    fn test_order_action_encoding_and_hash() {
+        let order = HlOrder { asset: 4, is_buy: true, price: 1670.1, size: 0.0147, reduce_only: false, tif: HlTif::Ioc, cloid: None };
+        let action = order_action(&[order]).unwrap();
+        let json = action.to_json();
+        assert_eq!(json["orders"][0]["t"]["limit"]["tif"], "Ioc");
+        assert_eq!(json["orders"][0]["s"], "0.0147");
+        assert_eq!(json["grouping"], "na");
+        // fixmap(3), "type", "order"
+        assert_eq!(action.to_msgpack()[..12], [0x83, 0xa4, b't', b'y', b'p', b'e', 0xa5, b'o', b'r', b'd', b'e', b'r']);
+
+        let vault = [7u8; 20];
+        assert_ne!(action_hash(&action, 1, None), action_hash(&action, 2, None));
+        assert_ne!(action_hash(&action, 1, None), action_hash(&action, 1, Some(&vault)));
+
+        let meta = json!({"universe": [
+            {"name": "BTC", "szDecimals": 5, "maxLeverage": 50},
+            {"name": "FTM", "szDecimals": 0, "maxLeverage": 3, "isDelisted": true}
+        ]});
+        let assets = parse_meta(&meta).unwrap();
+        assert_eq!((assets[1].index, assets[1].delisted), (1, true));
+        assert_eq!(assets[0].symbol(), Symbol::new("BTC_USDC"));
+        assert_eq!(find_asset(&assets, Symbol::new("FTM_USDC")).unwrap().coin, "FTM");
+        assert_eq!(find_asset(&[pepe_asset()], Symbol::new("KPEPE_USDC")).unwrap().coin, "kPEPE");
+    }
+}
@@ -0,0 +1,23 @@
+//! Hyperliquid perpetuals integration
+//!
+//! Hyperliquid is an on-chain order book: every order or cancel is an "L1
+//! action" whose msgpack encoding is hashed and signed as an EIP-712 message by
+//! an Ethereum key. This module builds and hashes those actions, talks to the
+//! `/info` and `/exchange` endpoints and parses the public stream.
+//!
+//! Key material never enters this process. All signing goes through the
+//! `WalletSigner` trait, whose only production implementation forwards 32-byte
+//! digests to an external signer (hardware wallet bridge, KMS, a local signing
+//! daemon), so an audit of key handling is an audit of that one trait.
+
+pub mod actions;
+pub mod parser;
+pub mod rest;
+pub mod signing;
+pub mod wire;
+
+pub use actions::*;
+pub use parser::*;
+pub use rest::*;
+pub use signing::*;
+pub use wire::Wire;
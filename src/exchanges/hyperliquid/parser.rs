@@ -0,0 +1,186 @@
+use super::actions::hl_symbol;
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Ts;
+use crate::base_classes::ws::{ExchangeHandler, HeartbeatPayload};
+use crate::exchanges::endpoints::{HyperliquidApi, Network};
+use chrono::DateTime;
+use serde_json::{json, Value};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlTrade {
+    pub symbol: Symbol,
+    pub tid: u64,
+    pub price: f64,
+    pub size: f64,
+    /// Taker side ("B" buys, "A" sells)
+    pub side: TradeSide,
+    pub ts_ms: u64,
+}
+
+impl HlTrade {
+    pub fn to_tick(&self, best: Option<&HlBbo>) -> TradeTick {
+        TradeTick {
+            timestamp: DateTime::from_timestamp_millis(self.ts_ms as i64).unwrap_or_default(),
+            symbol: self.symbol,
+            price: self.price,
+            volume: self.size,
+            side: self.side,
+            trade_id: self.tid.to_string(),
+            best_bid: best.and_then(|b| b.bid),
+            best_ask: best.and_then(|b| b.ask),
+        }
+    }
+}
+
+/// Top of book; a side is `None` while it is empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlBbo {
+    pub symbol: Symbol,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub ts_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HlEvent {
+    /// One frame carries every trade of a block for the coin
+    Trades(Vec<HlTrade>),
+    Bbo(HlBbo),
+}
+
+pub fn parse_hl_message(text: &str) -> Option<HlEvent> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    let data = value.get("data")?;
+    match value.get("channel").and_then(Value::as_str)? {
+        "trades" => {
+            let trades: Vec<HlTrade> = data.as_array()?.iter().filter_map(parse_trade).collect();
+            (!trades.is_empty()).then_some(HlEvent::Trades(trades))
+        }
+        "bbo" => {
+            let levels = data.get("bbo").and_then(Value::as_array)?;
+            let px = |i: usize| levels.get(i).and_then(|level| get_f64(level, "px"));
+            Some(HlEvent::Bbo(HlBbo {
+                symbol: hl_symbol(data.get("coin").and_then(Value::as_str)?),
+                bid: px(0),
+                ask: px(1),
+                ts_ms: data.get("time").and_then(Value::as_u64).unwrap_or(0),
+            }))
+        }
+        "error" => {
+            log::error!("hyperliquid stream error: {}", data);
+            None
+        }
+        // subscriptionResponse, pong
+        _ => None,
+    }
+}
+
+fn parse_trade(item: &Value) -> Option<HlTrade> {
+    let side = match item.get("side").and_then(Value::as_str)? {
+        "B" => TradeSide::Buy,
+        "A" => TradeSide::Sell,
+        _ => return None,
+    };
+    Some(HlTrade {
+        symbol: hl_symbol(item.get("coin").and_then(Value::as_str)?),
+        tid: item.get("tid").and_then(Value::as_u64).unwrap_or(0),
+        price: get_f64(item, "px")?,
+        size: get_f64(item, "sz")?,
+        side,
+        ts_ms: item.get("time").and_then(Value::as_u64)?,
+    })
+}
+
+fn get_f64(value: &Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+/// Trades and BBO for a set of coins (case-sensitive venue names, e.g. `kPEPE`)
+pub struct HyperliquidHandler {
+    url: &'static str,
+    coins: Vec<String>,
+    subs: Vec<String>,
+}
+
+impl HyperliquidHandler {
+    pub fn new(network: Network, coins: &[&str]) -> Self {
+        let subs = coins
+            .iter()
+            .flat_map(|coin| {
+                ["trades", "bbo"].map(|kind| json!({"method": "subscribe", "subscription": {"type": kind, "coin": coin}}).to_string())
+            })
+            .collect();
+        Self {
+            url: HyperliquidApi::ws(network),
+            coins: coins.iter().map(|c| c.to_string()).collect(),
+            subs,
+        }
+    }
+}
+
+impl ExchangeHandler for HyperliquidHandler {
+    type Out = HlEvent;
+
+    fn url(&self) -> &str {
+        self.url
+    }
+
+    fn initial_subscriptions(&self) -> &[String] {
+        &self.subs
+    }
+
+    fn parse_text(&self, text: &str, _ts: Ts, _recv_instant: Instant) -> Option<Self::Out> {
+        parse_hl_message(text)
+    }
+
+    fn parse_binary(&self, data: &[u8], _ts: Ts, _recv_instant: Instant) -> Option<Self::Out> {
+        parse_hl_message(std::str::from_utf8(data).ok()?)
+    }
+
+    /// The server drops connections idle for 60s
+    fn app_heartbeat_interval(&self) -> Option<u64> {
+        Some(30)
+    }
+
+    fn build_app_heartbeat(&self) -> Option<HeartbeatPayload> {
+        Some(HeartbeatPayload::Text(r#"{"method":"ping"}"#.to_string()))
+    }
+
+    fn label(&self) -> String {
+        format!("hyperliquid:{} coins", self.coins.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trades_and_bbo() {
+        let trades = r#"{"channel":"trades","data":[{"coin":"kPEPE","side":"A","px":"0.012345","sz":"1000","time":1717000000000,"hash":"0x00","tid":11,"users":["0x1","0x2"]},{"coin":"kPEPE","side":"B","px":"0.012346","sz":"50","time":1717000000001,"hash":"0x00","tid":12,"users":["0x3","0x4"]}]}"#;
+        let Some(HlEvent::Trades(trades)) = parse_hl_message(trades) else {
+            panic!("trades not parsed");
+        };
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].symbol, Symbol::new("KPEPE_USDC"));
+        assert_eq!((trades[0].side, trades[1].side), (TradeSide::Sell, TradeSide::Buy));
+
+        let bbo = r#"{"channel":"bbo","data":{"coin":"BTC","time":1717000000002,"bbo":[{"px":"67000.0","sz":"1.2","n":3},null]}}"#;
+        let Some(HlEvent::Bbo(bbo)) = parse_hl_message(bbo) else {
+            panic!("bbo not parsed");
+        };
+        assert_eq!((bbo.bid, bbo.ask), (Some(67_000.0), None));
+        assert_eq!(trades[1].to_tick(Some(&bbo)).best_bid, Some(67_000.0));
+
+        assert!(parse_hl_message(r#"{"channel":"pong"}"#).is_none());
+        let handler = HyperliquidHandler::new(Network::Testnet, &["BTC", "kPEPE"]);
+        assert_eq!(handler.initial_subscriptions().len(), 4);
+        assert_eq!(handler.url(), HyperliquidApi::TESTNET_WS);
+    }
+}
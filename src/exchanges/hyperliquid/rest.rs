@@ -0,0 +1,179 @@
+use super::actions::{action_hash, cancel_action, order_action, parse_meta, HlAsset, HlOrder};
+use super::signing::{l1_action_digest, to_hex, WalletSigner};
+use super::wire::Wire;
+use crate::exchanges::endpoints::{HyperliquidApi, Network};
+use crate::utils::time::current_unix_ms;
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Outcome of one order in an `order` action, in request order
+#[derive(Debug, Clone, PartialEq)]
+pub enum HlOrderStatus {
+    Resting { oid: u64 },
+    Filled { oid: u64, total_size: f64, avg_price: f64 },
+    /// Cancel accepted
+    Success,
+    Error(String),
+}
+
+/// Per-order statuses from an `/exchange` response. A top-level `"status":"err"`
+/// (bad signature, unknown agent) fails the whole call.
+pub fn parse_exchange_response(value: &Value) -> Result<Vec<HlOrderStatus>, String> {
+    if value.get("status").and_then(Value::as_str) != Some("ok") {
+        return Err(format!("exchange rejected the action: {}", value.get("response").unwrap_or(value)));
+    }
+    let Some(statuses) = value.pointer("/response/data/statuses").and_then(Value::as_array) else {
+        return Ok(Vec::new());
+    };
+    Ok(statuses
+        .iter()
+        .map(|status| {
+            let num = |v: &Value, key: &str| match v.get(key) {
+                Some(Value::String(s)) => s.parse::<f64>().ok(),
+                Some(Value::Number(n)) => n.as_f64(),
+                _ => None,
+            };
+            if let Some(resting) = status.get("resting") {
+                return HlOrderStatus::Resting { oid: resting.get("oid").and_then(Value::as_u64).unwrap_or(0) };
+            }
+            if let Some(filled) = status.get("filled") {
+                return HlOrderStatus::Filled {
+                    oid: filled.get("oid").and_then(Value::as_u64).unwrap_or(0),
+                    total_size: num(filled, "totalSz").unwrap_or(0.0),
+                    avg_price: num(filled, "avgPx").unwrap_or(0.0),
+                };
+            }
+            match status.get("error").and_then(Value::as_str) {
+                Some(error) => HlOrderStatus::Error(error.to_string()),
+                // Cancels answer with the bare string "success"
+                None => match status.as_str() {
+                    Some("success") => HlOrderStatus::Success,
+                    _ => HlOrderStatus::Error(status.to_string()),
+                },
+            }
+        })
+        .collect())
+}
+
+/// Signed access to `/exchange` plus the unsigned `/info` queries it needs
+pub struct HyperliquidClient {
+    http: reqwest::Client,
+    base: String,
+    network: Network,
+    signer: Arc<dyn WalletSigner>,
+    /// Trade on behalf of a vault or sub-account
+    vault: Option<[u8; 20]>,
+    last_nonce: AtomicU64,
+}
+
+impl HyperliquidClient {
+    pub fn new(network: Network, signer: Arc<dyn WalletSigner>, vault: Option<[u8; 20]>) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder().timeout(std::time::Duration::from_secs(10)).build()?,
+            base: HyperliquidApi::base(network).to_string(),
+            network,
+            signer,
+            vault,
+            last_nonce: AtomicU64::new(0),
+        })
+    }
+
+    /// Millisecond timestamp, strictly increasing: the venue rejects a reused nonce
+    fn next_nonce(&self) -> u64 {
+        let now = current_unix_ms();
+        let mut last = self.last_nonce.load(Ordering::Relaxed);
+        loop {
+            let next = now.max(last + 1);
+            match self.last_nonce.compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return next,
+                Err(current) => last = current,
+            }
+        }
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        let url = format!("{}{}", self.base, path);
+        let resp = self.http.post(&url).json(body).send().await.with_context(|| format!("POST {}", url))?;
+        let status = resp.status();
+        if !status.is_success() {
+            bail!("POST {} failed with {}: {}", url, status, resp.text().await.unwrap_or_default());
+        }
+        resp.json().await.with_context(|| format!("decode {}", url))
+    }
+
+    pub async fn meta(&self) -> Result<Vec<HlAsset>> {
+        let value = self.post(HyperliquidApi::INFO, &json!({ "type": "meta" })).await?;
+        parse_meta(&value).map_err(anyhow::Error::msg)
+    }
+
+    async fn send_action(&self, action: Wire) -> Result<Vec<HlOrderStatus>> {
+        let nonce = self.next_nonce();
+        let hash = action_hash(&action, nonce, self.vault.as_ref());
+        let digest = l1_action_digest(&hash, !self.network.is_testnet());
+        let signature = self.signer.sign_digest(&digest).await.context("sign hyperliquid action")?;
+        let body = json!({
+            "action": action.to_json(),
+            "nonce": nonce,
+            "signature": signature,
+            "vaultAddress": self.vault.map(|v| to_hex(&v)),
+        });
+        let value = self.post(HyperliquidApi::EXCHANGE, &body).await?;
+        parse_exchange_response(&value).map_err(|err| anyhow!("hyperliquid {}: {}", self.network, err))
+    }
+
+    pub async fn place(&self, orders: &[HlOrder]) -> Result<Vec<HlOrderStatus>> {
+        let action = order_action(orders).map_err(anyhow::Error::msg)?;
+        self.send_action(action).await
+    }
+
+    pub async fn cancel(&self, cancels: &[(u32, u64)]) -> Result<Vec<HlOrderStatus>> {
+        self.send_action(cancel_action(cancels)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::hyperliquid::signing::Signature;
+    use async_trait::async_trait;
+
+    struct FixedSigner;
+
+    #[async_trait]
+    impl WalletSigner for FixedSigner {
+        fn address(&self) -> [u8; 20] {
+            [1; 20]
+        }
+
+        async fn sign_digest(&self, _digest: &[u8; 32]) -> Result<Signature> {
+            Ok(Signature { r: to_hex(&[2; 32]), s: to_hex(&[3; 32]), v: 27 })
+        }
+    }
+
+    #[test]
+    fn test_parse_exchange_response() {
+        let ok = json!({"status": "ok", "response": {"type": "order", "data": {"statuses": [
+            {"resting": {"oid": 77738308}},
+            {"filled": {"totalSz": "0.02", "avgPx": "1891.4", "oid": 77747314}},
+            {"error": "Order must have minimum value of $10."}
+        ]}}});
+        let statuses = parse_exchange_response(&ok).unwrap();
+        assert_eq!(statuses[0], HlOrderStatus::Resting { oid: 77_738_308 });
+        assert_eq!(statuses[1], HlOrderStatus::Filled { oid: 77_747_314, total_size: 0.02, avg_price: 1891.4 });
+        assert!(matches!(&statuses[2], HlOrderStatus::Error(e) if e.contains("minimum value")));
+        let cancelled = json!({"status": "ok", "response": {"type": "cancel", "data": {"statuses": ["success"]}}});
+        assert_eq!(parse_exchange_response(&cancelled).unwrap(), vec![HlOrderStatus::Success]);
+        assert!(parse_exchange_response(&json!({"status": "err", "response": "User or API Wallet does not exist."})).is_err());
+    }
+
+    #[test]
+    fn test_nonces_strictly_increase() {
+        let client = HyperliquidClient::new(Network::Testnet, Arc::new(FixedSigner), None).unwrap();
+        let first = client.next_nonce();
+        let second = client.next_nonce();
+        assert!(second > first);
+        assert_eq!(client.signer.address(), [1; 20]);
+    }
+}
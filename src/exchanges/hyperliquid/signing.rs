@@ -0,0 +1,157 @@
+use crate::config::credentials::Secret;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tiny_keccak::{Hasher, Keccak};
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut out = [0u8; 32];
+    hasher.finalize(&mut out);
+    out
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("0x");
+    for b in bytes {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+pub fn from_hex<const N: usize>(s: &str) -> Result<[u8; N]> {
+    let s = s.trim_start_matches("0x");
+    if s.len() != N * 2 {
+        bail!("expected {} hex bytes, got {:?}", N, s);
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).with_context(|| format!("bad hex {:?}", s))?;
+    }
+    Ok(out)
+}
+
+/// secp256k1 signature in the `{r, s, v}` form `/exchange` expects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature {
+    /// 0x-prefixed 32-byte hex
+    pub r: String,
+    pub s: String,
+    /// 27 or 28
+    pub v: u8,
+}
+
+/// The one place key material is used. Implementations sign a ready EIP-712
+/// digest; they never see the action and cannot be asked to sign anything
+/// other than 32 bytes.
+#[async_trait]
+pub trait WalletSigner: Send + Sync {
+    /// Address of the signing key (an API "agent" wallet, not the funded one)
+    fn address(&self) -> [u8; 20];
+
+    async fn sign_digest(&self, digest: &[u8; 32]) -> Result<Signature>;
+}
+
+/// Signs through an external signing service:
+/// `POST {url}/sign {"address","digest"}` -> `{"r","s","v"}`, bearer-authenticated.
+/// The address is fixed at construction and every response is checked for shape.
+pub struct RemoteSigner {
+    http: reqwest::Client,
+    url: String,
+    token: Secret,
+    address: [u8; 20],
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, token: Secret, address: &str) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder().timeout(std::time::Duration::from_secs(5)).build()?,
+            url: url.trim_end_matches('/').to_string(),
+            token,
+            address: from_hex(address).context("signer address")?,
+        })
+    }
+}
+
+#[async_trait]
+impl WalletSigner for RemoteSigner {
+    fn address(&self) -> [u8; 20] {
+        self.address
+    }
+
+    async fn sign_digest(&self, digest: &[u8; 32]) -> Result<Signature> {
+        let url = format!("{}/sign", self.url);
+        let body = serde_json::json!({ "address": to_hex(&self.address), "digest": to_hex(digest) });
+        let resp = self
+            .http
+            .post(&url)
+            .bearer_auth(self.token.expose())
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("POST {}", url))?;
+        let status = resp.status();
+        if !status.is_success() {
+            bail!("signer {} refused with {}: {}", url, status, resp.text().await.unwrap_or_default());
+        }
+        let signature: Signature = resp.json().await.with_context(|| format!("decode {}", url))?;
+        from_hex::<32>(&signature.r).context("signature r")?;
+        from_hex::<32>(&signature.s).context("signature s")?;
+        if !matches!(signature.v, 27 | 28) {
+            bail!("signer {} returned v = {}", url, signature.v);
+        }
+        Ok(signature)
+    }
+}
+
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const AGENT_TYPE: &str = "Agent(string source,bytes32 connectionId)";
+/// Chain id of the L1 action domain; the same on mainnet and testnet
+const L1_CHAIN_ID: u64 = 1337;
+
+fn domain_separator() -> [u8; 32] {
+    let mut chain_id = [0u8; 32];
+    chain_id[24..].copy_from_slice(&L1_CHAIN_ID.to_be_bytes());
+    let encoded = [
+        keccak256(DOMAIN_TYPE.as_bytes()),
+        keccak256(b"Exchange"),
+        keccak256(b"1"),
+        chain_id,
+        // verifyingContract = address(0)
+        [0u8; 32],
+    ]
+    .concat();
+    keccak256(&encoded)
+}
+
+/// EIP-712 digest of the "phantom agent" wrapping an action hash. `source` is
+/// "a" on mainnet and "b" on testnet, so a testnet signature is useless on mainnet.
+pub fn l1_action_digest(action_hash: &[u8; 32], mainnet: bool) -> [u8; 32] {
+    let source = if mainnet { "a" } else { "b" };
+    let struct_hash = keccak256(&[keccak256(AGENT_TYPE.as_bytes()), keccak256(source.as_bytes()), *action_hash].concat());
+    keccak256(&[&[0x19, 0x01][..], &domain_separator(), &struct_hash].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keccak_and_eip712_domain_constants() {
+        assert_eq!(
+            to_hex(&keccak256(b"")),
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        // Standard EIP712Domain typehash
+        assert_eq!(
+            to_hex(&keccak256(DOMAIN_TYPE.as_bytes())),
+            "0x8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f"
+        );
+        let hash = keccak256(b"action");
+        assert_ne!(l1_action_digest(&hash, true), l1_action_digest(&hash, false));
+        assert_eq!(from_hex::<2>("0xab01").unwrap(), [0xab, 0x01]);
+        assert!(from_hex::<2>("0xab").is_err());
+    }
+}
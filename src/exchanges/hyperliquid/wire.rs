@@ -0,0 +1,138 @@
+//! Action payload tree with the two encodings Hyperliquid needs: msgpack for
+//! the hash that gets signed, JSON for the request body. Building both from one
+//! tree keeps them from drifting apart. Map keys keep insertion order: the
+//! hash is over the exact byte layout the venue re-encodes.
+
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Wire {
+    Nil,
+    Bool(bool),
+    UInt(u64),
+    Str(String),
+    Array(Vec<Wire>),
+    Map(Vec<(&'static str, Wire)>),
+}
+
+impl Wire {
+    pub fn str(s: impl Into<String>) -> Self {
+        Wire::Str(s.into())
+    }
+
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(128);
+        self.encode(&mut out);
+        out
+    }
+
+    /// Smallest encoding for every value, as rmp/msgpack-python produce
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Wire::Nil => out.push(0xc0),
+            Wire::Bool(false) => out.push(0xc2),
+            Wire::Bool(true) => out.push(0xc3),
+            Wire::UInt(v) => encode_uint(*v, out),
+            Wire::Str(s) => {
+                let len = s.len();
+                if len < 32 {
+                    out.push(0xa0 | len as u8);
+                } else if len <= 0xff {
+                    out.extend_from_slice(&[0xd9, len as u8]);
+                } else if len <= 0xffff {
+                    out.push(0xda);
+                    out.extend_from_slice(&(len as u16).to_be_bytes());
+                } else {
+                    out.push(0xdb);
+                    out.extend_from_slice(&(len as u32).to_be_bytes());
+                }
+                out.extend_from_slice(s.as_bytes());
+            }
+            Wire::Array(items) => {
+                encode_len(items.len(), 0x90, 0xdc, 0xdd, out);
+                for item in items {
+                    item.encode(out);
+                }
+            }
+            Wire::Map(entries) => {
+                encode_len(entries.len(), 0x80, 0xde, 0xdf, out);
+                for (key, value) in entries {
+                    Wire::Str(key.to_string()).encode(out);
+                    value.encode(out);
+                }
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            Wire::Nil => Value::Null,
+            Wire::Bool(b) => Value::Bool(*b),
+            Wire::UInt(v) => Value::from(*v),
+            Wire::Str(s) => Value::String(s.clone()),
+            Wire::Array(items) => Value::Array(items.iter().map(Wire::to_json).collect()),
+            Wire::Map(entries) => {
+                let mut map = Map::with_capacity(entries.len());
+                for (key, value) in entries {
+                    map.insert(key.to_string(), value.to_json());
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+fn encode_uint(v: u64, out: &mut Vec<u8>) {
+    if v < 0x80 {
+        out.push(v as u8);
+    } else if v <= 0xff {
+        out.extend_from_slice(&[0xcc, v as u8]);
+    } else if v <= 0xffff {
+        out.push(0xcd);
+        out.extend_from_slice(&(v as u16).to_be_bytes());
+    } else if v <= 0xffff_ffff {
+        out.push(0xce);
+        out.extend_from_slice(&(v as u32).to_be_bytes());
+    } else {
+        out.push(0xcf);
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+fn encode_len(len: usize, fix: u8, len16: u8, len32: u8, out: &mut Vec<u8>) {
+    if len < 16 {
+        out.push(fix | len as u8);
+    } else if len <= 0xffff {
+        out.push(len16);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(len32);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msgpack_layout() {
+        let wire = Wire::Map(vec![
+            ("a", Wire::UInt(4)),
+            ("b", Wire::Bool(true)),
+            ("p", Wire::str("1670.1")),
+            ("o", Wire::UInt(77_738_308)),
+            ("g", Wire::Array(vec![Wire::UInt(200), Wire::UInt(70_000), Wire::Nil])),
+        ]);
+        let expected: Vec<u8> = [
+            &[0x85, 0xa1, b'a', 0x04, 0xa1, b'b', 0xc3, 0xa1, b'p', 0xa6][..],
+            b"1670.1",
+            &[0xa1, b'o', 0xce, 0x04, 0xa2, 0x31, 0x44],
+            &[0xa1, b'g', 0x93, 0xcc, 200, 0xce, 0x00, 0x01, 0x11, 0x70, 0xc0],
+        ]
+        .concat();
+        assert_eq!(wire.to_msgpack(), expected);
+        assert_eq!(wire.to_json()["p"], "1670.1");
+        assert_eq!(Wire::str("x".repeat(40)).to_msgpack()[..2], [0xd9, 40]);
+    }
+}
@@ -0,0 +1,13 @@
+//! KuCoin spot integration
+//!
+//! KuCoin has no fixed socket URL: every connection starts with a REST "bullet"
+//! call that returns a token, the endpoint to dial and the ping interval the
+//! server expects. `KucoinHandler` performs that call through
+//! `ExchangeHandler::prepare_connection`, so the shared WS manager fetches a
+//! fresh token on every reconnect.
+
+pub mod parser;
+pub mod rest;
+
+pub use parser::*;
+pub use rest::*;
@@ -0,0 +1,233 @@
+use super::rest::fetch_public_bullet;
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Ts;
+use crate::base_classes::ws::{ConnectionInfo, ExchangeHandler, HeartbeatPayload};
+use crate::exchanges::endpoints::{KucoinGet, KucoinWs};
+use chrono::DateTime;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// KuCoin spells pairs `BTC-USDT`
+pub fn kucoin_symbol(symbol: Symbol) -> String {
+    symbol.as_str().replace('_', "-")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KucoinTrade {
+    pub symbol: Symbol,
+    pub trade_id: String,
+    pub price: f64,
+    pub size: f64,
+    /// Taker side
+    pub side: TradeSide,
+    pub ts_ms: u64,
+}
+
+impl KucoinTrade {
+    pub fn to_tick(&self, best: Option<&KucoinTicker>) -> TradeTick {
+        TradeTick {
+            timestamp: DateTime::from_timestamp_millis(self.ts_ms as i64).unwrap_or_default(),
+            symbol: self.symbol,
+            price: self.price,
+            volume: self.size,
+            side: self.side,
+            trade_id: self.trade_id.clone(),
+            best_bid: best.map(|b| b.bid),
+            best_ask: best.map(|b| b.ask),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KucoinTicker {
+    pub symbol: Symbol,
+    pub bid: f64,
+    pub bid_size: f64,
+    pub ask: f64,
+    pub ask_size: f64,
+    pub ts_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KucoinEvent {
+    Trade(KucoinTrade),
+    Ticker(KucoinTicker),
+}
+
+/// Parse one frame. Welcome, ack and pong frames yield `None`; error frames are
+/// logged loudly and yield `None`.
+pub fn parse_kucoin_message(text: &str) -> Option<KucoinEvent> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    match value.get("type").and_then(Value::as_str)? {
+        "message" => {}
+        "error" => {
+            log::error!("kucoin stream error: {}", text);
+            return None;
+        }
+        _ => return None,
+    }
+    let topic = value.get("topic").and_then(Value::as_str)?;
+    let (channel, pair) = topic.split_once(':')?;
+    let data = value.get("data")?;
+    match channel {
+        KucoinWs::MATCH => parse_match(data).map(KucoinEvent::Trade),
+        // Ticker data carries no symbol; it is only in the topic
+        KucoinWs::TICKER => parse_ticker(pair, data).map(KucoinEvent::Ticker),
+        _ => None,
+    }
+}
+
+fn parse_match(data: &Value) -> Option<KucoinTrade> {
+    let side = match data.get("side").and_then(Value::as_str)? {
+        "buy" => TradeSide::Buy,
+        "sell" => TradeSide::Sell,
+        _ => return None,
+    };
+    // Match time is a nanosecond string
+    let ts_ns: u64 = data.get("time").and_then(Value::as_str)?.parse().ok()?;
+    Some(KucoinTrade {
+        symbol: Symbol::new(data.get("symbol").and_then(Value::as_str)?),
+        trade_id: data.get("tradeId").and_then(Value::as_str).unwrap_or_default().to_string(),
+        price: get_f64(data, "price")?,
+        size: get_f64(data, "size")?,
+        side,
+        ts_ms: ts_ns / 1_000_000,
+    })
+}
+
+fn parse_ticker(pair: &str, data: &Value) -> Option<KucoinTicker> {
+    Some(KucoinTicker {
+        symbol: Symbol::new(pair),
+        bid: get_f64(data, "bestBid")?,
+        bid_size: get_f64(data, "bestBidSize")?,
+        ask: get_f64(data, "bestAsk")?,
+        ask_size: get_f64(data, "bestAskSize")?,
+        ts_ms: get_f64(data, "time").unwrap_or(0.0) as u64,
+    })
+}
+
+fn get_f64(value: &Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+/// Public trades and best bid/ask for many spot pairs on one connection
+pub struct KucoinHandler {
+    rest_base: String,
+    bullet_url: String,
+    pairs: Vec<Symbol>,
+    subs: Vec<String>,
+    connects: AtomicU64,
+}
+
+impl KucoinHandler {
+    pub fn new(pairs: &[Symbol]) -> Self {
+        Self::with_base(KucoinGet::BASE, pairs)
+    }
+
+    pub fn with_base(rest_base: &str, pairs: &[Symbol]) -> Self {
+        let names: Vec<String> = pairs.iter().map(|&s| kucoin_symbol(s)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut subs = Vec::new();
+        for chunk in names.chunks(KucoinWs::MAX_TOPIC_SYMBOLS) {
+            for topic in [KucoinWs::TICKER, KucoinWs::MATCH] {
+                subs.push(KucoinWs::subscribe(subs.len() as u64 + 1, topic, chunk));
+            }
+        }
+        Self {
+            rest_base: rest_base.to_string(),
+            bullet_url: format!("{}{}", rest_base, KucoinGet::BULLET_PUBLIC),
+            pairs: pairs.to_vec(),
+            subs,
+            connects: AtomicU64::new(0),
+        }
+    }
+
+    pub fn pairs(&self) -> &[Symbol] {
+        &self.pairs
+    }
+}
+
+impl ExchangeHandler for KucoinHandler {
+    type Out = KucoinEvent;
+
+    /// Not dialled; the real endpoint comes from `prepare_connection`
+    fn url(&self) -> &str {
+        &self.bullet_url
+    }
+
+    fn initial_subscriptions(&self) -> &[String] {
+        &self.subs
+    }
+
+    fn parse_text(&self, text: &str, _ts: Ts, _recv_instant: Instant) -> Option<Self::Out> {
+        parse_kucoin_message(text)
+    }
+
+    fn parse_binary(&self, data: &[u8], _ts: Ts, _recv_instant: Instant) -> Option<Self::Out> {
+        parse_kucoin_message(std::str::from_utf8(data).ok()?)
+    }
+
+    /// Used until a bullet dictates the interval
+    fn app_heartbeat_interval(&self) -> Option<u64> {
+        Some(15)
+    }
+
+    fn build_app_heartbeat(&self) -> Option<HeartbeatPayload> {
+        Some(HeartbeatPayload::Text(format!(
+            r#"{{"id":"{}","type":"ping"}}"#,
+            crate::utils::time::current_unix_ms()
+        )))
+    }
+
+    fn prepare_connection(&self) -> Result<Option<ConnectionInfo>, String> {
+        let bullet = fetch_public_bullet(&self.rest_base).map_err(|err| format!("{:#}", err))?;
+        let connect = self.connects.fetch_add(1, Ordering::Relaxed);
+        Ok(Some(bullet.connection_info(&format!("bot-{}-{}", std::process::id(), connect))))
+    }
+
+    fn label(&self) -> String {
+        format!("kucoin:{} pairs", self.pairs.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_match_and_ticker() {
+        let trade = r#"{"type":"message","topic":"/market/match:BTC-USDT","subject":"trade.l3match","data":{"makerOrderId":"a","price":"67523","sequence":"11067996711960577","side":"buy","size":"0.003","symbol":"BTC-USDT","takerOrderId":"b","time":"1729843222921000000","tradeId":"11067996711960577","type":"match"}}"#;
+        let Some(KucoinEvent::Trade(trade)) = parse_kucoin_message(trade) else {
+            panic!("match not parsed");
+        };
+        assert_eq!(trade.symbol, Symbol::new("BTC_USDT"));
+        assert_eq!((trade.ts_ms, trade.side), (1_729_843_222_921, TradeSide::Buy));
+
+        let ticker = r#"{"type":"message","topic":"/market/ticker:BTC-USDT","subject":"trade.ticker","data":{"bestAsk":"67523.1","bestAskSize":"0.5","bestBid":"67523","bestBidSize":"1.2","price":"67523","sequence":"1","size":"0.003","time":1729843222921}}"#;
+        let Some(KucoinEvent::Ticker(ticker)) = parse_kucoin_message(ticker) else {
+            panic!("ticker not parsed");
+        };
+        assert_eq!(ticker.symbol, Symbol::new("BTC_USDT"));
+        assert_eq!(trade.to_tick(Some(&ticker)).best_bid, Some(67523.0));
+
+        assert!(parse_kucoin_message(r#"{"id":"c1","type":"welcome"}"#).is_none());
+        assert!(parse_kucoin_message(r#"{"id":"1","type":"pong"}"#).is_none());
+    }
+
+    #[test]
+    fn test_subscriptions_chunk_by_topic_limit() {
+        let pairs: Vec<Symbol> = (0..120).map(|i| Symbol::new(&format!("ALT{}_USDT", i))).collect();
+        let handler = KucoinHandler::new(&pairs);
+        assert_eq!(handler.initial_subscriptions().len(), 4);
+        let first: Value = serde_json::from_str(&handler.initial_subscriptions()[0]).unwrap();
+        assert_eq!(first["type"], "subscribe");
+        assert!(first["topic"].as_str().unwrap().starts_with("/market/ticker:ALT0-USDT,ALT1-USDT"));
+        assert_eq!(kucoin_symbol(Symbol::new("ALT7_USDT")), "ALT7-USDT");
+    }
+}
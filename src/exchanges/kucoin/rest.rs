@@ -0,0 +1,87 @@
+use crate::base_classes::ws::ConnectionInfo;
+use crate::exchanges::endpoints::KucoinGet;
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Result of a bullet call: one-connection token plus the server to dial
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulletToken {
+    pub token: String,
+    pub endpoint: String,
+    pub ping_interval_ms: u64,
+    pub ping_timeout_ms: u64,
+}
+
+impl BulletToken {
+    /// `connect_id` is echoed in the welcome message; any unique string works.
+    pub fn connection_info(&self, connect_id: &str) -> ConnectionInfo {
+        ConnectionInfo {
+            url: format!("{}?token={}&connectId={}", self.endpoint, self.token, connect_id),
+            // Ping a bit more often than asked so one slow frame does not cost the session
+            app_heartbeat_interval_secs: Some((self.ping_interval_ms * 3 / 4 / 1_000).max(1)),
+        }
+    }
+}
+
+/// Parse the bullet response. KuCoin reports errors as HTTP 200 with a non
+/// "200000" `code`, so the code is checked before the payload.
+pub fn parse_bullet(value: &Value) -> Result<BulletToken, String> {
+    let code = value.get("code").and_then(Value::as_str).unwrap_or("");
+    if code != "200000" {
+        return Err(format!(
+            "bullet rejected with code {:?}: {}",
+            code,
+            value.get("msg").and_then(Value::as_str).unwrap_or("(no message)")
+        ));
+    }
+    let data = value.get("data").ok_or("bullet response without data")?;
+    let token = data.get("token").and_then(Value::as_str).ok_or("bullet response without token")?;
+    let server = data
+        .get("instanceServers")
+        .and_then(Value::as_array)
+        .and_then(|servers| servers.first())
+        .ok_or("bullet response without instanceServers")?;
+    let endpoint = server.get("endpoint").and_then(Value::as_str).ok_or("instance server without endpoint")?;
+    Ok(BulletToken {
+        token: token.to_string(),
+        endpoint: endpoint.trim_end_matches('/').to_string(),
+        ping_interval_ms: server.get("pingInterval").and_then(Value::as_u64).unwrap_or(18_000),
+        ping_timeout_ms: server.get("pingTimeout").and_then(Value::as_u64).unwrap_or(10_000),
+    })
+}
+
+/// Blocking: called from the WS worker thread before each connect.
+pub fn fetch_public_bullet(base: &str) -> Result<BulletToken> {
+    let url = format!("{}{}", base, KucoinGet::BULLET_PUBLIC);
+    let client = reqwest::blocking::Client::builder().timeout(Duration::from_secs(10)).build()?;
+    let resp = client.post(&url).send().with_context(|| format!("POST {}", url))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().unwrap_or_default();
+        bail!("POST {} failed with {}: {}", url, status, body);
+    }
+    let value: Value = resp.json().with_context(|| format!("decode {}", url))?;
+    parse_bullet(&value).map_err(anyhow::Error::msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_bullet_and_build_connection() {
+        let raw = json!({"code": "200000", "data": {"token": "2neAiuYvAU61ZD", "instanceServers": [
+            {"endpoint": "wss://ws-api-spot.kucoin.com/", "encrypt": true, "protocol": "websocket",
+             "pingInterval": 18000, "pingTimeout": 10000}
+        ]}});
+        let bullet = parse_bullet(&raw).unwrap();
+        let info = bullet.connection_info("c1");
+        assert_eq!(info.url, "wss://ws-api-spot.kucoin.com?token=2neAiuYvAU61ZD&connectId=c1");
+        assert_eq!(info.app_heartbeat_interval_secs, Some(13));
+
+        let err = parse_bullet(&json!({"code": "429000", "msg": "Too Many Requests"})).unwrap_err();
+        assert!(err.contains("429000"), "{}", err);
+    }
+}
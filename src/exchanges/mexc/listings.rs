@@ -0,0 +1,74 @@
+use crate::base_classes::symbol::Symbol;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+
+/// A token MEXC has announced with a scheduled opening time
+#[derive(Debug, Clone, PartialEq)]
+pub struct MexcListing {
+    /// `BASE_USDT`; MEXC opens new tokens against USDT
+    pub symbol: Symbol,
+    pub base: String,
+    pub full_name: String,
+    pub opens_at: DateTime<Utc>,
+}
+
+/// Parse the `new_coin_calendar` response. Entries without an opening time
+/// ("coming soon") are skipped: nothing can be scheduled on them.
+pub fn parse_listing_calendar(value: &Value) -> Result<Vec<MexcListing>, String> {
+    let coins = value
+        .pointer("/data/newCoins")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "expected data.newCoins array".to_string())?;
+    let mut listings: Vec<MexcListing> = coins
+        .iter()
+        .filter_map(|coin| {
+            let base = coin.get("vcoinName").and_then(Value::as_str)?.to_ascii_uppercase();
+            let opens_ms = match coin.get("firstOpenTime")? {
+                Value::Number(n) => n.as_i64()?,
+                Value::String(s) => s.parse::<i64>().ok()?,
+                _ => return None,
+            };
+            Some(MexcListing {
+                symbol: Symbol::new(&format!("{}_USDT", base)),
+                full_name: coin.get("vcoinNameFull").and_then(Value::as_str).unwrap_or(&base).to_string(),
+                opens_at: DateTime::from_timestamp_millis(opens_ms)?,
+                base,
+            })
+        })
+        .collect();
+    listings.sort_by_key(|l| l.opens_at);
+    Ok(listings)
+}
+
+/// Listings opening in `(now, now + within]`, soonest first
+pub fn upcoming_listings(listings: &[MexcListing], now: DateTime<Utc>, within: Duration) -> Vec<&MexcListing> {
+    listings
+        .iter()
+        .filter(|l| l.opens_at > now && l.opens_at <= now + within)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_calendar_and_filter_upcoming() {
+        let raw = json!({"data": {"newCoins": [
+            {"vcoinId": "b", "vcoinName": "late", "vcoinNameFull": "Late Token", "firstOpenTime": 1_717_010_000_000u64},
+            {"vcoinId": "a", "vcoinName": "SOON", "vcoinNameFull": "Soon Token", "firstOpenTime": "1717003600000"},
+            {"vcoinId": "c", "vcoinName": "TBA"}
+        ]}});
+        let listings = parse_listing_calendar(&raw).unwrap();
+        assert_eq!(listings.len(), 2);
+        assert_eq!(listings[0].symbol, Symbol::new("SOON_USDT"));
+        assert_eq!(listings[1].base, "LATE");
+
+        let now = DateTime::from_timestamp_millis(1_717_000_000_000).unwrap();
+        let soon = upcoming_listings(&listings, now, Duration::hours(2));
+        assert_eq!(soon.len(), 1);
+        assert_eq!(soon[0].full_name, "Soon Token");
+        assert!(parse_listing_calendar(&json!({"data": {}})).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+//! MEXC spot integration
+//!
+//! MEXC lists new tokens earlier than most venues and publishes the opening time
+//! ahead, which is what a listing sniper keys on. This module provides symbol
+//! metadata, the upcoming-listing calendar, signed order queries and the mapping
+//! of MEXC order statuses (including its terminal `PARTIALLY_CANCELED`) onto
+//! `OrderUpdate`.
+
+pub mod listings;
+pub mod orders;
+pub mod rest;
+pub mod signing;
+
+pub use listings::*;
+pub use orders::*;
+pub use rest::*;
+pub use signing::*;
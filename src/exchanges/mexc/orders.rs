@@ -0,0 +1,113 @@
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::execution::user_stream::OrderUpdate;
+use crate::execution::{ClientOrderId, ExchangeOrderId, OrderStatus};
+use serde_json::Value;
+
+/// MEXC order status. REST answers with names, the private stream with codes
+/// 1..=5. `PARTIALLY_CANCELED` is terminal: the order is gone and `executedQty`
+/// is the final fill; no separate `CANCELED` follows.
+pub fn parse_order_status(value: &Value) -> OrderStatus {
+    match value {
+        Value::String(name) => match name.as_str() {
+            "NEW" => OrderStatus::New,
+            "PARTIALLY_FILLED" => OrderStatus::PartiallyFilled,
+            "FILLED" => OrderStatus::Filled,
+            "CANCELED" => OrderStatus::Canceled,
+            "PARTIALLY_CANCELED" => OrderStatus::PartiallyCanceled,
+            other => match other.parse::<i64>() {
+                Ok(code) => order_status_from_code(code),
+                Err(_) => OrderStatus::Unknown,
+            },
+        },
+        Value::Number(code) => code.as_i64().map_or(OrderStatus::Unknown, order_status_from_code),
+        _ => OrderStatus::Unknown,
+    }
+}
+
+pub fn order_status_from_code(code: i64) -> OrderStatus {
+    match code {
+        1 => OrderStatus::New,
+        2 => OrderStatus::Filled,
+        3 => OrderStatus::PartiallyFilled,
+        4 => OrderStatus::Canceled,
+        5 => OrderStatus::PartiallyCanceled,
+        _ => OrderStatus::Unknown,
+    }
+}
+
+/// Order from `GET /api/v3/order` or a place/cancel response. The average fill
+/// price is not reported and is derived from `cummulativeQuoteQty` (sic).
+pub fn parse_order(value: &Value) -> Option<OrderUpdate> {
+    let side = match value.get("side").and_then(Value::as_str)? {
+        "BUY" => Side::Bid,
+        "SELL" => Side::Ask,
+        _ => return None,
+    };
+    let filled = get_f64(value, "executedQty").unwrap_or(0.0);
+    let quote_filled = get_f64(value, "cummulativeQuoteQty").unwrap_or(0.0);
+    let exchange_order_id = match value.get("orderId")? {
+        Value::String(id) => id.clone(),
+        Value::Number(id) => id.to_string(),
+        _ => return None,
+    };
+    Some(OrderUpdate {
+        symbol: Symbol::new(value.get("symbol").and_then(Value::as_str)?),
+        client_order_id: value
+            .get("clientOrderId")
+            .and_then(Value::as_str)
+            .filter(|id| !id.is_empty())
+            .map(ClientOrderId::new),
+        exchange_order_id: ExchangeOrderId(exchange_order_id),
+        status: parse_order_status(value.get("status").unwrap_or(&Value::Null)),
+        side,
+        price: get_f64(value, "price").unwrap_or(0.0),
+        size: get_f64(value, "origQty").unwrap_or(0.0),
+        filled,
+        avg_fill_price: (filled > 0.0 && quote_filled > 0.0).then(|| quote_filled / filled),
+        ts_ms: get_f64(value, "updateTime")
+            .or_else(|| get_f64(value, "transactTime"))
+            .or_else(|| get_f64(value, "time"))
+            .unwrap_or(0.0) as u64,
+    })
+}
+
+pub(super) fn get_f64(value: &Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_order_status_names_and_codes() {
+        assert_eq!(parse_order_status(&json!("PARTIALLY_CANCELED")), OrderStatus::PartiallyCanceled);
+        assert_eq!(parse_order_status(&json!(5)), OrderStatus::PartiallyCanceled);
+        assert_eq!(parse_order_status(&json!("3")), OrderStatus::PartiallyFilled);
+        assert_eq!(parse_order_status(&json!(2)), OrderStatus::Filled);
+        assert_eq!(parse_order_status(&json!("EXPIRED")), OrderStatus::Unknown);
+    }
+
+    #[test]
+    fn test_parse_partially_canceled_order() {
+        let raw = json!({
+            "symbol": "NEWCOINUSDT", "orderId": "C02__443776347957968896", "clientOrderId": "t-7",
+            "price": "0.05", "origQty": "1000", "executedQty": "400", "cummulativeQuoteQty": "19.6",
+            "status": "PARTIALLY_CANCELED", "type": "LIMIT", "side": "BUY",
+            "time": 1717000000000u64, "updateTime": 1717000000500u64
+        });
+        let order = parse_order(&raw).unwrap();
+        assert_eq!(order.symbol, Symbol::new("NEWCOIN_USDT"));
+        assert_eq!(order.status, OrderStatus::PartiallyCanceled);
+        assert_eq!(order.client_order_id, Some(ClientOrderId::new("t-7")));
+        assert_eq!((order.filled, order.ts_ms), (400.0, 1_717_000_000_500));
+        assert!((order.avg_fill_price.unwrap() - 0.049).abs() < 1e-12);
+        assert!(parse_order(&json!({"symbol": "XUSDT", "side": "BUY"})).is_none());
+    }
+}
@@ -0,0 +1,125 @@
+use super::listings::{parse_listing_calendar, MexcListing};
+use super::orders::{get_f64, parse_order};
+use super::signing::signed_query;
+use crate::base_classes::symbol::Symbol;
+use crate::config::credentials::Secret;
+use crate::exchanges::endpoints::MexcGet;
+use crate::execution::user_stream::OrderUpdate;
+use crate::utils::time::current_unix_ms;
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+/// One entry of `exchangeInfo.symbols`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MexcSymbolMeta {
+    pub symbol: Symbol,
+    /// Venue spelling (`BTCUSDT`) for requests
+    pub raw: String,
+    pub base: String,
+    pub quote: String,
+    /// Price decimals (`quotePrecision`)
+    pub price_decimals: u32,
+    /// Amount step (`baseSizePrecision`, a decimal string like "0.01", not a count)
+    pub amount_step: f64,
+    /// Minimum notional in quote (`quoteAmountPrecision`, despite the name)
+    pub min_quote_amount: f64,
+    /// Status "1" (or the older "ENABLED") and spot trading allowed
+    pub tradable: bool,
+}
+
+pub fn parse_exchange_info(value: &Value) -> Result<Vec<MexcSymbolMeta>, String> {
+    let symbols = value
+        .get("symbols")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "expected a symbols array".to_string())?;
+    Ok(symbols
+        .iter()
+        .filter_map(|item| {
+            let raw = item.get("symbol").and_then(Value::as_str)?;
+            let base = item.get("baseAsset").and_then(Value::as_str)?;
+            let quote = item.get("quoteAsset").and_then(Value::as_str)?;
+            let status = match item.get("status")? {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            let spot_allowed = item.get("isSpotTradingAllowed").and_then(Value::as_bool).unwrap_or(true);
+            Some(MexcSymbolMeta {
+                symbol: Symbol::new(&format!("{}_{}", base, quote)),
+                raw: raw.to_string(),
+                base: base.to_string(),
+                quote: quote.to_string(),
+                price_decimals: get_f64(item, "quotePrecision").map_or(8, |v| v as u32),
+                amount_step: get_f64(item, "baseSizePrecision").filter(|v| *v > 0.0).unwrap_or(0.0),
+                min_quote_amount: get_f64(item, "quoteAmountPrecision").unwrap_or(0.0),
+                tradable: matches!(status.as_str(), "1" | "ENABLED") && spot_allowed,
+            })
+        })
+        .collect())
+}
+
+async fn get_json(request: reqwest::RequestBuilder, what: &str) -> Result<Value> {
+    let resp = request.send().await.with_context(|| format!("GET {}", what))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        bail!("GET {} failed with {}: {}", what, status, body);
+    }
+    resp.json().await.with_context(|| format!("decode {}", what))
+}
+
+pub async fn fetch_exchange_info(client: &reqwest::Client, base: &str) -> Result<Vec<MexcSymbolMeta>> {
+    let url = format!("{}{}", base, MexcGet::EXCHANGE_INFO);
+    let value = get_json(client.get(&url), &url).await?;
+    parse_exchange_info(&value).map_err(anyhow::Error::msg)
+}
+
+/// Upcoming listings with a published opening time, soonest first
+pub async fn fetch_listing_calendar(client: &reqwest::Client) -> Result<Vec<MexcListing>> {
+    let url = MexcGet::NEW_COIN_CALENDAR;
+    let request = client.get(url).query(&[("timestamp", current_unix_ms().to_string())]);
+    let value = get_json(request, url).await?;
+    parse_listing_calendar(&value).map_err(anyhow::Error::msg)
+}
+
+/// Signed order lookup by our client id. `raw_symbol` is the venue spelling.
+pub async fn fetch_order(
+    client: &reqwest::Client,
+    base: &str,
+    api_key: &Secret,
+    api_secret: &Secret,
+    raw_symbol: &str,
+    client_order_id: &str,
+) -> Result<OrderUpdate> {
+    let query = signed_query(
+        &[("symbol", raw_symbol), ("origClientOrderId", client_order_id)],
+        current_unix_ms(),
+        api_secret.expose(),
+    );
+    let url = format!("{}{}?{}", base, MexcGet::ORDER, query);
+    let what = format!("{}{} {}", base, MexcGet::ORDER, client_order_id);
+    let value = get_json(client.get(&url).header(MexcGet::API_KEY_HEADER, api_key.expose()), &what).await?;
+    parse_order(&value).with_context(|| format!("unexpected order payload for {}: {}", client_order_id, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_exchange_info_quirks() {
+        let raw = json!({"symbols": [
+            {"symbol": "MXUSDT", "status": "1", "baseAsset": "MX", "quoteAsset": "USDT", "quotePrecision": 4,
+             "baseSizePrecision": "0.01", "quoteAmountPrecision": "5", "isSpotTradingAllowed": true},
+            {"symbol": "NEWUSDT", "status": "2", "baseAsset": "NEW", "quoteAsset": "USDT", "quotePrecision": 6,
+             "baseSizePrecision": "0", "quoteAmountPrecision": "1"}
+        ]});
+        let symbols = parse_exchange_info(&raw).unwrap();
+        assert_eq!(symbols[0].symbol, Symbol::new("MX_USDT"));
+        assert_eq!((symbols[0].amount_step, symbols[0].min_quote_amount), (0.01, 5.0));
+        assert!(symbols[0].tradable);
+        // Paused until the scheduled opening
+        assert!(!symbols[1].tradable);
+        assert_eq!(symbols[1].amount_step, 0.0);
+    }
+}
@@ -3,12 +3,20 @@
 //! # Structure
 //! Each exchange now has its own submodule with clear organization:
 //! - `gate`: Parser, orderbook, REST API, signing
+//! - `gate_spot`: Gate.io spot stream parser, pair precision normalizer, rate limiter
+//! - `hyperliquid`: On-chain perps; EIP-712 action signing behind `WalletSigner`
+//! - `kucoin`: KuCoin spot stream with bullet-token handshake
+//! - `mexc`: MEXC spot symbol metadata, listing calendar, signed order queries
 //! - `bybit`: Parser, orderbook
 //! - `binance`: Parser, orderbook, REST API, parsed types
 //! - `bitget`: Parser, orderbook
 //!
 //! # Supported Exchanges
 //! - **Gate.io**: Full support including execution
+//! - **Gate.io spot**: Market data and order normalization for long-tail altcoins
+//! - **Hyperliquid**: Perp market data and execution (`hyperliquid` feature)
+//! - **KuCoin**: Spot market data
+//! - **MEXC**: Spot metadata, scheduled listings, order status
 //! - **Bybit**: Market data
 //! - **Binance**: Market data
 //! - **Bitget**: Market data
@@ -21,14 +29,27 @@
 //! 4. Add `rest.rs` for REST API if needed
 //! 5. Create `mod.rs` to export public types
 //! 6. Add to this file
+//!
+//! Venues whose socket URL comes from a REST handshake (KuCoin) implement
+//! `ExchangeHandler::prepare_connection`; the WS manager calls it before every connect.
 
 pub mod binance;
 pub mod bitget;
 pub mod bybit;
 pub mod endpoints;
 pub mod gate;
+#[cfg(feature = "gate_exec")]
+pub mod gate_spot;
+#[cfg(feature = "hyperliquid")]
+pub mod hyperliquid;
+#[cfg(feature = "gate_exec")]
+pub mod kucoin;
+#[cfg(feature = "gate_exec")]
+pub mod mexc;
 pub mod okx;
 
+pub use endpoints::Network;
+
 // Backwards compatibility: Re-export for code using old flat structure
 #[cfg(feature = "binance_book")]
 pub use binance::orderbook as binance_book;
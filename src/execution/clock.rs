@@ -0,0 +1,285 @@
+//! Server-time synchronization and local clock skew tracking.
+//!
+//! Signed requests carry a timestamp the venue checks against its own clock (Gate allows
+//! ±60s, Binance rejects anything outside `recvWindow`). A drifting host clock therefore
+//! shows up as a burst of signature/timestamp rejections. [`ClockSync`] periodically
+//! samples each venue's time endpoint, keeps the offset in a [`ServerClock`] that signing
+//! code reads lock-free, and publishes the measured skew so risk can stop trading.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::exchanges::endpoints::{BinanceGet, BybitGet, GateioGet, Network};
+use crate::utils::parsing::value_to_u64;
+use crate::utils::time::current_unix_ms;
+
+fn default_sync_interval_ms() -> u64 {
+    30_000
+}
+
+fn default_samples() -> usize {
+    5
+}
+
+fn default_max_skew_ms() -> u64 {
+    1_000
+}
+
+fn default_recv_window_ms() -> u64 {
+    5_000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClockConfig {
+    #[serde(default = "default_sync_interval_ms")]
+    pub sync_interval_ms: u64,
+    /// Round trips per sync; the one with the lowest RTT wins
+    #[serde(default = "default_samples")]
+    pub samples: usize,
+    /// Skew above this is reported as an AutoStop condition
+    #[serde(default = "default_max_skew_ms")]
+    pub max_skew_ms: u64,
+    /// `recvWindow` sent with Binance-style signed requests
+    #[serde(default = "default_recv_window_ms")]
+    pub recv_window_ms: u64,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            sync_interval_ms: default_sync_interval_ms(),
+            samples: default_samples(),
+            max_skew_ms: default_max_skew_ms(),
+            recv_window_ms: default_recv_window_ms(),
+        }
+    }
+}
+
+/// Local clock corrected by the last measured server offset. Cheap to clone and share.
+#[derive(Debug, Clone)]
+pub struct ServerClock {
+    offset_ms: Arc<AtomicI64>,
+    recv_window_ms: u64,
+}
+
+impl Default for ServerClock {
+    fn default() -> Self {
+        Self::new(default_recv_window_ms())
+    }
+}
+
+impl ServerClock {
+    pub fn new(recv_window_ms: u64) -> Self {
+        Self {
+            offset_ms: Arc::new(AtomicI64::new(0)),
+            recv_window_ms,
+        }
+    }
+
+    /// Server time minus local time
+    pub fn offset_ms(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offset_ms(&self, offset_ms: i64) {
+        self.offset_ms.store(offset_ms, Ordering::Relaxed);
+    }
+
+    pub fn now_ms(&self) -> u64 {
+        (current_unix_ms() as i64 + self.offset_ms()).max(0) as u64
+    }
+
+    /// Timestamp for Gate signatures (seconds)
+    pub fn now_secs(&self) -> i64 {
+        (self.now_ms() / 1_000) as i64
+    }
+
+    pub fn recv_window_ms(&self) -> u64 {
+        self.recv_window_ms
+    }
+
+    /// `timestamp=..&recvWindow=..` for Binance-style signed queries
+    pub fn binance_params(&self) -> String {
+        format!("timestamp={}&recvWindow={}", self.now_ms(), self.recv_window_ms)
+    }
+}
+
+/// Venue time endpoint and how to read milliseconds out of its reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeEndpoint {
+    Gate,
+    Binance,
+    Bybit,
+}
+
+impl TimeEndpoint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gate => "gate",
+            Self::Binance => "binance",
+            Self::Bybit => "bybit",
+        }
+    }
+
+    pub fn url(self, network: Network) -> String {
+        match self {
+            Self::Gate => format!("{}{}", GateioGet::base(network), GateioGet::SERVER_TIME),
+            Self::Binance => format!("{}{}", BinanceGet::base(network), BinanceGet::SERVER_TIME),
+            Self::Bybit => format!("{}{}", BybitGet::base(network), BybitGet::SERVER_TIME),
+        }
+    }
+
+    pub fn parse_server_ms(self, body: &Value) -> Option<u64> {
+        match self {
+            Self::Gate => body.get("server_time").and_then(value_to_u64),
+            Self::Binance => body.get("serverTime").and_then(value_to_u64),
+            Self::Bybit => body.get("time").and_then(value_to_u64),
+        }
+    }
+}
+
+/// One round trip: local send time, server time, local receive time (all ms)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeProbe {
+    pub sent_ms: u64,
+    pub server_ms: u64,
+    pub received_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkewSample {
+    /// Server time minus local time, assuming the server stamped mid-flight
+    pub offset_ms: i64,
+    pub rtt_ms: u64,
+}
+
+impl SkewSample {
+    pub fn skew_ms(&self) -> u64 {
+        self.offset_ms.unsigned_abs()
+    }
+}
+
+/// Offset from the probe with the smallest RTT, where the midpoint assumption errs least.
+pub fn estimate_skew(probes: &[TimeProbe]) -> Option<SkewSample> {
+    probes
+        .iter()
+        .filter(|p| p.received_ms >= p.sent_ms)
+        .min_by_key(|p| p.received_ms - p.sent_ms)
+        .map(|p| {
+            let midpoint = (p.sent_ms + p.received_ms) / 2;
+            SkewSample {
+                offset_ms: p.server_ms as i64 - midpoint as i64,
+                rtt_ms: p.received_ms - p.sent_ms,
+            }
+        })
+}
+
+pub struct ClockSync;
+
+impl ClockSync {
+    /// Re-syncs `clock` against `endpoint` every `sync_interval_ms`. The receiver sees every
+    /// successful sample; failed syncs keep the previous offset and are logged.
+    pub fn spawn(
+        endpoint: TimeEndpoint,
+        network: Network,
+        config: ClockConfig,
+        clock: ServerClock,
+    ) -> (watch::Receiver<Option<SkewSample>>, JoinHandle<()>) {
+        let (tx, rx) = watch::channel(None);
+        let handle = tokio::spawn(async move {
+            let http = Client::new();
+            let url = endpoint.url(network);
+            let mut timer = tokio::time::interval(Duration::from_millis(config.sync_interval_ms.max(1)));
+            loop {
+                timer.tick().await;
+                match sync_once(&http, endpoint, &url, config.samples.max(1)).await {
+                    Ok(sample) => {
+                        clock.set_offset_ms(sample.offset_ms);
+                        if sample.skew_ms() > config.max_skew_ms {
+                            log::error!(
+                                "{} clock skew {}ms exceeds {}ms (rtt {}ms)",
+                                endpoint.name(),
+                                sample.offset_ms,
+                                config.max_skew_ms,
+                                sample.rtt_ms
+                            );
+                        }
+                        if tx.send(Some(sample)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => log::warn!("{} server time sync failed: {:#}", endpoint.name(), err),
+                }
+            }
+        });
+        (rx, handle)
+    }
+}
+
+async fn sync_once(http: &Client, endpoint: TimeEndpoint, url: &str, samples: usize) -> Result<SkewSample> {
+    let mut probes = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let sent_ms = current_unix_ms();
+        let body: Value = http
+            .get(url)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .with_context(|| format!("GET {}", url))?
+            .json()
+            .await?;
+        let received_ms = current_unix_ms();
+        let server_ms = endpoint
+            .parse_server_ms(&body)
+            .ok_or_else(|| anyhow!("no server time in {}", body))?;
+        probes.push(TimeProbe {
+            sent_ms,
+            server_ms,
+            received_ms,
+        });
+    }
+    estimate_skew(&probes).ok_or_else(|| anyhow!("local clock went backwards during sync"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_estimate_uses_fastest_round_trip() {
+        let probes = [
+            TimeProbe { sent_ms: 1_000, server_ms: 3_500, received_ms: 1_400 },
+            TimeProbe { sent_ms: 2_000, server_ms: 4_520, received_ms: 2_040 },
+        ];
+        let sample = estimate_skew(&probes).unwrap();
+        assert_eq!(sample, SkewSample { offset_ms: 2_500, rtt_ms: 40 });
+        assert!(estimate_skew(&[]).is_none());
+    }
+
+    #[test]
+    fn test_clock_applies_offset() {
+        let clock = ServerClock::new(5_000);
+        let local = current_unix_ms();
+        clock.set_offset_ms(-60_000);
+        let adjusted = clock.now_ms();
+        assert!(adjusted + 60_000 >= local && adjusted + 60_000 <= local + 1_000);
+        assert!(clock.binance_params().ends_with("&recvWindow=5000"));
+    }
+
+    #[test]
+    fn test_time_endpoints_parse_their_replies() {
+        assert_eq!(TimeEndpoint::Gate.parse_server_ms(&json!({"server_time": 1700000000123u64})), Some(1700000000123));
+        assert_eq!(TimeEndpoint::Binance.parse_server_ms(&json!({"serverTime": 5})), Some(5));
+        assert_eq!(TimeEndpoint::Bybit.parse_server_ms(&json!({"retCode": 0, "time": 7})), Some(7));
+        assert!(TimeEndpoint::Binance.url(Network::Testnet).starts_with("https://testnet.binancefuture.com"));
+    }
+}
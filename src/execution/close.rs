@@ -0,0 +1,157 @@
+//! Partial position closes as reduce-only orders.
+//!
+//! The deleverager, the dashboard and operator commands all ask for the same thing: take
+//! a share of a position off, either as a fraction of its size or as an amount in quote
+//! currency. [`PositionCloser`] turns that into one reduce-only IOC order on the opposite
+//! side, rounded down to the lot so it never closes more than asked. A request that
+//! covers the whole position closes exactly the position size.
+
+use anyhow::{Result, anyhow, bail};
+
+use crate::base_classes::types::Side;
+
+use super::instruments::InstrumentRegistry;
+use super::types::{ClientOrderIdGenerator, QuoteIntent, TimeInForce, Venue};
+use super::user_stream::PositionUpdate;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CloseAmount {
+    /// Share of the position size, in (0, 1]
+    Fraction(f64),
+    /// Quote-currency value at the mark price
+    Notional(f64),
+}
+
+/// Order that performs a partial close
+#[derive(Debug, Clone)]
+pub struct PartialClose {
+    pub intent: QuoteIntent,
+    /// Signed position size left once the order fills completely
+    pub remaining: f64,
+}
+
+pub struct PositionCloser {
+    venue: Venue,
+    instruments: InstrumentRegistry,
+    /// How far past the mark the IOC limit may fill
+    slippage_bps: f64,
+    ids: ClientOrderIdGenerator,
+}
+
+impl PositionCloser {
+    pub fn new(venue: Venue, instruments: InstrumentRegistry, slippage_bps: f64, ids: ClientOrderIdGenerator) -> Result<Self> {
+        if !(slippage_bps >= 0.0 && slippage_bps.is_finite()) {
+            bail!("close slippage {} bps must be a non-negative number", slippage_bps);
+        }
+        Ok(Self { venue, instruments, slippage_bps, ids })
+    }
+
+    pub fn close_fraction(&self, position: &PositionUpdate, fraction: f64, mark: f64) -> Result<PartialClose> {
+        self.close(position, CloseAmount::Fraction(fraction), mark)
+    }
+
+    pub fn close_notional(&self, position: &PositionUpdate, amount: f64, mark: f64) -> Result<PartialClose> {
+        self.close(position, CloseAmount::Notional(amount), mark)
+    }
+
+    pub fn close(&self, position: &PositionUpdate, amount: CloseAmount, mark: f64) -> Result<PartialClose> {
+        if position.size == 0.0 {
+            bail!("no open position on {} to close", position.symbol);
+        }
+        if !(mark > 0.0 && mark.is_finite()) {
+            bail!("cannot close {} at mark price {}", position.symbol, mark);
+        }
+        let held = position.size.abs();
+        let wanted = match amount {
+            CloseAmount::Fraction(fraction) if fraction > 0.0 && fraction <= 1.0 => held * fraction,
+            CloseAmount::Notional(notional) if notional > 0.0 && notional.is_finite() => notional / mark,
+            other => bail!("invalid close amount {:?} for {}", other, position.symbol),
+        };
+        if wanted > held {
+            log::warn!(
+                "close of {:?} on {} exceeds the {} position; closing all of it",
+                amount,
+                position.symbol,
+                held
+            );
+        }
+        let instrument = self
+            .instruments
+            .get(self.venue, position.symbol)
+            .ok_or_else(|| anyhow!("no instrument {} on {:?} to size the close", position.symbol, self.venue))?;
+        let size = instrument.precision.qty(wanted.min(held))?;
+
+        let (side, limit) = if position.size > 0.0 {
+            (Side::Ask, mark * (1.0 - self.slippage_bps / 10_000.0))
+        } else {
+            (Side::Bid, mark * (1.0 + self.slippage_bps / 10_000.0))
+        };
+        let price = instrument.precision.price_for_side(limit, side == Side::Bid)?;
+        let closed = size.to_f64().min(held);
+        let intent = QuoteIntent::new(
+            self.venue,
+            instrument.venue_symbol(),
+            side,
+            price,
+            size,
+            TimeInForce::Ioc,
+            self.ids.next_id()?,
+        )
+        .reduce_only();
+        Ok(PartialClose { intent, remaining: position.size - position.size.signum() * closed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::symbol::Symbol;
+    use crate::execution::instruments::{ContractType, Instrument};
+    use crate::execution::money::PricePrecision;
+
+    fn closer() -> PositionCloser {
+        let mut instruments = InstrumentRegistry::new();
+        let precision = PricePrecision::from_f64(0.01, 0.001).unwrap();
+        instruments
+            .register(Instrument::new(Venue::Gate, "ETH_USDT", ContractType::LinearPerpetual, precision).unwrap())
+            .unwrap();
+        PositionCloser::new(Venue::Gate, instruments, 20.0, ClientOrderIdGenerator::with_session("close", 1).unwrap()).unwrap()
+    }
+
+    fn position(size: f64) -> PositionUpdate {
+        PositionUpdate {
+            symbol: Symbol::new("ETH_USDT"),
+            size,
+            entry_price: 2000.0,
+            liq_price: None,
+            realised_pnl: 0.0,
+            ts_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_partial_closes_are_reduce_only_and_lot_rounded() {
+        let closer = closer();
+        let third = closer.close_fraction(&position(1.0), 1.0 / 3.0, 2000.0).unwrap();
+        assert!(third.intent.reduce_only);
+        assert_eq!((third.intent.side, third.intent.tif), (Side::Ask, TimeInForce::Ioc));
+        assert_eq!(third.intent.size.to_f64(), 0.333);
+        assert!((third.remaining - 0.667).abs() < 1e-12);
+        // 20 bps under the mark, rounded up for a sell
+        assert_eq!(third.intent.price.to_f64(), 1996.0);
+
+        // $500 of a short at 2000 buys back 0.25
+        let short = closer.close_notional(&position(-1.0), 500.0, 2000.0).unwrap();
+        assert_eq!((short.intent.side, short.intent.size.to_f64()), (Side::Bid, 0.25));
+        assert!((short.remaining + 0.75).abs() < 1e-12);
+
+        let all = closer.close_notional(&position(0.5), 1e9, 2000.0).unwrap();
+        assert_eq!((all.intent.size.to_f64(), all.remaining), (0.5, 0.0));
+
+        assert!(closer.close_fraction(&position(1.0), 0.0, 2000.0).is_err());
+        assert!(closer.close_fraction(&position(1.0), 1.5, 2000.0).is_err());
+        assert!(closer.close_fraction(&position(0.0), 0.5, 2000.0).is_err());
+        // Below one lot
+        assert!(closer.close_notional(&position(1.0), 1.0, 2000.0).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Pre-submit check of orders against the venue's trading rules.
+//!
+//! An order the venue is going to refuse still costs a request, counts against the
+//! API error budget and, repeated a few times, trips `AutoStopManager` and the
+//! per-symbol circuit breaker. [`ComplianceChecker`] refuses such orders locally
+//! instead, with a [`ComplianceViolation`] naming the rule and the numbers:
+//!
+//! - min notional: `price * size` below the venue minimum
+//! - percent price: limit price too far from the reference price (last trade or mark);
+//!   skipped until a reference price has been seen for the symbol
+//! - max open orders per symbol
+//! - position limits, in base units and in quote notional, for orders that grow the
+//!   position (buys that are not reduce-only); resting buys count as if filled
+//!
+//! Sells and reduce-only orders are only held to the price and notional rules, so a
+//! position can always be worked out of.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::error::ExecutionError;
+
+/// Allowed band around the reference price, as multipliers (Binance `PERCENT_PRICE`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PercentPrice {
+    /// Highest allowed price is `reference * multiplier_up`
+    pub multiplier_up: f64,
+    /// Lowest allowed price is `reference * multiplier_down`
+    pub multiplier_down: f64,
+}
+
+/// One symbol's rules. Zero or `None` switches a rule off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradingRules {
+    #[serde(default)]
+    pub min_notional: f64,
+    #[serde(default)]
+    pub percent_price: Option<PercentPrice>,
+    #[serde(default)]
+    pub max_open_orders: Option<usize>,
+    /// Absolute position size in base units
+    #[serde(default)]
+    pub max_position: Option<f64>,
+    /// Absolute position value in quote currency at the order price
+    #[serde(default)]
+    pub max_position_notional: Option<f64>,
+}
+
+/// Order about to be submitted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderCheck {
+    pub symbol: Symbol,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+    pub reduce_only: bool,
+}
+
+/// What the caller knows about the symbol right now
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Exposure {
+    /// Live orders on the symbol, both sides
+    pub open_orders: usize,
+    /// Signed position size in base units
+    pub position: f64,
+    /// Unfilled size of resting buys
+    pub open_buy_size: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComplianceViolation {
+    MinNotional { notional: f64, min: f64 },
+    PercentPrice { price: f64, reference: f64, low: f64, high: f64 },
+    MaxOpenOrders { open: usize, max: usize },
+    PositionLimit { projected: f64, max: f64 },
+    PositionNotional { projected: f64, max: f64 },
+}
+
+impl ComplianceViolation {
+    /// Rule name for logs and counters
+    pub fn rule(&self) -> &'static str {
+        match self {
+            ComplianceViolation::MinNotional { .. } => "min_notional",
+            ComplianceViolation::PercentPrice { .. } => "percent_price",
+            ComplianceViolation::MaxOpenOrders { .. } => "max_open_orders",
+            ComplianceViolation::PositionLimit { .. } => "max_position",
+            ComplianceViolation::PositionNotional { .. } => "max_position_notional",
+        }
+    }
+
+    pub fn into_error(self, symbol: Symbol) -> ExecutionError {
+        ExecutionError::Rejected { symbol: symbol.to_string(), reason: self.to_string() }
+    }
+}
+
+impl std::fmt::Display for ComplianceViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComplianceViolation::MinNotional { notional, min } => {
+                write!(f, "min_notional: notional {} below {}", notional, min)
+            }
+            ComplianceViolation::PercentPrice { price, reference, low, high } => {
+                write!(f, "percent_price: price {} outside [{}, {}] around {}", price, low, high, reference)
+            }
+            ComplianceViolation::MaxOpenOrders { open, max } => {
+                write!(f, "max_open_orders: {} orders already open, max {}", open, max)
+            }
+            ComplianceViolation::PositionLimit { projected, max } => {
+                write!(f, "max_position: position would reach {}, max {}", projected, max)
+            }
+            ComplianceViolation::PositionNotional { projected, max } => {
+                write!(f, "max_position_notional: position value would reach {}, max {}", projected, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ComplianceViolation {}
+
+/// Rules per symbol plus the reference prices the percent-price rule needs
+#[derive(Debug, Clone, Default)]
+pub struct ComplianceChecker {
+    default: TradingRules,
+    symbols: HashMap<Symbol, TradingRules>,
+    reference: HashMap<Symbol, f64>,
+    rejected: HashMap<&'static str, u64>,
+}
+
+impl ComplianceChecker {
+    /// `default` applies to every symbol without its own rules
+    pub fn new(default: TradingRules) -> Self {
+        Self { default, ..Self::default() }
+    }
+
+    pub fn set_rules(&mut self, symbol: Symbol, rules: TradingRules) {
+        self.symbols.insert(symbol, rules);
+    }
+
+    pub fn rules(&self, symbol: Symbol) -> &TradingRules {
+        self.symbols.get(&symbol).unwrap_or(&self.default)
+    }
+
+    /// Last trade or mark price of the symbol; non-positive prices are ignored
+    pub fn on_price(&mut self, symbol: Symbol, price: f64) {
+        if price > 0.0 && price.is_finite() {
+            self.reference.insert(symbol, price);
+        }
+    }
+
+    /// Orders refused so far, by rule
+    pub fn rejected(&self) -> &HashMap<&'static str, u64> {
+        &self.rejected
+    }
+
+    pub fn check(&mut self, order: &OrderCheck, exposure: &Exposure) -> Result<(), ComplianceViolation> {
+        let result = self.evaluate(order, exposure);
+        if let Err(violation) = &result {
+            *self.rejected.entry(violation.rule()).or_default() += 1;
+        }
+        result
+    }
+
+    fn evaluate(&self, order: &OrderCheck, exposure: &Exposure) -> Result<(), ComplianceViolation> {
+        let rules = self.rules(order.symbol);
+        let notional = order.price * order.size;
+        if rules.min_notional > 0.0 && notional < rules.min_notional {
+            return Err(ComplianceViolation::MinNotional { notional, min: rules.min_notional });
+        }
+        if let (Some(band), Some(&reference)) = (rules.percent_price, self.reference.get(&order.symbol)) {
+            let (low, high) = (reference * band.multiplier_down, reference * band.multiplier_up);
+            if order.price < low || order.price > high {
+                return Err(ComplianceViolation::PercentPrice { price: order.price, reference, low, high });
+            }
+        }
+        if order.reduce_only || order.side == Side::Ask {
+            return Ok(());
+        }
+        if let Some(max) = rules.max_open_orders
+            && exposure.open_orders >= max
+        {
+            return Err(ComplianceViolation::MaxOpenOrders { open: exposure.open_orders, max });
+        }
+        let projected = (exposure.position + exposure.open_buy_size + order.size).abs();
+        if let Some(max) = rules.max_position
+            && projected > max
+        {
+            return Err(ComplianceViolation::PositionLimit { projected, max });
+        }
+        if let Some(max) = rules.max_position_notional
+            && projected * order.price > max
+        {
+            return Err(ComplianceViolation::PositionNotional { projected: projected * order.price, max });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: Side, price: f64, size: f64) -> OrderCheck {
+        OrderCheck { symbol: Symbol::new("ETH_USDT"), side, price, size, reduce_only: false }
+    }
+
+    #[test]
+    fn each_rule_rejects_with_its_reason() {
+        let mut checker = ComplianceChecker::new(TradingRules {
+            min_notional: 5.0,
+            percent_price: Some(PercentPrice { multiplier_up: 1.2, multiplier_down: 0.8 }),
+            max_open_orders: Some(2),
+            max_position: Some(3.0),
+            max_position_notional: Some(250.0),
+        });
+        let flat = Exposure::default();
+
+        let err = checker.check(&order(Side::Bid, 100.0, 0.01), &flat).unwrap_err();
+        assert_eq!(err.to_string(), "min_notional: notional 1 below 5");
+        // No reference price yet: the band is not enforced
+        assert!(checker.check(&order(Side::Bid, 50.0, 1.0), &flat).is_ok());
+
+        checker.on_price(Symbol::new("ETH_USDT"), 100.0);
+        let err = checker.check(&order(Side::Bid, 70.0, 1.0), &flat).unwrap_err();
+        assert_eq!(err.rule(), "percent_price");
+        assert_eq!(
+            err.into_error(Symbol::new("ETH_USDT")).to_string(),
+            "ETH_USDT: order rejected, percent_price: price 70 outside [80, 120] around 100"
+        );
+
+        let busy = Exposure { open_orders: 2, ..flat };
+        assert_eq!(checker.check(&order(Side::Bid, 100.0, 1.0), &busy).unwrap_err().rule(), "max_open_orders");
+        let long = Exposure { open_orders: 1, position: 1.5, open_buy_size: 1.0 };
+        assert_eq!(
+            checker.check(&order(Side::Bid, 100.0, 1.0), &long).unwrap_err(),
+            ComplianceViolation::PositionLimit { projected: 3.5, max: 3.0 }
+        );
+        let err = checker.check(&order(Side::Bid, 100.0, 1.0), &Exposure { position: 2.0, ..flat }).unwrap_err();
+        assert_eq!(err.rule(), "max_position_notional");
+
+        // Exits are only held to price and notional
+        assert!(checker.check(&order(Side::Ask, 100.0, 5.0), &long).is_ok());
+        let mut reduce = order(Side::Bid, 100.0, 5.0);
+        reduce.reduce_only = true;
+        assert!(checker.check(&reduce, &busy).is_ok());
+
+        // Per-symbol rules override the default
+        checker.set_rules(Symbol::new("BTC_USDT"), TradingRules::default());
+        let btc = OrderCheck { symbol: Symbol::new("BTC_USDT"), ..order(Side::Bid, 1.0, 0.001) };
+        assert!(checker.check(&btc, &busy).is_ok());
+        assert_eq!(checker.rejected()["max_open_orders"], 1);
+        assert_eq!(checker.rejected().values().sum::<u64>(), 5);
+    }
+}
@@ -1,21 +1,29 @@
 #![allow(dead_code)]
 
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use anyhow::Result;
 
-use super::gateway::ExecutionGateway;
-use super::types::{ClientOrderId, ExchangeOrderId, ExecutionReport, OrderAck, QuoteIntent};
+use super::gateway::{ExecutionGateway, GatewayCapabilities};
+use super::stops::{ServerTrigger, TriggerId, TriggerOrder, TriggerStatus};
+use super::types::{ClientOrderId, ExchangeOrderId, ExecutionReport, OrderAck, OrderStatus, QuoteIntent};
 
 /// Simple in-memory gateway used for dry-run/testing flows.
 pub struct DryRunGateway {
     id_counter: AtomicU64,
+    /// Like a venue, a repeated client id gets the original order back
+    accepted: Mutex<HashMap<ClientOrderId, ExchangeOrderId>>,
+    triggers: Mutex<Vec<ServerTrigger>>,
 }
 
 impl Default for DryRunGateway {
     fn default() -> Self {
         Self {
             id_counter: AtomicU64::new(1),
+            accepted: Mutex::new(HashMap::new()),
+            triggers: Mutex::new(Vec::new()),
         }
     }
 }
@@ -29,16 +37,39 @@ impl DryRunGateway {
         let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
         ExchangeOrderId(format!("SIM-{}", id))
     }
+
+    /// Simulates the market reaching an open trigger
+    pub fn fire_trigger(&self, id: &TriggerId) {
+        let mut triggers = self.triggers.lock().unwrap();
+        let trigger = triggers
+            .iter_mut()
+            .find(|t| &t.id == id && t.status == TriggerStatus::Open)
+            .unwrap_or_else(|| panic!("no open trigger {}", id));
+        trigger.status = TriggerStatus::Fired;
+    }
 }
 
 #[async_trait::async_trait]
 impl ExecutionGateway for DryRunGateway {
+    fn capabilities(&self) -> GatewayCapabilities {
+        GatewayCapabilities {
+            server_triggers: true,
+            ..GatewayCapabilities::default()
+        }
+    }
+
     async fn submit(&self, intents: &[QuoteIntent]) -> Result<Vec<OrderAck>> {
+        let mut accepted = self.accepted.lock().unwrap();
         let acks = intents
             .iter()
             .map(|intent| OrderAck {
                 client_order_id: intent.client_order_id.clone(),
-                exchange_order_id: Some(self.next_exchange_id()),
+                exchange_order_id: Some(
+                    accepted
+                        .entry(intent.client_order_id.clone())
+                        .or_insert_with(|| self.next_exchange_id())
+                        .clone(),
+                ),
             })
             .collect();
         Ok(acks)
@@ -51,4 +82,41 @@ impl ExecutionGateway for DryRunGateway {
     async fn poll_reports(&self) -> Result<Vec<ExecutionReport>> {
         Ok(Vec::new())
     }
+
+    async fn query_by_client_id(&self, id: &ClientOrderId) -> Result<Option<ExecutionReport>> {
+        Ok(self.accepted.lock().unwrap().get(id).map(|exchange_id| ExecutionReport {
+            client_order_id: id.clone(),
+            exchange_order_id: Some(exchange_id.clone()),
+            status: OrderStatus::New,
+            filled_qty: 0.0,
+            avg_fill_price: None,
+            ts: None,
+        }))
+    }
+
+    async fn place_trigger(&self, order: &TriggerOrder) -> Result<TriggerId> {
+        let id = TriggerId(self.next_exchange_id().0);
+        self.triggers.lock().unwrap().push(ServerTrigger {
+            id: id.clone(),
+            order: order.clone(),
+            status: TriggerStatus::Open,
+        });
+        Ok(id)
+    }
+
+    async fn cancel_trigger(&self, id: &TriggerId) -> Result<()> {
+        let mut triggers = self.triggers.lock().unwrap();
+        match triggers.iter_mut().find(|t| &t.id == id && t.status == TriggerStatus::Open) {
+            Some(trigger) => {
+                trigger.status = TriggerStatus::Cancelled;
+                Ok(())
+            }
+            None => anyhow::bail!("trigger {} is not open", id),
+        }
+    }
+
+    async fn list_triggers(&self, symbol: &str) -> Result<Vec<ServerTrigger>> {
+        let triggers = self.triggers.lock().unwrap();
+        Ok(triggers.iter().filter(|t| t.order.symbol == symbol).cloned().collect())
+    }
 }
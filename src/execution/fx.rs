@@ -0,0 +1,181 @@
+//! Currency conversion from live tickers.
+//!
+//! Balances and PnL arrive in whatever the venue settles in (USDT, USDC, BTC, EUR).
+//! `FxRates` keeps the latest mid of every `BASE_QUOTE` pair it is fed and converts
+//! any amount into the configured reporting currency: directly, through the inverse
+//! pair, or through one bridge currency. A missing or stale rate is an error, never
+//! a silent 1.0 — aggregating USDT and BTC at par is worse than no number at all.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::base_classes::symbol::Symbol;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FxConfig {
+    /// Currency equity and limits are expressed in
+    pub reporting: String,
+    /// Intermediate currencies tried, in order, when no direct pair is quoted
+    #[serde(default = "default_bridges")]
+    pub bridges: Vec<String>,
+    /// A rate older than this is not used
+    #[serde(default = "default_max_age_ms")]
+    pub max_age_ms: u64,
+}
+
+fn default_bridges() -> Vec<String> {
+    vec!["USDT".to_string(), "USDC".to_string(), "BTC".to_string()]
+}
+
+fn default_max_age_ms() -> u64 {
+    60_000
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        Self { reporting: "USDT".to_string(), bridges: default_bridges(), max_age_ms: default_max_age_ms() }
+    }
+}
+
+/// One currency's contribution to a valuation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValuedAmount {
+    pub currency: String,
+    pub amount: f64,
+    pub reporting: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Valuation {
+    pub total: f64,
+    pub parts: Vec<ValuedAmount>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FxRates {
+    config: FxConfig,
+    /// (base, quote) -> (mid, received)
+    mids: HashMap<(String, String), (f64, Instant)>,
+}
+
+impl FxRates {
+    pub fn new(config: FxConfig) -> Self {
+        let reporting = config.reporting.to_uppercase();
+        let bridges = config.bridges.iter().map(|b| b.to_uppercase()).collect();
+        Self { config: FxConfig { reporting, bridges, ..config }, mids: HashMap::new() }
+    }
+
+    pub fn reporting(&self) -> &str {
+        &self.config.reporting
+    }
+
+    /// Feeds a ticker's best bid/ask. Crossed or empty books are dropped loudly.
+    pub fn on_ticker(&mut self, symbol: Symbol, bid: f64, ask: f64, at: Instant) {
+        let (Some(base), Some(quote)) = (symbol.base(), symbol.quote()) else {
+            log::error!("fx: ticker for {} has no BASE_QUOTE form", symbol);
+            return;
+        };
+        if !(bid > 0.0 && ask >= bid && ask.is_finite()) {
+            log::error!("fx: ignoring {} ticker bid={} ask={}", symbol, bid, ask);
+            return;
+        }
+        self.mids.insert((base.to_string(), quote.to_string()), ((bid + ask) / 2.0, at));
+    }
+
+    fn quoted(&self, from: &str, to: &str, now: Instant) -> Option<f64> {
+        let max_age = Duration::from_millis(self.config.max_age_ms);
+        let fresh = |key: (&str, &str)| {
+            self.mids
+                .get(&(key.0.to_string(), key.1.to_string()))
+                .filter(|(_, at)| now.saturating_duration_since(*at) <= max_age)
+                .map(|(mid, _)| *mid)
+        };
+        fresh((from, to)).or_else(|| fresh((to, from)).map(|mid| 1.0 / mid))
+    }
+
+    /// Units of `to` per one unit of `from`
+    pub fn rate(&self, from: &str, to: &str, now: Instant) -> Result<f64> {
+        let (from, to) = (from.to_uppercase(), to.to_uppercase());
+        if from == to {
+            return Ok(1.0);
+        }
+        if let Some(rate) = self.quoted(&from, &to, now) {
+            return Ok(rate);
+        }
+        for bridge in &self.config.bridges {
+            if *bridge == from || *bridge == to {
+                continue;
+            }
+            if let (Some(first), Some(second)) = (self.quoted(&from, bridge, now), self.quoted(bridge, &to, now)) {
+                return Ok(first * second);
+            }
+        }
+        bail!("no fresh {}/{} rate (direct or via {:?})", from, to, self.config.bridges)
+    }
+
+    /// `amount` of `currency` in the reporting currency
+    pub fn to_reporting(&self, amount: f64, currency: &str, now: Instant) -> Result<f64> {
+        Ok(amount * self.rate(currency, &self.config.reporting, now)?)
+    }
+
+    /// Sums amounts in mixed currencies. Fails on the first currency without a rate
+    /// rather than returning a partial total.
+    pub fn value<'a>(&self, amounts: impl IntoIterator<Item = (&'a str, f64)>, now: Instant) -> Result<Valuation> {
+        let mut valuation = Valuation::default();
+        for (currency, amount) in amounts {
+            if amount == 0.0 {
+                continue;
+            }
+            let reporting = self.to_reporting(amount, currency, now)?;
+            valuation.total += reporting;
+            valuation.parts.push(ValuedAmount { currency: currency.to_string(), amount, reporting });
+        }
+        Ok(valuation)
+    }
+
+    /// Mid of a traded symbol, if fresh; used as the mark for open positions
+    pub fn mid(&self, symbol: Symbol, now: Instant) -> Option<f64> {
+        self.quoted(symbol.base()?, symbol.quote()?, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates(reporting: &str) -> (FxRates, Instant) {
+        let now = Instant::now();
+        let mut fx = FxRates::new(FxConfig { reporting: reporting.to_string(), ..FxConfig::default() });
+        fx.on_ticker(Symbol::new("BTC_USDT"), 59_990.0, 60_010.0, now);
+        fx.on_ticker(Symbol::new("USDC_USDT"), 0.9999, 1.0001, now);
+        fx.on_ticker(Symbol::new("EUR_USDT"), 1.079, 1.081, now);
+        (fx, now)
+    }
+
+    #[test]
+    fn test_direct_inverse_and_bridged_rates() {
+        let (fx, now) = rates("EUR");
+        assert_eq!(fx.rate("usdt", "USDT", now).unwrap(), 1.0);
+        assert_eq!(fx.rate("BTC", "USDT", now).unwrap(), 60_000.0);
+        assert!((fx.rate("USDT", "EUR", now).unwrap() - 1.0 / 1.08).abs() < 1e-12);
+        // BTC -> USDT -> EUR
+        assert!((fx.to_reporting(0.5, "BTC", now).unwrap() - 30_000.0 / 1.08).abs() < 1e-6);
+
+        let valuation = fx.value([("USDT", 1_080.0), ("BTC", 0.0), ("USDC", 1_080.0)], now).unwrap();
+        assert_eq!(valuation.parts.len(), 2);
+        assert!((valuation.total - 2_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_missing_or_stale_rate_is_an_error() {
+        let (fx, now) = rates("USDT");
+        assert!(fx.to_reporting(1.0, "JPY", now).is_err());
+        assert!(fx.value([("USDT", 1.0), ("JPY", 1.0)], now).is_err());
+        let later = now + Duration::from_secs(61);
+        assert!(fx.to_reporting(1.0, "BTC", later).is_err());
+        assert_eq!(fx.mid(Symbol::new("BTC_USDT"), now), Some(60_000.0));
+    }
+}
@@ -1,35 +1,123 @@
 #![allow(dead_code)]
 
+use std::sync::Arc;
+
 use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
 use reqwest::{Client, Method};
 use serde_json::Value;
 
-use crate::exchanges::{endpoints::GateioGet, gate::signing};
-use crate::utils::parsing::value_to_f64;
-use crate::utils::time::current_unix_seconds_string;
+use crate::exchanges::{
+    endpoints::{GateioGet, Network},
+    gate::signing,
+};
+use crate::utils::parsing::{value_to_f64, value_to_string};
+use crate::config::credentials::{ApiCredentials, CredentialKey, CredentialStore};
+use super::clock::ServerClock;
+use super::margin::MarginLender;
+use super::money::{Price, Qty};
+use super::stops::{ServerTrigger, TriggerId, TriggerKind, TriggerOrder, TriggerStatus};
+use super::types::ClientOrderId;
+use crate::base_classes::types::Side;
 
 use super::types::{ExecutionReport, OrderAck, QuoteIntent};
 
 /// Gate API credentials used for both REST and websocket clients.
-#[derive(Debug, Clone)]
+///
+/// Store-backed credentials are resolved on every [`current`](Self::current) call, so keys
+/// rotated by [`CredentialStore::spawn_watcher`] apply to the next signature or session.
+#[derive(Clone)]
 pub struct GateCredentials {
-    pub api_key: String,
-    pub api_secret: String,
+    source: CredentialSource,
+}
+
+#[derive(Clone)]
+enum CredentialSource {
+    Fixed(Arc<ApiCredentials>),
+    Store {
+        store: Arc<CredentialStore>,
+        key: CredentialKey,
+    },
+}
+
+impl GateCredentials {
+    /// Keys read once, e.g. from env vars; never rotate.
+    pub fn new(api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
+        Self {
+            source: CredentialSource::Fixed(Arc::new(ApiCredentials::new(api_key, api_secret))),
+        }
+    }
+
+    /// Keys looked up in `store` under `key` on every use.
+    pub fn from_store(store: Arc<CredentialStore>, key: CredentialKey) -> Self {
+        Self {
+            source: CredentialSource::Store { store, key },
+        }
+    }
+
+    /// Keys to sign with now; hold the result for one signature or session only.
+    pub fn current(&self) -> Result<Arc<ApiCredentials>> {
+        match &self.source {
+            CredentialSource::Fixed(creds) => Ok(creds.clone()),
+            CredentialSource::Store { store, key } => store.get(&key.exchange, &key.account),
+        }
+    }
+
+    /// The backing store, whose watcher must run for rotation to take effect.
+    pub fn store(&self) -> Option<&Arc<CredentialStore>> {
+        match &self.source {
+            CredentialSource::Fixed(_) => None,
+            CredentialSource::Store { store, .. } => Some(store),
+        }
+    }
+}
+
+impl std::fmt::Debug for GateCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            CredentialSource::Fixed(creds) => f.debug_tuple("GateCredentials").field(creds).finish(),
+            CredentialSource::Store { key, .. } => {
+                f.debug_struct("GateCredentials").field("keystore", &key.to_string()).finish()
+            }
+        }
+    }
 }
 
 /// Thin wrapper for Gate REST endpoints. Only the subset required by the runner is implemented.
 pub struct GateClient {
     http: Client,
     credentials: GateCredentials,
+    network: Network,
+    clock: ServerClock,
 }
 
 impl GateClient {
     pub fn new(credentials: GateCredentials) -> Self {
+        Self::for_network(credentials, Network::Mainnet)
+    }
+
+    /// Client bound to a specific deployment; testnet keys only work against testnet.
+    pub fn for_network(credentials: GateCredentials, network: Network) -> Self {
         let http = Client::builder()
             .user_agent("gate-client/0.1")
             .build()
             .expect("reqwest client");
-        Self { http, credentials }
+        Self {
+            http,
+            credentials,
+            network,
+            clock: ServerClock::default(),
+        }
+    }
+
+    /// Sign with server-corrected timestamps from a [`ClockSync`](super::clock::ClockSync)-driven clock.
+    pub fn with_clock(mut self, clock: ServerClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
     }
 
     pub fn credentials(&self) -> &GateCredentials {
@@ -44,6 +132,40 @@ impl GateClient {
             .with_context(|| format!("failed to GET futures accounts for {}", settle))
     }
 
+    /// Spot balance free for withdrawal or transfer
+    pub async fn fetch_spot_available(&self, currency: &str) -> Result<f64> {
+        let query = format!("currency={}", currency);
+        let value = self
+            .signed_request(Method::GET, "/api/v4/spot/accounts", &query, "")
+            .await
+            .with_context(|| format!("failed to GET spot account for {}", currency))?;
+        let entry = value
+            .as_array()
+            .and_then(|entries| {
+                entries.iter().find(|e| {
+                    e.get("currency").and_then(Value::as_str).is_some_and(|c| c.eq_ignore_ascii_case(currency))
+                })
+            });
+        // A currency never held is simply absent
+        Ok(entry.and_then(|e| e.get("available")).and_then(value_to_f64).unwrap_or(0.0))
+    }
+
+    /// Moves `amount` between wallets of the same account (`spot`, `futures`, ...).
+    /// `settle` selects the futures wallet and is required when either side is futures.
+    pub async fn transfer(&self, currency: &str, from: &str, to: &str, amount: f64, settle: &str) -> Result<Value> {
+        let body = serde_json::json!({
+            "currency": currency,
+            "from": from,
+            "to": to,
+            "amount": format!("{}", amount),
+            "settle": settle,
+        })
+        .to_string();
+        self.signed_request(Method::POST, "/api/v4/wallet/transfers", "", &body)
+            .await
+            .with_context(|| format!("failed to transfer {} {} from {} to {}", amount, currency, from, to))
+    }
+
     /// Получить историю сделок
     pub async fn fetch_user_trades(
         &self,
@@ -115,6 +237,85 @@ impl GateClient {
         Ok(Vec::new())
     }
 
+    /// Places a futures price-triggered order; `contracts` is signed (negative sells).
+    /// Triggers always reduce only: they exist to close positions.
+    pub async fn place_price_order(&self, settle: &str, order: &TriggerOrder, contracts: i64) -> Result<TriggerId> {
+        let (price, tif) = match order.kind {
+            TriggerKind::StopLimit { limit } => (limit.to_string(), "gtc"),
+            TriggerKind::StopMarket | TriggerKind::TakeProfitMarket => ("0".to_string(), "ioc"),
+        };
+        let body = serde_json::json!({
+            "initial": {
+                "contract": order.symbol,
+                "size": contracts,
+                "price": price,
+                "tif": tif,
+                "text": order.client_order_id.to_string(),
+                "reduce_only": true,
+            },
+            "trigger": {
+                "strategy_type": 0,
+                "price_type": 0,
+                "price": order.trigger_price.to_string(),
+                // 1: fires at price >= trigger, 2: at price <= trigger
+                "rule": if order.fires_rising() { 1 } else { 2 },
+                "expiration": 0,
+            },
+        })
+        .to_string();
+        let path = format!("/api/v4/futures/{}/price_orders", settle);
+        let value = self
+            .signed_request(Method::POST, &path, "", &body)
+            .await
+            .with_context(|| format!("failed to place trigger {} on {}", order.client_order_id, order.symbol))?;
+        value
+            .get("id")
+            .and_then(value_to_string)
+            .map(TriggerId)
+            .ok_or_else(|| anyhow!("price order response without id: {}", value))
+    }
+
+    pub async fn cancel_price_order(&self, settle: &str, id: &TriggerId) -> Result<()> {
+        let path = format!("/api/v4/futures/{}/price_orders/{}", settle, id);
+        self.signed_request(Method::DELETE, &path, "", "")
+            .await
+            .with_context(|| format!("failed to cancel trigger {}", id))?;
+        Ok(())
+    }
+
+    /// Open and recently finished price-triggered orders on `contract`
+    pub async fn fetch_price_orders(&self, settle: &str, contract: &str, contract_size: f64) -> Result<Vec<ServerTrigger>> {
+        let path = format!("/api/v4/futures/{}/price_orders", settle);
+        let mut triggers = Vec::new();
+        for status in ["open", "finished"] {
+            let query = format!("status={}&contract={}&limit=100", status, contract);
+            let value = self
+                .signed_request(Method::GET, &path, &query, "")
+                .await
+                .with_context(|| format!("failed to GET {} price orders for {}", status, contract))?;
+            let entries = value.as_array().ok_or_else(|| anyhow!("price orders response is not a list: {}", value))?;
+            for entry in entries {
+                triggers.push(parse_price_order(entry, contract_size)?);
+            }
+        }
+        Ok(triggers)
+    }
+
+    async fn margin_loan(&self, currency: &str, kind: &str, amount: f64, pair: &str, repaid_all: bool) -> Result<()> {
+        let body = serde_json::json!({
+            "currency": currency,
+            "type": kind,
+            "amount": format!("{}", amount),
+            "currency_pair": pair,
+            "repaid_all": repaid_all,
+        })
+        .to_string();
+        self.signed_request(Method::POST, "/api/v4/margin/uni/loans", "", &body)
+            .await
+            .with_context(|| format!("failed to {} {} {} on {}", kind, amount, currency, pair))?;
+        Ok(())
+    }
+
     async fn signed_request(
         &self,
         method: Method,
@@ -123,18 +324,19 @@ impl GateClient {
         body: &str,
     ) -> Result<Value> {
         let method_name = method.as_str();
-        let ts = current_unix_seconds_string();
+        let ts = self.clock.now_secs().to_string();
         let payload_hash = signing::sha512_hex(body);
         let sign_payload = format!(
             "{}\n{}\n{}\n{}\n{}",
             method_name, path, query, payload_hash, ts
         );
-        let signature = signing::hmac_sha512_hex(&self.credentials.api_secret, &sign_payload);
+        let creds = self.credentials.current().context("Gate credentials unavailable")?;
+        let signature = signing::hmac_sha512_hex(creds.api_secret.expose(), &sign_payload);
 
         let url = if query.is_empty() {
-            format!("{}{}", GateioGet::BASE, path)
+            format!("{}{}", GateioGet::base(self.network), path)
         } else {
-            format!("{}{}?{}", GateioGet::BASE, path, query)
+            format!("{}{}?{}", GateioGet::base(self.network), path, query)
         };
 
         let mut request = self.http.request(method, &url);
@@ -142,7 +344,7 @@ impl GateClient {
             request = request.body(body.to_string());
         }
         let response = request
-            .header("KEY", &self.credentials.api_key)
+            .header("KEY", &creds.api_key)
             .header("Timestamp", &ts)
             .header("SIGN", signature)
             .header("Accept", "application/json")
@@ -164,6 +366,70 @@ impl GateClient {
     }
 }
 
+/// Spot-margin lending through `/margin/uni`
+#[async_trait]
+impl MarginLender for GateClient {
+    async fn hourly_rate(&self, currency: &str) -> Result<f64> {
+        let query = format!("currencies={}", currency);
+        let value = self
+            .signed_request(Method::GET, "/api/v4/margin/uni/estimate_rate", &query, "")
+            .await
+            .with_context(|| format!("failed to GET margin rate for {}", currency))?;
+        value
+            .get(currency)
+            .and_then(value_to_f64)
+            .ok_or_else(|| anyhow!("no margin rate for {} in {}", currency, value))
+    }
+
+    async fn borrow(&self, currency: &str, amount: f64, pair: &str) -> Result<()> {
+        self.margin_loan(currency, "borrow", amount, pair, false).await
+    }
+
+    async fn repay(&self, currency: &str, amount: f64, pair: &str, all: bool) -> Result<()> {
+        self.margin_loan(currency, "repay", amount, pair, all).await
+    }
+}
+
+fn parse_price_order(value: &Value, contract_size: f64) -> Result<ServerTrigger> {
+    let field = |path: &[&str]| {
+        path.iter()
+            .try_fold(value, |v, key| v.get(key))
+            .ok_or_else(|| anyhow!("price order without {}: {}", path.join("."), value))
+    };
+    let id = value_to_string(field(&["id"])?).ok_or_else(|| anyhow!("bad price order id: {}", value))?;
+    let contracts = value_to_f64(field(&["initial", "size"])?).ok_or_else(|| anyhow!("bad price order size: {}", value))?;
+    let limit = value_to_f64(field(&["initial", "price"])?).unwrap_or(0.0);
+    let trigger_price = value_to_f64(field(&["trigger", "price"])?).ok_or_else(|| anyhow!("bad trigger price: {}", value))?;
+    let rising = field(&["trigger", "rule"])?.as_i64() == Some(1);
+    let side = if contracts > 0.0 { Side::Bid } else { Side::Ask };
+    let kind = if limit != 0.0 {
+        TriggerKind::StopLimit { limit: Price::try_from_f64(limit)? }
+    } else if rising == (side == Side::Bid) {
+        TriggerKind::StopMarket
+    } else {
+        TriggerKind::TakeProfitMarket
+    };
+    let status = match (field(&["status"])?.as_str(), value.get("finish_as").and_then(Value::as_str)) {
+        (Some("open"), _) => TriggerStatus::Open,
+        (_, Some("succeeded")) => TriggerStatus::Fired,
+        (_, Some("failed")) => TriggerStatus::Failed,
+        _ => TriggerStatus::Cancelled,
+    };
+    Ok(ServerTrigger {
+        id: TriggerId(id),
+        order: TriggerOrder {
+            client_order_id: ClientOrderId::new(value_to_string(field(&["initial", "text"])?).unwrap_or_default()),
+            symbol: value_to_string(field(&["initial", "contract"])?).unwrap_or_default(),
+            side,
+            trigger_price: Price::try_from_f64(trigger_price)?,
+            // Decimal product: an f64 one would not compare equal to the wanted size
+            size: Qty::new(Qty::try_from_f64(contracts.abs())?.as_decimal() * Qty::try_from_f64(contract_size)?.as_decimal()),
+            kind,
+        },
+        status,
+    })
+}
+
 fn extract_position_contracts(value: &Value) -> Option<f64> {
     let fields = ["size", "current_size", "position", "contracts"];
     for key in fields {
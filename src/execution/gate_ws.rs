@@ -16,13 +16,17 @@ use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungsten
 
 use crate::base_classes::state::{TradeDirection, state};
 use crate::base_classes::types::Side;
+use crate::config::credentials::ApiCredentials;
 use crate::exchanges::gate::rest;
 use crate::exchanges::{endpoints::GateioWs, gate::signing};
-use crate::utils::math::format_price;
 use crate::utils::parsing::{extract_user_id, value_to_f64, value_to_string, value_to_u64};
-use crate::utils::time::{current_unix_ms, current_unix_ts};
+use crate::utils::time::current_unix_ms;
 
-use super::gateway::ExecutionGateway;
+use super::clock::ServerClock;
+use super::gate_client::{GateClient, GateCredentials};
+use super::gateway::{ExecutionGateway, GatewayCapabilities};
+use super::money::{Price, Qty};
+use super::stops::{ServerTrigger, TriggerId, TriggerOrder};
 use super::types::{
     ClientOrderId, ExchangeOrderId, ExecutionReport, OrderAck, OrderStatus, QuoteIntent,
 };
@@ -30,12 +34,13 @@ use super::types::{
 /// Configuration required to establish a Gate.io private websocket session.
 #[derive(Debug, Clone)]
 pub struct GateWsConfig {
-    pub api_key: String,
-    pub api_secret: String,
+    pub credentials: GateCredentials,
     pub symbol: String,
     pub settle: Option<String>,
     pub ws_url: Option<String>,
     pub contract_size: Option<f64>,
+    /// Server-corrected clock for signatures; local time when unset
+    pub clock: Option<ServerClock>,
 }
 
 impl GateWsConfig {
@@ -49,18 +54,23 @@ pub struct GateWsGateway {
     tx: mpsc::Sender<GatewayCommand>,
     reports: Arc<Mutex<Vec<ExecutionReport>>>,
     client_to_exchange: Arc<Mutex<HashMap<ClientOrderId, ExchangeOrderId>>>,
+    symbol: String,
+    settle: String,
+    contract_size: f64,
+    /// Price-triggered orders have no websocket API and go over REST
+    rest: Option<Arc<GateClient>>,
 }
 
 impl GateWsGateway {
     /// Connect to Gate websocket, perform login, and spawn the background worker.
     pub async fn connect(config: GateWsConfig) -> Result<Self> {
         let GateWsConfig {
-            api_key,
-            api_secret,
+            credentials,
             symbol,
             settle,
             ws_url,
             contract_size,
+            clock,
         } = config;
 
         let contract_size = if let Some(size) = contract_size {
@@ -76,13 +86,15 @@ impl GateWsGateway {
         let settle = settle.unwrap_or_else(|| "usdt".to_string());
         let ws_url = ws_url.unwrap_or_else(GateWsGateway::base_ws_url);
 
+        // Resolved again on every reconnect; fail here rather than in the worker loop
+        let session = credentials.current().context("Gate credentials unavailable")?;
         let worker_config = WorkerConfig {
-            api_key,
-            api_secret,
-            symbol,
-            settle,
+            credentials,
+            symbol: symbol.clone(),
+            settle: settle.clone(),
             ws_url,
             contract_size,
+            clock: clock.unwrap_or_default(),
         };
 
         let (tx, rx) = mpsc::channel(128);
@@ -91,6 +103,7 @@ impl GateWsGateway {
 
         let worker = GateWsWorker::new(
             worker_config,
+            session,
             rx,
             reports.clone(),
             client_to_exchange.clone(),
@@ -105,12 +118,28 @@ impl GateWsGateway {
             tx,
             reports,
             client_to_exchange,
+            symbol,
+            settle,
+            contract_size,
+            rest: None,
         })
     }
 
+    /// Enables server-side stops through the REST client of the same account
+    pub fn with_triggers(mut self, rest: Arc<GateClient>) -> Self {
+        self.rest = Some(rest);
+        self
+    }
+
+    fn trigger_client(&self) -> Result<&GateClient> {
+        self.rest
+            .as_deref()
+            .ok_or_else(|| anyhow!("Gate gateway for {} was built without a REST client for triggers", self.symbol))
+    }
+
     #[inline]
     fn base_ws_url() -> String {
-        "wss://fx-ws.gateio.ws/v4/ws".to_string()
+        GateioWs::ROOT.to_string()
     }
 
     /// Gracefully request the worker to shut down.
@@ -124,6 +153,14 @@ impl GateWsGateway {
 
 #[async_trait::async_trait]
 impl ExecutionGateway for GateWsGateway {
+    fn capabilities(&self) -> GatewayCapabilities {
+        GatewayCapabilities {
+            native_amend: true,
+            server_triggers: self.rest.is_some(),
+            ..GatewayCapabilities::default()
+        }
+    }
+
     async fn submit(&self, intents: &[QuoteIntent]) -> Result<Vec<OrderAck>> {
         let (resp_tx, resp_rx) = oneshot::channel();
         self.tx
@@ -159,16 +196,63 @@ impl ExecutionGateway for GateWsGateway {
             .context("failed to enqueue poll command")?;
         resp_rx.await.context("poll response channel closed")?
     }
+
+    async fn amend(&self, original: &QuoteIntent, price: Price, size: Qty) -> Result<OrderAck> {
+        let mut intent = original.clone();
+        intent.price = price;
+        intent.size = size;
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.tx
+            .send(GatewayCommand::Amend {
+                intent,
+                resp: resp_tx,
+            })
+            .await
+            .context("failed to enqueue amend command")?;
+        resp_rx.await.context("amend response channel closed")?
+    }
+
+    async fn query_by_client_id(&self, id: &ClientOrderId) -> Result<Option<ExecutionReport>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.tx
+            .send(GatewayCommand::Query {
+                id: id.clone(),
+                resp: resp_tx,
+            })
+            .await
+            .context("failed to enqueue order status query")?;
+        resp_rx.await.context("order status response channel closed")?
+    }
+
+    async fn place_trigger(&self, order: &TriggerOrder) -> Result<TriggerId> {
+        if order.symbol != self.symbol {
+            bail!("trigger {} is for {}, gateway trades {}", order.client_order_id, order.symbol, self.symbol);
+        }
+        let raw = (order.size.abs().to_f64() / self.contract_size).round() as i64;
+        if raw == 0 {
+            bail!("trigger {} size {} is below contract size {}", order.client_order_id, order.size, self.contract_size);
+        }
+        let contracts = if order.side == Side::Bid { raw } else { -raw };
+        self.trigger_client()?.place_price_order(&self.settle, order, contracts).await
+    }
+
+    async fn cancel_trigger(&self, id: &TriggerId) -> Result<()> {
+        self.trigger_client()?.cancel_price_order(&self.settle, id).await
+    }
+
+    async fn list_triggers(&self, symbol: &str) -> Result<Vec<ServerTrigger>> {
+        self.trigger_client()?.fetch_price_orders(&self.settle, symbol, self.contract_size).await
+    }
 }
 
 #[derive(Debug)]
 struct WorkerConfig {
-    api_key: String,
-    api_secret: String,
+    credentials: GateCredentials,
     symbol: String,
     settle: String,
     ws_url: String,
     contract_size: f64,
+    clock: ServerClock,
 }
 
 enum GatewayCommand {
@@ -183,6 +267,15 @@ enum GatewayCommand {
     Poll {
         resp: oneshot::Sender<Result<Vec<ExecutionReport>>>,
     },
+    Query {
+        id: ClientOrderId,
+        resp: oneshot::Sender<Result<Option<ExecutionReport>>>,
+    },
+    /// `intent` carries the new price/size under the original client id
+    Amend {
+        intent: QuoteIntent,
+        resp: oneshot::Sender<Result<OrderAck>>,
+    },
     Shutdown,
 }
 
@@ -195,10 +288,20 @@ enum PendingRequest {
         ids: Vec<ClientOrderId>,
         resp_tx: oneshot::Sender<Result<()>>,
     },
+    Query {
+        id: ClientOrderId,
+        resp_tx: oneshot::Sender<Result<Option<ExecutionReport>>>,
+    },
+    Amend {
+        intent: QuoteIntent,
+        resp_tx: oneshot::Sender<Result<OrderAck>>,
+    },
 }
 
 struct GateWsWorker {
     cfg: WorkerConfig,
+    /// Keys of the current connection, re-read from `cfg.credentials` before each login
+    session: Arc<ApiCredentials>,
     command_rx: mpsc::Receiver<GatewayCommand>,
     reports: Arc<Mutex<Vec<ExecutionReport>>>,
     client_to_exchange: Arc<Mutex<HashMap<ClientOrderId, ExchangeOrderId>>>,
@@ -209,12 +312,14 @@ struct GateWsWorker {
 impl GateWsWorker {
     fn new(
         cfg: WorkerConfig,
+        session: Arc<ApiCredentials>,
         command_rx: mpsc::Receiver<GatewayCommand>,
         reports: Arc<Mutex<Vec<ExecutionReport>>>,
         client_to_exchange: Arc<Mutex<HashMap<ClientOrderId, ExchangeOrderId>>>,
     ) -> Self {
         Self {
             cfg,
+            session,
             command_rx,
             reports,
             client_to_exchange,
@@ -405,6 +510,7 @@ impl GateWsWorker {
             .with_context(|| format!("failed to connect to {}", url))?;
         ws.send(Message::Ping(Vec::new())).await.ok();
 
+        self.session = self.cfg.credentials.current().context("Gate credentials unavailable")?;
         self.perform_login(&mut ws).await?;
         if let Err(err) = self.subscribe_user_trades(&mut ws).await {
             eprintln!("Gate WS user trade subscribe failed: {:#}", err);
@@ -417,9 +523,9 @@ impl GateWsWorker {
         &mut self,
         ws: &mut WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
     ) -> Result<()> {
-        let ts = current_unix_ts();
+        let ts = self.cfg.clock.now_secs();
         let req_id = self.next_request_id("login");
-        let request = build_api_request(&self.cfg, GateioWs::LOGIN, None, &req_id, ts)?;
+        let request = build_api_request(&self.session, GateioWs::LOGIN, None, &req_id, ts)?;
         ws.send(Message::Text(request)).await?;
 
         loop {
@@ -468,8 +574,8 @@ impl GateWsWorker {
             ));
         };
 
-        let ts = current_unix_ts();
-        let sign = sign_subscribe(&self.cfg.api_secret, GateioWs::USER_TRADES, ts);
+        let ts = self.cfg.clock.now_secs();
+        let sign = sign_subscribe(self.session.api_secret.expose(), GateioWs::USER_TRADES, ts);
         let payload = json!({
             "time": ts,
             "channel": GateioWs::USER_TRADES,
@@ -477,7 +583,7 @@ impl GateWsWorker {
             "payload": [uid, "!all"],
             "auth": {
                 "method": "api_key",
-                "KEY": self.cfg.api_key,
+                "KEY": self.session.api_key,
                 "SIGN": sign,
             }
         });
@@ -504,10 +610,10 @@ impl GateWsWorker {
                         return Ok(false);
                     }
                 };
-                let ts = current_unix_ts();
+                let ts = self.cfg.clock.now_secs();
                 let req_id = self.next_request_id("submit");
                 let request = match build_api_request(
-                    &self.cfg,
+                    &self.session,
                     GateioWs::CREATE_BATCH_ORDER,
                     Some(req_param),
                     &req_id,
@@ -548,6 +654,71 @@ impl GateWsWorker {
                 let _ = resp.send(Ok(reports));
                 Ok(false)
             }
+            GatewayCommand::Query { id, resp } => {
+                // Gate resolves `t-` client ids in place of the order id
+                let req_param = json!({ "order_id": id.to_string() });
+                let ts = self.cfg.clock.now_secs();
+                let req_id = self.next_request_id("status");
+                let request = match build_api_request(
+                    &self.session,
+                    GateioWs::ORDER_STATUS,
+                    Some(req_param),
+                    &req_id,
+                    ts,
+                ) {
+                    Ok(req) => req,
+                    Err(err) => {
+                        let _ = resp.send(Err(err));
+                        return Ok(false);
+                    }
+                };
+                if let Err(err) = sink.send(Message::Text(request)).await {
+                    let _ = resp.send(Err(anyhow!(err)));
+                    return Ok(false);
+                }
+                pending.insert(req_id, PendingRequest::Query { id, resp_tx: resp });
+                Ok(false)
+            }
+            GatewayCommand::Amend { intent, resp } => {
+                let order_id = {
+                    let guard = self.client_to_exchange.lock().await;
+                    guard.get(&intent.client_order_id).map(|eid| eid.0.clone())
+                };
+                let contracts = match self.size_to_contracts(&intent) {
+                    Ok(c) => c,
+                    Err(err) => {
+                        let _ = resp.send(Err(err));
+                        return Ok(false);
+                    }
+                };
+                let req_param = json!({
+                    // Gate also resolves the `t-` text, for orders acked before a reconnect
+                    "order_id": order_id.unwrap_or_else(|| intent.client_order_id.to_string()),
+                    "price": intent.price.to_string(),
+                    "size": contracts,
+                });
+                let ts = self.cfg.clock.now_secs();
+                let req_id = self.next_request_id("amend");
+                let request = match build_api_request(
+                    &self.session,
+                    GateioWs::ORDER_AMEND,
+                    Some(req_param),
+                    &req_id,
+                    ts,
+                ) {
+                    Ok(req) => req,
+                    Err(err) => {
+                        let _ = resp.send(Err(err));
+                        return Ok(false);
+                    }
+                };
+                if let Err(err) = sink.send(Message::Text(request)).await {
+                    let _ = resp.send(Err(anyhow!(err)));
+                    return Ok(false);
+                }
+                pending.insert(req_id, PendingRequest::Amend { intent, resp_tx: resp });
+                Ok(false)
+            }
             GatewayCommand::Shutdown => {
                 let _ = sink.send(Message::Close(None)).await;
                 Ok(true)
@@ -607,6 +778,30 @@ impl GateWsWorker {
                                     }
                                 }
                             }
+                            PendingRequest::Query { id, resp_tx } => {
+                                let _ = resp_tx.send(self.process_query_response(&id, &resp));
+                            }
+                            PendingRequest::Amend { intent, resp_tx } => {
+                                match self.process_query_response(&intent.client_order_id, &resp) {
+                                    Ok(Some(report)) => {
+                                        let ack = OrderAck {
+                                            client_order_id: report.client_order_id.clone(),
+                                            exchange_order_id: report.exchange_order_id.clone(),
+                                        };
+                                        self.reports.lock().await.push(report);
+                                        let _ = resp_tx.send(Ok(ack));
+                                    }
+                                    Ok(None) => {
+                                        let _ = resp_tx.send(Err(anyhow!(
+                                            "amend of {}: order not found",
+                                            intent.client_order_id
+                                        )));
+                                    }
+                                    Err(err) => {
+                                        let _ = resp_tx.send(Err(err));
+                                    }
+                                }
+                            }
                             PendingRequest::Cancel { ids, resp_tx } => {
                                 match self.process_cancel_response(ids, &resp).await {
                                     Ok(mut reports) => {
@@ -707,7 +902,7 @@ impl GateWsWorker {
                 exchange_order_id: exchange_id.clone().map(ExchangeOrderId),
             });
 
-            let report = self.build_execution_report(entry, intent, exchange_id.clone());
+            let report = self.build_execution_report(entry, &intent.client_order_id, exchange_id.clone());
             reports.push(report);
         }
 
@@ -791,10 +986,10 @@ impl GateWsWorker {
     ) {
         let texts: Vec<Value> = ids.iter().map(|id| Value::String(id.to_string())).collect();
         let req_param = Value::Array(texts);
-        let ts = current_unix_ts();
+        let ts = self.cfg.clock.now_secs();
         let req_id = self.next_request_id("cancel");
         let request = match build_api_request(
-            &self.cfg,
+            &self.session,
             GateioWs::CANCEL_BATCH_ORDER_IDS,
             Some(req_param),
             &req_id,
@@ -815,10 +1010,33 @@ impl GateWsWorker {
         pending.insert(req_id, PendingRequest::Cancel { ids, resp_tx });
     }
 
+    /// `ORDER_NOT_FOUND` means the venue does not have the order; any other error is
+    /// an unknown outcome and must not be read as "safe to resubmit". Amend responses
+    /// carry the same order object and go through here too.
+    fn process_query_response(&self, id: &ClientOrderId, resp: &WsResponse) -> Result<Option<ExecutionReport>> {
+        if !resp.is_success() {
+            let label = resp.data.as_ref().and_then(|d| d.errs.as_ref()).and_then(|e| e.label.as_deref());
+            if label == Some("ORDER_NOT_FOUND") {
+                return Ok(None);
+            }
+            let err = resp
+                .error_message()
+                .unwrap_or_else(|| "order status query failed".to_string());
+            return Err(anyhow!("status of {}: {}", id, err));
+        }
+        let entry = resp
+            .data
+            .as_ref()
+            .and_then(|d| d.result.as_ref())
+            .ok_or_else(|| anyhow!("missing result payload in order status response"))?;
+        let exchange_id = entry.get("id").and_then(value_to_string);
+        Ok(Some(self.build_execution_report(entry, id, exchange_id)))
+    }
+
     fn build_execution_report(
         &self,
         entry: &Value,
-        intent: &QuoteIntent,
+        client_order_id: &ClientOrderId,
         exchange_id: Option<String>,
     ) -> ExecutionReport {
         let size_contracts = entry.get("size").and_then(value_to_f64).unwrap_or(0.0);
@@ -870,7 +1088,7 @@ impl GateWsWorker {
             .map(|secs| (secs * 1_000_000.0) as u64);
 
         ExecutionReport {
-            client_order_id: intent.client_order_id.clone(),
+            client_order_id: client_order_id.clone(),
             exchange_order_id: exchange_id.map(ExchangeOrderId),
             status,
             filled_qty,
@@ -883,20 +1101,24 @@ impl GateWsWorker {
         let mut orders = Vec::with_capacity(intents.len());
         for intent in intents {
             let contracts = self.size_to_contracts(intent)?;
-            let price = format_price(intent.price);
-            orders.push(json!({
+            let price = intent.price.to_string();
+            let mut order = json!({
                 "contract": self.cfg.symbol,
                 "size": contracts,
                 "price": price,
                 "tif": intent.tif.to_string(),
                 "text": intent.client_order_id.to_string(),
-            }));
+            });
+            if intent.reduce_only {
+                order["reduce_only"] = Value::Bool(true);
+            }
+            orders.push(order);
         }
         Ok(Value::Array(orders))
     }
 
     fn size_to_contracts(&self, intent: &QuoteIntent) -> Result<i64> {
-        let raw = (intent.size.abs() / self.cfg.contract_size).round() as i64;
+        let raw = (intent.size.abs().to_f64() / self.cfg.contract_size).round() as i64;
         if raw == 0 {
             bail!(
                 "intent {} size {:.8} is below contract size {}",
@@ -918,6 +1140,12 @@ impl GateWsWorker {
                 PendingRequest::Cancel { resp_tx, .. } => {
                     let _ = resp_tx.send(Err(anyhow!(err.to_string())));
                 }
+                PendingRequest::Query { resp_tx, .. } => {
+                    let _ = resp_tx.send(Err(anyhow!(err.to_string())));
+                }
+                PendingRequest::Amend { resp_tx, .. } => {
+                    let _ = resp_tx.send(Err(anyhow!(err.to_string())));
+                }
             }
         }
     }
@@ -989,7 +1217,7 @@ struct ResponseError {
 }
 
 fn build_api_request(
-    cfg: &WorkerConfig,
+    creds: &ApiCredentials,
     channel: &str,
     req_param: Option<Value>,
     req_id: &str,
@@ -1001,11 +1229,11 @@ fn build_api_request(
         .transpose()?
         .unwrap_or_else(String::new);
 
-    let signature = sign_api(&cfg.api_secret, channel, &payload_str, ts);
+    let signature = sign_api(creds.api_secret.expose(), channel, &payload_str, ts);
     let mut payload = json!({
         "req_id": req_id,
         "timestamp": ts.to_string(),
-        "api_key": cfg.api_key,
+        "api_key": creds.api_key,
         "signature": signature,
     });
 
@@ -1028,7 +1256,7 @@ fn sign_api(secret: &str, channel: &str, req_param: &str, ts: i64) -> String {
     signing::hmac_sha512_hex(secret, &payload)
 }
 
-fn sign_subscribe(secret: &str, channel: &str, ts: i64) -> String {
+pub(crate) fn sign_subscribe(secret: &str, channel: &str, ts: i64) -> String {
     let payload = format!("channel={channel}&event=subscribe&time={ts}");
     signing::hmac_sha512_hex(secret, &payload)
 }
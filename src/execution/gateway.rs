@@ -1,14 +1,53 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use async_trait::async_trait;
 
+use super::money::{Price, Qty};
+use super::stops::{ServerTrigger, TriggerId, TriggerOrder};
 use super::types::{ClientOrderId, ExecutionReport, OrderAck, QuoteIntent};
 
+/// What a connector supports beyond plain order entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GatewayCapabilities {
+    /// Borrow/repay for spot-margin shorts; such a connector also provides a
+    /// [`MarginLender`](super::margin::MarginLender)
+    pub margin_borrow: bool,
+    /// Changes price/size of a resting order in place, so the order never leaves the book
+    pub native_amend: bool,
+    /// Holds stop/take-profit trigger orders on the venue side
+    pub server_triggers: bool,
+}
+
 #[async_trait]
 pub trait ExecutionGateway: Send + Sync {
+    fn capabilities(&self) -> GatewayCapabilities {
+        GatewayCapabilities::default()
+    }
     async fn submit(&self, intents: &[QuoteIntent]) -> Result<Vec<OrderAck>>;
     async fn cancel(&self, id: &ClientOrderId) -> Result<()> {
         self.cancel_batch(std::slice::from_ref(id)).await
     }
     async fn cancel_batch(&self, ids: &[ClientOrderId]) -> Result<()>;
     async fn poll_reports(&self) -> Result<Vec<ExecutionReport>>;
+    /// Moves `original` to a new price/size keeping its client id. Only called when
+    /// `capabilities().native_amend` is set; `OrderManager::amend` falls back to cancel+new.
+    async fn amend(&self, original: &QuoteIntent, _price: Price, _size: Qty) -> Result<OrderAck> {
+        bail!("gateway cannot amend order {} in place", original.client_order_id)
+    }
+    /// Current state of an order looked up by its client id; `None` if the venue never
+    /// received it. Used to resolve submits whose outcome is unknown after a timeout.
+    async fn query_by_client_id(&self, id: &ClientOrderId) -> Result<Option<ExecutionReport>> {
+        bail!("gateway cannot look up order {} by client id", id)
+    }
+    /// Places a reduce-only trigger order. Only called when `capabilities().server_triggers` is set.
+    async fn place_trigger(&self, order: &TriggerOrder) -> Result<TriggerId> {
+        bail!("gateway cannot hold trigger order {}", order.client_order_id)
+    }
+    async fn cancel_trigger(&self, id: &TriggerId) -> Result<()> {
+        bail!("gateway cannot cancel trigger order {}", id)
+    }
+    /// Open triggers on `symbol` plus recently finished ones, so fired stops can be told
+    /// apart from cancelled ones
+    async fn list_triggers(&self, symbol: &str) -> Result<Vec<ServerTrigger>> {
+        bail!("gateway cannot list trigger orders on {}", symbol)
+    }
 }
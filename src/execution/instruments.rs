@@ -0,0 +1,152 @@
+//! Instrument registry: per-venue contract metadata keyed by interned `Symbol`.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::base_classes::symbol::Symbol;
+
+use super::money::PricePrecision;
+use super::types::Venue;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractType {
+    Spot,
+    LinearPerpetual,
+    InversePerpetual,
+    Delivery,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Instrument {
+    pub venue: Venue,
+    pub symbol: Symbol,
+    pub contract: ContractType,
+    pub precision: PricePrecision,
+}
+
+impl Instrument {
+    /// Fails if the symbol cannot be split into base and quote.
+    pub fn new(
+        venue: Venue,
+        symbol: impl Into<Symbol>,
+        contract: ContractType,
+        precision: PricePrecision,
+    ) -> Result<Self> {
+        let symbol = symbol.into();
+        if symbol.base().is_none() || symbol.quote().is_none() {
+            bail!("symbol {} has no base/quote split", symbol);
+        }
+        Ok(Self {
+            venue,
+            symbol,
+            contract,
+            precision,
+        })
+    }
+
+    pub fn base(&self) -> &'static str {
+        self.symbol.base().unwrap_or_default()
+    }
+
+    pub fn quote(&self) -> &'static str {
+        self.symbol.quote().unwrap_or_default()
+    }
+
+    /// Symbol spelled the way the venue's API expects it.
+    pub fn venue_symbol(&self) -> String {
+        match self.venue {
+            Venue::Gate => self.symbol.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InstrumentRegistry {
+    instruments: HashMap<(Venue, Symbol), Instrument>,
+}
+
+impl InstrumentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an instrument; re-registering with a different spec is an error.
+    pub fn register(&mut self, instrument: Instrument) -> Result<()> {
+        let key = (instrument.venue, instrument.symbol);
+        if let Some(existing) = self.instruments.get(&key) {
+            if *existing != instrument {
+                bail!(
+                    "instrument {:?}/{} already registered with a different spec",
+                    instrument.venue,
+                    instrument.symbol
+                );
+            }
+            return Ok(());
+        }
+        self.instruments.insert(key, instrument);
+        Ok(())
+    }
+
+    pub fn get(&self, venue: Venue, symbol: Symbol) -> Option<&Instrument> {
+        self.instruments.get(&(venue, symbol))
+    }
+
+    /// Looks up an instrument by any venue spelling of its symbol.
+    pub fn resolve(&self, venue: Venue, raw: &str) -> Result<&Instrument> {
+        let symbol = Symbol::new(raw);
+        self.get(venue, symbol)
+            .ok_or_else(|| anyhow!("unknown instrument {:?}/{} (from {:?})", venue, symbol, raw))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn btc() -> Instrument {
+        Instrument::new(
+            Venue::Gate,
+            "BTC_USDT",
+            ContractType::LinearPerpetual,
+            PricePrecision::from_f64(0.1, 0.0001).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn resolves_any_spelling() {
+        let mut registry = InstrumentRegistry::new();
+        registry.register(btc()).unwrap();
+        let found = registry.resolve(Venue::Gate, "btcusdt").unwrap();
+        assert_eq!(found.base(), "BTC");
+        assert_eq!(found.quote(), "USDT");
+        assert_eq!(found.venue_symbol(), "BTC_USDT");
+        assert!(registry.resolve(Venue::Gate, "ETH_USDT").is_err());
+    }
+
+    #[test]
+    fn conflicting_registration_is_rejected() {
+        let mut registry = InstrumentRegistry::new();
+        registry.register(btc()).unwrap();
+        registry.register(btc()).unwrap();
+        let mut other = btc();
+        other.contract = ContractType::Spot;
+        assert!(registry.register(other).is_err());
+        assert_eq!(registry.len(), 1);
+    }
+}
@@ -122,7 +122,10 @@ impl InventoryTracker {
 
             if matches!(
                 report.status,
-                OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Rejected
+                OrderStatus::Filled
+                    | OrderStatus::Canceled
+                    | OrderStatus::PartiallyCanceled
+                    | OrderStatus::Rejected
             ) {
                 self.orders.remove(&report.client_order_id);
                 self.dangling.remove(&report.client_order_id);
@@ -182,7 +185,10 @@ impl InventoryTracker {
 
                 if matches!(
                     report.status,
-                    OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Rejected
+                    OrderStatus::Filled
+                        | OrderStatus::Canceled
+                        | OrderStatus::PartiallyCanceled
+                        | OrderStatus::Rejected
                 ) {
                     self.dangling.remove(&report.client_order_id);
                 }
@@ -229,6 +235,7 @@ fn infer_side_from_id(id: &ClientOrderId) -> Option<Side> {
 mod tests {
     use super::*;
     use crate::base_classes::types::Side;
+    use crate::execution::money::{Price, Qty};
     use crate::execution::types::{OrderStatus, QuoteIntent, TimeInForce, Venue};
 
     fn intent(id: &str, side: Side, contract_size: f64) -> QuoteIntent {
@@ -236,8 +243,8 @@ mod tests {
             Venue::Gate,
             "TEST".to_string(),
             side,
-            contract_size,
-            contract_size,
+            Price::from_f64(contract_size),
+            Qty::from_f64(contract_size),
             TimeInForce::PostOnly,
             ClientOrderId::new(id),
         )
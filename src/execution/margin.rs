@@ -0,0 +1,186 @@
+//! Borrow/repay orchestration for spot-margin shorts.
+//!
+//! A spot short sells coins the account does not hold, so the base currency has to be
+//! borrowed before the sell and repaid when the short is covered. [`MarginShorts`] keeps
+//! one loan per pair: it borrows the size of every short sell on top of it, accrues interest
+//! locally at the venue's hourly rate, and repays principal (and, on a full cover, the
+//! accrued interest) as cover fills arrive. Only connectors advertising
+//! `GatewayCapabilities::margin_borrow` can be used.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::gateway::GatewayCapabilities;
+
+#[async_trait]
+pub trait MarginLender: Send + Sync {
+    /// Current hourly interest rate for borrowing `currency` (0.0001 = 0.01%/h)
+    async fn hourly_rate(&self, currency: &str) -> Result<f64>;
+    async fn borrow(&self, currency: &str, amount: f64, pair: &str) -> Result<()>;
+    /// `all` repays principal and interest in one go, whatever `amount` says
+    async fn repay(&self, currency: &str, amount: f64, pair: &str, all: bool) -> Result<()>;
+}
+
+/// Outstanding borrow on one pair, in the base currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loan {
+    pub currency: String,
+    pub principal: f64,
+    pub hourly_rate: f64,
+    /// Accrued since the loan opened and not yet repaid
+    pub interest: f64,
+    pub opened_at: DateTime<Utc>,
+    last_accrual: DateTime<Utc>,
+}
+
+impl Loan {
+    fn accrue(&mut self, now: DateTime<Utc>) {
+        let hours = (now - self.last_accrual).num_milliseconds().max(0) as f64 / 3_600_000.0;
+        self.interest += self.principal * self.hourly_rate * hours;
+        self.last_accrual = now;
+    }
+}
+
+/// Result of a cover fill
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Repayment {
+    pub principal: f64,
+    pub interest: f64,
+    /// The loan is gone
+    pub closed: bool,
+}
+
+pub struct MarginShorts {
+    lender: Arc<dyn MarginLender>,
+    loans: HashMap<String, Loan>,
+    /// Interest paid on closed loans
+    interest_paid: f64,
+}
+
+impl MarginShorts {
+    pub fn new(capabilities: GatewayCapabilities, lender: Arc<dyn MarginLender>) -> Result<Self> {
+        if !capabilities.margin_borrow {
+            bail!("connector does not support margin borrowing; spot shorts are unavailable");
+        }
+        Ok(Self { lender, loans: HashMap::new(), interest_paid: 0.0 })
+    }
+
+    pub fn loan(&self, pair: &str) -> Option<&Loan> {
+        self.loans.get(pair)
+    }
+
+    pub fn interest_paid(&self) -> f64 {
+        self.interest_paid
+    }
+
+    /// Interest accrued on every open loan up to `now`
+    pub fn accrue(&mut self, now: DateTime<Utc>) -> f64 {
+        self.loans
+            .values_mut()
+            .map(|loan| {
+                loan.accrue(now);
+                loan.interest
+            })
+            .sum()
+    }
+
+    /// Borrows `size` for a short sell, adding to the pair's open loan.
+    /// Call before sending the sell; an error means the sell must not go out.
+    pub async fn borrow_for_short(&mut self, pair: &str, base: &str, size: f64, now: DateTime<Utc>) -> Result<&Loan> {
+        if size <= 0.0 || !size.is_finite() {
+            bail!("cannot borrow {} {} for {}", size, base, pair);
+        }
+        let rate = self.lender.hourly_rate(base).await?;
+        self.lender.borrow(base, size, pair).await?;
+        let loan = self.loans.entry(pair.to_string()).or_insert_with(|| Loan {
+            currency: base.to_string(),
+            principal: 0.0,
+            hourly_rate: rate,
+            interest: 0.0,
+            opened_at: now,
+            last_accrual: now,
+        });
+        loan.accrue(now);
+        loan.principal += size;
+        // The venue applies the current rate to the whole balance from here on
+        loan.hourly_rate = rate;
+        Ok(loan)
+    }
+
+    /// Repays after a cover buy of `covered`. Covering the whole loan repays
+    /// interest too and closes it; a partial cover repays principal only.
+    pub async fn repay_on_cover(&mut self, pair: &str, covered: f64, now: DateTime<Utc>) -> Result<Repayment> {
+        let Some(loan) = self.loans.get_mut(pair) else {
+            bail!("cover fill on {} without an open margin loan", pair);
+        };
+        loan.accrue(now);
+        let principal = covered.min(loan.principal);
+        let closed = principal >= loan.principal;
+        self.lender.repay(&loan.currency, principal, pair, closed).await?;
+        loan.principal -= principal;
+        if !closed {
+            return Ok(Repayment { principal, interest: 0.0, closed });
+        }
+        let interest = loan.interest;
+        self.interest_paid += interest;
+        self.loans.remove(pair);
+        Ok(Repayment { principal, interest, closed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeLender {
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl MarginLender for FakeLender {
+        async fn hourly_rate(&self, _currency: &str) -> Result<f64> {
+            Ok(0.0001)
+        }
+
+        async fn borrow(&self, currency: &str, amount: f64, pair: &str) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("borrow {} {} {}", amount, currency, pair));
+            Ok(())
+        }
+
+        async fn repay(&self, currency: &str, amount: f64, pair: &str, all: bool) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("repay {} {} {} all={}", amount, currency, pair, all));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_borrow_accrue_and_repay() {
+        let lender = Arc::new(FakeLender::default());
+        assert!(MarginShorts::new(GatewayCapabilities::default(), lender.clone()).is_err());
+        let mut shorts = MarginShorts::new(GatewayCapabilities { margin_borrow: true, ..GatewayCapabilities::default() }, lender.clone()).unwrap();
+
+        let t0 = Utc::now();
+        shorts.borrow_for_short("ETH_USDT", "ETH", 2.0, t0).await.unwrap();
+        let t1 = t0 + chrono::Duration::hours(10);
+        // 2 ETH * 0.01%/h * 10h
+        assert!((shorts.accrue(t1) - 0.002).abs() < 1e-12);
+
+        let partial = shorts.repay_on_cover("ETH_USDT", 0.5, t1).await.unwrap();
+        assert_eq!((partial.principal, partial.closed), (0.5, false));
+        let rest = shorts.repay_on_cover("ETH_USDT", 5.0, t1).await.unwrap();
+        assert_eq!((rest.principal, rest.closed), (1.5, true));
+        assert!((rest.interest - 0.002).abs() < 1e-12);
+        assert!(shorts.loan("ETH_USDT").is_none());
+        assert!(shorts.repay_on_cover("ETH_USDT", 1.0, t1).await.is_err());
+        assert_eq!(
+            *lender.calls.lock().unwrap(),
+            ["borrow 2 ETH ETH_USDT", "repay 0.5 ETH ETH_USDT all=false", "repay 1.5 ETH ETH_USDT all=true"]
+        );
+    }
+}
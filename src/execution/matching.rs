@@ -0,0 +1,478 @@
+//! In-process matching engine for tests and paper trading
+//!
+//! A venue in miniature: one book per symbol with price-time priority, fills at the
+//! resting order's price, partial fills, the four time-in-force modes and optional
+//! self-trade prevention between orders of one account. Books start empty; tests seed
+//! them with [`MatchingEngine::add_liquidity`], paper trading fills resting orders from
+//! the public tape with [`MatchingEngine::on_trade`]. Amounts stay decimal, as on the
+//! rest of the order path, so filled sizes add up exactly.
+//!
+//! Not modelled: fees, margin and positions (so `reduce_only` is not enforced), tick and
+//! lot validation, latency. [`super::sim_exchange::SimulatedExchange`] puts the engine
+//! behind [`super::ExecutionGateway`].
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::base_classes::types::Side;
+
+use super::money::{Price, Qty};
+use super::types::{ClientOrderId, OrderStatus, QuoteIntent, TimeInForce};
+
+/// Account of the liquidity added by [`MatchingEngine::add_liquidity`]
+pub const MARKET_ACCOUNT: &str = "market";
+
+/// What happens when an order would trade with a resting order of its own account
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTradePrevention {
+    /// Trade as with anyone else
+    #[default]
+    None,
+    /// Cancel the rest of the incoming order (Gate `cn`)
+    CancelNewest,
+    /// Cancel the resting order and keep matching (Gate `co`)
+    CancelOldest,
+    /// Cancel both (Gate `cb`)
+    CancelBoth,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MatchingRules {
+    #[serde(default)]
+    pub self_trade: SelfTradePrevention,
+    /// A tape print at exactly a resting order's price fills it. Off by default: the
+    /// order is assumed to queue behind the displayed size, so only a print through its
+    /// price proves it would have traded
+    #[serde(default)]
+    pub fill_at_touch: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineOrder {
+    pub id: u64,
+    pub account: String,
+    pub client_order_id: Option<ClientOrderId>,
+    pub symbol: String,
+    pub side: Side,
+    pub price: Price,
+    pub size: Qty,
+    pub filled: Qty,
+    pub tif: TimeInForce,
+    pub status: OrderStatus,
+    /// Sum of price * size over the fills
+    notional: Decimal,
+}
+
+impl EngineOrder {
+    fn new(
+        account: &str,
+        client_order_id: Option<ClientOrderId>,
+        symbol: &str,
+        side: Side,
+        price: Price,
+        size: Qty,
+        tif: TimeInForce,
+    ) -> Self {
+        Self {
+            id: 0,
+            account: account.to_string(),
+            client_order_id,
+            symbol: symbol.to_string(),
+            side,
+            price,
+            size,
+            filled: Qty(Decimal::ZERO),
+            tif,
+            status: OrderStatus::New,
+            notional: Decimal::ZERO,
+        }
+    }
+
+    pub fn remaining(&self) -> Qty {
+        Qty(self.size.0 - self.filled.0)
+    }
+
+    pub fn avg_fill_price(&self) -> Option<Price> {
+        (!self.filled.is_zero()).then(|| Price(self.notional / self.filled.0))
+    }
+
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, OrderStatus::New | OrderStatus::PartiallyFilled)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fill {
+    /// Incoming order; `None` for a tape print
+    pub taker: Option<u64>,
+    pub maker: u64,
+    pub price: Price,
+    pub size: Qty,
+}
+
+/// Outcome of a submission, cancel or tape print
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchResult {
+    pub fills: Vec<Fill>,
+    /// Orders whose status or fill changed, the submitted one first
+    pub touched: Vec<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Book {
+    bids: BTreeMap<Decimal, VecDeque<u64>>,
+    asks: BTreeMap<Decimal, VecDeque<u64>>,
+}
+
+impl Book {
+    fn levels(&mut self, side: Side) -> &mut BTreeMap<Decimal, VecDeque<u64>> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    fn remove(&mut self, side: Side, price: Decimal, id: u64) {
+        let levels = self.levels(side);
+        if let Some(queue) = levels.get_mut(&price) {
+            queue.retain(|queued| *queued != id);
+            if queue.is_empty() {
+                levels.remove(&price);
+            }
+        }
+    }
+
+    /// Resting orders on `side` at prices `accept` takes, in priority order
+    fn queue(&self, side: Side, accept: impl Fn(Decimal) -> bool) -> Vec<u64> {
+        let levels: Box<dyn Iterator<Item = (&Decimal, &VecDeque<u64>)>> = match side {
+            Side::Bid => Box::new(self.bids.iter().rev()),
+            Side::Ask => Box::new(self.asks.iter()),
+        };
+        levels.take_while(|(price, _)| accept(**price)).flat_map(|(_, queue)| queue.iter().copied()).collect()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MatchingEngine {
+    rules: MatchingRules,
+    books: HashMap<String, Book>,
+    orders: HashMap<u64, EngineOrder>,
+    next_id: u64,
+}
+
+impl MatchingEngine {
+    pub fn new(rules: MatchingRules) -> Self {
+        Self { rules, ..Self::default() }
+    }
+
+    pub fn rules(&self) -> &MatchingRules {
+        &self.rules
+    }
+
+    pub fn order(&self, id: u64) -> Option<&EngineOrder> {
+        self.orders.get(&id)
+    }
+
+    pub fn best_bid(&self, symbol: &str) -> Option<Price> {
+        self.books.get(symbol)?.bids.keys().next_back().copied().map(Price)
+    }
+
+    pub fn best_ask(&self, symbol: &str) -> Option<Price> {
+        self.books.get(symbol)?.asks.keys().next().copied().map(Price)
+    }
+
+    /// Resting size at one price level
+    pub fn depth(&self, symbol: &str, side: Side, price: Price) -> Qty {
+        let Some(book) = self.books.get(symbol) else {
+            return Qty(Decimal::ZERO);
+        };
+        let levels = match side {
+            Side::Bid => &book.bids,
+            Side::Ask => &book.asks,
+        };
+        let size = levels.get(&price.0).into_iter().flatten().map(|id| self.orders[id].remaining().0).sum();
+        Qty(size)
+    }
+
+    /// GTC order of [`MARKET_ACCOUNT`], the counterparty of orders under test; like any
+    /// order it trades with whatever it crosses before it rests
+    pub fn add_liquidity(&mut self, symbol: &str, side: Side, price: Price, size: Qty) -> (u64, MatchResult) {
+        self.place(EngineOrder::new(MARKET_ACCOUNT, None, symbol, side, price, size, TimeInForce::Gtc))
+    }
+
+    pub fn submit(&mut self, account: &str, intent: &QuoteIntent) -> (u64, MatchResult) {
+        self.place(EngineOrder::new(
+            account,
+            Some(intent.client_order_id.clone()),
+            &intent.symbol,
+            intent.side,
+            intent.price,
+            intent.size,
+            intent.tif,
+        ))
+    }
+
+    /// Cancels an open order; `None` if it is unknown or already finished
+    pub fn cancel(&mut self, id: u64) -> Option<&EngineOrder> {
+        if !self.orders.get(&id)?.is_open() {
+            return None;
+        }
+        self.close(id, OrderStatus::Canceled);
+        self.orders.get(&id)
+    }
+
+    /// A public trade at `price` fills resting orders it went through, oldest first,
+    /// each at its own price; `size: None` for a price-only feed that fills them all.
+    /// Liquidity of [`MARKET_ACCOUNT`] stands for the rest of the market and is left alone
+    pub fn on_trade(&mut self, symbol: &str, price: Price, size: Option<Qty>) -> MatchResult {
+        let mut result = MatchResult::default();
+        let Some(book) = self.books.get(symbol) else {
+            return result;
+        };
+        let touch = self.rules.fill_at_touch;
+        let mut makers = book.queue(Side::Bid, |level| level > price.0 || (touch && level == price.0));
+        makers.extend(book.queue(Side::Ask, |level| level < price.0 || (touch && level == price.0)));
+        let mut left = size.map(|size| size.0);
+        for maker in makers {
+            if left.is_some_and(|left| left <= Decimal::ZERO) {
+                break;
+            }
+            let order = &self.orders[&maker];
+            if order.account == MARKET_ACCOUNT {
+                continue;
+            }
+            let fill = left.map_or(order.remaining().0, |left| left.min(order.remaining().0));
+            let at = order.price;
+            self.fill(maker, at, fill);
+            left = left.map(|left| left - fill);
+            result.fills.push(Fill { taker: None, maker, price: at, size: Qty(fill) });
+            result.touched.push(maker);
+        }
+        result
+    }
+
+    fn place(&mut self, mut order: EngineOrder) -> (u64, MatchResult) {
+        self.next_id += 1;
+        let id = self.next_id;
+        order.id = id;
+        let (account, symbol, price, size, tif) =
+            (order.account.clone(), order.symbol.clone(), order.price, order.size, order.tif);
+        self.orders.insert(id, order);
+        let mut result = MatchResult { touched: vec![id], ..MatchResult::default() };
+        if price.0 <= Decimal::ZERO || size.0 <= Decimal::ZERO {
+            self.orders.get_mut(&id).expect("just placed").status = OrderStatus::Rejected;
+            return (id, result);
+        }
+
+        let makers = self.crossing(id);
+        match tif {
+            TimeInForce::PostOnly if !makers.is_empty() => {
+                self.orders.get_mut(&id).expect("just placed").status = OrderStatus::Rejected;
+                return (id, result);
+            }
+            TimeInForce::Fok if self.fillable(&account, &makers) < size.0 => {
+                self.orders.get_mut(&id).expect("just placed").status = OrderStatus::Canceled;
+                return (id, result);
+            }
+            _ => {}
+        }
+        self.take(id, makers, &mut result);
+
+        let order = self.orders.get_mut(&id).expect("just placed");
+        if order.is_open() {
+            if tif.is_immediate() {
+                order.status = OrderStatus::Canceled;
+            } else {
+                let (side, price) = (order.side, order.price.0);
+                self.books.entry(symbol).or_default().levels(side).entry(price).or_default().push_back(id);
+            }
+        }
+        (id, result)
+    }
+
+    /// Resting orders the incoming `id` crosses, in priority order
+    fn crossing(&self, id: u64) -> Vec<u64> {
+        let order = &self.orders[&id];
+        let Some(book) = self.books.get(&order.symbol) else {
+            return Vec::new();
+        };
+        let limit = order.price.0;
+        match order.side {
+            Side::Bid => book.queue(Side::Ask, |level| level <= limit),
+            Side::Ask => book.queue(Side::Bid, |level| level >= limit),
+        }
+    }
+
+    /// Size a fill-or-kill order of `account` could take from `makers`
+    fn fillable(&self, account: &str, makers: &[u64]) -> Decimal {
+        let mut total = Decimal::ZERO;
+        for maker in makers {
+            let order = &self.orders[maker];
+            if order.account == account {
+                match self.rules.self_trade {
+                    SelfTradePrevention::None => {}
+                    SelfTradePrevention::CancelOldest => continue,
+                    SelfTradePrevention::CancelNewest | SelfTradePrevention::CancelBoth => break,
+                }
+            }
+            total += order.remaining().0;
+        }
+        total
+    }
+
+    fn take(&mut self, taker: u64, makers: Vec<u64>, result: &mut MatchResult) {
+        let account = self.orders[&taker].account.clone();
+        for maker in makers {
+            let left = self.orders[&taker].remaining().0;
+            if left.is_zero() || !self.orders[&taker].is_open() {
+                break;
+            }
+            if self.orders[&maker].account == account {
+                match self.rules.self_trade {
+                    SelfTradePrevention::None => {}
+                    SelfTradePrevention::CancelNewest => {
+                        self.close(taker, OrderStatus::Canceled);
+                        break;
+                    }
+                    SelfTradePrevention::CancelOldest => {
+                        self.close(maker, OrderStatus::Canceled);
+                        result.touched.push(maker);
+                        continue;
+                    }
+                    SelfTradePrevention::CancelBoth => {
+                        self.close(maker, OrderStatus::Canceled);
+                        self.close(taker, OrderStatus::Canceled);
+                        result.touched.push(maker);
+                        break;
+                    }
+                }
+            }
+            let resting = &self.orders[&maker];
+            let (price, size) = (resting.price, left.min(resting.remaining().0));
+            self.fill(taker, price, size);
+            self.fill(maker, price, size);
+            result.fills.push(Fill { taker: Some(taker), maker, price, size: Qty(size) });
+            result.touched.push(maker);
+        }
+    }
+
+    /// Books a fill; a filled resting order leaves the book
+    fn fill(&mut self, id: u64, price: Price, size: Decimal) {
+        let order = self.orders.get_mut(&id).expect("filled order exists");
+        order.filled = Qty(order.filled.0 + size);
+        order.notional += price.0 * size;
+        if order.remaining().is_zero() {
+            order.status = OrderStatus::Filled;
+            let (symbol, side, price) = (order.symbol.clone(), order.side, order.price.0);
+            if let Some(book) = self.books.get_mut(&symbol) {
+                book.remove(side, price, id);
+            }
+        } else {
+            order.status = OrderStatus::PartiallyFilled;
+        }
+    }
+
+    fn close(&mut self, id: u64, status: OrderStatus) {
+        let order = self.orders.get_mut(&id).expect("closed order exists");
+        order.status = status;
+        let (symbol, side, price) = (order.symbol.clone(), order.side, order.price.0);
+        if let Some(book) = self.books.get_mut(&symbol) {
+            book.remove(side, price, id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::types::Venue;
+
+    const SYMBOL: &str = "BTC_USDT";
+
+    fn p(price: f64) -> Price {
+        Price::from_f64(price)
+    }
+
+    fn q(size: f64) -> Qty {
+        Qty::from_f64(size)
+    }
+
+    fn intent(id: &str, side: Side, price: f64, size: f64, tif: TimeInForce) -> QuoteIntent {
+        QuoteIntent::new(Venue::Gate, SYMBOL, side, p(price), q(size), tif, ClientOrderId::new(id))
+    }
+
+    #[test]
+    fn test_price_time_priority_partial_fills_and_time_in_force() {
+        let mut engine = MatchingEngine::default();
+        let (first, _) = engine.add_liquidity(SYMBOL, Side::Ask, p(100.0), q(1.0));
+        let (second, _) = engine.add_liquidity(SYMBOL, Side::Ask, p(100.0), q(2.0));
+        let (deeper, _) = engine.add_liquidity(SYMBOL, Side::Ask, p(101.0), q(5.0));
+
+        let (buy, result) = engine.submit("alice", &intent("b1", Side::Bid, 101.0, 4.0, TimeInForce::Gtc));
+        let makers: Vec<(u64, Price, Qty)> = result.fills.iter().map(|f| (f.maker, f.price, f.size)).collect();
+        assert_eq!(makers, vec![(first, p(100.0), q(1.0)), (second, p(100.0), q(2.0)), (deeper, p(101.0), q(1.0))]);
+        let order = engine.order(buy).unwrap();
+        assert_eq!((order.status.clone(), order.avg_fill_price()), (OrderStatus::Filled, Some(p(100.25))));
+        assert_eq!(engine.order(deeper).unwrap().status, OrderStatus::PartiallyFilled);
+        assert_eq!((engine.best_ask(SYMBOL), engine.depth(SYMBOL, Side::Ask, p(101.0))), (Some(p(101.0)), q(4.0)));
+
+        // IOC takes what is there and drops the rest
+        let (bid, _) = engine.submit("alice", &intent("b2", Side::Bid, 99.0, 2.0, TimeInForce::Gtc));
+        let (ioc, result) = engine.submit("bob", &intent("s1", Side::Ask, 98.0, 3.0, TimeInForce::Ioc));
+        assert_eq!(result.touched, vec![ioc, bid]);
+        let order = engine.order(ioc).unwrap();
+        assert_eq!((order.status.clone(), order.filled), (OrderStatus::Canceled, q(2.0)));
+        assert_eq!(engine.best_bid(SYMBOL), None);
+
+        // FOK needs it all; post-only never takes
+        let (fok, result) = engine.submit("bob", &intent("b3", Side::Bid, 102.0, 10.0, TimeInForce::Fok));
+        assert!(result.fills.is_empty());
+        assert_eq!(engine.order(fok).unwrap().status, OrderStatus::Canceled);
+        assert_eq!(engine.depth(SYMBOL, Side::Ask, p(101.0)), q(4.0));
+        let (crossing, _) = engine.submit("bob", &intent("b4", Side::Bid, 101.0, 1.0, TimeInForce::PostOnly));
+        assert_eq!(engine.order(crossing).unwrap().status, OrderStatus::Rejected);
+        let (maker, _) = engine.submit("bob", &intent("b5", Side::Bid, 100.5, 1.0, TimeInForce::PostOnly));
+        assert_eq!((engine.order(maker).unwrap().status.clone(), engine.best_bid(SYMBOL)), (OrderStatus::New, Some(p(100.5))));
+        assert_eq!(engine.cancel(maker).map(|o| o.status.clone()), Some(OrderStatus::Canceled));
+        assert!(engine.cancel(maker).is_none());
+    }
+
+    #[test]
+    fn test_self_trade_prevention_modes() {
+        let cases = [
+            (SelfTradePrevention::None, OrderStatus::Filled, OrderStatus::Filled, 1),
+            (SelfTradePrevention::CancelNewest, OrderStatus::Canceled, OrderStatus::New, 0),
+            (SelfTradePrevention::CancelOldest, OrderStatus::Filled, OrderStatus::Canceled, 1),
+            (SelfTradePrevention::CancelBoth, OrderStatus::Canceled, OrderStatus::Canceled, 0),
+        ];
+        for (self_trade, taker_status, own_status, fills) in cases {
+            let mut engine = MatchingEngine::new(MatchingRules { self_trade, ..MatchingRules::default() });
+            let (own, _) = engine.submit("alice", &intent("s1", Side::Ask, 100.0, 1.0, TimeInForce::Gtc));
+            engine.add_liquidity(SYMBOL, Side::Ask, p(100.0), q(1.0));
+            let (taker, result) = engine.submit("alice", &intent("b1", Side::Bid, 100.0, 1.0, TimeInForce::Gtc));
+            assert_eq!(engine.order(taker).unwrap().status, taker_status, "{:?}", self_trade);
+            assert_eq!(engine.order(own).unwrap().status, own_status, "{:?}", self_trade);
+            assert_eq!(result.fills.len(), fills, "{:?}", self_trade);
+        }
+    }
+
+    #[test]
+    fn test_tape_prints_fill_resting_orders_through_their_price() {
+        let mut engine = MatchingEngine::default();
+        let (bid, _) = engine.submit("alice", &intent("b1", Side::Bid, 99.0, 1.0, TimeInForce::Gtc));
+        let (market, _) = engine.add_liquidity(SYMBOL, Side::Bid, p(99.5), q(3.0));
+        assert!(engine.on_trade(SYMBOL, p(99.0), None).fills.is_empty(), "at the touch the order is still queued");
+
+        let result = engine.on_trade(SYMBOL, p(98.5), Some(q(0.4)));
+        assert_eq!(result.fills, vec![Fill { taker: None, maker: bid, price: p(99.0), size: q(0.4) }]);
+        assert_eq!(engine.order(market).unwrap().filled, q(0.0));
+
+        let mut touching = MatchingEngine::new(MatchingRules { fill_at_touch: true, ..MatchingRules::default() });
+        let (ask, _) = touching.submit("alice", &intent("s1", Side::Ask, 101.0, 1.0, TimeInForce::Gtc));
+        touching.on_trade(SYMBOL, p(101.0), None);
+        assert_eq!(touching.order(ask).unwrap().status, OrderStatus::Filled);
+    }
+}
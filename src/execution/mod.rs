@@ -1,22 +1,59 @@
 #![allow(dead_code)]
 
+pub mod clock;
+pub mod close;
+pub mod compliance;
 pub mod dry_run;
+pub mod fx;
 pub mod gate_client;
 pub mod gate_ws;
 pub mod gateway;
+pub mod instruments;
 pub mod inventory;
+pub mod margin;
+pub mod matching;
+pub mod money;
 pub mod order_manager;
+pub mod sim_exchange;
+pub mod stops;
+pub mod trailing;
+pub mod treasury;
 pub mod types;
+pub mod user_stream;
+pub mod venue_status;
 
+pub use clock::{ClockConfig, ClockSync, ServerClock, SkewSample, TimeEndpoint};
+pub use close::{CloseAmount, PartialClose, PositionCloser};
+pub use compliance::{
+    ComplianceChecker, ComplianceViolation, Exposure, OrderCheck, PercentPrice, TradingRules,
+};
 pub use dry_run::DryRunGateway;
+pub use fx::{FxConfig, FxRates, Valuation, ValuedAmount};
 pub use gate_client::{GateClient, GateCredentials};
 pub use gate_ws::{GateWsConfig, GateWsGateway};
-pub use gateway::ExecutionGateway;
+pub use gateway::{ExecutionGateway, GatewayCapabilities};
+pub use margin::{Loan, MarginLender, MarginShorts, Repayment};
+pub use instruments::{ContractType, Instrument, InstrumentRegistry};
 pub use inventory::{
     InventoryReportOutcome, InventoryTracker, InventoryUpdate, InventoryUpdateSource,
 };
-pub use order_manager::OrderManager;
+pub use matching::{EngineOrder, Fill, MatchResult, MatchingEngine, MatchingRules, SelfTradePrevention};
+pub use money::{Price, PricePrecision, Qty, Rounding};
+pub use order_manager::{AmendOutcome, OrderManager, SUBMIT_LOOKUPS, SUBMIT_TIMEOUT};
+pub use sim_exchange::SimulatedExchange;
+pub use stops::{
+    ServerTrigger, StopManager, StopSyncReport, TriggerId, TriggerKind, TriggerOrder, TriggerStatus,
+};
+pub use trailing::{TrailChanges, TrailTrigger, TrailingState, TrailingStops};
+pub use treasury::{
+    MarginSnapshot, TransferDirection, Treasury, TreasuryConfig, TreasuryDecision, TreasuryPlanner,
+};
+pub use user_stream::{UserEvent, UserStream, UserStreamConfig, UserStreamParser};
+pub use venue_status::{
+    MaintenanceAction, MaintenanceConfig, MaintenanceGuard, MaintenancePhase, MaintenanceWindow, StatusEndpoint,
+    StatusPoller, VenueStatus,
+};
 pub use types::{
-    ClientOrderId, ExchangeOrderId, ExecutionReport, OrderAck, OrderStatus, QuoteIntent,
-    TimeInForce, Venue,
+    ClientOrderId, ClientOrderIdGenerator, ExchangeOrderId, ExecutionReport, OrderAck,
+    OrderStatus, QuoteIntent, TimeInForce, Venue,
 };
@@ -0,0 +1,231 @@
+//! Decimal price/quantity types for the order path.
+//!
+//! Strategies compute in `f64`; everything from `QuoteIntent` onwards is carried as
+//! `rust_decimal::Decimal` so that tick/lot rounding and PnL arithmetic are exact.
+//! Conversion from `f64` is explicit and fails loudly on NaN/inf.
+
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use anyhow::{anyhow, bail, Result};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+/// Direction used when snapping a value onto a tick/lot grid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+    Nearest,
+}
+
+fn decimal_from_f64(value: f64, what: &str) -> Result<Decimal> {
+    if !value.is_finite() {
+        bail!("{} {} is not finite", what, value);
+    }
+    Decimal::from_f64(value).ok_or_else(|| anyhow!("{} {} is out of decimal range", what, value))
+}
+
+fn snap(value: Decimal, step: Decimal, rounding: Rounding) -> Decimal {
+    if step <= Decimal::ZERO {
+        return value;
+    }
+    let strategy = match rounding {
+        Rounding::Down => RoundingStrategy::ToNegativeInfinity,
+        Rounding::Up => RoundingStrategy::ToPositiveInfinity,
+        Rounding::Nearest => RoundingStrategy::MidpointAwayFromZero,
+    };
+    ((value / step).round_dp_with_strategy(0, strategy) * step).normalize()
+}
+
+macro_rules! decimal_newtype {
+    ($name:ident, $what:literal) => {
+        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub Decimal);
+
+        impl $name {
+            pub const ZERO: Self = Self(Decimal::ZERO);
+
+            pub fn new(value: Decimal) -> Self {
+                Self(value)
+            }
+
+            /// Converts from `f64`, rejecting NaN/inf.
+            pub fn try_from_f64(value: f64) -> Result<Self> {
+                decimal_from_f64(value, $what).map(Self)
+            }
+
+            /// Converts from `f64`. Panics on NaN/inf, so it is for literals and tests;
+            /// code building orders from computed values uses `try_from_f64`.
+            pub fn from_f64(value: f64) -> Self {
+                match Self::try_from_f64(value) {
+                    Ok(v) => v,
+                    Err(err) => panic!("{}", err),
+                }
+            }
+
+            /// Converts to `f64` for strategy math and logs. Every `Decimal` is within
+            /// `f64` range, so a failed conversion is a bug and panics with the value.
+            pub fn to_f64(self) -> f64 {
+                self.0
+                    .to_f64()
+                    .unwrap_or_else(|| panic!("{} {} does not convert to f64", $what, self.0))
+            }
+
+            pub fn as_decimal(self) -> Decimal {
+                self.0
+            }
+
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+
+            pub fn is_zero(self) -> bool {
+                self.0.is_zero()
+            }
+
+            pub fn is_sign_negative(self) -> bool {
+                self.0.is_sign_negative() && !self.0.is_zero()
+            }
+
+            /// Snaps onto a grid of `step` (tick size for prices, lot size for quantities).
+            pub fn round_to(self, step: Decimal, rounding: Rounding) -> Self {
+                Self(snap(self.0, step, rounding))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0.normalize())
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl From<Decimal> for $name {
+            fn from(value: Decimal) -> Self {
+                Self(value)
+            }
+        }
+    };
+}
+
+decimal_newtype!(Price, "price");
+decimal_newtype!(Qty, "quantity");
+
+impl Mul<Qty> for Price {
+    type Output = Decimal;
+    /// Notional value in quote currency.
+    fn mul(self, rhs: Qty) -> Decimal {
+        self.0 * rhs.0
+    }
+}
+
+/// Realized PnL of a round trip in quote currency (positive `qty` = long).
+pub fn realized_pnl(entry: Price, exit: Price, qty: Qty) -> Decimal {
+    (exit.0 - entry.0) * qty.0
+}
+
+/// Exchange tick and lot grid for one instrument.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PricePrecision {
+    pub tick_size: Decimal,
+    pub lot_size: Decimal,
+}
+
+impl PricePrecision {
+    pub fn new(tick_size: Decimal, lot_size: Decimal) -> Self {
+        Self { tick_size, lot_size }
+    }
+
+    pub fn from_f64(tick_size: f64, lot_size: f64) -> Result<Self> {
+        Ok(Self {
+            tick_size: decimal_from_f64(tick_size, "tick size")?,
+            lot_size: decimal_from_f64(lot_size, "lot size")?,
+        })
+    }
+
+    /// Buy prices round down and sell prices round up so a rounded quote is never
+    /// more aggressive than the strategy asked for.
+    pub fn price_for_side(&self, price: f64, is_buy: bool) -> Result<Price> {
+        let rounding = if is_buy { Rounding::Down } else { Rounding::Up };
+        Ok(Price::try_from_f64(price)?.round_to(self.tick_size, rounding))
+    }
+
+    /// Quantities round down to the lot; anything that rounds to zero is an error.
+    pub fn qty(&self, qty: f64) -> Result<Qty> {
+        let rounded = Qty::try_from_f64(qty)?.round_to(self.lot_size, Rounding::Down);
+        if rounded.is_zero() {
+            bail!("quantity {} is below lot size {}", qty, self.lot_size);
+        }
+        Ok(rounded)
+    }
+
+    pub fn is_on_tick(&self, price: Price) -> bool {
+        self.tick_size <= Decimal::ZERO || (price.0 % self.tick_size).is_zero()
+    }
+
+    pub fn is_on_lot(&self, qty: Qty) -> bool {
+        self.lot_size <= Decimal::ZERO || (qty.0 % self.lot_size).is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn d(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn rounds_to_tick_without_float_drift() {
+        let precision = PricePrecision::new(d("0.01"), d("0.001"));
+        assert_eq!(precision.price_for_side(0.1 + 0.2, true).unwrap().to_string(), "0.3");
+        assert_eq!(precision.price_for_side(100.123, true).unwrap().to_string(), "100.12");
+        assert_eq!(precision.price_for_side(100.123, false).unwrap().to_string(), "100.13");
+        assert!(precision.is_on_tick(precision.price_for_side(7.777, false).unwrap()));
+    }
+
+    #[test]
+    fn qty_below_lot_is_rejected() {
+        let precision = PricePrecision::new(d("0.01"), d("0.1"));
+        assert_eq!(precision.qty(1.29).unwrap().to_string(), "1.2");
+        assert!(precision.qty(0.05).is_err());
+    }
+
+    #[test]
+    fn non_finite_values_are_rejected() {
+        assert!(Price::try_from_f64(f64::NAN).is_err());
+        assert!(Qty::try_from_f64(f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn pnl_is_exact() {
+        let pnl = realized_pnl(Price::new(d("0.1")), Price::new(d("0.3")), Qty::new(d("3")));
+        assert_eq!(pnl, d("0.6"));
+        assert_eq!(Price::new(d("2.5")) * Qty::new(d("4")), d("10"));
+    }
+}
@@ -1,19 +1,73 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use tokio::sync::Mutex;
 
 use super::gateway::ExecutionGateway;
+use super::money::{Price, Qty};
 use super::types::{ClientOrderId, ExecutionReport, OrderAck, OrderStatus, QuoteIntent};
 
+/// How `OrderManager::amend` moved the order
+#[derive(Debug, Clone)]
+pub enum AmendOutcome {
+    /// Changed in place under the original client id
+    Amended(OrderAck),
+    /// Cancelled and placed again under the replacement id
+    Replaced(OrderAck),
+}
+
+/// How many recently sent client ids `submit_idempotent` remembers to refuse reuse.
+/// Generators never repeat an id, so this only has to cover retries of the same intent.
+const RECENT_ID_CAPACITY: usize = 100_000;
+/// First pause between lookups of an order whose submit outcome is unknown; doubles
+/// up to `LOOKUP_BACKOFF_MAX`, since the venue may not have indexed a fresh order yet
+const LOOKUP_BACKOFF: Duration = Duration::from_millis(50);
+const LOOKUP_BACKOFF_MAX: Duration = Duration::from_secs(1);
+/// `submit_idempotent` settings for the quoting loops: the longest a submit may take, and
+/// how many client-id lookups (about 1.5 s with backoff) settle an unknown outcome
+pub const SUBMIT_TIMEOUT: Duration = Duration::from_secs(3);
+pub const SUBMIT_LOOKUPS: usize = 6;
+
+/// Client ids sent most recently, oldest forgotten first
+#[derive(Debug)]
+struct RecentIds {
+    ids: HashSet<ClientOrderId>,
+    order: VecDeque<ClientOrderId>,
+    capacity: usize,
+}
+
+impl RecentIds {
+    fn new(capacity: usize) -> Self {
+        Self { ids: HashSet::new(), order: VecDeque::new(), capacity }
+    }
+
+    fn contains(&self, id: &ClientOrderId) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn insert(&mut self, id: ClientOrderId) {
+        if !self.ids.insert(id.clone()) {
+            return;
+        }
+        self.order.push_back(id);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+}
+
 /// Coordinates order submission, tracking, and reconciliation for a single venue.
 pub struct OrderManager {
     gateway: Arc<dyn ExecutionGateway>,
     inflight: Mutex<HashMap<ClientOrderId, QuoteIntent>>,
+    /// Recently sent client ids; reusing one would alias two orders
+    used_ids: Mutex<RecentIds>,
     last_persist: Mutex<Instant>,
     persist_interval: Duration,
 }
@@ -23,6 +77,7 @@ impl OrderManager {
         Self {
             gateway,
             inflight: Mutex::new(HashMap::new()),
+            used_ids: Mutex::new(RecentIds::new(RECENT_ID_CAPACITY)),
             last_persist: Mutex::new(Instant::now()),
             persist_interval,
         }
@@ -39,6 +94,139 @@ impl OrderManager {
         Ok(acks)
     }
 
+    /// Submit that never double-enters. A timeout or transport error leaves the outcome
+    /// unknown, so nothing is sent again: each order is looked up by client id up to
+    /// `lookups` times with backoff, because a fresh order may not be indexed yet. Found
+    /// orders are acked from the venue's state; an order still not visible after the
+    /// last lookup, or a failed lookup, is an error for the caller to reconcile.
+    pub async fn submit_idempotent(
+        &self,
+        intents: Vec<QuoteIntent>,
+        timeout: Duration,
+        lookups: usize,
+    ) -> Result<Vec<OrderAck>> {
+        {
+            let mut used = self.used_ids.lock().await;
+            for intent in &intents {
+                if used.contains(&intent.client_order_id) {
+                    bail!("client order id {} was already submitted", intent.client_order_id);
+                }
+            }
+            for intent in &intents {
+                used.insert(intent.client_order_id.clone());
+            }
+        }
+        let err = match tokio::time::timeout(timeout, self.gateway.submit(&intents)).await {
+            Ok(Ok(acks)) => {
+                self.track(intents).await;
+                return Ok(acks);
+            }
+            Ok(Err(err)) => err,
+            Err(_) => anyhow::anyhow!("submit timed out after {:?}", timeout),
+        };
+        log::warn!("submit of {} orders failed ({:#}); looking them up by client id", intents.len(), err);
+        let mut acks = Vec::with_capacity(intents.len());
+        let mut found = Vec::with_capacity(intents.len());
+        let mut unknown = Vec::new();
+        for intent in intents {
+            match self.lookup(&intent.client_order_id, lookups).await {
+                Ok(Some(report)) => {
+                    acks.push(OrderAck {
+                        client_order_id: report.client_order_id,
+                        exchange_order_id: report.exchange_order_id,
+                    });
+                    found.push(intent);
+                }
+                Ok(None) => unknown.push(intent.client_order_id),
+                Err(query_err) => {
+                    log::error!("order {} state unknown: {:#}", intent.client_order_id, query_err);
+                    self.track(found).await;
+                    return Err(query_err.context(format!(
+                        "submit failed ({:#}) and order {} could not be looked up",
+                        err, intent.client_order_id
+                    )));
+                }
+            }
+        }
+        // Orders the venue did show stay tracked, so `cancel_all` still reaches them
+        self.track(found).await;
+        if !unknown.is_empty() {
+            let ids: Vec<String> = unknown.iter().map(ClientOrderId::to_string).collect();
+            log::error!("orders {} not visible after {} lookups; not resubmitting", ids.join(","), lookups.max(1));
+            bail!(
+                "submit failed ({:#}) and orders {} were not found after {} lookups; reconcile before resubmitting",
+                err,
+                ids.join(","),
+                lookups.max(1)
+            );
+        }
+        Ok(acks)
+    }
+
+    /// Looks an order up by client id up to `attempts` times, backing off between misses
+    async fn lookup(&self, id: &ClientOrderId, attempts: usize) -> Result<Option<ExecutionReport>> {
+        let attempts = attempts.max(1);
+        let mut backoff = LOOKUP_BACKOFF;
+        for attempt in 1..=attempts {
+            if let Some(report) = self.gateway.query_by_client_id(id).await? {
+                return Ok(Some(report));
+            }
+            if attempt < attempts {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(LOOKUP_BACKOFF_MAX);
+            }
+        }
+        Ok(None)
+    }
+
+    async fn track(&self, intents: Vec<QuoteIntent>) {
+        if intents.is_empty() {
+            return;
+        }
+        let mut inflight = self.inflight.lock().await;
+        for intent in intents {
+            inflight.insert(intent.client_order_id.clone(), intent);
+        }
+        drop(inflight);
+        self.maybe_persist().await;
+    }
+
+    /// Moves a tracked order to `price`/`size`. Gateways with native amend keep the order
+    /// in the book the whole time; otherwise, or if the amend is refused, the order is
+    /// cancelled and re-placed under `replacement_id`. A failed cancel aborts the
+    /// replacement, so a filled order is never followed by a second one.
+    pub async fn amend(
+        &self,
+        id: &ClientOrderId,
+        price: Price,
+        size: Qty,
+        replacement_id: ClientOrderId,
+    ) -> Result<AmendOutcome> {
+        let Some(original) = self.inflight.lock().await.get(id).cloned() else {
+            bail!("cannot amend {}: not in flight", id);
+        };
+        if self.gateway.capabilities().native_amend {
+            match self.gateway.amend(&original, price, size).await {
+                Ok(ack) => {
+                    if let Some(intent) = self.inflight.lock().await.get_mut(id) {
+                        intent.price = price;
+                        intent.size = size;
+                    }
+                    return Ok(AmendOutcome::Amended(ack));
+                }
+                Err(err) => log::warn!("native amend of {} failed, falling back to cancel+new: {:#}", id, err),
+            }
+        }
+        self.cancel(id).await?;
+        let mut replacement = original;
+        replacement.price = price;
+        replacement.size = size;
+        replacement.client_order_id = replacement_id;
+        let mut acks = self.submit(vec![replacement]).await?;
+        let ack = acks.pop().ok_or_else(|| anyhow::anyhow!("replacement for {} was not acked", id))?;
+        Ok(AmendOutcome::Replaced(ack))
+    }
+
     pub async fn cancel(&self, id: &ClientOrderId) -> Result<()> {
         self.cancel_many(std::slice::from_ref(id)).await
     }
@@ -55,6 +243,13 @@ impl OrderManager {
         Ok(())
     }
 
+    /// Cancels every order still tracked as in flight; returns how many were cancelled.
+    pub async fn cancel_all(&self) -> Result<usize> {
+        let ids: Vec<ClientOrderId> = self.inflight.lock().await.keys().cloned().collect();
+        self.cancel_many(&ids).await?;
+        Ok(ids.len())
+    }
+
     pub async fn poll_reports(&self) -> Result<Vec<ExecutionReport>> {
         let reports = self.gateway.poll_reports().await?;
         if !reports.is_empty() {
@@ -62,7 +257,10 @@ impl OrderManager {
             for report in &reports {
                 if matches!(
                     report.status,
-                    OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Rejected
+                    OrderStatus::Filled
+                        | OrderStatus::Canceled
+                        | OrderStatus::PartiallyCanceled
+                        | OrderStatus::Rejected
                 ) {
                     inflight.remove(&report.client_order_id);
                 }
@@ -80,3 +278,182 @@ impl OrderManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::types::Side;
+    use crate::execution::dry_run::DryRunGateway;
+    use crate::execution::money::{Price, Qty};
+    use crate::execution::types::{ClientOrderIdGenerator, TimeInForce, Venue};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Places the order, then loses the response on the first call
+    struct FlakyGateway {
+        inner: DryRunGateway,
+        submits: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ExecutionGateway for FlakyGateway {
+        async fn submit(&self, intents: &[QuoteIntent]) -> Result<Vec<OrderAck>> {
+            let acks = self.inner.submit(intents).await?;
+            if self.submits.fetch_add(1, Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+            Ok(acks)
+        }
+
+        async fn cancel_batch(&self, ids: &[ClientOrderId]) -> Result<()> {
+            self.inner.cancel_batch(ids).await
+        }
+
+        async fn poll_reports(&self) -> Result<Vec<ExecutionReport>> {
+            self.inner.poll_reports().await
+        }
+
+        async fn query_by_client_id(&self, id: &ClientOrderId) -> Result<Option<ExecutionReport>> {
+            self.inner.query_by_client_id(id).await
+        }
+    }
+
+    /// Places the order but reports a transport error; lookups miss it `hidden` times
+    struct LaggingIndex {
+        inner: DryRunGateway,
+        submits: AtomicUsize,
+        hidden: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ExecutionGateway for LaggingIndex {
+        async fn submit(&self, intents: &[QuoteIntent]) -> Result<Vec<OrderAck>> {
+            self.submits.fetch_add(1, Ordering::SeqCst);
+            self.inner.submit(intents).await?;
+            bail!("connection reset")
+        }
+
+        async fn cancel_batch(&self, ids: &[ClientOrderId]) -> Result<()> {
+            self.inner.cancel_batch(ids).await
+        }
+
+        async fn poll_reports(&self) -> Result<Vec<ExecutionReport>> {
+            self.inner.poll_reports().await
+        }
+
+        async fn query_by_client_id(&self, id: &ClientOrderId) -> Result<Option<ExecutionReport>> {
+            if self.hidden.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                return Ok(None);
+            }
+            self.inner.query_by_client_id(id).await
+        }
+    }
+
+    fn lagging(hidden: usize) -> Arc<LaggingIndex> {
+        Arc::new(LaggingIndex { inner: DryRunGateway::new(), submits: AtomicUsize::new(0), hidden: AtomicUsize::new(hidden) })
+    }
+
+    fn intent(id: ClientOrderId) -> QuoteIntent {
+        QuoteIntent::new(Venue::Gate, "BTC_USDT", Side::Bid, Price::from_f64(100.0), Qty::from_f64(1.0), TimeInForce::Gtc, id)
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_submit_is_resolved_by_query_not_resent() {
+        let gateway = Arc::new(FlakyGateway { inner: DryRunGateway::new(), submits: AtomicUsize::new(0) });
+        let manager = OrderManager::new(gateway.clone(), Duration::from_secs(60));
+        let ids = ClientOrderIdGenerator::with_session("mm", 1_700_000_000_000).unwrap();
+        let id = ids.next_id().unwrap();
+
+        let acks = manager.submit_idempotent(vec![intent(id.clone())], Duration::from_millis(20), 3).await.unwrap();
+        assert_eq!(acks.len(), 1);
+        assert_eq!(acks[0].client_order_id, id);
+        assert_eq!(gateway.submits.load(Ordering::SeqCst), 1, "found by client id, never resubmitted");
+
+        // The same id cannot be sent twice
+        assert!(manager.submit_idempotent(vec![intent(id)], Duration::from_millis(20), 3).await.is_err());
+        let next = ids.next_id().unwrap();
+        assert!(next.0.starts_with("t-mm-") && next.0.ends_with("-2") && next.0.len() <= 30, "{}", next);
+        // A tag that leaves no room for the sequence is refused up front, not at submit
+        assert!(ClientOrderIdGenerator::with_session("marketmakerbtc", 1_700_000_000_000).is_err());
+        assert!(ClientOrderIdGenerator::with_session("mm-1", 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slow_index_is_waited_out_and_never_resubmitted() {
+        let gateway = lagging(2);
+        let manager = OrderManager::new(gateway.clone(), Duration::from_secs(60));
+        let id = ClientOrderId::new("t-lag-1");
+        let acks = manager.submit_idempotent(vec![intent(id.clone())], Duration::from_millis(20), 3).await.unwrap();
+        assert_eq!(acks[0].client_order_id, id);
+        assert_eq!(gateway.submits.load(Ordering::SeqCst), 1);
+        assert!(manager.inflight.lock().await.contains_key(&id));
+
+        // Still invisible after every lookup: an error, not a second order
+        let gateway = lagging(usize::MAX);
+        let manager = OrderManager::new(gateway.clone(), Duration::from_secs(60));
+        let err = manager
+            .submit_idempotent(vec![intent(ClientOrderId::new("t-lag-2"))], Duration::from_millis(20), 3)
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("not found after 3 lookups"), "{:#}", err);
+        assert_eq!(gateway.submits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_recent_ids_forget_the_oldest() {
+        let mut recent = RecentIds::new(2);
+        for id in ["t-a-1", "t-a-2", "t-a-3"] {
+            recent.insert(ClientOrderId::new(id));
+        }
+        assert!(!recent.contains(&ClientOrderId::new("t-a-1")));
+        assert!(recent.contains(&ClientOrderId::new("t-a-3")));
+        assert_eq!(recent.ids.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_amend_falls_back_to_cancel_replace() {
+        let manager = OrderManager::new(Arc::new(DryRunGateway::new()), Duration::from_secs(60));
+        let id = ClientOrderId::new("t-a-1");
+        manager.submit(vec![intent(id.clone())]).await.unwrap();
+        let outcome = manager
+            .amend(&id, Price::from_f64(99.5), Qty::from_f64(2.0), ClientOrderId::new("t-a-2"))
+            .await
+            .unwrap();
+        let AmendOutcome::Replaced(ack) = outcome else {
+            panic!("dry-run has no native amend: {:?}", outcome);
+        };
+        assert_eq!(ack.client_order_id, ClientOrderId::new("t-a-2"));
+        let inflight = manager.inflight.lock().await;
+        assert!(!inflight.contains_key(&id));
+        assert_eq!(inflight[&ack.client_order_id].price, Price::from_f64(99.5));
+        drop(inflight);
+        assert!(manager.amend(&id, Price::from_f64(99.0), Qty::from_f64(1.0), ClientOrderId::new("t-a-3")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_outcome_without_lookup_is_an_error() {
+        struct NoLookup;
+
+        #[async_trait]
+        impl ExecutionGateway for NoLookup {
+            async fn submit(&self, _intents: &[QuoteIntent]) -> Result<Vec<OrderAck>> {
+                bail!("connection reset")
+            }
+
+            async fn cancel_batch(&self, _ids: &[ClientOrderId]) -> Result<()> {
+                Ok(())
+            }
+
+            async fn poll_reports(&self) -> Result<Vec<ExecutionReport>> {
+                Ok(Vec::new())
+            }
+        }
+
+        let manager = OrderManager::new(Arc::new(NoLookup), Duration::from_secs(60));
+        let err = manager
+            .submit_idempotent(vec![intent(ClientOrderId::new("t-x-1"))], Duration::from_millis(20), 3)
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("could not be looked up"), "{:#}", err);
+    }
+}
@@ -0,0 +1,232 @@
+//! [`ExecutionGateway`] over the in-process [`MatchingEngine`]
+//!
+//! Orders sent through the gateway trade under one account against whatever the test or
+//! the paper feed puts on the book. Each change of those orders is queued as an
+//! [`ExecutionReport`] and handed out by `poll_reports`, the way a venue's order stream
+//! would; fills happen synchronously inside `submit`, `submit_as`, `add_liquidity` and
+//! `on_trade`, so tests need neither network nor timers.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::base_classes::types::Side;
+
+use super::gateway::ExecutionGateway;
+use super::matching::{EngineOrder, MatchResult, MatchingEngine, MatchingRules};
+use super::money::{Price, Qty};
+use super::types::{ClientOrderId, ExchangeOrderId, ExecutionReport, OrderAck, OrderStatus, QuoteIntent};
+
+/// Account of the orders sent through the gateway
+pub const GATEWAY_ACCOUNT: &str = "sim";
+
+pub struct SimulatedExchange {
+    account: String,
+    state: Mutex<SimState>,
+}
+
+#[derive(Default)]
+struct SimState {
+    engine: MatchingEngine,
+    /// Gateway orders by client id; a repeated id gets the original order back
+    by_client_id: HashMap<ClientOrderId, u64>,
+    reports: Vec<ExecutionReport>,
+}
+
+impl SimState {
+    /// Queues reports of the gateway's own orders among `touched`
+    fn report(&mut self, account: &str, result: &MatchResult) {
+        let mut seen = Vec::new();
+        for id in &result.touched {
+            if seen.contains(id) {
+                continue;
+            }
+            seen.push(*id);
+            if let Some(order) = self.engine.order(*id).filter(|order| order.account == account)
+                && let Some(report) = execution_report(order)
+            {
+                self.reports.push(report);
+            }
+        }
+    }
+}
+
+fn exchange_order_id(id: u64) -> ExchangeOrderId {
+    ExchangeOrderId(format!("SIM-{}", id))
+}
+
+fn execution_report(order: &EngineOrder) -> Option<ExecutionReport> {
+    Some(ExecutionReport {
+        client_order_id: order.client_order_id.clone()?,
+        exchange_order_id: Some(exchange_order_id(order.id)),
+        status: order.status.clone(),
+        filled_qty: order.filled.to_f64(),
+        avg_fill_price: order.avg_fill_price().map(Price::to_f64),
+        ts: None,
+    })
+}
+
+impl SimulatedExchange {
+    pub fn new(rules: MatchingRules) -> Self {
+        Self::with_account(rules, GATEWAY_ACCOUNT)
+    }
+
+    pub fn with_account(rules: MatchingRules, account: &str) -> Self {
+        Self {
+            account: account.to_string(),
+            state: Mutex::new(SimState { engine: MatchingEngine::new(rules), ..SimState::default() }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Counterparty liquidity; returns its engine id
+    pub fn add_liquidity(&self, symbol: &str, side: Side, price: Price, size: Qty) -> u64 {
+        let mut state = self.state();
+        let (id, result) = state.engine.add_liquidity(symbol, side, price, size);
+        state.report(&self.account, &result);
+        id
+    }
+
+    /// Order of another account, e.g. a second sub-account for self-trade tests;
+    /// returns its status after matching
+    pub fn submit_as(&self, account: &str, intent: &QuoteIntent) -> OrderStatus {
+        let mut state = self.state();
+        let (id, result) = state.engine.submit(account, intent);
+        state.report(&self.account, &result);
+        state.engine.order(id).map_or(OrderStatus::Unknown, |order| order.status.clone())
+    }
+
+    /// Public trade print; returns the number of fills it caused
+    pub fn on_trade(&self, symbol: &str, price: Price, size: Option<Qty>) -> usize {
+        let mut state = self.state();
+        let result = state.engine.on_trade(symbol, price, size);
+        state.report(&self.account, &result);
+        result.fills.len()
+    }
+
+    /// Current state of a gateway order
+    pub fn order(&self, id: &ClientOrderId) -> Option<EngineOrder> {
+        let state = self.state();
+        state.by_client_id.get(id).and_then(|engine_id| state.engine.order(*engine_id)).cloned()
+    }
+
+    pub fn best_bid(&self, symbol: &str) -> Option<Price> {
+        self.state().engine.best_bid(symbol)
+    }
+
+    pub fn best_ask(&self, symbol: &str) -> Option<Price> {
+        self.state().engine.best_ask(symbol)
+    }
+}
+
+#[async_trait]
+impl ExecutionGateway for SimulatedExchange {
+    async fn submit(&self, intents: &[QuoteIntent]) -> Result<Vec<OrderAck>> {
+        let mut state = self.state();
+        let mut acks = Vec::with_capacity(intents.len());
+        for intent in intents {
+            let id = match state.by_client_id.get(&intent.client_order_id) {
+                Some(id) => *id,
+                None => {
+                    let (id, result) = state.engine.submit(&self.account, intent);
+                    state.by_client_id.insert(intent.client_order_id.clone(), id);
+                    state.report(&self.account, &result);
+                    id
+                }
+            };
+            acks.push(OrderAck {
+                client_order_id: intent.client_order_id.clone(),
+                exchange_order_id: Some(exchange_order_id(id)),
+            });
+        }
+        Ok(acks)
+    }
+
+    async fn cancel_batch(&self, ids: &[ClientOrderId]) -> Result<()> {
+        let mut state = self.state();
+        for client_order_id in ids {
+            let Some(id) = state.by_client_id.get(client_order_id).copied() else {
+                continue;
+            };
+            // Finished orders are ignored, as a venue ignores a late cancel
+            if state.engine.cancel(id).is_some() {
+                state.report(&self.account, &MatchResult { touched: vec![id], ..MatchResult::default() });
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll_reports(&self) -> Result<Vec<ExecutionReport>> {
+        Ok(std::mem::take(&mut self.state().reports))
+    }
+
+    async fn query_by_client_id(&self, id: &ClientOrderId) -> Result<Option<ExecutionReport>> {
+        Ok(self.order(id).as_ref().and_then(execution_report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::matching::SelfTradePrevention;
+    use crate::execution::order_manager::OrderManager;
+    use crate::execution::types::{TimeInForce, Venue};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn intent(id: &str, side: Side, price: f64, size: f64) -> QuoteIntent {
+        QuoteIntent::new(
+            Venue::Gate,
+            "ETH_USDT",
+            side,
+            Price::from_f64(price),
+            Qty::from_f64(size),
+            TimeInForce::Gtc,
+            ClientOrderId::new(id),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_order_manager_sees_fills_and_cancels_without_network() {
+        let rules = MatchingRules { self_trade: SelfTradePrevention::CancelNewest, ..MatchingRules::default() };
+        let exchange = Arc::new(SimulatedExchange::new(rules));
+        let manager = OrderManager::new(exchange.clone(), Duration::from_secs(60));
+        exchange.add_liquidity("ETH_USDT", Side::Ask, Price::from_f64(2000.0), Qty::from_f64(0.5));
+
+        let acks = manager
+            .submit(vec![intent("t-buy-1", Side::Bid, 2001.0, 1.0), intent("t-sell-1", Side::Ask, 2010.0, 1.0)])
+            .await
+            .unwrap();
+        assert_eq!(acks[0].exchange_order_id, Some(ExchangeOrderId("SIM-2".to_string())));
+        let reports = manager.poll_reports().await.unwrap();
+        let states: Vec<(&str, OrderStatus, f64)> =
+            reports.iter().map(|r| (r.client_order_id.0.as_str(), r.status.clone(), r.filled_qty)).collect();
+        assert_eq!(
+            states,
+            vec![("t-buy-1", OrderStatus::PartiallyFilled, 0.5), ("t-sell-1", OrderStatus::New, 0.0)]
+        );
+
+        // The rest of the buy fills from another account's sell, the print then lifts ours
+        assert_eq!(exchange.submit_as("other", &intent("x-1", Side::Ask, 2001.0, 0.5)), OrderStatus::Filled);
+        assert_eq!(exchange.on_trade("ETH_USDT", Price::from_f64(2011.0), Some(Qty::from_f64(0.25))), 1);
+        let reports = manager.poll_reports().await.unwrap();
+        assert_eq!((reports[0].status.clone(), reports[0].avg_fill_price), (OrderStatus::Filled, Some(2000.5)));
+        assert_eq!((reports[1].status.clone(), reports[1].filled_qty), (OrderStatus::PartiallyFilled, 0.25));
+
+        // Self-trade: our buy against our own resting sell is cancelled
+        manager.submit(vec![intent("t-buy-2", Side::Bid, 2010.0, 1.0)]).await.unwrap();
+        manager.cancel(&ClientOrderId::new("t-sell-1")).await.unwrap();
+        let reports = manager.poll_reports().await.unwrap();
+        let states: Vec<(&str, OrderStatus)> = reports.iter().map(|r| (r.client_order_id.0.as_str(), r.status.clone())).collect();
+        assert_eq!(states, vec![("t-buy-2", OrderStatus::Canceled), ("t-sell-1", OrderStatus::Canceled)]);
+        assert_eq!(exchange.best_ask("ETH_USDT"), None);
+
+        let found = exchange.query_by_client_id(&ClientOrderId::new("t-buy-1")).await.unwrap().unwrap();
+        assert_eq!(found.status, OrderStatus::Filled);
+    }
+}
@@ -0,0 +1,323 @@
+//! Server-side trigger orders: stop-market, stop-limit and take-profit-market.
+//!
+//! A stop that lives only in the bot dies with the process. [`StopManager`] holds the stops
+//! strategies want on one contract and mirrors them onto the venue as trigger orders, so a
+//! crash or a deploy leaves positions protected. `sync` reconciles the two sides:
+//! - a venue trigger matching a wanted stop (same client id, or the same spec after a
+//!   restart) is adopted instead of being placed twice;
+//! - a wanted stop without a trigger is placed; a moved stop cancels its old trigger first;
+//! - a trigger that fired removes its stop and is reported, so the caller can close the
+//!   position in its books;
+//! - open triggers carrying this manager's client id prefix but no wanted stop are cancelled.
+//!   Triggers placed by hand or by other processes are left alone.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, bail};
+
+use crate::base_classes::types::Side;
+
+use super::gateway::{ExecutionGateway, GatewayCapabilities};
+use super::money::{Price, Qty};
+use super::types::ClientOrderId;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerKind {
+    /// Market order once price crosses the trigger against the position
+    StopMarket,
+    /// Limit order at `limit` once price crosses the trigger against the position
+    StopLimit { limit: Price },
+    /// Market order once price crosses the trigger in the position's favour
+    TakeProfitMarket,
+}
+
+/// Reduce-only conditional order held by the venue
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriggerOrder {
+    /// Sent as the order text, so triggers can be recognised after a restart
+    pub client_order_id: ClientOrderId,
+    pub symbol: String,
+    /// Side of the order the trigger sends: `Ask` protects a long
+    pub side: Side,
+    pub trigger_price: Price,
+    pub size: Qty,
+    pub kind: TriggerKind,
+}
+
+impl TriggerOrder {
+    /// Fires when the price rises to the trigger; otherwise when it falls to it
+    pub fn fires_rising(&self) -> bool {
+        match self.kind {
+            TriggerKind::StopMarket | TriggerKind::StopLimit { .. } => self.side == Side::Bid,
+            TriggerKind::TakeProfitMarket => self.side == Side::Ask,
+        }
+    }
+
+    /// Same order on the venue, whatever the client id
+    pub fn same_spec(&self, other: &TriggerOrder) -> bool {
+        self.symbol == other.symbol
+            && self.side == other.side
+            && self.trigger_price == other.trigger_price
+            && self.size == other.size
+            && self.kind == other.kind
+    }
+}
+
+/// Venue id of a trigger order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TriggerId(pub String);
+
+impl std::fmt::Display for TriggerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerStatus {
+    Open,
+    /// Triggered and the order was sent
+    Fired,
+    /// Triggered, but the venue could not place the order
+    Failed,
+    Cancelled,
+}
+
+/// A trigger as the venue reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerTrigger {
+    pub id: TriggerId,
+    pub order: TriggerOrder,
+    pub status: TriggerStatus,
+}
+
+/// What one `sync` changed
+#[derive(Debug, Default, PartialEq)]
+pub struct StopSyncReport {
+    pub placed: usize,
+    pub adopted: usize,
+    pub cancelled: usize,
+    /// Keys of stops whose trigger fired; they are no longer managed
+    pub fired: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct ManagedStop {
+    order: TriggerOrder,
+    trigger: Option<TriggerId>,
+}
+
+pub struct StopManager {
+    symbol: String,
+    /// Client id prefix of triggers this manager owns, e.g. `t-mstrike-`
+    prefix: String,
+    stops: HashMap<String, ManagedStop>,
+}
+
+impl StopManager {
+    pub fn new(capabilities: GatewayCapabilities, symbol: impl Into<String>, prefix: impl Into<String>) -> Result<Self> {
+        if !capabilities.server_triggers {
+            bail!("connector does not support trigger orders; stops cannot live on the exchange");
+        }
+        let prefix = prefix.into();
+        if prefix.is_empty() {
+            bail!("stop manager needs a client id prefix to tell its own triggers apart");
+        }
+        Ok(Self { symbol: symbol.into(), prefix, stops: HashMap::new() })
+    }
+
+    /// Wants `order` under `key` (usually the position). A changed order replaces the
+    /// previous trigger on the next `sync`. Keep the client id across restarts: a trigger
+    /// that fired while the bot was down is only recognised by it.
+    pub fn set(&mut self, key: impl Into<String>, order: TriggerOrder) -> Result<()> {
+        if order.symbol != self.symbol {
+            bail!("stop for {} given to the {} stop manager", order.symbol, self.symbol);
+        }
+        if !order.client_order_id.0.starts_with(&self.prefix) {
+            bail!("stop {} does not carry the manager prefix {}", order.client_order_id, self.prefix);
+        }
+        let key = key.into();
+        match self.stops.get_mut(&key) {
+            Some(stop) if stop.order == order => {}
+            Some(stop) => stop.order = order,
+            None => {
+                self.stops.insert(key, ManagedStop { order, trigger: None });
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops wanting the stop; its trigger is cancelled on the next `sync`
+    pub fn remove(&mut self, key: &str) -> Option<TriggerOrder> {
+        self.stops.remove(key).map(|stop| stop.order)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&TriggerOrder> {
+        self.stops.get(key).map(|stop| &stop.order)
+    }
+
+    /// Venue id of the trigger backing the stop, once placed or adopted
+    pub fn trigger_id(&self, key: &str) -> Option<&TriggerId> {
+        self.stops.get(key)?.trigger.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stops.is_empty()
+    }
+
+    /// Brings the venue in line with the wanted stops. Stops are processed even if one
+    /// of them fails; the first error is returned after the pass.
+    pub async fn sync(&mut self, gateway: &dyn ExecutionGateway) -> Result<StopSyncReport> {
+        let server = gateway.list_triggers(&self.symbol).await?;
+        let mut report = StopSyncReport::default();
+        let mut claimed: HashSet<TriggerId> = HashSet::new();
+        let mut first_error = None;
+
+        let mut keys: Vec<String> = self.stops.keys().cloned().collect();
+        keys.sort();
+        for key in keys {
+            let stop = self.stops.get_mut(&key).expect("key taken from the map");
+            let current = stop
+                .trigger
+                .as_ref()
+                .and_then(|id| server.iter().find(|t| &t.id == id))
+                .or_else(|| {
+                    server.iter().find(|t| {
+                        !claimed.contains(&t.id)
+                            && (t.order.client_order_id == stop.order.client_order_id
+                                || (t.status == TriggerStatus::Open && t.order.same_spec(&stop.order)))
+                    })
+                });
+
+            if let Some(trigger) = current {
+                claimed.insert(trigger.id.clone());
+                match trigger.status {
+                    TriggerStatus::Fired => {
+                        log::warn!("stop {} on {} fired at the venue (trigger {})", key, self.symbol, trigger.id);
+                        report.fired.push(key.clone());
+                        self.stops.remove(&key);
+                        continue;
+                    }
+                    TriggerStatus::Open if trigger.order.same_spec(&stop.order) => {
+                        if stop.trigger.as_ref() != Some(&trigger.id) {
+                            stop.trigger = Some(trigger.id.clone());
+                            report.adopted += 1;
+                        }
+                        continue;
+                    }
+                    TriggerStatus::Open => {
+                        // Stop moved: the venue cannot amend triggers, so cancel and place anew
+                        if let Err(err) = gateway.cancel_trigger(&trigger.id).await {
+                            first_error.get_or_insert(err);
+                            continue;
+                        }
+                        report.cancelled += 1;
+                    }
+                    TriggerStatus::Failed => log::error!(
+                        "stop {} on {} triggered but the venue failed to send the order; position still open, placing again",
+                        key,
+                        self.symbol
+                    ),
+                    TriggerStatus::Cancelled => log::error!(
+                        "stop {} on {} was cancelled outside the bot; placing again",
+                        key,
+                        self.symbol
+                    ),
+                }
+            } else if let Some(id) = &stop.trigger {
+                log::error!("trigger {} for stop {} on {} vanished from the venue; placing again", id, key, self.symbol);
+            }
+
+            stop.trigger = None;
+            match gateway.place_trigger(&stop.order).await {
+                Ok(id) => {
+                    claimed.insert(id.clone());
+                    stop.trigger = Some(id);
+                    report.placed += 1;
+                }
+                Err(err) => {
+                    log::error!("failed to place stop {} on {}: {:#}", key, self.symbol, err);
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        for orphan in server.iter().filter(|t| {
+            t.status == TriggerStatus::Open
+                && !claimed.contains(&t.id)
+                && t.order.client_order_id.0.starts_with(&self.prefix)
+        }) {
+            match gateway.cancel_trigger(&orphan.id).await {
+                Ok(()) => report.cancelled += 1,
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        match first_error {
+            Some(err) => Err(err.context(format!("stop sync on {} incomplete", self.symbol))),
+            None => Ok(report),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::DryRunGateway;
+
+    fn stop(id: &str, trigger: f64) -> TriggerOrder {
+        TriggerOrder {
+            client_order_id: ClientOrderId::new(id),
+            symbol: "BTC_USDT".to_string(),
+            side: Side::Ask,
+            trigger_price: Price::from_f64(trigger),
+            size: Qty::from_f64(1.0),
+            kind: TriggerKind::StopMarket,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_places_adopts_moves_and_reports_fired() {
+        let gateway = DryRunGateway::new();
+        assert!(StopManager::new(GatewayCapabilities::default(), "BTC_USDT", "t-s-").is_err());
+        let mut stops = StopManager::new(gateway.capabilities(), "BTC_USDT", "t-s-").unwrap();
+        assert!(stops.set("pos-1", stop("t-x-1", 95.0)).is_err());
+        stops.set("pos-1", stop("t-s-1", 95.0)).unwrap();
+        assert!(!stops.get("pos-1").unwrap().fires_rising());
+        assert_eq!(stops.sync(&gateway).await.unwrap().placed, 1);
+        assert_eq!(stops.sync(&gateway).await.unwrap(), StopSyncReport::default());
+
+        // After a restart the same protection under a new id does not get a second trigger
+        let mut restarted = StopManager::new(gateway.capabilities(), "BTC_USDT", "t-s-").unwrap();
+        restarted.set("pos-1", stop("t-s-2", 95.0)).unwrap();
+        let manual = gateway.place_trigger(&stop("web", 90.0)).await.unwrap();
+        let stray = gateway.place_trigger(&stop("t-s-old", 80.0)).await.unwrap();
+        let report = restarted.sync(&gateway).await.unwrap();
+        assert_eq!((report.placed, report.adopted, report.cancelled), (0, 1, 1));
+        let open: Vec<TriggerId> = gateway
+            .list_triggers("BTC_USDT")
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|t| t.status == TriggerStatus::Open)
+            .map(|t| t.id)
+            .collect();
+        assert!(open.contains(&manual) && !open.contains(&stray) && open.len() == 2);
+
+        restarted.set("pos-1", stop("t-s-3", 97.0)).unwrap();
+        let report = restarted.sync(&gateway).await.unwrap();
+        assert_eq!((report.placed, report.cancelled), (1, 1));
+
+        let id = restarted.trigger_id("pos-1").unwrap().clone();
+        gateway.fire_trigger(&id);
+        let report = restarted.sync(&gateway).await.unwrap();
+        assert_eq!(report.fired, ["pos-1"]);
+        assert!(restarted.is_empty());
+    }
+}
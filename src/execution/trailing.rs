@@ -0,0 +1,271 @@
+//! Client-side trailing stops for venues without a native trailing order.
+//!
+//! The stop follows the best price seen since activation (the watermark: the high for a
+//! long, the low for a short) at a fixed fraction behind it, and fires once price crosses
+//! back through it. The watermark is the only state that cannot be rebuilt after a
+//! restart, so every change is queued in [`TrailChanges`]; the caller drains it with
+//! `take_changes` and writes it to storage, and `restore` puts the trails back on start.
+//! Persistence stays off the tick path: at most the changes since the last flush are lost.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::base_classes::types::Side;
+
+use super::money::{Price, Qty};
+use super::types::{ClientOrderId, QuoteIntent, TimeInForce, Venue};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrailingState {
+    pub key: String,
+    pub symbol: String,
+    /// Side of the closing order: `Ask` trails a long below its high, `Bid` a short above its low
+    pub side: Side,
+    pub size: f64,
+    /// Distance behind the watermark as a fraction of it (0.02 = 2%)
+    pub trail: f64,
+    /// Trailing starts once price reaches this level; `None` starts right away
+    pub activation: Option<f64>,
+    /// Best price since activation; `None` while not yet active
+    pub watermark: Option<f64>,
+    pub updated_ms: i64,
+}
+
+impl TrailingState {
+    /// Current stop level; `None` while not yet active
+    pub fn stop_price(&self) -> Option<f64> {
+        let watermark = self.watermark?;
+        Some(match self.side {
+            Side::Ask => watermark * (1.0 - self.trail),
+            Side::Bid => watermark * (1.0 + self.trail),
+        })
+    }
+
+    /// `a` is a better price than `b` for the position being protected
+    fn better(&self, a: f64, b: f64) -> bool {
+        match self.side {
+            Side::Ask => a > b,
+            Side::Bid => a < b,
+        }
+    }
+}
+
+/// A trail that fired; the position has to be closed now
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrailTrigger {
+    pub key: String,
+    pub symbol: String,
+    pub side: Side,
+    pub size: f64,
+    pub stop_price: f64,
+    pub watermark: f64,
+    /// Price that crossed the stop
+    pub price: f64,
+}
+
+impl TrailTrigger {
+    /// IOC close limited to `slippage_bps` past the crossing price
+    pub fn to_intent(&self, client_order_id: ClientOrderId, slippage_bps: f64) -> Result<QuoteIntent> {
+        let slip = self.price * slippage_bps / 10_000.0;
+        let limit = match self.side {
+            Side::Ask => self.price - slip,
+            Side::Bid => self.price + slip,
+        };
+        Ok(QuoteIntent::new(
+            Venue::Gate,
+            self.symbol.clone(),
+            self.side,
+            Price::try_from_f64(limit)?,
+            Qty::try_from_f64(self.size)?,
+            TimeInForce::Ioc,
+            client_order_id,
+        ))
+    }
+}
+
+/// Changes not yet written to storage
+#[derive(Debug, Default, PartialEq)]
+pub struct TrailChanges {
+    pub upserts: Vec<TrailingState>,
+    pub removed: Vec<String>,
+}
+
+impl TrailChanges {
+    pub fn is_empty(&self) -> bool {
+        self.upserts.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TrailingStops {
+    trails: HashMap<String, TrailingState>,
+    /// Keys added, moved or removed since the last `take_changes`
+    dirty: HashSet<String>,
+}
+
+impl TrailingStops {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts trailing a position. The watermark is set by the first price at or past
+    /// the activation level.
+    pub fn add(&mut self, mut state: TrailingState) -> Result<()> {
+        if !(state.trail > 0.0 && state.trail < 1.0) {
+            bail!("trail {} for {} must be a fraction in (0, 1)", state.trail, state.key);
+        }
+        if !(state.size > 0.0 && state.size.is_finite()) {
+            bail!("trailing stop {} has size {}", state.key, state.size);
+        }
+        if self.trails.contains_key(&state.key) {
+            bail!("trailing stop {} already exists", state.key);
+        }
+        if state.activation.is_some() {
+            state.watermark = None;
+        }
+        self.dirty.insert(state.key.clone());
+        self.trails.insert(state.key.clone(), state);
+        Ok(())
+    }
+
+    /// Puts back trails loaded from storage, watermarks included
+    pub fn restore(&mut self, states: impl IntoIterator<Item = TrailingState>) {
+        for state in states {
+            log::info!(
+                "trailing stop {} on {} restored: watermark {:?}, stop {:?}",
+                state.key,
+                state.symbol,
+                state.watermark,
+                state.stop_price()
+            );
+            self.trails.insert(state.key.clone(), state);
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&TrailingState> {
+        self.trails.get(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<TrailingState> {
+        let state = self.trails.remove(key)?;
+        self.dirty.insert(key.to_string());
+        Some(state)
+    }
+
+    pub fn len(&self) -> usize {
+        self.trails.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trails.is_empty()
+    }
+
+    /// Moves watermarks with a trade or mark price and returns the trails it fired.
+    /// Fired trails are removed.
+    pub fn on_price(&mut self, symbol: &str, price: f64, ts_ms: i64) -> Vec<TrailTrigger> {
+        let mut fired = Vec::new();
+        for state in self.trails.values_mut().filter(|s| s.symbol == symbol) {
+            let Some(watermark) = state.watermark else {
+                let reached = state.activation.is_none_or(|level| !state.better(level, price));
+                if reached {
+                    state.watermark = Some(price);
+                    state.updated_ms = ts_ms;
+                    self.dirty.insert(state.key.clone());
+                }
+                continue;
+            };
+            if state.better(price, watermark) {
+                state.watermark = Some(price);
+                state.updated_ms = ts_ms;
+                self.dirty.insert(state.key.clone());
+                continue;
+            }
+            let stop = state.stop_price().expect("active trail has a stop");
+            let crossed = match state.side {
+                Side::Ask => price <= stop,
+                Side::Bid => price >= stop,
+            };
+            if crossed {
+                fired.push(TrailTrigger {
+                    key: state.key.clone(),
+                    symbol: state.symbol.clone(),
+                    side: state.side,
+                    size: state.size,
+                    stop_price: stop,
+                    watermark,
+                    price,
+                });
+            }
+        }
+        for trigger in &fired {
+            self.remove(&trigger.key);
+        }
+        fired
+    }
+
+    /// Drains the changes to persist since the previous call
+    pub fn take_changes(&mut self) -> TrailChanges {
+        let mut changes = TrailChanges::default();
+        for key in self.dirty.drain() {
+            match self.trails.get(&key) {
+                Some(state) => changes.upserts.push(state.clone()),
+                None => changes.removed.push(key),
+            }
+        }
+        changes.upserts.sort_by(|a, b| a.key.cmp(&b.key));
+        changes.removed.sort();
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn long(activation: Option<f64>) -> TrailingState {
+        TrailingState {
+            key: "pos-1".to_string(),
+            symbol: "ETH_USDT".to_string(),
+            side: Side::Ask,
+            size: 2.0,
+            trail: 0.05,
+            activation,
+            watermark: None,
+            updated_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_trail_follows_high_and_survives_restart() {
+        let mut trails = TrailingStops::new();
+        trails.add(long(Some(105.0))).unwrap();
+        assert!(trails.add(long(None)).is_err());
+        assert!(trails.on_price("ETH_USDT", 104.0, 1).is_empty());
+        assert_eq!(trails.get("pos-1").unwrap().watermark, None);
+        trails.on_price("ETH_USDT", 106.0, 2);
+        trails.on_price("ETH_USDT", 120.0, 3);
+        // 120 * 0.95 = 114: a dip to 115 does not fire
+        assert!(trails.on_price("ETH_USDT", 115.0, 4).is_empty());
+
+        // The restarted process keeps the 120 high instead of trailing from the current price
+        let changes = trails.take_changes();
+        assert_eq!(changes.upserts.len(), 1);
+        assert!(trails.take_changes().is_empty());
+        let mut restarted = TrailingStops::new();
+        restarted.restore(changes.upserts);
+        assert!((restarted.get("pos-1").unwrap().stop_price().unwrap() - 114.0).abs() < 1e-9);
+
+        assert!(restarted.on_price("BTC_USDT", 1.0, 5).is_empty());
+        let fired = restarted.on_price("ETH_USDT", 113.5, 6);
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].watermark, fired[0].size), (120.0, 2.0));
+        assert!(restarted.is_empty());
+        assert_eq!(restarted.take_changes().removed, ["pos-1"]);
+
+        let intent = fired[0].to_intent(ClientOrderId::new("t-trail-1"), 10.0).unwrap();
+        assert_eq!((intent.side, intent.tif), (Side::Ask, TimeInForce::Ioc));
+        assert!((intent.price.to_f64() - 113.5 * 0.999).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,350 @@
+//! Automatic margin transfers between the spot and futures wallets.
+//!
+//! The futures wallet's margin level is `equity / maintenance_margin`. When it drops
+//! below `top_up_below`, spot funds are moved in to bring it back to `target_level`;
+//! when it rises above `sweep_above`, the surplus over `target_level` goes back to spot.
+//! Every transfer is capped per move and per UTC day, moves are spaced by a cooldown,
+//! and each one (or a top-up that could not be funded) is announced through the
+//! notification router. Flat accounts (no maintenance margin) are left alone.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::task::JoinHandle;
+
+use super::gate_client::GateClient;
+use crate::notifications::{Category, Notification, NotificationRouter, Severity};
+use crate::utils::parsing::value_to_f64;
+
+fn default_currency() -> String {
+    "USDT".to_string()
+}
+
+fn default_settle() -> String {
+    "usdt".to_string()
+}
+
+fn default_check_interval_secs() -> u64 {
+    30
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TreasuryConfig {
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default = "default_settle")]
+    pub settle: String,
+    /// Margin level (`equity / maintenance_margin`) that triggers a top-up
+    pub top_up_below: f64,
+    /// Level both directions aim for
+    pub target_level: f64,
+    /// Margin level above which the surplus is swept back to spot
+    pub sweep_above: f64,
+    /// Largest single transfer
+    pub max_transfer: f64,
+    /// Total moved in either direction per UTC day
+    pub max_daily: f64,
+    /// Smaller moves are not worth a transfer
+    #[serde(default)]
+    pub min_transfer: f64,
+    /// Spot balance never drawn down below this
+    #[serde(default)]
+    pub spot_reserve: f64,
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl TreasuryConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.top_up_below > 1.0 && self.top_up_below < self.target_level && self.target_level < self.sweep_above) {
+            return Err(anyhow!(
+                "treasury levels must satisfy 1 < top_up_below < target_level < sweep_above, got {} / {} / {}",
+                self.top_up_below,
+                self.target_level,
+                self.sweep_above
+            ));
+        }
+        if self.max_transfer <= 0.0 || self.max_daily < self.max_transfer {
+            return Err(anyhow!(
+                "treasury caps need 0 < max_transfer <= max_daily, got {} / {}",
+                self.max_transfer,
+                self.max_daily
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Futures wallet state read from `/futures/{settle}/accounts`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginSnapshot {
+    /// Wallet balance plus unrealised PnL
+    pub equity: f64,
+    pub maintenance_margin: f64,
+    /// Not locked by positions or orders; the most a sweep can take
+    pub available: f64,
+}
+
+impl MarginSnapshot {
+    pub fn from_account(value: &Value) -> Result<Self> {
+        let field = |key: &str| value.get(key).and_then(value_to_f64).ok_or_else(|| anyhow!("futures account without {}: {}", key, value));
+        Ok(Self {
+            equity: field("total")? + field("unrealised_pnl")?,
+            maintenance_margin: field("maintenance_margin")?,
+            available: field("available")?,
+        })
+    }
+
+    pub fn level(&self) -> Option<f64> {
+        (self.maintenance_margin > 0.0).then(|| self.equity / self.maintenance_margin)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    ToFutures,
+    ToSpot,
+}
+
+impl TransferDirection {
+    /// `(from, to)` wallet names for `/wallet/transfers`
+    pub fn wallets(self) -> (&'static str, &'static str) {
+        match self {
+            Self::ToFutures => ("spot", "futures"),
+            Self::ToSpot => ("futures", "spot"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreasuryDecision {
+    Hold,
+    Transfer { direction: TransferDirection, amount: f64, level: f64 },
+    /// Margin is low but caps, cooldown or the spot balance left nothing to move
+    Starved { level: f64, needed: f64, reason: String },
+}
+
+/// Transfer decisions with the cap and cooldown state; no I/O
+#[derive(Debug, Clone)]
+pub struct TreasuryPlanner {
+    config: TreasuryConfig,
+    day: Option<NaiveDate>,
+    moved_today: f64,
+    last_transfer: Option<DateTime<Utc>>,
+}
+
+impl TreasuryPlanner {
+    pub fn new(config: TreasuryConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { config, day: None, moved_today: 0.0, last_transfer: None })
+    }
+
+    pub fn config(&self) -> &TreasuryConfig {
+        &self.config
+    }
+
+    pub fn decide(&mut self, margin: &MarginSnapshot, spot_available: f64, now: DateTime<Utc>) -> TreasuryDecision {
+        if self.day != Some(now.date_naive()) {
+            self.day = Some(now.date_naive());
+            self.moved_today = 0.0;
+        }
+        let Some(level) = margin.level() else {
+            return TreasuryDecision::Hold;
+        };
+        let config = &self.config;
+        let (direction, wanted, source_limit) = if level < config.top_up_below {
+            let wanted = margin.maintenance_margin * config.target_level - margin.equity;
+            (TransferDirection::ToFutures, wanted, spot_available - config.spot_reserve)
+        } else if level > config.sweep_above {
+            let wanted = margin.equity - margin.maintenance_margin * config.target_level;
+            (TransferDirection::ToSpot, wanted, margin.available)
+        } else {
+            return TreasuryDecision::Hold;
+        };
+
+        let cooling = self
+            .last_transfer
+            .is_some_and(|last| now - last < chrono::Duration::seconds(config.cooldown_secs as i64));
+        let amount = wanted
+            .min(config.max_transfer)
+            .min(config.max_daily - self.moved_today)
+            .min(source_limit)
+            .max(0.0);
+        if cooling || amount < config.min_transfer.max(f64::EPSILON) {
+            if direction == TransferDirection::ToSpot {
+                return TreasuryDecision::Hold;
+            }
+            let reason = if cooling {
+                "cooldown".to_string()
+            } else if config.max_daily - self.moved_today < config.min_transfer.max(f64::EPSILON) {
+                format!("daily cap {} reached", config.max_daily)
+            } else {
+                format!("spot has {:.2} {} over the reserve", source_limit.max(0.0), config.currency)
+            };
+            return TreasuryDecision::Starved { level, needed: wanted, reason };
+        }
+        TreasuryDecision::Transfer { direction, amount, level }
+    }
+
+    /// Call after the venue accepted the transfer
+    pub fn record(&mut self, amount: f64, now: DateTime<Utc>) {
+        self.moved_today += amount;
+        self.last_transfer = Some(now);
+    }
+}
+
+pub struct Treasury;
+
+impl Treasury {
+    /// Checks the futures margin every `check_interval_secs` and transfers as planned.
+    /// Failed reads are logged and retried next tick; a failed transfer is notified.
+    pub fn spawn(client: Arc<GateClient>, mut planner: TreasuryPlanner, notifications: Arc<NotificationRouter>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let interval = Duration::from_secs(planner.config().check_interval_secs.max(1));
+            let mut timer = tokio::time::interval(interval);
+            let mut starved_notified = false;
+            loop {
+                timer.tick().await;
+                let decision = match check(&client, &mut planner).await {
+                    Ok(decision) => decision,
+                    Err(err) => {
+                        log::warn!("treasury check failed: {:#}", err);
+                        continue;
+                    }
+                };
+                let currency = planner.config().currency.clone();
+                let notification = match decision {
+                    TreasuryDecision::Hold => {
+                        starved_notified = false;
+                        continue;
+                    }
+                    TreasuryDecision::Starved { level, needed, reason } => {
+                        // Once per episode, not every check
+                        if std::mem::replace(&mut starved_notified, true) {
+                            continue;
+                        }
+                        Notification::new(
+                            Severity::Critical,
+                            "Futures margin top-up blocked",
+                            format!("margin level {:.2}, needs {:.2} {}: {}", level, needed, currency, reason),
+                        )
+                    }
+                    TreasuryDecision::Transfer { direction, amount, level } => {
+                        starved_notified = false;
+                        let (from, to) = direction.wallets();
+                        match client.transfer(&currency, from, to, amount, &planner.config().settle).await {
+                            Ok(_) => {
+                                planner.record(amount, Utc::now());
+                                let severity = match direction {
+                                    TransferDirection::ToFutures => Severity::Warning,
+                                    TransferDirection::ToSpot => Severity::Info,
+                                };
+                                Notification::new(
+                                    severity,
+                                    "Margin transfer",
+                                    format!("moved {:.2} {} {} -> {} at margin level {:.2}", amount, currency, from, to, level),
+                                )
+                            }
+                            Err(err) => Notification::new(
+                                Severity::Critical,
+                                "Margin transfer failed",
+                                format!("{} -> {} {:.2} {}: {:#}", from, to, amount, currency, err),
+                            ),
+                        }
+                    }
+                };
+                if let Err(err) = notifications.notify(&notification.in_category(Category::Risk)).await {
+                    log::error!("treasury notification failed: {:#}", err);
+                }
+            }
+        })
+    }
+}
+
+async fn check(client: &GateClient, planner: &mut TreasuryPlanner) -> Result<TreasuryDecision> {
+    let config = planner.config();
+    let account = client.fetch_futures_accounts(&config.settle).await?;
+    let margin = MarginSnapshot::from_account(&account).context("futures margin")?;
+    let spot = client.fetch_spot_available(&config.currency).await?;
+    Ok(planner.decide(&margin, spot, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> TreasuryConfig {
+        TreasuryConfig {
+            currency: default_currency(),
+            settle: default_settle(),
+            top_up_below: 2.0,
+            target_level: 4.0,
+            sweep_above: 8.0,
+            max_transfer: 500.0,
+            max_daily: 800.0,
+            min_transfer: 10.0,
+            spot_reserve: 100.0,
+            check_interval_secs: 30,
+            cooldown_secs: 300,
+        }
+    }
+
+    fn margin(equity: f64, maintenance: f64) -> MarginSnapshot {
+        MarginSnapshot { equity, maintenance_margin: maintenance, available: equity / 2.0 }
+    }
+
+    #[test]
+    fn test_top_up_respects_caps_cooldown_and_reserve() {
+        let mut planner = TreasuryPlanner::new(config()).unwrap();
+        let now = Utc::now();
+        // level 1.5: target needs 500 more
+        assert_eq!(
+            planner.decide(&margin(300.0, 200.0), 10_000.0, now),
+            TreasuryDecision::Transfer { direction: TransferDirection::ToFutures, amount: 500.0, level: 1.5 }
+        );
+        planner.record(500.0, now);
+        assert!(matches!(planner.decide(&margin(300.0, 200.0), 10_000.0, now), TreasuryDecision::Starved { ref reason, .. } if reason == "cooldown"));
+
+        // After the cooldown 300 of the daily cap is left, and the spot reserve binds first
+        let later = now + chrono::Duration::seconds(301);
+        assert_eq!(
+            planner.decide(&margin(1_000.0, 1_000.0), 350.0, later),
+            TreasuryDecision::Transfer { direction: TransferDirection::ToFutures, amount: 250.0, level: 1.0 }
+        );
+        assert!(matches!(planner.decide(&margin(1_000.0, 1_000.0), 105.0, later), TreasuryDecision::Starved { .. }));
+        assert_eq!(planner.decide(&margin(700.0, 200.0), 10_000.0, later), TreasuryDecision::Hold);
+        assert_eq!(planner.decide(&margin(700.0, 0.0), 10_000.0, later), TreasuryDecision::Hold);
+    }
+
+    #[test]
+    fn test_sweep_surplus_and_parse_account() {
+        let mut planner = TreasuryPlanner::new(config()).unwrap();
+        // level 10: 600 over target, capped at 500 per transfer and by what is available
+        assert_eq!(
+            planner.decide(&margin(1_000.0, 100.0), 0.0, Utc::now()),
+            TreasuryDecision::Transfer { direction: TransferDirection::ToSpot, amount: 500.0, level: 10.0 }
+        );
+        let locked = MarginSnapshot { available: 120.0, ..margin(1_000.0, 100.0) };
+        assert_eq!(
+            planner.decide(&locked, 0.0, Utc::now()),
+            TreasuryDecision::Transfer { direction: TransferDirection::ToSpot, amount: 120.0, level: 10.0 }
+        );
+
+        let account = json!({"total": "950.5", "unrealised_pnl": "49.5", "maintenance_margin": "100", "available": "400", "currency": "USDT"});
+        let snapshot = MarginSnapshot::from_account(&account).unwrap();
+        assert_eq!((snapshot.equity, snapshot.level()), (1_000.0, Some(10.0)));
+        assert!(MarginSnapshot::from_account(&json!({"total": "1"})).is_err());
+        assert!(TreasuryPlanner::new(TreasuryConfig { sweep_above: 3.0, ..config() }).is_err());
+    }
+}
@@ -1,24 +1,40 @@
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::base_classes::types::Side;
 
+use super::money::{Price, Qty};
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Venue {
     Gate,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TimeInForce {
+    #[default]
     Gtc,
+    /// Takes whatever is on the book now; the rest is cancelled instead of resting
     Ioc,
+    /// Fills in full immediately or not at all
     Fok,
+    /// GTX: rejected instead of taking liquidity, so the order only ever rests as maker
+    #[serde(alias = "gtx")]
     PostOnly,
 }
 
+impl TimeInForce {
+    /// The unfilled remainder never rests on the book
+    pub fn is_immediate(self) -> bool {
+        matches!(self, Self::Ioc | Self::Fok)
+    }
+}
+
 impl fmt::Display for TimeInForce {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let as_str = match self {
@@ -46,6 +62,70 @@ impl fmt::Display for ClientOrderId {
     }
 }
 
+/// Gate accepts `t-` plus at most 28 bytes of `[0-9a-zA-Z_.-]` as the order `text`
+const MAX_CLIENT_ID_LEN: usize = 30;
+/// Sequence digits a generator must leave room for: ten million orders per session
+const MIN_SEQ_DIGITS: usize = 7;
+
+/// Unique client order ids: `t-{tag}-{session}-{seq}`. The session part comes from the
+/// start time, so ids from a restarted process never collide with orders still resting
+/// from the previous one; the sequence makes them unique within a session.
+#[derive(Debug)]
+pub struct ClientOrderIdGenerator {
+    prefix: String,
+    next: AtomicU64,
+}
+
+impl ClientOrderIdGenerator {
+    pub fn new(tag: &str) -> Result<Self> {
+        let started_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self::with_session(tag, started_ms)
+    }
+
+    /// Fails if the tag is not ASCII alphanumeric or too long to leave the venue limit
+    /// room for `MIN_SEQ_DIGITS` of sequence.
+    pub fn with_session(tag: &str, session: u64) -> Result<Self> {
+        if tag.is_empty() || !tag.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            bail!("client id tag must be non-empty ASCII alphanumeric, got {:?}", tag);
+        }
+        // base36 keeps the millisecond session short enough for the venue limit
+        let mut digits = Vec::new();
+        let mut rest = session;
+        loop {
+            digits.push(char::from_digit((rest % 36) as u32, 36).unwrap());
+            rest /= 36;
+            if rest == 0 {
+                break;
+            }
+        }
+        let session: String = digits.into_iter().rev().collect();
+        let prefix = format!("t-{}-{}-", tag, session);
+        if prefix.len() + MIN_SEQ_DIGITS > MAX_CLIENT_ID_LEN {
+            bail!(
+                "client id tag {:?} too long: ids {}<seq> leave fewer than {} of {} bytes for the sequence",
+                tag,
+                prefix,
+                MIN_SEQ_DIGITS,
+                MAX_CLIENT_ID_LEN
+            );
+        }
+        Ok(Self { prefix, next: AtomicU64::new(1) })
+    }
+
+    /// Fails only once the sequence outgrows the room checked in `with_session`
+    pub fn next_id(&self) -> Result<ClientOrderId> {
+        let seq = self.next.fetch_add(1, Ordering::Relaxed);
+        let id = format!("{}{}", self.prefix, seq);
+        if id.len() > MAX_CLIENT_ID_LEN {
+            bail!("client order id {} exceeds {} bytes; start a new session", id, MAX_CLIENT_ID_LEN);
+        }
+        Ok(ClientOrderId(id))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ExchangeOrderId(pub String);
 
@@ -56,6 +136,9 @@ pub enum OrderStatus {
     PartiallyFilled,
     Filled,
     Canceled,
+    /// Cancelled after a partial fill. MEXC reports this as its own terminal
+    /// status; other venues send `Canceled` with a non-zero filled size.
+    PartiallyCanceled,
     Rejected,
     Unknown,
 }
@@ -65,10 +148,12 @@ pub struct QuoteIntent {
     pub venue: Venue,
     pub symbol: String,
     pub side: Side,
-    pub price: f64,
-    pub size: f64,
+    pub price: Price,
+    pub size: Qty,
     pub tif: TimeInForce,
     pub client_order_id: ClientOrderId,
+    /// Can only shrink the position; the venue rejects or trims anything that would grow it
+    pub reduce_only: bool,
 }
 
 impl QuoteIntent {
@@ -76,8 +161,8 @@ impl QuoteIntent {
         venue: Venue,
         symbol: impl Into<String>,
         side: Side,
-        price: f64,
-        size: f64,
+        price: Price,
+        size: Qty,
         tif: TimeInForce,
         client_order_id: ClientOrderId,
     ) -> Self {
@@ -89,8 +174,14 @@ impl QuoteIntent {
             size,
             tif,
             client_order_id,
+            reduce_only: false,
         }
     }
+
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
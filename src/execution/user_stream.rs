@@ -0,0 +1,441 @@
+//! Gate futures user-data stream: order updates, own trades, balances and positions.
+//!
+//! Events are pushed over the private websocket as they happen, so fills reach the
+//! router within the websocket latency instead of waiting for a poll. The stream
+//! reconnects on its own; after every reconnect it emits [`UserEvent::StreamGap`]
+//! because updates sent while disconnected are lost and state must be reconciled.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::exchanges::endpoints::GateioWs;
+use crate::utils::parsing::{value_to_f64, value_to_string, value_to_u64};
+use crate::utils::time::current_unix_ms;
+
+use super::clock::ServerClock;
+use super::gate_ws::sign_subscribe;
+use super::types::{ClientOrderId, ExchangeOrderId, OrderStatus};
+
+pub const USER_ORDERS: &str = "futures.orders";
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Order state as reported by the exchange. Sizes are in base units (contracts × multiplier).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderUpdate {
+    pub symbol: Symbol,
+    /// Our `text` tag; `None` for orders placed outside the bot
+    pub client_order_id: Option<ClientOrderId>,
+    pub exchange_order_id: ExchangeOrderId,
+    pub status: OrderStatus,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+    /// Cumulative filled size
+    pub filled: f64,
+    /// Average price of the cumulative fill
+    pub avg_fill_price: Option<f64>,
+    pub ts_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserTrade {
+    pub symbol: Symbol,
+    pub client_order_id: Option<ClientOrderId>,
+    pub exchange_order_id: ExchangeOrderId,
+    pub trade_id: String,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+    pub fee: f64,
+    pub maker: bool,
+    pub ts_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceUpdate {
+    pub currency: String,
+    pub balance: f64,
+    pub change: f64,
+    /// Gate's change type: `fee`, `pnl`, `fund`, ...
+    pub reason: String,
+    pub ts_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionUpdate {
+    pub symbol: Symbol,
+    /// Signed base size: positive long, negative short, zero flat
+    pub size: f64,
+    pub entry_price: f64,
+    pub liq_price: Option<f64>,
+    pub realised_pnl: f64,
+    pub ts_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserEvent {
+    Order(OrderUpdate),
+    Trade(UserTrade),
+    Balance(BalanceUpdate),
+    Position(PositionUpdate),
+    /// The stream reconnected; anything sent while it was down is lost
+    StreamGap { reconnects: u64 },
+}
+
+/// Turns private-channel frames into [`UserEvent`]s. Pure, so it can be fed recorded frames.
+#[derive(Debug, Clone, Default)]
+pub struct UserStreamParser {
+    /// Contract multipliers by Gate contract name; missing contracts use 1.0
+    contract_sizes: HashMap<String, f64>,
+}
+
+impl UserStreamParser {
+    pub fn new(contract_sizes: HashMap<String, f64>) -> Self {
+        Self { contract_sizes }
+    }
+
+    fn multiplier(&self, contract: &str) -> f64 {
+        self.contract_sizes.get(contract).copied().unwrap_or(1.0)
+    }
+
+    /// Parses one text frame. Pongs and subscribe acks yield no events; error replies and
+    /// malformed updates are errors, so a broken subscription is never silently ignored.
+    pub fn parse(&self, text: &str) -> Result<Vec<UserEvent>> {
+        let value: Value = serde_json::from_str(text)
+            .with_context(|| format!("user stream frame is not JSON: {}", text))?;
+        let channel = value.get("channel").and_then(|v| v.as_str()).unwrap_or("");
+        if let Some(error) = value.get("error").filter(|e| !e.is_null()) {
+            bail!("user stream {} error: {}", channel, error);
+        }
+        if value.get("event").and_then(|v| v.as_str()) != Some("update") {
+            return Ok(Vec::new());
+        }
+        let results = value
+            .get("result")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow!("user stream {} update without result array", channel))?;
+
+        results
+            .iter()
+            .map(|entry| match channel {
+                USER_ORDERS => self.parse_order(entry).map(UserEvent::Order),
+                GateioWs::USER_TRADES => self.parse_trade(entry).map(UserEvent::Trade),
+                GateioWs::USER_BALANCES => parse_balance(entry).map(UserEvent::Balance),
+                GateioWs::USER_POSITIONS => self.parse_position(entry).map(UserEvent::Position),
+                other => Err(anyhow!("unexpected user stream channel {}", other)),
+            })
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("malformed {} update: {}", channel, text))
+    }
+
+    fn parse_order(&self, entry: &Value) -> Result<OrderUpdate> {
+        let contract = str_field(entry, "contract")?;
+        let multiplier = self.multiplier(contract);
+        let size_contracts = f64_field(entry, "size")?;
+        let left_contracts = entry.get("left").and_then(value_to_f64).unwrap_or(0.0).abs();
+        let size = size_contracts.abs() * multiplier;
+        let filled = (size_contracts.abs() - left_contracts).max(0.0) * multiplier;
+        let status = match str_field(entry, "status")? {
+            "open" if filled > 0.0 => OrderStatus::PartiallyFilled,
+            "open" => OrderStatus::New,
+            "finished" => match entry.get("finish_as").and_then(|v| v.as_str()) {
+                Some("filled") => OrderStatus::Filled,
+                _ => OrderStatus::Canceled,
+            },
+            _ => OrderStatus::Unknown,
+        };
+        Ok(OrderUpdate {
+            symbol: Symbol::new(contract),
+            client_order_id: client_id(entry),
+            exchange_order_id: ExchangeOrderId(
+                entry.get("id").and_then(value_to_string).ok_or_else(|| anyhow!("missing id"))?,
+            ),
+            status,
+            side: side_of(size_contracts),
+            price: entry.get("price").and_then(value_to_f64).unwrap_or(0.0),
+            size,
+            filled,
+            avg_fill_price: entry
+                .get("fill_price")
+                .and_then(value_to_f64)
+                .filter(|p| *p > 0.0),
+            ts_ms: ts_ms(entry, "finish_time_ms", "update_time"),
+        })
+    }
+
+    fn parse_trade(&self, entry: &Value) -> Result<UserTrade> {
+        let contract = str_field(entry, "contract")?;
+        let size_contracts = f64_field(entry, "size")?;
+        Ok(UserTrade {
+            symbol: Symbol::new(contract),
+            client_order_id: client_id(entry),
+            exchange_order_id: ExchangeOrderId(
+                entry
+                    .get("order_id")
+                    .and_then(value_to_string)
+                    .ok_or_else(|| anyhow!("missing order_id"))?,
+            ),
+            trade_id: entry.get("id").and_then(value_to_string).unwrap_or_default(),
+            side: side_of(size_contracts),
+            price: f64_field(entry, "price")?,
+            size: size_contracts.abs() * self.multiplier(contract),
+            fee: entry.get("fee").and_then(value_to_f64).unwrap_or(0.0),
+            maker: entry.get("role").and_then(|v| v.as_str()) == Some("maker"),
+            ts_ms: ts_ms(entry, "create_time_ms", "create_time"),
+        })
+    }
+
+    fn parse_position(&self, entry: &Value) -> Result<PositionUpdate> {
+        let contract = str_field(entry, "contract")?;
+        Ok(PositionUpdate {
+            symbol: Symbol::new(contract),
+            size: f64_field(entry, "size")? * self.multiplier(contract),
+            entry_price: entry.get("entry_price").and_then(value_to_f64).unwrap_or(0.0),
+            liq_price: entry
+                .get("liq_price")
+                .and_then(value_to_f64)
+                .filter(|p| *p > 0.0),
+            realised_pnl: entry.get("realised_pnl").and_then(value_to_f64).unwrap_or(0.0),
+            ts_ms: ts_ms(entry, "time_ms", "time"),
+        })
+    }
+}
+
+fn parse_balance(entry: &Value) -> Result<BalanceUpdate> {
+    Ok(BalanceUpdate {
+        currency: entry
+            .get("currency")
+            .and_then(|v| v.as_str())
+            .unwrap_or("usdt")
+            .to_ascii_lowercase(),
+        balance: f64_field(entry, "balance")?,
+        change: entry.get("change").and_then(value_to_f64).unwrap_or(0.0),
+        reason: entry
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        ts_ms: ts_ms(entry, "time_ms", "time"),
+    })
+}
+
+fn str_field<'a>(entry: &'a Value, name: &str) -> Result<&'a str> {
+    entry
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("missing {}", name))
+}
+
+fn f64_field(entry: &Value, name: &str) -> Result<f64> {
+    entry
+        .get(name)
+        .and_then(value_to_f64)
+        .ok_or_else(|| anyhow!("missing {}", name))
+}
+
+/// Gate echoes our `text`; the `web`/`api` placeholders mean the order had no client tag.
+fn client_id(entry: &Value) -> Option<ClientOrderId> {
+    entry
+        .get("text")
+        .and_then(|v| v.as_str())
+        .filter(|text| text.starts_with("t-"))
+        .map(ClientOrderId::new)
+}
+
+fn side_of(signed_size: f64) -> Side {
+    if signed_size >= 0.0 { Side::Bid } else { Side::Ask }
+}
+
+fn ts_ms(entry: &Value, ms_field: &str, secs_field: &str) -> u64 {
+    entry
+        .get(ms_field)
+        .and_then(value_to_u64)
+        .or_else(|| entry.get(secs_field).and_then(value_to_u64).map(|s| s * 1_000))
+        .unwrap_or_else(current_unix_ms)
+}
+
+#[derive(Debug, Clone)]
+pub struct UserStreamConfig {
+    pub api_key: String,
+    pub api_secret: String,
+    /// Gate uid; channel payloads are addressed by it
+    pub user_id: String,
+    pub settle: String,
+    /// Settle-less root, e.g. `GateioWs::root(network)`
+    pub ws_url: String,
+    pub contract_sizes: HashMap<String, f64>,
+    pub reconnect_delay: Duration,
+    pub clock: ServerClock,
+}
+
+pub struct UserStream;
+
+impl UserStream {
+    /// Connects in the background and forwards events until the receiver is dropped.
+    pub fn spawn(config: UserStreamConfig) -> (mpsc::UnboundedReceiver<UserEvent>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(async move {
+            let parser = UserStreamParser::new(config.contract_sizes.clone());
+            let mut reconnects = 0u64;
+            loop {
+                match run_session(&config, &parser, &tx, reconnects).await {
+                    Ok(()) => return,
+                    Err(err) => {
+                        log::error!("gate user stream dropped: {:#}; reconnecting", err);
+                    }
+                }
+                if tx.is_closed() {
+                    return;
+                }
+                reconnects += 1;
+                tokio::time::sleep(config.reconnect_delay).await;
+            }
+        });
+        (rx, handle)
+    }
+}
+
+/// One websocket session. `Ok` means the consumer went away and the stream should stop.
+async fn run_session(
+    config: &UserStreamConfig,
+    parser: &UserStreamParser,
+    tx: &mpsc::UnboundedSender<UserEvent>,
+    reconnects: u64,
+) -> Result<()> {
+    let url = format!("{}/{}", config.ws_url.trim_end_matches('/'), config.settle);
+    let (ws, _) = connect_async(&url)
+        .await
+        .with_context(|| format!("failed to connect to {}", url))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let uid = config.user_id.as_str();
+    for (channel, payload) in [
+        (USER_ORDERS, json!([uid, "!all"])),
+        (GateioWs::USER_TRADES, json!([uid, "!all"])),
+        (GateioWs::USER_BALANCES, json!([uid])),
+        (GateioWs::USER_POSITIONS, json!([uid, "!all"])),
+    ] {
+        let ts = config.clock.now_secs();
+        let request = json!({
+            "time": ts,
+            "channel": channel,
+            "event": "subscribe",
+            "payload": payload,
+            "auth": {
+                "method": "api_key",
+                "KEY": config.api_key,
+                "SIGN": sign_subscribe(&config.api_secret, channel, ts),
+            }
+        });
+        sink.send(Message::Text(request.to_string())).await?;
+    }
+    if reconnects > 0 && tx.send(UserEvent::StreamGap { reconnects }).is_err() {
+        return Ok(());
+    }
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                let request = json!({ "time": config.clock.now_secs(), "channel": GateioWs::PING });
+                sink.send(Message::Text(request.to_string())).await?;
+            }
+            msg = stream.next() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    for event in parser.parse(&text)? {
+                        if tx.send(event).is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                Some(Ok(Message::Ping(data))) => {
+                    sink.send(Message::Pong(data)).await.ok();
+                }
+                Some(Ok(Message::Close(_))) | None => bail!("websocket closed by remote"),
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(anyhow!("user stream error: {err}")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser() -> UserStreamParser {
+        UserStreamParser::new(HashMap::from([("BTC_USDT".to_string(), 0.0001)]))
+    }
+
+    #[test]
+    fn test_order_updates_map_to_status_and_base_size() {
+        let frame = r#"{"time":1700000000,"channel":"futures.orders","event":"update","result":[
+            {"contract":"BTC_USDT","id":"42","size":-100,"left":40,"price":"30000","fill_price":"30010",
+             "status":"open","text":"t-abc","finish_time_ms":1700000000123},
+            {"contract":"BTC_USDT","id":"43","size":10,"left":10,"price":"29000","fill_price":"0",
+             "status":"finished","finish_as":"cancelled","text":"web"}]}"#;
+        let events = parser().parse(frame).unwrap();
+        let UserEvent::Order(partial) = &events[0] else { panic!("expected order") };
+        assert_eq!(partial.status, OrderStatus::PartiallyFilled);
+        assert_eq!(partial.side, Side::Ask);
+        assert!((partial.size - 0.01).abs() < 1e-12);
+        assert!((partial.filled - 0.006).abs() < 1e-12);
+        assert_eq!(partial.avg_fill_price, Some(30010.0));
+        assert_eq!(partial.client_order_id, Some(ClientOrderId::new("t-abc")));
+        assert_eq!(partial.ts_ms, 1700000000123);
+
+        let UserEvent::Order(cancelled) = &events[1] else { panic!("expected order") };
+        assert_eq!(cancelled.status, OrderStatus::Canceled);
+        assert_eq!(cancelled.client_order_id, None);
+        assert_eq!(cancelled.avg_fill_price, None);
+    }
+
+    #[test]
+    fn test_balance_position_and_trade_frames() {
+        let p = parser();
+        let balance = p
+            .parse(r#"{"channel":"futures.balances","event":"update","result":[
+                {"balance":1000.5,"change":-0.2,"type":"fee","currency":"USDT","time_ms":5}]}"#)
+            .unwrap();
+        assert!(matches!(&balance[0], UserEvent::Balance(b) if b.currency == "usdt" && b.balance == 1000.5));
+
+        let position = p
+            .parse(r#"{"channel":"futures.positions","event":"update","result":[
+                {"contract":"BTC_USDT","size":-20,"entry_price":"30000","liq_price":"0","realised_pnl":"1.5","time_ms":6}]}"#)
+            .unwrap();
+        let UserEvent::Position(pos) = &position[0] else { panic!("expected position") };
+        assert!((pos.size + 0.002).abs() < 1e-12);
+        assert_eq!(pos.liq_price, None);
+
+        let trade = p
+            .parse(r#"{"channel":"futures.usertrades","event":"update","result":[
+                {"contract":"BTC_USDT","id":"7","order_id":"42","size":5,"price":"30000","role":"maker","fee":"0.01","text":"t-abc","create_time_ms":7}]}"#)
+            .unwrap();
+        assert!(matches!(&trade[0], UserEvent::Trade(t) if t.maker && t.side == Side::Bid));
+    }
+
+    #[test]
+    fn test_acks_are_silent_and_errors_are_loud() {
+        let p = parser();
+        assert!(p
+            .parse(r#"{"channel":"futures.orders","event":"subscribe","error":null,"result":{"status":"success"}}"#)
+            .unwrap()
+            .is_empty());
+        assert!(p.parse(r#"{"channel":"futures.pong","result":null}"#).unwrap().is_empty());
+        assert!(p
+            .parse(r#"{"channel":"futures.orders","event":"subscribe","error":{"code":2,"message":"invalid sign"}}"#)
+            .is_err());
+        assert!(p
+            .parse(r#"{"channel":"futures.orders","event":"update","result":[{"contract":"BTC_USDT"}]}"#)
+            .is_err());
+    }
+}
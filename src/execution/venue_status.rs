@@ -0,0 +1,386 @@
+//! Exchange system status and maintenance windows.
+//!
+//! Venues announce maintenance ahead of time and then go dark: orders are rejected,
+//! sockets drop and every request errors. Trading into that window leaves positions we
+//! cannot manage, and the error storm trips AutoStop for a reason that is not ours.
+//! [`StatusPoller`] polls a venue status endpoint; [`MaintenanceGuard`] turns the known
+//! windows into actions: close entries `lead_ms` before a window, optionally flatten,
+//! and suppress AutoStop error/ping checks until `settle_ms` after it ends.
+//!
+//! Binance publishes only an up/down flag, Bybit publishes scheduled windows. Gate has no
+//! status endpoint, so its windows come from `scheduled` in the config, copied from the
+//! announcement.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::exchanges::endpoints::{BinanceGet, BybitGet, Network};
+use crate::risk::AutoStopManager;
+use crate::runtime::EntryGate;
+use crate::utils::parsing::value_to_u64;
+use crate::utils::time::current_unix_ms;
+
+fn default_poll_interval_ms() -> u64 {
+    60_000
+}
+
+fn default_lead_ms() -> u64 {
+    15 * 60_000
+}
+
+fn default_settle_ms() -> u64 {
+    10 * 60_000
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MaintenanceWindow {
+    pub start_ms: u64,
+    /// `None` while the venue has not said when it is back
+    #[serde(default)]
+    pub end_ms: Option<u64>,
+    #[serde(default)]
+    pub title: String,
+}
+
+impl MaintenanceWindow {
+    pub fn is_active(&self, now_ms: u64) -> bool {
+        now_ms >= self.start_ms && self.end_ms.is_none_or(|end| now_ms < end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VenueStatus {
+    /// Venue reports it is down right now
+    pub down: bool,
+    /// Ongoing and upcoming windows; finished ones are dropped
+    pub windows: Vec<MaintenanceWindow>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaintenanceConfig {
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Stop opening positions this long before a window starts
+    #[serde(default = "default_lead_ms")]
+    pub lead_ms: u64,
+    /// Close open positions when entries are stopped
+    #[serde(default)]
+    pub flatten: bool,
+    /// AutoStop stays suppressed this long after the window, while the venue recovers
+    #[serde(default = "default_settle_ms")]
+    pub settle_ms: u64,
+    /// Windows known from announcements, for venues without a status endpoint
+    #[serde(default)]
+    pub scheduled: Vec<MaintenanceWindow>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: default_poll_interval_ms(),
+            lead_ms: default_lead_ms(),
+            flatten: false,
+            settle_ms: default_settle_ms(),
+            scheduled: Vec::new(),
+        }
+    }
+}
+
+/// Venue status endpoint and how to read its reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusEndpoint {
+    Binance,
+    Bybit,
+}
+
+impl StatusEndpoint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Binance => "binance",
+            Self::Bybit => "bybit",
+        }
+    }
+
+    pub fn url(self, network: Network) -> String {
+        match self {
+            Self::Binance => BinanceGet::SYSTEM_STATUS_URL.to_string(),
+            Self::Bybit => format!("{}{}", BybitGet::base(network), BybitGet::SYSTEM_STATUS),
+        }
+    }
+
+    pub fn parse(self, body: &Value, now_ms: u64) -> Result<VenueStatus> {
+        match self {
+            // {"status": 0, "msg": "normal"}; 1 means system maintenance
+            Self::Binance => {
+                let status = body.get("status").and_then(value_to_u64).ok_or_else(|| anyhow!("no status in {}", body))?;
+                let down = status != 0;
+                let windows = if down {
+                    let title = body.get("msg").and_then(Value::as_str).unwrap_or("system maintenance");
+                    vec![MaintenanceWindow { start_ms: now_ms, end_ms: None, title: title.to_string() }]
+                } else {
+                    Vec::new()
+                };
+                Ok(VenueStatus { down, windows })
+            }
+            // {"retCode": 0, "result": {"list": [{"title", "state", "begin", "end", ...}]}}
+            Self::Bybit => {
+                let code = body.get("retCode").and_then(value_to_u64);
+                if code != Some(0) {
+                    bail!("bybit system status error: {}", body);
+                }
+                let list = body
+                    .pointer("/result/list")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| anyhow!("no result.list in {}", body))?;
+                let mut status = VenueStatus::default();
+                for item in list {
+                    let state = item.get("state").and_then(Value::as_str).unwrap_or_default();
+                    if state == "completed" {
+                        continue;
+                    }
+                    let start_ms = item
+                        .get("begin")
+                        .and_then(value_to_u64)
+                        .ok_or_else(|| anyhow!("maintenance without begin: {}", item))?;
+                    let window = MaintenanceWindow {
+                        start_ms,
+                        end_ms: item.get("end").and_then(value_to_u64),
+                        title: item.get("title").and_then(Value::as_str).unwrap_or_default().to_string(),
+                    };
+                    status.down |= state == "ongoing" || window.is_active(now_ms);
+                    status.windows.push(window);
+                }
+                Ok(status)
+            }
+        }
+    }
+}
+
+pub struct StatusPoller;
+
+impl StatusPoller {
+    /// Polls `endpoint` every `poll_interval_ms`. Failed polls keep the previous status
+    /// and are logged: a venue going down often takes its status endpoint with it.
+    pub fn spawn(
+        endpoint: StatusEndpoint,
+        network: Network,
+        config: MaintenanceConfig,
+    ) -> (watch::Receiver<Option<VenueStatus>>, JoinHandle<()>) {
+        let (tx, rx) = watch::channel(None);
+        let handle = tokio::spawn(async move {
+            let http = Client::new();
+            let url = endpoint.url(network);
+            let mut timer = tokio::time::interval(Duration::from_millis(config.poll_interval_ms.max(1)));
+            loop {
+                timer.tick().await;
+                match poll_once(&http, endpoint, &url).await {
+                    Ok(status) => {
+                        if tx.send(Some(status)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => log::warn!("{} system status poll failed: {:#}", endpoint.name(), err),
+                }
+            }
+        });
+        (rx, handle)
+    }
+}
+
+async fn poll_once(http: &Client, endpoint: StatusEndpoint, url: &str) -> Result<VenueStatus> {
+    let body: Value = http
+        .get(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .with_context(|| format!("GET {}", url))?
+        .json()
+        .await?;
+    endpoint.parse(&body, current_unix_ms())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenancePhase {
+    Normal,
+    /// A window starts within `lead_ms`
+    Imminent,
+    Ongoing,
+    /// Window over; the venue gets `settle_ms` to recover before errors count again
+    Settling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceAction {
+    CloseEntries { flatten: bool },
+    /// Suppress AutoStop error and ping checks until this time
+    SuppressErrors { until_ms: u64 },
+    ReopenEntries,
+}
+
+pub struct MaintenanceGuard {
+    config: MaintenanceConfig,
+    /// Latest polled windows; the configured ones are always considered too
+    polled: VenueStatus,
+    phase: MaintenancePhase,
+    /// End of the window being waited out, once known
+    settle_until_ms: Option<u64>,
+}
+
+impl MaintenanceGuard {
+    pub fn new(config: MaintenanceConfig) -> Self {
+        Self { config, polled: VenueStatus::default(), phase: MaintenancePhase::Normal, settle_until_ms: None }
+    }
+
+    pub fn phase(&self) -> MaintenancePhase {
+        self.phase
+    }
+
+    pub fn on_status(&mut self, status: VenueStatus) {
+        for window in &status.windows {
+            if !self.polled.windows.contains(window) {
+                log::warn!(
+                    "venue maintenance announced: {} from {} to {}",
+                    window.title,
+                    fmt_ms(window.start_ms),
+                    window.end_ms.map(fmt_ms).unwrap_or_else(|| "unknown".to_string())
+                );
+            }
+        }
+        self.polled = status;
+    }
+
+    /// Moves the phase to `now_ms` and returns what to do on the transition
+    pub fn evaluate(&mut self, now_ms: u64) -> Vec<MaintenanceAction> {
+        let windows = || self.polled.windows.iter().chain(&self.config.scheduled);
+        let active = windows().filter(|w| w.is_active(now_ms)).map(|w| w.end_ms).next();
+        let phase = if self.polled.down || active.is_some() {
+            MaintenancePhase::Ongoing
+        } else if windows().any(|w| w.start_ms > now_ms && w.start_ms - now_ms <= self.config.lead_ms) {
+            MaintenancePhase::Imminent
+        } else if self.settle_until_ms.is_some_and(|until| now_ms < until) {
+            MaintenancePhase::Settling
+        } else {
+            MaintenancePhase::Normal
+        };
+
+        let mut actions = Vec::new();
+        if phase == MaintenancePhase::Ongoing {
+            // Until the end is known, keep suppressing one settle period ahead
+            let end = active.flatten().unwrap_or(now_ms);
+            let until = end + self.config.settle_ms;
+            if self.settle_until_ms.is_none_or(|current| current < until) {
+                self.settle_until_ms = Some(until);
+                actions.push(MaintenanceAction::SuppressErrors { until_ms: until });
+            }
+        }
+        if phase != self.phase {
+            log::warn!("venue maintenance: {:?} -> {:?}", self.phase, phase);
+            let was_closed = self.phase != MaintenancePhase::Normal;
+            match phase {
+                MaintenancePhase::Imminent | MaintenancePhase::Ongoing if !was_closed => {
+                    actions.insert(0, MaintenanceAction::CloseEntries { flatten: self.config.flatten });
+                }
+                MaintenancePhase::Normal => {
+                    self.settle_until_ms = None;
+                    actions.push(MaintenanceAction::ReopenEntries);
+                }
+                _ => {}
+            }
+            self.phase = phase;
+        }
+        actions
+    }
+
+    /// Applies an action to the entry gate and AutoStop. Flattening is left to the caller,
+    /// which owns the positions.
+    pub fn apply(action: MaintenanceAction, entries: &EntryGate, auto_stop: &mut AutoStopManager) {
+        match action {
+            MaintenanceAction::CloseEntries { .. } => entries.close(),
+            MaintenanceAction::ReopenEntries => entries.open(),
+            MaintenanceAction::SuppressErrors { until_ms } => {
+                if let Some(until) = DateTime::<Utc>::from_timestamp_millis(until_ms as i64) {
+                    auto_stop.suppress_until(until);
+                }
+            }
+        }
+    }
+}
+
+fn fmt_ms(ms: u64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(ms as i64).map(|t| t.to_rfc3339()).unwrap_or_else(|| ms.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MIN: u64 = 60_000;
+
+    #[test]
+    fn test_status_endpoints_parse_their_replies() {
+        let up = StatusEndpoint::Binance.parse(&json!({"status": 0, "msg": "normal"}), 5).unwrap();
+        assert_eq!(up, VenueStatus::default());
+        let down = StatusEndpoint::Binance.parse(&json!({"status": 1, "msg": "system maintenance"}), 5).unwrap();
+        assert!(down.down && down.windows[0].is_active(5));
+
+        let body = json!({"retCode": 0, "result": {"list": [
+            {"title": "Upgrade", "state": "scheduled", "begin": "1000000", "end": "2000000"},
+            {"title": "Old", "state": "completed", "begin": "1", "end": "2"}
+        ]}});
+        let bybit = StatusEndpoint::Bybit.parse(&body, 0).unwrap();
+        assert!(!bybit.down);
+        assert_eq!(bybit.windows, [MaintenanceWindow { start_ms: 1_000_000, end_ms: Some(2_000_000), title: "Upgrade".to_string() }]);
+        assert!(StatusEndpoint::Bybit.parse(&json!({"retCode": 10001}), 0).is_err());
+    }
+
+    #[test]
+    fn test_guard_closes_before_window_and_reopens_after_settle() {
+        let start = 1_000 * MIN;
+        let config = MaintenanceConfig {
+            lead_ms: 15 * MIN,
+            settle_ms: 10 * MIN,
+            flatten: true,
+            scheduled: vec![MaintenanceWindow { start_ms: start, end_ms: Some(start + 30 * MIN), title: "gate".to_string() }],
+            ..MaintenanceConfig::default()
+        };
+        let mut guard = MaintenanceGuard::new(config);
+        let entries = EntryGate::new();
+        let mut auto_stop = AutoStopManager::default();
+
+        assert!(guard.evaluate(start - 20 * MIN).is_empty());
+        let actions = guard.evaluate(start - 10 * MIN);
+        assert_eq!(actions, [MaintenanceAction::CloseEntries { flatten: true }]);
+        for action in actions {
+            MaintenanceGuard::apply(action, &entries, &mut auto_stop);
+        }
+        assert!(!entries.is_open());
+
+        let actions = guard.evaluate(start + MIN);
+        assert_eq!(actions, [MaintenanceAction::SuppressErrors { until_ms: start + 40 * MIN }]);
+        assert!(guard.evaluate(start + 2 * MIN).is_empty());
+        assert!(guard.evaluate(start + 35 * MIN).is_empty());
+        assert_eq!(guard.phase(), MaintenancePhase::Settling);
+        assert_eq!(guard.evaluate(start + 40 * MIN), [MaintenanceAction::ReopenEntries]);
+        MaintenanceGuard::apply(MaintenanceAction::ReopenEntries, &entries, &mut auto_stop);
+        assert!(entries.is_open());
+
+        // Venue down without notice: entries close and errors are suppressed at once
+        guard.on_status(VenueStatus { down: true, windows: Vec::new() });
+        let now = start + 100 * MIN;
+        assert_eq!(
+            guard.evaluate(now),
+            [
+                MaintenanceAction::CloseEntries { flatten: true },
+                MaintenanceAction::SuppressErrors { until_ms: now + 10 * MIN }
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod base_classes;
+pub mod error;
 pub mod collectors;
 pub mod exchanges;
 pub mod utils;
@@ -15,6 +16,10 @@ pub mod config;
 #[cfg(feature = "gate_exec")]
 pub mod logging;
 
+// Operator notifications: Telegram, Discord, Slack, SMS hook (requires gate_exec)
+#[cfg(feature = "gate_exec")]
+pub mod notifications;
+
 // Analytics and testing
 pub mod analytics;
 pub mod tests;
@@ -37,4 +42,12 @@ pub mod saas;
 // Backtest module (requires gate_exec)
 #[cfg(feature = "gate_exec")]
 pub mod backtest;
+
+// Test harness: scripted scenarios and end-to-end simulation (requires gate_exec)
+#[cfg(feature = "gate_exec")]
+pub mod testing;
+
+// Runtime: symbols sharded across worker threads (requires gate_exec)
+#[cfg(feature = "gate_exec")]
+pub mod runtime;
 pub mod risk;
@@ -7,7 +7,7 @@ use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result, anyhow};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use tokio::time::MissedTickBehavior;
 
 use crate::base_classes::reference::ReferenceEvent;
@@ -46,6 +46,7 @@ enum LogEvent {
         sent_ts: SystemTime,
     },
     Reports(Vec<ExecutionReport>),
+    Flush(oneshot::Sender<Result<()>>),
 }
 
 impl QuoteLogHandle {
@@ -94,6 +95,17 @@ impl QuoteLogHandle {
         &self.path
     }
 
+    /// Writes out everything queued before this call and flushes the file.
+    pub async fn flush(&self) -> Result<()> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.tx
+            .send(LogEvent::Flush(done_tx))
+            .map_err(|_| anyhow!("logger channel closed before flush"))?;
+        done_rx
+            .await
+            .map_err(|_| anyhow!("logger task exited before flush"))?
+    }
+
     pub fn log_market_snapshot(&self) {
         if let Err(err) = self.tx.send(LogEvent::MarketSnapshot) {
             eprintln!(
@@ -205,6 +217,10 @@ fn handle_event(logger: &mut QuoteCsvLogger, event: LogEvent, debug: &DebugLogge
             }
             Ok(())
         }
+        LogEvent::Flush(done) => {
+            let _ = done.send(logger.flush());
+            Ok(())
+        }
     }
 }
 
@@ -398,8 +414,8 @@ impl QuoteCsvLogger {
                 intent.client_order_id.0.clone(),
                 QuoteSnapshot {
                     side: intent.side,
-                    price: intent.price,
-                    size: intent.size,
+                    price: intent.price.to_f64(),
+                    size: intent.size.to_f64(),
                     filled_qty: 0.0,
                     sent_ns: Some(sent_ns),
                     send_instant: Some(send_instant),
@@ -412,12 +428,12 @@ impl QuoteCsvLogger {
                 "quote",
                 None,
                 None,
-                Some(intent.price),
+                Some(intent.price.to_f64()),
                 side_to_direction(intent.side),
                 "quote",
                 &intent.client_order_id.0,
                 side_to_str(intent.side),
-                Some(intent.size),
+                Some(intent.size.to_f64()),
                 reference_meta.map(|m| m.source.as_str()),
                 reference_meta.and_then(|m| m.ts_ns),
                 Some(reference_price),
@@ -567,7 +583,8 @@ impl QuoteCsvLogger {
             crate::execution::OrderStatus::New => Some("quote_ack"),
             crate::execution::OrderStatus::PartiallyFilled => Some("quote_ack"),
             crate::execution::OrderStatus::Filled => Some("quote_ack"),
-            crate::execution::OrderStatus::Canceled => Some("cancel_ack"),
+            crate::execution::OrderStatus::Canceled
+            | crate::execution::OrderStatus::PartiallyCanceled => Some("cancel_ack"),
             crate::execution::OrderStatus::Rejected => Some("quote_reject"),
             _ => None,
         };
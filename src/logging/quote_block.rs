@@ -441,7 +441,7 @@ async fn handle_quote_tick(
     )
     .await?;
 
-    if let Some(mut plan) = strategy.plan_quotes(now) {
+    if let Some(mut plan) = strategy.plan_quotes(now)? {
         let reference_price = plan.reference_price;
         let net_contracts = {
             let guard = inventory.lock().await;
@@ -559,7 +559,10 @@ async fn handle_quote_tick(
         let debug_clone = debug.clone();
         tokio::spawn(async move {
             let call_start = Instant::now();
-            match order_manager_clone.submit(intents_for_send.clone()).await {
+            match order_manager_clone
+                .submit_idempotent(intents_for_send.clone(), SUBMIT_TIMEOUT, SUBMIT_LOOKUPS)
+                .await
+            {
                 Ok(acks) => {
                     if latency_debug_enabled() {
                         let call_elapsed = call_start.elapsed();
@@ -678,7 +681,7 @@ fn filter_intents(
     let mut skipped = Vec::new();
 
     for intent in intents {
-        let contracts = (intent.size.abs() / contract_size).round() as i64;
+        let contracts = (intent.size.abs().to_f64() / contract_size).round() as i64;
         if contracts == 0 {
             bail!(
                 "intent {} size {:.8} is below contract size {}",
@@ -689,7 +692,7 @@ fn filter_intents(
         }
 
         let effective_size = contracts as f64 * contract_size;
-        let price = intent.price.abs();
+        let price = intent.price.abs().to_f64();
         let notional = price * effective_size;
         if notional > risk.max_order_notional {
             bail!(
@@ -1124,8 +1127,8 @@ impl QuoteCsvLogger {
                 intent.client_order_id.0.clone(),
                 QuoteSnapshot {
                     side: intent.side,
-                    price: intent.price,
-                    size: intent.size,
+                    price: intent.price.to_f64(),
+                    size: intent.size.to_f64(),
                     filled_qty: 0.0,
                     sent_ns: Some(sent_ns),
                     send_instant: Some(send_instant),
@@ -1136,12 +1139,12 @@ impl QuoteCsvLogger {
                 clamp_u128_to_u64(sent_ns),
                 venue.as_str(),
                 "quote",
-                Some(intent.price),
+                Some(intent.price.to_f64()),
                 side_to_direction(intent.side),
                 "quote",
                 &intent.client_order_id.0,
                 side_to_str(intent.side),
-                Some(intent.size),
+                Some(intent.size.to_f64()),
                 reference_meta.map(|m| m.source.as_str()),
                 reference_meta.and_then(|m| m.ts_ns),
                 Some(reference_price),
@@ -1287,7 +1290,7 @@ impl QuoteCsvLogger {
             OrderStatus::New => Some("quote_ack"),
             OrderStatus::PartiallyFilled => Some("quote_ack"),
             OrderStatus::Filled => Some("quote_ack"),
-            OrderStatus::Canceled => Some("cancel_ack"),
+            OrderStatus::Canceled | OrderStatus::PartiallyCanceled => Some("cancel_ack"),
             OrderStatus::Rejected => Some("quote_reject"),
             _ => None,
         };
@@ -1321,7 +1324,10 @@ impl QuoteCsvLogger {
 
         if matches!(
             report.status,
-            OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Rejected
+            OrderStatus::Filled
+                | OrderStatus::Canceled
+                | OrderStatus::PartiallyCanceled
+                | OrderStatus::Rejected
         ) {
             self.orders.remove(&report.client_order_id.0);
             self.pending_cancels.remove(&report.client_order_id.0);
@@ -0,0 +1,310 @@
+//! SMTP email alerts with an hourly or daily digest.
+//!
+//! Each [`Category`] is configured as immediate, digest or off. `Critical` notifications
+//! are always mailed immediately unless their category is off. Digest entries are
+//! buffered and mailed as one message when the interval ends; the timer from
+//! [`EmailNotifier::spawn_digest`] flushes even when no new notification arrives.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+
+use super::{Category, Notification, Notifier, Severity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailDelivery {
+    Immediate,
+    Digest,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestInterval {
+    Hourly,
+    #[default]
+    Daily,
+}
+
+impl DigestInterval {
+    pub fn duration(self) -> Duration {
+        match self {
+            Self::Hourly => Duration::hours(1),
+            Self::Daily => Duration::days(1),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    /// Defaults to 587 with STARTTLS, 465 with implicit TLS
+    #[serde(default)]
+    pub smtp_port: Option<u16>,
+    #[serde(default = "default_true")]
+    pub starttls: bool,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub digest: DigestInterval,
+    /// Categories not listed go to the digest
+    #[serde(default)]
+    pub categories: HashMap<Category, EmailDelivery>,
+}
+
+impl EmailConfig {
+    pub fn delivery(&self, notification: &Notification) -> EmailDelivery {
+        let configured = self
+            .categories
+            .get(&notification.category)
+            .copied()
+            .unwrap_or(EmailDelivery::Digest);
+        match configured {
+            EmailDelivery::Off => EmailDelivery::Off,
+            _ if notification.severity == Severity::Critical => EmailDelivery::Immediate,
+            other => other,
+        }
+    }
+}
+
+/// Notifications waiting for the next digest
+#[derive(Debug)]
+pub struct DigestBuffer {
+    interval: Duration,
+    period_start: DateTime<Utc>,
+    entries: Vec<(DateTime<Utc>, Notification)>,
+}
+
+impl DigestBuffer {
+    pub fn new(interval: Duration, now: DateTime<Utc>) -> Self {
+        Self {
+            interval,
+            period_start: now,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, at: DateTime<Utc>, notification: Notification) {
+        self.entries.push((at, notification));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Closes the period once the interval has passed and returns its subject and body;
+    /// `None` while the period runs or when nothing was buffered
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Option<(String, String)> {
+        if now - self.period_start < self.interval {
+            return None;
+        }
+        let from = self.period_start;
+        self.period_start = now;
+        if self.entries.is_empty() {
+            return None;
+        }
+        let entries = std::mem::take(&mut self.entries);
+        Some(render_digest(from, now, &entries))
+    }
+}
+
+fn render_digest(from: DateTime<Utc>, to: DateTime<Utc>, entries: &[(DateTime<Utc>, Notification)]) -> (String, String) {
+    let mut by_category: BTreeMap<&str, Vec<&(DateTime<Utc>, Notification)>> = BTreeMap::new();
+    for entry in entries {
+        by_category.entry(entry.1.category.name()).or_default().push(entry);
+    }
+    let subject = format!(
+        "Digest {} - {}: {} events",
+        from.format("%Y-%m-%d %H:%M"),
+        to.format("%H:%M UTC"),
+        entries.len()
+    );
+    let mut body = String::new();
+    for (category, items) in &by_category {
+        let _ = writeln!(body, "== {} ({}) ==", category, items.len());
+        for (at, notification) in items {
+            let _ = writeln!(body, "{} {}", at.format("%H:%M:%S"), notification.text());
+        }
+        let _ = writeln!(body);
+    }
+    (subject, body)
+}
+
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    config: EmailConfig,
+    digest: Mutex<DigestBuffer>,
+}
+
+impl EmailNotifier {
+    /// Fails on unparsable addresses or relay host, so bad config is caught at startup
+    pub fn new(config: EmailConfig) -> Result<Self> {
+        let builder = if config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)
+        }
+        .with_context(|| format!("smtp relay {}", config.smtp_host))?;
+        let builder = match config.smtp_port {
+            Some(port) => builder.port(port),
+            None => builder,
+        };
+        let transport = builder
+            .credentials(Credentials::new(config.username.clone(), config.password.clone()))
+            .build();
+        let from = config.from.parse().with_context(|| format!("email from {}", config.from))?;
+        let to = config
+            .to
+            .iter()
+            .map(|to| to.parse().with_context(|| format!("email to {}", to)))
+            .collect::<Result<Vec<Mailbox>>>()?;
+        anyhow::ensure!(!to.is_empty(), "email notifier needs at least one recipient");
+        Ok(Self {
+            transport,
+            from,
+            to,
+            digest: Mutex::new(DigestBuffer::new(config.digest.duration(), Utc::now())),
+            config,
+        })
+    }
+
+    async fn mail(&self, subject: String, body: String) -> Result<()> {
+        let mut builder = Message::builder().from(self.from.clone()).subject(subject);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let message = builder.body(body).context("build email")?;
+        self.transport.send(message).await.context("smtp send")?;
+        Ok(())
+    }
+
+    /// Mails the digest if its period has ended
+    pub async fn flush_due(&self) -> Result<()> {
+        let due = self.digest.lock().expect("digest lock").take_due(Utc::now());
+        match due {
+            Some((subject, body)) => self.mail(subject, body).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the digest once a minute until the runtime shuts down
+    pub fn spawn_digest(self: &Arc<Self>) -> JoinHandle<()> {
+        let notifier = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                tick.tick().await;
+                if let Err(err) = notifier.flush_due().await {
+                    log::error!("email digest failed: {:#}", err);
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        match self.config.delivery(notification) {
+            EmailDelivery::Off => Ok(()),
+            EmailDelivery::Digest => {
+                self.digest
+                    .lock()
+                    .expect("digest lock")
+                    .push(Utc::now(), notification.clone());
+                Ok(())
+            }
+            EmailDelivery::Immediate => {
+                let subject = format!("[{}] {}", notification.severity.label(), notification.title);
+                self.mail(subject, notification.body.clone()).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> EmailConfig {
+        EmailConfig {
+            smtp_host: "smtp.test".to_string(),
+            smtp_port: None,
+            starttls: true,
+            username: "bot".to_string(),
+            password: "secret".to_string(),
+            from: "bot@test.io".to_string(),
+            to: vec!["ops@test.io".to_string()],
+            digest: DigestInterval::Hourly,
+            categories: HashMap::from([
+                (Category::Order, EmailDelivery::Immediate),
+                (Category::System, EmailDelivery::Off),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_delivery_per_category() {
+        let config = config();
+        let trade = Notification::new(Severity::Info, "Fill", "BTC").in_category(Category::Trade);
+        assert_eq!(config.delivery(&trade), EmailDelivery::Digest);
+        let reject = Notification::new(Severity::Warning, "Reject", "t-1").in_category(Category::Order);
+        assert_eq!(config.delivery(&reject), EmailDelivery::Immediate);
+        let stop = Notification::new(Severity::Critical, "Stop", "ping").in_category(Category::Risk);
+        assert_eq!(config.delivery(&stop), EmailDelivery::Immediate);
+        let muted = Notification::new(Severity::Critical, "Restart", "ok");
+        assert_eq!(config.delivery(&muted), EmailDelivery::Off);
+    }
+
+    #[test]
+    fn test_digest_groups_by_category_when_due() {
+        let start = Utc::now();
+        let mut digest = DigestBuffer::new(DigestInterval::Hourly.duration(), start);
+        digest.push(start, Notification::new(Severity::Info, "Fill", "BTC +1.2%").in_category(Category::Trade));
+        digest.push(start, Notification::new(Severity::Info, "Fill", "ETH -0.4%").in_category(Category::Trade));
+        digest.push(start, Notification::new(Severity::Warning, "Liquidation risk", "SOL").in_category(Category::Risk));
+
+        assert!(digest.take_due(start + Duration::minutes(59)).is_none());
+        let (subject, body) = digest.take_due(start + Duration::minutes(60)).unwrap();
+        assert!(subject.ends_with("3 events"), "{}", subject);
+        assert!(body.contains("== risk (1) =="));
+        assert!(body.contains("== trade (2) =="));
+        assert!(body.contains("[INFO] Fill: ETH -0.4%"));
+        assert!(digest.is_empty());
+        // An empty period sends nothing
+        assert!(digest.take_due(start + Duration::hours(3)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bad_address_fails_at_startup() {
+        let notifier = EmailNotifier::new(EmailConfig { to: vec!["not an address".to_string()], ..config() });
+        assert!(notifier.is_err());
+        assert!(EmailNotifier::new(config()).is_ok());
+    }
+}
@@ -0,0 +1,390 @@
+//! Operator notifications routed by severity.
+//!
+//! Every chat backend implements [`Notifier`]; [`NotificationRouter`] decides who hears
+//! what: `Info` only goes to the log, `Warning` goes to the chat channels, `Critical`
+//! goes to every chat channel plus the optional SMS gateway hook. Risk events from the
+//! [`RiskEventBus`](crate::risk::RiskEventBus) map onto notifications with
+//! [`Notification::from_risk_event`]. Digest channels such as email get every
+//! notification and decide per [`Category`] whether to send now, batch or skip.
+
+#[cfg(feature = "email")]
+pub mod email;
+pub mod webhooks;
+
+#[cfg(feature = "email")]
+pub use email::{DigestBuffer, DigestInterval, EmailConfig, EmailDelivery, EmailNotifier};
+pub use webhooks::{DiscordNotifier, SlackNotifier, SmsGatewayNotifier, TelegramNotifier};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::risk::{LiquidationWarning, RiskEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Info => "INFO",
+            Self::Warning => "WARNING",
+            Self::Critical => "CRITICAL",
+        }
+    }
+}
+
+/// What a notification is about; digest channels are configured per category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Trade,
+    /// Stops, liquidation warnings, panic sells
+    Risk,
+    Session,
+    Order,
+    System,
+}
+
+impl Category {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Trade => "trade",
+            Self::Risk => "risk",
+            Self::Session => "session",
+            Self::Order => "order",
+            Self::System => "system",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub severity: Severity,
+    pub category: Category,
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    pub fn new(severity: Severity, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            severity,
+            category: Category::System,
+            title: title.into(),
+            body: body.into(),
+        }
+    }
+
+    pub fn in_category(mut self, category: Category) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// One-line plain text form used by backends without rich formatting
+    pub fn text(&self) -> String {
+        format!("[{}] {}: {}", self.severity.label(), self.title, self.body)
+    }
+
+    pub fn from_risk_event(event: &RiskEvent) -> Self {
+        match event {
+            RiskEvent::StopTriggered { reason, panic_sell } => Self::new(
+                Severity::Critical,
+                "Trading stopped",
+                format!("reason {:?}, panic sell {}", reason, panic_sell),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::LiquidationWarningChanged { symbol, from, to } => {
+                let severity = match to {
+                    LiquidationWarning::Critical | LiquidationWarning::High => Severity::Critical,
+                    LiquidationWarning::Medium => Severity::Warning,
+                    LiquidationWarning::Low | LiquidationWarning::None => Severity::Info,
+                };
+                Self::new(severity, "Liquidation risk", format!("{} {:?} -> {:?}", symbol, from, to))
+                    .in_category(Category::Risk)
+            }
+            RiskEvent::PanicSellExecuted { symbol, price, size } => Self::new(
+                Severity::Critical,
+                "Panic sell",
+                format!("{} sold {} @ {}", symbol, size, price),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::SessionClosed { session, pnl, trades, action } => Self::new(
+                Severity::Info,
+                "Session closed",
+                format!("{}: pnl {:.2} over {} trades ({:?})", session, pnl, trades, action),
+            )
+            .in_category(Category::Session),
+            RiskEvent::OrderRejected { symbol, client_order_id, strategy } => Self::new(
+                Severity::Warning,
+                "Order rejected",
+                format!("{} order {} from strategy #{}", symbol, client_order_id, strategy),
+            )
+            .in_category(Category::Order),
+            RiskEvent::PerformanceDrift { strategy, metric, live_mean, expected_mean, z, paused } => Self::new(
+                if *paused { Severity::Critical } else { Severity::Warning },
+                "Live drift from backtest",
+                format!(
+                    "{} {}: live {:.4} vs backtest {:.4} (z {:.1}){}",
+                    strategy,
+                    metric.name(),
+                    live_mean,
+                    expected_mean,
+                    z,
+                    if *paused { ", strategy paused" } else { "" }
+                ),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::SymbolCircuitBreaker { symbol, tripped: Some(reason) } => Self::new(
+                Severity::Critical,
+                "Symbol disabled",
+                format!("{} trading disabled by circuit breaker ({:?})", symbol, reason),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::SymbolCircuitBreaker { symbol, tripped: None } => Self::new(
+                Severity::Info,
+                "Symbol re-enabled",
+                format!("{} circuit breaker reset", symbol),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::OpenInterestCascade { symbol, oi_change_pct, price_change_pct } => Self::new(
+                Severity::Warning,
+                "Liquidation cascade",
+                format!(
+                    "{} open interest {:+.2}% with price {:+.2}%, new longs blocked",
+                    symbol, oi_change_pct, price_change_pct
+                ),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::SymbolStale { symbol, silent_ms, stale: true } => Self::new(
+                Severity::Warning,
+                "Symbol data stale",
+                format!("{} silent for {}s while other symbols flow, entries held, stops widened", symbol, silent_ms / 1_000),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::SymbolStale { symbol, silent_ms, stale: false } => Self::new(
+                Severity::Info,
+                "Symbol data recovered",
+                format!("{} ticking again after {}s of silence", symbol, silent_ms / 1_000),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::OrphanFill { symbol, strategy, side, price, size } => Self::new(
+                Severity::Critical,
+                "Orphan fill",
+                format!(
+                    "{} {:?} fill {} @ {} for strategy #{} the shard no longer runs, reconcile the position",
+                    symbol, side, size, price, strategy
+                ),
+            )
+            .in_category(Category::Risk),
+            RiskEvent::DelistingStranded { symbol, size, halted } => Self::new(
+                Severity::Critical,
+                "Position stranded by delisting",
+                format!(
+                    "{} {} with a {} position still open, close it by hand",
+                    symbol,
+                    if *halted { "halted" } else { "delisted" },
+                    size
+                ),
+            )
+            .in_category(Category::Risk),
+        }
+    }
+}
+
+/// A delivery channel for notifications
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+    async fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Lets a channel be shared with a background task (e.g. the email digest timer)
+#[async_trait]
+impl<T: Notifier + ?Sized> Notifier for Arc<T> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        (**self).send(notification).await
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    pub telegram: Option<webhooks::TelegramConfig>,
+    #[serde(default)]
+    pub discord: Option<webhooks::DiscordConfig>,
+    #[serde(default)]
+    pub slack: Option<webhooks::SlackConfig>,
+    /// Only used for `Critical`
+    #[serde(default)]
+    pub sms: Option<webhooks::SmsGatewayConfig>,
+    #[cfg(feature = "email")]
+    #[serde(default)]
+    pub email: Option<email::EmailConfig>,
+}
+
+/// Fans notifications out to the channels their severity calls for
+#[derive(Default)]
+pub struct NotificationRouter {
+    chat: Vec<Box<dyn Notifier>>,
+    sms: Option<Box<dyn Notifier>>,
+    /// Receive every severity and filter by category themselves
+    digest: Vec<Box<dyn Notifier>>,
+}
+
+impl NotificationRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds every configured channel. With email configured the digest timer is
+    /// spawned, so this must run inside a tokio runtime.
+    pub fn from_config(config: &NotificationsConfig) -> Result<Self> {
+        let http = webhooks::http_client();
+        let mut router = Self::new();
+        if let Some(telegram) = &config.telegram {
+            router = router.with_chat(TelegramNotifier::new(http.clone(), telegram.clone()));
+        }
+        if let Some(discord) = &config.discord {
+            router = router.with_chat(DiscordNotifier::new(http.clone(), discord.clone()));
+        }
+        if let Some(slack) = &config.slack {
+            router = router.with_chat(SlackNotifier::new(http.clone(), slack.clone()));
+        }
+        if let Some(sms) = &config.sms {
+            router = router.with_sms(SmsGatewayNotifier::new(http, sms.clone()));
+        }
+        #[cfg(feature = "email")]
+        if let Some(email) = &config.email {
+            let notifier = Arc::new(EmailNotifier::new(email.clone())?);
+            notifier.spawn_digest();
+            router = router.with_digest(notifier);
+        }
+        Ok(router)
+    }
+
+    pub fn with_chat(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.chat.push(Box::new(notifier));
+        self
+    }
+
+    pub fn with_sms(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.sms = Some(Box::new(notifier));
+        self
+    }
+
+    pub fn with_digest(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.digest.push(Box::new(notifier));
+        self
+    }
+
+    /// Channels a notification of this severity is delivered to
+    pub fn targets(&self, severity: Severity) -> Vec<&dyn Notifier> {
+        let chat: &[Box<dyn Notifier>] = match severity {
+            Severity::Info => &[],
+            Severity::Warning | Severity::Critical => &self.chat,
+        };
+        let sms = self.sms.iter().filter(|_| severity == Severity::Critical);
+        chat.iter()
+            .chain(sms)
+            .chain(self.digest.iter())
+            .map(|n| n.as_ref())
+            .collect()
+    }
+
+    /// Logs the notification and delivers it to every target. A failing channel does not
+    /// stop the others; all failures are logged and returned together.
+    pub async fn notify(&self, notification: &Notification) -> Result<()> {
+        match notification.severity {
+            Severity::Info => log::info!("{}", notification.text()),
+            Severity::Warning => log::warn!("{}", notification.text()),
+            Severity::Critical => log::error!("{}", notification.text()),
+        }
+        let targets = self.targets(notification.severity);
+        if notification.severity == Severity::Critical && targets.is_empty() {
+            log::error!("critical notification has no delivery channel configured");
+        }
+        let mut failures = Vec::new();
+        for notifier in targets {
+            if let Err(err) = notifier.send(notification).await {
+                log::error!("notifier {} failed: {:#}", notifier.name(), err);
+                failures.push(format!("{}: {:#}", notifier.name(), err));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("notification delivery failed: {}", failures.join("; ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::symbol::Symbol;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder {
+        name: &'static str,
+        sent: Arc<Mutex<Vec<(&'static str, Severity)>>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Notifier for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(&self, notification: &Notification) -> Result<()> {
+            self.sent.lock().unwrap().push((self.name, notification.severity));
+            if self.fail {
+                return Err(anyhow!("webhook returned 500"));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_severity_routing() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let recorder = |name, fail| Recorder { name, sent: sent.clone(), fail };
+        let router = NotificationRouter::new()
+            .with_chat(recorder("discord", true))
+            .with_chat(recorder("slack", false))
+            .with_sms(recorder("sms", false));
+
+        router.notify(&Notification::new(Severity::Info, "fill", "ok")).await.unwrap();
+        assert!(sent.lock().unwrap().is_empty());
+
+        // Discord fails, Slack still gets the message
+        let err = router
+            .notify(&Notification::new(Severity::Warning, "reject", "order"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("discord"));
+        assert_eq!(
+            sent.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            [("discord", Severity::Warning), ("slack", Severity::Warning)]
+        );
+
+        let critical = Notification::from_risk_event(&RiskEvent::PanicSellExecuted {
+            symbol: Symbol::new("BTC_USDT"),
+            price: 100.0,
+            size: 1.0,
+        });
+        let _ = router.notify(&critical).await;
+        let names: Vec<_> = sent.lock().unwrap().iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["discord", "slack", "sms"]);
+    }
+}
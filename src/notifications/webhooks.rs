@@ -0,0 +1,244 @@
+//! Webhook backends: Telegram bot API, Discord webhooks, Slack incoming webhooks and a
+//! generic SMS gateway hook. Each backend builds its JSON payload separately from the
+//! HTTP call so formatting is testable without a network.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{Notification, Notifier, Severity};
+
+/// Shared client for all webhook backends; a hung webhook must not hold up the caller
+pub fn http_client() -> Client {
+    Client::builder()
+        .user_agent("trading-bot-notifier/0.1")
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("reqwest client")
+}
+
+async fn post_json(http: &Client, name: &str, url: &str, payload: &Value) -> Result<()> {
+    let response = http
+        .post(url)
+        .json(payload)
+        .send()
+        .await
+        .with_context(|| format!("{} webhook request", name))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("{} webhook returned {}: {}", name, status, body);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+pub struct TelegramNotifier {
+    http: Client,
+    config: TelegramConfig,
+}
+
+impl TelegramNotifier {
+    pub fn new(http: Client, config: TelegramConfig) -> Self {
+        Self { http, config }
+    }
+
+    pub fn payload(&self, notification: &Notification) -> Value {
+        json!({
+            "chat_id": self.config.chat_id,
+            "text": notification.text(),
+            // Info never reaches chat; warnings arrive silently, critical ones ring
+            "disable_notification": notification.severity < Severity::Critical,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.config.bot_token);
+        post_json(&self.http, self.name(), &url, &self.payload(notification)).await
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    /// Overrides the webhook's default bot name
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+pub struct DiscordNotifier {
+    http: Client,
+    config: DiscordConfig,
+}
+
+impl DiscordNotifier {
+    pub fn new(http: Client, config: DiscordConfig) -> Self {
+        Self { http, config }
+    }
+
+    pub fn payload(&self, notification: &Notification) -> Value {
+        let color = match notification.severity {
+            Severity::Info => 0x3498db,
+            Severity::Warning => 0xf1c40f,
+            Severity::Critical => 0xe74c3c,
+        };
+        let mut payload = json!({
+            "embeds": [{
+                "title": format!("[{}] {}", notification.severity.label(), notification.title),
+                "description": notification.body,
+                "color": color,
+            }],
+        });
+        if let Some(username) = &self.config.username {
+            payload["username"] = json!(username);
+        }
+        payload
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        post_json(&self.http, self.name(), &self.config.webhook_url, &self.payload(notification)).await
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackConfig {
+    pub webhook_url: String,
+}
+
+pub struct SlackNotifier {
+    http: Client,
+    config: SlackConfig,
+}
+
+impl SlackNotifier {
+    pub fn new(http: Client, config: SlackConfig) -> Self {
+        Self { http, config }
+    }
+
+    pub fn payload(&self, notification: &Notification) -> Value {
+        let mention = if notification.severity == Severity::Critical { "<!channel> " } else { "" };
+        json!({
+            "text": format!(
+                "{}*[{}] {}*\n{}",
+                mention,
+                notification.severity.label(),
+                notification.title,
+                notification.body
+            ),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        post_json(&self.http, self.name(), &self.config.webhook_url, &self.payload(notification)).await
+    }
+}
+
+/// Generic SMS gateway: POSTs `{"to": ..., "message": ...}` to the configured URL,
+/// optionally with a bearer token. Adapters for specific providers sit behind the URL.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmsGatewayConfig {
+    pub url: String,
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
+pub struct SmsGatewayNotifier {
+    http: Client,
+    config: SmsGatewayConfig,
+}
+
+impl SmsGatewayNotifier {
+    pub fn new(http: Client, config: SmsGatewayConfig) -> Self {
+        Self { http, config }
+    }
+
+    pub fn payload(&self, notification: &Notification) -> Value {
+        json!({
+            "to": self.config.to,
+            "message": notification.text(),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SmsGatewayNotifier {
+    fn name(&self) -> &str {
+        "sms"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let mut request = self.http.post(&self.config.url).json(&self.payload(notification));
+        if let Some(token) = &self.config.auth_token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.context("sms gateway request")?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("sms gateway returned {}", status);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn critical() -> Notification {
+        Notification::new(Severity::Critical, "Trading stopped", "ping 1500ms")
+    }
+
+    #[test]
+    fn test_payloads() {
+        let http = http_client();
+        let discord = DiscordNotifier::new(
+            http.clone(),
+            DiscordConfig { webhook_url: "https://discord.test/hook".to_string(), username: Some("bot".to_string()) },
+        );
+        let payload = discord.payload(&critical());
+        assert_eq!(payload["username"], "bot");
+        assert_eq!(payload["embeds"][0]["title"], "[CRITICAL] Trading stopped");
+        assert_eq!(payload["embeds"][0]["color"], 0xe74c3c);
+
+        let slack = SlackNotifier::new(http.clone(), SlackConfig { webhook_url: "https://slack.test/hook".to_string() });
+        assert_eq!(slack.payload(&critical())["text"], "<!channel> *[CRITICAL] Trading stopped*\nping 1500ms");
+        let warning = Notification::new(Severity::Warning, "Order rejected", "t-1");
+        assert_eq!(slack.payload(&warning)["text"], "*[WARNING] Order rejected*\nt-1");
+
+        let telegram = TelegramNotifier::new(http, TelegramConfig { bot_token: "t".to_string(), chat_id: "42".to_string() });
+        let payload = telegram.payload(&warning);
+        assert_eq!(payload["chat_id"], "42");
+        assert_eq!(payload["disable_notification"], true);
+    }
+}
@@ -0,0 +1,225 @@
+//! Capital Allocator - перераспределение бюджета order_size между стратегиями
+//!
+//! Каждая стратегия оценивается по скользящему окну доходностей сделок (% от её бюджета):
+//! score = среднее / стандартное отклонение, как Sharpe на сделку. Общий бюджет делится
+//! пропорционально положительным оценкам в пределах [min_budget, max_budget]; стратегии
+//! с отрицательной оценкой уходят на минимум. Стратегии, у которых в окне меньше
+//! `min_trades` сделок, держат текущий бюджет. Перераспределение не чаще `cooldown`.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct AllocationConfig {
+    /// Сумма бюджетов всех стратегий (USDT на ордер)
+    pub total_budget: f64,
+    pub min_budget: f64,
+    pub max_budget: f64,
+    /// Сделок в скользящем окне оценки
+    pub window: usize,
+    pub min_trades: usize,
+    pub cooldown: Duration,
+}
+
+impl Default for AllocationConfig {
+    fn default() -> Self {
+        Self {
+            total_budget: 100.0,
+            min_budget: 5.0,
+            max_budget: 50.0,
+            window: 50,
+            min_trades: 10,
+            cooldown: Duration::hours(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    name: String,
+    budget: f64,
+    returns: VecDeque<f64>,
+}
+
+impl Slot {
+    fn score(&self, min_trades: usize) -> Option<f64> {
+        let n = self.returns.len();
+        if n < min_trades.max(2) {
+            return None;
+        }
+        let mean = self.returns.iter().sum::<f64>() / n as f64;
+        let var = self.returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        let std = var.sqrt();
+        Some(if std > 0.0 { mean / std } else { mean.signum() * 10.0 })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CapitalAllocator {
+    config: AllocationConfig,
+    slots: Vec<Slot>,
+    last_rebalance: Option<DateTime<Utc>>,
+}
+
+impl CapitalAllocator {
+    pub fn new(config: AllocationConfig) -> Self {
+        assert!(
+            config.min_budget >= 0.0 && config.min_budget <= config.max_budget,
+            "allocation bounds must satisfy 0 <= min <= max"
+        );
+        Self { config, slots: Vec::new(), last_rebalance: None }
+    }
+
+    /// Регистрирует стратегию; начальный бюджет - равная доля, в пределах границ
+    pub fn register(&mut self, name: &str) {
+        assert!(self.slots.iter().all(|s| s.name != name), "strategy {} registered twice", name);
+        self.slots.push(Slot { name: name.to_string(), budget: 0.0, returns: VecDeque::new() });
+        let equal = (self.config.total_budget / self.slots.len() as f64).clamp(self.config.min_budget, self.config.max_budget);
+        for slot in &mut self.slots {
+            slot.budget = equal;
+        }
+        let total = self.slots.len() as f64 * self.config.min_budget;
+        assert!(
+            total <= self.config.total_budget + 1e-9,
+            "{} strategies need {} at min_budget, total budget is {}",
+            self.slots.len(),
+            total,
+            self.config.total_budget
+        );
+    }
+
+    /// Доходность закрытой сделки в % от бюджета стратегии
+    pub fn record_trade(&mut self, name: &str, return_pct: f64) {
+        let window = self.config.window;
+        let Some(slot) = self.slots.iter_mut().find(|s| s.name == name) else {
+            log::warn!("allocation: trade for unregistered strategy {}", name);
+            return;
+        };
+        if slot.returns.len() == window {
+            slot.returns.pop_front();
+        }
+        slot.returns.push_back(return_pct);
+    }
+
+    pub fn budget(&self, name: &str) -> Option<f64> {
+        self.slots.iter().find(|s| s.name == name).map(|s| s.budget)
+    }
+
+    pub fn score(&self, name: &str) -> Option<f64> {
+        self.slots.iter().find(|s| s.name == name)?.score(self.config.min_trades)
+    }
+
+    /// Перераспределяет бюджеты, если прошёл cooldown и есть что оценивать.
+    /// Возвращает новые бюджеты (имя, бюджет) или None.
+    pub fn rebalance(&mut self, now: DateTime<Utc>) -> Option<Vec<(String, f64)>> {
+        if self.last_rebalance.is_some_and(|at| now - at < self.config.cooldown) {
+            return None;
+        }
+        let scores: Vec<Option<f64>> = self.slots.iter().map(|s| s.score(self.config.min_trades)).collect();
+        if scores.iter().all(Option::is_none) {
+            return None;
+        }
+        // Неоценённые держат свой бюджет, остальные делят остаток
+        let fixed: f64 = self.slots.iter().zip(&scores).filter(|(_, s)| s.is_none()).map(|(slot, _)| slot.budget).sum();
+        let scored: Vec<(usize, f64)> = scores
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| s.map(|s| (i, s.max(0.0))))
+            .collect();
+        let budgets = self.water_fill(self.config.total_budget - fixed, &scored);
+        for (i, budget) in budgets {
+            self.slots[i].budget = budget;
+        }
+        self.last_rebalance = Some(now);
+        Some(self.slots.iter().map(|s| (s.name.clone(), s.budget)).collect())
+    }
+
+    /// Делит `pool` пропорционально весам с зажимом в [min, max]: зажатые выбывают,
+    /// остаток пула делится между оставшимися
+    fn water_fill(&self, pool: f64, weights: &[(usize, f64)]) -> Vec<(usize, f64)> {
+        let (min, max) = (self.config.min_budget, self.config.max_budget);
+        let mut result: Vec<(usize, f64)> = Vec::with_capacity(weights.len());
+        let mut free: Vec<(usize, f64)> = weights.to_vec();
+        let mut pool = pool;
+        while !free.is_empty() {
+            let total_weight: f64 = free.iter().map(|(_, w)| w).sum();
+            let share = |w: f64| {
+                if total_weight > 0.0 { pool * w / total_weight } else { pool / free.len() as f64 }
+            };
+            let clamped: Vec<(usize, f64)> = free
+                .iter()
+                .filter_map(|&(i, w)| {
+                    let s = share(w);
+                    (s < min || s > max).then(|| (i, s.clamp(min, max)))
+                })
+                .collect();
+            if clamped.is_empty() {
+                result.extend(free.iter().map(|&(i, w)| (i, share(w))));
+                break;
+            }
+            for (i, budget) in clamped {
+                pool -= budget;
+                free.retain(|&(j, _)| j != i);
+                result.push((i, budget));
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allocator() -> CapitalAllocator {
+        let mut allocator = CapitalAllocator::new(AllocationConfig {
+            total_budget: 90.0,
+            min_budget: 10.0,
+            max_budget: 50.0,
+            window: 20,
+            min_trades: 10,
+            cooldown: Duration::minutes(30),
+        });
+        for name in ["Hook", "MStrike", "MShot"] {
+            allocator.register(name);
+        }
+        allocator
+    }
+
+    #[test]
+    fn test_shifts_budget_to_better_strategy_within_bounds() {
+        let mut allocator = allocator();
+        assert_eq!(allocator.budget("Hook"), Some(30.0));
+        for i in 0..10 {
+            allocator.record_trade("Hook", if i % 5 == 0 { -0.5 } else { 1.0 });
+            allocator.record_trade("MStrike", if i % 2 == 0 { 0.6 } else { -0.4 });
+            allocator.record_trade("MShot", -0.3);
+        }
+        let now = Utc::now();
+        let budgets = allocator.rebalance(now).unwrap();
+        let total: f64 = budgets.iter().map(|(_, b)| b).sum();
+        assert!((total - 90.0).abs() < 1e-9);
+        // MShot в минусе - минимум, Hook упирается в максимум, остаток - MStrike
+        assert_eq!(allocator.budget("MShot"), Some(10.0));
+        assert_eq!(allocator.budget("Hook"), Some(50.0));
+        assert!((allocator.budget("MStrike").unwrap() - 30.0).abs() < 1e-9);
+
+        // Cooldown не даёт дёргать бюджеты
+        assert!(allocator.rebalance(now + Duration::minutes(10)).is_none());
+        assert!(allocator.rebalance(now + Duration::minutes(30)).is_some());
+    }
+
+    #[test]
+    fn test_unscored_strategies_keep_budget() {
+        let mut allocator = allocator();
+        assert!(allocator.rebalance(Utc::now()).is_none());
+        for _ in 0..10 {
+            allocator.record_trade("Hook", 1.0);
+            allocator.record_trade("MStrike", -1.0);
+        }
+        allocator.rebalance(Utc::now()).unwrap();
+        assert_eq!(allocator.budget("MShot"), Some(30.0));
+        assert_eq!(allocator.budget("Hook"), Some(50.0));
+        assert_eq!(allocator.budget("MStrike"), Some(10.0));
+    }
+}
@@ -3,9 +3,13 @@
 //! Функции:
 //! - Auto Stop if errors level >= N
 //! - Auto Stop if Ping > N ms
+//! - Auto Stop if рассинхрон часов с биржей > N ms (подписанные запросы начнут отвергаться)
 //! - Panic Sell опция при остановке
 //! - Restart in N minutes после остановки
+//! - Подавление на время обслуживания биржи: ошибки и пинг лежащей площадки не
+//!   останавливают торговлю и не копятся в уровень ошибок
 
+use super::events::{RiskEvent, RiskEventBus};
 use chrono::{DateTime, Utc, Duration};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,6 +17,7 @@ pub enum StopReason {
     None,
     ErrorLevelExceeded,
     PingTooHigh,
+    ClockSkew,
     Manual,
 }
 
@@ -21,12 +26,17 @@ pub struct AutoStopManager {
     pub max_error_level: u32,
     pub current_error_level: u32,
     pub max_ping_ms: u64,
+    pub max_clock_skew_ms: u64,
     pub panic_sell_on_stop: bool,
     pub restart_after_minutes: Option<u32>,
     
     pub stopped_at: Option<DateTime<Utc>>,
     pub stop_reason: StopReason,
     pub error_history: Vec<DateTime<Utc>>, // История ошибок для decay
+    /// Шина событий: остановка публикуется как StopTriggered
+    pub events: Option<RiskEventBus>,
+    /// До этого момента ошибки и пинг не учитываются (обслуживание биржи)
+    pub suppressed_until: Option<DateTime<Utc>>,
 }
 
 impl Default for AutoStopManager {
@@ -35,11 +45,14 @@ impl Default for AutoStopManager {
             max_error_level: 3,
             current_error_level: 0,
             max_ping_ms: 1000,
+            max_clock_skew_ms: 1000,
             panic_sell_on_stop: false,
             restart_after_minutes: Some(5),
             stopped_at: None,
             stop_reason: StopReason::None,
             error_history: Vec::new(),
+            events: None,
+            suppressed_until: None,
         }
     }
 }
@@ -55,16 +68,34 @@ impl AutoStopManager {
             max_error_level,
             current_error_level: 0,
             max_ping_ms,
+            max_clock_skew_ms: 1000,
             panic_sell_on_stop,
             restart_after_minutes,
             stopped_at: None,
             stop_reason: StopReason::None,
             error_history: Vec::new(),
+            events: None,
+            suppressed_until: None,
         }
     }
 
+    /// Не учитывать ошибки и пинг до `until` (продлевает, но не сокращает подавление)
+    pub fn suppress_until(&mut self, until: DateTime<Utc>) {
+        if self.suppressed_until.is_none_or(|current| current < until) {
+            log::warn!("auto stop: error and ping checks suppressed until {}", until);
+            self.suppressed_until = Some(until);
+        }
+    }
+
+    pub fn is_suppressed(&self) -> bool {
+        self.suppressed_until.is_some_and(|until| Utc::now() < until)
+    }
+
     /// Записывает ошибку и увеличивает уровень ошибок
     pub fn record_error(&mut self) {
+        if self.is_suppressed() {
+            return;
+        }
         let now = Utc::now();
         self.error_history.push(now);
         self.current_error_level += 1;
@@ -81,16 +112,25 @@ impl AutoStopManager {
 
     /// Проверяет пинг и возвращает true если нужно остановиться
     pub fn check_ping(&mut self, ping_ms: u64) -> bool {
-        if ping_ms > self.max_ping_ms {
+        if ping_ms > self.max_ping_ms && !self.is_suppressed() {
             self.stop(StopReason::PingTooHigh);
             return true;
         }
         false
     }
 
+    /// Проверяет рассинхрон часов (сервер минус локальные, ms) и возвращает true если нужно остановиться
+    pub fn check_clock_skew(&mut self, skew_ms: i64) -> bool {
+        if skew_ms.unsigned_abs() > self.max_clock_skew_ms {
+            self.stop(StopReason::ClockSkew);
+            return true;
+        }
+        false
+    }
+
     /// Проверяет уровень ошибок и возвращает true если нужно остановиться
     pub fn check_errors(&mut self) -> bool {
-        if self.current_error_level >= self.max_error_level {
+        if self.current_error_level >= self.max_error_level && !self.is_suppressed() {
             self.stop(StopReason::ErrorLevelExceeded);
             return true;
         }
@@ -102,6 +142,9 @@ impl AutoStopManager {
         if self.stopped_at.is_none() {
             self.stopped_at = Some(Utc::now());
             self.stop_reason = reason;
+            if let Some(events) = &self.events {
+                events.publish(RiskEvent::StopTriggered { reason, panic_sell: self.panic_sell_on_stop });
+            }
         }
     }
 
@@ -182,6 +225,15 @@ mod tests {
         assert_eq!(manager.stop_reason(), StopReason::PingTooHigh);
     }
 
+    #[test]
+    fn test_stop_on_clock_skew() {
+        let mut manager = AutoStopManager::default();
+
+        assert!(!manager.check_clock_skew(-900));
+        assert!(manager.check_clock_skew(-1500));
+        assert_eq!(manager.stop_reason(), StopReason::ClockSkew);
+    }
+
     #[test]
     fn test_restart() {
         let mut manager = AutoStopManager::new(3, 1000, false, Some(1));
@@ -195,5 +247,32 @@ mod tests {
         assert!(!manager.is_stopped());
         assert_eq!(manager.current_error_level, 0);
     }
-}
 
+    #[test]
+    fn test_stop_is_published_once() {
+        let bus = RiskEventBus::new();
+        let notifier = bus.subscribe("notifier", &[]);
+        let mut manager = AutoStopManager { events: Some(bus), ..AutoStopManager::default() };
+
+        assert!(manager.check_ping(5000));
+        manager.stop(StopReason::Manual);
+        let events: Vec<_> = notifier.try_iter().map(|e| e.event.clone()).collect();
+        assert_eq!(events, [RiskEvent::StopTriggered { reason: StopReason::PingTooHigh, panic_sell: false }]);
+    }
+    #[test]
+    fn test_suppression_ignores_venue_error_storm() {
+        let mut manager = AutoStopManager::new(2, 1000, false, None);
+        manager.suppress_until(Utc::now() + Duration::minutes(5));
+        for _ in 0..10 {
+            manager.record_error();
+        }
+        assert!(!manager.check_errors());
+        assert!(!manager.check_ping(60_000));
+        assert!(!manager.is_stopped());
+
+        manager.suppressed_until = None;
+        manager.record_error();
+        manager.record_error();
+        assert!(manager.check_errors());
+    }
+}
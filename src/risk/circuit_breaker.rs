@@ -0,0 +1,202 @@
+//! Circuit Breaker - отключение торговли по одному символу
+//!
+//! Пара, которую сняли с торгов или приостановили, отвергает каждую заявку и не даёт
+//! отменять ордера. Глобальный AutoStop из-за неё останавливать нельзя, поэтому символ
+//! выключается отдельно: после `max_rejections` отказов подряд или `max_failed_cancels`
+//! неудачных отмен за `window`. Принятая биржей заявка обнуляет серию отказов.
+//! Через `cooldown` символ включается сам; срабатывание и сброс публикуются в шину событий.
+
+use super::events::{RiskEvent, RiskEventBus};
+use crate::base_classes::symbol::Symbol;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    pub max_rejections: usize,
+    pub max_failed_cancels: usize,
+    pub window: Duration,
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            max_rejections: 5,
+            max_failed_cancels: 3,
+            window: Duration::minutes(5),
+            cooldown: Duration::minutes(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerTrip {
+    Rejections,
+    FailedCancels,
+}
+
+#[derive(Debug, Default)]
+struct SymbolFaults {
+    rejections: VecDeque<DateTime<Utc>>,
+    failed_cancels: VecDeque<DateTime<Utc>>,
+    tripped_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct SymbolCircuitBreaker {
+    config: CircuitBreakerConfig,
+    symbols: HashMap<Symbol, SymbolFaults>,
+    /// Выключенных символов; при нуле проверка на горячем пути не смотрит в карту
+    tripped: usize,
+    pub events: Option<RiskEventBus>,
+}
+
+impl SymbolCircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        assert!(
+            config.max_rejections >= 1 && config.max_failed_cancels >= 1,
+            "circuit breaker thresholds must be at least 1"
+        );
+        Self { config, symbols: HashMap::new(), tripped: 0, events: None }
+    }
+
+    /// Биржа отвергла заявку по символу. Возвращает true, если символ выключился этим отказом
+    pub fn record_rejection(&mut self, symbol: Symbol, now: DateTime<Utc>) -> bool {
+        let window = self.config.window;
+        let faults = self.symbols.entry(symbol).or_default();
+        faults.rejections.push_back(now);
+        prune(&mut faults.rejections, now, window);
+        let count = faults.rejections.len();
+        count >= self.config.max_rejections && self.trip(symbol, BreakerTrip::Rejections, now)
+    }
+
+    /// Отмена ордера по символу не прошла
+    pub fn record_failed_cancel(&mut self, symbol: Symbol, now: DateTime<Utc>) -> bool {
+        let window = self.config.window;
+        let faults = self.symbols.entry(symbol).or_default();
+        faults.failed_cancels.push_back(now);
+        prune(&mut faults.failed_cancels, now, window);
+        let count = faults.failed_cancels.len();
+        count >= self.config.max_failed_cancels && self.trip(symbol, BreakerTrip::FailedCancels, now)
+    }
+
+    /// Биржа приняла заявку: серия отказов прервана
+    pub fn record_accepted(&mut self, symbol: Symbol) {
+        if let Some(faults) = self.symbols.get_mut(&symbol) {
+            faults.rejections.clear();
+        }
+    }
+
+    /// Можно ли торговать символом; истёкший cooldown включает символ обратно
+    pub fn allows(&mut self, symbol: Symbol, now: DateTime<Utc>) -> bool {
+        if self.tripped == 0 {
+            return true;
+        }
+        let Some(until) = self.symbols.get(&symbol).and_then(|f| f.tripped_until) else {
+            return true;
+        };
+        if now < until {
+            return false;
+        }
+        log::warn!("circuit breaker on {}: cooldown over, trading re-enabled", symbol);
+        self.reset(symbol);
+        true
+    }
+
+    pub fn is_tripped(&self, symbol: Symbol) -> bool {
+        self.symbols.get(&symbol).is_some_and(|f| f.tripped_until.is_some())
+    }
+
+    /// Включает символ вручную (или по истечении cooldown) и забывает накопленные ошибки
+    pub fn reset(&mut self, symbol: Symbol) {
+        let Some(faults) = self.symbols.remove(&symbol) else {
+            return;
+        };
+        if faults.tripped_until.is_some() {
+            self.tripped -= 1;
+            if let Some(events) = &self.events {
+                events.publish(RiskEvent::SymbolCircuitBreaker { symbol, tripped: None });
+            }
+        }
+    }
+
+    fn trip(&mut self, symbol: Symbol, reason: BreakerTrip, now: DateTime<Utc>) -> bool {
+        let faults = self.symbols.get_mut(&symbol).expect("faults recorded before trip");
+        if faults.tripped_until.is_some() {
+            return false;
+        }
+        let until = now + self.config.cooldown;
+        faults.tripped_until = Some(until);
+        self.tripped += 1;
+        log::error!(
+            "circuit breaker on {}: {:?} ({} rejections, {} failed cancels within {} min), trading disabled until {}",
+            symbol,
+            reason,
+            faults.rejections.len(),
+            faults.failed_cancels.len(),
+            self.config.window.num_minutes(),
+            until
+        );
+        if let Some(events) = &self.events {
+            events.publish(RiskEvent::SymbolCircuitBreaker { symbol, tripped: Some(reason) });
+        }
+        true
+    }
+}
+
+fn prune(times: &mut VecDeque<DateTime<Utc>>, now: DateTime<Utc>, window: Duration) {
+    while times.front().is_some_and(|&at| now - at > window) {
+        times.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskEventKind;
+
+    #[test]
+    fn test_breaker_trips_per_symbol_and_resets_after_cooldown() {
+        let mut breaker = SymbolCircuitBreaker::new(CircuitBreakerConfig {
+            max_rejections: 3,
+            max_failed_cancels: 2,
+            window: Duration::minutes(5),
+            cooldown: Duration::minutes(30),
+        });
+        let bus = RiskEventBus::new();
+        let alerts = bus.subscribe("notifier", &[RiskEventKind::SymbolCircuitBreaker]);
+        breaker.events = Some(bus);
+        let (dead, live) = (Symbol::new("LUNA_USDT"), Symbol::new("BTC_USDT"));
+        let t0 = Utc::now();
+
+        // Принятая заявка обнуляет серию, старые отказы выпадают из окна
+        breaker.record_rejection(dead, t0);
+        breaker.record_rejection(dead, t0);
+        breaker.record_accepted(dead);
+        breaker.record_rejection(dead, t0);
+        assert!(!breaker.record_rejection(dead, t0 + Duration::minutes(6)));
+        assert!(!breaker.record_rejection(dead, t0 + Duration::minutes(7)));
+        assert!(breaker.record_rejection(dead, t0 + Duration::minutes(8)));
+        assert!(!breaker.record_rejection(dead, t0 + Duration::minutes(8)));
+
+        let t1 = t0 + Duration::minutes(9);
+        assert!(!breaker.allows(dead, t1));
+        assert!(breaker.allows(live, t1));
+        assert!(!breaker.record_failed_cancel(live, t1));
+        assert!(breaker.record_failed_cancel(live, t1));
+        assert!(!breaker.allows(live, t1));
+
+        assert!(breaker.allows(dead, t1 + Duration::minutes(30)));
+        assert!(!breaker.is_tripped(dead));
+        let events: Vec<RiskEvent> = alerts.try_iter().map(|e| e.event.clone()).collect();
+        assert_eq!(
+            events,
+            [
+                RiskEvent::SymbolCircuitBreaker { symbol: dead, tripped: Some(BreakerTrip::Rejections) },
+                RiskEvent::SymbolCircuitBreaker { symbol: live, tripped: Some(BreakerTrip::FailedCancels) },
+                RiskEvent::SymbolCircuitBreaker { symbol: dead, tripped: None },
+            ]
+        );
+    }
+}
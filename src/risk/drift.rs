@@ -0,0 +1,298 @@
+//! Drift Monitor - живое поведение стратегии против распределения бэктеста
+//!
+//! Для того же конфига бэктест даёт ожидаемые распределения: доля исполненных ордеров,
+//! глубина прострела на детекте и PnL сделки. Монитор держит скользящее окно живых
+//! значений и считает z-оценку среднего окна относительно бэктеста
+//! (`(live - mean) / (std / sqrt(n))`). Выход за `alert_z` - алерт, за `pause_z` -
+//! пауза стратегии. Алерт по метрике публикуется один раз, пока метрика не вернётся в норму.
+
+use super::events::{RiskEvent, RiskEventBus};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriftMetric {
+    FillRate,
+    DetectionDepth,
+    TradePnl,
+}
+
+impl DriftMetric {
+    pub const ALL: [DriftMetric; 3] = [Self::FillRate, Self::DetectionDepth, Self::TradePnl];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FillRate => "fill_rate",
+            Self::DetectionDepth => "detection_depth",
+            Self::TradePnl => "trade_pnl",
+        }
+    }
+}
+
+/// Среднее и стандартное отклонение выборки бэктеста
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+    pub mean: f64,
+    pub std: f64,
+    pub samples: usize,
+}
+
+impl Distribution {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.len() < 2 {
+            return None;
+        }
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        Some(Self { mean, std: var.sqrt(), samples: samples.len() })
+    }
+
+    /// Доля исполнений как распределение Бернулли
+    pub fn from_fills(fills: &[bool]) -> Option<Self> {
+        if fills.is_empty() {
+            return None;
+        }
+        let p = fills.iter().filter(|&&f| f).count() as f64 / fills.len() as f64;
+        Some(Self { mean: p, std: (p * (1.0 - p)).sqrt(), samples: fills.len() })
+    }
+}
+
+/// Ожидания бэктеста; метрики без данных не проверяются
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BacktestExpectation {
+    pub fill_rate: Option<Distribution>,
+    pub detection_depth: Option<Distribution>,
+    pub trade_pnl: Option<Distribution>,
+}
+
+impl BacktestExpectation {
+    pub fn from_backtest(fills: &[bool], depths: &[f64], pnls: &[f64]) -> Self {
+        Self {
+            fill_rate: Distribution::from_fills(fills),
+            detection_depth: Distribution::from_samples(depths),
+            trade_pnl: Distribution::from_samples(pnls),
+        }
+    }
+
+    fn get(&self, metric: DriftMetric) -> Option<Distribution> {
+        match metric {
+            DriftMetric::FillRate => self.fill_rate,
+            DriftMetric::DetectionDepth => self.detection_depth,
+            DriftMetric::TradePnl => self.trade_pnl,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DriftConfig {
+    pub alert_z: f64,
+    /// None - только алерт, без паузы
+    pub pause_z: Option<f64>,
+    /// Меньше значений в окне - z не считается
+    pub min_samples: usize,
+    /// Размер скользящего окна живых значений
+    pub window: usize,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            alert_z: 3.0,
+            pause_z: None,
+            min_samples: 20,
+            window: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftAction {
+    None,
+    Alert,
+    PauseStrategy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftReport {
+    pub metric: DriftMetric,
+    pub live_mean: f64,
+    pub expected_mean: f64,
+    pub z: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Window {
+    values: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Window {
+    fn push(&mut self, value: f64, cap: usize) {
+        if self.values.len() == cap {
+            self.sum -= self.values.pop_front().unwrap_or(0.0);
+        }
+        self.values.push_back(value);
+        self.sum += value;
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum / self.values.len() as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DriftMonitor {
+    pub strategy: String,
+    pub expectation: BacktestExpectation,
+    pub config: DriftConfig,
+    pub events: Option<RiskEventBus>,
+    windows: [Window; 3],
+    /// Метрики, по которым алерт уже опубликован
+    alerted: [bool; 3],
+}
+
+impl DriftMonitor {
+    pub fn new(strategy: &str, expectation: BacktestExpectation, config: DriftConfig) -> Self {
+        assert!(config.window >= config.min_samples && config.min_samples > 1, "drift window must hold min_samples > 1");
+        Self {
+            strategy: strategy.to_string(),
+            expectation,
+            config,
+            events: None,
+            windows: Default::default(),
+            alerted: [false; 3],
+        }
+    }
+
+    fn record(&mut self, metric: DriftMetric, value: f64) {
+        self.windows[metric as usize].push(value, self.config.window);
+    }
+
+    /// Ордер на вход завершён: исполнен или снят
+    pub fn record_order(&mut self, filled: bool) {
+        self.record(DriftMetric::FillRate, if filled { 1.0 } else { 0.0 });
+    }
+
+    /// Глубина прострела (%) на детекте
+    pub fn record_detection(&mut self, depth_pct: f64) {
+        self.record(DriftMetric::DetectionDepth, depth_pct);
+    }
+
+    pub fn record_trade(&mut self, pnl: f64) {
+        self.record(DriftMetric::TradePnl, pnl);
+    }
+
+    /// z-оценка метрики; None без ожидания или при нехватке живых значений
+    pub fn report(&self, metric: DriftMetric) -> Option<DriftReport> {
+        let expected = self.expectation.get(metric)?;
+        let window = &self.windows[metric as usize];
+        if window.values.len() < self.config.min_samples {
+            return None;
+        }
+        let live_mean = window.mean();
+        let stderr = expected.std / (window.values.len() as f64).sqrt();
+        let z = if stderr > 0.0 {
+            (live_mean - expected.mean) / stderr
+        } else if live_mean == expected.mean {
+            0.0
+        } else {
+            // Бэктест без разброса: любое отличие - дрейф
+            f64::INFINITY.copysign(live_mean - expected.mean)
+        };
+        Some(DriftReport { metric, live_mean, expected_mean: expected.mean, z })
+    }
+
+    /// Проверяет все метрики; новые выходы за пороги публикуются в шину
+    pub fn check(&mut self) -> DriftAction {
+        let mut action = DriftAction::None;
+        for metric in DriftMetric::ALL {
+            let Some(report) = self.report(metric) else {
+                continue;
+            };
+            let z = report.z.abs();
+            let pause = self.config.pause_z.is_some_and(|pause_z| z >= pause_z);
+            let drifting = z >= self.config.alert_z || pause;
+            let metric_action = match (drifting, pause) {
+                (_, true) => DriftAction::PauseStrategy,
+                (true, false) => DriftAction::Alert,
+                _ => DriftAction::None,
+            };
+            if drifting && !self.alerted[metric as usize] {
+                log::warn!(
+                    "{} drifted from backtest on {}: live {:.4} vs expected {:.4} (z {:.2})",
+                    self.strategy,
+                    metric.name(),
+                    report.live_mean,
+                    report.expected_mean,
+                    report.z
+                );
+                if let Some(events) = &self.events {
+                    events.publish(RiskEvent::PerformanceDrift {
+                        strategy: self.strategy.clone(),
+                        metric,
+                        live_mean: report.live_mean,
+                        expected_mean: report.expected_mean,
+                        z: report.z,
+                        paused: pause,
+                    });
+                }
+            }
+            self.alerted[metric as usize] = drifting;
+            action = match (action, metric_action) {
+                (DriftAction::PauseStrategy, _) | (_, DriftAction::PauseStrategy) => DriftAction::PauseStrategy,
+                (DriftAction::Alert, _) | (_, DriftAction::Alert) => DriftAction::Alert,
+                _ => DriftAction::None,
+            };
+        }
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskEventKind;
+
+    fn monitor(config: DriftConfig) -> DriftMonitor {
+        let fills: Vec<bool> = (0..100).map(|i| i % 5 != 0).collect();
+        let pnls: Vec<f64> = (0..100).map(|i| if i % 2 == 0 { 1.5 } else { -0.5 }).collect();
+        DriftMonitor::new("Hook", BacktestExpectation::from_backtest(&fills, &[], &pnls), config)
+    }
+
+    #[test]
+    fn test_no_verdict_until_enough_samples() {
+        let mut monitor = monitor(DriftConfig::default());
+        for _ in 0..19 {
+            monitor.record_order(false);
+        }
+        assert_eq!(monitor.check(), DriftAction::None);
+        monitor.record_order(false);
+        assert_eq!(monitor.check(), DriftAction::Alert);
+        // Без данных бэктеста по глубине метрика не проверяется
+        monitor.record_detection(5.0);
+        assert!(monitor.report(DriftMetric::DetectionDepth).is_none());
+    }
+
+    #[test]
+    fn test_pnl_drift_pauses_and_alerts_once() {
+        let bus = RiskEventBus::new();
+        let alerts = bus.subscribe("notifier", &[RiskEventKind::PerformanceDrift]);
+        let mut monitor = monitor(DriftConfig { pause_z: Some(5.0), ..DriftConfig::default() });
+        monitor.events = Some(bus);
+
+        // Живые сделки в среднем как в бэктесте
+        for i in 0..40 {
+            monitor.record_trade(if i % 2 == 0 { 1.4 } else { -0.4 });
+        }
+        assert_eq!(monitor.check(), DriftAction::None);
+
+        for _ in 0..40 {
+            monitor.record_trade(-1.0);
+        }
+        let report = monitor.report(DriftMetric::TradePnl).unwrap();
+        assert!(report.z < -5.0, "{:?}", report);
+        assert_eq!(monitor.check(), DriftAction::PauseStrategy);
+        assert_eq!(monitor.check(), DriftAction::PauseStrategy);
+        assert_eq!(alerts.try_iter().count(), 1);
+    }
+}
@@ -0,0 +1,267 @@
+//! Risk Events - общая шина событий риска и торговли
+//!
+//! Риск-менеджеры и роутер публикуют типизированные события, а уведомления, журнал,
+//! метрики и дашборд подписываются на нужные виды, вместо отдельной проводки от каждого
+//! источника к каждому потребителю.
+//!
+//! Подписчик получает свой `Receiver` и разбирает его в своём потоке, публикация не
+//! блокируется на медленном потребителе. Отвалившийся подписчик удаляется при следующей
+//! публикации с предупреждением в лог. События редкие, поэтому шина под мьютексом.
+
+use super::auto_stop::StopReason;
+use super::circuit_breaker::BreakerTrip;
+use super::drift::DriftMetric;
+use super::liquidation::LiquidationWarning;
+use super::session::SessionAction;
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use chrono::{DateTime, Utc};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskEvent {
+    /// AutoStop остановил торговлю
+    StopTriggered { reason: StopReason, panic_sell: bool },
+    LiquidationWarningChanged {
+        symbol: Symbol,
+        from: LiquidationWarning,
+        to: LiquidationWarning,
+    },
+    PanicSellExecuted { symbol: Symbol, price: f64, size: f64 },
+    /// Сессия сброшена; итог до сброса
+    SessionClosed {
+        session: String,
+        pnl: f64,
+        trades: usize,
+        action: SessionAction,
+    },
+    OrderRejected {
+        symbol: Symbol,
+        client_order_id: String,
+        /// Индекс стратегии в наборе символа
+        strategy: usize,
+    },
+    /// Живое поведение стратегии ушло от бэктеста
+    PerformanceDrift {
+        strategy: String,
+        metric: DriftMetric,
+        live_mean: f64,
+        expected_mean: f64,
+        z: f64,
+        paused: bool,
+    },
+    /// Торговля символом выключена (`tripped` - причина) или снова включена (`None`)
+    SymbolCircuitBreaker { symbol: Symbol, tripped: Option<BreakerTrip> },
+    /// Цена и открытый интерес падают вместе: лонги по символу заблокированы
+    OpenInterestCascade { symbol: Symbol, oi_change_pct: f64, price_change_pct: f64 },
+    /// Поток символа молчит `silent_ms`, пока остальные идут (`stale`), или вернулся
+    SymbolStale { symbol: Symbol, silent_ms: u64, stale: bool },
+    /// Фил пришёл стратегии, которой в шарде нет (например, символ выселен):
+    /// позиция на бирже разошлась со стратегиями и требует сверки
+    OrphanFill { symbol: Symbol, strategy: usize, side: Side, price: f64, size: f64 },
+    /// Символ снимается с торгов (делистинг или остановка, `halted`), а позицию `size`
+    /// закрыть не удалось: её нужно закрыть вручную
+    DelistingStranded { symbol: Symbol, size: f64, halted: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RiskEventKind {
+    StopTriggered,
+    LiquidationWarningChanged,
+    PanicSellExecuted,
+    SessionClosed,
+    OrderRejected,
+    PerformanceDrift,
+    SymbolCircuitBreaker,
+    OpenInterestCascade,
+    SymbolStale,
+    OrphanFill,
+    DelistingStranded,
+}
+
+impl RiskEventKind {
+    pub const ALL: [RiskEventKind; 11] = [
+        Self::StopTriggered,
+        Self::LiquidationWarningChanged,
+        Self::PanicSellExecuted,
+        Self::SessionClosed,
+        Self::OrderRejected,
+        Self::PerformanceDrift,
+        Self::SymbolCircuitBreaker,
+        Self::OpenInterestCascade,
+        Self::SymbolStale,
+        Self::OrphanFill,
+        Self::DelistingStranded,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::StopTriggered => "stop_triggered",
+            Self::LiquidationWarningChanged => "liquidation_warning_changed",
+            Self::PanicSellExecuted => "panic_sell_executed",
+            Self::SessionClosed => "session_closed",
+            Self::OrderRejected => "order_rejected",
+            Self::PerformanceDrift => "performance_drift",
+            Self::SymbolCircuitBreaker => "symbol_circuit_breaker",
+            Self::OpenInterestCascade => "open_interest_cascade",
+            Self::SymbolStale => "symbol_stale",
+            Self::OrphanFill => "orphan_fill",
+            Self::DelistingStranded => "delisting_stranded",
+        }
+    }
+}
+
+impl RiskEvent {
+    pub fn kind(&self) -> RiskEventKind {
+        match self {
+            Self::StopTriggered { .. } => RiskEventKind::StopTriggered,
+            Self::LiquidationWarningChanged { .. } => RiskEventKind::LiquidationWarningChanged,
+            Self::PanicSellExecuted { .. } => RiskEventKind::PanicSellExecuted,
+            Self::SessionClosed { .. } => RiskEventKind::SessionClosed,
+            Self::OrderRejected { .. } => RiskEventKind::OrderRejected,
+            Self::PerformanceDrift { .. } => RiskEventKind::PerformanceDrift,
+            Self::SymbolCircuitBreaker { .. } => RiskEventKind::SymbolCircuitBreaker,
+            Self::OpenInterestCascade { .. } => RiskEventKind::OpenInterestCascade,
+            Self::SymbolStale { .. } => RiskEventKind::SymbolStale,
+            Self::OrphanFill { .. } => RiskEventKind::OrphanFill,
+            Self::DelistingStranded { .. } => RiskEventKind::DelistingStranded,
+        }
+    }
+}
+
+/// Событие с номером и временем публикации
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskEnvelope {
+    /// Сквозной номер в шине: по пропускам журнал видит потерянные события
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    pub event: RiskEvent,
+}
+
+struct Subscriber {
+    name: String,
+    /// Пустой список - все виды
+    kinds: Vec<RiskEventKind>,
+    tx: Sender<Arc<RiskEnvelope>>,
+}
+
+#[derive(Default)]
+struct BusInner {
+    subscribers: Vec<Subscriber>,
+    next_seq: u64,
+    published: [u64; RiskEventKind::ALL.len()],
+}
+
+/// Шина событий; клоны публикуют в одну и ту же шину
+#[derive(Clone, Default)]
+pub struct RiskEventBus {
+    inner: Arc<Mutex<BusInner>>,
+}
+
+impl std::fmt::Debug for RiskEventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.lock();
+        f.debug_struct("RiskEventBus")
+            .field("subscribers", &inner.subscribers.iter().map(|s| s.name.as_str()).collect::<Vec<_>>())
+            .field("next_seq", &inner.next_seq)
+            .finish()
+    }
+}
+
+impl RiskEventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BusInner> {
+        // Паника подписчика не держит мьютекс: внутри только отправка в каналы
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Подписка на виды событий (`&[]` - на все)
+    pub fn subscribe(&self, name: &str, kinds: &[RiskEventKind]) -> Receiver<Arc<RiskEnvelope>> {
+        let (tx, rx) = mpsc::channel();
+        self.lock().subscribers.push(Subscriber {
+            name: name.to_string(),
+            kinds: kinds.to_vec(),
+            tx,
+        });
+        rx
+    }
+
+    /// Публикует событие всем подписчикам его вида; возвращает номер события
+    pub fn publish(&self, event: RiskEvent) -> u64 {
+        let mut inner = self.lock();
+        let kind = event.kind();
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner.published[kind as usize] += 1;
+        let envelope = Arc::new(RiskEnvelope { seq, at: Utc::now(), event });
+        inner.subscribers.retain(|subscriber| {
+            if !subscriber.kinds.is_empty() && !subscriber.kinds.contains(&kind) {
+                return true;
+            }
+            let delivered = subscriber.tx.send(Arc::clone(&envelope)).is_ok();
+            if !delivered {
+                log::warn!(
+                    "risk event subscriber '{}' is gone, dropping it (missed {} #{})",
+                    subscriber.name,
+                    kind.name(),
+                    seq
+                );
+            }
+            delivered
+        });
+        seq
+    }
+
+    /// Сколько событий вида опубликовано с момента создания шины
+    pub fn published(&self, kind: RiskEventKind) -> u64 {
+        self.lock().published[kind as usize]
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.lock().subscribers.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(id: &str) -> RiskEvent {
+        RiskEvent::OrderRejected {
+            symbol: Symbol::new("BTC_USDT"),
+            client_order_id: id.to_string(),
+            strategy: 0,
+        }
+    }
+
+    #[test]
+    fn test_subscribers_get_only_their_kinds() {
+        let bus = RiskEventBus::new();
+        let journal = bus.subscribe("journal", &[]);
+        let notifier = bus.subscribe("notifier", &[RiskEventKind::StopTriggered]);
+
+        bus.publish(rejected("t-1"));
+        bus.publish(RiskEvent::StopTriggered { reason: StopReason::PingTooHigh, panic_sell: false });
+
+        let seqs: Vec<u64> = journal.try_iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [0, 1]);
+        let stops: Vec<_> = notifier.try_iter().collect();
+        assert_eq!(stops.len(), 1);
+        assert_eq!(stops[0].event.kind(), RiskEventKind::StopTriggered);
+        assert_eq!(bus.published(RiskEventKind::OrderRejected), 1);
+    }
+
+    #[test]
+    fn test_dropped_subscriber_is_removed() {
+        let bus = RiskEventBus::new();
+        let metrics = bus.subscribe("metrics", &[]);
+        drop(bus.subscribe("dashboard", &[]));
+        bus.publish(rejected("t-1"));
+        assert_eq!(bus.subscribers(), 1);
+        assert_eq!(metrics.try_iter().count(), 1);
+    }
+}
@@ -4,6 +4,11 @@
 //! - Расчет риска ликвидации
 //! - Предупреждения о близости к ликвидации
 //! - Автоматическое уменьшение позиции при риске
+//! - Смена уровня предупреждения публикуется в шину событий
+
+use super::events::{RiskEvent, RiskEventBus};
+use crate::base_classes::symbol::Symbol;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LiquidationWarning {
@@ -20,6 +25,9 @@ pub struct LiquidationControl {
     pub max_leverage: u32,
     pub maintenance_margin_rate: f64, // Процент маржи для поддержания позиции (обычно 0.5-1%)
     pub liquidation_price_threshold: f64, // % до ликвидации для предупреждения (например, 20%)
+    pub events: Option<RiskEventBus>,
+    /// Последний уровень предупреждения по символу
+    warnings: HashMap<Symbol, LiquidationWarning>,
 }
 
 impl Default for LiquidationControl {
@@ -29,6 +37,8 @@ impl Default for LiquidationControl {
             max_leverage: 125,
             maintenance_margin_rate: 0.01, // 1%
             liquidation_price_threshold: 20.0, // Предупреждение при 20% до ликвидации
+            events: None,
+            warnings: HashMap::new(),
         }
     }
 }
@@ -45,7 +55,22 @@ impl LiquidationControl {
             max_leverage,
             maintenance_margin_rate,
             liquidation_price_threshold,
+            events: None,
+            warnings: HashMap::new(),
+        }
+    }
+
+    /// Запоминает уровень предупреждения по символу; при смене публикует
+    /// LiquidationWarningChanged и возвращает true
+    pub fn observe_warning(&mut self, symbol: Symbol, warning: LiquidationWarning) -> bool {
+        let previous = self.warnings.insert(symbol, warning).unwrap_or(LiquidationWarning::None);
+        if previous == warning {
+            return false;
+        }
+        if let Some(events) = &self.events {
+            events.publish(RiskEvent::LiquidationWarningChanged { symbol, from: previous, to: warning });
         }
+        true
     }
 
     /// Проверяет риск ликвидации для позиции
@@ -110,7 +135,7 @@ impl LiquidationControl {
     ///
     /// Формула для short:
     /// liquidation_price = entry_price * (1 + (1 - maintenance_margin_rate) / leverage)
-    fn calculate_liquidation_price(
+    pub fn calculate_liquidation_price(
         &self,
         position_size: f64,
         entry_price: f64,
@@ -1,15 +1,33 @@
 //! Risk Management модуль
-//! Глобальное управление рисками, сессиями, паник-селлами
+//! Глобальное управление рисками, сессиями, паник-селлами, шина событий риска,
+//! профили лимитов риска, лимиты позиций по волатильности, режимы рынка,
+//! выключение отдельных символов по отказам биржи, блокировка лонгов на каскаде ликвидаций по OI
 
 pub mod global;
 pub mod session;
 pub mod panic_sell;
 pub mod auto_stop;
 pub mod liquidation;
+pub mod events;
+pub mod drift;
+pub mod allocation;
+pub mod profiles;
+pub mod vol_scaling;
+pub mod regime;
+pub mod circuit_breaker;
+pub mod open_interest;
 
 pub use global::{GlobalRiskManager, RiskAction};
 pub use session::{SessionManager, SessionAction};
 pub use panic_sell::{PanicSellManager};
 pub use auto_stop::{AutoStopManager, StopReason};
 pub use liquidation::{LiquidationControl, LiquidationWarning};
+pub use allocation::{AllocationConfig, CapitalAllocator};
+pub use profiles::{GlobalLimits, LiquidationLimits, ProfileWindow, RiskProfile, RiskProfileBook, SessionLimits};
+pub use vol_scaling::{ScaledLimits, VolScalingConfig, VolatilityPercentile, VolatilityScaler};
+pub use regime::{Regime, RegimeClassifier, RegimeConfig, RegimeTimeline};
+pub use circuit_breaker::{BreakerTrip, CircuitBreakerConfig, SymbolCircuitBreaker};
+pub use open_interest::{OiChange, OpenInterestConfig, OpenInterestMonitor};
+pub use drift::{BacktestExpectation, DriftAction, DriftConfig, DriftMetric, DriftMonitor};
 
+pub use events::{RiskEnvelope, RiskEvent, RiskEventBus, RiskEventKind};
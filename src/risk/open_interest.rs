@@ -0,0 +1,177 @@
+//! Открытый интерес: скорость изменения и блокировка лонгов на каскаде ликвидаций
+//!
+//! Цена и OI, падающие вместе, — след каскада ликвидаций: лонги закрываются
+//! принудительно, и каждая ликвидация давит цену дальше. Покупать прострел в такой
+//! момент — ловить нож. Монитор хранит отсчёты OI за `window` по символу; когда за окно
+//! OI упал на `oi_drop_pct` %, а цена — на `price_drop_pct` %, новые лонги по символу
+//! блокируются на `cooldown`, срабатывание публикуется в шину событий. Источник
+//! отсчётов любой: поле OI стрима тикеров или опрос `contract_stats`.
+
+use super::events::{RiskEvent, RiskEventBus};
+use crate::base_classes::symbol::Symbol;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone)]
+pub struct OpenInterestConfig {
+    pub window: Duration,
+    pub oi_drop_pct: f64,
+    pub price_drop_pct: f64,
+    pub cooldown: Duration,
+}
+
+impl Default for OpenInterestConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::minutes(5),
+            oi_drop_pct: 3.0,
+            price_drop_pct: 2.0,
+            cooldown: Duration::minutes(15),
+        }
+    }
+}
+
+/// Изменение OI и цены от первого до последнего отсчёта окна
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OiChange {
+    pub oi_change_pct: f64,
+    pub price_change_pct: f64,
+    pub span: Duration,
+}
+
+impl OiChange {
+    /// Скорость изменения OI, % в минуту
+    pub fn oi_rate_per_min(&self) -> f64 {
+        let minutes = self.span.num_milliseconds() as f64 / 60_000.0;
+        if minutes > 0.0 { self.oi_change_pct / minutes } else { 0.0 }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SymbolOi {
+    /// (время, OI, цена)
+    samples: VecDeque<(DateTime<Utc>, f64, f64)>,
+    blocked_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct OpenInterestMonitor {
+    config: OpenInterestConfig,
+    symbols: HashMap<Symbol, SymbolOi>,
+    pub events: Option<RiskEventBus>,
+}
+
+impl OpenInterestMonitor {
+    pub fn new(config: OpenInterestConfig) -> Self {
+        assert!(config.window > Duration::zero(), "open interest window must be positive");
+        assert!(
+            config.oi_drop_pct > 0.0 && config.price_drop_pct > 0.0,
+            "cascade thresholds must be positive, got oi {} price {}",
+            config.oi_drop_pct,
+            config.price_drop_pct
+        );
+        Self { config, symbols: HashMap::new(), events: None }
+    }
+
+    /// Отсчёт OI по символу; время неубывающее. Возвращает true, если этим отсчётом
+    /// символ впервые попал под блокировку (пока каскад длится, блокировка продлевается)
+    pub fn on_sample(&mut self, symbol: Symbol, at: DateTime<Utc>, open_interest: f64, price: f64) -> bool {
+        if !(open_interest >= 0.0 && open_interest.is_finite() && price > 0.0 && price.is_finite()) {
+            log::warn!("{}: open interest sample oi {} price {} ignored", symbol, open_interest, price);
+            return false;
+        }
+        let cutoff = at - self.config.window;
+        let entry = self.symbols.entry(symbol).or_default();
+        entry.samples.push_back((at, open_interest, price));
+        while entry.samples.front().is_some_and(|&(t, _, _)| t < cutoff) {
+            entry.samples.pop_front();
+        }
+
+        let Some(change) = self.change(symbol) else {
+            return false;
+        };
+        let cascade = change.oi_change_pct <= -self.config.oi_drop_pct
+            && change.price_change_pct <= -self.config.price_drop_pct;
+        if !cascade {
+            return false;
+        }
+        let entry = self.symbols.get_mut(&symbol).expect("sample just pushed");
+        let fresh = entry.blocked_until.is_none_or(|until| at >= until);
+        entry.blocked_until = Some(at + self.config.cooldown);
+        if fresh {
+            log::warn!(
+                "{}: liquidation cascade, OI {:+.2}% and price {:+.2}% over {}s; longs blocked",
+                symbol,
+                change.oi_change_pct,
+                change.price_change_pct,
+                change.span.num_seconds()
+            );
+            if let Some(events) = &self.events {
+                events.publish(RiskEvent::OpenInterestCascade {
+                    symbol,
+                    oi_change_pct: change.oi_change_pct,
+                    price_change_pct: change.price_change_pct,
+                });
+            }
+        }
+        fresh
+    }
+
+    /// Изменение за окно; None, пока отсчётов меньше двух
+    pub fn change(&self, symbol: Symbol) -> Option<OiChange> {
+        let samples = &self.symbols.get(&symbol)?.samples;
+        let (&(t0, oi0, p0), &(t1, oi1, p1)) = (samples.front()?, samples.back()?);
+        if samples.len() < 2 || oi0 <= 0.0 {
+            return None;
+        }
+        Some(OiChange {
+            oi_change_pct: (oi1 - oi0) / oi0 * 100.0,
+            price_change_pct: (p1 - p0) / p0 * 100.0,
+            span: t1 - t0,
+        })
+    }
+
+    /// Можно ли открывать лонг по символу
+    pub fn allows_long(&self, symbol: Symbol, now: DateTime<Utc>) -> bool {
+        self.symbols
+            .get(&symbol)
+            .and_then(|s| s.blocked_until)
+            .is_none_or(|until| now >= until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskEventKind;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_blocks_longs_when_price_and_oi_fall_together() {
+        let at = |s: i64| Utc.timestamp_opt(1_700_000_000 + s, 0).unwrap();
+        let eth = Symbol::new("ETH_USDT");
+        let mut monitor = OpenInterestMonitor::new(OpenInterestConfig::default());
+        let bus = RiskEventBus::new();
+        let rx = bus.subscribe("test", &[RiskEventKind::OpenInterestCascade]);
+        monitor.events = Some(bus);
+
+        // Цена падает, но OI растёт: новые шорты, не ликвидации
+        assert!(!monitor.on_sample(eth, at(0), 1_000.0, 100.0));
+        assert!(!monitor.on_sample(eth, at(60), 1_050.0, 97.0));
+        assert!(monitor.allows_long(eth, at(60)));
+
+        // OI -4.8% и цена -3% от начала окна
+        assert!(!monitor.on_sample(eth, at(120), 1_000.0, 98.0));
+        assert!(monitor.on_sample(eth, at(180), 952.0, 95.06));
+        let change = monitor.change(eth).unwrap();
+        assert!((change.oi_change_pct + 4.8).abs() < 1e-9);
+        assert!((change.oi_rate_per_min() + 1.6).abs() < 1e-9);
+        assert!(!monitor.allows_long(eth, at(200)));
+        assert!(monitor.allows_long(Symbol::new("BTC_USDT"), at(200)));
+        // Каскад продолжается: блокировка продлевается без нового события
+        assert!(!monitor.on_sample(eth, at(240), 900.0, 94.0));
+        assert!(!monitor.allows_long(eth, at(240 + 14 * 60)));
+        assert!(monitor.allows_long(eth, at(240 + 15 * 60)));
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+}
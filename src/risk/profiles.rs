@@ -0,0 +1,344 @@
+//! Risk Profiles - именованные наборы лимитов риска
+//!
+//! Профиль собирает настройки `GlobalRiskManager`, `SessionManager` и `LiquidationControl`
+//! в одно целое: вместо правки лимитов по полю переключаем профиль целиком.
+//! Встроенные профили: conservative, normal, aggressive; можно добавлять свои.
+//!
+//! Активный профиль выбирается так: ручное переключение > окно расписания (часы UTC,
+//! например aggressive только в часы США) > профиль по умолчанию. Применение профиля
+//! меняет только лимиты - накопленный PnL, счётчики сделок и предупреждения сохраняются.
+
+use super::global::GlobalRiskManager;
+use super::liquidation::LiquidationControl;
+use super::session::{SessionManager, SessionState};
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use std::collections::HashMap;
+
+/// Лимиты `GlobalRiskManager`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalLimits {
+    pub max_loss_per_trades: Option<(f64, usize)>,
+    pub max_loss_per_hours: Option<(f64, u32, usize)>,
+    pub auto_reset_interval_hours: Option<u32>,
+    pub panic_sell_on_btc_delta: Option<(f64, f64)>,
+    pub panic_sell_on_market_delta: Option<f64>,
+}
+
+/// Лимиты каждой сессии `SessionManager`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionLimits {
+    pub auto_reset_interval: Option<Duration>,
+    pub max_loss_per_trades: Option<(f64, usize)>,
+    pub max_loss_per_time: Option<(f64, Duration, usize)>,
+    pub order_size_multiplier: f64,
+    pub profit_lock: Option<f64>,
+    pub profit_lock_tighten_exits: bool,
+}
+
+/// Настройки `LiquidationControl`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidationLimits {
+    pub enabled: bool,
+    pub max_leverage: u32,
+    pub maintenance_margin_rate: f64,
+    pub liquidation_price_threshold: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskProfile {
+    pub name: String,
+    pub global: GlobalLimits,
+    pub session: SessionLimits,
+    pub liquidation: LiquidationLimits,
+}
+
+impl RiskProfile {
+    /// Малые убытки до остановки, половинный размер ордеров, плечо до 5
+    pub fn conservative() -> Self {
+        Self {
+            name: "conservative".to_string(),
+            global: GlobalLimits {
+                max_loss_per_trades: Some((20.0, 3)),
+                max_loss_per_hours: Some((30.0, 4, 3)),
+                auto_reset_interval_hours: Some(24),
+                panic_sell_on_btc_delta: Some((2.0, 3.0)),
+                panic_sell_on_market_delta: Some(3.0),
+            },
+            session: SessionLimits {
+                auto_reset_interval: Some(Duration::hours(24)),
+                max_loss_per_trades: Some((10.0, 3)),
+                max_loss_per_time: Some((15.0, Duration::hours(1), 3)),
+                order_size_multiplier: 0.5,
+                profit_lock: Some(30.0),
+                profit_lock_tighten_exits: true,
+            },
+            liquidation: LiquidationLimits {
+                enabled: true,
+                max_leverage: 5,
+                maintenance_margin_rate: 0.01,
+                liquidation_price_threshold: 40.0,
+            },
+        }
+    }
+
+    pub fn normal() -> Self {
+        Self {
+            name: "normal".to_string(),
+            global: GlobalLimits {
+                max_loss_per_trades: Some((50.0, 5)),
+                max_loss_per_hours: Some((75.0, 4, 5)),
+                auto_reset_interval_hours: Some(24),
+                panic_sell_on_btc_delta: Some((4.0, 5.0)),
+                panic_sell_on_market_delta: Some(5.0),
+            },
+            session: SessionLimits {
+                auto_reset_interval: Some(Duration::hours(24)),
+                max_loss_per_trades: Some((25.0, 5)),
+                max_loss_per_time: Some((40.0, Duration::hours(1), 5)),
+                order_size_multiplier: 1.0,
+                profit_lock: Some(100.0),
+                profit_lock_tighten_exits: false,
+            },
+            liquidation: LiquidationLimits {
+                enabled: true,
+                max_leverage: 20,
+                maintenance_margin_rate: 0.01,
+                liquidation_price_threshold: 20.0,
+            },
+        }
+    }
+
+    /// Широкие лимиты и полуторный размер ордеров - для ликвидных часов
+    pub fn aggressive() -> Self {
+        Self {
+            name: "aggressive".to_string(),
+            global: GlobalLimits {
+                max_loss_per_trades: Some((150.0, 10)),
+                max_loss_per_hours: Some((200.0, 4, 10)),
+                auto_reset_interval_hours: Some(24),
+                panic_sell_on_btc_delta: Some((8.0, 10.0)),
+                panic_sell_on_market_delta: Some(10.0),
+            },
+            session: SessionLimits {
+                auto_reset_interval: Some(Duration::hours(24)),
+                max_loss_per_trades: Some((75.0, 10)),
+                max_loss_per_time: Some((100.0, Duration::hours(1), 10)),
+                order_size_multiplier: 1.5,
+                profit_lock: None,
+                profit_lock_tighten_exits: false,
+            },
+            liquidation: LiquidationLimits {
+                enabled: true,
+                max_leverage: 50,
+                maintenance_margin_rate: 0.01,
+                liquidation_price_threshold: 10.0,
+            },
+        }
+    }
+
+    /// Переносит лимиты в менеджеры; состояние сессий (PnL, сделки, фиксация) не трогается.
+    /// Сессии, созданные позже, получают лимиты профиля через шаблон `SessionManager`
+    pub fn apply(&self, global: &mut GlobalRiskManager, sessions: &mut SessionManager, liquidation: &mut LiquidationControl) {
+        global.max_loss_per_trades = self.global.max_loss_per_trades;
+        global.max_loss_per_hours = self.global.max_loss_per_hours;
+        global.auto_reset_interval_hours = self.global.auto_reset_interval_hours;
+        global.panic_sell_on_btc_delta = self.global.panic_sell_on_btc_delta;
+        global.panic_sell_on_market_delta = self.global.panic_sell_on_market_delta;
+
+        let mut template = sessions.template.take().unwrap_or_default();
+        self.apply_session(&mut template);
+        for (_, state) in sessions.sessions_mut() {
+            self.apply_session(state);
+        }
+        sessions.template = Some(template);
+
+        liquidation.enabled = self.liquidation.enabled;
+        liquidation.max_leverage = self.liquidation.max_leverage;
+        liquidation.maintenance_margin_rate = self.liquidation.maintenance_margin_rate;
+        liquidation.liquidation_price_threshold = self.liquidation.liquidation_price_threshold;
+    }
+
+    fn apply_session(&self, state: &mut SessionState) {
+        let limits = &self.session;
+        state.auto_reset_interval = limits.auto_reset_interval;
+        state.max_loss_per_trades = limits.max_loss_per_trades;
+        state.max_loss_per_time = limits.max_loss_per_time;
+        state.order_size_multiplier = limits.order_size_multiplier;
+        state.profit_lock = limits.profit_lock;
+        state.profit_lock_tighten_exits = limits.profit_lock_tighten_exits;
+    }
+}
+
+/// Окно расписания: профиль действует с `from_hour` до `to_hour` (UTC, конец не включён).
+/// Окно может переходить через полночь, например 22..6
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileWindow {
+    pub from_hour: u32,
+    pub to_hour: u32,
+    pub profile: String,
+}
+
+impl ProfileWindow {
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let hour = now.hour();
+        if self.from_hour <= self.to_hour {
+            hour >= self.from_hour && hour < self.to_hour
+        } else {
+            hour >= self.from_hour || hour < self.to_hour
+        }
+    }
+}
+
+/// Набор профилей с переключением вручную и по расписанию
+#[derive(Debug, Clone)]
+pub struct RiskProfileBook {
+    profiles: HashMap<String, RiskProfile>,
+    default: String,
+    /// Первое подходящее окно выигрывает
+    schedule: Vec<ProfileWindow>,
+    /// Ручное переключение перекрывает расписание до `clear_override`
+    manual: Option<String>,
+    /// Профиль, применённый последним
+    active: Option<String>,
+}
+
+impl RiskProfileBook {
+    /// Встроенные conservative/normal/aggressive, по умолчанию normal
+    pub fn new() -> Self {
+        let mut profiles = HashMap::new();
+        for profile in [RiskProfile::conservative(), RiskProfile::normal(), RiskProfile::aggressive()] {
+            profiles.insert(profile.name.clone(), profile);
+        }
+        Self { profiles, default: "normal".to_string(), schedule: Vec::new(), manual: None, active: None }
+    }
+
+    /// Добавляет или заменяет профиль; замена активного применится на следующем `apply`
+    pub fn insert(&mut self, profile: RiskProfile) {
+        if self.active.as_deref() == Some(profile.name.as_str()) {
+            self.active = None;
+        }
+        self.profiles.insert(profile.name.clone(), profile);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RiskProfile> {
+        self.profiles.get(name)
+    }
+
+    pub fn set_default(&mut self, name: &str) -> Result<()> {
+        self.ensure_known(name)?;
+        self.default = name.to_string();
+        Ok(())
+    }
+
+    pub fn schedule(&mut self, window: ProfileWindow) -> Result<()> {
+        self.ensure_known(&window.profile)?;
+        if window.from_hour > 23 || window.to_hour > 24 || window.from_hour == window.to_hour {
+            bail!("invalid profile window {}..{} for {}", window.from_hour, window.to_hour, window.profile);
+        }
+        self.schedule.push(window);
+        Ok(())
+    }
+
+    /// Ручное переключение (оператор, команда бота)
+    pub fn switch(&mut self, name: &str) -> Result<()> {
+        self.ensure_known(name)?;
+        self.manual = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Возврат к расписанию
+    pub fn clear_override(&mut self) {
+        self.manual = None;
+    }
+
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Профиль, который должен действовать в момент `now`
+    pub fn resolve(&self, now: DateTime<Utc>) -> &RiskProfile {
+        let name = self
+            .manual
+            .as_deref()
+            .or_else(|| self.schedule.iter().find(|w| w.contains(now)).map(|w| w.profile.as_str()))
+            .unwrap_or(&self.default);
+        &self.profiles[name]
+    }
+
+    /// Применяет профиль для `now`, если он сменился. Возвращает имя нового профиля.
+    /// Вызывать периодически (например раз в минуту), чтобы срабатывало расписание
+    pub fn apply(
+        &mut self,
+        now: DateTime<Utc>,
+        global: &mut GlobalRiskManager,
+        sessions: &mut SessionManager,
+        liquidation: &mut LiquidationControl,
+    ) -> Option<String> {
+        let profile = self.resolve(now);
+        if self.active.as_deref() == Some(profile.name.as_str()) {
+            return None;
+        }
+        log::warn!("risk profile {} -> {}", self.active.as_deref().unwrap_or("-"), profile.name);
+        profile.apply(global, sessions, liquidation);
+        let name = profile.name.clone();
+        self.active = Some(name.clone());
+        Some(name)
+    }
+
+    fn ensure_known(&self, name: &str) -> Result<()> {
+        if !self.profiles.contains_key(name) {
+            let mut known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            known.sort();
+            bail!("unknown risk profile {} (known: {})", name, known.join(", "));
+        }
+        Ok(())
+    }
+}
+
+impl Default for RiskProfileBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_schedule_and_manual_switch_keep_session_state() {
+        let mut book = RiskProfileBook::new();
+        book.schedule(ProfileWindow { from_hour: 13, to_hour: 21, profile: "aggressive".to_string() }).unwrap();
+        assert!(book.switch("yolo").is_err());
+
+        let mut global = GlobalRiskManager::new();
+        let mut sessions = SessionManager::new();
+        let mut liquidation = LiquidationControl::default();
+        sessions.update_session("day", -12.0);
+
+        let night = Utc.with_ymd_and_hms(2026, 3, 2, 3, 0, 0).unwrap();
+        assert_eq!(book.apply(night, &mut global, &mut sessions, &mut liquidation).as_deref(), Some("normal"));
+        assert_eq!(book.apply(night, &mut global, &mut sessions, &mut liquidation), None);
+        assert_eq!(liquidation.max_leverage, 20);
+
+        let us = Utc.with_ymd_and_hms(2026, 3, 2, 15, 0, 0).unwrap();
+        assert_eq!(book.apply(us, &mut global, &mut sessions, &mut liquidation).as_deref(), Some("aggressive"));
+        assert_eq!(global.max_loss_per_trades, Some((150.0, 10)));
+        assert_eq!(sessions.get_order_size_multiplier("day"), 1.5);
+        // Лимиты сменились, накопленный убыток сессии остался
+        assert_eq!(sessions.session("day").unwrap().pnl, -12.0);
+        // Новая сессия сразу создаётся с лимитами профиля
+        assert_eq!(sessions.session_mut("week").order_size_multiplier, 1.5);
+
+        book.switch("conservative").unwrap();
+        assert_eq!(book.apply(us, &mut global, &mut sessions, &mut liquidation).as_deref(), Some("conservative"));
+        assert_eq!(liquidation.max_leverage, 5);
+        book.clear_override();
+        assert_eq!(book.apply(us, &mut global, &mut sessions, &mut liquidation).as_deref(), Some("aggressive"));
+
+        let overnight = ProfileWindow { from_hour: 22, to_hour: 6, profile: "conservative".to_string() };
+        assert!(overnight.contains(night) && !overnight.contains(us));
+    }
+}
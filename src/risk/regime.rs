@@ -0,0 +1,208 @@
+//! Классификатор режимов рынка: спокойный, волатильный, трендовый
+//!
+//! Тики собираются в бары по `bar_ms`; на закрытии бара режим пересчитывается по
+//! закрытым барам, без заглядывания вперёд. Волатильный — перцентиль реализованной
+//! волатильности (`VolatilityPercentile`) не ниже `volatile_percentile`; иначе трендовый —
+//! коэффициент эффективности (|чистое движение| / сумма |движений| за `trend_window`
+//! баров) не ниже `trend_efficiency`; иначе спокойный. Обвал попадает в волатильные:
+//! для оценки конфига важнее, что рынок нервный, чем что он направленный.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::vol_scaling::VolatilityPercentile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Regime {
+    Quiet,
+    Volatile,
+    Trending,
+}
+
+impl fmt::Display for Regime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Regime::Quiet => "quiet",
+            Regime::Volatile => "volatile",
+            Regime::Trending => "trending",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegimeConfig {
+    pub bar_ms: i64,
+    /// Лог-доходностей баров в окне реализованной волатильности
+    pub vol_window: usize,
+    /// Значений волатильности в истории для перцентиля
+    pub history: usize,
+    /// Пока значений меньше, режим не определён (разогрев)
+    pub min_history: usize,
+    pub volatile_percentile: f64,
+    pub trend_window: usize,
+    pub trend_efficiency: f64,
+}
+
+impl Default for RegimeConfig {
+    fn default() -> Self {
+        Self {
+            bar_ms: 60_000,
+            vol_window: 20,
+            history: 720,
+            min_history: 60,
+            volatile_percentile: 80.0,
+            trend_window: 20,
+            trend_efficiency: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegimeClassifier {
+    config: RegimeConfig,
+    volatility: VolatilityPercentile,
+    closes: VecDeque<f64>,
+    bar_end: Option<i64>,
+    last_price: f64,
+    current: Option<Regime>,
+}
+
+impl RegimeClassifier {
+    pub fn new(config: RegimeConfig) -> Self {
+        assert!(config.bar_ms > 0, "bar_ms must be positive, got {}", config.bar_ms);
+        assert!(config.trend_window >= 2, "trend_window needs at least 2 bars");
+        Self {
+            volatility: VolatilityPercentile::new(config.vol_window, config.history),
+            closes: VecDeque::with_capacity(config.trend_window),
+            bar_end: None,
+            last_price: 0.0,
+            current: None,
+            config,
+        }
+    }
+
+    /// Тик; возвращает режим, действующий на момент тика (None — разогрев)
+    pub fn on_price(&mut self, ts_ms: i64, price: f64) -> Option<Regime> {
+        let bar_ms = self.config.bar_ms;
+        let bar_end = *self.bar_end.get_or_insert(ts_ms - ts_ms.rem_euclid(bar_ms) + bar_ms);
+        if ts_ms >= bar_end {
+            // Пустые бары между тиками не добавляются: закрывается только бар с последней ценой
+            self.close_bar(self.last_price);
+            self.bar_end = Some(ts_ms - ts_ms.rem_euclid(bar_ms) + bar_ms);
+        }
+        self.last_price = price;
+        self.current
+    }
+
+    pub fn current(&self) -> Option<Regime> {
+        self.current
+    }
+
+    fn close_bar(&mut self, close: f64) {
+        if !(close > 0.0 && close.is_finite()) {
+            return;
+        }
+        self.volatility.push_price(close);
+        if self.closes.len() == self.config.trend_window {
+            self.closes.pop_front();
+        }
+        self.closes.push_back(close);
+        if self.volatility.samples() < self.config.min_history.max(1) {
+            return;
+        }
+        let volatile = self.volatility.percentile().is_some_and(|p| p >= self.config.volatile_percentile);
+        self.current = Some(if volatile {
+            Regime::Volatile
+        } else if self.efficiency() >= self.config.trend_efficiency {
+            Regime::Trending
+        } else {
+            Regime::Quiet
+        });
+    }
+
+    fn efficiency(&self) -> f64 {
+        let (Some(first), Some(last)) = (self.closes.front(), self.closes.back()) else {
+            return 0.0;
+        };
+        let path: f64 = self.closes.iter().zip(self.closes.iter().skip(1)).map(|(a, b)| (b - a).abs()).sum();
+        if path > 0.0 { (last - first).abs() / path } else { 0.0 }
+    }
+}
+
+/// Режимы на отрезке времени: участки `[start_ms, следующий start_ms)`, последний до `end_ms`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegimeTimeline {
+    segments: Vec<(i64, Option<Regime>)>,
+    end_ms: i64,
+}
+
+impl RegimeTimeline {
+    pub fn new(segments: Vec<(i64, Option<Regime>)>, end_ms: i64) -> Self {
+        debug_assert!(segments.windows(2).all(|w| w[0].0 <= w[1].0), "segments must be sorted");
+        Self { segments, end_ms }
+    }
+
+    /// Прогоняет цены `(ts_ms, price)` через классификатор
+    pub fn classify(prices: impl IntoIterator<Item = (i64, f64)>, config: &RegimeConfig) -> Self {
+        let mut classifier = RegimeClassifier::new(config.clone());
+        let mut segments: Vec<(i64, Option<Regime>)> = Vec::new();
+        let mut end_ms = 0;
+        for (ts_ms, price) in prices {
+            let regime = classifier.on_price(ts_ms, price);
+            if segments.last().is_none_or(|(_, last)| *last != regime) {
+                segments.push((ts_ms, regime));
+            }
+            end_ms = ts_ms;
+        }
+        Self { segments, end_ms }
+    }
+
+    pub fn at(&self, ts_ms: i64) -> Option<Regime> {
+        let idx = self.segments.partition_point(|(start, _)| *start <= ts_ms);
+        idx.checked_sub(1).and_then(|i| self.segments[i].1)
+    }
+
+    /// Длительность каждого режима, мс; `None` — разогрев
+    pub fn durations(&self) -> Vec<(Option<Regime>, i64)> {
+        let mut totals: Vec<(Option<Regime>, i64)> = Vec::new();
+        for (i, (start, regime)) in self.segments.iter().enumerate() {
+            let end = self.segments.get(i + 1).map_or(self.end_ms, |(next, _)| *next);
+            match totals.iter_mut().find(|(r, _)| r == regime) {
+                Some((_, total)) => *total += end - start,
+                None => totals.push((*regime, end - start)),
+            }
+        }
+        totals.sort();
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_trend_and_volatile_segments() {
+        // Секунда на тик, минутные бары: 3 часа пилы, 2 часа ровного роста, час размаха
+        let mut prices = Vec::new();
+        let mut price = 100.0;
+        for s in 0..6 * 3600i64 {
+            let hour = s / 3600;
+            price = match hour {
+                0..=2 => 100.0 + 0.05 * ((s / 60) % 7) as f64 * if (s / 60) % 2 == 0 { 1.0 } else { -1.0 },
+                3..=4 => price * 1.00002,
+                _ => price * if (s / 60) % 2 == 0 { 1.0005 } else { 0.9995 },
+            };
+            prices.push((s * 1000, price));
+        }
+        let timeline = RegimeTimeline::classify(prices, &RegimeConfig::default());
+        assert_eq!(timeline.at(10 * 60_000), None);
+        assert_eq!(timeline.at(2 * 3_600_000 + 30 * 60_000), Some(Regime::Quiet));
+        assert_eq!(timeline.at(4 * 3_600_000 + 30 * 60_000), Some(Regime::Trending));
+        assert_eq!(timeline.at(5 * 3_600_000 + 30 * 60_000), Some(Regime::Volatile));
+
+        let durations = timeline.durations();
+        assert_eq!(durations.iter().map(|(_, d)| d).sum::<i64>(), 6 * 3_600_000 - 1_000);
+        assert_eq!(durations[0].0, None);
+    }
+}
@@ -1,3 +1,4 @@
+use super::events::{RiskEvent, RiskEventBus};
 use chrono::{DateTime, Utc, Duration};
 use std::collections::HashMap;
 
@@ -5,6 +6,9 @@ use std::collections::HashMap;
 pub enum SessionAction {
     None,
     BlockTrading,
+    /// Дневная цель по прибыли достигнута: новые позиции не открывать,
+    /// открытые при `tighten_exits` закрывать по более близким целям
+    LockProfits { tighten_exits: bool },
 }
 
 #[derive(Debug, Clone)]
@@ -18,6 +22,11 @@ pub struct SessionState {
     pub max_loss_per_time: Option<(f64, Duration, usize)>,
     pub order_size_multiplier: f64,
     pub penalty_until: Option<DateTime<Utc>>,
+    /// Реализованный PnL сессии, после которого фиксируем прибыль (зеркально лимиту убытка)
+    pub profit_lock: Option<f64>,
+    pub profit_lock_tighten_exits: bool,
+    /// Момент достижения цели; фиксация держится до сброса сессии, даже если PnL откатил
+    pub profit_locked_at: Option<DateTime<Utc>>,
 }
 
 impl Default for SessionState {
@@ -33,22 +42,64 @@ impl Default for SessionState {
             max_loss_per_time: None,
             order_size_multiplier: 1.0,
             penalty_until: None,
+            profit_lock: None,
+            profit_lock_tighten_exits: false,
+            profit_locked_at: None,
         }
     }
 }
 
+/// Новая сессия с настройками шаблона и чистым состоянием
+fn fresh_session(template: &Option<SessionState>) -> SessionState {
+    let Some(template) = template else { return SessionState::default() };
+    let now = Utc::now();
+    SessionState {
+        pnl: 0.0,
+        trades_count: 0,
+        start_time: now,
+        last_reset: now,
+        penalty_until: None,
+        profit_locked_at: None,
+        ..template.clone()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SessionManager {
     sessions: HashMap<String, SessionState>,
+    /// Шина событий: сброс сессии публикуется как SessionClosed
+    pub events: Option<RiskEventBus>,
+    /// Настройки новых сессий (ставит профиль риска); без шаблона - `SessionState::default()`
+    pub template: Option<SessionState>,
 }
 
 impl SessionManager {
-    pub fn new() -> Self { Self { sessions: HashMap::new() } }
+    pub fn new() -> Self { Self { sessions: HashMap::new(), events: None, template: None } }
+
+    /// Настройки и состояние сессии (создаётся по шаблону)
+    pub fn session_mut(&mut self, key: &str) -> &mut SessionState {
+        self.sessions.entry(key.to_string()).or_insert_with(|| fresh_session(&self.template))
+    }
+
+    pub fn sessions_mut(&mut self) -> impl Iterator<Item = (&String, &mut SessionState)> {
+        self.sessions.iter_mut()
+    }
+
+    pub fn session(&self, key: &str) -> Option<&SessionState> {
+        self.sessions.get(key)
+    }
 
     pub fn update_session(&mut self, key: &str, pnl_delta: f64) {
-        let entry = self.sessions.entry(key.to_string()).or_default();
+        let entry = self.sessions.entry(key.to_string()).or_insert_with(|| fresh_session(&self.template));
         entry.pnl += pnl_delta;
         entry.trades_count += 1;
+        if let Some(target) = entry.profit_lock
+            && entry.profit_locked_at.is_none()
+            && entry.pnl >= target
+        {
+            entry.profit_locked_at = Some(Utc::now());
+            log::info!("session {} locked profit: pnl {:.2} >= {:.2}", key, entry.pnl, target);
+        }
     }
 
     pub fn check_stop_conditions(&self, key: &str) -> SessionAction {
@@ -61,6 +112,9 @@ impl SessionManager {
                     if Utc::now() - state.last_reset <= window && state.pnl <= -max_loss { return SessionAction::BlockTrading; }
                 }
             }
+            if state.profit_locked_at.is_some() {
+                return SessionAction::LockProfits { tighten_exits: state.profit_lock_tighten_exits };
+            }
         }
         SessionAction::None
     }
@@ -79,10 +133,75 @@ impl SessionManager {
     }
 
     pub fn reset(&mut self, key: &str) {
+        if let Some(events) = &self.events
+            && let Some(state) = self.sessions.get(key)
+        {
+            events.publish(RiskEvent::SessionClosed {
+                session: key.to_string(),
+                pnl: state.pnl,
+                trades: state.trades_count,
+                action: self.check_stop_conditions(key),
+            });
+        }
         if let Some(state) = self.sessions.get_mut(key) {
             state.pnl = 0.0;
             state.trades_count = 0;
             state.last_reset = Utc::now();
+            state.profit_locked_at = None;
         }
     }
+
+    pub fn is_profit_locked(&self, key: &str) -> bool {
+        self.sessions.get(key).is_some_and(|s| s.profit_locked_at.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profit_lock_latches_until_reset() {
+        let mut manager = SessionManager::new();
+        let session = manager.session_mut("day");
+        session.profit_lock = Some(50.0);
+        session.profit_lock_tighten_exits = true;
+        session.max_loss_per_trades = Some((30.0, 1));
+
+        manager.update_session("day", 30.0);
+        assert_eq!(manager.check_stop_conditions("day"), SessionAction::None);
+        manager.update_session("day", 25.0);
+        assert_eq!(manager.check_stop_conditions("day"), SessionAction::LockProfits { tighten_exits: true });
+
+        // Откат PnL не снимает фиксацию - зелёный день сохраняется
+        manager.update_session("day", -20.0);
+        assert!(manager.is_profit_locked("day"));
+
+        let bus = RiskEventBus::new();
+        let journal = bus.subscribe("journal", &[]);
+        manager.events = Some(bus);
+        manager.reset("day");
+        assert_eq!(manager.check_stop_conditions("day"), SessionAction::None);
+        let closed = journal.try_recv().unwrap();
+        assert_eq!(
+            closed.event,
+            RiskEvent::SessionClosed {
+                session: "day".to_string(),
+                pnl: 35.0,
+                trades: 3,
+                action: SessionAction::LockProfits { tighten_exits: true },
+            }
+        );
+    }
+
+    #[test]
+    fn test_loss_limit_takes_precedence() {
+        let mut manager = SessionManager::new();
+        let session = manager.session_mut("day");
+        session.profit_lock = Some(10.0);
+        session.max_loss_per_trades = Some((5.0, 1));
+        manager.update_session("day", 12.0);
+        manager.update_session("day", -20.0);
+        assert_eq!(manager.check_stop_conditions("day"), SessionAction::BlockTrading);
+    }
 }
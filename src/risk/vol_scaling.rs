@@ -0,0 +1,251 @@
+//! Volatility Scaling - лимиты позиций, сужающиеся с ростом волатильности
+//!
+//! Реализованная волатильность считается по скользящему окну лог-доходностей цены
+//! (одна выборка на бар, например минутный close). Каждое новое значение сравнивается
+//! с историей значений за длинное окно: перцентиль показывает, насколько рынок сейчас
+//! неспокоен относительно своей нормы. Перцентиль считает `VolatilityPercentile` (на нём
+//! же строится классификатор режимов `regime`); `VolatilityScaler` принимает перцентиль
+//! из любого источника.
+//!
+//! До `calm_percentile` лимиты базовые, от `chaos_percentile` - умножены на
+//! `min_multiplier`, между ними множитель убывает линейно. Максимум одновременных
+//! позиций округляется вниз, но не ниже одной.
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct VolScalingConfig {
+    /// Лог-доходностей в окне реализованной волатильности
+    pub vol_window: usize,
+    /// Значений волатильности в истории для перцентиля
+    pub history: usize,
+    /// Минимум значений в истории, пока их меньше - лимиты базовые
+    pub min_history: usize,
+    pub calm_percentile: f64,
+    pub chaos_percentile: f64,
+    /// Множитель лимитов на хаотичном рынке, в (0, 1]
+    pub min_multiplier: f64,
+    pub base_max_position_size: f64,
+    pub base_max_positions: usize,
+}
+
+impl Default for VolScalingConfig {
+    fn default() -> Self {
+        Self {
+            vol_window: 60,
+            history: 1440,
+            min_history: 120,
+            calm_percentile: 50.0,
+            chaos_percentile: 95.0,
+            min_multiplier: 0.25,
+            base_max_position_size: 100.0,
+            base_max_positions: 5,
+        }
+    }
+}
+
+/// Перцентиль текущей реализованной волатильности среди недавней истории
+#[derive(Debug, Clone)]
+pub struct VolatilityPercentile {
+    vol_window: usize,
+    history_len: usize,
+    last_price: Option<f64>,
+    returns: VecDeque<f64>,
+    history: VecDeque<f64>,
+}
+
+impl VolatilityPercentile {
+    pub fn new(vol_window: usize, history: usize) -> Self {
+        assert!(vol_window >= 2, "realized volatility needs at least 2 returns");
+        assert!(history >= 1, "volatility history must hold at least one value");
+        Self {
+            vol_window,
+            history_len: history,
+            last_price: None,
+            returns: VecDeque::with_capacity(vol_window),
+            history: VecDeque::with_capacity(history),
+        }
+    }
+
+    /// Добавляет цену бара; возвращает реализованную волатильность, когда окно заполнено
+    pub fn push_price(&mut self, price: f64) -> Option<f64> {
+        if !(price > 0.0 && price.is_finite()) {
+            log::warn!("volatility: ignoring price {}", price);
+            return None;
+        }
+        let previous = self.last_price.replace(price)?;
+        if self.returns.len() == self.vol_window {
+            self.returns.pop_front();
+        }
+        self.returns.push_back((price / previous).ln());
+        if self.returns.len() < self.vol_window {
+            return None;
+        }
+        let n = self.returns.len() as f64;
+        let mean = self.returns.iter().sum::<f64>() / n;
+        let vol = (self.returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(vol);
+        Some(vol)
+    }
+
+    pub fn current(&self) -> Option<f64> {
+        self.history.back().copied()
+    }
+
+    pub fn samples(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Перцентиль текущего значения (0..100) по среднему рангу: равные значения считаются
+    /// наполовину, так что ровный рынок даёт ~50, а не 100
+    pub fn percentile(&self) -> Option<f64> {
+        let current = self.current()?;
+        let tolerance = current * 1e-9;
+        let (mut below, mut equal) = (0usize, 0usize);
+        for &v in &self.history {
+            if (v - current).abs() <= tolerance {
+                equal += 1;
+            } else if v < current {
+                below += 1;
+            }
+        }
+        Some((below as f64 + equal as f64 / 2.0) / self.history.len() as f64 * 100.0)
+    }
+}
+
+/// Лимиты после масштабирования
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaledLimits {
+    pub multiplier: f64,
+    pub max_position_size: f64,
+    pub max_positions: usize,
+    /// Перцентиль, по которому считались лимиты; None - истории мало, лимиты базовые
+    pub percentile: Option<f64>,
+}
+
+impl ScaledLimits {
+    pub fn allows_position(&self, size: f64) -> bool {
+        size.abs() <= self.max_position_size
+    }
+
+    pub fn allows_new_position(&self, open_positions: usize) -> bool {
+        open_positions < self.max_positions
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VolatilityScaler {
+    config: VolScalingConfig,
+    volatility: VolatilityPercentile,
+    limits: ScaledLimits,
+}
+
+impl VolatilityScaler {
+    pub fn new(config: VolScalingConfig) -> Self {
+        assert!(
+            0.0 <= config.calm_percentile && config.calm_percentile < config.chaos_percentile && config.chaos_percentile <= 100.0,
+            "volatility percentiles must satisfy 0 <= calm < chaos <= 100"
+        );
+        assert!(
+            config.min_multiplier > 0.0 && config.min_multiplier <= 1.0,
+            "min_multiplier must be in (0, 1]"
+        );
+        assert!(config.base_max_positions >= 1, "base_max_positions must be at least 1");
+        let volatility = VolatilityPercentile::new(config.vol_window, config.history);
+        let limits = ScaledLimits {
+            multiplier: 1.0,
+            max_position_size: config.base_max_position_size,
+            max_positions: config.base_max_positions,
+            percentile: None,
+        };
+        Self { config, volatility, limits }
+    }
+
+    /// Цена нового бара; возвращает лимиты, если они изменились
+    pub fn on_price(&mut self, price: f64) -> Option<ScaledLimits> {
+        self.volatility.push_price(price)?;
+        if self.volatility.samples() < self.config.min_history {
+            return None;
+        }
+        let percentile = self.volatility.percentile()?;
+        let previous = self.limits;
+        self.limits = self.limits_at(percentile);
+        let changed = previous.max_positions != self.limits.max_positions
+            || (previous.multiplier - self.limits.multiplier).abs() > 1e-9;
+        if changed {
+            log::info!(
+                "volatility p{:.0}: limits x{:.2}, max size {:.4}, max positions {}",
+                percentile,
+                self.limits.multiplier,
+                self.limits.max_position_size,
+                self.limits.max_positions
+            );
+        }
+        changed.then_some(self.limits)
+    }
+
+    pub fn limits(&self) -> ScaledLimits {
+        self.limits
+    }
+
+    pub fn volatility(&self) -> &VolatilityPercentile {
+        &self.volatility
+    }
+
+    /// Лимиты для заданного перцентиля (например от внешнего классификатора)
+    pub fn limits_at(&self, percentile: f64) -> ScaledLimits {
+        let c = &self.config;
+        let t = ((percentile - c.calm_percentile) / (c.chaos_percentile - c.calm_percentile)).clamp(0.0, 1.0);
+        let multiplier = 1.0 - t * (1.0 - c.min_multiplier);
+        ScaledLimits {
+            multiplier,
+            max_position_size: c.base_max_position_size * multiplier,
+            max_positions: ((c.base_max_positions as f64 * multiplier).floor() as usize).max(1),
+            percentile: Some(percentile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_tighten_when_volatility_spikes() {
+        let config = VolScalingConfig {
+            vol_window: 5,
+            history: 100,
+            min_history: 20,
+            base_max_position_size: 10.0,
+            base_max_positions: 4,
+            ..VolScalingConfig::default()
+        };
+        let mut scaler = VolatilityScaler::new(config);
+        assert_eq!(scaler.limits_at(30.0).multiplier, 1.0);
+        assert_eq!(scaler.limits_at(100.0).max_positions, 1);
+        assert!((scaler.limits_at(72.5).multiplier - 0.625).abs() < 1e-12);
+
+        // Спокойный рынок: колебания 0.1%
+        let mut price = 100.0;
+        for i in 0..40 {
+            price *= if i % 2 == 0 { 1.001 } else { 0.999 };
+            scaler.on_price(price);
+        }
+        assert!(scaler.limits().multiplier > 0.99);
+        assert!(scaler.limits().allows_new_position(3));
+
+        // Хаос: колебания 3% - волатильность на максимуме истории
+        for i in 0..5 {
+            price *= if i % 2 == 0 { 1.03 } else { 0.97 };
+            scaler.on_price(price);
+        }
+        let limits = scaler.limits();
+        assert!(limits.percentile.unwrap() > 95.0);
+        assert_eq!((limits.max_position_size, limits.max_positions), (2.5, 1));
+        assert!(!limits.allows_new_position(1));
+        assert!(!limits.allows_position(-3.0));
+    }
+}
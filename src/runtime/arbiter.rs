@@ -0,0 +1,210 @@
+//! Арбитр детектов: один вход на событие по символу
+//!
+//! Hook, MStrike и MShot на одном символе видят один и тот же прострел и без арбитра
+//! покупают каждый своим размером - капитал на событие молча удваивается. Арбитр живёт
+//! в слоте символа внутри шарда, поэтому решает без локов. Событие - окно `window`
+//! от первого входа (по времени тиков): входы других стратегий в этом окне
+//! отклоняются или урезаются, входы стратегии-владельца проходят как есть.
+
+use crate::backtest::strategy_adapter::StrategyAction;
+use chrono::{DateTime, Duration, Utc};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArbiterMode {
+    /// Без арбитража: каждая стратегия входит сама
+    AllowAll,
+    /// Событие забирает стратегия, вошедшая первой; на одном тике - с меньшим индексом
+    FirstWins,
+    /// Как FirstWins, но на одном тике побеждает стратегия, стоящая раньше в списке имён.
+    /// Уже отправленный вход не отзывается, поэтому между тиками всё равно решает первый.
+    Priority(Vec<String>),
+    /// Входят все, но каждый следующий вход события умножается на `factor` ещё раз
+    ScaleSize { factor: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionPolicy {
+    pub mode: ArbiterMode,
+    /// Сколько после первого входа прочие входы считаются тем же событием
+    pub window: Duration,
+}
+
+impl Default for DetectionPolicy {
+    fn default() -> Self {
+        Self {
+            mode: ArbiterMode::FirstWins,
+            window: Duration::seconds(60),
+        }
+    }
+}
+
+impl DetectionPolicy {
+    pub fn allow_all() -> Self {
+        Self {
+            mode: ArbiterMode::AllowAll,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn validate(&self) {
+        assert!(self.window >= Duration::zero(), "detection window must not be negative");
+        if let ArbiterMode::ScaleSize { factor } = self.mode {
+            assert!(
+                factor > 0.0 && factor <= 1.0,
+                "scaled entry factor must be in (0, 1], got {}",
+                factor
+            );
+        }
+    }
+
+    /// Ранг стратегии для `Priority`: позиция в списке, не указанные - после всех
+    fn rank(&self, name: &str) -> usize {
+        match &self.mode {
+            ArbiterMode::Priority(order) => order.iter().position(|n| n == name).unwrap_or(order.len()),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Claim {
+    strategy: usize,
+    at: DateTime<Utc>,
+    /// Входы события, включая первый
+    entries: u32,
+}
+
+/// Состояние арбитра одного символа
+#[derive(Debug, Clone)]
+pub struct DetectionArbiter {
+    policy: DetectionPolicy,
+    /// Ранги стратегий набора символа по индексу
+    ranks: Vec<usize>,
+    claim: Option<Claim>,
+}
+
+impl DetectionArbiter {
+    pub fn new<'a>(policy: DetectionPolicy, names: impl IntoIterator<Item = &'a str>) -> Self {
+        let ranks = names.into_iter().map(|name| policy.rank(name)).collect();
+        Self {
+            policy,
+            ranks,
+            claim: None,
+        }
+    }
+
+    /// Стратегия, которой сейчас принадлежит событие
+    pub fn owner(&self, now: DateTime<Utc>) -> Option<usize> {
+        self.claim
+            .filter(|claim| now - claim.at <= self.policy.window)
+            .map(|claim| claim.strategy)
+    }
+
+    /// Решает по входам одного тика: `entries` - пары (индекс стратегии, действие) в порядке
+    /// набора. Отклонённый PlaceBuy заменяется на NoAction и передаётся в `declined`,
+    /// урезанный получает новый размер; остальные действия не трогаются.
+    pub fn arbitrate(
+        &mut self,
+        now: DateTime<Utc>,
+        entries: &mut [(usize, StrategyAction)],
+        mut declined: impl FnMut(usize),
+    ) {
+        if self.policy.mode == ArbiterMode::AllowAll {
+            return;
+        }
+        if self.owner(now).is_none() {
+            self.claim = None;
+        }
+        if self.claim.is_none() {
+            let winner = entries
+                .iter()
+                .filter(|(_, action)| matches!(action, StrategyAction::PlaceBuy { .. }))
+                .map(|&(idx, _)| idx)
+                .min_by_key(|&idx| (self.ranks.get(idx).copied().unwrap_or(usize::MAX), idx));
+            let Some(strategy) = winner else {
+                return;
+            };
+            self.claim = Some(Claim { strategy, at: now, entries: 0 });
+        }
+        let claim = self.claim.as_mut().expect("claim set above");
+        for (idx, action) in entries.iter_mut() {
+            let StrategyAction::PlaceBuy { size, .. } = action else {
+                continue;
+            };
+            if *idx != claim.strategy {
+                if let ArbiterMode::ScaleSize { factor } = self.policy.mode {
+                    *size *= factor.powi(claim.entries.max(1) as i32);
+                } else {
+                    *action = StrategyAction::NoAction;
+                    declined(*idx);
+                    continue;
+                }
+            }
+            claim.entries += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy(size: f64) -> StrategyAction {
+        StrategyAction::PlaceBuy { price: 100.0, size }
+    }
+
+    /// Размеры прошедших входов и индексы отклонённых
+    fn run(arbiter: &mut DetectionArbiter, now: DateTime<Utc>, entries: &[(usize, StrategyAction)]) -> (Vec<(usize, f64)>, Vec<usize>) {
+        let mut entries = entries.to_vec();
+        let mut declined = Vec::new();
+        arbiter.arbitrate(now, &mut entries, |idx| declined.push(idx));
+        let passed = entries
+            .iter()
+            .filter_map(|(idx, action)| match *action {
+                StrategyAction::PlaceBuy { size, .. } => Some((*idx, size)),
+                _ => None,
+            })
+            .collect();
+        (passed, declined)
+    }
+
+    #[test]
+    fn test_first_wins_owns_event_until_window_ends() {
+        let mut arbiter = DetectionArbiter::new(DetectionPolicy::default(), ["Hook", "MStrike"]);
+        let t0 = Utc::now();
+        let first = run(&mut arbiter, t0, &[(0, StrategyAction::NoAction), (1, buy(10.0))]);
+        assert_eq!(first, (vec![(1, 10.0)], vec![]));
+        assert_eq!(arbiter.owner(t0), Some(1));
+
+        let later = run(&mut arbiter, t0 + Duration::seconds(5), &[(0, buy(10.0)), (1, buy(5.0))]);
+        assert_eq!(later, (vec![(1, 5.0)], vec![0]));
+
+        // После окна событие новое
+        let next = run(&mut arbiter, t0 + Duration::seconds(61), &[(0, buy(10.0))]);
+        assert_eq!(next, (vec![(0, 10.0)], vec![]));
+    }
+
+    #[test]
+    fn test_priority_breaks_same_tick_ties() {
+        let policy = DetectionPolicy {
+            mode: ArbiterMode::Priority(vec!["MStrike".to_string(), "Hook".to_string()]),
+            ..DetectionPolicy::default()
+        };
+        let mut arbiter = DetectionArbiter::new(policy, ["MShot", "Hook", "MStrike"]);
+        let verdicts = run(&mut arbiter, Utc::now(), &[(0, buy(1.0)), (1, buy(1.0)), (2, buy(1.0))]);
+        assert_eq!(verdicts, (vec![(2, 1.0)], vec![0, 1]));
+    }
+
+    #[test]
+    fn test_scaled_entries_shrink_per_join() {
+        let policy = DetectionPolicy {
+            mode: ArbiterMode::ScaleSize { factor: 0.5 },
+            ..DetectionPolicy::default()
+        };
+        let mut arbiter = DetectionArbiter::new(policy, ["Hook", "MStrike", "MShot"]);
+        let now = Utc::now();
+        assert_eq!(run(&mut arbiter, now, &[(0, buy(8.0))]), (vec![(0, 8.0)], vec![]));
+        let verdicts = run(&mut arbiter, now, &[(1, buy(8.0)), (2, buy(8.0))]);
+        assert_eq!(verdicts, (vec![(1, 4.0), (2, 2.0)], vec![]));
+    }
+}
@@ -0,0 +1,74 @@
+//! Бэкенд архива в Kafka (librdkafka)
+//!
+//! Продюсер идемпотентный, `acks=all`, сжатие lz4. Повтор пачки после частичной
+//! ошибки пишет часть сообщений второй раз - доставка at-least-once, потребитель
+//! убирает дубли по (`source`, `seq`).
+
+use super::{ArchiveRecord, ArchiveSink};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::collections::HashMap;
+
+pub struct KafkaSink {
+    producer: FutureProducer,
+    brokers: String,
+}
+
+impl KafkaSink {
+    /// `properties` перекрывают умолчания
+    pub fn new(brokers: &str, properties: &HashMap<String, String>) -> Result<Self> {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", brokers)
+            .set("enable.idempotence", "true")
+            .set("acks", "all")
+            .set("compression.type", "lz4")
+            .set("linger.ms", "20");
+        for (key, value) in properties {
+            config.set(key, value);
+        }
+        let producer = config.create().with_context(|| format!("create kafka producer for {}", brokers))?;
+        Ok(Self { producer, brokers: brokers.to_string() })
+    }
+}
+
+#[async_trait]
+impl ArchiveSink for KafkaSink {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    async fn send_batch(&self, batch: &[ArchiveRecord]) -> Result<()> {
+        let mut deliveries = Vec::with_capacity(batch.len());
+        for record in batch {
+            let message = FutureRecord::to(&record.topic).key(record.key.as_str()).payload(&record.payload);
+            let delivery = self
+                .producer
+                .send_result(message)
+                .map_err(|(err, _)| anyhow!("kafka {} rejected a message for {}: {}", self.brokers, record.topic, err))?;
+            deliveries.push(delivery);
+        }
+        let mut failed = 0;
+        let mut last_error = None;
+        for result in join_all(deliveries).await {
+            match result {
+                Ok(Ok(_)) => {}
+                Ok(Err((err, _))) => {
+                    failed += 1;
+                    last_error = Some(err.to_string());
+                }
+                Err(_) => {
+                    failed += 1;
+                    last_error = Some("producer dropped the delivery".to_string());
+                }
+            }
+        }
+        match last_error {
+            None => Ok(()),
+            Some(err) => Err(anyhow!("kafka {}: {} of {} messages not delivered: {}", self.brokers, failed, batch.len(), err)),
+        }
+    }
+}
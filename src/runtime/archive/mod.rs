@@ -0,0 +1,480 @@
+//! Архив тиков и исполнений в Kafka или NATS
+//!
+//! Для центрального data lake: каждый тик насоса и каждое событие исполнения из
+//! user-data стрима (апдейты ордеров и собственные сделки) уходят нормализованным
+//! JSON в топик. Ключ сообщения - символ, поэтому в Kafka события символа лежат в
+//! одной партиции по порядку; в NATS символ - последний токен subject
+//! (`{topic}.{SYMBOL}`).
+//!
+//! Горячий путь только кладёт событие в ограниченную очередь (сериализация - в
+//! фоновой задаче). Полная очередь - событие теряется и считается в
+//! `ArchiveStats::dropped`; пачка, которую бэкенд не принял за `max_retries`
+//! попыток, тоже. Брокеры подключаются фичами `kafka` и `nats`; конфиг с бэкендом,
+//! который не собран, - ошибка при старте.
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::config::credentials::Secret;
+use crate::execution::types::{ClientOrderId, ExchangeOrderId, OrderStatus};
+use crate::execution::user_stream::UserEvent;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArchiveBackend {
+    Kafka {
+        /// `host:port[,host:port]`
+        brokers: String,
+        /// Дополнительные свойства librdkafka (`security.protocol`, `sasl.*`, ...)
+        #[serde(default)]
+        properties: HashMap<String, String>,
+    },
+    Nats {
+        url: String,
+        #[serde(default)]
+        token: Option<Secret>,
+    },
+}
+
+impl ArchiveBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Kafka { .. } => "kafka",
+            Self::Nats { .. } => "nats",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    pub backend: ArchiveBackend,
+    /// Кто пишет: одинаковые топики делят несколько ботов
+    #[serde(default = "default_source")]
+    pub source: String,
+    #[serde(default = "default_tick_topic")]
+    pub tick_topic: String,
+    #[serde(default = "default_execution_topic")]
+    pub execution_topic: String,
+    #[serde(default = "default_queue")]
+    pub queue: usize,
+    #[serde(default = "default_batch")]
+    pub batch: usize,
+    /// Сколько неполная пачка ждёт добора перед отправкой
+    #[serde(default = "default_linger_ms")]
+    pub linger_ms: u64,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_source() -> String {
+    "bot".to_string()
+}
+
+fn default_tick_topic() -> String {
+    "bot.ticks".to_string()
+}
+
+fn default_execution_topic() -> String {
+    "bot.executions".to_string()
+}
+
+fn default_queue() -> usize {
+    65_536
+}
+
+fn default_batch() -> usize {
+    500
+}
+
+fn default_linger_ms() -> u64 {
+    100
+}
+
+fn default_max_retries() -> u32 {
+    5
+}
+
+/// Нормализованное событие архива; `type` - вид события
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArchiveEvent {
+    Tick {
+        symbol: Symbol,
+        ts: DateTime<Utc>,
+        price: f64,
+        volume: f64,
+        side: TradeSide,
+        trade_id: String,
+        best_bid: Option<f64>,
+        best_ask: Option<f64>,
+    },
+    Order {
+        symbol: Symbol,
+        client_order_id: Option<ClientOrderId>,
+        exchange_order_id: ExchangeOrderId,
+        status: OrderStatus,
+        side: Side,
+        price: f64,
+        size: f64,
+        filled: f64,
+        avg_fill_price: Option<f64>,
+        ts_ms: u64,
+    },
+    Trade {
+        symbol: Symbol,
+        client_order_id: Option<ClientOrderId>,
+        exchange_order_id: ExchangeOrderId,
+        trade_id: String,
+        side: Side,
+        price: f64,
+        size: f64,
+        fee: f64,
+        maker: bool,
+        ts_ms: u64,
+    },
+}
+
+impl ArchiveEvent {
+    pub fn symbol(&self) -> Symbol {
+        match self {
+            Self::Tick { symbol, .. } | Self::Order { symbol, .. } | Self::Trade { symbol, .. } => *symbol,
+        }
+    }
+
+    fn is_tick(&self) -> bool {
+        matches!(self, Self::Tick { .. })
+    }
+}
+
+/// Сообщение в топике: событие плюс источник и сквозной номер (по пропускам
+/// потребитель видит потерянные события)
+#[derive(Serialize)]
+struct ArchiveEnvelope<'a> {
+    source: &'a str,
+    seq: u64,
+    #[serde(flatten)]
+    event: &'a ArchiveEvent,
+}
+
+/// Готовое к отправке сообщение
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveRecord {
+    pub topic: Arc<str>,
+    pub key: Symbol,
+    pub payload: Vec<u8>,
+}
+
+/// Бэкенд архива. Пачка уходит целиком: ошибка - повтор всей пачки.
+#[async_trait]
+pub trait ArchiveSink: Send + Sync {
+    fn name(&self) -> &str;
+    async fn send_batch(&self, batch: &[ArchiveRecord]) -> Result<()>;
+}
+
+/// Подключает бэкенд из конфига
+pub async fn connect_sink(backend: &ArchiveBackend) -> Result<Box<dyn ArchiveSink>> {
+    match backend {
+        #[cfg(feature = "kafka")]
+        ArchiveBackend::Kafka { brokers, properties } => Ok(Box::new(kafka::KafkaSink::new(brokers, properties)?)),
+        #[cfg(feature = "nats")]
+        ArchiveBackend::Nats { url, token } => Ok(Box::new(nats::NatsSink::connect(url, token.as_ref()).await?)),
+        #[allow(unreachable_patterns)]
+        other => anyhow::bail!("archive backend {} is not compiled in, enable the `{}` feature", other.name(), other.name()),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ArchiveStats {
+    pub sent: AtomicU64,
+    pub dropped: AtomicU64,
+    pub retries: AtomicU64,
+}
+
+enum Msg {
+    Event(ArchiveEvent),
+    Stop,
+}
+
+/// Ручка для горячего пути; клоны пишут в одну очередь
+#[derive(Clone)]
+pub struct ArchiveHandle {
+    tx: mpsc::Sender<Msg>,
+    stats: Arc<ArchiveStats>,
+}
+
+impl ArchiveHandle {
+    fn push(&self, event: ArchiveEvent) {
+        if self.tx.try_send(Msg::Event(event)).is_err() {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn on_tick(&self, tick: &TradeTick) {
+        self.push(ArchiveEvent::Tick {
+            symbol: tick.symbol,
+            ts: tick.timestamp,
+            price: tick.price,
+            volume: tick.volume,
+            side: tick.side,
+            trade_id: tick.trade_id.clone(),
+            best_bid: tick.best_bid,
+            best_ask: tick.best_ask,
+        });
+    }
+
+    /// Архивируются апдейты ордеров и сделки; балансы и позиции - нет
+    pub fn on_user_event(&self, event: &UserEvent) {
+        match event {
+            UserEvent::Order(update) => self.push(ArchiveEvent::Order {
+                symbol: update.symbol,
+                client_order_id: update.client_order_id.clone(),
+                exchange_order_id: update.exchange_order_id.clone(),
+                status: update.status.clone(),
+                side: update.side,
+                price: update.price,
+                size: update.size,
+                filled: update.filled,
+                avg_fill_price: update.avg_fill_price,
+                ts_ms: update.ts_ms,
+            }),
+            UserEvent::Trade(trade) => self.push(ArchiveEvent::Trade {
+                symbol: trade.symbol,
+                client_order_id: trade.client_order_id.clone(),
+                exchange_order_id: trade.exchange_order_id.clone(),
+                trade_id: trade.trade_id.clone(),
+                side: trade.side,
+                price: trade.price,
+                size: trade.size,
+                fee: trade.fee,
+                maker: trade.maker,
+                ts_ms: trade.ts_ms,
+            }),
+            UserEvent::Balance(_) | UserEvent::Position(_) | UserEvent::StreamGap { .. } => {}
+        }
+    }
+
+    pub fn stats(&self) -> &ArchiveStats {
+        &self.stats
+    }
+}
+
+/// Фоновая задача архива
+pub struct EventArchiver {
+    handle: ArchiveHandle,
+    task: JoinHandle<()>,
+}
+
+impl EventArchiver {
+    /// Подключает бэкенд из конфига и запускает задачу
+    pub async fn start(config: ArchiveConfig) -> Result<Self> {
+        let sink = connect_sink(&config.backend).await?;
+        log::info!(
+            "archiving ticks to {} and executions to {} via {}",
+            config.tick_topic,
+            config.execution_topic,
+            sink.name()
+        );
+        Ok(Self::with_sink(config, sink))
+    }
+
+    pub fn with_sink(config: ArchiveConfig, sink: Box<dyn ArchiveSink>) -> Self {
+        assert!(config.queue > 0 && config.batch > 0, "archive queue and batch must be positive");
+        let (tx, rx) = mpsc::channel(config.queue);
+        let stats = Arc::new(ArchiveStats::default());
+        let task = tokio::spawn(run(config, sink, rx, stats.clone()));
+        Self { handle: ArchiveHandle { tx, stats }, task }
+    }
+
+    pub fn handle(&self) -> ArchiveHandle {
+        self.handle.clone()
+    }
+
+    pub fn stats(&self) -> &ArchiveStats {
+        &self.handle.stats
+    }
+
+    /// Отправляет всё, что уже в очереди, и останавливает задачу
+    pub async fn shutdown(self) {
+        let _ = self.handle.tx.send(Msg::Stop).await;
+        if let Err(err) = self.task.await {
+            log::error!("archive task failed: {}", err);
+        }
+    }
+}
+
+async fn run(config: ArchiveConfig, sink: Box<dyn ArchiveSink>, mut rx: mpsc::Receiver<Msg>, stats: Arc<ArchiveStats>) {
+    let ticks: Arc<str> = config.tick_topic.as_str().into();
+    let executions: Arc<str> = config.execution_topic.as_str().into();
+    let linger = Duration::from_millis(config.linger_ms);
+    let mut seq = 0u64;
+    let mut batch: Vec<ArchiveRecord> = Vec::with_capacity(config.batch);
+    let mut stopping = false;
+    let mut encode = |event: ArchiveEvent, batch: &mut Vec<ArchiveRecord>| {
+        seq += 1;
+        let envelope = ArchiveEnvelope { source: &config.source, seq, event: &event };
+        match serde_json::to_vec(&envelope) {
+            Ok(payload) => batch.push(ArchiveRecord {
+                topic: if event.is_tick() { ticks.clone() } else { executions.clone() },
+                key: event.symbol(),
+                payload,
+            }),
+            Err(err) => log::error!("archive event {:?} not serialized: {}", event, err),
+        }
+    };
+
+    while !stopping {
+        match rx.recv().await {
+            Some(Msg::Event(event)) => encode(event, &mut batch),
+            Some(Msg::Stop) | None => break,
+        }
+        let deadline = tokio::time::Instant::now() + linger;
+        while batch.len() < config.batch {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(Msg::Event(event))) => encode(event, &mut batch),
+                Ok(Some(Msg::Stop)) | Ok(None) => {
+                    stopping = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        send(sink.as_ref(), &batch, config.max_retries, &stats).await;
+        batch.clear();
+    }
+}
+
+async fn send(sink: &dyn ArchiveSink, batch: &[ArchiveRecord], max_retries: u32, stats: &ArchiveStats) {
+    if batch.is_empty() {
+        return;
+    }
+    let mut attempt = 0;
+    loop {
+        match sink.send_batch(batch).await {
+            Ok(()) => {
+                stats.sent.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                return;
+            }
+            Err(err) if attempt < max_retries => {
+                attempt += 1;
+                stats.retries.fetch_add(1, Ordering::Relaxed);
+                log::warn!("{} archive batch failed (attempt {}/{}): {:#}", sink.name(), attempt, max_retries, err);
+                tokio::time::sleep(Duration::from_millis(200) * 2u32.pow(attempt.min(6))).await;
+            }
+            Err(err) => {
+                stats.dropped.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                log::error!("{} archive dropped {} events after {} retries: {:#}", sink.name(), batch.len(), max_retries, err);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemorySink {
+        records: Mutex<Vec<ArchiveRecord>>,
+        batches: AtomicU64,
+        failures_left: AtomicU64,
+    }
+
+    #[async_trait]
+    impl ArchiveSink for Arc<MemorySink> {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        async fn send_batch(&self, batch: &[ArchiveRecord]) -> Result<()> {
+            if self.failures_left.load(Ordering::Relaxed) > 0 {
+                self.failures_left.fetch_sub(1, Ordering::Relaxed);
+                anyhow::bail!("broker unavailable");
+            }
+            self.batches.fetch_add(1, Ordering::Relaxed);
+            self.records.lock().unwrap().extend_from_slice(batch);
+            Ok(())
+        }
+    }
+
+    fn config(json: &str) -> ArchiveConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_config_backends() {
+        let kafka = config(r#"{"backend": {"kind": "kafka", "brokers": "k1:9092,k2:9092"}, "source": "bot-eu"}"#);
+        assert_eq!(kafka.backend.name(), "kafka");
+        assert_eq!(kafka.tick_topic, "bot.ticks");
+        assert_eq!(kafka.batch, 500);
+        let nats = config(r#"{"backend": {"kind": "nats", "url": "nats://lake:4222", "token": "t"}}"#);
+        assert!(matches!(nats.backend, ArchiveBackend::Nats { token: Some(_), .. }));
+    }
+
+    #[tokio::test]
+    async fn test_events_are_batched_retried_and_flushed() {
+        let sink = Arc::new(MemorySink { failures_left: AtomicU64::new(1), ..Default::default() });
+        let mut config = config(r#"{"backend": {"kind": "nats", "url": "nats://lake:4222"}, "source": "bot-1"}"#);
+        config.batch = 2;
+        let archiver = EventArchiver::with_sink(config, Box::new(sink.clone()));
+        let handle = archiver.handle();
+
+        let symbol = Symbol::new("ETH_USDT");
+        let tick = TradeTick {
+            timestamp: Utc::now(),
+            symbol,
+            price: 3000.0,
+            volume: 0.5,
+            side: TradeSide::Sell,
+            trade_id: "t1".to_string(),
+            best_bid: Some(2999.9),
+            best_ask: None,
+        };
+        handle.on_tick(&tick);
+        handle.on_tick(&tick);
+        handle.on_user_event(&UserEvent::StreamGap { reconnects: 1 });
+        handle.on_user_event(&UserEvent::Order(crate::execution::user_stream::OrderUpdate {
+            symbol,
+            client_order_id: Some(ClientOrderId("t-1".to_string())),
+            exchange_order_id: ExchangeOrderId("42".to_string()),
+            status: OrderStatus::Filled,
+            side: Side::Bid,
+            price: 3000.0,
+            size: 1.0,
+            filled: 1.0,
+            avg_fill_price: Some(3000.0),
+            ts_ms: 1,
+        }));
+        archiver.shutdown().await;
+
+        let records = sink.records.lock().unwrap().clone();
+        assert_eq!(records.len(), 3);
+        assert_eq!(sink.batches.load(Ordering::Relaxed), 2);
+        assert_eq!(&*records[0].topic, "bot.ticks");
+        assert_eq!(&*records[2].topic, "bot.executions");
+        assert!(records.iter().all(|r| r.key == symbol));
+        let order: serde_json::Value = serde_json::from_slice(&records[2].payload).unwrap();
+        assert_eq!(order["type"], "order");
+        assert_eq!(order["source"], "bot-1");
+        assert_eq!(order["seq"], 3);
+        assert_eq!(order["status"], "filled");
+        assert_eq!(handle.stats().retries.load(Ordering::Relaxed), 1);
+        assert_eq!(handle.stats().dropped.load(Ordering::Relaxed), 0);
+    }
+}
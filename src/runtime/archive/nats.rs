@@ -0,0 +1,44 @@
+//! Бэкенд архива в NATS
+//!
+//! Subject - `{topic}.{SYMBOL}`. Обычный publish без подтверждений: хранение даёт
+//! JetStream-стрим, подписанный на `{topic}.>`. Пачка считается отправленной,
+//! когда `flush` дописал её в сокет.
+
+use super::{ArchiveRecord, ArchiveSink};
+use crate::config::credentials::Secret;
+use anyhow::{Context, Result};
+use async_nats::{Client, ConnectOptions};
+use async_trait::async_trait;
+
+pub struct NatsSink {
+    client: Client,
+    url: String,
+}
+
+impl NatsSink {
+    pub async fn connect(url: &str, token: Option<&Secret>) -> Result<Self> {
+        let mut options = ConnectOptions::new().name("crypto-trading-bot archive");
+        if let Some(token) = token {
+            options = options.token(token.expose().to_string());
+        }
+        let client = options.connect(url).await.with_context(|| format!("connect to nats {}", url))?;
+        Ok(Self { client, url: url.to_string() })
+    }
+}
+
+#[async_trait]
+impl ArchiveSink for NatsSink {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    async fn send_batch(&self, batch: &[ArchiveRecord]) -> Result<()> {
+        for record in batch {
+            self.client
+                .publish(format!("{}.{}", record.topic, record.key), record.payload.clone().into())
+                .await
+                .with_context(|| format!("publish to nats {}", self.url))?;
+        }
+        self.client.flush().await.with_context(|| format!("flush nats {}", self.url))
+    }
+}
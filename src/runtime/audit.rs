@@ -0,0 +1,183 @@
+//! Журнал изменений конфигурации: кто, что, когда, было -> стало и откуда
+//!
+//! Каждое изменение конфига (перечитанный файл, control API, команда Telegram) сводится
+//! к списку ключей с прежним и новым значением: `ConfigAudit::record` сравнивает
+//! конфиг до и после как JSON и по каждому изменившемуся листу шлёт `ConfigChange` в
+//! канал. Из канала изменения забирает хранилище (`StateStore::spawn_audit_writer`) и
+//! пишет в журнал только на добавление - по нему разбор инцидента связывает сдвиг PnL
+//! с правкой параметров. Вложенные объекты раскладываются в ключи через точку
+//! (`risk.max_positions`), массивы сравниваются целиком.
+//!
+//! Запись не блокирует вызывающего: изменения редки, канал неограниченный. Если
+//! писатель журнала умер, изменение теряется с ошибкой в логе.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+
+/// Откуда пришло изменение
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeSource {
+    /// Конфиг перечитан с диска
+    File,
+    Api,
+    Telegram,
+    Cli,
+}
+
+impl ChangeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeSource::File => "file",
+            ChangeSource::Api => "api",
+            ChangeSource::Telegram => "telegram",
+            ChangeSource::Cli => "cli",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "file" => Some(ChangeSource::File),
+            "api" => Some(ChangeSource::Api),
+            "telegram" => Some(ChangeSource::Telegram),
+            "cli" => Some(ChangeSource::Cli),
+            _ => None,
+        }
+    }
+}
+
+/// Кто меняет: источник и его пользователь (тенант токена, chat id, путь файла)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeOrigin {
+    pub source: ChangeSource,
+    pub actor: String,
+}
+
+impl ChangeOrigin {
+    pub fn new(source: ChangeSource, actor: impl Into<String>) -> Self {
+        Self { source, actor: actor.into() }
+    }
+}
+
+/// Один изменившийся ключ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub ts_ms: i64,
+    pub source: ChangeSource,
+    pub actor: String,
+    /// Что менялось: `symbol_lists`, `strategy:hook`, `risk_profile`
+    pub target: String,
+    /// Путь ключа через точку; пустой - значение целиком
+    pub key: String,
+    /// `None` - ключа не было
+    pub old: Option<Value>,
+    /// `None` - ключ удалён
+    pub new: Option<Value>,
+}
+
+/// Изменившиеся листы `new` относительно `old`: (ключ, было, стало), по ключам
+pub fn diff_values(old: &Value, new: &Value) -> Vec<(String, Option<Value>, Option<Value>)> {
+    let mut out = Vec::new();
+    diff_into(String::new(), Some(old), Some(new), &mut out);
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+fn diff_into(key: String, old: Option<&Value>, new: Option<&Value>, out: &mut Vec<(String, Option<Value>, Option<Value>)>) {
+    let empty = Value::Object(Map::new());
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            for name in old.keys().chain(new.keys().filter(|name| !old.contains_key(*name))) {
+                let child = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                diff_into(child, old.get(name), new.get(name), out);
+            }
+        }
+        // Появившийся или исчезнувший объект - по листу на ключ
+        (Some(old @ Value::Object(_)), None) => diff_into(key, Some(old), Some(&empty), out),
+        (None, Some(new @ Value::Object(_))) => diff_into(key, Some(&empty), Some(new), out),
+        (old, new) if old != new => out.push((key, old.cloned(), new.cloned())),
+        _ => {}
+    }
+}
+
+/// Ручка журнала для всех, кто меняет конфиг; клонируется
+#[derive(Debug, Clone)]
+pub struct ConfigAudit {
+    tx: mpsc::UnboundedSender<ConfigChange>,
+}
+
+impl ConfigAudit {
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<ConfigChange>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+
+    /// Записывает разницу `old` -> `new` цели; возвращает число изменившихся ключей
+    pub fn record<T: Serialize>(&self, origin: &ChangeOrigin, target: &str, old: &T, new: &T) -> usize {
+        let (old, new) = match (serde_json::to_value(old), serde_json::to_value(new)) {
+            (Ok(old), Ok(new)) => (old, new),
+            (Err(err), _) | (_, Err(err)) => {
+                log::error!("config audit: {} change by {} not recorded: {}", target, origin.actor, err);
+                return 0;
+            }
+        };
+        let ts_ms = chrono::Utc::now().timestamp_millis();
+        let changes = diff_values(&old, &new);
+        let count = changes.len();
+        for (key, old, new) in changes {
+            log::warn!(
+                "config change {} {}: {} -> {} ({} {})",
+                target,
+                key,
+                old.as_ref().map_or("-".to_string(), Value::to_string),
+                new.as_ref().map_or("-".to_string(), Value::to_string),
+                origin.source.as_str(),
+                origin.actor
+            );
+            let change = ConfigChange {
+                ts_ms,
+                source: origin.source,
+                actor: origin.actor.clone(),
+                target: target.to_string(),
+                key,
+                old,
+                new,
+            };
+            if self.tx.send(change).is_err() {
+                log::error!("config audit writer is gone: {} change by {} is not stored", target, origin.actor);
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_flattens_objects_and_records_each_leaf() {
+        let old = json!({"risk": {"max_positions": 3, "stop_pct": 1.0}, "symbols": ["BTC_USDT"], "paused": false});
+        let new = json!({"risk": {"max_positions": 5, "stop_pct": 1.0}, "symbols": ["BTC_USDT", "ETH_USDT"], "hedge": {"on": true}});
+        let diff = diff_values(&old, &new);
+        assert_eq!(
+            diff,
+            vec![
+                ("hedge.on".to_string(), None, Some(json!(true))),
+                ("paused".to_string(), Some(json!(false)), None),
+                ("risk.max_positions".to_string(), Some(json!(3)), Some(json!(5))),
+                ("symbols".to_string(), Some(json!(["BTC_USDT"])), Some(json!(["BTC_USDT", "ETH_USDT"]))),
+            ]
+        );
+
+        let (audit, mut rx) = ConfigAudit::channel();
+        let origin = ChangeOrigin::new(ChangeSource::Telegram, "chat:42");
+        assert_eq!(audit.record(&origin, "strategy:hook", &old, &new), 4);
+        assert_eq!(audit.record(&origin, "strategy:hook", &new, &new), 0);
+        let first = rx.try_recv().unwrap();
+        assert_eq!((first.source, first.actor.as_str(), first.target.as_str()), (ChangeSource::Telegram, "chat:42", "strategy:hook"));
+        assert_eq!(std::iter::from_fn(|| rx.try_recv().ok()).count(), 3);
+    }
+}
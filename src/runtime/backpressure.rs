@@ -0,0 +1,206 @@
+//! Backpressure market-data насоса: что делать с тиком, когда шард не успевает
+//!
+//! При Coalesce насос придерживает по одному тику на символ и сторону сделки и склеивает
+//! в него следующие (последняя цена, суммарный объём), пока очередь шарда выше порога.
+//! Покупки и продажи не смешиваются, иначе весь объём ушёл бы стороне последней сделки
+//! и `OrderFlow` увидел бы ложный дисбаланс как раз под нагрузкой.
+//! Память ограничена числом символов, а не скоростью биржи.
+
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use std::collections::HashMap;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    /// Ждать места в кольце шарда
+    Block,
+    /// Склеивать тики символа, пока в очереди шарда `high_water` и больше команд
+    Coalesce { high_water: usize },
+    /// Отбрасывать новые тики, пока в очереди шарда `high_water` и больше команд
+    Drop { high_water: usize },
+}
+
+impl BackpressurePolicy {
+    pub fn high_water(&self) -> Option<usize> {
+        match *self {
+            Self::Block => None,
+            Self::Coalesce { high_water } | Self::Drop { high_water } => Some(high_water),
+        }
+    }
+}
+
+/// Счётчики насоса по одному шарду
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PumpStats {
+    /// Тики, отданные в кольцо шарда (склеенный тик считается один раз)
+    pub forwarded: u64,
+    /// Тики, поглощённые склейкой
+    pub coalesced: u64,
+    pub dropped: u64,
+    /// Тики символов из чёрного списка рантайма
+    pub blacklisted: u64,
+    /// Тики символов вне набора `UniverseScanner`
+    pub out_of_universe: u64,
+    /// Тики, отбракованные `TickGuard`
+    pub rejected: u64,
+    /// Тики REST-опроса устаревших символов (`RestFallback`)
+    pub fallback: u64,
+    /// Максимальная глубина очереди шарда, которую видел насос
+    pub max_backlog: usize,
+}
+
+/// Склеивает `next` в `held` той же стороны: цена, время и стакан берутся из последнего тика,
+/// объём суммируется
+pub fn merge_tick(held: &mut TradeTick, next: TradeTick) {
+    debug_assert_eq!(held.symbol, next.symbol, "coalescing ticks of different symbols");
+    debug_assert_eq!(held.side, next.side, "coalescing buys with sells");
+    held.timestamp = next.timestamp;
+    held.price = next.price;
+    held.volume += next.volume;
+    held.trade_id = next.trade_id;
+    held.best_bid = next.best_bid.or(held.best_bid);
+    held.best_ask = next.best_ask.or(held.best_ask);
+}
+
+/// Придержанные тики одного символа: покупки и продажи склеиваются раздельно
+#[derive(Debug, Default)]
+struct HeldTicks {
+    buy: Option<(TradeTick, Instant)>,
+    sell: Option<(TradeTick, Instant)>,
+}
+
+impl HeldTicks {
+    fn count(&self) -> u64 {
+        self.buy.is_some() as u64 + self.sell.is_some() as u64
+    }
+}
+
+/// Придержанные для шарда тики, не больше одного на символ и сторону, с моментом приёма
+/// самого раннего из склеенных - задержка считается от него
+#[derive(Debug, Default)]
+pub(crate) struct Coalescer {
+    held: HashMap<Symbol, HeldTicks>,
+}
+
+impl Coalescer {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    pub(crate) fn holds(&self, symbol: Symbol) -> bool {
+        self.held.contains_key(&symbol)
+    }
+
+    /// Придерживает тик; возвращает true, если он склеен с уже придержанным той же стороны
+    pub(crate) fn hold(&mut self, tick: TradeTick, received: Instant) -> bool {
+        let held = self.held.entry(tick.symbol).or_default();
+        let slot = match tick.side {
+            TradeSide::Buy => &mut held.buy,
+            TradeSide::Sell => &mut held.sell,
+        };
+        match slot {
+            Some((held, _)) => {
+                merge_tick(held, tick);
+                true
+            }
+            None => {
+                *slot = Some((tick, received));
+                false
+            }
+        }
+    }
+
+    /// Выбрасывает придержанные тики символа; возвращает, сколько их было
+    pub(crate) fn discard(&mut self, symbol: Symbol) -> u64 {
+        self.held.remove(&symbol).map_or(0, |held| held.count())
+    }
+
+    /// Все придержанные тики; стороны одного символа идут в порядке последней сделки
+    pub(crate) fn take(&mut self) -> Vec<(TradeTick, Instant)> {
+        let mut ticks = Vec::with_capacity(self.held.len() * 2);
+        for (_, held) in self.held.drain() {
+            let mut sides = [held.buy, held.sell];
+            if let [Some((buy, _)), Some((sell, _))] = &sides
+                && sell.timestamp < buy.timestamp
+            {
+                sides.swap(0, 1);
+            }
+            ticks.extend(sides.into_iter().flatten());
+        }
+        ticks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn tick(symbol: &str, price: f64, volume: f64, best_bid: Option<f64>) -> TradeTick {
+        TradeTick {
+            timestamp: Utc::now(),
+            symbol: Symbol::new(symbol),
+            price,
+            volume,
+            side: TradeSide::Sell,
+            trade_id: format!("{}", price),
+            best_bid,
+            best_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_coalesce_keeps_latest_price_and_sums_volume() {
+        let mut coalescer = Coalescer::default();
+        let first = Instant::now();
+        assert!(!coalescer.hold(tick("BTC_USDT", 100.0, 1.0, Some(99.0)), first));
+        assert!(coalescer.hold(tick("BTC_USDT", 95.0, 2.0, None), Instant::now()));
+        assert!(coalescer.hold(tick("BTC_USDT", 90.0, 3.0, Some(89.5)), Instant::now()));
+        assert!(!coalescer.hold(tick("ETH_USDT", 10.0, 5.0, None), Instant::now()));
+
+        let held = coalescer.take();
+        assert!(coalescer.is_empty());
+        let (btc, received) = held.iter().find(|(tick, _)| tick.symbol == "BTC_USDT").unwrap();
+        assert_eq!(*received, first);
+        assert_eq!(btc.price, 90.0);
+        assert_eq!(btc.volume, 6.0);
+        assert_eq!(btc.best_bid, Some(89.5));
+        assert_eq!(btc.trade_id, "90");
+        assert_eq!(held.iter().find(|(tick, _)| tick.symbol == "ETH_USDT").unwrap().0.volume, 5.0);
+    }
+
+    #[test]
+    fn test_buys_and_sells_coalesce_apart() {
+        let mut coalescer = Coalescer::default();
+        let now = Utc::now();
+        let at = |ms, side, volume| TradeTick {
+            timestamp: now + chrono::Duration::milliseconds(ms),
+            side,
+            ..tick("BTC_USDT", 100.0 + ms as f64, volume, None)
+        };
+        coalescer.hold(at(0, TradeSide::Buy, 1.0), Instant::now());
+        coalescer.hold(at(1, TradeSide::Sell, 4.0), Instant::now());
+        assert!(coalescer.hold(at(2, TradeSide::Buy, 2.0), Instant::now()));
+        assert!(coalescer.hold(at(3, TradeSide::Sell, 8.0), Instant::now()));
+        assert_eq!(coalescer.discard(Symbol::new("ETH_USDT")), 0);
+
+        let held = coalescer.take();
+        let sides: Vec<(TradeSide, f64, f64)> = held.iter().map(|(tick, _)| (tick.side, tick.volume, tick.price)).collect();
+        // Последней была продажа - она идёт второй и несёт последнюю цену
+        assert_eq!(sides, vec![(TradeSide::Buy, 3.0, 102.0), (TradeSide::Sell, 12.0, 103.0)]);
+
+        coalescer.hold(at(4, TradeSide::Buy, 1.0), Instant::now());
+        coalescer.hold(at(5, TradeSide::Sell, 1.0), Instant::now());
+        assert_eq!(coalescer.discard(Symbol::new("BTC_USDT")), 2);
+        assert!(coalescer.is_empty());
+    }
+
+    #[test]
+    fn test_merge_keeps_book_when_next_tick_has_none() {
+        let mut held = tick("BTC_USDT", 100.0, 1.0, Some(99.0));
+        merge_tick(&mut held, tick("BTC_USDT", 101.0, 1.0, None));
+        assert_eq!(held.best_bid, Some(99.0));
+        assert_eq!(held.price, 101.0);
+    }
+}
@@ -0,0 +1,323 @@
+//! Делистинг и остановка торгов по символу
+//!
+//! Два источника:
+//! - объявления: биржа помечает контракт (у Gate `in_delisting`, `status`,
+//!   `delisting_time`) или оператор заносит дату вручную;
+//! - остановка торгов без объявления: по символу нет сделок дольше `halt_silence_ms`,
+//!   а последний стакан пуст или перевёрнут (bid >= ask).
+//!
+//! Политика: за `exit_lead_ms` до даты делистинга (или сразу, если даты нет) позиции по
+//! символу закрываются, к дате символ попадает в чёрный список рантайма. Остановленный
+//! символ уходит в чёрный список сразу - выйти из него всё равно нельзя. Каждое действие
+//! выдаётся один раз.
+//!
+//! Действия исполняет `ShardedRuntime::poll_delisting`: выход - reduce-only заявкой через
+//! `PositionCloser`, чёрный список - когда позиция закрыта, а если нет - после
+//! `RiskEvent::DelistingStranded`. Объявления Gate приносит `ContractStatusPoll`.
+
+use super::ShardedRuntime;
+use crate::base_classes::symbol::Symbol;
+use crate::exchanges::endpoints::GateioGet;
+use crate::exchanges::gate::rest::GateContractMeta;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct DelistingConfig {
+    /// За сколько до делистинга закрывать позиции
+    pub exit_lead_ms: u64,
+    /// Тишина по сделкам, после которой пустой стакан считается остановкой торгов
+    pub halt_silence_ms: u64,
+    /// Период проверки в `ShardedRuntime::poll_delisting`, мс
+    pub check_interval_ms: u64,
+}
+
+impl Default for DelistingConfig {
+    fn default() -> Self {
+        Self { exit_lead_ms: 6 * 3_600_000, halt_silence_ms: 10 * 60_000, check_interval_ms: 1_000 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelistingNotice {
+    pub symbol: Symbol,
+    /// Момент делистинга (ms); None - дата неизвестна, выходим сразу
+    pub delist_at_ms: Option<u64>,
+    pub source: String,
+}
+
+impl DelistingNotice {
+    /// Объявление по метаданным контракта Gate; None, если контракт торгуется как обычно
+    pub fn from_gate_contract(symbol: Symbol, meta: &GateContractMeta) -> Option<Self> {
+        let flagged = meta.in_delisting.unwrap_or(false)
+            || matches!(meta.status.as_deref(), Some("delisting" | "delisted"));
+        if !flagged {
+            return None;
+        }
+        Some(Self {
+            symbol,
+            delist_at_ms: meta.delisting_time.map(|secs| secs * 1_000),
+            source: format!("gate contract status {}", meta.status.as_deref().unwrap_or("in_delisting")),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelistReason {
+    Delisting,
+    Halted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelistAction {
+    /// Закрыть позиции и снять ордера по символу
+    ExitPositions { symbol: Symbol, reason: DelistReason },
+    /// Убрать символ из рантайма
+    Blacklist { symbol: Symbol, reason: DelistReason },
+}
+
+#[derive(Debug, Default)]
+struct SymbolWatch {
+    notice: Option<DelistingNotice>,
+    last_trade_ms: Option<u64>,
+    /// Цена последней сделки - ориентир для закрывающей заявки
+    last_price: Option<f64>,
+    /// Последний стакан пуст или перевёрнут
+    book_closed: bool,
+    exited: bool,
+    blacklisted: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct DelistingMonitor {
+    config: DelistingConfig,
+    symbols: HashMap<Symbol, SymbolWatch>,
+}
+
+impl DelistingMonitor {
+    pub fn new(config: DelistingConfig) -> Self {
+        Self { config, symbols: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &DelistingConfig {
+        &self.config
+    }
+
+    /// Регистрирует объявление; повтор с той же датой ничего не меняет
+    pub fn on_notice(&mut self, notice: DelistingNotice) {
+        let watch = self.symbols.entry(notice.symbol).or_default();
+        if watch.notice.as_ref() == Some(&notice) {
+            return;
+        }
+        log::error!(
+            "{} delisting announced ({}), delist at {:?} ms",
+            notice.symbol,
+            notice.source,
+            notice.delist_at_ms
+        );
+        watch.notice = Some(notice);
+    }
+
+    /// Сделка по символу; лучшие цены стакана, если известны
+    pub fn on_trade(&mut self, symbol: Symbol, ts_ms: u64, price: f64, best_bid: Option<f64>, best_ask: Option<f64>) {
+        let watch = self.symbols.entry(symbol).or_default();
+        watch.last_trade_ms = Some(watch.last_trade_ms.map_or(ts_ms, |last| last.max(ts_ms)));
+        watch.last_price = Some(price);
+        watch.book_closed = false;
+        if best_bid.is_some() || best_ask.is_some() {
+            watch.book_closed = book_closed(best_bid, best_ask);
+        }
+    }
+
+    /// Снимок стакана без сделки (например из book ticker)
+    pub fn on_book(&mut self, symbol: Symbol, best_bid: Option<f64>, best_ask: Option<f64>) {
+        self.symbols.entry(symbol).or_default().book_closed = book_closed(best_bid, best_ask);
+    }
+
+    pub fn is_blacklisted(&self, symbol: Symbol) -> bool {
+        self.symbols.get(&symbol).is_some_and(|w| w.blacklisted)
+    }
+
+    /// Символы, по которым шли сделки или есть объявление
+    pub fn symbols(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.symbols.keys().copied()
+    }
+
+    pub fn last_price(&self, symbol: Symbol) -> Option<f64> {
+        self.symbols.get(&symbol).and_then(|w| w.last_price)
+    }
+
+    /// Проверяет символы на момент `now_ms` и возвращает новые действия
+    pub fn evaluate(&mut self, now_ms: u64) -> Vec<DelistAction> {
+        let mut actions = Vec::new();
+        for (&symbol, watch) in self.symbols.iter_mut().filter(|(_, w)| !w.blacklisted) {
+            if let Some(notice) = &watch.notice {
+                let exit_at = notice.delist_at_ms.map_or(0, |at| at.saturating_sub(self.config.exit_lead_ms));
+                if !watch.exited && now_ms >= exit_at {
+                    watch.exited = true;
+                    actions.push(DelistAction::ExitPositions { symbol, reason: DelistReason::Delisting });
+                }
+                if notice.delist_at_ms.is_some_and(|at| now_ms >= at) {
+                    watch.blacklisted = true;
+                    log::error!("{} delisted: blacklisted from the runtime", symbol);
+                    actions.push(DelistAction::Blacklist { symbol, reason: DelistReason::Delisting });
+                    continue;
+                }
+            }
+            let silent = watch.last_trade_ms.is_some_and(|last| now_ms.saturating_sub(last) >= self.config.halt_silence_ms);
+            if silent && watch.book_closed {
+                watch.blacklisted = true;
+                log::error!(
+                    "{} looks halted: no trades for {} ms and no two-sided book; blacklisted from the runtime",
+                    symbol,
+                    now_ms - watch.last_trade_ms.unwrap_or(now_ms)
+                );
+                actions.push(DelistAction::Blacklist { symbol, reason: DelistReason::Halted });
+            }
+        }
+        actions.sort_by_key(|action| match action {
+            DelistAction::ExitPositions { symbol, .. } => (0, symbol.to_string()),
+            DelistAction::Blacklist { symbol, .. } => (1, symbol.to_string()),
+        });
+        actions
+    }
+}
+
+/// Опрос статуса контрактов Gate: один запрос на все контракты раз в `interval`.
+/// Объявления отдаются рантайму только по символам, которые видит монитор
+pub struct ContractStatusPoll {
+    base_url: String,
+    interval: Duration,
+    polled_at: Option<Instant>,
+}
+
+impl ContractStatusPoll {
+    pub fn new(interval: Duration) -> Self {
+        Self::with_base(GateioGet::BASE, interval)
+    }
+
+    pub fn with_base(base_url: &str, interval: Duration) -> Self {
+        Self { base_url: base_url.to_string(), interval, polled_at: None }
+    }
+
+    fn due(&mut self, now: Instant) -> bool {
+        if self.polled_at.is_some_and(|at| now.duration_since(at) < self.interval) {
+            return false;
+        }
+        self.polled_at = Some(now);
+        true
+    }
+
+    /// Опрашивает контракты, если пора, и возвращает число отданных объявлений.
+    /// Вызывать из потока насоса, как и `ShardedRuntime::poll_delisting`
+    #[cfg(feature = "gate_exec")]
+    pub async fn poll(&mut self, runtime: &mut ShardedRuntime, client: &reqwest::Client) -> anyhow::Result<usize> {
+        if runtime.delisting().is_none() || !self.due(Instant::now()) {
+            return Ok(0);
+        }
+        let contracts = match crate::exchanges::gate::rest::fetch_contracts(client, &self.base_url).await {
+            Ok(contracts) => contracts,
+            Err(err) => {
+                log::error!("contract status poll failed, delistings go unnoticed until the next one: {:#}", err);
+                return Err(err);
+            }
+        };
+        Ok(apply_contracts(runtime, &contracts))
+    }
+}
+
+/// Объявления по метаданным контрактов для символов, которые видит рантайм
+fn apply_contracts(runtime: &mut ShardedRuntime, contracts: &[(String, GateContractMeta)]) -> usize {
+    let Some(monitor) = runtime.delisting() else {
+        return 0;
+    };
+    let by_name: HashMap<&str, &GateContractMeta> = contracts.iter().map(|(name, meta)| (name.as_str(), meta)).collect();
+    let notices: Vec<DelistingNotice> = monitor
+        .symbols()
+        .filter_map(|symbol| DelistingNotice::from_gate_contract(symbol, by_name.get(symbol.as_str())?))
+        .collect();
+    let count = notices.len();
+    for notice in notices {
+        runtime.on_delisting_notice(notice);
+    }
+    count
+}
+
+fn book_closed(best_bid: Option<f64>, best_ask: Option<f64>) -> bool {
+    match (best_bid, best_ask) {
+        (Some(bid), Some(ask)) => !(bid > 0.0 && ask > 0.0 && bid < ask),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3_600_000;
+
+    #[test]
+    fn test_delisting_exits_ahead_then_blacklists() {
+        let mut monitor = DelistingMonitor::new(DelistingConfig { exit_lead_ms: 6 * HOUR, halt_silence_ms: 600_000, ..DelistingConfig::default() });
+        let symbol = Symbol::new("LUNA_USDT");
+        let meta = GateContractMeta {
+            in_delisting: Some(true),
+            delisting_time: Some(100 * 3_600),
+            ..GateContractMeta::default()
+        };
+        assert!(DelistingNotice::from_gate_contract(symbol, &GateContractMeta::default()).is_none());
+        monitor.on_notice(DelistingNotice::from_gate_contract(symbol, &meta).unwrap());
+
+        assert!(monitor.evaluate(90 * HOUR).is_empty());
+        let exit = DelistAction::ExitPositions { symbol, reason: DelistReason::Delisting };
+        assert_eq!(monitor.evaluate(95 * HOUR), [exit]);
+        assert!(monitor.evaluate(96 * HOUR).is_empty());
+        assert_eq!(monitor.evaluate(100 * HOUR), [DelistAction::Blacklist { symbol, reason: DelistReason::Delisting }]);
+        assert!(monitor.is_blacklisted(symbol));
+        assert!(monitor.evaluate(101 * HOUR).is_empty());
+    }
+
+    #[test]
+    fn test_halt_needs_silence_and_closed_book() {
+        let mut monitor = DelistingMonitor::new(DelistingConfig::default());
+        let (quiet, halted) = (Symbol::new("QUIET_USDT"), Symbol::new("HALT_USDT"));
+        monitor.on_trade(quiet, 0, 1.005, Some(1.0), Some(1.01));
+        monitor.on_trade(halted, 0, 1.005, Some(1.0), Some(1.01));
+        monitor.on_book(halted, None, None);
+        assert!(monitor.evaluate(60_000).is_empty());
+
+        // Тихий, но живой стакан - не остановка
+        assert_eq!(monitor.evaluate(700_000), [DelistAction::Blacklist { symbol: halted, reason: DelistReason::Halted }]);
+        assert!(!monitor.is_blacklisted(quiet));
+    }
+
+    #[test]
+    fn test_contract_poll_notices_only_watched_symbols() {
+        use crate::backtest::strategy_adapter::HookAdapter;
+        use crate::runtime::{RuntimeConfig, StrategyFactory, StrategySet};
+        use crate::testing::ScenarioBuilder;
+        use std::sync::Arc;
+
+        let factory: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet { vec![Box::new(HookAdapter::default())] });
+        let (mut runtime, _router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, factory);
+        let delisting = GateContractMeta { in_delisting: Some(true), ..GateContractMeta::default() };
+        let contracts = vec![
+            ("LUNA_USDT".to_string(), delisting.clone()),
+            ("OTHER_USDT".to_string(), delisting),
+            ("BTC_USDT".to_string(), GateContractMeta::default()),
+        ];
+        // Без монитора опрос ничего не отдаёт
+        assert_eq!(apply_contracts(&mut runtime, &contracts), 0);
+
+        runtime.set_delisting(DelistingConfig::default());
+        for symbol in ["LUNA_USDT", "BTC_USDT"] {
+            for tick in ScenarioBuilder::new(symbol, 1.0).spread_pct(0.1).flat(200).build() {
+                runtime.on_tick(tick);
+            }
+        }
+        assert_eq!(apply_contracts(&mut runtime, &contracts), 1);
+        let monitor = runtime.delisting().unwrap();
+        assert_eq!(monitor.last_price(Symbol::new("LUNA_USDT")), Some(1.0));
+        runtime.shutdown();
+    }
+}
@@ -0,0 +1,424 @@
+//! A/B эксперименты: варианты конфига одной стратегии делят капитал
+//!
+//! Эксперимент раскладывает символы по вариантам (`ExperimentSplit::Symbols`: доля
+//! варианта - доля символов, непересекающиеся подмножества) или чередует варианты
+//! по окнам времени (`TimeWindows`: доля - доля окон). Фабрика `Experiment::factory`
+//! создаёт стратегии символа по фабрике выбранного варианта и запоминает, какой вариант
+//! торгует символ. Сделки закрытых позиций (`record_trade`) приписываются этому варианту.
+//!
+//! В режиме окон фабрика вызывается только при создании стратегий, поэтому насос на
+//! смене окна берёт `Experiment::stale_symbols` и пересоздаёт их через
+//! `ShardedRuntime::recreate`; символы с открытой позицией доторговывают старым вариантом.
+//!
+//! `report` сравнивает средний PnL сделки лидера и ближайшего соперника t-тестом Уэлча.
+//! Победитель объявляется, когда у обоих не меньше `min_trades` сделок и разница значима
+//! на уровне `confidence`. p-value берётся по нормальному приближению, поэтому
+//! `min_trades` меньше 30 не принимается.
+
+use super::shard::{StrategyFactory, StrategySet};
+use crate::base_classes::symbol::Symbol;
+use crate::error::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+/// Меньше сделок - нормальное приближение t-распределения слишком грубое
+pub const MIN_TRADES_FLOOR: usize = 30;
+
+/// Доля окна в последовательности: золотое сечение раскладывает окна по вариантам
+/// равномерно в любом отрезке времени, а не блоками
+const GOLDEN: f64 = 0.618_033_988_749_895;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariantSpec {
+    pub name: String,
+    /// Доля капитала (символов или окон); доли нормируются на сумму
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ExperimentSplit {
+    /// Каждый символ всё время торгует один вариант
+    Symbols,
+    /// Все символы торгуют вариант текущего окна
+    TimeWindows { window_ms: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    pub name: String,
+    pub variants: Vec<VariantSpec>,
+    pub split: ExperimentSplit,
+    /// Сделок у каждого из сравниваемых вариантов до вывода о победителе
+    #[serde(default = "default_min_trades")]
+    pub min_trades: usize,
+    /// Уровень значимости разницы, например 0.95
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+}
+
+fn default_min_trades() -> usize {
+    MIN_TRADES_FLOOR
+}
+
+fn default_confidence() -> f64 {
+    0.95
+}
+
+impl ExperimentConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key: &str, reason: String| ConfigError::Invalid { key: format!("experiment.{}", key), reason };
+        if self.variants.len() < 2 {
+            return Err(invalid("variants", format!("need at least 2 variants, got {}", self.variants.len())));
+        }
+        for variant in &self.variants {
+            if !(variant.share > 0.0 && variant.share.is_finite()) {
+                return Err(invalid("variants", format!("{} share must be positive, got {}", variant.name, variant.share)));
+            }
+            if self.variants.iter().filter(|other| other.name == variant.name).count() > 1 {
+                return Err(invalid("variants", format!("duplicate variant {}", variant.name)));
+            }
+        }
+        if let ExperimentSplit::TimeWindows { window_ms: 0 } = self.split {
+            return Err(invalid("split.window_ms", "must be positive".into()));
+        }
+        if self.min_trades < MIN_TRADES_FLOOR {
+            return Err(invalid("min_trades", format!("must be at least {}, got {}", MIN_TRADES_FLOOR, self.min_trades)));
+        }
+        if !(self.confidence > 0.5 && self.confidence < 1.0) {
+            return Err(invalid("confidence", format!("must be in (0.5, 1), got {}", self.confidence)));
+        }
+        Ok(())
+    }
+
+    /// Вариант по точке в [0, 1) и накопленным долям
+    fn pick(&self, point: f64) -> usize {
+        let total: f64 = self.variants.iter().map(|v| v.share).sum();
+        let mut edge = 0.0;
+        for (idx, variant) in self.variants.iter().enumerate() {
+            edge += variant.share / total;
+            if point < edge {
+                return idx;
+            }
+        }
+        self.variants.len() - 1
+    }
+
+    /// Вариант символа в момент `now_ms`. Раскладка по символам стабильна между
+    /// запусками, но своя у каждого эксперимента
+    pub fn variant_for(&self, symbol: Symbol, now_ms: u64) -> usize {
+        match self.split {
+            ExperimentSplit::Symbols => {
+                let mut hasher = DefaultHasher::new();
+                self.name.hash(&mut hasher);
+                symbol.as_str().hash(&mut hasher);
+                self.pick((hasher.finish() >> 11) as f64 / (1u64 << 53) as f64)
+            }
+            ExperimentSplit::TimeWindows { window_ms } => self.pick((((now_ms / window_ms) as f64) * GOLDEN).fract()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Variant {
+    symbols: usize,
+    pnls: Vec<f64>,
+}
+
+#[derive(Debug)]
+struct ExperimentState {
+    /// Символ -> вариант, с которым созданы его стратегии
+    assigned: HashMap<Symbol, usize>,
+    variants: Vec<Variant>,
+}
+
+/// Эксперимент, общий для насоса и фабрики в потоках шардов: клон делит те же данные
+#[derive(Debug, Clone)]
+pub struct Experiment {
+    config: Arc<ExperimentConfig>,
+    state: Arc<RwLock<ExperimentState>>,
+}
+
+impl Experiment {
+    pub fn new(config: ExperimentConfig) -> Result<Self, ConfigError> {
+        config.validate()?;
+        let variants = config.variants.iter().map(|_| Variant::default()).collect();
+        Ok(Self {
+            config: Arc::new(config),
+            state: Arc::new(RwLock::new(ExperimentState { assigned: HashMap::new(), variants })),
+        })
+    }
+
+    pub fn config(&self) -> &ExperimentConfig {
+        &self.config
+    }
+
+    /// Оборачивает фабрики вариантов (в порядке `variants`): стратегии символа создаёт
+    /// фабрика варианта, выбранного на момент создания
+    pub fn factory(&self, variants: Vec<StrategyFactory>) -> StrategyFactory {
+        assert_eq!(
+            variants.len(),
+            self.config.variants.len(),
+            "experiment {} needs one factory per variant",
+            self.config.name
+        );
+        let experiment = self.clone();
+        Arc::new(move |symbol: Symbol| -> StrategySet {
+            let idx = experiment.assign(symbol, now_ms());
+            variants[idx](symbol)
+        })
+    }
+
+    fn assign(&self, symbol: Symbol, now_ms: u64) -> usize {
+        let idx = self.config.variant_for(symbol, now_ms);
+        let mut state = self.state.write().expect("experiment lock poisoned");
+        if let Some(previous) = state.assigned.insert(symbol, idx) {
+            state.variants[previous].symbols -= 1;
+        }
+        state.variants[idx].symbols += 1;
+        log::debug!("experiment {}: {} trades {}", self.config.name, symbol, self.config.variants[idx].name);
+        idx
+    }
+
+    /// Вариант, с которым сейчас созданы стратегии символа
+    pub fn assigned(&self, symbol: Symbol) -> Option<usize> {
+        let state = self.state.read().expect("experiment lock poisoned");
+        state.assigned.get(&symbol).copied()
+    }
+
+    /// Символы, чей вариант к `now_ms` сменился (только режим окон): их стратегии
+    /// пора пересоздать
+    pub fn stale_symbols(&self, now_ms: u64) -> Vec<Symbol> {
+        if self.config.split == ExperimentSplit::Symbols {
+            return Vec::new();
+        }
+        let state = self.state.read().expect("experiment lock poisoned");
+        let mut stale: Vec<Symbol> = state
+            .assigned
+            .iter()
+            .filter(|(symbol, idx)| self.config.variant_for(**symbol, now_ms) != **idx)
+            .map(|(symbol, _)| *symbol)
+            .collect();
+        stale.sort();
+        stale
+    }
+
+    /// PnL закрытой сделки символа; символ без варианта в эксперименте не участвует
+    pub fn record_trade(&self, symbol: Symbol, pnl: f64) -> bool {
+        let mut state = self.state.write().expect("experiment lock poisoned");
+        let Some(&idx) = state.assigned.get(&symbol) else {
+            return false;
+        };
+        state.variants[idx].pnls.push(pnl);
+        true
+    }
+
+    pub fn report(&self) -> ExperimentReport {
+        let state = self.state.read().expect("experiment lock poisoned");
+        let variants: Vec<VariantReport> = self
+            .config
+            .variants
+            .iter()
+            .zip(&state.variants)
+            .map(|(spec, variant)| VariantReport::new(spec, variant))
+            .collect();
+        let mut ranked: Vec<usize> = (0..variants.len()).collect();
+        ranked.sort_by(|&a, &b| variants[b].mean_pnl.total_cmp(&variants[a].mean_pnl));
+        let (leader, runner_up) = (ranked[0], ranked[1]);
+        let comparison = welch(&variants[leader], &variants[runner_up]);
+        let enough = variants[leader].trades >= self.config.min_trades && variants[runner_up].trades >= self.config.min_trades;
+        let winner = comparison
+            .filter(|c| enough && c.p_value <= 1.0 - self.config.confidence)
+            .map(|_| variants[leader].name.clone());
+        ExperimentReport {
+            experiment: self.config.name.clone(),
+            leader: variants[leader].name.clone(),
+            runner_up: variants[runner_up].name.clone(),
+            t_stat: comparison.map(|c| c.t_stat),
+            p_value: comparison.map(|c| c.p_value),
+            winner,
+            variants,
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis().max(0) as u64
+}
+
+/// Итог варианта: сделки, PnL и его разброс
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariantReport {
+    pub name: String,
+    pub share: f64,
+    /// Символов, торгующих вариант сейчас
+    pub symbols: usize,
+    pub trades: usize,
+    pub total_pnl: f64,
+    pub mean_pnl: f64,
+    /// Выборочное стандартное отклонение PnL сделки
+    pub stddev: f64,
+    pub win_rate: f64,
+}
+
+impl VariantReport {
+    fn new(spec: &VariantSpec, variant: &Variant) -> Self {
+        let trades = variant.pnls.len();
+        let total_pnl: f64 = variant.pnls.iter().sum();
+        let mean_pnl = if trades > 0 { total_pnl / trades as f64 } else { 0.0 };
+        let stddev = if trades > 1 {
+            let sq: f64 = variant.pnls.iter().map(|p| (p - mean_pnl).powi(2)).sum();
+            (sq / (trades - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let wins = variant.pnls.iter().filter(|p| **p > 0.0).count();
+        Self {
+            name: spec.name.clone(),
+            share: spec.share,
+            symbols: variant.symbols,
+            trades,
+            total_pnl,
+            mean_pnl,
+            stddev,
+            win_rate: if trades > 0 { wins as f64 / trades as f64 } else { 0.0 },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExperimentReport {
+    pub experiment: String,
+    pub variants: Vec<VariantReport>,
+    /// Вариант с лучшим средним PnL сделки и ближайший к нему
+    pub leader: String,
+    pub runner_up: String,
+    pub t_stat: Option<f64>,
+    /// Двусторонний p-value разницы средних лидера и соперника
+    pub p_value: Option<f64>,
+    /// Лидер, если разница значима и сделок хватает
+    pub winner: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Comparison {
+    t_stat: f64,
+    p_value: f64,
+}
+
+/// t-тест Уэлча; без разброса (меньше двух сделок, одинаковый PnL) сравнивать нечего
+fn welch(a: &VariantReport, b: &VariantReport) -> Option<Comparison> {
+    if a.trades < 2 || b.trades < 2 {
+        return None;
+    }
+    let se = (a.stddev.powi(2) / a.trades as f64 + b.stddev.powi(2) / b.trades as f64).sqrt();
+    if se <= 0.0 || !se.is_finite() {
+        return None;
+    }
+    let t_stat = (a.mean_pnl - b.mean_pnl) / se;
+    Some(Comparison { t_stat, p_value: 2.0 * (1.0 - normal_cdf(t_stat.abs())) })
+}
+
+/// Функция распределения N(0, 1) через erf (Абрамовиц-Стиган 7.1.26, ошибка < 1.5e-7)
+fn normal_cdf(x: f64) -> f64 {
+    let z = x / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * z.abs());
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    0.5 * (1.0 + erf.copysign(z))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::strategy_adapter::{HookAdapter, MStrikeAdapter, StrategyAdapter};
+
+    fn config(split: ExperimentSplit) -> ExperimentConfig {
+        ExperimentConfig {
+            name: "hook_depth".into(),
+            variants: vec![
+                VariantSpec { name: "control".into(), share: 0.7 },
+                VariantSpec { name: "deeper".into(), share: 0.3 },
+            ],
+            split,
+            min_trades: 30,
+            confidence: 0.95,
+        }
+    }
+
+    #[test]
+    fn test_split_respects_shares_and_names_significant_winner() {
+        let symbols: Vec<Symbol> = (0..1_000).map(|i| Symbol::new(&format!("T{}_USDT", i))).collect();
+        let experiment = Experiment::new(config(ExperimentSplit::Symbols)).unwrap();
+        for &symbol in &symbols {
+            experiment.assign(symbol, 0);
+        }
+        let factory = experiment.factory(vec![
+            Arc::new(|_| vec![Box::new(HookAdapter::default()) as Box<dyn StrategyAdapter + Send>]),
+            Arc::new(|_| vec![Box::new(MStrikeAdapter::default()) as Box<dyn StrategyAdapter + Send>]),
+        ]);
+        for &symbol in &symbols[..50] {
+            let expected = if experiment.assigned(symbol) == Some(0) { "Hook" } else { "MStrike" };
+            assert_eq!(factory(symbol)[0].get_name(), expected);
+        }
+        let report = experiment.report();
+        assert_eq!(report.variants[0].symbols + report.variants[1].symbols, 1_000);
+        assert!((650..=750).contains(&report.variants[0].symbols), "{}", report.variants[0].symbols);
+        // Раскладка по символам не зависит от времени
+        assert!(experiment.stale_symbols(u64::MAX).is_empty());
+        assert_eq!(experiment.config().variant_for(symbols[0], 0), experiment.assigned(symbols[0]).unwrap());
+
+        let control = *symbols.iter().find(|s| experiment.assigned(**s) == Some(0)).unwrap();
+        let deeper = *symbols.iter().find(|s| experiment.assigned(**s) == Some(1)).unwrap();
+        for i in 0..20 {
+            let noise = if i % 2 == 0 { 0.5 } else { -0.5 };
+            experiment.record_trade(control, 0.1 + noise);
+            experiment.record_trade(deeper, 0.6 + noise);
+        }
+        assert!(!experiment.record_trade(Symbol::new("UNKNOWN_USDT"), 1.0));
+        // Лидер ясен, но сделок меньше min_trades
+        let report = experiment.report();
+        assert_eq!(report.leader, "deeper");
+        assert!(report.p_value.unwrap() < 0.05);
+        assert_eq!(report.winner, None);
+
+        for i in 0..20 {
+            let noise = if i % 2 == 0 { 0.5 } else { -0.5 };
+            experiment.record_trade(control, 0.1 + noise);
+            experiment.record_trade(deeper, 0.6 + noise);
+        }
+        let report = experiment.report();
+        assert_eq!(report.winner.as_deref(), Some("deeper"));
+        assert_eq!(report.variants[1].trades, 40);
+        assert!((report.variants[1].mean_pnl - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_time_windows_rotate_variants_and_flag_stale_symbols() {
+        let experiment = Experiment::new(config(ExperimentSplit::TimeWindows { window_ms: 1_000 })).unwrap();
+        let windows: Vec<usize> = (0..1_000u64).map(|w| experiment.config().variant_for(Symbol::new("ETH_USDT"), w * 1_000)).collect();
+        let control = windows.iter().filter(|v| **v == 0).count();
+        assert!((680..=720).contains(&control), "{}", control);
+        // Оба варианта встречаются уже на первых окнах, а не блоками
+        assert!(windows[..5].contains(&0) && windows[..5].contains(&1));
+
+        let symbol = Symbol::new("ETH_USDT");
+        let first = experiment.assign(symbol, 0);
+        let switch = (0..1_000u64).find(|w| windows[*w as usize] != first).unwrap() * 1_000;
+        assert!(experiment.stale_symbols(0).is_empty());
+        assert_eq!(experiment.stale_symbols(switch), vec![symbol]);
+        experiment.assign(symbol, switch);
+        assert!(experiment.stale_symbols(switch).is_empty());
+        assert_eq!(experiment.report().variants.iter().map(|v| v.symbols).sum::<usize>(), 1);
+
+        let mut bad = config(ExperimentSplit::Symbols);
+        bad.min_trades = 5;
+        assert!(Experiment::new(bad).is_err());
+        bad = config(ExperimentSplit::TimeWindows { window_ms: 0 });
+        assert_eq!(bad.validate().unwrap_err().to_string(), "experiment.split.window_ms: must be positive");
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+    }
+}
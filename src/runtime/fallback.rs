@@ -0,0 +1,243 @@
+//! Резервный REST-опрос символов, чей поток устарел
+//!
+//! Пока `StaleWatchdog` держит символ устаревшим, шард не пускает его входы, но открытая
+//! позиция без тиков остаётся без стопов, panic-проверок и выходов. `RestFallback` раз
+//! в `poll_interval_ms` тянет по каждому устаревшему символу сделки и верх стакана Gate
+//! и отдаёт их шарду через `ShardedRuntime::on_fallback_tick`: стратегии ведут позицию
+//! по ним, а символ остаётся устаревшим - входы держатся, стопы расширены. REST-тики
+//! сторож не видит, поэтому вернуть символ может только живой поток.
+//!
+//! Опрос редкий и последовательный: лимит REST Gate общий с ордерами, поэтому за один
+//! проход опрашивается не больше `max_symbols` символов, остальные - на следующих.
+//! Сделки, уже отданные шарду, отсекаются по id.
+
+use super::ShardedRuntime;
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use crate::exchanges::endpoints::GateioGet;
+use crate::exchanges::gate::{fetch_book_top, fetch_trades, GateBookTop, GateTrade};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FallbackConfig {
+    /// Как часто опрашивается один символ, мс
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Символов за один проход `poll`
+    #[serde(default = "default_max_symbols")]
+    pub max_symbols: usize,
+    #[serde(default = "default_base")]
+    pub base_url: String,
+}
+
+fn default_poll_interval_ms() -> u64 {
+    5_000
+}
+
+fn default_max_symbols() -> usize {
+    5
+}
+
+fn default_base() -> String {
+    GateioGet::BASE.to_string()
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: default_poll_interval_ms(),
+            max_symbols: default_max_symbols(),
+            base_url: default_base(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FallbackReport {
+    pub symbols: usize,
+    pub ticks: usize,
+    /// Символы, по которым опрос не удался, и причина
+    pub failed: Vec<(Symbol, String)>,
+}
+
+/// Докуда символ уже отдан шарду
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    last_id: u64,
+    /// Время последней отданной сделки (или начала опроса), мс
+    last_ms: i64,
+    polled_at: Instant,
+}
+
+#[derive(Debug)]
+pub struct RestFallback {
+    config: FallbackConfig,
+    cursors: HashMap<Symbol, Cursor>,
+}
+
+impl RestFallback {
+    pub fn new(config: FallbackConfig) -> Self {
+        assert!(config.poll_interval_ms > 0, "fallback poll_interval_ms must be positive");
+        assert!(config.max_symbols > 0, "fallback max_symbols must be positive");
+        Self { config, cursors: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &FallbackConfig {
+        &self.config
+    }
+
+    /// Устаревшие символы, которым пора опроса. Вернувшиеся в поток символы забываются:
+    /// если поток снова замолчит, опрос начнётся с нового окна
+    pub fn due(&mut self, stale: &[Symbol], now: Instant) -> Vec<Symbol> {
+        self.cursors.retain(|symbol, _| stale.contains(symbol));
+        let interval = Duration::from_millis(self.config.poll_interval_ms);
+        let mut due: Vec<(Option<Instant>, Symbol)> = stale
+            .iter()
+            .map(|symbol| (self.cursors.get(symbol).map(|cursor| cursor.polled_at), *symbol))
+            .filter(|(polled_at, _)| polled_at.is_none_or(|at| now.duration_since(at) >= interval))
+            .collect();
+        // Дольше всех не опрошенные - первыми
+        due.sort();
+        due.into_iter().take(self.config.max_symbols).map(|(_, symbol)| symbol).collect()
+    }
+
+    /// С какого момента (unix секунды) тянуть сделки символа
+    pub fn since_secs(&self, symbol: Symbol, now: DateTime<Utc>) -> i64 {
+        match self.cursors.get(&symbol) {
+            Some(cursor) => cursor.last_ms / 1_000,
+            None => (now.timestamp_millis() - self.config.poll_interval_ms as i64) / 1_000,
+        }
+    }
+
+    /// Новые сделки опроса в тики со стаканом опроса. Первый опрос символа берёт сделки
+    /// за последний `poll_interval_ms`: поток к этому моменту молчит дольше
+    pub fn to_ticks(
+        &mut self,
+        symbol: Symbol,
+        trades: &[GateTrade],
+        book: Option<GateBookTop>,
+        now: DateTime<Utc>,
+        polled_at: Instant,
+    ) -> Vec<TradeTick> {
+        let window_start = now.timestamp_millis() - self.config.poll_interval_ms as i64;
+        let cursor = self.cursors.entry(symbol).or_insert(Cursor { last_id: 0, last_ms: window_start, polled_at });
+        cursor.polled_at = polled_at;
+        let mut ticks = Vec::new();
+        for trade in trades {
+            if trade.id <= cursor.last_id || trade.time_ms < cursor.last_ms {
+                continue;
+            }
+            let Some(timestamp) = DateTime::from_timestamp_millis(trade.time_ms) else {
+                continue;
+            };
+            cursor.last_id = trade.id;
+            cursor.last_ms = trade.time_ms;
+            ticks.push(TradeTick {
+                timestamp,
+                symbol,
+                price: trade.price,
+                volume: trade.size.abs(),
+                side: if trade.size < 0.0 { TradeSide::Sell } else { TradeSide::Buy },
+                trade_id: trade.id.to_string(),
+                best_bid: book.map(|book| book.bid),
+                best_ask: book.map(|book| book.ask),
+            });
+        }
+        ticks
+    }
+
+    /// Опрашивает устаревшие символы, которым пора, и отдаёт их сделки шардам. Вызывать
+    /// из потока насоса, как и `ShardedRuntime::poll_stale`
+    pub async fn poll(&mut self, runtime: &mut ShardedRuntime, client: &reqwest::Client) -> FallbackReport {
+        let mut report = FallbackReport::default();
+        let Some(watchdog) = runtime.stale_watchdog() else {
+            return report;
+        };
+        let stale = watchdog.stale_symbols();
+        for symbol in self.due(&stale, Instant::now()) {
+            let now = Utc::now();
+            let polled_at = Instant::now();
+            let from = self.since_secs(symbol, now);
+            let trades = match fetch_trades(client, &self.config.base_url, symbol.as_str(), from, now.timestamp() + 1).await {
+                Ok(trades) => trades,
+                Err(err) => {
+                    log::error!("REST fallback for stale {} failed, position runs blind: {:#}", symbol, err);
+                    // Окно не сдвигается, следующий опрос - через интервал
+                    self.to_ticks(symbol, &[], None, now, polled_at);
+                    report.failed.push((symbol, format!("{:#}", err)));
+                    continue;
+                }
+            };
+            let book = match fetch_book_top(client, &self.config.base_url, symbol.as_str()).await {
+                Ok(book) => Some(book),
+                Err(err) => {
+                    log::warn!("REST fallback book for {} failed, ticks go without quotes: {:#}", symbol, err);
+                    None
+                }
+            };
+            let ticks = self.to_ticks(symbol, &trades, book, now, polled_at);
+            report.symbols += 1;
+            for tick in ticks {
+                if runtime.on_fallback_tick(tick) {
+                    report.ticks += 1;
+                }
+            }
+        }
+        if report.symbols > 0 || !report.failed.is_empty() {
+            log::warn!(
+                "REST fallback: {} stale symbols polled, {} ticks, {} failed",
+                report.symbols,
+                report.ticks,
+                report.failed.len()
+            );
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: u64, time_ms: i64, price: f64, size: f64) -> GateTrade {
+        GateTrade { id, time_ms, price, size }
+    }
+
+    #[test]
+    fn test_polls_stale_symbols_in_turn_and_skips_seen_trades() {
+        let (eth, btc, sol) = (Symbol::new("ETH_USDT"), Symbol::new("BTC_USDT"), Symbol::new("SOL_USDT"));
+        let mut fallback = RestFallback::new(FallbackConfig { max_symbols: 2, ..FallbackConfig::default() });
+        let start = Instant::now();
+        let now = DateTime::from_timestamp_millis(1_700_000_010_000).unwrap();
+
+        let due = fallback.due(&[eth, btc, sol], start);
+        assert_eq!(due.len(), 2);
+        let book = GateBookTop { bid: 99.9, ask: 100.1 };
+        // Сделка старше окна первого опроса - до разрыва, её шард уже видел
+        let trades = [trade(1, 1_700_000_000_000, 99.0, 1.0), trade(2, 1_700_000_008_000, 100.0, -2.0)];
+        let ticks = fallback.to_ticks(due[0], &trades, Some(book), now, start);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!((ticks[0].side, ticks[0].volume, ticks[0].best_bid), (TradeSide::Sell, 2.0, Some(99.9)));
+        fallback.to_ticks(due[1], &[], None, now, start);
+        assert_eq!(fallback.since_secs(due[0], now), 1_700_000_008);
+
+        // Третий символ ждал дольше всех; опрошенные ждут интервала
+        let later = start + Duration::from_millis(1_000);
+        let third = fallback.due(&[eth, btc, sol], later);
+        assert_eq!(third.len(), 1);
+        assert!(!due.contains(&third[0]));
+
+        let again = start + Duration::from_millis(5_000);
+        assert!(fallback.due(&[eth, btc, sol], again).contains(&due[0]));
+        let trades = [trade(2, 1_700_000_008_000, 100.0, -2.0), trade(3, 1_700_000_012_000, 101.0, 1.0)];
+        let ticks = fallback.to_ticks(due[0], &trades, None, now, again);
+        assert_eq!(ticks.iter().map(|t| t.trade_id.as_str()).collect::<Vec<_>>(), ["3"]);
+
+        // Символ вернулся в поток: окно забыто
+        fallback.due(&[], again);
+        assert_eq!(fallback.since_secs(due[0], now), 1_700_000_005);
+    }
+}
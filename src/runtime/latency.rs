@@ -0,0 +1,295 @@
+//! Задержки пути тик -> сигнал -> отправка -> подтверждение биржи
+//!
+//! Метки ставятся там, где событие происходит: приём тика в насосе, решение стратегии
+//! в шарде, отправка и подтверждение в роутере. Все метки - `Instant`, поэтому
+//! разница между потоками корректна и не зависит от коррекции системных часов.
+//! Распределения копятся в лог-линейных гистограммах (8 корзин на октаву, ~12% точности)
+//! без аллокаций на записи.
+
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// Метки действия до его выхода из шарда
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionTiming {
+    /// Приём тика насосом (для склеенного тика - самого раннего из склеенных),
+    /// для действия после фила - момент, когда шард взял фил
+    pub received: Instant,
+    /// Стратегия вернула действие
+    pub signalled: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatencyStage {
+    /// Очередь шарда + DeltaCalculator + стратегия
+    TickToSignal,
+    /// Исходящее кольцо + роутер + исполнитель
+    SignalToSubmit,
+    /// Сеть и биржа до первого ответа по ордеру
+    SubmitToAck,
+    /// Весь путь
+    TickToAck,
+}
+
+impl LatencyStage {
+    pub const ALL: [LatencyStage; 4] = [
+        Self::TickToSignal,
+        Self::SignalToSubmit,
+        Self::SubmitToAck,
+        Self::TickToAck,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TickToSignal => "tick_to_signal",
+            Self::SignalToSubmit => "signal_to_submit",
+            Self::SubmitToAck => "submit_to_ack",
+            Self::TickToAck => "tick_to_ack",
+        }
+    }
+}
+
+const SUB_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+const BUCKETS: usize = (64 - SUB_BITS as usize + 1) * SUB_BUCKETS;
+
+/// Гистограмма наносекунд с фиксированным числом корзин
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    counts: Box<[u64; BUCKETS]>,
+    count: u64,
+    sum_ns: u128,
+    min_ns: u64,
+    max_ns: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            counts: Box::new([0; BUCKETS]),
+            count: 0,
+            sum_ns: 0,
+            min_ns: u64::MAX,
+            max_ns: 0,
+        }
+    }
+}
+
+fn bucket_of(ns: u64) -> usize {
+    if ns < SUB_BUCKETS as u64 {
+        return ns as usize;
+    }
+    let exp = 63 - ns.leading_zeros();
+    let sub = (ns >> (exp - SUB_BITS)) as usize & (SUB_BUCKETS - 1);
+    (exp - SUB_BITS + 1) as usize * SUB_BUCKETS + sub
+}
+
+/// Верхняя граница корзины (включительно)
+fn bucket_upper(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let exp = (index / SUB_BUCKETS) as u32 + SUB_BITS - 1;
+    let sub = (index % SUB_BUCKETS) as u64;
+    let shift = exp - SUB_BITS;
+    let lower = (SUB_BUCKETS as u64 + sub) << shift;
+    lower.saturating_add((1u64 << shift) - 1)
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, elapsed: Duration) {
+        let ns = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.counts[bucket_of(ns)] += 1;
+        self.count += 1;
+        self.sum_ns += ns as u128;
+        self.min_ns = self.min_ns.min(ns);
+        self.max_ns = self.max_ns.max(ns);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> Duration {
+        Duration::from_nanos(u64::try_from(self.sum_ns).unwrap_or(u64::MAX))
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos(self.min_ns))
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos(self.max_ns))
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos((self.sum_ns / self.count as u128) as u64))
+    }
+
+    /// Квантиль `q` в [0, 1]: верхняя граница корзины, не больше наблюдённого максимума
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_nanos(bucket_upper(index).min(self.max_ns)));
+            }
+        }
+        self.max()
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (mine, theirs) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *mine += theirs;
+        }
+        self.count += other.count;
+        self.sum_ns += other.sum_ns;
+        self.min_ns = self.min_ns.min(other.min_ns);
+        self.max_ns = self.max_ns.max(other.max_ns);
+    }
+}
+
+/// Сводка стадии для дашборда и логов
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageSummary {
+    pub stage: LatencyStage,
+    pub count: u64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+    pub max: Duration,
+}
+
+const QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 0.999];
+
+/// Гистограммы по стадиям; живёт в потоке роутера
+#[derive(Debug, Clone, Default)]
+pub struct LatencyRecorder {
+    stages: [LatencyHistogram; 4],
+}
+
+impl LatencyRecorder {
+    pub fn record(&mut self, stage: LatencyStage, elapsed: Duration) {
+        self.stages[stage as usize].record(elapsed);
+    }
+
+    /// Отрезки, известные на момент отправки
+    pub fn record_submit(&mut self, timing: &ActionTiming, submitted: Instant) {
+        self.record(LatencyStage::TickToSignal, timing.signalled.duration_since(timing.received));
+        self.record(LatencyStage::SignalToSubmit, submitted.duration_since(timing.signalled));
+    }
+
+    /// Отрезки, известные после первого ответа биржи
+    pub fn record_ack(&mut self, timing: &ActionTiming, submitted: Instant, acked: Instant) {
+        self.record(LatencyStage::SubmitToAck, acked.duration_since(submitted));
+        self.record(LatencyStage::TickToAck, acked.duration_since(timing.received));
+    }
+
+    pub fn histogram(&self, stage: LatencyStage) -> &LatencyHistogram {
+        &self.stages[stage as usize]
+    }
+
+    pub fn summary(&self) -> Vec<StageSummary> {
+        LatencyStage::ALL
+            .iter()
+            .filter_map(|&stage| {
+                let hist = self.histogram(stage);
+                Some(StageSummary {
+                    stage,
+                    count: hist.count(),
+                    p50: hist.quantile(0.5)?,
+                    p90: hist.quantile(0.9)?,
+                    p99: hist.quantile(0.99)?,
+                    p999: hist.quantile(0.999)?,
+                    max: hist.max()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Prometheus text format: summary `{name}_seconds` с меткой `stage`
+    pub fn render_prometheus(&self, name: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP {name}_seconds Order path latency by stage");
+        let _ = writeln!(out, "# TYPE {name}_seconds summary");
+        for stage in LatencyStage::ALL {
+            let hist = self.histogram(stage);
+            for q in QUANTILES {
+                let value = hist.quantile(q).map(|d| d.as_secs_f64()).unwrap_or(f64::NAN);
+                let _ = writeln!(
+                    out,
+                    "{name}_seconds{{stage=\"{}\",quantile=\"{}\"}} {}",
+                    stage.name(),
+                    q,
+                    value
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{name}_seconds_sum{{stage=\"{}\"}} {}",
+                stage.name(),
+                hist.sum().as_secs_f64()
+            );
+            let _ = writeln!(out, "{name}_seconds_count{{stage=\"{}\"}} {}", stage.name(), hist.count());
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_cover_values_within_precision() {
+        for ns in [0u64, 7, 8, 9, 15, 16, 1_000, 123_456, 10_000_000_000, u64::MAX] {
+            let index = bucket_of(ns);
+            assert!(index < BUCKETS);
+            let upper = bucket_upper(index);
+            assert!(upper >= ns, "{} above its bucket bound {}", ns, upper);
+            assert!(upper - ns <= ns / SUB_BUCKETS as u64, "bucket too wide for {}", ns);
+        }
+    }
+
+    #[test]
+    fn test_quantiles_follow_distribution() {
+        let mut hist = LatencyHistogram::default();
+        for us in 1..=1_000u64 {
+            hist.record(Duration::from_micros(us));
+        }
+        let p50 = hist.quantile(0.5).unwrap().as_micros() as f64;
+        let p99 = hist.quantile(0.99).unwrap().as_micros() as f64;
+        assert!((500.0..=570.0).contains(&p50), "p50 {}", p50);
+        assert!((990.0..=1_000.0).contains(&p99), "p99 {}", p99);
+        assert_eq!(hist.max(), Some(Duration::from_micros(1_000)));
+        assert_eq!(hist.quantile(1.0), hist.max());
+        assert!(LatencyHistogram::default().quantile(0.5).is_none());
+    }
+
+    #[test]
+    fn test_recorder_splits_path_into_stages() {
+        let received = Instant::now();
+        let timing = ActionTiming {
+            received,
+            signalled: received + Duration::from_micros(20),
+        };
+        let mut recorder = LatencyRecorder::default();
+        recorder.record_submit(&timing, received + Duration::from_micros(50));
+        recorder.record_ack(&timing, received + Duration::from_micros(50), received + Duration::from_millis(3));
+
+        let summary = recorder.summary();
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary[0].max, Duration::from_micros(20));
+        assert_eq!(summary[1].max, Duration::from_micros(30));
+        assert_eq!(summary[3].max, Duration::from_millis(3));
+
+        let text = recorder.render_prometheus("router_latency");
+        assert!(text.contains("router_latency_seconds_count{stage=\"submit_to_ack\"} 1"));
+        assert!(text.contains("# TYPE router_latency_seconds summary"));
+    }
+}
@@ -0,0 +1,1544 @@
+//! Рантайм: символы шардированы по рабочим потокам
+//!
+//! У каждого шарда свои стратегии и свой реестр дельт (DeltaCalculator на символ),
+//! поэтому на тиковом пути нет общих локов. Тики уходят в шард, а действия стратегий -
+//! в `OrderRouter`, через SPSC-кольца из `base_classes::ring_buffer`.
+
+pub mod shard;
+pub mod router;
+pub mod backpressure;
+pub mod shutdown;
+pub mod orders;
+pub mod latency;
+pub mod arbiter;
+pub mod redis;
+pub mod archive;
+pub mod warmup;
+pub mod venue;
+pub mod delisting;
+pub mod universe;
+pub mod symbol_lists;
+pub mod ticker_stats;
+pub mod timer;
+pub mod readiness;
+pub mod shadow;
+pub mod experiment;
+pub mod stale;
+pub mod fallback;
+pub mod audit;
+
+pub use shard::{
+    shard_for, FillEvent, PlacedEvent, RoutedAction, ShardCommand, ShardStats, StrategyFactory, StrategySet,
+};
+pub use router::OrderRouter;
+pub use orders::{AckTiming, Applied, OrderState, OrderTable, PendingReplace, ReplaceOutcome, TrackedOrder};
+pub use arbiter::{ArbiterMode, DetectionArbiter, DetectionPolicy};
+pub use latency::{ActionTiming, LatencyHistogram, LatencyRecorder, LatencyStage, StageSummary};
+pub use archive::{ArchiveConfig, ArchiveHandle, ArchiveSink, EventArchiver};
+pub use warmup::{warm_start, WarmupConfig, WarmupReport};
+pub use delisting::{
+    ContractStatusPoll, DelistAction, DelistReason, DelistingConfig, DelistingMonitor, DelistingNotice,
+};
+pub use symbol_lists::{filter_factory, ListChange, ListCommand, SymbolListStore, SymbolLists};
+pub use ticker_stats::{TickerFilter, TickerStats24h, TickerStatsCache};
+pub use timer::TimerService;
+pub use readiness::{ReadinessBoard, StrategyReadiness, WarmupTracker};
+pub use shadow::{ShadowBoard, ShadowPnl};
+pub use stale::{StaleChange, StaleCheck, StaleConfig, StaleWatchdog};
+pub use fallback::{FallbackConfig, FallbackReport, RestFallback};
+pub use audit::{diff_values, ChangeOrigin, ChangeSource, ConfigAudit, ConfigChange};
+pub use experiment::{
+    Experiment, ExperimentConfig, ExperimentReport, ExperimentSplit, VariantReport, VariantSpec,
+};
+pub use universe::{RankBy, SymbolStats, UniverseChange, UniverseConfig, UniverseScanner};
+pub use venue::{VenueChoice, VenueFees, VenueQuote, VenueReason, VenueRoutingConfig, VenueSelector};
+pub use redis::{BotEvent, RedisBridge, RedisConfig, RedisPublisher, RedisStats};
+pub use backpressure::{merge_tick, BackpressurePolicy, PumpStats};
+pub use shutdown::{
+    EntryGate, ShutdownConfig, ShutdownCoordinator, ShutdownOrders, ShutdownPhase, ShutdownReport,
+    ShutdownStep,
+};
+
+use crate::backtest::market::TradeTick;
+use crate::backtest::tick_guard::{TickGuard, TickGuardConfig, TickGuardStats};
+use crate::base_classes::liquidations::Liquidation;
+use crate::base_classes::ring_buffer::Producer;
+use crate::base_classes::symbol::Symbol;
+use crate::execution::close::{PartialClose, PositionCloser};
+use crate::risk::{RiskEvent, RiskEventBus};
+use backpressure::Coalescer;
+use chrono::{DateTime, Utc};
+use shadow::ShadowSetup;
+use shard::{SharedRiskEvents, ShardSetup, ShardWorker, ROUTER_OUTBOX, SHARD_CONTROL, SHARD_INBOX};
+use std::collections::HashSet;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct RuntimeConfig {
+    /// Число рабочих потоков-шардов
+    pub shards: usize,
+    /// Поведение насоса, когда шард не успевает разбирать тики
+    pub backpressure: BackpressurePolicy,
+    /// Что делать, когда несколько стратегий символа входят на одном событии
+    pub detection: DetectionPolicy,
+    /// Период `StrategyAdapter::on_timer`, мс (0 - без таймера)
+    pub timer_interval_ms: u64,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        // Один поток оставляем под market-data насос и роутер
+        let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(2);
+        Self {
+            shards: cores.saturating_sub(1).max(1),
+            backpressure: BackpressurePolicy::Coalesce {
+                high_water: SHARD_INBOX / 4,
+            },
+            detection: DetectionPolicy::default(),
+            timer_interval_ms: 250,
+        }
+    }
+}
+
+/// Входная сторона рантайма: раздаёт тики по шардам. Владеет единственными
+/// продюсерами входящих колец, поэтому живёт в потоке market-data насоса.
+pub struct ShardedRuntime {
+    inboxes: Vec<Producer<ShardCommand, SHARD_INBOX>>,
+    workers: Vec<JoinHandle<ShardStats>>,
+    backpressure: BackpressurePolicy,
+    held: Vec<Coalescer>,
+    pump_stats: Vec<PumpStats>,
+    redis: Option<RedisPublisher>,
+    archive: Option<ArchiveHandle>,
+    /// Символы, снятые с торгов: их тики не доходят до шардов
+    blacklist: HashSet<Symbol>,
+    /// Набор торгуемых символов; None - все символы насоса
+    universe: Option<HashSet<Symbol>>,
+    /// Битые тики отсекаются до Redis и шардов; архив пишет поток как есть
+    tick_guard: TickGuard,
+    timer: TimerService,
+    readiness: ReadinessBoard,
+    shadow: ShadowBoard,
+    /// Сторож тишины символов и его период проверки
+    stale: Option<(StaleWatchdog, TimerService)>,
+    /// Общая с шардами: они публикуют OrphanFill
+    events: SharedRiskEvents,
+    /// Монитор делистинга и его период проверки
+    delisting: Option<(DelistingMonitor, TimerService)>,
+}
+
+impl ShardedRuntime {
+    /// Запускает потоки шардов и возвращает роутер, читающий их действия
+    pub fn start(config: RuntimeConfig, factory: StrategyFactory) -> (Self, OrderRouter) {
+        Self::launch(config, factory, ShadowSetup::default())
+    }
+
+    /// Как `start`, плюс теневые копии из `shadows` (обычно те же стратегии с правкой
+    /// конфига) на каждом символе: они видят тот же поток, но торгуют только в симуляторе,
+    /// а их PnL "что было бы" копится в `ShardedRuntime::shadow`
+    pub fn start_with_shadows(
+        config: RuntimeConfig,
+        factory: StrategyFactory,
+        shadows: StrategyFactory,
+    ) -> (Self, OrderRouter) {
+        Self::launch(config, factory, ShadowSetup { factory: Some(shadows), board: ShadowBoard::default() })
+    }
+
+    fn launch(config: RuntimeConfig, factory: StrategyFactory, shadow: ShadowSetup) -> (Self, OrderRouter) {
+        assert!(config.shards > 0, "runtime needs at least one shard");
+        if let Some(high_water) = config.backpressure.high_water() {
+            assert!(
+                high_water > 0 && high_water < SHARD_INBOX - 1,
+                "high_water must be in 1..{}, got {}",
+                SHARD_INBOX - 1,
+                high_water
+            );
+        }
+        config.detection.validate();
+        let mut inboxes = Vec::with_capacity(config.shards);
+        let mut workers = Vec::with_capacity(config.shards);
+        let mut outboxes = Vec::with_capacity(config.shards);
+        let mut controls = Vec::with_capacity(config.shards);
+        let readiness = ReadinessBoard::default();
+        let events = SharedRiskEvents::default();
+
+        for id in 0..config.shards {
+            let (inbox_tx, inbox_rx) = Producer::<ShardCommand, SHARD_INBOX>::new_pair();
+            let (control_tx, control_rx) = Producer::<ShardCommand, SHARD_CONTROL>::new_pair();
+            let (outbox_tx, outbox_rx) = Producer::<RoutedAction, ROUTER_OUTBOX>::new_pair();
+            let setup = ShardSetup {
+                id,
+                factory: factory.clone(),
+                detection: config.detection.clone(),
+                readiness: readiness.clone(),
+                shadow: shadow.clone(),
+                events: events.clone(),
+            };
+            let worker = ShardWorker::new(
+                setup,
+                inbox_rx,
+                control_rx,
+                outbox_tx,
+            );
+            let handle = thread::Builder::new()
+                .name(format!("shard-{}", id))
+                .spawn(move || worker.run())
+                .expect("failed to spawn shard thread");
+            inboxes.push(inbox_tx);
+            workers.push(handle);
+            outboxes.push(outbox_rx);
+            controls.push(control_tx);
+        }
+
+        let runtime = Self {
+            inboxes,
+            workers,
+            backpressure: config.backpressure,
+            held: (0..config.shards).map(|_| Coalescer::default()).collect(),
+            pump_stats: vec![PumpStats::default(); config.shards],
+            redis: None,
+            archive: None,
+            blacklist: HashSet::new(),
+            universe: None,
+            tick_guard: TickGuard::default(),
+            timer: TimerService::new(config.timer_interval_ms, Instant::now()),
+            readiness,
+            shadow: shadow.board,
+            stale: None,
+            events,
+            delisting: None,
+        };
+        (runtime, OrderRouter::new(outboxes, controls))
+    }
+
+    pub fn shards(&self) -> usize {
+        self.inboxes.len()
+    }
+
+    pub fn shard_of(&self, symbol: Symbol) -> usize {
+        shard_for(symbol, self.inboxes.len())
+    }
+
+    /// Доска прогрева стратегий: кто ещё держит входы и чего ждёт. Клон делит данные
+    /// с шардами, дашборд читает его из своего потока
+    pub fn readiness(&self) -> ReadinessBoard {
+        self.readiness.clone()
+    }
+
+    /// Доска теневых итогов; пустая, если рантайм запущен без теней
+    pub fn shadow(&self) -> ShadowBoard {
+        self.shadow.clone()
+    }
+
+    /// Счётчики насоса по шардам
+    pub fn pump_stats(&self) -> &[PumpStats] {
+        &self.pump_stats
+    }
+
+    /// Прорежённые тики уходят в Redis до шардов, в том числе отброшенные насосом
+    pub fn set_redis(&mut self, publisher: RedisPublisher) {
+        self.redis = Some(publisher);
+    }
+
+    /// Архив получает каждый тик насоса, в том числе отброшенные
+    pub fn set_archive(&mut self, archive: ArchiveHandle) {
+        self.archive = Some(archive);
+    }
+
+    /// Меняет настройки сторожа тиков; история последних цен начинается заново
+    pub fn set_tick_guard(&mut self, config: TickGuardConfig) {
+        self.tick_guard = TickGuard::new(config);
+    }
+
+    pub fn tick_guard_stats(&self) -> TickGuardStats {
+        self.tick_guard.stats()
+    }
+
+    /// Включает сторож тишины символов; проверка - `poll_stale` в цикле насоса
+    pub fn set_stale_watchdog(&mut self, config: StaleConfig) {
+        let timer = TimerService::new(config.check_interval_ms, Instant::now());
+        self.stale = Some((StaleWatchdog::new(config), timer));
+    }
+
+    pub fn stale_watchdog(&self) -> Option<&StaleWatchdog> {
+        self.stale.as_ref().map(|(watchdog, _)| watchdog)
+    }
+
+    /// Шина событий риска: устаревшие и вернувшиеся символы публикуются как SymbolStale,
+    /// филы стратегий, которых нет в шардах, - как OrphanFill
+    pub fn set_risk_events(&mut self, events: RiskEventBus) {
+        *self.events.write().expect("risk events lock poisoned") = Some(events);
+    }
+
+    /// Снимает символ с торгов (делистинг, остановка): стратегии перестают получать его
+    /// тики и больше по нему не действуют. Позицию по символу это не закрывает: монитор
+    /// делистинга зовёт этот метод только после выхода или тревоги, см. `check_delisting`
+    pub fn blacklist(&mut self, symbol: Symbol) {
+        if let Some((watchdog, _)) = &mut self.stale {
+            watchdog.forget(symbol);
+        }
+        if self.blacklist.insert(symbol) {
+            log::warn!("{} blacklisted: ticks no longer reach shard {}", symbol, self.shard_of(symbol));
+        }
+    }
+
+    pub fn is_blacklisted(&self, symbol: Symbol) -> bool {
+        self.blacklist.contains(&symbol)
+    }
+
+    /// Включает монитор делистинга: сделки насоса он видит сам, объявления приходят через
+    /// `on_delisting_notice` (например из `ContractStatusPoll`), проверка - `poll_delisting`
+    pub fn set_delisting(&mut self, config: DelistingConfig) {
+        let timer = TimerService::new(config.check_interval_ms, Instant::now());
+        self.delisting = Some((DelistingMonitor::new(config), timer));
+    }
+
+    pub fn delisting(&self) -> Option<&DelistingMonitor> {
+        self.delisting.as_ref().map(|(monitor, _)| monitor)
+    }
+
+    /// Объявление о делистинге. Без монитора позиции по символу никто не закроет - ошибка в лог
+    pub fn on_delisting_notice(&mut self, notice: DelistingNotice) {
+        match &mut self.delisting {
+            Some((monitor, _)) => monitor.on_notice(notice),
+            None => log::error!(
+                "{} delisting announced ({}) but no delisting monitor is set: positions will not exit",
+                notice.symbol,
+                notice.source
+            ),
+        }
+    }
+
+    /// Верх стакана без сделки (book ticker) - для распознавания остановки торгов
+    pub fn on_book(&mut self, symbol: Symbol, best_bid: Option<f64>, best_ask: Option<f64>) {
+        if let Some((monitor, _)) = &mut self.delisting {
+            monitor.on_book(symbol, best_bid, best_ask);
+        }
+    }
+
+    /// Проверяет делистинги, если прошёл `DelistingConfig::check_interval_ms`. Возвращает
+    /// reduce-only заявки, закрывающие позиции уходящих символов, - отправляет их вызывающий.
+    /// Вызывать из цикла насоса, как и `poll_stale`
+    pub fn poll_delisting(&mut self, router: &OrderRouter, closer: &PositionCloser) -> Vec<PartialClose> {
+        let due = match &mut self.delisting {
+            Some((_, timer)) => timer.due(Instant::now()),
+            None => false,
+        };
+        if !due {
+            return Vec::new();
+        }
+        self.check_delisting(Utc::now(), router, closer)
+    }
+
+    /// Проверка делистингов на момент `now` без оглядки на период. Символ уходит в чёрный
+    /// список, если позиция по нему закрыта (по стриму позиций `router`); если нет - только
+    /// после `RiskEvent::DelistingStranded`, иначе позиция осталась бы без присмотра молча
+    pub fn check_delisting(&mut self, now: DateTime<Utc>, router: &OrderRouter, closer: &PositionCloser) -> Vec<PartialClose> {
+        let Some((monitor, _)) = &mut self.delisting else {
+            return Vec::new();
+        };
+        let mut closes = Vec::new();
+        let mut stranded = Vec::new();
+        let mut leaving = Vec::new();
+        for action in monitor.evaluate(now.timestamp_millis().max(0) as u64) {
+            match action {
+                DelistAction::ExitPositions { symbol, reason } => {
+                    let size = router.position_size(symbol);
+                    if size == 0.0 {
+                        log::warn!("{} {:?}: no open position to exit", symbol, reason);
+                        continue;
+                    }
+                    let close = match monitor.last_price(symbol) {
+                        Some(mark) => router.close_fraction(closer, symbol, 1.0, mark),
+                        None => Err(anyhow::anyhow!("no trade price to size the close")),
+                    };
+                    match close {
+                        Ok(close) => {
+                            log::error!(
+                                "{} {:?}: closing the {} position with reduce-only {:?} {} @ {}",
+                                symbol,
+                                reason,
+                                size,
+                                close.intent.side,
+                                close.intent.size,
+                                close.intent.price
+                            );
+                            closes.push(close);
+                        }
+                        Err(err) => {
+                            log::error!("{} {:?}: cannot close the {} position: {:#}", symbol, reason, size, err);
+                            stranded.push((symbol, size, reason));
+                        }
+                    }
+                }
+                DelistAction::Blacklist { symbol, reason } => leaving.push((symbol, reason)),
+            }
+        }
+        for (symbol, reason) in leaving {
+            let size = router.position_size(symbol);
+            if size != 0.0 {
+                log::error!("{} {:?} with a {} position still open: blacklisted, close it by hand", symbol, reason, size);
+                stranded.push((symbol, size, reason));
+            }
+            self.blacklist(symbol);
+        }
+        if let Some(events) = self.events.read().expect("risk events lock poisoned").as_ref() {
+            for (symbol, size, reason) in stranded {
+                events.publish(RiskEvent::DelistingStranded { symbol, size, halted: reason == DelistReason::Halted });
+            }
+        }
+        closes
+    }
+
+    /// Применяет результат скана `UniverseScanner`. Первый вызов включает фильтр по набору:
+    /// с этого момента до шардов доходят только тики добавленных символов. Стратегии
+    /// ушедших символов выселяются из шардов после уже отправленных им тиков.
+    /// Символы с живыми ордерами или открытой позицией в `router` остаются в наборе - их филы
+    /// и выход ведут нынешние стратегии; они возвращаются, чтобы повторить, когда символ
+    /// останется без ордеров и без позиции.
+    pub fn apply_universe(&mut self, change: &UniverseChange, router: &OrderRouter) -> Vec<Symbol> {
+        let resting: HashSet<Symbol> = router.orders().live().map(|(_, order)| order.symbol).collect();
+        let (deferred, leaving): (Vec<Symbol>, Vec<Symbol>) = change
+            .removed
+            .iter()
+            .copied()
+            .partition(|symbol| resting.contains(symbol) || router.position_size(*symbol) != 0.0);
+        for symbol in &deferred {
+            log::warn!("{} has live orders or an open position: it leaves the universe once it is flat", symbol);
+        }
+        let universe = self.universe.get_or_insert_with(HashSet::new);
+        universe.extend(change.added.iter().copied());
+        for symbol in &leaving {
+            universe.remove(symbol);
+        }
+        for symbol in leaving {
+            self.evict(symbol);
+        }
+        deferred
+    }
+
+    /// Удаляет стратегии символа в шарде; следующий тик создаст их заново через фабрику
+    fn evict(&mut self, symbol: Symbol) {
+        let shard = self.shard_of(symbol);
+        if let Some((watchdog, _)) = &mut self.stale {
+            watchdog.forget(symbol);
+        }
+        // Придержанные тики выселенного символа больше не нужны шарду
+        self.pump_stats[shard].out_of_universe += self.held[shard].discard(symbol);
+        self.inboxes[shard].push_spin(ShardCommand::Evict(symbol));
+    }
+
+    /// Пересоздаёт стратегии символов, затронутых правкой `SymbolListStore`
+    pub fn on_lists_changed(&mut self, change: &ListChange, pinned: &HashSet<Symbol>) -> Vec<Symbol> {
+        self.recreate(&change.symbols, pinned)
+    }
+
+    /// Пересоздаёт стратегии символов на следующем тике (новые списки, пересчитанный
+    /// конфиг). Символы из `pinned` (открытая позиция) не трогаются - их филы адресованы
+    /// нынешним стратегиям; они возвращаются, чтобы повторить после закрытия позиции.
+    pub fn recreate(&mut self, symbols: &[Symbol], pinned: &HashSet<Symbol>) -> Vec<Symbol> {
+        let mut deferred = Vec::new();
+        for &symbol in symbols {
+            if pinned.contains(&symbol) {
+                log::warn!("{} has an open position: its strategies are recreated once it is flat", symbol);
+                deferred.push(symbol);
+            } else {
+                self.evict(symbol);
+            }
+        }
+        deferred
+    }
+
+    pub fn in_universe(&self, symbol: Symbol) -> bool {
+        self.universe.as_ref().is_none_or(|universe| universe.contains(&symbol))
+    }
+
+    /// Отдаёт тик шарду символа согласно `BackpressurePolicy`.
+    /// Момент вызова считается приёмом тика для `LatencyStage::TickToSignal`.
+    pub fn on_tick(&mut self, tick: TradeTick) {
+        self.route_tick(tick, true);
+    }
+
+    /// Тик REST-опроса (`RestFallback`) устаревшего символа: идёт в шард, как живой, но
+    /// мимо архива и сторожа тишины - символ остаётся устаревшим. Тик символа, поток
+    /// которого уже вернулся, отбрасывается: false
+    pub fn on_fallback_tick(&mut self, tick: TradeTick) -> bool {
+        let stale = self.stale.as_ref().is_some_and(|(watchdog, _)| watchdog.is_stale(tick.symbol));
+        if !stale {
+            return false;
+        }
+        let shard = self.shard_of(tick.symbol);
+        self.pump_stats[shard].fallback += 1;
+        self.route_tick(tick, false);
+        true
+    }
+
+    /// `from_stream` - тик живого потока: пишется в архив и отмечается сторожем тишины
+    fn route_tick(&mut self, tick: TradeTick, from_stream: bool) {
+        let received = Instant::now();
+        if from_stream && let Some(archive) = &self.archive {
+            archive.on_tick(&tick);
+        }
+        let shard = self.shard_of(tick.symbol);
+        if self.tick_guard.check(&tick).is_err() {
+            self.pump_stats[shard].rejected += 1;
+            return;
+        }
+        if let Some(redis) = &mut self.redis {
+            redis.on_tick(&tick);
+        }
+        if !self.blacklist.is_empty() && self.blacklist.contains(&tick.symbol) {
+            self.pump_stats[shard].blacklisted += 1;
+            return;
+        }
+        if !self.in_universe(tick.symbol) {
+            self.pump_stats[shard].out_of_universe += 1;
+            return;
+        }
+        // Тик, отброшенный насосом, тоже значит, что поток символа жив
+        let recovered = match &mut self.stale {
+            Some((watchdog, _)) if from_stream => watchdog.on_tick(tick.symbol, Utc::now().timestamp_millis() as u64),
+            _ => None,
+        };
+        if let Some((monitor, _)) = &mut self.delisting {
+            let ts_ms = tick.timestamp.timestamp_millis().max(0) as u64;
+            monitor.on_trade(tick.symbol, ts_ms, tick.price, tick.best_bid, tick.best_ask);
+        }
+        let backlog = self.inboxes[shard].len();
+        let stats = &mut self.pump_stats[shard];
+        stats.max_backlog = stats.max_backlog.max(backlog);
+
+        match self.backpressure {
+            BackpressurePolicy::Block => {
+                self.inboxes[shard].push_spin(ShardCommand::Tick(tick, received));
+                stats.forwarded += 1;
+            }
+            BackpressurePolicy::Drop { high_water } => {
+                if backlog >= high_water {
+                    stats.dropped += 1;
+                } else {
+                    self.inboxes[shard].push_spin(ShardCommand::Tick(tick, received));
+                    stats.forwarded += 1;
+                }
+            }
+            BackpressurePolicy::Coalesce { high_water } => {
+                if backlog < high_water {
+                    self.release_held(shard);
+                }
+                // Пока по символу есть придержанный тик, новые склеиваются в него,
+                // иначе шард увидел бы тики символа не по порядку
+                if backlog >= high_water || self.held[shard].holds(tick.symbol) {
+                    if self.held[shard].hold(tick, received) {
+                        self.pump_stats[shard].coalesced += 1;
+                    }
+                } else {
+                    self.inboxes[shard].push_spin(ShardCommand::Tick(tick, received));
+                    self.pump_stats[shard].forwarded += 1;
+                }
+            }
+        }
+        // Восстановление - вслед за тиком: тик после разрыва проходит с расширенными стопами
+        if let Some(change) = recovered {
+            self.on_stale_change(change);
+        }
+    }
+
+    /// Проверяет тишину символов, если прошёл `StaleConfig::check_interval_ms`.
+    /// Вызывать из цикла насоса, как и `poll_timers`, в том числе когда тиков нет
+    pub fn poll_stale(&mut self) -> StaleCheck {
+        let due = match &mut self.stale {
+            Some((_, timer)) => timer.due(Instant::now()),
+            None => false,
+        };
+        if !due {
+            return StaleCheck::default();
+        }
+        self.check_stale(Utc::now())
+    }
+
+    /// Проверка тишины на момент `now` без оглядки на период (тесты и насос со своими часами).
+    /// Молчат все символы - это обрыв соединения: символы не помечаются, его разбирает
+    /// переподключение коллектора
+    pub fn check_stale(&mut self, now: DateTime<Utc>) -> StaleCheck {
+        let Some((watchdog, _)) = &mut self.stale else {
+            return StaleCheck::default();
+        };
+        let check = watchdog.check(now.timestamp_millis() as u64);
+        for &change in &check.changes {
+            self.on_stale_change(change);
+        }
+        check
+    }
+
+    fn on_stale_change(&mut self, change: StaleChange) {
+        let Some((watchdog, _)) = &self.stale else {
+            return;
+        };
+        let (symbol, silent_ms, widen_stops_pct) = match change {
+            StaleChange::Stale { symbol, silent_ms } => {
+                let widen = watchdog.config().widen_stops_pct;
+                log::error!(
+                    "{} silent for {} ms while other symbols flow: entries held, stops widened by {}%",
+                    symbol,
+                    silent_ms,
+                    widen
+                );
+                (symbol, silent_ms, Some(widen))
+            }
+            StaleChange::Recovered { symbol, silent_ms } => {
+                log::warn!("{} ticking again after {} ms of silence", symbol, silent_ms);
+                (symbol, silent_ms, None)
+            }
+        };
+        let shard = self.shard_of(symbol);
+        self.inboxes[shard].push_spin(ShardCommand::Stale { symbol, widen_stops_pct });
+        if let Some(events) = self.events.read().expect("risk events lock poisoned").as_ref() {
+            events.publish(RiskEvent::SymbolStale { symbol, silent_ms, stale: widen_stops_pct.is_some() });
+        }
+    }
+
+    /// Отдаёт шарду символа отсчёт открытого интереса (стрим тикеров или опрос
+    /// `contract_stats`); стратегии видят его как `Deltas::delta_oi_5m`.
+    /// Вызывать из потока насоса, как и `on_tick`
+    pub fn on_open_interest(&mut self, symbol: Symbol, at: DateTime<Utc>, open_interest: f64) {
+        if self.blacklist.contains(&symbol) || !self.in_universe(symbol) {
+            return;
+        }
+        let shard = self.shard_of(symbol);
+        self.inboxes[shard].push_spin(ShardCommand::OpenInterest { symbol, at, open_interest });
+    }
+
+    /// Отдаёт шарду символа ликвидацию из ленты биржи (`collectors::*::update_liquidations`).
+    /// Вызывать из потока насоса, как и `on_tick`
+    pub fn on_liquidation(&mut self, liquidation: Liquidation) {
+        let symbol = Symbol::new(&liquidation.symbol);
+        if self.blacklist.contains(&symbol) || !self.in_universe(symbol) {
+            return;
+        }
+        let shard = self.shard_of(symbol);
+        self.inboxes[shard].push_spin(ShardCommand::Liquidation(symbol, liquidation));
+    }
+
+    /// Рассылает таймер шардам, если прошёл `timer_interval_ms`. Вызывать из цикла
+    /// насоса на каждой итерации, в том числе когда тиков нет
+    pub fn poll_timers(&mut self) -> bool {
+        if !self.timer.due(Instant::now()) {
+            return false;
+        }
+        self.on_timer(Utc::now());
+        true
+    }
+
+    /// Таймер на момент `now` во все шарды (тесты и насос со своими часами)
+    pub fn on_timer(&mut self, now: DateTime<Utc>) {
+        for inbox in &mut self.inboxes {
+            inbox.push_spin(ShardCommand::Timer(now));
+        }
+    }
+
+    /// Отдаёт шардам исторические тики прогрева (по возрастанию времени в пределах
+    /// символа). Вызывать до живых тиков символа: после них шард историю отбросит.
+    pub fn warm_up(&mut self, ticks: impl IntoIterator<Item = TradeTick>) {
+        for tick in ticks {
+            let shard = self.shard_of(tick.symbol);
+            self.inboxes[shard].push_spin(ShardCommand::Warmup(tick));
+        }
+    }
+
+    /// Отдаёт придержанные тики шардам, у которых очередь ниже порога.
+    /// Насосу стоит вызывать это, когда биржа замолкает, иначе последний
+    /// склеенный тик символа ждёт следующего тика по его шарду.
+    pub fn flush(&mut self) {
+        let Some(high_water) = self.backpressure.high_water() else {
+            return;
+        };
+        for shard in 0..self.inboxes.len() {
+            if self.inboxes[shard].len() < high_water {
+                self.release_held(shard);
+            }
+        }
+    }
+
+    fn release_held(&mut self, shard: usize) {
+        if self.held[shard].is_empty() {
+            return;
+        }
+        for (tick, received) in self.held[shard].take() {
+            match self.inboxes[shard].try_push(ShardCommand::Tick(tick, received)) {
+                Ok(()) => self.pump_stats[shard].forwarded += 1,
+                Err(ShardCommand::Tick(tick, received)) => {
+                    self.held[shard].hold(tick, received);
+                }
+                Err(other) => unreachable!("pushed a tick, got back {:?}", other),
+            }
+        }
+    }
+
+    /// Останавливает шарды после обработки уже отправленных тиков.
+    /// Роутер должен продолжать `poll` в своём потоке, иначе шард с полным
+    /// исходящим кольцом не сможет завершиться.
+    pub fn shutdown(mut self) -> Vec<ShardStats> {
+        for shard in 0..self.inboxes.len() {
+            for (tick, received) in self.held[shard].take() {
+                self.inboxes[shard].push_spin(ShardCommand::Tick(tick, received));
+                self.pump_stats[shard].forwarded += 1;
+            }
+            self.inboxes[shard].push_spin(ShardCommand::Shutdown);
+        }
+        self.workers
+            .into_iter()
+            .map(|handle| handle.join().expect("shard thread panicked"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::strategy_adapter::{HookAdapter, StrategyAction};
+    use crate::base_classes::types::Side;
+    use crate::strategy::moon_strategies::HookConfig;
+    use crate::testing::ScenarioBuilder;
+    use std::sync::Arc;
+
+    const SYMBOLS: [&str; 6] = ["BTC_USDT", "ETH_USDT", "SOL_USDT", "XRP_USDT", "DOGE_USDT", "ADA_USDT"];
+
+    /// Hook без ожидания отката: buy уходит на тике детекта
+    fn hook() -> HookAdapter {
+        HookAdapter::new(HookConfig { hook_roll_back_wait: 0, ..Default::default() })
+    }
+
+    fn hook_factory() -> StrategyFactory {
+        Arc::new(|_symbol: Symbol| -> StrategySet { vec![Box::new(hook())] })
+    }
+
+    #[test]
+    fn test_shard_for_is_pinned_across_builds() {
+        // FNV-1a: значения не зависят от версии компилятора
+        assert_eq!(shard_for(Symbol::new("BTC_USDT"), 4), 1);
+        assert_eq!(shard_for(Symbol::new("SOL_USDT"), 4), 2);
+        assert_eq!(shard_for(Symbol::new("SOL_USDT"), 2), 0);
+    }
+
+    #[test]
+    fn test_symbols_stay_on_their_shard_and_reach_router() {
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 3, ..Default::default() }, hook_factory());
+        let scenarios: Vec<Vec<TradeTick>> = SYMBOLS
+            .iter()
+            .map(|symbol| {
+                ScenarioBuilder::new(symbol, 100.0)
+                    .flat(1_000)
+                    .crash(10.0, 500)
+                    .flat(500)
+                    .build()
+            })
+            .collect();
+        // Перемежаем символы, как в живом потоке
+        let longest = scenarios.iter().map(Vec::len).max().unwrap();
+        for i in 0..longest {
+            for ticks in &scenarios {
+                if let Some(tick) = ticks.get(i) {
+                    runtime.on_tick(tick.clone());
+                }
+            }
+        }
+        let expected_shards: Vec<usize> = SYMBOLS.iter().map(|s| runtime.shard_of(Symbol::new(s))).collect();
+        let stats = runtime.shutdown();
+
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+
+        let total_ticks: usize = scenarios.iter().map(Vec::len).sum();
+        assert_eq!(stats.iter().map(|s| s.ticks).sum::<u64>(), total_ticks as u64);
+        assert_eq!(stats.iter().map(|s| s.symbols).sum::<usize>(), SYMBOLS.len());
+        assert_eq!(stats.iter().map(|s| s.actions).sum::<u64>(), actions.len() as u64);
+
+        for (symbol, shard) in SYMBOLS.iter().zip(expected_shards) {
+            let mine: Vec<_> = actions.iter().filter(|a| a.symbol == *symbol).collect();
+            assert!(
+                mine.iter().any(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })),
+                "{}: expected a buy, got {:?}",
+                symbol,
+                mine
+            );
+            assert!(mine.iter().all(|a| a.shard == shard && a.strategy_name == "Hook"));
+        }
+    }
+
+    #[test]
+    fn test_fill_is_routed_back_to_owning_shard() {
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 2, ..Default::default() }, hook_factory());
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+
+        // Ждём buy, филим его и проверяем, что шард учёл фил
+        let mut actions = Vec::new();
+        let buy = loop {
+            router.poll(&mut actions, usize::MAX);
+            if let Some(buy) = actions.iter().find_map(|a| match a.action {
+                StrategyAction::PlaceBuy { price, size } => Some((a.strategy, price, size)),
+                _ => None,
+            }) {
+                break buy;
+            }
+            thread::yield_now();
+        };
+        router.fill(FillEvent {
+            symbol: Symbol::new("ETH_USDT"),
+            strategy: buy.0,
+            side: Side::Bid,
+            price: buy.1,
+            size: buy.2,
+        });
+
+        let stats = runtime.shutdown();
+        assert_eq!(stats.iter().map(|s| s.fills).sum::<u64>(), 1);
+        assert_eq!(stats[shard_for(Symbol::new("ETH_USDT"), 2)].fills, 1);
+    }
+
+    #[test]
+    fn test_user_stream_fill_reaches_shard_once() {
+        use crate::execution::user_stream::{OrderUpdate, UserEvent};
+        use crate::execution::{ClientOrderId, ExchangeOrderId, OrderStatus};
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 2, ..Default::default() }, hook_factory());
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        let mut actions = Vec::new();
+        let buy = loop {
+            router.poll(&mut actions, usize::MAX);
+            if let Some(buy) = actions.iter().find(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })) {
+                break buy.clone();
+            }
+            thread::yield_now();
+        };
+        let StrategyAction::PlaceBuy { price, size } = buy.action else { unreachable!() };
+        let id = ClientOrderId::new("t-eth-1");
+        router.submitted(id.clone(), &buy);
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let update = |status, filled| {
+            UserEvent::Order(OrderUpdate {
+                symbol: buy.symbol,
+                client_order_id: Some(id.clone()),
+                exchange_order_id: ExchangeOrderId("1".to_string()),
+                status,
+                side: Side::Bid,
+                price,
+                size,
+                filled,
+                avg_fill_price: Some(price),
+                ts_ms: 0,
+            })
+        };
+        tx.send(update(OrderStatus::New, 0.0)).unwrap();
+        tx.send(update(OrderStatus::Filled, size)).unwrap();
+        tx.send(update(OrderStatus::Filled, size)).unwrap();
+        assert_eq!(router.drain_user_events(&mut rx), 3);
+        assert_eq!(router.orders().get(&id).unwrap().state, OrderState::Filled);
+        // Отправка и первый апдейт стрима дают по одной точке на каждую стадию
+        for stage in LatencyStage::ALL {
+            assert_eq!(router.latency().histogram(stage).count(), 1, "{}", stage.name());
+        }
+
+        let stats = runtime.shutdown();
+        assert_eq!(stats.iter().map(|s| s.fills).sum::<u64>(), 1);
+    }
+
+    #[test]
+    fn test_timer_cancels_resting_entry_in_quiet_market() {
+        use crate::strategy::moon_strategies::HookRearmPolicy;
+
+        let factory: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet {
+            let config = HookConfig {
+                hook_rearm_policy: HookRearmPolicy::AfterSeconds(10.0),
+                hook_roll_back_wait: 0,
+                ..Default::default()
+            };
+            vec![Box::new(HookAdapter::new(config))]
+        });
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 2, ..Default::default() }, factory);
+        let ticks = ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build();
+        let last = ticks.last().unwrap().timestamp;
+        for tick in ticks {
+            runtime.on_tick(tick);
+        }
+        let mut actions = Vec::new();
+        let buy = loop {
+            router.poll(&mut actions, usize::MAX);
+            if let Some(buy) = actions.iter().find(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })) {
+                break buy.clone();
+            }
+            thread::yield_now();
+        };
+        // Отмена адресуется ордеру, под которым роутер отправил buy
+        router.submitted(crate::execution::ClientOrderId::new("t-eth-1"), &buy);
+        let order_id = router.orders().get(&crate::execution::ClientOrderId::new("t-eth-1")).unwrap().handle;
+        // Тиков больше нет: снять стоящий buy может только таймер
+        runtime.on_timer(last + chrono::Duration::seconds(5));
+        runtime.on_timer(last + chrono::Duration::seconds(30));
+        let stats = runtime.shutdown();
+
+        router.poll(&mut actions, usize::MAX);
+        let cancels: Vec<_> = actions.iter().filter(|a| matches!(a.action, StrategyAction::CancelOrder { .. })).collect();
+        assert_eq!(cancels.len(), 1);
+        assert!(matches!(cancels[0].action, StrategyAction::CancelOrder { order_id: id } if id == order_id));
+        assert_eq!(cancels[0].source_time, last + chrono::Duration::seconds(30));
+        assert_eq!(stats.iter().map(|s| s.timers).sum::<u64>(), 4);
+    }
+
+    #[test]
+    fn test_entries_held_until_warmup_requirements_hold() {
+        use crate::backtest::strategy_adapter::MStrikeAdapter;
+        use crate::strategy::moon_strategies::MStrikeConfig;
+
+        // 15-минутная дельта в модификаторе: без 15 минут истории входить нельзя
+        let factory: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet {
+            let config = MStrikeConfig { mstrike_depth: 5.0, mstrike_add_15min_delta: 0.1, ..Default::default() };
+            vec![Box::new(MStrikeAdapter::new(config))]
+        });
+        let run = |warmup: bool| {
+            let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, factory.clone());
+            let ticks = ScenarioBuilder::new("ETH_USDT", 100.0)
+                .step_ms(60_000)
+                .flat(20 * 60_000)
+                .step_ms(100)
+                .flat(1_000)
+                .crash(10.0, 500)
+                .build();
+            let (history, live) = ticks.split_at(20);
+            if warmup {
+                runtime.warm_up(history.to_vec());
+            }
+            for tick in live {
+                runtime.on_tick(tick.clone());
+            }
+            let board = runtime.readiness();
+            let stats = runtime.shutdown();
+            let mut actions = Vec::new();
+            router.poll(&mut actions, usize::MAX);
+            let buys = actions.iter().filter(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })).count();
+            (buys, stats[0].held_entries, board.snapshot())
+        };
+
+        let (buys, held, readiness) = run(false);
+        assert_eq!(buys, 0);
+        assert!(held > 0);
+        assert_eq!(readiness.len(), 1);
+        assert!(!readiness[0].ready);
+        assert_eq!(readiness[0].requirements.min_history_ms, 15 * 60_000);
+
+        let (buys, held, readiness) = run(true);
+        assert!(buys > 0);
+        assert_eq!(held, 0);
+        assert!(readiness[0].ready && readiness[0].missing.is_empty());
+    }
+
+    #[test]
+    fn test_shadow_copies_trade_only_in_simulator() {
+        // Тень с той же настройкой и тень с детектом глубже прострела
+        let shadows: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet {
+            let deep = HookConfig { hook_detect_depth: 50.0, ..Default::default() };
+            vec![Box::new(hook()), Box::new(HookAdapter::new(deep))]
+        });
+        let (mut runtime, mut router) =
+            ShardedRuntime::start_with_shadows(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory(), shadows);
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .flat(1_000)
+            .crash(10.0, 500)
+            .flat(500)
+            .ramp_to(100.0, 6_000)
+            .flat(500)
+            .build();
+        for tick in ticks {
+            runtime.on_tick(tick);
+        }
+        let board = runtime.shadow();
+        runtime.shutdown();
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+
+        // В роутер ушли только действия живой стратегии
+        assert!(actions.iter().all(|a| a.strategy == 0));
+        assert_eq!(actions.iter().filter(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })).count(), 1);
+
+        let shadow = board.snapshot();
+        assert_eq!(shadow.len(), 2);
+        assert_eq!((shadow[0].entries, shadow[0].round_trips), (1, 1));
+        assert!(shadow[0].realized_pnl > 0.0);
+        assert_eq!((shadow[1].entries, shadow[1].realized_pnl), (0, 0.0));
+
+        let (runtime, _router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        assert!(runtime.shadow().snapshot().is_empty());
+        runtime.shutdown();
+    }
+
+    #[test]
+    fn test_strategy_time_in_force_reaches_router() {
+        use crate::execution::TimeInForce;
+
+        let factory: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet {
+            // Ключи конфига как у Gate: GTX - это post-only
+            let config = HookConfig {
+                hook_buy_tif: serde_json::from_str("\"gtx\"").unwrap(),
+                hook_roll_back_wait: 0,
+                ..Default::default()
+            };
+            vec![Box::new(HookAdapter::new(config))]
+        });
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, factory);
+        for tick in ScenarioBuilder::new("SOL_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        runtime.shutdown();
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+        let buys: Vec<_> = actions.iter().filter(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })).collect();
+        assert!(!buys.is_empty());
+        assert!(buys.iter().all(|a| a.tif == TimeInForce::PostOnly));
+        assert!(actions.iter().filter(|a| !matches!(a.action, StrategyAction::PlaceBuy { .. })).all(|a| a.tif == TimeInForce::Gtc));
+    }
+
+    #[test]
+    fn test_cancel_replace_accounts_for_raced_fills() {
+        use crate::execution::user_stream::{OrderUpdate, UserEvent};
+        use crate::execution::{ClientOrderId, ExchangeOrderId, OrderStatus};
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        let mut actions = Vec::new();
+        let buy = loop {
+            router.poll(&mut actions, usize::MAX);
+            if let Some(buy) = actions.iter().find(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })) {
+                break buy.clone();
+            }
+            thread::yield_now();
+        };
+        let StrategyAction::PlaceBuy { price, size } = buy.action else { unreachable!() };
+        let mut replacement = buy.clone();
+        replacement.action = StrategyAction::PlaceBuy { price: price * 0.99, size };
+        let update = |id: &ClientOrderId, exchange: &str, status, filled| {
+            UserEvent::Order(OrderUpdate {
+                symbol: buy.symbol,
+                client_order_id: Some(id.clone()),
+                exchange_order_id: ExchangeOrderId(exchange.to_string()),
+                status,
+                side: Side::Bid,
+                price,
+                size,
+                filled,
+                avg_fill_price: Some(price),
+                ts_ms: 0,
+            })
+        };
+
+        // Четверть исполнилась, пока летела отмена: замена уходит на остаток и один раз
+        let first = ClientOrderId::new("t-eth-1");
+        router.submitted(first.clone(), &buy);
+        router.on_user_event(update(&first, "1", OrderStatus::New, 0.0));
+        router.cancel_replace(&first, replacement.clone()).unwrap();
+        assert!(router.cancel_replace(&first, replacement.clone()).is_err());
+        actions.clear();
+        router.poll(&mut actions, usize::MAX);
+        assert!(actions.iter().all(|a| !matches!(a.action, StrategyAction::PlaceBuy { price: p, .. } if p < price)));
+        router.on_user_event(update(&first, "1", OrderStatus::PartiallyFilled, size * 0.25));
+        router.on_user_event(update(&first, "1", OrderStatus::Canceled, size * 0.25));
+        router.on_user_event(update(&first, "1", OrderStatus::Canceled, size * 0.25));
+        assert_eq!(router.pending_replaces(), 0);
+        actions.clear();
+        router.poll(&mut actions, usize::MAX);
+        router.poll(&mut actions, usize::MAX);
+        let replaced: Vec<f64> = actions
+            .iter()
+            .filter_map(|a| match a.action {
+                StrategyAction::PlaceBuy { price: p, size } if p < price => Some(size),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(replaced.len(), 1);
+        assert!((replaced[0] - size * 0.75).abs() < 1e-12);
+
+        // Ордер исполнился целиком раньше отмены: замены нет, шард видит весь фил
+        let second = ClientOrderId::new("t-eth-2");
+        router.submitted(second.clone(), &buy);
+        router.cancel_replace(&second, replacement.clone()).unwrap();
+        router.on_user_event(update(&second, "2", OrderStatus::Filled, size));
+        actions.clear();
+        router.poll(&mut actions, usize::MAX);
+        assert!(actions.iter().all(|a| !matches!(a.action, StrategyAction::PlaceBuy { price: p, .. } if p < price)));
+        let mut not_placement = buy.clone();
+        not_placement.action = StrategyAction::NoAction;
+        assert!(router.cancel_replace(&second, not_placement).is_err());
+
+        let stats = runtime.shutdown();
+        assert_eq!(stats.iter().map(|s| s.fills).sum::<u64>(), 2);
+    }
+
+    #[test]
+    fn test_circuit_breaker_blocks_entries_on_rejecting_symbol() {
+        use crate::execution::user_stream::{OrderUpdate, UserEvent};
+        use crate::execution::{ClientOrderId, ExchangeOrderId, OrderStatus};
+        use crate::risk::{CircuitBreakerConfig, SymbolCircuitBreaker};
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        router.set_circuit_breaker(SymbolCircuitBreaker::new(CircuitBreakerConfig {
+            max_rejections: 2,
+            ..Default::default()
+        }));
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        let mut actions = Vec::new();
+        let buy = loop {
+            router.poll(&mut actions, usize::MAX);
+            if let Some(buy) = actions.iter().find(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })) {
+                break buy.clone();
+            }
+            thread::yield_now();
+        };
+
+        // Принятая заявка прерывает серию отказов
+        let ids: Vec<ClientOrderId> = (1..=4).map(|i| ClientOrderId::new(format!("t-eth-{}", i))).collect();
+        for id in &ids {
+            router.submitted(id.clone(), &buy);
+        }
+        router.rejected(&ids[0]);
+        router.acked(&ids[3]);
+        router.rejected(&ids[1]);
+        assert!(!router.circuit_breaker_mut().unwrap().is_tripped(buy.symbol));
+        router.rejected(&ids[2]);
+        assert!(router.circuit_breaker_mut().unwrap().is_tripped(buy.symbol));
+
+        // Замена-вход по выключенному символу не уходит
+        router.cancel_replace(&ids[3], buy.clone()).unwrap();
+        router.on_user_event(UserEvent::Order(OrderUpdate {
+            symbol: buy.symbol,
+            client_order_id: Some(ids[3].clone()),
+            exchange_order_id: ExchangeOrderId("4".to_string()),
+            status: OrderStatus::Canceled,
+            side: Side::Bid,
+            price: 0.0,
+            size: 0.0,
+            filled: 0.0,
+            avg_fill_price: None,
+            ts_ms: 0,
+        }));
+        actions.clear();
+        router.poll(&mut actions, usize::MAX);
+        assert!(actions.iter().all(|a| !matches!(a.action, StrategyAction::PlaceBuy { .. })));
+        assert_eq!(router.breaker_blocked(), 1);
+        runtime.shutdown();
+    }
+
+    #[test]
+    fn test_orders_breaking_venue_rules_are_rejected_before_submit() {
+        use crate::execution::{ComplianceChecker, PercentPrice, TradingRules};
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        // Коридор ±1% от последней сделки: вход Hook в прострел на 10% под него не проходит
+        router.set_compliance(ComplianceChecker::new(TradingRules {
+            percent_price: Some(PercentPrice { multiplier_up: 1.01, multiplier_down: 0.99 }),
+            ..Default::default()
+        }));
+        router.on_reference_price(Symbol::new("ETH_USDT"), 100.0);
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).flat(500).build() {
+            runtime.on_tick(tick);
+        }
+        let stats = runtime.shutdown();
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+
+        assert!(stats[0].actions > 0);
+        assert!(actions.iter().all(|a| !matches!(a.action, StrategyAction::PlaceBuy { .. })));
+        assert!(router.compliance_rejected() > 0);
+        assert_eq!(router.compliance().unwrap().rejected()["percent_price"], router.compliance_rejected());
+    }
+
+    #[test]
+    fn test_liquidation_cascade_blocks_entries() {
+        use crate::risk::{OpenInterestConfig, OpenInterestMonitor};
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        router.set_open_interest_monitor(OpenInterestMonitor::new(OpenInterestConfig::default()));
+        let eth = Symbol::new("ETH_USDT");
+        let now = Utc::now();
+        for (secs_ago, open_interest, price) in [(120, 1_000.0, 100.0), (60, 980.0, 99.0), (0, 950.0, 96.0)] {
+            let at = now - chrono::Duration::seconds(secs_ago);
+            router.on_open_interest(eth, at, open_interest, price);
+            runtime.on_open_interest(eth, at, open_interest);
+        }
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        // Шарды останавливаются после всех тиков: действия уже в исходящих кольцах
+        runtime.shutdown();
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+        assert!(actions.iter().all(|a| !matches!(a.action, StrategyAction::PlaceBuy { .. })));
+        assert!(router.cascade_blocked() >= 1);
+        let change = router.open_interest_monitor().unwrap().change(eth).unwrap();
+        assert!((change.oi_change_pct + 5.0).abs() < 1e-9);
+    }
+
+    /// Два Hook на символе видят один прострел; возвращает размеры их buy и счётчик отказов
+    fn twin_hook_entries(detection: DetectionPolicy) -> (Vec<(usize, f64)>, u64) {
+        let factory: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet {
+            vec![Box::new(hook()), Box::new(hook())]
+        });
+        let config = RuntimeConfig { shards: 1, detection, ..Default::default() };
+        let (mut runtime, mut router) = ShardedRuntime::start(config, factory);
+        for tick in ScenarioBuilder::new("SOL_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        let stats = runtime.shutdown();
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+        let buys = actions
+            .iter()
+            .filter_map(|a| match a.action {
+                StrategyAction::PlaceBuy { size, .. } => Some((a.strategy, size)),
+                _ => None,
+            })
+            .collect();
+        (buys, stats[0].declined)
+    }
+
+    #[test]
+    fn test_detection_arbiter_keeps_one_entry_per_event() {
+        let (buys, declined) = twin_hook_entries(DetectionPolicy::default());
+        assert_eq!(buys.len(), 1, "{:?}", buys);
+        assert_eq!(buys[0].0, 0);
+        assert_eq!(declined, 1);
+
+        let (buys, declined) = twin_hook_entries(DetectionPolicy::allow_all());
+        assert_eq!((buys.len(), declined), (2, 0));
+
+        let scaled = DetectionPolicy {
+            mode: ArbiterMode::ScaleSize { factor: 0.5 },
+            ..DetectionPolicy::default()
+        };
+        let (buys, _) = twin_hook_entries(scaled);
+        assert_eq!(buys.len(), 2);
+        assert_eq!(buys[1].1, buys[0].1 * 0.5);
+    }
+
+    fn flood(runtime: &mut ShardedRuntime) -> u64 {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .step_ms(1)
+            .choppy(1.0, 50_000, 500)
+            .build();
+        let sent = ticks.len() as u64;
+        for tick in ticks {
+            runtime.on_tick(tick);
+        }
+        sent
+    }
+
+    #[test]
+    fn test_coalesce_accounts_for_every_tick() {
+        let config = RuntimeConfig {
+            shards: 1,
+            backpressure: BackpressurePolicy::Coalesce { high_water: 4 },
+            ..Default::default()
+        };
+        let (mut runtime, _router) = ShardedRuntime::start(config, hook_factory());
+        let sent = flood(&mut runtime);
+        let pump = loop {
+            runtime.flush();
+            let pump = runtime.pump_stats()[0];
+            if pump.forwarded + pump.coalesced == sent {
+                break pump;
+            }
+            thread::yield_now();
+        };
+        assert!(pump.max_backlog <= SHARD_INBOX);
+        assert_eq!(runtime.shutdown()[0].ticks, pump.forwarded);
+    }
+
+    #[test]
+    fn test_drop_policy_counts_dropped_ticks() {
+        let config = RuntimeConfig {
+            shards: 1,
+            backpressure: BackpressurePolicy::Drop { high_water: 4 },
+            ..Default::default()
+        };
+        let (mut runtime, _router) = ShardedRuntime::start(config, hook_factory());
+        let sent = flood(&mut runtime);
+        let pump = runtime.pump_stats()[0];
+        assert_eq!(pump.forwarded + pump.dropped, sent);
+        assert_eq!(runtime.shutdown()[0].ticks, pump.forwarded);
+    }
+    #[test]
+    fn test_blacklisted_symbol_ticks_stop_at_pump() {
+        let (mut runtime, _router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        let btc = Symbol::new("BTC_USDT");
+        runtime.blacklist(btc);
+        assert!(runtime.is_blacklisted(btc));
+        let sent = flood(&mut runtime);
+        assert_eq!(runtime.pump_stats()[0].blacklisted, sent);
+        let stats = runtime.shutdown();
+        assert_eq!((stats[0].ticks, stats[0].symbols), (0, 0));
+    }
+
+    #[test]
+    fn test_universe_admits_added_and_evicts_removed_symbols() {
+        let (mut runtime, router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        let (btc, eth) = (Symbol::new("BTC_USDT"), Symbol::new("ETH_USDT"));
+        runtime.apply_universe(&UniverseChange { added: vec![eth], removed: Vec::new() }, &router);
+        assert!(runtime.in_universe(eth) && !runtime.in_universe(btc));
+        let sent = flood(&mut runtime);
+        assert_eq!(runtime.pump_stats()[0].out_of_universe, sent);
+
+        let eth_ticks = ScenarioBuilder::new("ETH_USDT", 100.0).step_ms(1).flat(100).build();
+        let eth_sent = eth_ticks.len() as u64;
+        for tick in eth_ticks {
+            runtime.on_tick(tick);
+        }
+        assert!(runtime.apply_universe(&UniverseChange { added: vec![btc], removed: vec![eth] }, &router).is_empty());
+        let pump = runtime.pump_stats()[0];
+        // Каждый тик ETH либо дошёл до шарда, либо склеен, либо выброшен при выселении
+        assert_eq!(pump.forwarded + pump.coalesced + pump.out_of_universe - sent, eth_sent);
+        let stats = runtime.shutdown();
+        assert_eq!(stats[0].symbols, 0);
+    }
+
+    #[test]
+    fn test_universe_keeps_symbol_until_orders_settle_and_position_is_flat() {
+        use crate::execution::user_stream::{OrderUpdate, PositionUpdate, UserEvent};
+        use crate::execution::{ClientOrderId, ExchangeOrderId, OrderStatus};
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        let eth = Symbol::new("ETH_USDT");
+        runtime.apply_universe(&UniverseChange { added: vec![eth], removed: Vec::new() }, &router);
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        let mut actions = Vec::new();
+        let buy = loop {
+            router.poll(&mut actions, usize::MAX);
+            if let Some(buy) = actions.iter().find(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })) {
+                break buy.clone();
+            }
+            thread::yield_now();
+        };
+        let id = ClientOrderId::new("t-eth-1");
+        router.submitted(id.clone(), &buy);
+
+        // Buy ещё стоит: символ не выселяется, его фил дойдёт до стратегии
+        let change = UniverseChange { added: Vec::new(), removed: vec![eth] };
+        assert_eq!(runtime.apply_universe(&change, &router), vec![eth]);
+        assert!(runtime.in_universe(eth));
+        let StrategyAction::PlaceBuy { price, size } = buy.action else { unreachable!() };
+        router.on_user_event(UserEvent::Order(OrderUpdate {
+            symbol: eth,
+            client_order_id: Some(id),
+            exchange_order_id: ExchangeOrderId("1".to_string()),
+            status: OrderStatus::Filled,
+            side: Side::Bid,
+            price,
+            size,
+            filled: size,
+            avg_fill_price: Some(price),
+            ts_ms: 0,
+        }));
+        // Ордер исполнен, но позиция открыта: её выход ведёт та же стратегия
+        assert_eq!(runtime.apply_universe(&change, &router), vec![eth]);
+        assert!(runtime.in_universe(eth));
+
+        router.on_user_event(UserEvent::Position(PositionUpdate {
+            symbol: eth,
+            size: 0.0,
+            entry_price: 0.0,
+            liq_price: None,
+            realised_pnl: 0.0,
+            ts_ms: 0,
+        }));
+        assert!(runtime.apply_universe(&change, &router).is_empty());
+        assert!(!runtime.in_universe(eth));
+
+        let stats = runtime.shutdown();
+        assert_eq!((stats[0].fills, stats[0].orphan_fills, stats[0].symbols), (1, 0, 0));
+    }
+
+    #[test]
+    fn test_symbol_lists_gate_strategy_creation() {
+        let path = std::env::temp_dir().join(format!("runtime_lists_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let lists = SymbolListStore::open(&path).unwrap();
+        let api = ChangeOrigin::new(ChangeSource::Api, "test");
+        lists.handle("/whitelist add Hook ETH_USDT SOL_USDT", &api).unwrap();
+        lists.handle("/blacklist add SOL_USDT", &api).unwrap();
+        let factory = filter_factory(hook_factory(), lists.clone());
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, factory);
+        for symbol in ["BTC_USDT", "ETH_USDT", "SOL_USDT"] {
+            for tick in ScenarioBuilder::new(symbol, 100.0).flat(1_000).crash(10.0, 500).build() {
+                runtime.on_tick(tick);
+            }
+        }
+        // Шард создаёт стратегии в своём потоке, поэтому сами списки до остановки не правим
+        let change = ListChange { symbols: vec![Symbol::new("BTC_USDT")], reply: String::new() };
+        let pinned: HashSet<Symbol> = [Symbol::new("BTC_USDT")].into();
+        assert_eq!(runtime.on_lists_changed(&change, &pinned), [Symbol::new("BTC_USDT")]);
+        assert!(runtime.on_lists_changed(&change, &HashSet::new()).is_empty());
+        runtime.shutdown();
+
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+        assert!(!actions.is_empty());
+        assert!(actions.iter().all(|a| a.symbol == "ETH_USDT"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_silent_symbol_holds_entries_until_it_recovers() {
+        use crate::risk::RiskEventKind;
+
+        let (eth, btc) = (Symbol::new("ETH_USDT"), Symbol::new("BTC_USDT"));
+        let config = RuntimeConfig { shards: 1, backpressure: BackpressurePolicy::Block, ..Default::default() };
+        let (mut runtime, mut router) = ShardedRuntime::start(config, hook_factory());
+        let crash = ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build();
+        runtime.set_stale_watchdog(StaleConfig {
+            silent_ms: 100,
+            recovery_ticks: crash.len() as u32 - 2,
+            ..StaleConfig::default()
+        });
+        let bus = RiskEventBus::new();
+        let rx = bus.subscribe("test", &[RiskEventKind::SymbolStale]);
+        runtime.set_risk_events(bus);
+
+        let btc_ticks = ScenarioBuilder::new("BTC_USDT", 100.0).flat(1_000).build();
+        runtime.on_tick(crash[0].clone());
+        runtime.on_tick(btc_ticks[0].clone());
+        thread::sleep(std::time::Duration::from_millis(150));
+        // Молчат оба - обрыв соединения, а не устаревший символ
+        assert!(runtime.check_stale(Utc::now()).outage);
+
+        runtime.on_tick(btc_ticks[1].clone());
+        let check = runtime.check_stale(Utc::now());
+        assert!(matches!(check.changes[..], [StaleChange::Stale { symbol, .. }] if symbol == eth));
+        assert!(runtime.stale_watchdog().unwrap().is_stale(eth));
+        assert!(!runtime.stale_watchdog().unwrap().is_stale(btc));
+
+        // Весь обвал ETH - тики восстановления: вход Hook отклоняется
+        // REST-тик ведёт позицию, но символ не возвращает
+        assert!(runtime.on_fallback_tick(crash[1].clone()));
+        assert!(!runtime.on_fallback_tick(btc_ticks[2].clone()));
+        assert!(runtime.stale_watchdog().unwrap().is_stale(eth));
+        assert_eq!(runtime.pump_stats()[0].fallback, 1);
+
+        let (last, recovering) = crash[2..].split_last().unwrap();
+        for tick in recovering {
+            runtime.on_tick(tick.clone());
+        }
+        assert!(runtime.stale_watchdog().unwrap().is_stale(eth));
+        runtime.on_tick(last.clone());
+        assert!(runtime.stale_watchdog().unwrap().stale_symbols().is_empty());
+        let stats = runtime.shutdown();
+
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+        assert!(!actions.iter().any(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })), "{:?}", actions);
+        assert!(stats[0].stale_entries > 0);
+        let events: Vec<_> = rx.try_iter().map(|envelope| envelope.event.clone()).collect();
+        assert!(matches!(events[..], [
+            RiskEvent::SymbolStale { stale: true, .. },
+            RiskEvent::SymbolStale { stale: false, silent_ms, .. },
+        ] if silent_ms >= 100));
+    }
+
+    #[test]
+    fn test_orphan_fill_alerts_without_stopping_the_shard() {
+        use crate::risk::RiskEventKind;
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        let bus = RiskEventBus::new();
+        let rx = bus.subscribe("test", &[RiskEventKind::OrphanFill]);
+        runtime.set_risk_events(bus);
+
+        // Выселенный символ: стратегий нет, а его ордер исполнился
+        let sol = Symbol::new("SOL_USDT");
+        router.fill(FillEvent { symbol: sol, strategy: 0, side: Side::Bid, price: 20.0, size: 1.0 });
+        let ticks = ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build();
+        let count = ticks.len() as u64;
+        for tick in ticks {
+            runtime.on_tick(tick);
+        }
+        let stats = runtime.shutdown();
+
+        // Шард пережил фил и отработал тики остальных символов
+        assert_eq!(stats[0].orphan_fills, 1);
+        assert_eq!(stats[0].ticks, count);
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+        assert!(actions.iter().any(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })));
+        let events: Vec<_> = rx.try_iter().map(|envelope| envelope.event.clone()).collect();
+        assert!(matches!(events[..], [RiskEvent::OrphanFill { symbol, strategy: 0, .. }] if symbol == sol));
+    }
+
+    #[test]
+    fn test_delisting_closes_position_and_blacklists_only_after_alert() {
+        use crate::execution::close::PositionCloser;
+        use crate::execution::instruments::{ContractType, Instrument, InstrumentRegistry};
+        use crate::execution::money::PricePrecision;
+        use crate::execution::user_stream::{PositionUpdate, UserEvent};
+        use crate::execution::{ClientOrderIdGenerator, TimeInForce, Venue};
+        use crate::risk::RiskEventKind;
+
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, hook_factory());
+        let bus = RiskEventBus::new();
+        let rx = bus.subscribe("test", &[RiskEventKind::DelistingStranded]);
+        runtime.set_risk_events(bus);
+        runtime.set_delisting(DelistingConfig::default());
+
+        let mut instruments = InstrumentRegistry::new();
+        let precision = PricePrecision::from_f64(0.01, 0.001).unwrap();
+        instruments
+            .register(Instrument::new(Venue::Gate, "ETH_USDT", ContractType::LinearPerpetual, precision).unwrap())
+            .unwrap();
+        let ids = ClientOrderIdGenerator::with_session("delist", 1).unwrap();
+        let closer = PositionCloser::new(Venue::Gate, instruments, 20.0, ids).unwrap();
+
+        let (eth, sol) = (Symbol::new("ETH_USDT"), Symbol::new("SOL_USDT"));
+        // Двусторонний стакан: тишина с 2024 года не выглядит остановкой торгов
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).spread_pct(0.02).flat(1_000).build() {
+            runtime.on_tick(tick);
+        }
+        router.on_user_event(UserEvent::Position(PositionUpdate {
+            symbol: eth,
+            size: 2.0,
+            entry_price: 100.0,
+            liq_price: None,
+            realised_pnl: 0.0,
+            ts_ms: 0,
+        }));
+        let now = Utc::now();
+        let delist_at = now + chrono::Duration::hours(1);
+        for symbol in [eth, sol] {
+            runtime.on_delisting_notice(DelistingNotice {
+                symbol,
+                delist_at_ms: Some(delist_at.timestamp_millis() as u64),
+                source: "test".to_string(),
+            });
+        }
+
+        // За час до даты: позиция ETH закрывается reduce-only заявкой, символы ещё торгуются
+        let closes = runtime.check_delisting(now, &router, &closer);
+        assert_eq!(closes.len(), 1);
+        let intent = &closes[0].intent;
+        assert!(intent.reduce_only);
+        assert_eq!((intent.side, intent.tif, intent.size.to_f64()), (Side::Ask, TimeInForce::Ioc, 2.0));
+        assert!(!runtime.is_blacklisted(eth));
+
+        // Закрытие не исполнилось: SOL без позиции уходит тихо, ETH - только вслед за тревогой
+        assert!(runtime.check_delisting(delist_at, &router, &closer).is_empty());
+        assert!(runtime.is_blacklisted(eth) && runtime.is_blacklisted(sol));
+        let events: Vec<_> = rx.try_iter().map(|envelope| envelope.event.clone()).collect();
+        assert!(matches!(events[..], [RiskEvent::DelistingStranded { symbol, size, halted: false }] if symbol == eth && size == 2.0));
+        runtime.shutdown();
+    }
+}
@@ -0,0 +1,437 @@
+//! Машина состояний ордеров роутера
+//!
+//! Submitted -> Open -> PartiallyFilled -> Filled | Cancelled, плюс Rejected, если биржа
+//! не приняла заявку. Исполнение считается по накопленному `filled` из апдейтов ордера:
+//! дельта между апдейтами и есть фил стратегии, поэтому повтор или перестановка апдейтов
+//! не даёт двойного фила. Первый ответ биржи по ордеру фиксирует момент подтверждения.
+//!
+//! MEXC не шлёт отдельный Canceled после частичного исполнения: у неё свой конечный
+//! статус `PartiallyCanceled`. Он ведёт в Cancelled, а исполненная часть приходит в
+//! `filled` того же апдейта.
+
+use super::latency::ActionTiming;
+use super::shard::{FillEvent, RoutedAction};
+use crate::backtest::strategy_adapter::StrategyAction;
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::execution::user_stream::OrderUpdate;
+use crate::execution::{ClientOrderId, ExchangeOrderId, OrderStatus, TimeInForce};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Меньше этого считаем нулём при сравнении накопленных объёмов
+const SIZE_EPS: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// Отправлен, биржа ещё не подтвердила
+    Submitted,
+    Open,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+}
+
+impl OrderState {
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Filled | Self::Cancelled | Self::Rejected)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackedOrder {
+    pub symbol: Symbol,
+    /// Индекс стратегии в наборе символа
+    pub strategy: usize,
+    /// Числовой id ордера для стратегии (`StrategyAdapter::on_order_placed`, `CancelOrder`)
+    pub handle: u64,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+    pub tif: TimeInForce,
+    pub filled: f64,
+    pub avg_fill_price: Option<f64>,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub state: OrderState,
+    pub timing: ActionTiming,
+    pub submitted_at: Instant,
+    /// Первый ответ биржи: ack на отправку или первый апдейт из стрима
+    pub acked_at: Option<Instant>,
+}
+
+/// Метки ордера на момент подтверждения
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AckTiming {
+    pub timing: ActionTiming,
+    pub submitted: Instant,
+    pub acked: Instant,
+}
+
+/// Что изменил апдейт ордера
+#[derive(Debug, Default)]
+pub struct Applied {
+    pub fill: Option<FillEvent>,
+    /// Заполнено, если это первый ответ биржи по ордеру
+    pub ack: Option<AckTiming>,
+    /// Ордер перешёл в Rejected этим апдейтом
+    pub rejected: Option<ClientOrderId>,
+    /// Ордер дошёл до конечного состояния этим апдейтом
+    pub finished: Option<ClientOrderId>,
+}
+
+impl TrackedOrder {
+    fn mark_acked(&mut self, at: Instant) -> Option<AckTiming> {
+        if self.acked_at.is_some() {
+            return None;
+        }
+        self.acked_at = Some(at);
+        Some(AckTiming {
+            timing: self.timing,
+            submitted: self.submitted_at,
+            acked: at,
+        })
+    }
+}
+
+/// Ордера, отправленные роутером, по client id
+#[derive(Debug, Default)]
+pub struct OrderTable {
+    orders: HashMap<ClientOrderId, TrackedOrder>,
+    by_exchange: HashMap<ExchangeOrderId, ClientOrderId>,
+    by_handle: HashMap<u64, ClientOrderId>,
+    /// Последний выданный `TrackedOrder::handle`; 0 стратегии считают неизвестным id
+    last_handle: u64,
+    /// Апдейты по ордерам, которых роутер не отправлял (ручные, от другого процесса)
+    foreign_updates: u64,
+}
+
+impl OrderTable {
+    /// Регистрирует отправленный PlaceBuy/PlaceSell и возвращает его `handle`.
+    /// Остальные действия ордеров не создают.
+    pub fn submit(&mut self, client_order_id: ClientOrderId, action: &RoutedAction, submitted_at: Instant) -> u64 {
+        let (side, price, size) = match action.action {
+            StrategyAction::PlaceBuy { price, size } => (Side::Bid, price, size),
+            StrategyAction::PlaceSell { price, size } => (Side::Ask, price, size),
+            ref other => panic!("{:?} is not an order placement", other),
+        };
+        self.last_handle += 1;
+        self.by_handle.insert(self.last_handle, client_order_id.clone());
+        let previous = self.orders.insert(
+            client_order_id.clone(),
+            TrackedOrder {
+                symbol: action.symbol,
+                strategy: action.strategy,
+                handle: self.last_handle,
+                side,
+                price,
+                size,
+                tif: action.tif,
+                filled: 0.0,
+                avg_fill_price: None,
+                exchange_order_id: None,
+                state: OrderState::Submitted,
+                timing: action.timing,
+                submitted_at,
+                acked_at: None,
+            },
+        );
+        assert!(previous.is_none(), "client order id {} submitted twice", client_order_id);
+        self.last_handle
+    }
+
+    /// Биржа отклонила заявку до появления в стриме; возвращает ордер, если он
+    /// перешёл в Rejected (повтор или отказ по завершённому ордеру - None)
+    pub fn reject(&mut self, client_order_id: &ClientOrderId) -> Option<&TrackedOrder> {
+        let order = self.orders.get_mut(client_order_id)?;
+        if order.state.is_terminal() {
+            return None;
+        }
+        order.state = OrderState::Rejected;
+        Some(order)
+    }
+
+    /// Ack на отправку пришёл раньше стрима; повторный ack ничего не возвращает
+    pub fn ack(&mut self, client_order_id: &ClientOrderId, at: Instant) -> Option<AckTiming> {
+        self.orders.get_mut(client_order_id)?.mark_acked(at)
+    }
+
+    pub fn get(&self, client_order_id: &ClientOrderId) -> Option<&TrackedOrder> {
+        self.orders.get(client_order_id)
+    }
+
+    /// Ордер по id, который стратегия указала в `CancelOrder`
+    pub fn by_handle(&self, handle: u64) -> Option<(&ClientOrderId, &TrackedOrder)> {
+        let client = self.by_handle.get(&handle)?;
+        self.orders.get_key_value(client)
+    }
+
+    /// Ордера, ещё не дошедшие до конечного состояния
+    pub fn live(&self) -> impl Iterator<Item = (&ClientOrderId, &TrackedOrder)> {
+        self.orders.iter().filter(|(_, order)| !order.state.is_terminal())
+    }
+
+    pub fn foreign_updates(&self) -> u64 {
+        self.foreign_updates
+    }
+
+    /// Убирает ордера в конечном состоянии; возвращает сколько убрано
+    pub fn prune_terminal(&mut self) -> usize {
+        let before = self.orders.len();
+        self.orders.retain(|_, order| !order.state.is_terminal());
+        let orders = &self.orders;
+        self.by_exchange.retain(|_, client| orders.contains_key(client));
+        self.by_handle.retain(|_, client| orders.contains_key(client));
+        before - self.orders.len()
+    }
+
+    /// Применяет апдейт ордера: фил, если накопленное исполнение выросло, и метки,
+    /// если это первый ответ биржи по ордеру
+    pub fn apply(&mut self, update: &OrderUpdate) -> Applied {
+        let client = update
+            .client_order_id
+            .clone()
+            .filter(|id| self.orders.contains_key(id))
+            .or_else(|| self.by_exchange.get(&update.exchange_order_id).cloned());
+        let Some(client) = client else {
+            self.foreign_updates += 1;
+            log::debug!("order update for untracked order {:?}", update.exchange_order_id);
+            return Applied::default();
+        };
+        self.by_exchange
+            .insert(update.exchange_order_id.clone(), client.clone());
+        let order = self.orders.get_mut(&client).expect("indexed order exists");
+        order.exchange_order_id = Some(update.exchange_order_id.clone());
+        let ack = order.mark_acked(Instant::now());
+
+        let fill = if update.filled > order.filled + SIZE_EPS {
+            let delta = update.filled - order.filled;
+            // Цена дельты из средних цен до и после: апдейт несёт только среднюю
+            let new_avg = update.avg_fill_price.unwrap_or(order.price);
+            let paid_before = order.avg_fill_price.unwrap_or(0.0) * order.filled;
+            let price = (new_avg * update.filled - paid_before) / delta;
+            order.filled = update.filled;
+            order.avg_fill_price = Some(new_avg);
+            Some(FillEvent {
+                symbol: order.symbol,
+                strategy: order.strategy,
+                side: order.side,
+                price,
+                size: delta,
+            })
+        } else {
+            if update.filled + SIZE_EPS < order.filled {
+                log::warn!(
+                    "order {} update went back in filled size ({} < {}); keeping the larger",
+                    client,
+                    update.filled,
+                    order.filled
+                );
+            }
+            None
+        };
+
+        let mut rejected = None;
+        let mut finished = None;
+        if !order.state.is_terminal() {
+            order.state = match update.status {
+                OrderStatus::New => OrderState::Open,
+                OrderStatus::PartiallyFilled => OrderState::PartiallyFilled,
+                OrderStatus::Filled => OrderState::Filled,
+                OrderStatus::Canceled | OrderStatus::PartiallyCanceled => OrderState::Cancelled,
+                OrderStatus::Rejected => OrderState::Rejected,
+                OrderStatus::Unknown => order.state,
+            };
+            if update.status == OrderStatus::PartiallyCanceled && order.filled <= SIZE_EPS {
+                log::error!(
+                    "order {} cancelled after a partial fill, but no filled size ever arrived; position needs reconciling",
+                    client
+                );
+            }
+            if order.state == OrderState::Rejected {
+                rejected = Some(client.clone());
+            }
+            if order.state.is_terminal() {
+                finished = Some(client);
+            }
+        } else if fill.is_none() {
+            log::debug!("stale update for finished order {}", client);
+        }
+        Applied { fill, ack, rejected, finished }
+    }
+}
+
+/// Замена, ждущая конечного статуса отменяемого ордера
+#[derive(Debug, Clone)]
+pub struct PendingReplace {
+    pub replacement: RoutedAction,
+    /// Исполнено у отменяемого ордера на момент решения о замене
+    pub filled_at_cancel: f64,
+}
+
+/// Чем закончилась замена после конечного статуса отменяемого ордера
+#[derive(Debug, Clone)]
+pub enum ReplaceOutcome {
+    /// Отмена прошла; замена уходит на размер за вычетом исполненного в гонке
+    Released(RoutedAction),
+    /// Пока летела отмена, ордер исполнился целиком или замене ничего не осталось
+    Dropped { raced_fill: f64 },
+}
+
+impl PendingReplace {
+    /// Решение по замене, когда `cancelled` дошёл до конечного состояния
+    pub fn resolve(self, cancelled: &TrackedOrder) -> ReplaceOutcome {
+        debug_assert!(cancelled.state.is_terminal());
+        let raced_fill = (cancelled.filled - self.filled_at_cancel).max(0.0);
+        if cancelled.state == OrderState::Filled {
+            return ReplaceOutcome::Dropped { raced_fill };
+        }
+        let mut replacement = self.replacement;
+        let size = match &mut replacement.action {
+            StrategyAction::PlaceBuy { size, .. } | StrategyAction::PlaceSell { size, .. } => size,
+            other => unreachable!("replacement {:?} checked on registration", other),
+        };
+        *size -= raced_fill;
+        if *size <= SIZE_EPS {
+            return ReplaceOutcome::Dropped { raced_fill };
+        }
+        ReplaceOutcome::Released(replacement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn placed(symbol: Symbol, action: StrategyAction) -> RoutedAction {
+        RoutedAction {
+            shard: 0,
+            symbol,
+            strategy: 1,
+            strategy_name: "test".to_string(),
+            action,
+            tif: TimeInForce::Gtc,
+            source_time: Utc::now(),
+            timing: ActionTiming {
+                received: Instant::now(),
+                signalled: Instant::now(),
+            },
+        }
+    }
+
+    fn update(symbol: Symbol, status: OrderStatus, filled: f64, avg: Option<f64>) -> OrderUpdate {
+        OrderUpdate {
+            symbol,
+            client_order_id: Some(ClientOrderId::new("t-1")),
+            exchange_order_id: ExchangeOrderId("42".to_string()),
+            status,
+            side: Side::Bid,
+            price: 100.0,
+            size: 3.0,
+            filled,
+            avg_fill_price: avg,
+            ts_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_partial_fills_emit_deltas_with_incremental_price() {
+        let symbol = Symbol::new("BTC_USDT");
+        let mut table = OrderTable::default();
+        let id = ClientOrderId::new("t-1");
+        let handle = table.submit(id.clone(), &placed(symbol, StrategyAction::PlaceBuy { price: 100.0, size: 3.0 }), Instant::now());
+        assert_eq!(table.get(&id).unwrap().state, OrderState::Submitted);
+        assert_eq!(table.by_handle(handle).unwrap().0, &id);
+
+        let opened = table.apply(&update(symbol, OrderStatus::New, 0.0, None));
+        assert!(opened.fill.is_none());
+        assert!(opened.ack.is_some());
+        assert_eq!(table.get(&id).unwrap().state, OrderState::Open);
+
+        let partial = table.apply(&update(symbol, OrderStatus::PartiallyFilled, 1.0, Some(100.0)));
+        assert!(partial.ack.is_none());
+        let first = partial.fill.unwrap();
+        assert_eq!((first.strategy, first.side, first.size, first.price), (1, Side::Bid, 1.0, 100.0));
+
+        // Средняя 99 после 3.0 => вторые 2.0 по 98.5
+        let second = table
+            .apply(&update(symbol, OrderStatus::Filled, 3.0, Some(99.0)))
+            .fill
+            .unwrap();
+        assert!((second.size - 2.0).abs() < 1e-12);
+        assert!((second.price - 98.5).abs() < 1e-9);
+        assert_eq!(table.get(&id).unwrap().state, OrderState::Filled);
+
+        // Повтор финального апдейта не даёт второго фила
+        assert!(table.apply(&update(symbol, OrderStatus::Filled, 3.0, Some(99.0))).fill.is_none());
+        assert_eq!(table.prune_terminal(), 1);
+        assert!(table.get(&id).is_none());
+        assert!(table.by_handle(handle).is_none());
+    }
+
+    #[test]
+    fn test_untracked_orders_are_counted_not_applied() {
+        let symbol = Symbol::new("BTC_USDT");
+        let mut table = OrderTable::default();
+        assert!(table.apply(&update(symbol, OrderStatus::Filled, 3.0, Some(100.0))).fill.is_none());
+        assert_eq!(table.foreign_updates(), 1);
+    }
+
+    #[test]
+    fn test_cancel_after_partial_keeps_filled_size() {
+        let symbol = Symbol::new("ETH_USDT");
+        let mut table = OrderTable::default();
+        let id = ClientOrderId::new("t-1");
+        table.submit(id.clone(), &placed(symbol, StrategyAction::PlaceSell { price: 100.0, size: 3.0 }), Instant::now());
+        assert!(table.ack(&id, Instant::now()).is_some());
+        assert!(table.ack(&id, Instant::now()).is_none());
+        let mut partial = update(symbol, OrderStatus::Canceled, 1.0, Some(101.0));
+        // Без client id апдейт находится по exchange id после первого сопоставления
+        table.apply(&update(symbol, OrderStatus::New, 0.0, None));
+        partial.client_order_id = None;
+        let applied = table.apply(&partial);
+        assert!(applied.ack.is_none());
+        let fill = applied.fill.unwrap();
+        assert_eq!(fill.side, Side::Ask);
+        let order = table.get(&id).unwrap();
+        assert_eq!((order.state, order.filled), (OrderState::Cancelled, 1.0));
+        assert_eq!(table.live().count(), 0);
+    }
+
+    #[test]
+    fn test_partially_canceled_is_terminal_with_fill() {
+        let symbol = Symbol::new("MX_USDT");
+        let mut table = OrderTable::default();
+        let id = ClientOrderId::new("t-1");
+        table.submit(id.clone(), &placed(symbol, StrategyAction::PlaceBuy { price: 100.0, size: 3.0 }), Instant::now());
+        table.apply(&update(symbol, OrderStatus::PartiallyFilled, 1.0, Some(100.0)));
+        // Остаток отменён, а в финальном апдейте исполнилось ещё 0.5
+        let applied = table.apply(&update(symbol, OrderStatus::PartiallyCanceled, 1.5, Some(100.0)));
+        assert!((applied.fill.unwrap().size - 0.5).abs() < 1e-12);
+        assert!(applied.rejected.is_none());
+        let order = table.get(&id).unwrap();
+        assert_eq!((order.state, order.filled), (OrderState::Cancelled, 1.5));
+        assert_eq!(table.live().count(), 0);
+    }
+
+    #[test]
+    fn test_rejection_is_reported_once() {
+        let symbol = Symbol::new("BTC_USDT");
+        let mut table = OrderTable::default();
+        let id = ClientOrderId::new("t-1");
+        let buy = placed(symbol, StrategyAction::PlaceBuy { price: 100.0, size: 3.0 });
+        table.submit(id.clone(), &buy, Instant::now());
+        assert!(table.reject(&id).is_some());
+        assert!(table.reject(&id).is_none());
+        // Стрим подтверждает отказ уже после ответа на отправку
+        assert!(table.apply(&update(symbol, OrderStatus::Rejected, 0.0, None)).rejected.is_none());
+
+        let other = ClientOrderId::new("t-2");
+        table.submit(other.clone(), &buy, Instant::now());
+        let mut rejected = update(symbol, OrderStatus::Rejected, 0.0, None);
+        rejected.client_order_id = Some(other.clone());
+        rejected.exchange_order_id = ExchangeOrderId("43".to_string());
+        assert_eq!(table.apply(&rejected).rejected, Some(other));
+    }
+}
@@ -0,0 +1,188 @@
+//! Готовность стратегий после старта: прогрев до первого входа
+//!
+//! Стратегия, созданная на первом тике символа, считает EMA и дельты по паре тиков и
+//! торгует по ним как по полной истории. `StrategyAdapter::warmup_requirements`
+//! задаёт, сколько тиков, какой длины историю и какие индикаторы ей нужно увидеть;
+//! шард считает тики прогрева и живые, а до выполнения требований отклоняет входы
+//! стратегии (`on_buy_declined`). Выход уже открытой позиции не держится.
+//!
+//! Состояние прогрева шарды пишут в общую `ReadinessBoard` при создании стратегий,
+//! при готовности и раз в `PROGRESS_EVERY` тиков прогрева, а не на каждом тике;
+//! дашборд читает её через `ShardedRuntime::readiness`.
+
+use crate::backtest::strategy_adapter::{StrategyAdapter, WarmupRequirements};
+use crate::base_classes::symbol::Symbol;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Как часто прогревающаяся стратегия обновляет прогресс на доске, тиков
+pub const PROGRESS_EVERY: u64 = 256;
+
+/// Счётчик прогрева одной стратегии символа. Готовность защёлкивается
+#[derive(Debug, Clone)]
+pub struct WarmupTracker {
+    requirements: WarmupRequirements,
+    ticks: u64,
+    first_at: Option<DateTime<Utc>>,
+    last_at: Option<DateTime<Utc>>,
+    ready: bool,
+}
+
+impl WarmupTracker {
+    pub fn new(requirements: WarmupRequirements) -> Self {
+        let ready = requirements.is_empty();
+        Self { requirements, ticks: 0, first_at: None, last_at: None, ready }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn history_ms(&self) -> u64 {
+        match (self.first_at, self.last_at) {
+            (Some(first), Some(last)) => (last - first).num_milliseconds().max(0) as u64,
+            _ => 0,
+        }
+    }
+
+    /// Учитывает тик и возвращает true, если стратегия стала готова на нём
+    pub fn observe(&mut self, at: DateTime<Utc>, strategy: &dyn StrategyAdapter) -> bool {
+        if self.ready {
+            return false;
+        }
+        self.ticks += 1;
+        self.first_at.get_or_insert(at);
+        self.last_at = Some(at);
+        self.ready = self.missing(strategy).is_empty();
+        self.ready
+    }
+
+    /// Невыполненные требования: `ticks`, `history` и имена индикаторов
+    pub fn missing(&self, strategy: &dyn StrategyAdapter) -> Vec<&'static str> {
+        if self.ready {
+            return Vec::new();
+        }
+        let mut missing = Vec::new();
+        if self.ticks < self.requirements.min_ticks {
+            missing.push("ticks");
+        }
+        if self.history_ms() < self.requirements.min_history_ms {
+            missing.push("history");
+        }
+        missing.extend(self.requirements.indicators.iter().filter(|name| !strategy.indicator_ready(name)));
+        missing
+    }
+
+    pub fn readiness(&self, symbol: Symbol, strategy: &dyn StrategyAdapter) -> StrategyReadiness {
+        StrategyReadiness {
+            symbol,
+            strategy: strategy.get_name().to_string(),
+            ready: self.ready,
+            ticks: self.ticks,
+            history_ms: self.history_ms(),
+            requirements: self.requirements.clone(),
+            missing: self.missing(strategy),
+        }
+    }
+}
+
+/// Прогрев стратегии символа для дашборда
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StrategyReadiness {
+    pub symbol: Symbol,
+    pub strategy: String,
+    pub ready: bool,
+    pub ticks: u64,
+    pub history_ms: u64,
+    pub requirements: WarmupRequirements,
+    pub missing: Vec<&'static str>,
+}
+
+/// Общая доска готовности: клон делит те же данные. Лок берётся на переходах, не на тиках
+#[derive(Debug, Clone, Default)]
+pub struct ReadinessBoard {
+    entries: Arc<RwLock<HashMap<(Symbol, usize), StrategyReadiness>>>,
+}
+
+impl ReadinessBoard {
+    pub fn set(&self, idx: usize, readiness: StrategyReadiness) {
+        let mut entries = self.entries.write().expect("readiness lock poisoned");
+        entries.insert((readiness.symbol, idx), readiness);
+    }
+
+    /// Стратегии символа выселены из шарда
+    pub fn remove_symbol(&self, symbol: Symbol) {
+        let mut entries = self.entries.write().expect("readiness lock poisoned");
+        entries.retain(|(s, _), _| *s != symbol);
+    }
+
+    /// Все стратегии по символу и порядку в наборе
+    pub fn snapshot(&self) -> Vec<StrategyReadiness> {
+        let entries = self.entries.read().expect("readiness lock poisoned");
+        let mut all: Vec<_> = entries.iter().collect();
+        all.sort_by_key(|((symbol, idx), _)| (*symbol, *idx));
+        all.into_iter().map(|(_, readiness)| readiness.clone()).collect()
+    }
+
+    /// Стратегии, которые ещё держатся прогревом
+    pub fn warming(&self) -> usize {
+        let entries = self.entries.read().expect("readiness lock poisoned");
+        entries.values().filter(|readiness| !readiness.ready).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::strategy_adapter::MStrikeAdapter;
+    use crate::backtest::market::{TradeSide, TradeTick};
+    use crate::strategy::moon_strategies::{mshot::Deltas, MStrikeConfig};
+
+    #[test]
+    fn test_tracker_latches_once_every_requirement_holds() {
+        let config = MStrikeConfig { mstrike_add_15min_delta: 0.5, ..MStrikeConfig::default() };
+        let mut strategy = MStrikeAdapter::new(config);
+        let mut tracker = WarmupTracker::new(strategy.warmup_requirements());
+        let start = Utc::now();
+        let deltas = Deltas::default();
+        let mut ready_at = None;
+        for i in 0..20 {
+            let at = start + chrono::Duration::minutes(i);
+            let tick = TradeTick {
+                timestamp: at,
+                symbol: Symbol::new("ETH_USDT"),
+                price: 100.0,
+                volume: 1.0,
+                side: TradeSide::Buy,
+                trade_id: i.to_string(),
+                best_bid: Some(99.9),
+                best_ask: Some(100.1),
+            };
+            strategy.on_tick(&tick, &deltas);
+            if i == 2 {
+                assert_eq!(tracker.missing(&strategy), vec!["ticks", "history", "last_bid_ema"]);
+            }
+            if tracker.observe(at, &strategy) {
+                ready_at = Some(i);
+            }
+        }
+        // EMA(4) готова на 4-м тике, но 15-минутная дельта ждёт 15 минут истории
+        assert_eq!(ready_at, Some(15));
+        let readiness = tracker.readiness(Symbol::new("ETH_USDT"), &strategy);
+        assert!(readiness.ready && readiness.missing.is_empty());
+        assert_eq!(readiness.ticks, 16);
+
+        let board = ReadinessBoard::default();
+        board.set(0, readiness);
+        assert_eq!(board.warming(), 0);
+        board.remove_symbol(Symbol::new("ETH_USDT"));
+        assert!(board.snapshot().is_empty());
+        assert!(WarmupTracker::new(WarmupRequirements::default()).is_ready());
+    }
+}
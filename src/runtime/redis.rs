@@ -0,0 +1,496 @@
+//! Публикация событий бота в Redis pub/sub
+//!
+//! Сторонние процессы (дашборды, аналитика, другие боты) читают события бота из
+//! Redis, не линкуясь с крейтом. Каналы `{prefix}:ticks` (тики, прорежённые до одного
+//! на символ за `tick_sample_ms`), `{prefix}:signals` (действия стратегий из роутера),
+//! `{prefix}:fills` и `{prefix}:risk` (шина `RiskEventBus`); сообщения - JSON.
+//!
+//! Горячий путь не ждёт Redis: события уходят в ограниченную очередь, которую
+//! разбирает отдельный поток пачками (pipelining). Переполнение очереди и ошибки
+//! соединения не блокируют торговлю - события теряются и считаются в `RedisStats`,
+//! поток переподключается с паузой. Клиент - минимальный RESP (AUTH, SELECT,
+//! PUBLISH), без внешних зависимостей.
+
+use super::shard::{FillEvent, RoutedAction};
+use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::StrategyAction;
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::risk::{RiskEnvelope, RiskEventBus};
+use anyhow::{bail, ensure, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Сколько сообщений уходит в Redis одной пачкой
+const BATCH: usize = 256;
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisConfig {
+    /// `redis://[:password@]host:port[/db]`
+    pub url: String,
+    #[serde(default = "default_prefix")]
+    pub channel_prefix: String,
+    /// Не чаще одного тика символа за столько мс, 0 = тики не публикуются
+    #[serde(default = "default_tick_sample_ms")]
+    pub tick_sample_ms: u64,
+    /// Длина очереди до потока публикации
+    #[serde(default = "default_queue")]
+    pub queue: usize,
+}
+
+fn default_prefix() -> String {
+    "bot".to_string()
+}
+
+fn default_tick_sample_ms() -> u64 {
+    1_000
+}
+
+fn default_queue() -> usize {
+    8_192
+}
+
+/// Событие в канале; `type` - вид события
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotEvent {
+    Tick { symbol: String, ts: DateTime<Utc>, price: f64, volume: f64 },
+    Signal { symbol: String, strategy: String, ts: DateTime<Utc>, action: String, price: Option<f64>, size: Option<f64> },
+    Fill { symbol: String, strategy: usize, ts: DateTime<Utc>, side: String, price: f64, size: f64 },
+    Risk { seq: u64, ts: DateTime<Utc>, kind: String, detail: String },
+}
+
+#[derive(Debug, Default)]
+pub struct RedisStats {
+    pub published: AtomicU64,
+    /// Потеряно: очередь полна, нет соединения или ошибка записи
+    pub dropped: AtomicU64,
+    pub reconnects: AtomicU64,
+}
+
+enum Command {
+    Publish { channel: Arc<str>, payload: String },
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RedisUrl {
+    addr: String,
+    password: Option<String>,
+    db: u32,
+}
+
+fn parse_url(url: &str) -> Result<RedisUrl> {
+    let rest = url.strip_prefix("redis://").with_context(|| format!("redis url {} must start with redis://", url))?;
+    let (auth, rest) = match rest.rsplit_once('@') {
+        Some((auth, rest)) => (Some(auth), rest),
+        None => (None, rest),
+    };
+    let password = auth.map(|auth| auth.split_once(':').map_or(auth, |(_, password)| password).to_string());
+    let (addr, db) = match rest.split_once('/') {
+        Some((addr, "")) => (addr, 0),
+        Some((addr, db)) => (addr, db.parse().with_context(|| format!("redis db {:?}", db))?),
+        None => (rest, 0),
+    };
+    ensure!(!addr.is_empty(), "redis url {} has no host", url);
+    let addr = if addr.contains(':') { addr.to_string() } else { format!("{}:6379", addr) };
+    Ok(RedisUrl { addr, password: password.filter(|p| !p.is_empty()), db })
+}
+
+/// RESP-массив из bulk-строк
+fn encode(out: &mut Vec<u8>, args: &[&[u8]]) {
+    out.extend_from_slice(format!("*{}\r\n", args.len()).as_bytes());
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg);
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+struct Connection {
+    writer: TcpStream,
+    reader: BufReader<TcpStream>,
+    line: String,
+}
+
+impl Connection {
+    fn open(url: &RedisUrl) -> Result<Self> {
+        let stream = TcpStream::connect(&url.addr).with_context(|| format!("connect to redis {}", url.addr))?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let mut conn = Self { reader: BufReader::new(stream.try_clone()?), writer: stream, line: String::new() };
+        let mut out = Vec::new();
+        let mut replies = 0;
+        if let Some(password) = &url.password {
+            encode(&mut out, &[b"AUTH", password.as_bytes()]);
+            replies += 1;
+        }
+        if url.db != 0 {
+            encode(&mut out, &[b"SELECT", url.db.to_string().as_bytes()]);
+            replies += 1;
+        }
+        if replies > 0 {
+            conn.writer.write_all(&out)?;
+            for _ in 0..replies {
+                conn.reply().with_context(|| format!("redis {} handshake", url.addr))?;
+            }
+        }
+        Ok(conn)
+    }
+
+    /// Одна строка ответа: `+OK`, `:N`; `-ERR` - ошибка
+    fn reply(&mut self) -> Result<()> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            bail!("redis closed the connection");
+        }
+        match self.line.as_bytes().first() {
+            Some(b'+') | Some(b':') => Ok(()),
+            Some(b'-') => bail!("redis error: {}", self.line.trim_end()),
+            _ => bail!("unexpected redis reply {:?}", self.line.trim_end()),
+        }
+    }
+
+    fn publish(&mut self, batch: &[(Arc<str>, String)], out: &mut Vec<u8>) -> Result<()> {
+        out.clear();
+        for (channel, payload) in batch {
+            encode(out, &[b"PUBLISH", channel.as_bytes(), payload.as_bytes()]);
+        }
+        self.writer.write_all(out)?;
+        for _ in batch {
+            self.reply()?;
+        }
+        Ok(())
+    }
+}
+
+/// Ручка публикации для горячего пути; клоны пишут в одну очередь.
+/// Прореживание тиков у каждого клона своё - тики публикует один поток насоса.
+#[derive(Clone)]
+pub struct RedisPublisher {
+    tx: SyncSender<Command>,
+    stats: Arc<RedisStats>,
+    ticks: Arc<str>,
+    signals: Arc<str>,
+    fills: Arc<str>,
+    risk: Arc<str>,
+    tick_sample_ms: i64,
+    last_tick: HashMap<Symbol, DateTime<Utc>>,
+}
+
+impl RedisPublisher {
+    fn send(&self, channel: &Arc<str>, event: &BotEvent) {
+        let payload = match serde_json::to_string(event) {
+            Ok(payload) => payload,
+            Err(err) => {
+                log::error!("redis event not serialized: {}", err);
+                return;
+            }
+        };
+        match self.tx.try_send(Command::Publish { channel: channel.clone(), payload }) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn on_tick(&mut self, tick: &TradeTick) {
+        if self.tick_sample_ms <= 0 {
+            return;
+        }
+        let last = self.last_tick.get(&tick.symbol);
+        if last.is_some_and(|last| (tick.timestamp - *last).num_milliseconds() < self.tick_sample_ms) {
+            return;
+        }
+        self.last_tick.insert(tick.symbol, tick.timestamp);
+        let event = BotEvent::Tick {
+            symbol: tick.symbol.as_str().to_string(),
+            ts: tick.timestamp,
+            price: tick.price,
+            volume: tick.volume,
+        };
+        self.send(&self.ticks.clone(), &event);
+    }
+
+    pub fn on_action(&self, action: &RoutedAction) {
+        let (name, price, size) = match &action.action {
+            StrategyAction::NoAction | StrategyAction::Error(_) => return,
+            StrategyAction::PlaceBuy { price, size } => ("place_buy", Some(*price), Some(*size)),
+            StrategyAction::PlaceSell { price, size } => ("place_sell", Some(*price), Some(*size)),
+            StrategyAction::ReplaceBuy { new_price } => ("replace_buy", Some(*new_price), None),
+            StrategyAction::CancelOrder { .. } => ("cancel_order", None, None),
+            StrategyAction::DetectSignal { .. } => ("detect", None, None),
+        };
+        let event = BotEvent::Signal {
+            symbol: action.symbol.as_str().to_string(),
+            strategy: action.strategy_name.clone(),
+            ts: action.source_time,
+            action: name.to_string(),
+            price,
+            size,
+        };
+        self.send(&self.signals, &event);
+    }
+
+    pub fn on_fill(&self, fill: &FillEvent) {
+        let event = BotEvent::Fill {
+            symbol: fill.symbol.as_str().to_string(),
+            strategy: fill.strategy,
+            ts: Utc::now(),
+            side: match fill.side {
+                Side::Bid => "buy",
+                Side::Ask => "sell",
+            }
+            .to_string(),
+            price: fill.price,
+            size: fill.size,
+        };
+        self.send(&self.fills, &event);
+    }
+
+    pub fn on_risk_event(&self, envelope: &RiskEnvelope) {
+        let event = BotEvent::Risk {
+            seq: envelope.seq,
+            ts: envelope.at,
+            kind: envelope.event.kind().name().to_string(),
+            detail: format!("{:?}", envelope.event),
+        };
+        self.send(&self.risk, &event);
+    }
+
+    pub fn stats(&self) -> &RedisStats {
+        &self.stats
+    }
+}
+
+/// Поток публикации и его ручка
+pub struct RedisBridge {
+    publisher: RedisPublisher,
+    worker: Option<JoinHandle<()>>,
+    risk_forwarder: Option<JoinHandle<()>>,
+}
+
+impl RedisBridge {
+    /// Подключается сразу: недоступный Redis при старте - ошибка конфигурации
+    pub fn start(config: &RedisConfig) -> Result<Self> {
+        let url = parse_url(&config.url)?;
+        let conn = Connection::open(&url)?;
+        let (tx, rx) = mpsc::sync_channel(config.queue.max(1));
+        let stats = Arc::new(RedisStats::default());
+        let worker_stats = stats.clone();
+        let worker = thread::Builder::new()
+            .name("redis-publisher".to_string())
+            .spawn(move || run(url, conn, rx, worker_stats))
+            .context("spawn redis publisher")?;
+        let channel = |name: &str| -> Arc<str> { format!("{}:{}", config.channel_prefix, name).into() };
+        let publisher = RedisPublisher {
+            tx,
+            stats,
+            ticks: channel("ticks"),
+            signals: channel("signals"),
+            fills: channel("fills"),
+            risk: channel("risk"),
+            tick_sample_ms: config.tick_sample_ms as i64,
+            last_tick: HashMap::new(),
+        };
+        log::info!("publishing bot events to redis {} under {}:*", parse_url(&config.url)?.addr, config.channel_prefix);
+        Ok(Self { publisher, worker: Some(worker), risk_forwarder: None })
+    }
+
+    pub fn publisher(&self) -> RedisPublisher {
+        self.publisher.clone()
+    }
+
+    pub fn stats(&self) -> &RedisStats {
+        &self.publisher.stats
+    }
+
+    /// Пересылает все события шины риска в `{prefix}:risk`
+    pub fn forward_risk_events(&mut self, bus: &RiskEventBus) -> Result<()> {
+        ensure!(self.risk_forwarder.is_none(), "risk events are already forwarded to redis");
+        let events = bus.subscribe("redis", &[]);
+        let publisher = self.publisher();
+        let handle = thread::Builder::new()
+            .name("redis-risk-events".to_string())
+            .spawn(move || {
+                for envelope in events {
+                    publisher.on_risk_event(&envelope);
+                }
+            })
+            .context("spawn redis risk forwarder")?;
+        self.risk_forwarder = Some(handle);
+        Ok(())
+    }
+
+    /// Дописывает очередь и останавливает поток. Пересылка событий риска
+    /// завершится вместе с шиной.
+    pub fn shutdown(mut self) {
+        let _ = self.publisher.tx.send(Command::Stop);
+        if let Some(worker) = self.worker.take()
+            && worker.join().is_err()
+        {
+            log::error!("redis publisher thread panicked");
+        }
+    }
+}
+
+fn run(url: RedisUrl, conn: Connection, rx: Receiver<Command>, stats: Arc<RedisStats>) {
+    let mut conn = Some(conn);
+    let mut retry_at = Instant::now();
+    let mut batch: Vec<(Arc<str>, String)> = Vec::with_capacity(BATCH);
+    let mut out = Vec::new();
+    let mut stopping = false;
+    while !stopping {
+        match rx.recv() {
+            Ok(Command::Publish { channel, payload }) => batch.push((channel, payload)),
+            Ok(Command::Stop) | Err(_) => break,
+        }
+        while batch.len() < BATCH {
+            match rx.try_recv() {
+                Ok(Command::Publish { channel, payload }) => batch.push((channel, payload)),
+                Ok(Command::Stop) => {
+                    stopping = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        if conn.is_none() && Instant::now() >= retry_at {
+            stats.reconnects.fetch_add(1, Ordering::Relaxed);
+            match Connection::open(&url) {
+                Ok(fresh) => {
+                    log::warn!("redis {} reconnected", url.addr);
+                    conn = Some(fresh);
+                }
+                Err(err) => {
+                    log::error!("redis {} reconnect failed: {:#}", url.addr, err);
+                    retry_at = Instant::now() + RECONNECT_DELAY;
+                }
+            }
+        }
+        let sent = match conn.as_mut() {
+            Some(c) => match c.publish(&batch, &mut out) {
+                Ok(()) => true,
+                Err(err) => {
+                    log::error!("redis {} publish failed, {} events lost: {:#}", url.addr, batch.len(), err);
+                    conn = None;
+                    retry_at = Instant::now() + RECONNECT_DELAY;
+                    false
+                }
+            },
+            None => false,
+        };
+        let counter = if sent { &stats.published } else { &stats.dropped };
+        counter.fetch_add(batch.len() as u64, Ordering::Relaxed);
+        batch.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::TradeSide;
+    use crate::risk::RiskEvent;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            parse_url("redis://:pw@cache:6380/2").unwrap(),
+            RedisUrl { addr: "cache:6380".to_string(), password: Some("pw".to_string()), db: 2 }
+        );
+        assert_eq!(
+            parse_url("redis://localhost").unwrap(),
+            RedisUrl { addr: "localhost:6379".to_string(), password: None, db: 0 }
+        );
+        assert!(parse_url("http://localhost").is_err());
+        let mut out = Vec::new();
+        encode(&mut out, &[b"PUBLISH", b"bot:fills", b"{}"]);
+        assert_eq!(out, b"*3\r\n$7\r\nPUBLISH\r\n$9\r\nbot:fills\r\n$2\r\n{}\r\n");
+    }
+
+    /// Фейковый Redis: отвечает +OK/:1 и возвращает всё, что получил
+    fn fake_redis(expected_commands: usize) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0u8; 4096];
+            let mut answered = 0;
+            while answered < expected_commands {
+                let n = stream.read(&mut buf).unwrap();
+                assert!(n > 0, "client closed early");
+                received.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&received);
+                let commands = text.matches("*3\r\n").count() + text.matches("*2\r\n").count();
+                while answered < commands {
+                    let reply: &[u8] = if text.contains("AUTH") && answered == 0 { b"+OK\r\n" } else { b":1\r\n" };
+                    stream.write_all(reply).unwrap();
+                    answered += 1;
+                }
+            }
+            String::from_utf8(received).unwrap()
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_events_are_published_and_ticks_sampled() {
+        let (addr, server) = fake_redis(5);
+        let config = RedisConfig {
+            url: format!("redis://:secret@{}", addr),
+            channel_prefix: "mm".to_string(),
+            tick_sample_ms: 1_000,
+            queue: 64,
+        };
+        let mut bridge = RedisBridge::start(&config).unwrap();
+        let mut publisher = bridge.publisher();
+        let base = Utc::now();
+        let tick = |ms: i64| TradeTick {
+            timestamp: base + chrono::Duration::milliseconds(ms),
+            symbol: Symbol::new("BTC_USDT"),
+            price: 100.0,
+            volume: 1.0,
+            side: TradeSide::Buy,
+            trade_id: String::new(),
+            best_bid: None,
+            best_ask: None,
+        };
+        publisher.on_tick(&tick(0));
+        publisher.on_tick(&tick(500));
+        publisher.on_tick(&tick(1_000));
+        publisher.on_fill(&FillEvent { symbol: Symbol::new("BTC_USDT"), strategy: 0, side: Side::Bid, price: 99.5, size: 2.0 });
+
+        let bus = RiskEventBus::new();
+        bridge.forward_risk_events(&bus).unwrap();
+        assert!(bridge.forward_risk_events(&bus).is_err());
+        let _ = bus.publish(RiskEvent::OrderRejected {
+            symbol: Symbol::new("BTC_USDT"),
+            client_order_id: "t-1".to_string(),
+            strategy: 0,
+        });
+
+        let received = server.join().unwrap();
+        assert!(received.starts_with("*2\r\n$4\r\nAUTH\r\n$6\r\nsecret\r\n"), "{}", received);
+        assert_eq!(received.matches("mm:ticks").count(), 2);
+        assert!(received.contains("mm:fills"));
+        assert!(received.contains(r#""type":"fill""#) && received.contains(r#""side":"buy""#));
+        assert!(received.contains("mm:risk") && received.contains("order_rejected"));
+        assert_eq!(bridge.stats().dropped.load(Ordering::Relaxed), 0);
+        bridge.shutdown();
+    }
+}
@@ -0,0 +1,566 @@
+//! Роутер ордеров: единственный потребитель исходящих колец всех шардов
+//!
+//! Роутер живёт в одном потоке (исполнение), сам стратегий не знает и адресует
+//! филы обратно по (символ, индекс стратегии) в шард, которому принадлежит символ.
+//! Филы приходят из user-data стрима биржи через `on_user_event`.
+//!
+//! Cancel-replace: замена ждёт конечного статуса отменяемого ордера. Если пока летела
+//! отмена ордер исполнился, шард получает настоящий фил, а замена уменьшается на
+//! исполненное или не уходит вовсе; отпущенная замена выдаётся через `poll` один раз.
+//!
+//! Circuit breaker: отказы биржи и неудачные отмены считаются по символу; выключенный
+//! символ не получает новых входов (PlaceBuy), выходы и отмены проходят.
+//! Так же блокируются входы по символу в каскаде ликвидаций (`OpenInterestMonitor`)
+//! и по 24h-статистике из общего кэша тикеров (`TickerFilter`).
+//!
+//! Правила биржи (`ComplianceChecker`): заявка, которую биржа всё равно отвергнет
+//! (min notional, коридор цены, лимит ордеров или позиции), отклоняется здесь с причиной
+//! в логе и не тратит бюджет ошибок API, circuit breaker и AutoStop.
+
+use super::latency::LatencyRecorder;
+use super::orders::{AckTiming, OrderTable, PendingReplace, ReplaceOutcome};
+use super::archive::ArchiveHandle;
+use super::redis::RedisPublisher;
+use super::shard::{shard_for, FillEvent, PlacedEvent, RoutedAction, ShardCommand, ROUTER_OUTBOX, SHARD_CONTROL};
+use super::shutdown::EntryGate;
+use super::ticker_stats::{TickerFilter, TickerStatsCache};
+use super::venue::{VenueChoice, VenueQuote, VenueSelector};
+use crate::backtest::strategy_adapter::StrategyAction;
+use crate::base_classes::ring_buffer::{Consumer, Producer};
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::execution::user_stream::{BalanceUpdate, PositionUpdate, UserEvent};
+use crate::execution::close::{CloseAmount, PartialClose, PositionCloser};
+use crate::execution::fx::{FxRates, Valuation};
+use crate::execution::compliance::{ComplianceChecker, Exposure, OrderCheck};
+use crate::execution::ClientOrderId;
+use crate::risk::{OpenInterestMonitor, RiskEvent, RiskEventBus, SymbolCircuitBreaker};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+pub struct OrderRouter {
+    outboxes: Vec<Consumer<RoutedAction, ROUTER_OUTBOX>>,
+    controls: Vec<Producer<ShardCommand, SHARD_CONTROL>>,
+    next_shard: usize,
+    entries: EntryGate,
+    blocked_entries: u64,
+    orders: OrderTable,
+    positions: HashMap<Symbol, PositionUpdate>,
+    balances: HashMap<String, BalanceUpdate>,
+    stream_gaps: u64,
+    latency: LatencyRecorder,
+    events: Option<RiskEventBus>,
+    redis: Option<RedisPublisher>,
+    archive: Option<ArchiveHandle>,
+    venues: Option<VenueSelector>,
+    /// Замены по client id отменяемого ордера
+    replaces: HashMap<ClientOrderId, PendingReplace>,
+    /// Отпущенные замены, уходят первыми в следующем `poll`
+    released: Vec<RoutedAction>,
+    breaker: Option<SymbolCircuitBreaker>,
+    breaker_blocked: u64,
+    open_interest: Option<OpenInterestMonitor>,
+    cascade_blocked: u64,
+    ticker_filter: Option<(TickerStatsCache, TickerFilter)>,
+    ticker_blocked: u64,
+    compliance: Option<ComplianceChecker>,
+    compliance_rejected: u64,
+    /// Чистое исполнение по символу (buy +, sell -): позиция, если стрим позиций молчит (спот)
+    net_filled: HashMap<Symbol, f64>,
+}
+
+impl OrderRouter {
+    pub(crate) fn new(
+        outboxes: Vec<Consumer<RoutedAction, ROUTER_OUTBOX>>,
+        controls: Vec<Producer<ShardCommand, SHARD_CONTROL>>,
+    ) -> Self {
+        assert_eq!(outboxes.len(), controls.len(), "every shard needs an outbox and a control ring");
+        Self {
+            outboxes,
+            controls,
+            next_shard: 0,
+            entries: EntryGate::new(),
+            blocked_entries: 0,
+            orders: OrderTable::default(),
+            positions: HashMap::new(),
+            balances: HashMap::new(),
+            stream_gaps: 0,
+            latency: LatencyRecorder::default(),
+            events: None,
+            redis: None,
+            archive: None,
+            venues: None,
+            replaces: HashMap::new(),
+            released: Vec::new(),
+            breaker: None,
+            breaker_blocked: 0,
+            open_interest: None,
+            cascade_blocked: 0,
+            ticker_filter: None,
+            ticker_blocked: 0,
+            compliance: None,
+            compliance_rejected: 0,
+            net_filled: HashMap::new(),
+        }
+    }
+
+    pub fn shards(&self) -> usize {
+        self.outboxes.len()
+    }
+
+    /// Флаг новых входов; закрывается `ShutdownCoordinator`
+    pub fn entry_gate(&self) -> EntryGate {
+        self.entries.clone()
+    }
+
+    /// Сколько PlaceBuy отброшено после закрытия входов
+    pub fn blocked_entries(&self) -> u64 {
+        self.blocked_entries
+    }
+
+    /// Сколько PlaceBuy отброшено по выключенным символам
+    pub fn breaker_blocked(&self) -> u64 {
+        self.breaker_blocked
+    }
+
+    /// Сколько PlaceBuy отброшено по каскаду ликвидаций
+    pub fn cascade_blocked(&self) -> u64 {
+        self.cascade_blocked
+    }
+
+    /// Сколько PlaceBuy отброшено фильтром 24h-статистики
+    pub fn ticker_blocked(&self) -> u64 {
+        self.ticker_blocked
+    }
+
+    /// Сколько заявок отклонено по правилам биржи до отправки
+    pub fn compliance_rejected(&self) -> u64 {
+        self.compliance_rejected
+    }
+
+    /// Вход не пропускается: входы закрыты, символ выключен, в каскаде ликвидаций
+    /// или не прошёл фильтр 24h-статистики
+    fn entry_blocked(&mut self, action: &RoutedAction) -> bool {
+        if !matches!(action.action, StrategyAction::PlaceBuy { .. }) {
+            return false;
+        }
+        if !self.entries.is_open() {
+            self.blocked_entries += 1;
+            return true;
+        }
+        if let Some(breaker) = &mut self.breaker
+            && !breaker.allows(action.symbol, Utc::now())
+        {
+            self.breaker_blocked += 1;
+            return true;
+        }
+        if let Some(open_interest) = &self.open_interest
+            && !open_interest.allows_long(action.symbol, Utc::now())
+        {
+            self.cascade_blocked += 1;
+            return true;
+        }
+        if let Some((cache, filter)) = &self.ticker_filter {
+            let now_ms = Utc::now().timestamp_millis().max(0) as u64;
+            if let Some(reason) = filter.rejects(cache.get(action.symbol, now_ms).as_ref()) {
+                log::debug!("{} entry on {} blocked: {}", action.strategy_name, action.symbol, reason);
+                self.ticker_blocked += 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Заявка нарушает правила биржи. Отклонённый вход - предупреждение, отклонённый
+    /// выход - ошибка: позиция остаётся без продажи
+    fn compliance_blocked(&mut self, action: &RoutedAction) -> bool {
+        let (side, price, size) = match action.action {
+            StrategyAction::PlaceBuy { price, size } => (Side::Bid, price, size),
+            StrategyAction::PlaceSell { price, size } => (Side::Ask, price, size),
+            _ => return false,
+        };
+        if self.compliance.is_none() {
+            return false;
+        }
+        let symbol = action.symbol;
+        let mut exposure = Exposure { position: self.position_size(symbol), ..Exposure::default() };
+        for (_, order) in self.orders.live().filter(|(_, order)| order.symbol == symbol) {
+            exposure.open_orders += 1;
+            if order.side == Side::Bid {
+                exposure.open_buy_size += (order.size - order.filled).max(0.0);
+            }
+        }
+        let order = OrderCheck { symbol, side, price, size, reduce_only: false };
+        let checker = self.compliance.as_mut().expect("checked above");
+        let Err(violation) = checker.check(&order, &exposure) else {
+            return false;
+        };
+        if side == Side::Bid {
+            log::warn!("{} entry on {} rejected locally: {}", action.strategy_name, symbol, violation);
+        } else {
+            log::error!("{} exit on {} rejected locally: {}", action.strategy_name, symbol, violation);
+        }
+        self.compliance_rejected += 1;
+        true
+    }
+
+    /// Забирает до `max` действий из всех шардов по кругу, чтобы шумный шард
+    /// не задерживал остальные. Возвращает число добавленных в `out`.
+    /// После закрытия входов новые PlaceBuy не отдаются, выходы проходят как обычно.
+    pub fn poll(&mut self, out: &mut Vec<RoutedAction>, max: usize) -> usize {
+        let shards = self.outboxes.len();
+        let mut taken = 0;
+        while taken < max && !self.released.is_empty() {
+            let action = self.released.remove(0);
+            if self.entry_blocked(&action) || self.compliance_blocked(&action) {
+                continue;
+            }
+            out.push(action);
+            taken += 1;
+        }
+        let mut empty_in_row = 0;
+        while taken < max && empty_in_row < shards {
+            let shard = self.next_shard;
+            self.next_shard = (self.next_shard + 1) % shards;
+            match self.outboxes[shard].try_pop() {
+                Ok(action) => {
+                    empty_in_row = 0;
+                    if self.entry_blocked(&action) || self.compliance_blocked(&action) {
+                        continue;
+                    }
+                    if let Some(redis) = &self.redis {
+                        redis.on_action(&action);
+                    }
+                    out.push(action);
+                    taken += 1;
+                }
+                Err(()) => empty_in_row += 1,
+            }
+        }
+        taken
+    }
+
+    /// Отдаёт исполнение стратегии в её шард
+    pub fn fill(&mut self, fill: FillEvent) {
+        if let Some(redis) = &self.redis {
+            redis.on_fill(&fill);
+        }
+        let signed = if fill.side == Side::Bid { fill.size } else { -fill.size };
+        *self.net_filled.entry(fill.symbol).or_default() += signed;
+        let shard = shard_for(fill.symbol, self.controls.len());
+        self.controls[shard].push_spin(ShardCommand::Fill(fill));
+    }
+
+    /// Исполнитель отправил PlaceBuy/PlaceSell под этим client id.
+    /// Вызывать сразу после записи в сокет: момент вызова - метка отправки.
+    pub fn submitted(&mut self, client_order_id: ClientOrderId, action: &RoutedAction) {
+        let now = Instant::now();
+        self.latency.record_submit(&action.timing, now);
+        let handle = self.orders.submit(client_order_id, action, now);
+        let side = if matches!(action.action, StrategyAction::PlaceBuy { .. }) { Side::Bid } else { Side::Ask };
+        let shard = shard_for(action.symbol, self.controls.len());
+        self.controls[shard].push_spin(ShardCommand::Placed(PlacedEvent {
+            symbol: action.symbol,
+            strategy: action.strategy,
+            side,
+            order_id: handle,
+        }));
+    }
+
+    /// Биржа ответила на отправку (ack шлюза); стрим может прийти позже
+    pub fn acked(&mut self, client_order_id: &ClientOrderId) {
+        if let Some(ack) = self.orders.ack(client_order_id, Instant::now()) {
+            self.record_ack(ack);
+        }
+        if let (Some(breaker), Some(order)) = (&mut self.breaker, self.orders.get(client_order_id)) {
+            breaker.record_accepted(order.symbol);
+        }
+    }
+
+    /// Исполнитель отправил отмену `cancelled` и хочет поставить `replacement` вместо него.
+    /// Замена выйдет из `poll` только после конечного статуса ордера: при отмене - на размер
+    /// за вычетом исполненного после этого вызова, при полном исполнении - никогда.
+    pub fn cancel_replace(&mut self, cancelled: &ClientOrderId, replacement: RoutedAction) -> Result<()> {
+        if !matches!(replacement.action, StrategyAction::PlaceBuy { .. } | StrategyAction::PlaceSell { .. }) {
+            bail!("replacement for {} must be a placement, got {:?}", cancelled, replacement.action);
+        }
+        let Some(order) = self.orders.get(cancelled) else {
+            bail!("cancel-replace of untracked order {}", cancelled);
+        };
+        if self.replaces.contains_key(cancelled) {
+            bail!("order {} already has a pending replacement", cancelled);
+        }
+        let pending = PendingReplace { replacement, filled_at_cancel: order.filled };
+        self.replaces.insert(cancelled.clone(), pending);
+        // Ордер мог закончиться раньше, чем исполнитель решил его заменить
+        if order.state.is_terminal() {
+            self.resolve_replace(cancelled);
+        }
+        Ok(())
+    }
+
+    /// Биржа отвергла отмену, а ордер жив: замена не нужна. Возвращает её, если была.
+    pub fn abandon_replace(&mut self, cancelled: &ClientOrderId) -> Option<RoutedAction> {
+        self.replaces.remove(cancelled).map(|pending| pending.replacement)
+    }
+
+    /// Замены, ждущие конечного статуса отменяемых ордеров
+    pub fn pending_replaces(&self) -> usize {
+        self.replaces.len()
+    }
+
+    fn resolve_replace(&mut self, cancelled: &ClientOrderId) {
+        let Some(pending) = self.replaces.remove(cancelled) else {
+            return;
+        };
+        let order = self.orders.get(cancelled).expect("replaced order is tracked");
+        match pending.resolve(order) {
+            ReplaceOutcome::Released(replacement) => self.released.push(replacement),
+            ReplaceOutcome::Dropped { raced_fill } => log::warn!(
+                "order {} filled {} while being cancelled; replacement dropped",
+                cancelled,
+                raced_fill
+            ),
+        }
+    }
+
+    fn record_ack(&mut self, ack: AckTiming) {
+        self.latency.record_ack(&ack.timing, ack.submitted, ack.acked);
+    }
+
+    /// Шина событий риска: отказы биржи публикуются как OrderRejected
+    pub fn set_risk_events(&mut self, events: RiskEventBus) {
+        self.events = Some(events);
+    }
+
+    /// Выключение символов по отказам биржи и неудачным отменам
+    pub fn set_circuit_breaker(&mut self, breaker: SymbolCircuitBreaker) {
+        self.breaker = Some(breaker);
+    }
+
+    pub fn circuit_breaker_mut(&mut self) -> Option<&mut SymbolCircuitBreaker> {
+        self.breaker.as_mut()
+    }
+
+    /// Блокировка входов по открытому интересу; отсчёты - через `on_open_interest`
+    pub fn set_open_interest_monitor(&mut self, monitor: OpenInterestMonitor) {
+        self.open_interest = Some(monitor);
+    }
+
+    /// Отсчёт открытого интереса по символу; без монитора игнорируется
+    pub fn on_open_interest(&mut self, symbol: Symbol, at: DateTime<Utc>, open_interest: f64, price: f64) {
+        if let Some(monitor) = &mut self.open_interest {
+            monitor.on_sample(symbol, at, open_interest, price);
+        }
+    }
+
+    pub fn open_interest_monitor(&self) -> Option<&OpenInterestMonitor> {
+        self.open_interest.as_ref()
+    }
+
+    /// Фильтр входов по 24h-статистике; кэш обновляет опрос тикеров
+    pub fn set_ticker_filter(&mut self, cache: TickerStatsCache, filter: TickerFilter) {
+        self.ticker_filter = Some((cache, filter));
+    }
+
+    /// Проверка заявок по правилам биржи перед отправкой
+    pub fn set_compliance(&mut self, checker: ComplianceChecker) {
+        self.compliance = Some(checker);
+    }
+
+    pub fn compliance(&self) -> Option<&ComplianceChecker> {
+        self.compliance.as_ref()
+    }
+
+    /// Цена сделки или mark для коридора цены; без проверки правил игнорируется
+    pub fn on_reference_price(&mut self, symbol: Symbol, price: f64) {
+        if let Some(checker) = &mut self.compliance {
+            checker.on_price(symbol, price);
+        }
+    }
+
+    /// Публикация действий стратегий и филов в Redis
+    pub fn set_redis(&mut self, publisher: RedisPublisher) {
+        self.redis = Some(publisher);
+    }
+
+    /// Архив апдейтов ордеров и сделок из user-data стрима
+    pub fn set_archive(&mut self, archive: ArchiveHandle) {
+        self.archive = Some(archive);
+    }
+
+    /// Выбор площадки для символов, торгующихся на нескольких биржах
+    pub fn set_venue_selector(&mut self, venues: VenueSelector) {
+        self.venues = Some(venues);
+    }
+
+    /// Лучшие цены площадки из её market data; без селектора игнорируются
+    pub fn venue_quote(&mut self, venue: &str, symbol: Symbol, quote: VenueQuote) {
+        if let Some(venues) = &mut self.venues {
+            venues.on_quote(venue, symbol, quote, Instant::now());
+        }
+    }
+
+    /// Площадка для заявки из `poll`. None - селектор не задан, действие не заявка
+    /// или ни у одной площадки нет свежей котировки.
+    pub fn select_venue(&self, action: &RoutedAction) -> Option<VenueChoice> {
+        self.venues.as_ref()?.select(action, Instant::now())
+    }
+
+    /// Биржа отвергла заявку в ответе на отправку
+    pub fn rejected(&mut self, client_order_id: &ClientOrderId) {
+        if self.orders.reject(client_order_id).is_some() {
+            self.publish_rejected(client_order_id);
+            self.resolve_replace(client_order_id);
+        }
+    }
+
+    /// Отмена ордера не прошла (ответ шлюза с ошибкой); считается в circuit breaker символа
+    pub fn cancel_failed(&mut self, client_order_id: &ClientOrderId) {
+        let (Some(breaker), Some(order)) = (&mut self.breaker, self.orders.get(client_order_id)) else {
+            return;
+        };
+        breaker.record_failed_cancel(order.symbol, Utc::now());
+    }
+
+    fn publish_rejected(&mut self, client_order_id: &ClientOrderId) {
+        let Some(order) = self.orders.get(client_order_id) else {
+            return;
+        };
+        if let Some(breaker) = &mut self.breaker {
+            breaker.record_rejection(order.symbol, Utc::now());
+        }
+        let Some(events) = &self.events else {
+            return;
+        };
+        events.publish(RiskEvent::OrderRejected {
+            symbol: order.symbol,
+            client_order_id: client_order_id.to_string(),
+            strategy: order.strategy,
+        });
+    }
+
+    /// Применяет событие user-data стрима: апдейт ордера двигает машину состояний и
+    /// отдаёт дельту исполнения в шард, баланс и позиция запоминаются последними значениями
+    pub fn on_user_event(&mut self, event: UserEvent) {
+        if let Some(archive) = &self.archive {
+            archive.on_user_event(&event);
+        }
+        match event {
+            UserEvent::Order(update) => {
+                let applied = self.orders.apply(&update);
+                if let Some(ack) = applied.ack {
+                    self.record_ack(ack);
+                }
+                if let Some(fill) = applied.fill {
+                    self.fill(fill);
+                }
+                if let Some(client_order_id) = applied.rejected {
+                    self.publish_rejected(&client_order_id);
+                }
+                // Фил уже ушёл в шард, замена считается от настоящего исполнения
+                if let Some(client_order_id) = applied.finished {
+                    self.resolve_replace(&client_order_id);
+                }
+            }
+            // Исполнение считается по апдейтам ордеров, сделки - только для комиссий и журнала
+            UserEvent::Trade(_) => {}
+            UserEvent::Balance(balance) => {
+                self.balances.insert(balance.currency.clone(), balance);
+            }
+            UserEvent::Position(position) => {
+                self.positions.insert(position.symbol, position);
+            }
+            UserEvent::StreamGap { reconnects } => {
+                self.stream_gaps += 1;
+                log::error!(
+                    "user stream reconnected ({} times): updates may be lost, {} live orders and {} pending replacements need reconciling",
+                    reconnects,
+                    self.orders.live().count(),
+                    self.replaces.len()
+                );
+            }
+        }
+    }
+
+    /// Разбирает всё, что уже пришло из стрима, не блокируясь. Возвращает число событий.
+    pub fn drain_user_events(&mut self, events: &mut mpsc::UnboundedReceiver<UserEvent>) -> usize {
+        let mut drained = 0;
+        while let Ok(event) = events.try_recv() {
+            self.on_user_event(event);
+            drained += 1;
+        }
+        drained
+    }
+
+    pub fn orders(&self) -> &OrderTable {
+        &self.orders
+    }
+
+    /// Забывает ордера в конечном состоянии
+    pub fn prune_orders(&mut self) -> usize {
+        self.orders.prune_terminal()
+    }
+
+    pub fn position(&self, symbol: Symbol) -> Option<&PositionUpdate> {
+        self.positions.get(&symbol)
+    }
+
+    /// Размер позиции (buy +, sell -): из стрима позиций, а пока он молчит (спот) -
+    /// по чистому исполнению ордеров роутера
+    pub fn position_size(&self, symbol: Symbol) -> f64 {
+        match self.positions.get(&symbol) {
+            Some(position) => position.size,
+            None => self.net_filled.get(&symbol).copied().unwrap_or_default(),
+        }
+    }
+
+    pub fn balance(&self, currency: &str) -> Option<&BalanceUpdate> {
+        self.balances.get(currency)
+    }
+
+    /// Капитал в валюте отчётности: балансы плюс нереализованный PnL позиций по mid.
+    /// Нет курса или mid по открытой позиции - ошибка, а не частичная сумма.
+    pub fn equity(&self, fx: &FxRates) -> Result<Valuation> {
+        let now = Instant::now();
+        let mut amounts: Vec<(&str, f64)> = self.balances.values().map(|b| (b.currency.as_str(), b.balance)).collect();
+        for position in self.positions.values().filter(|p| p.size != 0.0) {
+            let quote = position.symbol.quote().ok_or_else(|| anyhow!("position {} has no quote currency", position.symbol))?;
+            let mark = fx.mid(position.symbol, now).ok_or_else(|| anyhow!("no fresh mid to mark {}", position.symbol))?;
+            amounts.push((quote, (mark - position.entry_price) * position.size));
+        }
+        fx.value(amounts, now)
+    }
+
+    /// Reduce-only заявка, закрывающая долю позиции по `symbol` (0..1] - для делеверажера,
+    /// дашборда и команд оператора. Нет позиции из стрима - ошибка.
+    pub fn close_fraction(&self, closer: &PositionCloser, symbol: Symbol, fraction: f64, mark: f64) -> Result<PartialClose> {
+        self.close(closer, symbol, CloseAmount::Fraction(fraction), mark)
+    }
+
+    /// То же на сумму в валюте котировки по цене `mark`
+    pub fn close_notional(&self, closer: &PositionCloser, symbol: Symbol, amount: f64, mark: f64) -> Result<PartialClose> {
+        self.close(closer, symbol, CloseAmount::Notional(amount), mark)
+    }
+
+    fn close(&self, closer: &PositionCloser, symbol: Symbol, amount: CloseAmount, mark: f64) -> Result<PartialClose> {
+        let position = self
+            .positions
+            .get(&symbol)
+            .ok_or_else(|| anyhow!("no position on {} from the user stream", symbol))?;
+        closer.close(position, amount, mark)
+    }
+
+    /// Распределения задержек тик -> сигнал -> отправка -> подтверждение
+    pub fn latency(&self) -> &LatencyRecorder {
+        &self.latency
+    }
+
+    /// Сколько раз стрим переподключался с момента старта
+    pub fn stream_gaps(&self) -> u64 {
+        self.stream_gaps
+    }
+}
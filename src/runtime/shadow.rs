@@ -0,0 +1,311 @@
+//! Теневые стратегии: A/B правки конфига на живом потоке без ордеров
+//!
+//! `ShardedRuntime::start_with_shadows` получает вторую фабрику - те же стратегии
+//! с альтернативным конфигом. Шард создаёт теневой набор вместе с живым, отдаёт ему
+//! те же тики, прогрев, ликвидации и таймеры, но действия теневых стратегий в роутер
+//! не уходят: их ордера исполняются симулятором по правилам `SimulationHarness`
+//! (buy - сделкой по цене не выше лимита, sell - не ниже; ордер, выставленный на тике,
+//! исполняется не раньше следующего тика). Входы держатся прогревом так же, как живые.
+//!
+//! Итоги "что было бы" шарды пишут в общую `ShadowBoard` на филах и раз в
+//! `PROGRESS_EVERY` тиков открытой позиции; арбитр детектов теневые входы не видит.
+//! Итоги выселенного символа остаются на доске и продолжаются, если символ вернётся.
+
+use super::readiness::{WarmupTracker, PROGRESS_EVERY};
+use super::shard::StrategyFactory;
+use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::{StrategyAction, StrategyAdapter};
+use crate::base_classes::liquidations::Liquidation;
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::strategy::moon_strategies::mshot::Deltas;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Итог теневой стратегии символа
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShadowPnl {
+    pub symbol: Symbol,
+    pub strategy: String,
+    /// Исполненные входы (филы buy)
+    pub entries: u64,
+    pub round_trips: u64,
+    pub realized_pnl: f64,
+    pub open_position: f64,
+    pub position_cost: f64,
+    /// Цена последнего тика на момент записи
+    pub mark_price: f64,
+    /// Входы, отклонённые прогревом
+    pub held_entries: u64,
+    /// Действия, которые симулятор не смог применить (sell без позиции, второй buy)
+    pub ignored_actions: u64,
+    pub errors: u64,
+}
+
+impl ShadowPnl {
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.open_position * self.mark_price - self.position_cost
+    }
+}
+
+/// Общая доска теневых итогов: клон делит те же данные
+#[derive(Debug, Clone, Default)]
+pub struct ShadowBoard {
+    entries: Arc<RwLock<HashMap<(Symbol, usize), ShadowPnl>>>,
+}
+
+impl ShadowBoard {
+    pub fn set(&self, idx: usize, pnl: ShadowPnl) {
+        let mut entries = self.entries.write().expect("shadow lock poisoned");
+        entries.insert((pnl.symbol, idx), pnl);
+    }
+
+    pub fn get(&self, symbol: Symbol, idx: usize) -> Option<ShadowPnl> {
+        let entries = self.entries.read().expect("shadow lock poisoned");
+        entries.get(&(symbol, idx)).cloned()
+    }
+
+    /// Все теневые стратегии по символу и порядку в наборе
+    pub fn snapshot(&self) -> Vec<ShadowPnl> {
+        let entries = self.entries.read().expect("shadow lock poisoned");
+        let mut all: Vec<_> = entries.iter().collect();
+        all.sort_by_key(|((symbol, idx), _)| (*symbol, *idx));
+        all.into_iter().map(|(_, pnl)| pnl.clone()).collect()
+    }
+
+    /// Реализованный PnL по имени стратегии, суммарно по символам
+    pub fn realized_by_strategy(&self) -> Vec<(String, f64)> {
+        let entries = self.entries.read().expect("shadow lock poisoned");
+        let mut totals: HashMap<&str, f64> = HashMap::new();
+        for pnl in entries.values() {
+            *totals.entry(pnl.strategy.as_str()).or_default() += pnl.realized_pnl;
+        }
+        let mut totals: Vec<_> = totals.into_iter().map(|(name, pnl)| (name.to_string(), pnl)).collect();
+        totals.sort_by(|a, b| a.0.cmp(&b.0));
+        totals
+    }
+}
+
+/// Фабрика теневых наборов и доска, общие для шардов
+#[derive(Clone, Default)]
+pub(crate) struct ShadowSetup {
+    pub(crate) factory: Option<StrategyFactory>,
+    pub(crate) board: ShadowBoard,
+}
+
+impl ShadowSetup {
+    /// Теневой набор символа; без фабрики - пустой
+    pub(crate) fn build(&self, symbol: Symbol) -> Vec<ShadowStrategy> {
+        let Some(factory) = &self.factory else {
+            return Vec::new();
+        };
+        factory(symbol)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, strategy)| {
+                let shadow = ShadowStrategy::new(symbol, strategy, self.board.get(symbol, idx));
+                self.board.set(idx, shadow.pnl.clone());
+                shadow
+            })
+            .collect()
+    }
+}
+
+/// Id ордеров тени для `on_order_placed`: у неё не больше одного ордера на сторону
+const SHADOW_BUY_ID: u64 = 1;
+const SHADOW_SELL_ID: u64 = 2;
+
+#[derive(Debug, Clone, Copy)]
+struct ShadowOrder {
+    price: f64,
+    size: f64,
+}
+
+/// Теневая стратегия с симулятором исполнения
+pub(crate) struct ShadowStrategy {
+    strategy: Box<dyn StrategyAdapter + Send>,
+    warmup: WarmupTracker,
+    buy: Option<ShadowOrder>,
+    sell: Option<ShadowOrder>,
+    pnl: ShadowPnl,
+    ticks: u64,
+}
+
+impl ShadowStrategy {
+    /// `previous` - итог того же слота до выселения символа: счётчики продолжаются,
+    /// позиция начинается с нуля вместе с новой стратегией
+    fn new(symbol: Symbol, strategy: Box<dyn StrategyAdapter + Send>, previous: Option<ShadowPnl>) -> Self {
+        let pnl = match previous {
+            Some(previous) => ShadowPnl { open_position: 0.0, position_cost: 0.0, ..previous },
+            None => ShadowPnl { symbol, strategy: strategy.get_name().to_string(), ..ShadowPnl::default() },
+        };
+        Self {
+            warmup: WarmupTracker::new(strategy.warmup_requirements()),
+            strategy,
+            buy: None,
+            sell: None,
+            pnl,
+            ticks: 0,
+        }
+    }
+
+    pub(crate) fn warm_up(&mut self, tick: &TradeTick, deltas: &Deltas) {
+        self.strategy.warm_up(tick, deltas);
+        self.warmup.observe(tick.timestamp, self.strategy.as_ref());
+    }
+
+    pub(crate) fn on_liquidation(&mut self, liquidation: &Liquidation) {
+        self.strategy.on_liquidation(liquidation);
+    }
+
+    /// Ордера предыдущих тиков исполняются до решения стратегии на этом тике
+    pub(crate) fn on_tick(&mut self, idx: usize, tick: &TradeTick, deltas: &Deltas, board: &ShadowBoard) {
+        self.ticks += 1;
+        self.pnl.mark_price = tick.price;
+        let filled = self.match_orders(tick);
+        let action = self.strategy.on_tick(tick, deltas);
+        self.warmup.observe(tick.timestamp, self.strategy.as_ref());
+        self.apply(action);
+        let progress = self.pnl.open_position > 0.0 && self.ticks.is_multiple_of(PROGRESS_EVERY);
+        if filled || progress {
+            board.set(idx, self.pnl.clone());
+        }
+    }
+
+    pub(crate) fn on_timer(&mut self, now: DateTime<Utc>) {
+        let action = self.strategy.on_timer(now);
+        self.apply(action);
+    }
+
+    /// true, если был хотя бы один фил
+    fn match_orders(&mut self, tick: &TradeTick) -> bool {
+        let mut filled = false;
+        // Sell, выставленный филом buy на этом же тике, ждёт следующего
+        let resting_sell = self.sell;
+        if let Some(order) = self.buy
+            && tick.price <= order.price
+        {
+            self.buy = None;
+            filled = true;
+            self.pnl.entries += 1;
+            self.pnl.open_position += order.size;
+            self.pnl.position_cost += order.price * order.size;
+            if let Some(action) = self.strategy.on_buy_filled(order.price, order.size) {
+                self.apply(action);
+            }
+        }
+        if let Some(order) = resting_sell
+            && tick.price >= order.price
+        {
+            self.sell = None;
+            filled = true;
+            let size = order.size.min(self.pnl.open_position);
+            let avg_cost = self.pnl.position_cost / self.pnl.open_position;
+            self.pnl.realized_pnl += (order.price - avg_cost) * size;
+            self.pnl.open_position -= size;
+            self.pnl.position_cost -= avg_cost * size;
+            if self.pnl.open_position <= f64::EPSILON {
+                self.pnl.open_position = 0.0;
+                self.pnl.position_cost = 0.0;
+                self.pnl.round_trips += 1;
+            }
+            self.strategy.on_sell_filled(order.price, size);
+        }
+        filled
+    }
+
+    fn apply(&mut self, action: StrategyAction) {
+        match action {
+            StrategyAction::NoAction | StrategyAction::DetectSignal { .. } => {}
+            StrategyAction::Error(err) => {
+                log::debug!("shadow {} on {} failed: {}", self.pnl.strategy, self.pnl.symbol, err);
+                self.pnl.errors += 1;
+            }
+            StrategyAction::PlaceBuy { .. } if !self.warmup.is_ready() => {
+                self.strategy.on_buy_declined();
+                self.pnl.held_entries += 1;
+            }
+            StrategyAction::PlaceBuy { price, size } => match self.buy {
+                Some(_) => self.pnl.ignored_actions += 1,
+                None => {
+                    self.buy = Some(ShadowOrder { price, size });
+                    self.strategy.on_order_placed(Side::Bid, SHADOW_BUY_ID);
+                }
+            },
+            StrategyAction::ReplaceBuy { new_price } => match &mut self.buy {
+                Some(order) => order.price = new_price,
+                None => self.pnl.ignored_actions += 1,
+            },
+            StrategyAction::PlaceSell { price, size } => {
+                // Без позиции продавать нечего; повторный сигнал двигает уже стоящий sell
+                if self.pnl.open_position <= 0.0 {
+                    self.pnl.ignored_actions += 1;
+                    return;
+                }
+                match &mut self.sell {
+                    Some(order) => order.price = price,
+                    None => {
+                        self.sell = Some(ShadowOrder { price, size: size.min(self.pnl.open_position) });
+                        self.strategy.on_order_placed(Side::Ask, SHADOW_SELL_ID);
+                    }
+                }
+            }
+            // У тени один ордер на сторону: отменяется стоящий buy, иначе sell
+            StrategyAction::CancelOrder { .. } => {
+                if self.buy.take().is_none() && self.sell.take().is_none() {
+                    self.pnl.ignored_actions += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::strategy_adapter::HookAdapter;
+    use crate::strategy::moon_strategies::HookConfig;
+    use crate::testing::{ScenarioBuilder, SimulationHarness};
+
+    /// Hook без ожидания отката: buy уходит на тике детекта
+    fn hook() -> HookAdapter {
+        HookAdapter::new(HookConfig { hook_roll_back_wait: 0, ..Default::default() })
+    }
+
+    #[test]
+    fn test_simulated_fills_match_harness() {
+        let symbol = Symbol::new("BTC_USDT");
+        let factory: StrategyFactory = Arc::new(|_| vec![Box::new(hook()) as Box<dyn StrategyAdapter + Send>]);
+        let setup = ShadowSetup { factory: Some(factory), board: ShadowBoard::default() };
+        let mut shadows = setup.build(symbol);
+        assert_eq!(setup.board.snapshot()[0].strategy, "Hook");
+
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .flat(1_000)
+            .crash(10.0, 500)
+            .flat(500)
+            .ramp_to(100.0, 6_000)
+            .flat(500)
+            .build();
+        let deltas = Deltas::default();
+        for tick in &ticks {
+            shadows[0].on_tick(0, tick, &deltas, &setup.board);
+        }
+        let report = SimulationHarness::new(hook()).run(&ticks);
+
+        let pnl = setup.board.get(symbol, 0).expect("shadow on board");
+        assert_eq!(pnl.entries, 1);
+        assert_eq!(pnl.round_trips, 1);
+        assert_eq!(pnl.open_position, 0.0);
+        assert!(pnl.realized_pnl > 0.0);
+        assert!((pnl.realized_pnl - report.realized_pnl).abs() < 1e-9, "{:?}", pnl);
+        assert_eq!(setup.board.realized_by_strategy(), vec![("Hook".to_string(), pnl.realized_pnl)]);
+
+        // Символ выселен и вернулся: итоги продолжаются с доски
+        let again = setup.build(symbol);
+        assert_eq!(again[0].pnl.round_trips, 1);
+        assert!(ShadowSetup::default().build(symbol).is_empty());
+    }
+}
@@ -0,0 +1,579 @@
+//! Шард рантайма: поток со своими стратегиями и DeltaCalculator на каждый символ
+//!
+//! Шард читает два SPSC-кольца: тики от market-data насоса и филы от роутера.
+//! Всё, что стратегии решили сделать, уходит в роутер через исходящее кольцо шарда.
+
+use super::arbiter::{DetectionArbiter, DetectionPolicy};
+use super::latency::ActionTiming;
+use super::readiness::{ReadinessBoard, WarmupTracker, PROGRESS_EVERY};
+use super::shadow::{ShadowSetup, ShadowStrategy};
+use crate::backtest::delta_calculator::DeltaCalculator;
+use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::{StrategyAction, StrategyAdapter};
+use crate::base_classes::liquidations::Liquidation;
+use crate::base_classes::ring_buffer::{Consumer, Producer};
+use crate::base_classes::symbol::Symbol;
+use crate::base_classes::types::Side;
+use crate::execution::TimeInForce;
+use crate::risk::{RiskEvent, RiskEventBus};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+/// Ёмкость входящего кольца тиков одного шарда
+pub const SHARD_INBOX: usize = 1 << 16;
+/// Ёмкость кольца филов роутер -> шард
+pub const SHARD_CONTROL: usize = 1 << 10;
+/// Ёмкость исходящего кольца шард -> роутер
+pub const ROUTER_OUTBOX: usize = 1 << 12;
+
+/// Сколько пустых опросов крутиться на spin_loop перед yield
+const IDLE_SPINS: u32 = 256;
+
+/// Набор стратегий одного символа
+pub type StrategySet = Vec<Box<dyn StrategyAdapter + Send>>;
+
+/// Создаёт стратегии для символа при первом тике по нему (вызывается в потоке шарда)
+pub type StrategyFactory = Arc<dyn Fn(Symbol) -> StrategySet + Send + Sync>;
+
+/// Шард, которому принадлежит символ. Стабилен между запусками при том же числе шардов.
+pub fn shard_for(symbol: Symbol, shards: usize) -> usize {
+    assert!(shards > 0, "runtime needs at least one shard");
+    (fnv1a64(symbol.as_str().as_bytes()) % shards as u64) as usize
+}
+
+/// FNV-1a: фиксированный алгоритм, в отличие от `DefaultHasher`, который может смениться с версией Rust
+#[inline(always)]
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+/// Time-in-force, с которым стратегия хочет видеть заявку `action`
+fn time_in_force(strategy: &dyn StrategyAdapter, action: &StrategyAction) -> TimeInForce {
+    match action {
+        StrategyAction::PlaceBuy { .. } => strategy.time_in_force(Side::Bid),
+        StrategyAction::PlaceSell { .. } => strategy.time_in_force(Side::Ask),
+        _ => TimeInForce::Gtc,
+    }
+}
+
+/// Действие стратегии в очередь арбитража. Ошибка логируется и считается, в роутер не уходит
+fn enqueue(
+    pending: &mut Vec<(usize, StrategyAction)>,
+    signalled: &mut Vec<Instant>,
+    stats: &mut ShardStats,
+    symbol: Symbol,
+    idx: usize,
+    strategy: &dyn StrategyAdapter,
+    action: StrategyAction,
+) {
+    match action {
+        StrategyAction::NoAction => {}
+        StrategyAction::Error(err) => {
+            log::error!("{} on {} failed: {}", strategy.get_name(), symbol, err);
+            stats.strategy_errors += 1;
+        }
+        action => {
+            pending.push((idx, action));
+            signalled.push(Instant::now());
+        }
+    }
+}
+
+/// Вход стратегии устаревшего символа или не закончившей прогрев отклоняется, как отказ арбитра
+fn hold_entry(
+    tracker: &WarmupTracker,
+    stale: bool,
+    strategy: &mut (dyn StrategyAdapter + Send),
+    symbol: Symbol,
+    stats: &mut ShardStats,
+    action: StrategyAction,
+) -> StrategyAction {
+    if !matches!(action, StrategyAction::PlaceBuy { .. }) {
+        return action;
+    }
+    if stale {
+        log::debug!("{} entry on {} held: symbol data is stale", strategy.get_name(), symbol);
+        strategy.on_buy_declined();
+        stats.stale_entries += 1;
+        return StrategyAction::NoAction;
+    }
+    if tracker.is_ready() {
+        return action;
+    }
+    log::debug!("{} entry on {} held: warm-up after {} ticks", strategy.get_name(), symbol, tracker.ticks());
+    strategy.on_buy_declined();
+    stats.held_entries += 1;
+    StrategyAction::NoAction
+}
+
+/// Исполнение ордера стратегии, которое роутер возвращает в шард
+#[derive(Debug, Clone, Copy)]
+pub struct FillEvent {
+    pub symbol: Symbol,
+    /// Индекс стратегии в наборе символа (`RoutedAction::strategy`)
+    pub strategy: usize,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Ордер стратегии отправлен под `order_id` (`TrackedOrder::handle`)
+#[derive(Debug, Clone, Copy)]
+pub struct PlacedEvent {
+    pub symbol: Symbol,
+    pub strategy: usize,
+    pub side: Side,
+    pub order_id: u64,
+}
+
+#[derive(Debug)]
+pub enum ShardCommand {
+    /// Тик и момент его приёма насосом
+    Tick(TradeTick, Instant),
+    Fill(FillEvent),
+    /// Роутер отправил ордер стратегии: id для её `CancelOrder`
+    Placed(PlacedEvent),
+    /// Исторический тик прогрева: наполняет дельты и индикаторы, действий не даёт
+    Warmup(TradeTick),
+    /// Символ ушёл из набора: стратегии и дельты символа удаляются
+    Evict(Symbol),
+    /// Отсчёт открытого интереса символа для `Deltas::delta_oi_5m`
+    OpenInterest { symbol: Symbol, at: DateTime<Utc>, open_interest: f64 },
+    /// Ликвидация из ленты биржи для стратегий символа
+    Liquidation(Symbol, Liquidation),
+    /// Таймер рантайма: `on_timer` стратегий символов с живыми тиками
+    Timer(DateTime<Utc>),
+    /// Поток символа замолчал (`Some(%)` расширения стопов) или вернулся (`None`),
+    /// см. `runtime::stale`. Пока символ устарел, его входы отклоняются
+    Stale { symbol: Symbol, widen_stops_pct: Option<f64> },
+    /// Обработать всё, что уже в кольце, и завершить поток
+    Shutdown,
+}
+
+/// Действие стратегии, адресованное роутеру
+#[derive(Debug, Clone)]
+pub struct RoutedAction {
+    pub shard: usize,
+    pub symbol: Symbol,
+    /// Индекс стратегии в наборе символа - по нему роутер адресует филы обратно
+    pub strategy: usize,
+    pub strategy_name: String,
+    pub action: StrategyAction,
+    /// Time-in-force заявки по настройкам стратегии; для остальных действий GTC
+    pub tif: TimeInForce,
+    /// Время тика (фила, таймера), породившего действие
+    pub source_time: DateTime<Utc>,
+    pub timing: ActionTiming,
+}
+
+/// Счётчики шарда на момент остановки
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShardStats {
+    pub shard: usize,
+    pub symbols: usize,
+    pub ticks: u64,
+    /// Исторические тики прогрева
+    pub warmup_ticks: u64,
+    pub fills: u64,
+    /// Таймеры рантайма, разосланные стратегиям шарда
+    pub timers: u64,
+    pub actions: u64,
+    /// Входы, отклонённые арбитром детектов
+    pub declined: u64,
+    /// Входы стратегий, ещё не закончивших прогрев (`StrategyAdapter::warmup_requirements`)
+    pub held_entries: u64,
+    /// Входы символов, чей поток устарел (`ShardCommand::Stale`)
+    pub stale_entries: u64,
+    /// `StrategyAction::Error`: в роутер не уходят
+    pub strategy_errors: u64,
+    /// Филы стратегий, которых в шарде нет: в лог и `RiskEvent::OrphanFill`
+    pub orphan_fills: u64,
+}
+
+struct SymbolSlot {
+    strategies: StrategySet,
+    deltas: DeltaCalculator,
+    arbiter: DetectionArbiter,
+    /// Прогрев стратегий по индексу в наборе
+    warmup: Vec<WarmupTracker>,
+    /// Был живой тик: история прогрева после него легла бы в дельты не по порядку
+    live: bool,
+    /// Теневые копии с альтернативным конфигом: симулированные филы, без роутера
+    shadows: Vec<ShadowStrategy>,
+    /// Поток символа устарел: входы отклоняются, стопы расширены
+    stale: bool,
+}
+
+impl SymbolSlot {
+    /// Стратегии символа создаются при первом тике по нему (живом или прогрева)
+    fn new(
+        factory: &StrategyFactory,
+        detection: &DetectionPolicy,
+        board: &ReadinessBoard,
+        shadow: &ShadowSetup,
+        symbol: Symbol,
+    ) -> Self {
+        let strategies = factory(symbol);
+        let arbiter = DetectionArbiter::new(detection.clone(), strategies.iter().map(|s| s.get_name()));
+        let warmup: Vec<_> = strategies.iter().map(|s| WarmupTracker::new(s.warmup_requirements())).collect();
+        for (idx, (tracker, strategy)) in warmup.iter().zip(&strategies).enumerate() {
+            board.set(idx, tracker.readiness(symbol, strategy.as_ref()));
+        }
+        Self {
+            strategies,
+            deltas: DeltaCalculator::new(),
+            arbiter,
+            warmup,
+            live: false,
+            shadows: shadow.build(symbol),
+            stale: false,
+        }
+    }
+
+    /// Тик (прогрева или живой) в счётчики прогрева; доска обновляется при готовности
+    /// и раз в `PROGRESS_EVERY` тиков
+    fn observe_warmup(&mut self, idx: usize, symbol: Symbol, at: DateTime<Utc>, board: &ReadinessBoard) {
+        let tracker = &mut self.warmup[idx];
+        if tracker.is_ready() {
+            return;
+        }
+        let strategy = self.strategies[idx].as_ref();
+        if tracker.observe(at, strategy) {
+            log::info!("{} on {} ready after {} ticks", strategy.get_name(), symbol, tracker.ticks());
+            board.set(idx, tracker.readiness(symbol, strategy));
+        } else if tracker.ticks().is_multiple_of(PROGRESS_EVERY) {
+            board.set(idx, tracker.readiness(symbol, strategy));
+        }
+    }
+}
+
+/// Общее для шардов рантайма; `id` у каждого свой
+#[derive(Clone)]
+pub(crate) struct ShardSetup {
+    pub id: usize,
+    pub factory: StrategyFactory,
+    pub detection: DetectionPolicy,
+    pub readiness: ReadinessBoard,
+    pub shadow: ShadowSetup,
+    /// Шина событий риска рантайма; задаётся и после старта (`set_risk_events`)
+    pub events: SharedRiskEvents,
+}
+
+/// Шина событий риска, которую рантайм может подключить к уже запущенным шардам
+pub(crate) type SharedRiskEvents = Arc<RwLock<Option<RiskEventBus>>>;
+
+pub(crate) struct ShardWorker {
+    id: usize,
+    factory: StrategyFactory,
+    detection: DetectionPolicy,
+    readiness: ReadinessBoard,
+    shadow: ShadowSetup,
+    events: SharedRiskEvents,
+    symbols: HashMap<Symbol, SymbolSlot>,
+    /// Действия текущего тика до арбитража (буфер переиспользуется)
+    pending: Vec<(usize, StrategyAction)>,
+    signalled: Vec<Instant>,
+    inbox: Consumer<ShardCommand, SHARD_INBOX>,
+    control: Consumer<ShardCommand, SHARD_CONTROL>,
+    outbox: Producer<RoutedAction, ROUTER_OUTBOX>,
+    stats: ShardStats,
+}
+
+impl ShardWorker {
+    pub(crate) fn new(
+        setup: ShardSetup,
+        inbox: Consumer<ShardCommand, SHARD_INBOX>,
+        control: Consumer<ShardCommand, SHARD_CONTROL>,
+        outbox: Producer<RoutedAction, ROUTER_OUTBOX>,
+    ) -> Self {
+        let ShardSetup { id, factory, detection, readiness, shadow, events } = setup;
+        Self {
+            id,
+            factory,
+            detection,
+            readiness,
+            shadow,
+            events,
+            symbols: HashMap::new(),
+            pending: Vec::new(),
+            signalled: Vec::new(),
+            inbox,
+            control,
+            outbox,
+            stats: ShardStats { shard: id, ..Default::default() },
+        }
+    }
+
+    /// Цикл потока шарда. Филы обрабатываются раньше тиков, чтобы стратегия
+    /// видела свою позицию до следующего решения.
+    pub(crate) fn run(mut self) -> ShardStats {
+        let mut idle = 0u32;
+        loop {
+            let command = match self.control.try_pop() {
+                Ok(command) => command,
+                Err(()) => match self.inbox.try_pop() {
+                    Ok(command) => command,
+                    Err(()) => {
+                        idle = idle.saturating_add(1);
+                        if idle < IDLE_SPINS {
+                            core::hint::spin_loop();
+                        } else {
+                            std::thread::yield_now();
+                        }
+                        continue;
+                    }
+                },
+            };
+            idle = 0;
+            match command {
+                ShardCommand::Tick(tick, received) => self.on_tick(&tick, received),
+                ShardCommand::Fill(fill) => self.on_fill(fill),
+                ShardCommand::Placed(placed) => self.on_placed(placed),
+                ShardCommand::Timer(now) => self.on_timer(now),
+                ShardCommand::Warmup(tick) => self.on_warmup(&tick),
+                ShardCommand::OpenInterest { symbol, at, open_interest } => {
+                    let slot = self
+                        .symbols
+                        .entry(symbol)
+                        .or_insert_with(|| {
+                            SymbolSlot::new(&self.factory, &self.detection, &self.readiness, &self.shadow, symbol)
+                        });
+                    slot.deltas.update_open_interest(at, open_interest);
+                }
+                ShardCommand::Liquidation(symbol, liquidation) => {
+                    let slot = self
+                        .symbols
+                        .entry(symbol)
+                        .or_insert_with(|| {
+                            SymbolSlot::new(&self.factory, &self.detection, &self.readiness, &self.shadow, symbol)
+                        });
+                    for strategy in slot.strategies.iter_mut() {
+                        strategy.on_liquidation(&liquidation);
+                    }
+                    for shadow in slot.shadows.iter_mut() {
+                        shadow.on_liquidation(&liquidation);
+                    }
+                }
+                ShardCommand::Stale { symbol, widen_stops_pct } => {
+                    // Символ без стратегий ещё не торговал: расширять нечего
+                    if let Some(slot) = self.symbols.get_mut(&symbol) {
+                        slot.stale = widen_stops_pct.is_some();
+                        for strategy in slot.strategies.iter_mut() {
+                            strategy.on_stale(widen_stops_pct);
+                        }
+                    }
+                }
+                ShardCommand::Evict(symbol) => {
+                    if self.symbols.remove(&symbol).is_some() {
+                        self.readiness.remove_symbol(symbol);
+                        log::info!("shard {}: strategies for {} evicted", self.id, symbol);
+                    }
+                }
+                ShardCommand::Shutdown => break,
+            }
+        }
+        // Филы, пришедшие до остановки, всё ещё должны дойти до стратегий
+        while let Ok(command) = self.control.try_pop() {
+            if let ShardCommand::Fill(fill) = command {
+                self.on_fill(fill);
+            }
+        }
+        self.stats.symbols = self.symbols.len();
+        self.stats
+    }
+
+    fn on_warmup(&mut self, tick: &TradeTick) {
+        let slot = self
+            .symbols
+            .entry(tick.symbol)
+            .or_insert_with(|| {
+                SymbolSlot::new(&self.factory, &self.detection, &self.readiness, &self.shadow, tick.symbol)
+            });
+        if slot.live {
+            log::warn!("warm-up tick for {} after live ticks ignored", tick.symbol);
+            return;
+        }
+        slot.deltas.update(tick, tick.timestamp);
+        let deltas = slot.deltas.calculate_deltas(tick.price, tick.timestamp);
+        for idx in 0..slot.strategies.len() {
+            slot.strategies[idx].warm_up(tick, &deltas);
+            slot.observe_warmup(idx, tick.symbol, tick.timestamp, &self.readiness);
+        }
+        for shadow in slot.shadows.iter_mut() {
+            shadow.warm_up(tick, &deltas);
+        }
+        self.stats.warmup_ticks += 1;
+    }
+
+    fn on_tick(&mut self, tick: &TradeTick, received: Instant) {
+        self.stats.ticks += 1;
+        let slot = self
+            .symbols
+            .entry(tick.symbol)
+            .or_insert_with(|| {
+                SymbolSlot::new(&self.factory, &self.detection, &self.readiness, &self.shadow, tick.symbol)
+            });
+        slot.live = true;
+        slot.deltas.update(tick, tick.timestamp);
+        let deltas = slot.deltas.calculate_deltas(tick.price, tick.timestamp);
+        self.pending.clear();
+        self.signalled.clear();
+        for idx in 0..slot.strategies.len() {
+            let action = slot.strategies[idx].on_tick(tick, &deltas);
+            slot.observe_warmup(idx, tick.symbol, tick.timestamp, &self.readiness);
+            let strategy = slot.strategies[idx].as_mut();
+            let action = hold_entry(&slot.warmup[idx], slot.stale, strategy, tick.symbol, &mut self.stats, action);
+            enqueue(&mut self.pending, &mut self.signalled, &mut self.stats, tick.symbol, idx, strategy, action);
+        }
+        self.dispatch(tick.symbol, tick.timestamp, received);
+        // Тени решают после отправки живых действий в роутер, чтобы не задерживать их
+        let slot = self.symbols.get_mut(&tick.symbol).expect("slot inserted above");
+        for (idx, shadow) in slot.shadows.iter_mut().enumerate() {
+            shadow.on_tick(idx, tick, &deltas, &self.shadow.board);
+        }
+    }
+
+    /// Таймауты стратегий без тиков. Символы без живых тиков пропускаются:
+    /// их стратегии ещё не торговали
+    fn on_timer(&mut self, now: DateTime<Utc>) {
+        let received = Instant::now();
+        self.stats.timers += 1;
+        let symbols: Vec<Symbol> = self.symbols.iter().filter(|(_, slot)| slot.live).map(|(symbol, _)| *symbol).collect();
+        for symbol in symbols {
+            self.pending.clear();
+            self.signalled.clear();
+            let slot = self.symbols.get_mut(&symbol).expect("symbol collected above");
+            for (idx, strategy) in slot.strategies.iter_mut().enumerate() {
+                let action = strategy.on_timer(now);
+                let action = hold_entry(&slot.warmup[idx], slot.stale, strategy.as_mut(), symbol, &mut self.stats, action);
+                enqueue(&mut self.pending, &mut self.signalled, &mut self.stats, symbol, idx, strategy.as_ref(), action);
+            }
+            self.dispatch(symbol, now, received);
+            let slot = self.symbols.get_mut(&symbol).expect("symbol collected above");
+            for shadow in slot.shadows.iter_mut() {
+                shadow.on_timer(now);
+            }
+        }
+    }
+
+    /// Арбитраж и отправка в роутер действий из `pending`, порождённых событием в `source_time`
+    fn dispatch(&mut self, symbol: Symbol, source_time: DateTime<Utc>, received: Instant) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let Some(slot) = self.symbols.get_mut(&symbol) else {
+            return;
+        };
+
+        // Входы одного события решаются вместе, чтобы Priority видел всех кандидатов
+        let strategies = &mut slot.strategies;
+        let stats = &mut self.stats;
+        slot.arbiter.arbitrate(source_time, &mut self.pending, |idx| {
+            log::debug!("{} entry on {} declined by detection arbiter", strategies[idx].get_name(), symbol);
+            strategies[idx].on_buy_declined();
+            stats.declined += 1;
+        });
+
+        for ((idx, action), signalled) in self.pending.drain(..).zip(self.signalled.drain(..)) {
+            if matches!(action, StrategyAction::NoAction) {
+                continue;
+            }
+            self.stats.actions += 1;
+            self.outbox.push_spin(RoutedAction {
+                shard: self.id,
+                symbol,
+                strategy: idx,
+                strategy_name: slot.strategies[idx].get_name().to_string(),
+                tif: time_in_force(slot.strategies[idx].as_ref(), &action),
+                action,
+                source_time,
+                timing: ActionTiming { received, signalled },
+            });
+        }
+    }
+
+    fn on_placed(&mut self, placed: PlacedEvent) {
+        match self.symbols.get_mut(&placed.symbol).and_then(|slot| slot.strategies.get_mut(placed.strategy)) {
+            Some(strategy) => strategy.on_order_placed(placed.side, placed.order_id),
+            None => log::warn!(
+                "shard {}: order {} placed for unknown strategy {} on {}",
+                self.id,
+                placed.order_id,
+                placed.strategy,
+                placed.symbol
+            ),
+        }
+    }
+
+    /// Фил стратегии, которой нет: остальные символы шарда продолжают торговать,
+    /// а расхождение позиции уходит в лог и на шину риска
+    fn on_orphan_fill(&mut self, fill: FillEvent) {
+        log::error!(
+            "shard {}: {:?} fill {} @ {} for unknown strategy {} on {}; position needs reconciling",
+            self.id,
+            fill.side,
+            fill.size,
+            fill.price,
+            fill.strategy,
+            fill.symbol
+        );
+        self.stats.orphan_fills += 1;
+        if let Some(events) = self.events.read().expect("risk events lock poisoned").as_ref() {
+            events.publish(RiskEvent::OrphanFill {
+                symbol: fill.symbol,
+                strategy: fill.strategy,
+                side: fill.side,
+                price: fill.price,
+                size: fill.size,
+            });
+        }
+    }
+
+    fn on_fill(&mut self, fill: FillEvent) {
+        let received = Instant::now();
+        self.stats.fills += 1;
+        let Some(strategy) = self
+            .symbols
+            .get_mut(&fill.symbol)
+            .and_then(|slot| slot.strategies.get_mut(fill.strategy))
+        else {
+            self.on_orphan_fill(fill);
+            return;
+        };
+        let follow_up = match fill.side {
+            Side::Bid => strategy.on_buy_filled(fill.price, fill.size),
+            Side::Ask => {
+                strategy.on_sell_filled(fill.price, fill.size);
+                None
+            }
+        };
+        if let Some(StrategyAction::Error(err)) = &follow_up {
+            log::error!("{} on {} failed after fill: {}", strategy.get_name(), fill.symbol, err);
+            self.stats.strategy_errors += 1;
+            return;
+        }
+        if let Some(action) = follow_up {
+            self.stats.actions += 1;
+            self.outbox.push_spin(RoutedAction {
+                shard: self.id,
+                symbol: fill.symbol,
+                strategy: fill.strategy,
+                strategy_name: strategy.get_name().to_string(),
+                tif: time_in_force(strategy.as_ref(), &action),
+                action,
+                source_time: Utc::now(),
+                timing: ActionTiming {
+                    received,
+                    signalled: Instant::now(),
+                },
+            });
+        }
+    }
+}
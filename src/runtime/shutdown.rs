@@ -0,0 +1,288 @@
+//! Координатор остановки по SIGINT/SIGTERM
+//!
+//! Порядок: закрыть новые входы -> шаги ордеров (снять / закрыть позиции по конфигу) ->
+//! шаги сброса (хранилища, журналы) -> выход. Упавший шаг не прерывает остальные:
+//! журнал должен сброситься, даже если биржа не ответила на отмену.
+
+use crate::execution::OrderManager;
+use crate::logging::quote::QuoteLogHandle;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Что делать с ордерами и позициями при остановке
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownOrders {
+    /// Оставить стоящие ордера на бирже
+    #[default]
+    Leave,
+    /// Снять все стоящие ордера
+    Cancel,
+    /// Снять ордера и закрыть открытые позиции
+    Flatten,
+}
+
+fn default_step_timeout_ms() -> u64 {
+    5_000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShutdownConfig {
+    #[serde(default)]
+    pub orders: ShutdownOrders,
+    /// Сколько ждать один шаг, прежде чем считать его проваленным
+    #[serde(default = "default_step_timeout_ms")]
+    pub step_timeout_ms: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            orders: ShutdownOrders::default(),
+            step_timeout_ms: default_step_timeout_ms(),
+        }
+    }
+}
+
+/// Флаг "новые входы разрешены", общий для роутера и координатора
+#[derive(Debug, Clone)]
+pub struct EntryGate(Arc<AtomicBool>);
+
+impl EntryGate {
+    pub fn new() -> Self {
+        Self(Arc::new(AtomicBool::new(true)))
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub fn close(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+
+    /// Снова пропускает входы. Для временных гейтов (окно обслуживания биржи);
+    /// гейт остановки после `close` не открывается
+    pub fn open(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+impl Default for EntryGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownPhase {
+    /// Снятие ордеров и закрытие позиций
+    Orders,
+    /// Сброс хранилищ и журналов
+    Flush,
+}
+
+/// Шаг остановки. Шаги фазы выполняются по порядку регистрации.
+#[async_trait]
+pub trait ShutdownStep: Send + Sync {
+    fn name(&self) -> &str;
+    async fn run(&self, orders: ShutdownOrders) -> Result<()>;
+}
+
+#[derive(Debug)]
+pub struct StepOutcome {
+    pub phase: ShutdownPhase,
+    pub name: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    pub steps: Vec<StepOutcome>,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.steps.iter().all(|step| step.error.is_none())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &StepOutcome> {
+        self.steps.iter().filter(|step| step.error.is_some())
+    }
+}
+
+pub struct ShutdownCoordinator {
+    config: ShutdownConfig,
+    entries: EntryGate,
+    steps: Vec<(ShutdownPhase, Arc<dyn ShutdownStep>)>,
+}
+
+impl ShutdownCoordinator {
+    /// `entries` - тот же флаг, что у `OrderRouter`, чтобы остановка закрыла входы
+    pub fn new(config: ShutdownConfig, entries: EntryGate) -> Self {
+        Self {
+            config,
+            entries,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, phase: ShutdownPhase, step: Arc<dyn ShutdownStep>) {
+        self.steps.push((phase, step));
+    }
+
+    /// Ждёт SIGINT или SIGTERM и возвращает имя сигнала
+    pub async fn wait_for_signal() -> Result<&'static str> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut terminate = signal(SignalKind::terminate())?;
+            tokio::select! {
+                res = tokio::signal::ctrl_c() => {
+                    res?;
+                    Ok("SIGINT")
+                }
+                _ = terminate.recv() => Ok("SIGTERM"),
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            tokio::signal::ctrl_c().await?;
+            Ok("ctrl-c")
+        }
+    }
+
+    /// Выполняет остановку целиком. Повторный вызов снова прогонит все шаги.
+    pub async fn shutdown(&self) -> ShutdownReport {
+        self.entries.close();
+        let timeout = Duration::from_millis(self.config.step_timeout_ms);
+        let mut report = ShutdownReport::default();
+
+        for phase in [ShutdownPhase::Orders, ShutdownPhase::Flush] {
+            for (_, step) in self.steps.iter().filter(|(p, _)| *p == phase) {
+                let result = match tokio::time::timeout(timeout, step.run(self.config.orders)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow!("timed out after {:?}", timeout)),
+                };
+                let error = result.err().map(|err| {
+                    log::error!("shutdown step '{}' ({:?}) failed: {:#}", step.name(), phase, err);
+                    format!("{:#}", err)
+                });
+                report.steps.push(StepOutcome {
+                    phase,
+                    name: step.name().to_string(),
+                    error,
+                });
+            }
+        }
+        report
+    }
+}
+
+#[async_trait]
+impl ShutdownStep for OrderManager {
+    fn name(&self) -> &str {
+        "order_manager"
+    }
+
+    async fn run(&self, orders: ShutdownOrders) -> Result<()> {
+        match orders {
+            ShutdownOrders::Leave => Ok(()),
+            ShutdownOrders::Cancel | ShutdownOrders::Flatten => {
+                let cancelled = self.cancel_all().await?;
+                log::info!("shutdown: cancelled {} resting orders", cancelled);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ShutdownStep for QuoteLogHandle {
+    fn name(&self) -> &str {
+        "quote_log"
+    }
+
+    async fn run(&self, _orders: ShutdownOrders) -> Result<()> {
+        self.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// (шаг, политика ордеров, были ли входы открыты в момент шага)
+    type Calls = Arc<Mutex<Vec<(&'static str, ShutdownOrders, bool)>>>;
+
+    struct Recorder {
+        name: &'static str,
+        calls: Calls,
+        entries: EntryGate,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl ShutdownStep for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn run(&self, orders: ShutdownOrders) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((self.name, orders, self.entries.is_open()));
+            if self.fail {
+                anyhow::bail!("exchange unreachable");
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_phases_run_in_order_after_entries_close() {
+        let entries = EntryGate::new();
+        let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+        let config = ShutdownConfig {
+            orders: ShutdownOrders::Cancel,
+            ..Default::default()
+        };
+        let mut coordinator = ShutdownCoordinator::new(config, entries.clone());
+        let step = |name, fail| {
+            Arc::new(Recorder {
+                name,
+                calls: calls.clone(),
+                entries: entries.clone(),
+                fail,
+            })
+        };
+        // Регистрация вперемешку: фазы всё равно идут Orders -> Flush
+        coordinator.register(ShutdownPhase::Flush, step("journal", false));
+        coordinator.register(ShutdownPhase::Orders, step("cancel", true));
+        coordinator.register(ShutdownPhase::Flush, step("storage", false));
+
+        let report = coordinator.shutdown().await;
+
+        assert!(!entries.is_open());
+        let calls = calls.lock().unwrap().clone();
+        let names: Vec<_> = calls.iter().map(|c| c.0).collect();
+        assert_eq!(names, ["cancel", "journal", "storage"]);
+        assert!(calls.iter().all(|c| c.1 == ShutdownOrders::Cancel && !c.2));
+        assert!(!report.is_clean());
+        let failed: Vec<_> = report.failures().map(|s| s.name.as_str()).collect();
+        assert_eq!(failed, ["cancel"]);
+    }
+
+    #[test]
+    fn test_config_parses_from_yaml() {
+        let config: ShutdownConfig = serde_yaml::from_str("orders: flatten").unwrap();
+        assert_eq!(config.orders, ShutdownOrders::Flatten);
+        assert_eq!(config.step_timeout_ms, 5_000);
+    }
+}
@@ -0,0 +1,164 @@
+//! Сторож тишины по символу: поток одного символа замолчал, а остальные идут
+//!
+//! Если по символу `silent_ms` нет тиков, а хотя бы `min_flowing` других символов за то
+//! же время тикали, символ помечается устаревшим: насос шлёт шарду
+//! `ShardCommand::Stale`, шард отклоняет новые входы стратегий символа, а стратегии
+//! расширяют стопы на `widen_stops_pct` % (`StrategyAdapter::on_stale`), чтобы первый
+//! тик после разрыва не выбил позицию по цене, до которой рынок дошёл без нас.
+//! Тишина всех символов - обрыв соединения, а не устаревший символ: его разбирает
+//! переподключение коллектора, и символы здесь не помечаются.
+//!
+//! Символ возвращается после `recovery_ticks` тиков: первые тики после разрыва считают
+//! дельты через дыру в истории, поэтому ещё проходят с расширенными стопами и без входов.
+//! Команда восстановления уходит в шард вслед за последним из них.
+
+use crate::base_classes::symbol::Symbol;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StaleConfig {
+    /// Сколько символ может молчать, мс
+    pub silent_ms: u64,
+    /// Сколько других символов должно тикать, чтобы тишина считалась тишиной символа
+    pub min_flowing: usize,
+    /// На сколько % стратегии отодвигают стопы устаревшего символа
+    pub widen_stops_pct: f64,
+    /// Как часто `ShardedRuntime::poll_stale` проверяет символы, мс
+    pub check_interval_ms: u64,
+    /// Сколько тиков после разрыва символ ещё остаётся устаревшим
+    pub recovery_ticks: u32,
+}
+
+impl Default for StaleConfig {
+    fn default() -> Self {
+        Self { silent_ms: 30_000, min_flowing: 1, widen_stops_pct: 1.0, check_interval_ms: 1_000, recovery_ticks: 5 }
+    }
+}
+
+/// Символ замолчал или вернулся
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StaleChange {
+    Stale { symbol: Symbol, silent_ms: u64 },
+    Recovered { symbol: Symbol, silent_ms: u64 },
+}
+
+/// Итог проверки: новые устаревшие символы или обрыв всего потока
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StaleCheck {
+    pub changes: Vec<StaleChange>,
+    /// Тикающих символов меньше `min_flowing`: молчит соединение, символы не помечены
+    pub outage: bool,
+}
+
+/// Устаревший символ: длина разрыва и тики после него
+#[derive(Debug, Clone, Copy, Default)]
+struct Silence {
+    gap_ms: Option<u64>,
+    ticks: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct StaleWatchdog {
+    config: StaleConfig,
+    last_tick_ms: HashMap<Symbol, u64>,
+    stale: HashMap<Symbol, Silence>,
+}
+
+impl StaleWatchdog {
+    pub fn new(config: StaleConfig) -> Self {
+        assert!(config.silent_ms > 0, "stale silent_ms must be positive");
+        assert!(config.widen_stops_pct >= 0.0, "stale widen_stops_pct must not be negative");
+        Self { config, last_tick_ms: HashMap::new(), stale: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &StaleConfig {
+        &self.config
+    }
+
+    /// Тик символа; `Recovered` на `recovery_ticks`-м тике после разрыва
+    pub fn on_tick(&mut self, symbol: Symbol, now_ms: u64) -> Option<StaleChange> {
+        let previous = self.last_tick_ms.insert(symbol, now_ms);
+        if self.stale.is_empty() {
+            return None;
+        }
+        let silence = self.stale.get_mut(&symbol)?;
+        let gap_ms = *silence.gap_ms.get_or_insert(now_ms.saturating_sub(previous.unwrap_or(now_ms)));
+        silence.ticks += 1;
+        if silence.ticks < self.config.recovery_ticks {
+            return None;
+        }
+        self.stale.remove(&symbol);
+        Some(StaleChange::Recovered { symbol, silent_ms: gap_ms })
+    }
+
+    /// Символ больше не торгуется (выселен, снят с торгов)
+    pub fn forget(&mut self, symbol: Symbol) {
+        self.last_tick_ms.remove(&symbol);
+        self.stale.remove(&symbol);
+    }
+
+    pub fn is_stale(&self, symbol: Symbol) -> bool {
+        self.stale.contains_key(&symbol)
+    }
+
+    pub fn stale_symbols(&self) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = self.stale.keys().copied().collect();
+        symbols.sort();
+        symbols
+    }
+
+    pub fn check(&mut self, now_ms: u64) -> StaleCheck {
+        let silent = |last: u64| now_ms.saturating_sub(last) >= self.config.silent_ms;
+        let flowing = self.last_tick_ms.values().filter(|last| !silent(**last)).count();
+        if flowing < self.config.min_flowing {
+            return StaleCheck { changes: Vec::new(), outage: !self.last_tick_ms.is_empty() };
+        }
+        let mut silenced: Vec<(Symbol, u64)> = self
+            .last_tick_ms
+            .iter()
+            .filter(|(symbol, last)| silent(**last) && !self.stale.contains_key(*symbol))
+            .map(|(symbol, last)| (*symbol, now_ms - *last))
+            .collect();
+        silenced.sort();
+        self.stale.extend(silenced.iter().map(|(symbol, _)| (*symbol, Silence::default())));
+        let changes = silenced.into_iter().map(|(symbol, silent_ms)| StaleChange::Stale { symbol, silent_ms }).collect();
+        StaleCheck { changes, outage: false }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_silent_symbol_goes_stale_only_while_others_flow() {
+        let (eth, btc) = (Symbol::new("ETH_USDT"), Symbol::new("BTC_USDT"));
+        let config = StaleConfig { silent_ms: 1_000, recovery_ticks: 2, ..StaleConfig::default() };
+        let mut watchdog = StaleWatchdog::new(config);
+        watchdog.on_tick(eth, 0);
+        watchdog.on_tick(btc, 0);
+        assert_eq!(watchdog.check(500), StaleCheck::default());
+
+        // Молчат оба - обрыв соединения, символы не помечаются
+        let outage = watchdog.check(5_000);
+        assert!(outage.outage && outage.changes.is_empty());
+
+        watchdog.on_tick(btc, 5_000);
+        let check = watchdog.check(5_100);
+        assert_eq!(check.changes, vec![StaleChange::Stale { symbol: eth, silent_ms: 5_100 }]);
+        assert!(watchdog.is_stale(eth) && !watchdog.is_stale(btc));
+        // Повторная проверка не дублирует тревогу
+        assert!(watchdog.check(5_200).changes.is_empty());
+
+        // Первый тик после разрыва ещё устаревший, второй возвращает символ
+        assert_eq!(watchdog.on_tick(eth, 6_000), None);
+        assert!(watchdog.is_stale(eth));
+        assert_eq!(watchdog.on_tick(eth, 6_010), Some(StaleChange::Recovered { symbol: eth, silent_ms: 6_000 }));
+        assert_eq!(watchdog.on_tick(eth, 6_020), None);
+        assert!(watchdog.stale_symbols().is_empty());
+
+        watchdog.forget(btc);
+        assert!(watchdog.check(20_000).outage);
+    }
+}
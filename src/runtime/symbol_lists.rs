@@ -0,0 +1,306 @@
+//! Чёрный и белые списки символов с сохранением на диск
+//!
+//! Чёрный список общий: символ из него не торгуется ни одной стратегией и не попадает в
+//! набор `UniverseScanner`. Белый список задаётся на стратегию: если он есть и не пуст,
+//! стратегия создаётся только для перечисленных символов. Списки правятся на лету
+//! текстовыми командами (`/blacklist add BTC_USDT`) из Telegram или control API; каждая
+//! правка сразу пишется в файл и, если подключён `ConfigAudit`, в журнал изменений
+//! конфигурации с источником команды.
+//!
+//! Стратегии символа создаются один раз, на первом тике, поэтому правка списка выселяет
+//! символ из рантайма (`ShardedRuntime::on_lists_changed`): следующий тик пересоздаст его
+//! стратегии уже по новым спискам.
+
+use crate::base_classes::symbol::Symbol;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use super::audit::{ChangeOrigin, ConfigAudit};
+use super::shard::{StrategyFactory, StrategySet};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SymbolLists {
+    #[serde(default)]
+    pub blacklist: BTreeSet<String>,
+    /// Стратегия -> разрешённые символы
+    #[serde(default)]
+    pub whitelists: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl SymbolLists {
+    pub fn is_blacklisted(&self, symbol: Symbol) -> bool {
+        self.blacklist.contains(symbol.as_str())
+    }
+
+    /// Может ли стратегия торговать символ
+    pub fn allows(&self, strategy: &str, symbol: Symbol) -> bool {
+        if self.is_blacklisted(symbol) {
+            return false;
+        }
+        self.whitelists
+            .get(strategy)
+            .is_none_or(|allowed| allowed.is_empty() || allowed.contains(symbol.as_str()))
+    }
+
+    /// Пустой файл или его отсутствие - пустые списки; битый файл - ошибка
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse symbol lists at {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("failed to read symbol lists at {}", path.display())),
+        }
+    }
+
+    /// Через временный файл и rename, чтобы падение не оставило обрезанный файл
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write symbol lists to {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("failed to move symbol lists into {}", path.display()))
+    }
+
+    fn render(&self) -> String {
+        let join = |set: &BTreeSet<String>| {
+            if set.is_empty() { "-".to_string() } else { set.iter().cloned().collect::<Vec<_>>().join(", ") }
+        };
+        let mut out = format!("blacklist: {}", join(&self.blacklist));
+        for (strategy, allowed) in &self.whitelists {
+            out.push_str(&format!("\nwhitelist {}: {}", strategy, join(allowed)));
+        }
+        out
+    }
+}
+
+/// Команда правки списков из Telegram или control API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListCommand {
+    Show,
+    BlacklistAdd(Vec<Symbol>),
+    BlacklistRemove(Vec<Symbol>),
+    WhitelistAdd { strategy: String, symbols: Vec<Symbol> },
+    WhitelistRemove { strategy: String, symbols: Vec<Symbol> },
+    /// Убрать белый список стратегии целиком: она снова торгует всё
+    WhitelistClear { strategy: String },
+}
+
+impl ListCommand {
+    /// `/lists`, `/blacklist add|remove SYM..`, `/whitelist add|remove STRATEGY SYM..`,
+    /// `/whitelist clear STRATEGY`. Ведущий `/` необязателен, символы приводятся к верхнему регистру.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut words = text.split_whitespace();
+        let head = words.next().unwrap_or_default().trim_start_matches('/').to_ascii_lowercase();
+        let op = words.next().map(str::to_ascii_lowercase);
+        let rest: Vec<&str> = words.collect();
+        let symbols = |words: &[&str]| -> Result<Vec<Symbol>> {
+            if words.is_empty() {
+                bail!("'{}' needs at least one symbol", text.trim());
+            }
+            Ok(words.iter().map(|w| Symbol::new(&w.to_ascii_uppercase())).collect())
+        };
+        Ok(match (head.as_str(), op.as_deref()) {
+            ("lists", None) => Self::Show,
+            ("blacklist", Some("add")) => Self::BlacklistAdd(symbols(&rest)?),
+            ("blacklist", Some("remove")) => Self::BlacklistRemove(symbols(&rest)?),
+            ("whitelist", Some(op @ ("add" | "remove" | "clear"))) => {
+                let Some((strategy, rest)) = rest.split_first() else {
+                    bail!("'{}' needs a strategy name", text.trim());
+                };
+                let strategy = strategy.to_string();
+                match op {
+                    "add" => Self::WhitelistAdd { strategy, symbols: symbols(rest)? },
+                    "remove" => Self::WhitelistRemove { strategy, symbols: symbols(rest)? },
+                    _ => Self::WhitelistClear { strategy },
+                }
+            }
+            _ => bail!("unknown symbol list command '{}'", text.trim()),
+        })
+    }
+}
+
+/// Что изменила команда: эти символы надо пересоздать в рантайме
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListChange {
+    pub symbols: Vec<Symbol>,
+    /// Ответ оператору
+    pub reply: String,
+}
+
+/// Списки, общие для фабрики стратегий, сканера и обработчика команд.
+/// Лок берётся только при создании стратегий символа и при скане, не на тиковом пути.
+#[derive(Debug, Clone)]
+pub struct SymbolListStore {
+    path: PathBuf,
+    lists: Arc<RwLock<SymbolLists>>,
+    audit: Option<ConfigAudit>,
+}
+
+impl SymbolListStore {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let lists = SymbolLists::load(&path)?;
+        log::info!(
+            "symbol lists from {}: {} blacklisted, {} strategy whitelists",
+            path.display(),
+            lists.blacklist.len(),
+            lists.whitelists.len()
+        );
+        Ok(Self { path, lists: Arc::new(RwLock::new(lists)), audit: None })
+    }
+
+    /// Правки списков пишутся в журнал изменений конфигурации
+    pub fn with_audit(mut self, audit: ConfigAudit) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub fn snapshot(&self) -> SymbolLists {
+        self.lists.read().expect("symbol lists lock poisoned").clone()
+    }
+
+    pub fn is_blacklisted(&self, symbol: Symbol) -> bool {
+        self.lists.read().expect("symbol lists lock poisoned").is_blacklisted(symbol)
+    }
+
+    pub fn allows(&self, strategy: &str, symbol: Symbol) -> bool {
+        self.lists.read().expect("symbol lists lock poisoned").allows(strategy, symbol)
+    }
+
+    /// Применяет команду и сохраняет списки. Если запись не удалась, списки в памяти
+    /// не меняются: иначе после рестарта бот молча вернулся бы к старым.
+    pub fn apply(&self, command: ListCommand, origin: &ChangeOrigin) -> Result<ListChange> {
+        let mut guard = self.lists.write().expect("symbol lists lock poisoned");
+        let mut next = guard.clone();
+        let names = |symbols: &[Symbol]| symbols.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let (symbols, reply) = match command {
+            ListCommand::Show => return Ok(ListChange { symbols: Vec::new(), reply: guard.render() }),
+            ListCommand::BlacklistAdd(symbols) => {
+                next.blacklist.extend(names(&symbols));
+                let reply = format!("blacklisted {}", names(&symbols).join(", "));
+                (symbols, reply)
+            }
+            ListCommand::BlacklistRemove(symbols) => {
+                for name in names(&symbols) {
+                    next.blacklist.remove(&name);
+                }
+                let reply = format!("removed {} from the blacklist", names(&symbols).join(", "));
+                (symbols, reply)
+            }
+            ListCommand::WhitelistAdd { strategy, symbols } => {
+                next.whitelists.entry(strategy.clone()).or_default().extend(names(&symbols));
+                let reply = format!("whitelisted {} for {}", names(&symbols).join(", "), strategy);
+                (symbols, reply)
+            }
+            ListCommand::WhitelistRemove { strategy, symbols } => {
+                if let Some(allowed) = next.whitelists.get_mut(&strategy) {
+                    for name in names(&symbols) {
+                        allowed.remove(&name);
+                    }
+                }
+                let reply = format!("removed {} from the {} whitelist", names(&symbols).join(", "), strategy);
+                (symbols, reply)
+            }
+            ListCommand::WhitelistClear { strategy } => {
+                // Все символы прежнего списка и так могли торговаться; новых символов
+                // стратегия увидит на их первом тике
+                let Some(old) = next.whitelists.remove(&strategy) else {
+                    bail!("strategy {} has no whitelist", strategy);
+                };
+                let symbols = old.iter().map(|s| Symbol::new(s)).collect();
+                (symbols, format!("cleared the {} whitelist", strategy))
+            }
+        };
+        if next == *guard {
+            return Ok(ListChange { symbols: Vec::new(), reply: format!("{} (no change)", reply) });
+        }
+        next.save(&self.path)?;
+        if let Some(audit) = &self.audit {
+            audit.record(origin, "symbol_lists", &*guard, &next);
+        }
+        *guard = next;
+        log::warn!("symbol lists changed by {} {}: {}", origin.source.as_str(), origin.actor, reply);
+        Ok(ListChange { symbols, reply })
+    }
+
+    /// Текстовая команда целиком: разбор, применение, ответ оператору
+    pub fn handle(&self, text: &str, origin: &ChangeOrigin) -> Result<ListChange> {
+        self.apply(ListCommand::parse(text)?, origin)
+    }
+}
+
+/// Оборачивает фабрику: для символа из чёрного списка стратегий нет, остальные
+/// отбираются по белым спискам
+pub fn filter_factory(factory: StrategyFactory, store: SymbolListStore) -> StrategyFactory {
+    Arc::new(move |symbol: Symbol| -> StrategySet {
+        let lists = store.snapshot();
+        if lists.is_blacklisted(symbol) {
+            log::info!("{} is blacklisted: no strategies created", symbol);
+            return Vec::new();
+        }
+        factory(symbol)
+            .into_iter()
+            .filter(|strategy| {
+                let allowed = lists.allows(strategy.get_name(), symbol);
+                if !allowed {
+                    log::debug!("{} is not whitelisted for {}", symbol, strategy.get_name());
+                }
+                allowed
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::audit::{ChangeSource, ConfigChange};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("symbol_lists_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("lists.json")
+    }
+
+    #[test]
+    fn test_commands_edit_and_persist_lists() {
+        let path = temp_path("persist");
+        let _ = std::fs::remove_file(&path);
+        let (audit, mut changes) = ConfigAudit::channel();
+        let store = SymbolListStore::open(&path).unwrap().with_audit(audit);
+        let operator = ChangeOrigin::new(ChangeSource::Telegram, "chat:1");
+        let (btc, eth, sol) = (Symbol::new("BTC_USDT"), Symbol::new("ETH_USDT"), Symbol::new("SOL_USDT"));
+
+        let change = store.handle("/blacklist add btc_usdt", &operator).unwrap();
+        assert_eq!(change.symbols, [btc]);
+        store.handle("whitelist add Momentum ETH_USDT", &operator).unwrap();
+        assert!(!store.allows("Momentum", btc) && !store.allows("Grid", btc));
+        assert!(store.allows("Momentum", eth) && !store.allows("Momentum", sol));
+        assert!(store.allows("Grid", sol));
+        assert!(store.handle("/blacklist add BTC_USDT", &operator).unwrap().symbols.is_empty());
+        // Каждая правка - в журнале, повтор без изменений - нет
+        let logged: Vec<ConfigChange> = std::iter::from_fn(|| changes.try_recv().ok()).collect();
+        assert_eq!(logged.iter().map(|c| c.key.as_str()).collect::<Vec<_>>(), ["blacklist", "whitelists.Momentum"]);
+        assert_eq!(logged[0].new, Some(serde_json::json!(["BTC_USDT"])));
+        assert_eq!((logged[1].source, logged[1].actor.as_str()), (ChangeSource::Telegram, "chat:1"));
+
+        // После рестарта списки те же
+        let reopened = SymbolListStore::open(&path).unwrap();
+        assert_eq!(reopened.snapshot(), store.snapshot());
+        assert!(reopened.handle("/lists", &operator).unwrap().reply.contains("whitelist Momentum: ETH_USDT"));
+
+        reopened.handle("/whitelist clear Momentum", &operator).unwrap();
+        reopened.handle("/blacklist remove BTC_USDT", &operator).unwrap();
+        assert!(reopened.allows("Momentum", btc));
+        assert_eq!(SymbolLists::load(&path).unwrap(), SymbolLists::default());
+
+        assert!(ListCommand::parse("/blacklist add").is_err());
+        assert!(ListCommand::parse("/whitelist add").is_err());
+        assert!(ListCommand::parse("/graylist add BTC_USDT").is_err());
+        assert!(reopened.handle("/whitelist clear Nobody", &operator).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
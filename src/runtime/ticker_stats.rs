@@ -0,0 +1,228 @@
+//! Кэш 24h-статистики тикеров: один опрос биржи на весь бот
+//!
+//! Кэш обновляется целиком ответом `/futures/usdt/tickers` (или `/ticker/24hr` Binance)
+//! и читается сканером набора символов и фильтром входов роутера, поэтому стратегиям
+//! не нужны свои REST-запросы. Запись старше `max_age_ms` считается неизвестной: фильтр
+//! не пропускает вход по протухшей статистике, а сканер не видит такой символ.
+
+use super::universe::SymbolStats;
+use crate::base_classes::symbol::Symbol;
+#[cfg(feature = "binance_book")]
+use crate::exchanges::binance::rest::Binance24hTicker;
+use crate::exchanges::gate::rest::GateTicker;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// 24h-статистика символа на момент `updated_at_ms`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickerStats24h {
+    pub symbol: Symbol,
+    pub last: f64,
+    pub high_24h: f64,
+    pub low_24h: f64,
+    /// Изменение цены за 24ч, %
+    pub change_pct_24h: Option<f64>,
+    /// Оборот за 24ч в валюте котировки
+    pub volume_24h_quote: f64,
+    /// Число сделок за 24ч; Gate его не отдаёт
+    pub trade_count_24h: Option<u64>,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub updated_at_ms: u64,
+}
+
+impl TickerStats24h {
+    pub fn from_gate_ticker(ticker: &GateTicker, now_ms: u64) -> Self {
+        Self {
+            symbol: Symbol::new(&ticker.contract),
+            last: ticker.last,
+            high_24h: ticker.high_24h,
+            low_24h: ticker.low_24h,
+            change_pct_24h: ticker.change_pct_24h,
+            volume_24h_quote: ticker.volume_24h_quote,
+            trade_count_24h: None,
+            best_bid: ticker.highest_bid,
+            best_ask: ticker.lowest_ask,
+            updated_at_ms: now_ms,
+        }
+    }
+
+    #[cfg(feature = "binance_book")]
+    pub fn from_binance_ticker(ticker: &Binance24hTicker, now_ms: u64) -> Self {
+        Self {
+            symbol: Symbol::new(&ticker.symbol),
+            last: ticker.last,
+            high_24h: ticker.high_24h,
+            low_24h: ticker.low_24h,
+            change_pct_24h: Some(ticker.change_pct_24h),
+            volume_24h_quote: ticker.quote_volume_24h,
+            trade_count_24h: Some(ticker.count_24h),
+            best_bid: None,
+            best_ask: None,
+            updated_at_ms: now_ms,
+        }
+    }
+
+    /// Диапазон 24ч (high - low) / last
+    pub fn volatility_24h(&self) -> f64 {
+        if self.last > 0.0 { (self.high_24h - self.low_24h).max(0.0) / self.last } else { 0.0 }
+    }
+
+    pub fn spread_bps(&self) -> Option<f64> {
+        match (self.best_bid, self.best_ask) {
+            (Some(bid), Some(ask)) if ask >= bid => Some((ask - bid) / ((ask + bid) / 2.0) * 10_000.0),
+            _ => None,
+        }
+    }
+
+    pub fn symbol_stats(&self, listed_at_ms: Option<u64>) -> SymbolStats {
+        SymbolStats {
+            symbol: self.symbol,
+            volume_24h: self.volume_24h_quote,
+            volatility_24h: self.volatility_24h(),
+            spread_bps: self.spread_bps(),
+            listed_at_ms,
+            trade_count_24h: self.trade_count_24h,
+        }
+    }
+}
+
+/// Общий кэш: клон делит те же данные. Лок берётся на опросе и на входе, не на тиках
+#[derive(Debug, Clone)]
+pub struct TickerStatsCache {
+    max_age_ms: u64,
+    entries: Arc<RwLock<HashMap<Symbol, TickerStats24h>>>,
+}
+
+impl TickerStatsCache {
+    pub fn new(max_age_ms: u64) -> Self {
+        assert!(max_age_ms > 0, "ticker stats max age must be positive");
+        Self { max_age_ms, entries: Arc::new(RwLock::new(HashMap::new())) }
+    }
+
+    /// Записывает свежий опрос. Символы, которых в нём нет, остаются до устаревания
+    pub fn update(&self, stats: impl IntoIterator<Item = TickerStats24h>) -> usize {
+        let mut entries = self.entries.write().expect("ticker stats lock poisoned");
+        let mut updated = 0;
+        for s in stats {
+            entries.insert(s.symbol, s);
+            updated += 1;
+        }
+        updated
+    }
+
+    pub fn update_from_gate(&self, tickers: &[GateTicker], now_ms: u64) -> usize {
+        self.update(tickers.iter().map(|t| TickerStats24h::from_gate_ticker(t, now_ms)))
+    }
+
+    #[cfg(feature = "binance_book")]
+    pub fn update_from_binance(&self, tickers: &[Binance24hTicker], now_ms: u64) -> usize {
+        self.update(tickers.iter().map(|t| TickerStats24h::from_binance_ticker(t, now_ms)))
+    }
+
+    /// Статистика символа, если она не старше `max_age_ms`
+    pub fn get(&self, symbol: Symbol, now_ms: u64) -> Option<TickerStats24h> {
+        let entries = self.entries.read().expect("ticker stats lock poisoned");
+        entries.get(&symbol).filter(|s| self.fresh(s, now_ms)).cloned()
+    }
+
+    /// Все свежие записи, для сканера набора символов
+    pub fn snapshot(&self, now_ms: u64) -> Vec<TickerStats24h> {
+        let entries = self.entries.read().expect("ticker stats lock poisoned");
+        entries.values().filter(|s| self.fresh(s, now_ms)).cloned().collect()
+    }
+
+    /// Свежие записи в виде `SymbolStats`; `listed_at_ms` - время листинга символа
+    pub fn symbol_stats(&self, now_ms: u64, listed_at_ms: impl Fn(Symbol) -> Option<u64>) -> Vec<SymbolStats> {
+        self.snapshot(now_ms).iter().map(|s| s.symbol_stats(listed_at_ms(s.symbol))).collect()
+    }
+
+    fn fresh(&self, stats: &TickerStats24h, now_ms: u64) -> bool {
+        now_ms.saturating_sub(stats.updated_at_ms) <= self.max_age_ms
+    }
+
+    /// Опрос тикеров Gate в кэш
+    pub async fn refresh_gate(&self, client: &reqwest::Client, base: &str) -> anyhow::Result<usize> {
+        let tickers = crate::exchanges::gate::rest::fetch_tickers(client, base).await?;
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        Ok(self.update_from_gate(&tickers, now_ms))
+    }
+}
+
+/// Фильтр входов по 24h-статистике. Неизвестная или протухшая статистика входа не пускает
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TickerFilter {
+    pub min_volume_24h_quote: Option<f64>,
+    /// Gate число сделок не отдаёт: при заданном минимуме такие символы проходят,
+    /// иначе фильтр закрыл бы все входы на Gate
+    pub min_trades_24h: Option<u64>,
+    /// Максимальный |изменение цены за 24ч|, %
+    pub max_abs_change_pct_24h: Option<f64>,
+}
+
+impl TickerFilter {
+    /// Причина отказа; None - вход разрешён
+    pub fn rejects(&self, stats: Option<&TickerStats24h>) -> Option<&'static str> {
+        let Some(stats) = stats else {
+            return Some("no 24h stats");
+        };
+        if self.min_volume_24h_quote.is_some_and(|min| stats.volume_24h_quote < min) {
+            return Some("24h volume");
+        }
+        if let (Some(min), Some(count)) = (self.min_trades_24h, stats.trade_count_24h)
+            && count < min
+        {
+            return Some("24h trades");
+        }
+        if let (Some(max), Some(change)) = (self.max_abs_change_pct_24h, stats.change_pct_24h)
+            && change.abs() > max
+        {
+            return Some("24h change");
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticker(symbol: &str, volume: f64, count: u64, change: f64, now_ms: u64) -> TickerStats24h {
+        TickerStats24h {
+            symbol: Symbol::new(symbol),
+            last: 100.0,
+            high_24h: 110.0,
+            low_24h: 95.0,
+            change_pct_24h: Some(change),
+            volume_24h_quote: volume,
+            trade_count_24h: Some(count),
+            best_bid: Some(99.99),
+            best_ask: Some(100.01),
+            updated_at_ms: now_ms,
+        }
+    }
+
+    #[test]
+    fn test_cache_expires_entries_and_feeds_filters() {
+        let cache = TickerStatsCache::new(60_000);
+        let shared = cache.clone();
+        cache.update([ticker("BTC_USDT", 1e9, 2_000_000, 1.5, 0), ticker("PUMP_USDT", 3e7, 90_000, 45.0, 0)]);
+        cache.update([ticker("ETH_USDT", 5e8, 900_000, -2.0, 50_000)]);
+
+        let btc = shared.get(Symbol::new("BTC_USDT"), 30_000).unwrap();
+        assert!((btc.volatility_24h() - 0.15).abs() < 1e-12);
+        assert_eq!(shared.snapshot(90_000).len(), 1);
+        assert!(shared.get(Symbol::new("BTC_USDT"), 90_000).is_none());
+
+        let stats = shared.symbol_stats(30_000, |_| Some(0));
+        assert_eq!(stats.len(), 3);
+        assert!(stats.iter().all(|s| s.spread_bps.is_some_and(|bps| (bps - 2.0).abs() < 1e-6)));
+
+        let filter = TickerFilter { min_volume_24h_quote: Some(1e8), min_trades_24h: Some(100_000), max_abs_change_pct_24h: Some(20.0) };
+        assert_eq!(filter.rejects(shared.get(Symbol::new("ETH_USDT"), 60_000).as_ref()), None);
+        assert_eq!(filter.rejects(shared.get(Symbol::new("PUMP_USDT"), 30_000).as_ref()), Some("24h volume"));
+        let pump = TickerFilter { min_volume_24h_quote: None, ..filter.clone() };
+        assert_eq!(pump.rejects(shared.get(Symbol::new("PUMP_USDT"), 30_000).as_ref()), Some("24h trades"));
+        assert_eq!(filter.rejects(shared.get(Symbol::new("BTC_USDT"), 90_000).as_ref()), Some("no 24h stats"));
+    }
+}
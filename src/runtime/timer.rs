@@ -0,0 +1,58 @@
+//! Таймер рантайма: `on_timer` стратегий без тиков
+//!
+//! Стратегии считают таймауты по времени тиков, поэтому в тихом рынке ожидание
+//! разворота MStrike, перевзведение Hook по времени и TimeStop позиции не срабатывают,
+//! пока не придёт следующий тик символа. Насос вызывает `ShardedRuntime::poll_timers`
+//! в своём цикле (как и `flush`, особенно когда биржа молчит); раз в `interval_ms`
+//! каждый шард получает `ShardCommand::Timer` и зовёт `StrategyAdapter::on_timer`
+//! у стратегий символов, по которым уже были живые тики.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct TimerService {
+    interval: Option<Duration>,
+    next: Instant,
+}
+
+impl TimerService {
+    /// `interval_ms` = 0 - таймер выключен
+    pub fn new(interval_ms: u64, now: Instant) -> Self {
+        let interval = (interval_ms > 0).then(|| Duration::from_millis(interval_ms));
+        Self { interval, next: now + interval.unwrap_or_default() }
+    }
+
+    /// Пора ли разослать таймер. Пропущенные интервалы не догоняются: после долгой
+    /// паузы насоса шарды получат один таймер, а не очередь
+    pub fn due(&mut self, now: Instant) -> bool {
+        let Some(interval) = self.interval else {
+            return false;
+        };
+        if now < self.next {
+            return false;
+        }
+        self.next = now + interval;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fires_once_per_interval_without_backlog() {
+        let start = Instant::now();
+        let mut timer = TimerService::new(250, start);
+        assert!(!timer.due(start));
+        assert!(!timer.due(start + Duration::from_millis(249)));
+        assert!(timer.due(start + Duration::from_millis(250)));
+        assert!(!timer.due(start + Duration::from_millis(300)));
+        // Пауза насоса на 10 интервалов - один таймер
+        assert!(timer.due(start + Duration::from_secs(3)));
+        assert!(!timer.due(start + Duration::from_millis(3_100)));
+
+        let mut off = TimerService::new(0, start);
+        assert!(!off.due(start + Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,269 @@
+//! Динамический набор символов: отбор по объёму, волатильности, спреду и возрасту
+//!
+//! Сканер периодически получает 24h-статистику всех контрактов биржи, отбрасывает не
+//! прошедшие фильтры, ранжирует остальных и держит в наборе лучшие `max_symbols`.
+//! Разница с текущим набором отдаётся как `UniverseChange`; рантайм по ней начинает
+//! пропускать тики новых символов (стратегии создаются на первом тике) и выселяет
+//! стратегии ушедших. Символы с открытой позицией (`pinned`) не выселяются, пока позиция
+//! не закрыта, даже если перестали проходить фильтры.
+//!
+//! Набор не больше, чем биржа даёт стримить: `set_symbol_limit` принимает
+//! `ConnectionManager::symbol_limit` перед сканом, и набор режется до меньшего из лимитов.
+
+use super::symbol_lists::SymbolListStore;
+use super::ticker_stats::TickerStats24h;
+use crate::base_classes::symbol::Symbol;
+use crate::exchanges::gate::rest::GateTicker;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RankBy {
+    #[default]
+    Volume,
+    Volatility,
+}
+
+#[derive(Debug, Clone)]
+pub struct UniverseConfig {
+    /// Минимальный оборот за 24ч в валюте котировки
+    pub min_volume_24h: f64,
+    /// Диапазон 24ч (high - low) / last
+    pub min_volatility_24h: f64,
+    pub max_volatility_24h: Option<f64>,
+    pub max_spread_bps: Option<f64>,
+    /// Минимум сделок за 24ч; символы без счётчика (Gate) проходят
+    pub min_trades_24h: Option<u64>,
+    /// Не торговать только что залистенные контракты
+    pub min_age_days: Option<u64>,
+    pub max_symbols: usize,
+    pub rank_by: RankBy,
+    /// Всегда в наборе, если есть статистика
+    pub always: Vec<Symbol>,
+    pub never: Vec<Symbol>,
+}
+
+impl Default for UniverseConfig {
+    fn default() -> Self {
+        Self {
+            min_volume_24h: 5_000_000.0,
+            min_volatility_24h: 0.03,
+            max_volatility_24h: Some(0.5),
+            max_spread_bps: Some(10.0),
+            min_trades_24h: None,
+            min_age_days: Some(7),
+            max_symbols: 30,
+            rank_by: RankBy::Volume,
+            always: Vec::new(),
+            never: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolStats {
+    pub symbol: Symbol,
+    pub volume_24h: f64,
+    pub volatility_24h: f64,
+    /// None - стакан пуст с одной из сторон
+    pub spread_bps: Option<f64>,
+    /// Время листинга (ms); None - неизвестно
+    pub listed_at_ms: Option<u64>,
+    /// Сделок за 24ч; None - биржа не отдаёт
+    pub trade_count_24h: Option<u64>,
+}
+
+impl SymbolStats {
+    pub fn from_gate_ticker(ticker: &GateTicker, listed_at_ms: Option<u64>) -> Self {
+        TickerStats24h::from_gate_ticker(ticker, 0).symbol_stats(listed_at_ms)
+    }
+}
+
+/// Изменение набора после скана
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UniverseChange {
+    pub added: Vec<Symbol>,
+    pub removed: Vec<Symbol>,
+}
+
+impl UniverseChange {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub struct UniverseScanner {
+    config: UniverseConfig,
+    active: HashSet<Symbol>,
+    /// Чёрный список оператора, правится на лету
+    lists: Option<SymbolListStore>,
+    /// Сколько символов влезает в подписки биржи; None - без лимита
+    symbol_limit: Option<usize>,
+}
+
+impl UniverseScanner {
+    pub fn new(config: UniverseConfig) -> Self {
+        assert!(config.max_symbols > 0, "universe must allow at least one symbol");
+        Self { config, active: HashSet::new(), lists: None, symbol_limit: None }
+    }
+
+    pub fn with_symbol_lists(mut self, lists: SymbolListStore) -> Self {
+        self.lists = Some(lists);
+        self
+    }
+
+    /// Лимит подписок биржи (`ConnectionManager::symbol_limit`) для следующих сканов
+    pub fn set_symbol_limit(&mut self, limit: Option<usize>) {
+        self.symbol_limit = limit;
+    }
+
+    pub fn active(&self) -> &HashSet<Symbol> {
+        &self.active
+    }
+
+    /// Причина, по которой символ не проходит фильтры
+    pub fn rejects(&self, stats: &SymbolStats, now_ms: u64) -> Option<&'static str> {
+        let c = &self.config;
+        if c.never.contains(&stats.symbol) {
+            return Some("never list");
+        }
+        if self.lists.as_ref().is_some_and(|lists| lists.is_blacklisted(stats.symbol)) {
+            return Some("blacklist");
+        }
+        if c.always.contains(&stats.symbol) {
+            return None;
+        }
+        if stats.volume_24h < c.min_volume_24h {
+            return Some("volume");
+        }
+        if stats.volatility_24h < c.min_volatility_24h || c.max_volatility_24h.is_some_and(|max| stats.volatility_24h > max) {
+            return Some("volatility");
+        }
+        if let Some(max) = c.max_spread_bps
+            && stats.spread_bps.is_none_or(|spread| spread > max)
+        {
+            return Some("spread");
+        }
+        if let (Some(min), Some(count)) = (c.min_trades_24h, stats.trade_count_24h)
+            && count < min
+        {
+            return Some("trades");
+        }
+        if let Some(days) = c.min_age_days {
+            // Неизвестный возраст не даёт пройти фильтр: свежий листинг опаснее пропуска
+            let old_enough = stats.listed_at_ms.is_some_and(|at| now_ms.saturating_sub(at) >= days * 86_400_000);
+            if !old_enough {
+                return Some("age");
+            }
+        }
+        None
+    }
+
+    /// Пересобирает набор по свежей статистике. `pinned` - символы с открытой позицией
+    pub fn scan(&mut self, stats: &[SymbolStats], pinned: &HashSet<Symbol>, now_ms: u64) -> UniverseChange {
+        if stats.is_empty() {
+            log::error!("universe scan got no symbol statistics; keeping {} symbols", self.active.len());
+            return UniverseChange::default();
+        }
+        let mut passing: Vec<&SymbolStats> = stats.iter().filter(|s| self.rejects(s, now_ms).is_none()).collect();
+        let key = |s: &SymbolStats| match self.config.rank_by {
+            RankBy::Volume => s.volume_24h,
+            RankBy::Volatility => s.volatility_24h,
+        };
+        passing.sort_by(|a, b| {
+            let forced = |s: &SymbolStats| self.config.always.contains(&s.symbol);
+            forced(b).cmp(&forced(a)).then(key(b).total_cmp(&key(a)))
+        });
+
+        let max_symbols = self.config.max_symbols.min(self.symbol_limit.unwrap_or(usize::MAX));
+        if max_symbols < self.config.max_symbols && passing.len() > max_symbols {
+            log::warn!(
+                "universe capped at {} symbols by the subscription budget ({} configured)",
+                max_symbols,
+                self.config.max_symbols
+            );
+        }
+        let mut next: HashSet<Symbol> = pinned.iter().copied().filter(|s| self.active.contains(s)).collect();
+        for candidate in passing {
+            if next.len() >= max_symbols {
+                break;
+            }
+            next.insert(candidate.symbol);
+        }
+        let mut change = UniverseChange {
+            added: next.difference(&self.active).copied().collect(),
+            removed: self.active.difference(&next).copied().collect(),
+        };
+        change.added.sort_by_key(|s| s.to_string());
+        change.removed.sort_by_key(|s| s.to_string());
+        if !change.is_empty() {
+            log::info!("universe: +{:?} -{:?} ({} symbols)", change.added, change.removed, next.len());
+        }
+        self.active = next;
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::audit::{ChangeOrigin, ChangeSource};
+
+    const DAY: u64 = 86_400_000;
+
+    fn stats(symbol: &str, volume: f64, volatility: f64) -> SymbolStats {
+        SymbolStats { symbol: Symbol::new(symbol), volume_24h: volume, volatility_24h: volatility, spread_bps: Some(2.0), listed_at_ms: Some(0), trade_count_24h: None }
+    }
+
+    #[test]
+    fn test_scan_ranks_filters_and_keeps_pinned() {
+        let mut scanner = UniverseScanner::new(UniverseConfig { max_symbols: 2, ..UniverseConfig::default() });
+        let now = 30 * DAY;
+        let mut fresh = stats("NEW_USDT", 9e9, 0.2);
+        fresh.listed_at_ms = Some(now - DAY);
+        let mut wide = stats("WIDE_USDT", 9e9, 0.2);
+        wide.spread_bps = Some(50.0);
+        let market = vec![
+            stats("BTC_USDT", 1e9, 0.04),
+            stats("ETH_USDT", 5e8, 0.05),
+            stats("SOL_USDT", 2e8, 0.08),
+            stats("DEAD_USDT", 1e5, 0.1),
+            fresh,
+            wide,
+        ];
+        assert_eq!(scanner.rejects(&market[3], now), Some("volume"));
+        assert_eq!(scanner.rejects(&market[4], now), Some("age"));
+        assert_eq!(scanner.rejects(&market[5], now), Some("spread"));
+
+        let change = scanner.scan(&market, &HashSet::new(), now);
+        assert_eq!(change.added, [Symbol::new("BTC_USDT"), Symbol::new("ETH_USDT")]);
+
+        // ETH выпал из фильтров, но по нему открыта позиция; BTC ушёл без позиции
+        let market = vec![stats("BTC_USDT", 1e9, 0.01), stats("ETH_USDT", 1e5, 0.05), stats("SOL_USDT", 2e8, 0.08)];
+        let pinned: HashSet<Symbol> = [Symbol::new("ETH_USDT")].into();
+        let change = scanner.scan(&market, &pinned, now);
+        assert_eq!(change, UniverseChange { added: vec![Symbol::new("SOL_USDT")], removed: vec![Symbol::new("BTC_USDT")] });
+        assert!(scanner.scan(&[], &pinned, now).is_empty());
+
+        // Подписки биржи вмещают один символ: держится только ETH с позицией
+        scanner.set_symbol_limit(Some(1));
+        let change = scanner.scan(&market, &pinned, now);
+        assert_eq!(change.removed, [Symbol::new("SOL_USDT")]);
+        assert_eq!(scanner.active().len(), 1);
+    }
+
+    #[test]
+    fn test_operator_blacklist_beats_ranking() {
+        let path = std::env::temp_dir().join(format!("universe_lists_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let lists = SymbolListStore::open(&path).unwrap();
+        let mut scanner = UniverseScanner::new(UniverseConfig { max_symbols: 1, ..UniverseConfig::default() })
+            .with_symbol_lists(lists.clone());
+        let operator = ChangeOrigin::new(ChangeSource::Telegram, "chat:1");
+        lists.handle("/blacklist add BTC_USDT", &operator).unwrap();
+        let market = vec![stats("BTC_USDT", 1e9, 0.04), stats("ETH_USDT", 5e8, 0.05)];
+        assert_eq!(scanner.rejects(&market[0], 30 * DAY), Some("blacklist"));
+        assert_eq!(scanner.scan(&market, &HashSet::new(), 30 * DAY).added, [Symbol::new("ETH_USDT")]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
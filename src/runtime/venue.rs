@@ -0,0 +1,233 @@
+//! Выбор биржи под ордер, когда символ торгуется на нескольких площадках
+//!
+//! Для каждой заявки считается ожидаемая полная стоимость в б.п. от цены ордера на
+//! каждой площадке со свежей котировкой: если ордер пересекает спред - проскальзывание
+//! до лучшей цены, тейкерская комиссия и штраф за нехватку глубины на лучшем уровне;
+//! если встаёт в стакан - мейкерская комиссия и половина спреда (широкий спред хуже
+//! исполняется). Побеждает минимальная стоимость, при равенстве - площадка, указанная
+//! в конфиге раньше. Закрепление стратегии за площадкой важнее любой оценки.
+
+use super::shard::RoutedAction;
+use crate::backtest::strategy_adapter::StrategyAction;
+use crate::base_classes::symbol::Symbol;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Потолок штрафа за объём сверх лучшего уровня (штраф - спред на каждый
+/// лишний объём уровня), чтобы пустой уровень не выглядел бесконечно дорогим
+const DEPTH_PENALTY_CAP_BPS: f64 = 500.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VenueFees {
+    pub name: String,
+    #[serde(default)]
+    pub maker_fee_bps: f64,
+    pub taker_fee_bps: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VenueRoutingConfig {
+    /// Порядок задаёт приоритет при равной стоимости
+    pub venues: Vec<VenueFees>,
+    /// Имя стратегии -> площадка; выбор по стоимости для неё не делается
+    #[serde(default)]
+    pub pins: HashMap<String, String>,
+    /// Котировка старше этого не участвует в выборе
+    #[serde(default = "default_max_quote_age_ms")]
+    pub max_quote_age_ms: u64,
+}
+
+fn default_max_quote_age_ms() -> u64 {
+    2_000
+}
+
+/// Лучшие цены площадки и объём на них (в базовой валюте)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VenueQuote {
+    pub bid: f64,
+    pub bid_size: f64,
+    pub ask: f64,
+    pub ask_size: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VenueReason {
+    Pinned,
+    /// Заявка пересекает спред на выбранной площадке
+    BestTaker,
+    /// Заявка встаёт в стакан
+    BestMaker,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueChoice {
+    pub venue: String,
+    pub reason: VenueReason,
+    /// Оценка полной стоимости в б.п. от цены ордера; для закрепления - None
+    pub cost_bps: Option<f64>,
+}
+
+pub struct VenueSelector {
+    config: VenueRoutingConfig,
+    quotes: HashMap<(Symbol, usize), (VenueQuote, Instant)>,
+}
+
+impl VenueSelector {
+    pub fn new(config: VenueRoutingConfig) -> Self {
+        for (strategy, venue) in &config.pins {
+            assert!(
+                config.venues.iter().any(|v| &v.name == venue),
+                "strategy {} pinned to unconfigured venue {}",
+                strategy,
+                venue
+            );
+        }
+        Self { config, quotes: HashMap::new() }
+    }
+
+    fn venue_index(&self, venue: &str) -> Option<usize> {
+        self.config.venues.iter().position(|v| v.name == venue)
+    }
+
+    /// Котировка от market data площадки; неизвестные площадки игнорируются
+    pub fn on_quote(&mut self, venue: &str, symbol: Symbol, quote: VenueQuote, at: Instant) {
+        match self.venue_index(venue) {
+            Some(index) => {
+                self.quotes.insert((symbol, index), (quote, at));
+            }
+            None => log::debug!("quote from unconfigured venue {} ignored", venue),
+        }
+    }
+
+    /// Стоимость заявки на площадке в б.п.; None, если котировки нет или она устарела
+    fn cost_bps(&self, index: usize, symbol: Symbol, buy: bool, price: f64, size: f64, now: Instant) -> Option<(f64, VenueReason)> {
+        let (quote, at) = self.quotes.get(&(symbol, index))?;
+        if now.saturating_duration_since(*at) > Duration::from_millis(self.config.max_quote_age_ms) {
+            return None;
+        }
+        if quote.bid <= 0.0 || quote.ask < quote.bid || price <= 0.0 {
+            return None;
+        }
+        let fees = &self.config.venues[index];
+        let mid = (quote.bid + quote.ask) / 2.0;
+        let spread_bps = (quote.ask - quote.bid) / mid * 1e4;
+        let (touch, depth, marketable) = if buy {
+            (quote.ask, quote.ask_size, quote.ask <= price)
+        } else {
+            (quote.bid, quote.bid_size, quote.bid >= price)
+        };
+        if !marketable {
+            return Some((fees.maker_fee_bps + spread_bps / 2.0, VenueReason::BestMaker));
+        }
+        // Отрицательное - улучшение относительно цены ордера
+        let slippage_bps = if buy { (touch / price - 1.0) * 1e4 } else { (1.0 - touch / price) * 1e4 };
+        let depth_bps = if depth <= 0.0 {
+            DEPTH_PENALTY_CAP_BPS
+        } else {
+            (spread_bps * (size / depth - 1.0).max(0.0)).min(DEPTH_PENALTY_CAP_BPS)
+        };
+        Some((slippage_bps + fees.taker_fee_bps + depth_bps, VenueReason::BestTaker))
+    }
+
+    /// Площадка для PlaceBuy/PlaceSell; для отмен и прочего - None (они идут туда,
+    /// где стоит ордер). None и для ордера, если ни у одной площадки нет свежей котировки.
+    pub fn select(&self, action: &RoutedAction, now: Instant) -> Option<VenueChoice> {
+        let (buy, price, size) = match action.action {
+            StrategyAction::PlaceBuy { price, size } => (true, price, size),
+            StrategyAction::PlaceSell { price, size } => (false, price, size),
+            _ => return None,
+        };
+        if let Some(venue) = self.config.pins.get(&action.strategy_name) {
+            return Some(VenueChoice { venue: venue.clone(), reason: VenueReason::Pinned, cost_bps: None });
+        }
+        let mut best: Option<(usize, f64, VenueReason)> = None;
+        for index in 0..self.config.venues.len() {
+            let Some((cost, reason)) = self.cost_bps(index, action.symbol, buy, price, size, now) else {
+                continue;
+            };
+            if best.is_none_or(|(_, best_cost, _)| cost < best_cost) {
+                best = Some((index, cost, reason));
+            }
+        }
+        let Some((index, cost, reason)) = best else {
+            log::warn!("no fresh venue quote for {} {}, order has no venue", action.strategy_name, action.symbol);
+            return None;
+        };
+        Some(VenueChoice { venue: self.config.venues[index].name.clone(), reason, cost_bps: Some(cost) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::latency::ActionTiming;
+    use crate::execution::TimeInForce;
+    use chrono::Utc;
+
+    fn config() -> VenueRoutingConfig {
+        VenueRoutingConfig {
+            venues: vec![
+                VenueFees { name: "gate".into(), maker_fee_bps: 2.0, taker_fee_bps: 5.0 },
+                VenueFees { name: "mexc".into(), maker_fee_bps: 0.0, taker_fee_bps: 2.0 },
+            ],
+            pins: HashMap::from([("listing".to_string(), "mexc".to_string())]),
+            max_quote_age_ms: 2_000,
+        }
+    }
+
+    fn action(strategy: &str, action: StrategyAction) -> RoutedAction {
+        RoutedAction {
+            shard: 0,
+            symbol: Symbol::new("ALT_USDT"),
+            strategy: 0,
+            strategy_name: strategy.to_string(),
+            action,
+            tif: TimeInForce::Gtc,
+            source_time: Utc::now(),
+            timing: ActionTiming { received: Instant::now(), signalled: Instant::now() },
+        }
+    }
+
+    fn quote(bid: f64, ask: f64, size: f64) -> VenueQuote {
+        VenueQuote { bid, bid_size: size, ask, ask_size: size }
+    }
+
+    #[test]
+    fn test_cheapest_venue_wins_fee_spread_and_depth() {
+        let now = Instant::now();
+        let symbol = Symbol::new("ALT_USDT");
+        let mut selector = VenueSelector::new(config());
+        selector.on_quote("gate", symbol, quote(99.9, 100.0, 50.0), now);
+        // Цена хуже на 1 б.п., но комиссия ниже на 3 б.п.
+        selector.on_quote("mexc", symbol, quote(99.9, 100.01, 50.0), now);
+
+        let buy = action("hook", StrategyAction::PlaceBuy { price: 100.1, size: 10.0 });
+        let choice = selector.select(&buy, now).unwrap();
+        assert_eq!((choice.venue.as_str(), choice.reason), ("mexc", VenueReason::BestTaker));
+
+        // Объём больше глубины MEXC: штраф за глубину перевешивает
+        selector.on_quote("mexc", symbol, quote(99.9, 100.01, 2.0), now);
+        assert_eq!(selector.select(&buy, now).unwrap().venue, "gate");
+
+        // Котировка Gate устарела - остаётся только MEXC
+        let later = now + Duration::from_secs(3);
+        selector.on_quote("mexc", symbol, quote(99.9, 100.01, 2.0), later);
+        assert_eq!(selector.select(&buy, later).unwrap().venue, "mexc");
+    }
+
+    #[test]
+    fn test_pins_and_non_orders() {
+        let now = Instant::now();
+        let symbol = Symbol::new("ALT_USDT");
+        let mut selector = VenueSelector::new(config());
+        selector.on_quote("gate", symbol, quote(99.9, 100.0, 50.0), now);
+
+        let pinned = selector.select(&action("listing", StrategyAction::PlaceBuy { price: 100.0, size: 1.0 }), now).unwrap();
+        assert_eq!((pinned.venue.as_str(), pinned.reason, pinned.cost_bps), ("mexc", VenueReason::Pinned, None));
+        // Пассивная продажа встаёт в стакан
+        let sell = selector.select(&action("hook", StrategyAction::PlaceSell { price: 100.5, size: 1.0 }), now).unwrap();
+        assert_eq!(sell.reason, VenueReason::BestMaker);
+        assert!(selector.select(&action("hook", StrategyAction::CancelOrder { order_id: 1 }), now).is_none());
+    }
+}
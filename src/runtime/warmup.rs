@@ -0,0 +1,221 @@
+//! Прогрев при старте: история свечей с REST биржи до живого потока
+//!
+//! Без прогрева дельты `DeltaCalculator` (до 3 часов) и индикаторы стратегий
+//! первые часы после старта считаются по неполной истории, и модификаторы по
+//! дельтам врут. Прогрев тянет минутные свечи Gate за `lookback_minutes`, делает
+//! из каждой свечи четыре тика (open, экстремумы в порядке движения, close) и
+//! отдаёт их шардам командой `Warmup`: дельты и `StrategyAdapter::warm_up`
+//! наполняются, действий нет.
+
+use super::ShardedRuntime;
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use crate::exchanges::endpoints::GateioGet;
+use crate::exchanges::gate::{fetch_candles, GateCandle};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+/// Лимит Gate на число свечей в одном запросе
+const MAX_CANDLES_PER_REQUEST: i64 = 2_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupConfig {
+    /// Сколько истории тянуть; самое длинное окно дельт - 3 часа
+    #[serde(default = "default_lookback_minutes")]
+    pub lookback_minutes: u32,
+    /// Интервал свечей Gate: `10s`, `1m`, `5m`, ...
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    #[serde(default = "default_base")]
+    pub base_url: String,
+}
+
+fn default_lookback_minutes() -> u32 {
+    180
+}
+
+fn default_interval() -> String {
+    "1m".to_string()
+}
+
+fn default_base() -> String {
+    GateioGet::BASE.to_string()
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            lookback_minutes: default_lookback_minutes(),
+            interval: default_interval(),
+            base_url: default_base(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WarmupReport {
+    pub symbols: usize,
+    pub ticks: usize,
+    /// Символы без прогрева и причина; они стартуют с пустой историей
+    pub failed: Vec<(Symbol, String)>,
+}
+
+/// Длина интервала свечей Gate в секундах
+fn interval_secs(interval: &str) -> Result<i64> {
+    let (value, unit) = interval.split_at(interval.len().saturating_sub(1));
+    let value: i64 = match value.parse() {
+        Ok(v) if v > 0 => v,
+        _ => bail!("bad candle interval {:?}", interval),
+    };
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => bail!("bad candle interval {:?}", interval),
+    };
+    Ok(value * unit)
+}
+
+/// Четыре тика на свечу внутри её интервала. Незакрытые к `now` свечи
+/// пропускаются: их close ещё не финальный и позже живого тика.
+pub fn candles_to_ticks(symbol: Symbol, candles: &[GateCandle], interval_secs: i64, now: DateTime<Utc>) -> Vec<TradeTick> {
+    let step = Duration::milliseconds(interval_secs * 1_000 / 4);
+    let mut ticks = Vec::with_capacity(candles.len() * 4);
+    for candle in candles {
+        let Some(open_time) = DateTime::from_timestamp(candle.t, 0) else {
+            continue;
+        };
+        if open_time + Duration::seconds(interval_secs) > now {
+            continue;
+        }
+        let up = candle.close >= candle.open;
+        let (first, second) = if up { (candle.low, candle.high) } else { (candle.high, candle.low) };
+        let side = if up { TradeSide::Buy } else { TradeSide::Sell };
+        for (i, price) in [candle.open, first, second, candle.close].into_iter().enumerate() {
+            ticks.push(TradeTick {
+                timestamp: open_time + step * i as i32,
+                symbol,
+                price,
+                volume: candle.volume / 4.0,
+                side,
+                trade_id: String::new(),
+                best_bid: None,
+                best_ask: None,
+            });
+        }
+    }
+    ticks
+}
+
+async fn fetch_history(client: &reqwest::Client, config: &WarmupConfig, symbol: Symbol, now: DateTime<Utc>) -> Result<Vec<GateCandle>> {
+    let step = interval_secs(&config.interval)?;
+    let to = now.timestamp();
+    let mut from = to - config.lookback_minutes as i64 * 60;
+    let mut candles = Vec::new();
+    while from < to {
+        let chunk_to = (from + step * (MAX_CANDLES_PER_REQUEST - 1)).min(to);
+        let chunk = fetch_candles(client, &config.base_url, symbol.as_str(), &config.interval, from, chunk_to).await?;
+        // Границы соседних запросов включительные: свеча на стыке пришла бы дважды
+        let last = candles.last().map_or(i64::MIN, |c: &GateCandle| c.t);
+        candles.extend(chunk.into_iter().filter(|c| c.t > last));
+        from = chunk_to + 1;
+    }
+    Ok(candles)
+}
+
+/// Тянет историю по всем символам параллельно и отдаёт её шардам. Вызывать до
+/// первого живого тика. Символ, который не удалось прогреть, стартует с пустой
+/// историей - это громко логируется и попадает в `WarmupReport::failed`.
+pub async fn warm_start(runtime: &mut ShardedRuntime, symbols: &[Symbol], config: &WarmupConfig) -> Result<WarmupReport> {
+    let step = interval_secs(&config.interval)?;
+    let client = reqwest::Client::builder().timeout(std::time::Duration::from_secs(15)).build()?;
+    let now = Utc::now();
+    let fetched = join_all(symbols.iter().map(|&symbol| fetch_history(&client, config, symbol, now))).await;
+
+    let mut report = WarmupReport::default();
+    for (&symbol, result) in symbols.iter().zip(fetched) {
+        match result {
+            Ok(candles) if !candles.is_empty() => {
+                let ticks = candles_to_ticks(symbol, &candles, step, now);
+                report.symbols += 1;
+                report.ticks += ticks.len();
+                runtime.warm_up(ticks);
+            }
+            Ok(_) => {
+                log::error!("warm-up: no {} candles for {}, deltas start empty", config.interval, symbol);
+                report.failed.push((symbol, "no candles".to_string()));
+            }
+            Err(err) => {
+                log::error!("warm-up: {} history failed, deltas start empty: {:#}", symbol, err);
+                report.failed.push((symbol, format!("{:#}", err)));
+            }
+        }
+    }
+    log::info!(
+        "warm-up: {} of {} symbols seeded with {} ticks over {} min",
+        report.symbols,
+        symbols.len(),
+        report.ticks,
+        config.lookback_minutes
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::strategy_adapter::HookAdapter;
+    use crate::runtime::{RuntimeConfig, StrategyFactory, StrategySet};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_candles_become_ordered_ticks() {
+        assert_eq!(interval_secs("1m").unwrap(), 60);
+        assert_eq!(interval_secs("10s").unwrap(), 10);
+        assert!(interval_secs("m").is_err());
+
+        let symbol = Symbol::new("BTC_USDT");
+        let now = DateTime::from_timestamp(1_700_000_150, 0).unwrap();
+        let candles = [
+            GateCandle { t: 1_700_000_000, open: 100.0, high: 101.0, low: 99.0, close: 100.5, volume: 8.0 },
+            GateCandle { t: 1_700_000_060, open: 100.5, high: 100.8, low: 99.5, close: 99.8, volume: 4.0 },
+            // Ещё не закрыта к now
+            GateCandle { t: 1_700_000_120, open: 99.8, high: 99.9, low: 99.7, close: 99.9, volume: 1.0 },
+        ];
+        let ticks = candles_to_ticks(symbol, &candles, 60, now);
+        let prices: Vec<f64> = ticks.iter().map(|t| t.price).collect();
+        assert_eq!(prices, vec![100.0, 99.0, 101.0, 100.5, 100.5, 100.8, 99.5, 99.8]);
+        assert!(ticks.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        assert_eq!(ticks[3].timestamp, DateTime::from_timestamp(1_700_000_045, 0).unwrap());
+        assert_eq!(ticks[0].volume, 2.0);
+    }
+
+    #[test]
+    fn test_warmup_seeds_shards_without_actions() {
+        let factory: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet { vec![Box::new(HookAdapter::default())] });
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 2, ..Default::default() }, factory);
+        let symbol = Symbol::new("ETH_USDT");
+        let start = Utc::now() - Duration::hours(3);
+        // Обвал на истории: живой Hook купил бы, прогрев - нет
+        let candles: Vec<GateCandle> = (0..180)
+            .map(|i| {
+                let price = if i < 170 { 100.0 } else { 90.0 };
+                GateCandle { t: start.timestamp() + i * 60, open: price, high: price, low: price * 0.99, close: price, volume: 1.0 }
+            })
+            .collect();
+        let ticks = candles_to_ticks(symbol, &candles, 60, Utc::now());
+        let expected = ticks.len() as u64;
+        runtime.warm_up(ticks);
+        let stats = runtime.shutdown();
+
+        let mut actions = Vec::new();
+        router.poll(&mut actions, usize::MAX);
+        assert!(actions.is_empty(), "{:?}", actions);
+        assert_eq!(stats.iter().map(|s| s.warmup_ticks).sum::<u64>(), expected);
+        assert_eq!(stats.iter().map(|s| s.ticks).sum::<u64>(), 0);
+        assert_eq!(stats.iter().map(|s| s.symbols).sum::<usize>(), 1);
+    }
+}
@@ -1,13 +1,369 @@
-//! AI Recommendations placeholder module
-//! Future: provide AI-driven suggestions for strategy improvements
+//! ИИ рекомендации по параметрам стратегии
+//!
+//! Движок сравнивает журнал сделок стратегии и прогоны бэктеста с другими значениями
+//! параметров за последние `lookback_days` и выдаёт конкретные рекомендации, отсортированные
+//! по приросту profit factor: "hook_sell_level 75→60 improves PF from 1.1 to 1.4 in the
+//! last 30 days". Цифры считает только движок; `LlmProvider` (любой OpenAI-совместимый API)
+//! может лишь переформулировать готовые рекомендации, при его ошибке остаётся исходный текст.
+
+use crate::backtest::metrics::TradeRecord;
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// PF без убыточных сделок бесконечен - для сравнения и хранения ограничиваем
+const PF_CAP: f64 = 10.0;
+
+/// Показатели сделок за окно
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TradeStats {
+    pub trades: usize,
+    pub win_rate: f64,
+    pub profit_factor: f64,
+    pub total_pnl: f64,
+    pub max_drawdown: f64,
+}
+
+impl TradeStats {
+    /// Сделки, закрытые не раньше `since`
+    pub fn from_trades(trades: &[TradeRecord], since: DateTime<Utc>) -> Self {
+        let (mut count, mut wins, mut profit, mut loss) = (0usize, 0usize, 0.0, 0.0);
+        let (mut equity, mut peak, mut max_drawdown) = (0.0f64, 0.0f64, 0.0f64);
+        for trade in trades.iter().filter(|t| t.exit_time >= since) {
+            count += 1;
+            if trade.pnl > 0.0 {
+                wins += 1;
+                profit += trade.pnl;
+            } else {
+                loss += trade.pnl.abs();
+            }
+            equity += trade.pnl;
+            peak = peak.max(equity);
+            max_drawdown = max_drawdown.max(peak - equity);
+        }
+        let profit_factor = if loss > 0.0 {
+            (profit / loss).min(PF_CAP)
+        } else if profit > 0.0 {
+            PF_CAP
+        } else {
+            0.0
+        };
+        Self {
+            trades: count,
+            win_rate: if count > 0 { wins as f64 / count as f64 * 100.0 } else { 0.0 },
+            profit_factor,
+            total_pnl: profit - loss,
+            max_drawdown,
+        }
+    }
+}
+
+/// Прогон бэктеста с одним значением параметра
+#[derive(Debug, Clone)]
+pub struct SweepRun {
+    pub value: f64,
+    pub trades: Vec<TradeRecord>,
+}
+
+/// Перебор одного параметра. Прогон с `current` - база сравнения; если его нет,
+/// базой служит журнал живых сделок.
+#[derive(Debug, Clone)]
+pub struct ParameterSweep {
+    pub param: String,
+    pub current: f64,
+    pub runs: Vec<SweepRun>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AiRecommendation {
+    pub param: String,
+    pub current: f64,
+    pub suggested: f64,
+    pub baseline: TradeStats,
+    pub projected: TradeStats,
+    /// Прирост PF, по нему ранжируются рекомендации
+    pub score: f64,
+    pub text: String,
+}
 
 #[derive(Debug, Clone)]
-pub struct AiRecommendation;
+pub struct RecommendationConfig {
+    pub lookback_days: i64,
+    /// Меньше сделок в окне - статистике не верим
+    pub min_trades: usize,
+    /// Минимальный прирост PF для рекомендации
+    pub min_pf_gain: f64,
+    pub max_recommendations: usize,
+}
+
+impl Default for RecommendationConfig {
+    fn default() -> Self {
+        Self {
+            lookback_days: 30,
+            min_trades: 20,
+            min_pf_gain: 0.1,
+            max_recommendations: 5,
+        }
+    }
+}
+
+/// Провайдер формулировок (OpenAI-совместимый chat API или заглушка в тестах)
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    async fn complete(&self, system: &str, prompt: &str) -> Result<String>;
+}
+
+/// Любой API с `/chat/completions` в формате OpenAI (OpenAI, OpenRouter, vLLM, Ollama)
+pub struct OpenAiCompatible {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiCompatible {
+    pub fn new(base_url: impl Into<String>, api_key: impl Into<String>, model: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("reqwest client");
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: api_key.into(),
+            model: model.into(),
+        }
+    }
+}
 
-impl AiRecommendation {
-    pub fn suggest(_strategy_text: &str) -> Vec<String> {
-        Vec::new()
+#[async_trait]
+impl LlmProvider for OpenAiCompatible {
+    async fn complete(&self, system: &str, prompt: &str) -> Result<String> {
+        let response = self
+            .http
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "model": self.model,
+                "temperature": 0.2,
+                "messages": [
+                    { "role": "system", "content": system },
+                    { "role": "user", "content": prompt },
+                ],
+            }))
+            .send()
+            .await
+            .context("llm request")?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.context("llm response")?;
+        if !status.is_success() {
+            return Err(anyhow!("llm returned {}: {}", status, body));
+        }
+        body["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| anyhow!("llm response without content: {}", body))
     }
 }
 
+const PHRASE_SYSTEM: &str = "You rewrite trading strategy recommendations for the strategy owner. \
+Keep every number and parameter name exactly as given. One short sentence per recommendation, \
+one recommendation per line, same order, no extra lines.";
 
+pub struct RecommendationEngine {
+    config: RecommendationConfig,
+    llm: Option<Box<dyn LlmProvider>>,
+}
+
+impl RecommendationEngine {
+    pub fn new(config: RecommendationConfig) -> Self {
+        Self { config, llm: None }
+    }
+
+    pub fn with_llm(mut self, llm: impl LlmProvider + 'static) -> Self {
+        self.llm = Some(Box::new(llm));
+        self
+    }
+
+    /// Рекомендации по убыванию прироста PF. Кандидат проходит, если в окне у него не
+    /// меньше `min_trades` сделок, PF выше базы на `min_pf_gain` и PnL не хуже базы.
+    pub fn analyze(&self, journal: &[TradeRecord], sweeps: &[ParameterSweep], now: DateTime<Utc>) -> Vec<AiRecommendation> {
+        let since = now - Duration::days(self.config.lookback_days);
+        let journal_stats = TradeStats::from_trades(journal, since);
+        let mut recommendations: Vec<AiRecommendation> = sweeps
+            .iter()
+            .filter_map(|sweep| {
+                let baseline = sweep
+                    .runs
+                    .iter()
+                    .find(|run| run.value == sweep.current)
+                    .map(|run| TradeStats::from_trades(&run.trades, since))
+                    .unwrap_or(journal_stats);
+                if baseline.trades < self.config.min_trades {
+                    return None;
+                }
+                let (value, projected) = sweep
+                    .runs
+                    .iter()
+                    .filter(|run| run.value != sweep.current)
+                    .map(|run| (run.value, TradeStats::from_trades(&run.trades, since)))
+                    .filter(|(_, stats)| {
+                        stats.trades >= self.config.min_trades
+                            && stats.profit_factor >= baseline.profit_factor + self.config.min_pf_gain
+                            && stats.total_pnl >= baseline.total_pnl
+                    })
+                    .max_by(|a, b| {
+                        a.1.profit_factor
+                            .total_cmp(&b.1.profit_factor)
+                            .then(a.1.total_pnl.total_cmp(&b.1.total_pnl))
+                    })?;
+                Some(AiRecommendation {
+                    param: sweep.param.clone(),
+                    current: sweep.current,
+                    suggested: value,
+                    baseline,
+                    projected,
+                    score: projected.profit_factor - baseline.profit_factor,
+                    text: format!(
+                        "{} {}→{} improves PF from {:.1} to {:.1} in the last {} days",
+                        sweep.param,
+                        sweep.current,
+                        value,
+                        baseline.profit_factor,
+                        projected.profit_factor,
+                        self.config.lookback_days
+                    ),
+                })
+            })
+            .collect();
+        recommendations.sort_by(|a, b| b.score.total_cmp(&a.score));
+        recommendations.truncate(self.config.max_recommendations);
+        recommendations
+    }
+
+    /// Тексты рекомендаций; с провайдером - переформулированные. Если провайдер упал или
+    /// вернул другое число строк, остаются исходные тексты.
+    pub async fn phrase(&self, strategy_name: &str, recommendations: &[AiRecommendation]) -> Vec<String> {
+        let plain: Vec<String> = recommendations.iter().map(|r| r.text.clone()).collect();
+        let Some(llm) = &self.llm else {
+            return plain;
+        };
+        if plain.is_empty() {
+            return plain;
+        }
+        let prompt = format!("Strategy: {}\n{}", strategy_name, plain.join("\n"));
+        match llm.complete(PHRASE_SYSTEM, &prompt).await {
+            Ok(text) => {
+                let lines: Vec<String> = text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if lines.len() == plain.len() {
+                    lines
+                } else {
+                    log::warn!(
+                        "llm returned {} lines for {} recommendations, keeping plain text",
+                        lines.len(),
+                        plain.len()
+                    );
+                    plain
+                }
+            }
+            Err(err) => {
+                log::warn!("llm phrasing failed, keeping plain text: {:#}", err);
+                plain
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `wins` сделок по +`win` и `losses` по -1.0, закрытых `days_ago` дней назад
+    fn trades(now: DateTime<Utc>, days_ago: i64, wins: usize, win: f64, losses: usize) -> Vec<TradeRecord> {
+        let at = now - Duration::days(days_ago);
+        let trade = |pnl: f64| TradeRecord {
+            symbol: "BTC_USDT".to_string(),
+            entry_price: 100.0,
+            exit_price: 100.0 + pnl,
+            size: 1.0,
+            is_buy: true,
+            pnl,
+            entry_time: at,
+            exit_time: at,
+        };
+        (0..wins).map(|_| trade(win)).chain((0..losses).map(|_| trade(-1.0))).collect()
+    }
+
+    #[test]
+    fn test_ranks_parameter_changes_by_pf_gain() {
+        let now = Utc::now();
+        let sweeps = vec![
+            ParameterSweep {
+                param: "hook_sell_level".to_string(),
+                current: 75.0,
+                runs: vec![
+                    SweepRun { value: 75.0, trades: trades(now, 5, 11, 1.0, 10) },
+                    SweepRun { value: 60.0, trades: trades(now, 5, 14, 1.0, 10) },
+                    // Выше PF, но слишком мало сделок
+                    SweepRun { value: 50.0, trades: trades(now, 5, 5, 3.0, 1) },
+                ],
+            },
+            ParameterSweep {
+                param: "mstrike_depth".to_string(),
+                current: 2.0,
+                runs: vec![SweepRun { value: 3.0, trades: trades(now, 5, 47, 1.0, 40) }],
+            },
+        ];
+        // Журнал - база для mstrike_depth; старые сделки вне окна не считаются
+        let mut journal = trades(now, 3, 11, 1.0, 10);
+        journal.extend(trades(now, 45, 0, 1.0, 50));
+
+        let engine = RecommendationEngine::new(RecommendationConfig::default());
+        let recs = engine.analyze(&journal, &sweeps, now);
+        assert_eq!(recs.len(), 1, "{:?}", recs);
+        assert_eq!(recs[0].text, "hook_sell_level 75→60 improves PF from 1.1 to 1.4 in the last 30 days");
+        assert_eq!(recs[0].baseline.trades, 21);
+
+        let lenient = RecommendationEngine::new(RecommendationConfig { min_pf_gain: 0.05, ..RecommendationConfig::default() });
+        let recs = lenient.analyze(&journal, &sweeps, now);
+        let params: Vec<_> = recs.iter().map(|r| r.param.as_str()).collect();
+        assert_eq!(params, ["hook_sell_level", "mstrike_depth"]);
+    }
+
+    struct Echo(Result<&'static str, &'static str>);
+
+    #[async_trait]
+    impl LlmProvider for Echo {
+        async fn complete(&self, _system: &str, _prompt: &str) -> Result<String> {
+            self.0.map(str::to_string).map_err(|err| anyhow!(err))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_llm_phrasing_falls_back_to_plain_text() {
+        let now = Utc::now();
+        let sweeps = vec![ParameterSweep {
+            param: "hook_sell_level".to_string(),
+            current: 75.0,
+            runs: vec![
+                SweepRun { value: 75.0, trades: trades(now, 1, 11, 1.0, 10) },
+                SweepRun { value: 60.0, trades: trades(now, 1, 14, 1.0, 10) },
+            ],
+        }];
+        let recs = RecommendationEngine::new(RecommendationConfig::default()).analyze(&[], &sweeps, now);
+
+        let phrased = RecommendationEngine::new(RecommendationConfig::default())
+            .with_llm(Echo(Ok("Lower hook_sell_level from 75 to 60: PF 1.1 → 1.4 over 30 days.")));
+        assert_eq!(phrased.phrase("Hook BTC", &recs).await[0], "Lower hook_sell_level from 75 to 60: PF 1.1 → 1.4 over 30 days.");
+
+        let broken = RecommendationEngine::new(RecommendationConfig::default()).with_llm(Echo(Err("timeout")));
+        assert_eq!(broken.phrase("Hook BTC", &recs).await, [recs[0].text.clone()]);
+    }
+}
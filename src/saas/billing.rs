@@ -0,0 +1,296 @@
+//! Учёт использования по тенантам и выгрузка в биллинг
+//!
+//! `UsageMeter` считает по дням (UTC): исполненные сделки, запущенные бэктесты и
+//! подписанные символы - пик за день и символо-часы (число символов, умноженное на
+//! время подписки). Отчёт за период отдаётся в JSON/CSV или в `BillingProvider`;
+//! сам провайдер (Stripe, свой биллинг) подключается реализацией трейта.
+
+use crate::notifications::webhooks::http_client;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub trades: u64,
+    pub backtests: u64,
+    pub peak_symbols: usize,
+    pub symbol_hours: f64,
+}
+
+impl DailyUsage {
+    fn new(date: NaiveDate) -> Self {
+        Self { date, trades: 0, backtests: 0, peak_symbols: 0, symbol_hours: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub trades: u64,
+    pub backtests: u64,
+    pub peak_symbols: usize,
+    pub symbol_hours: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub tenant: String,
+    /// Первый и последний день периода включительно
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub totals: UsageTotals,
+    pub days: Vec<DailyUsage>,
+}
+
+impl UsageReport {
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("tenant,date,trades,backtests,peak_symbols,symbol_hours\n");
+        for day in &self.days {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{:.4}",
+                self.tenant, day.date, day.trades, day.backtests, day.peak_symbols, day.symbol_hours
+            );
+        }
+        csv
+    }
+}
+
+#[derive(Debug)]
+struct TenantUsage {
+    days: BTreeMap<NaiveDate, DailyUsage>,
+    symbols: usize,
+    /// С какого момента символо-часы ещё не начислены
+    accrued_to: DateTime<Utc>,
+}
+
+impl TenantUsage {
+    fn day(&mut self, date: NaiveDate) -> &mut DailyUsage {
+        self.days.entry(date).or_insert_with(|| DailyUsage::new(date))
+    }
+
+    /// Начисляет символо-часы до `now`, раскладывая интервал по дням
+    fn accrue(&mut self, now: DateTime<Utc>) {
+        while self.accrued_to < now {
+            let date = self.accrued_to.date_naive();
+            let midnight = (date + Duration::days(1)).and_hms_opt(0, 0, 0).expect("midnight").and_utc();
+            let until = now.min(midnight);
+            let hours = (until - self.accrued_to).num_milliseconds() as f64 / 3_600_000.0;
+            let symbols = self.symbols;
+            if symbols > 0 {
+                let day = self.day(date);
+                day.symbol_hours += symbols as f64 * hours;
+                day.peak_symbols = day.peak_symbols.max(symbols);
+            }
+            self.accrued_to = until;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UsageMeter {
+    tenants: Mutex<HashMap<String, TenantUsage>>,
+}
+
+impl UsageMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_tenant<R>(&self, tenant: &str, at: DateTime<Utc>, f: impl FnOnce(&mut TenantUsage) -> R) -> R {
+        let mut tenants = self.tenants.lock().expect("usage meter poisoned");
+        let usage = tenants.entry(tenant.to_string()).or_insert_with(|| TenantUsage {
+            days: BTreeMap::new(),
+            symbols: 0,
+            accrued_to: at,
+        });
+        usage.accrue(at);
+        f(usage)
+    }
+
+    pub fn record_trade(&self, tenant: &str, at: DateTime<Utc>) {
+        self.with_tenant(tenant, at, |usage| usage.day(at.date_naive()).trades += 1);
+    }
+
+    pub fn record_backtest(&self, tenant: &str, at: DateTime<Utc>) {
+        self.with_tenant(tenant, at, |usage| usage.day(at.date_naive()).backtests += 1);
+    }
+
+    /// Число подписанных символов с момента `at` (0 - инстанс остановлен)
+    pub fn set_symbols(&self, tenant: &str, symbols: usize, at: DateTime<Utc>) {
+        self.with_tenant(tenant, at, |usage| {
+            usage.symbols = symbols;
+            let day = usage.day(at.date_naive());
+            day.peak_symbols = day.peak_symbols.max(symbols);
+        });
+    }
+
+    pub fn tenants(&self) -> Vec<String> {
+        let mut tenants: Vec<String> = self.tenants.lock().expect("usage meter poisoned").keys().cloned().collect();
+        tenants.sort_unstable();
+        tenants
+    }
+
+    /// Отчёт за дни `from..=to`; открытая подписка начисляется по `now`
+    pub fn report(&self, tenant: &str, from: NaiveDate, to: NaiveDate, now: DateTime<Utc>) -> UsageReport {
+        let days: Vec<DailyUsage> = self.with_tenant(tenant, now, |usage| {
+            usage.days.range(from..=to).map(|(_, day)| day.clone()).collect()
+        });
+        let totals = days.iter().fold(UsageTotals::default(), |mut totals, day| {
+            totals.trades += day.trades;
+            totals.backtests += day.backtests;
+            totals.peak_symbols = totals.peak_symbols.max(day.peak_symbols);
+            totals.symbol_hours += day.symbol_hours;
+            totals
+        });
+        UsageReport { tenant: tenant.to_string(), from, to, generated_at: now, totals, days }
+    }
+}
+
+/// Куда уходят отчёты об использовании
+#[async_trait]
+pub trait BillingProvider: Send + Sync {
+    fn name(&self) -> &str;
+    async fn submit(&self, report: &UsageReport) -> Result<()>;
+}
+
+/// POST отчёта в JSON на URL биллинга
+pub struct WebhookBillingProvider {
+    http: reqwest::Client,
+    url: String,
+    bearer: Option<String>,
+}
+
+impl WebhookBillingProvider {
+    pub fn new(url: impl Into<String>, bearer: Option<String>) -> Self {
+        Self { http: http_client(), url: url.into(), bearer }
+    }
+}
+
+#[async_trait]
+impl BillingProvider for WebhookBillingProvider {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn submit(&self, report: &UsageReport) -> Result<()> {
+        let mut request = self.http.post(&self.url).json(report);
+        if let Some(bearer) = &self.bearer {
+            request = request.bearer_auth(bearer);
+        }
+        let response = request.send().await.context("billing webhook request")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("billing webhook returned {}: {}", status, body);
+        }
+        Ok(())
+    }
+}
+
+/// Отправляет отчёты всех тенантов за период. Ошибка по одному тенанту не мешает
+/// остальным; в конце возвращается ошибка со списком неотправленных
+pub async fn submit_usage(
+    meter: &UsageMeter,
+    provider: &dyn BillingProvider,
+    from: NaiveDate,
+    to: NaiveDate,
+    now: DateTime<Utc>,
+) -> Result<Vec<UsageReport>> {
+    let mut submitted = Vec::new();
+    let mut failed = Vec::new();
+    for tenant in meter.tenants() {
+        let report = meter.report(&tenant, from, to, now);
+        match provider.submit(&report).await {
+            Ok(()) => submitted.push(report),
+            Err(err) => {
+                log::error!("billing {}: usage of {} for {}..{} not submitted: {:#}", provider.name(), tenant, from, to, err);
+                failed.push(tenant);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        bail!("billing {}: {} tenants not submitted: {}", provider.name(), failed.len(), failed.join(", "));
+    }
+    Ok(submitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_symbol_hours_split_across_midnight() {
+        let meter = UsageMeter::new();
+        meter.set_symbols("alice", 4, at(1, 22));
+        meter.record_trade("alice", at(1, 23));
+        meter.record_trade("alice", at(2, 1));
+        meter.set_symbols("alice", 1, at(2, 2));
+        meter.record_backtest("alice", at(2, 3));
+
+        let report = meter.report("alice", date(1), date(2), at(2, 12));
+        assert_eq!(report.days.len(), 2);
+        // 1-го: 4 символа x 2 часа; 2-го: 4 x 2 + 1 x 10
+        assert!((report.days[0].symbol_hours - 8.0).abs() < 1e-9);
+        assert!((report.days[1].symbol_hours - 18.0).abs() < 1e-9);
+        assert_eq!(report.totals.trades, 2);
+        assert_eq!(report.totals.backtests, 1);
+        assert_eq!(report.totals.peak_symbols, 4);
+
+        // Отчёт за один день не видит соседние
+        assert_eq!(meter.report("alice", date(2), date(2), at(2, 12)).totals.trades, 1);
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.contains("alice,2024-03-02,1,1,4,18.0000"), "{}", csv);
+    }
+
+    struct Recording {
+        reports: Mutex<Vec<UsageReport>>,
+        reject: &'static str,
+    }
+
+    #[async_trait]
+    impl BillingProvider for Recording {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn submit(&self, report: &UsageReport) -> Result<()> {
+            if report.tenant == self.reject {
+                bail!("account closed");
+            }
+            self.reports.lock().unwrap().push(report.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_tenant_does_not_block_others() {
+        let meter = UsageMeter::new();
+        meter.record_backtest("alice", at(1, 10));
+        meter.record_backtest("bob", at(1, 11));
+        meter.record_backtest("carol", at(1, 12));
+        let provider = Recording { reports: Mutex::new(Vec::new()), reject: "bob" };
+
+        let err = submit_usage(&meter, &provider, date(1), date(1), at(1, 23)).await.unwrap_err();
+        assert!(err.to_string().contains("1 tenants not submitted: bob"), "{}", err);
+        let reports = provider.reports.lock().unwrap();
+        let tenants: Vec<&str> = reports.iter().map(|r| r.tenant.as_str()).collect();
+        assert_eq!(tenants, vec!["alice", "carol"]);
+    }
+}
@@ -0,0 +1,369 @@
+//! Маркетплейс стратегий: подписанные бандлы пресетов и локальная библиотека
+//!
+//! Бандл - JSON-файл: манифест (стратегия с параметрами, описание, автор и опционально
+//! результаты бэктеста) и подпись Ed25519 автора. Подписываются ровно байты манифеста,
+//! поэтому он лежит в файле строкой - пересериализация не ломает подпись.
+//!
+//! Библиотека принимает только бандлы с верной подписью доверенного ключа и с
+//! параметрами, которые ложатся на конфиг стратегии. Ссылки на локальные файлы
+//! (`entry_model`) в бандле запрещены: пресет не должен зависеть от чужой машины.
+
+use super::tenants::TenantStrategy;
+use crate::backtest::metrics::BacktestResult;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+pub const BUNDLE_FORMAT: u32 = 1;
+const BUNDLE_EXTENSION: &str = "bundle.json";
+const TRUSTED_KEYS_FILE: &str = "trusted_keys.json";
+
+/// Итоги бэктеста, на котором автор проверял пресет
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BacktestEvidence {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub symbols: Vec<String>,
+    pub total_trades: usize,
+    pub total_pnl: f64,
+    pub win_rate: f64,
+    pub profit_factor: f64,
+    pub max_drawdown: f64,
+    pub sharpe_ratio: f64,
+    pub stars: u8,
+}
+
+impl BacktestEvidence {
+    pub fn from_result(result: &BacktestResult, from: NaiveDate, to: NaiveDate, symbols: Vec<String>) -> Self {
+        Self {
+            from,
+            to,
+            symbols,
+            total_trades: result.total_trades,
+            total_pnl: result.total_pnl,
+            win_rate: result.win_rate,
+            profit_factor: result.profit_factor,
+            max_drawdown: result.max_drawdown,
+            sharpe_ratio: result.sharpe_ratio,
+            stars: result.rating.stars,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleManifest {
+    /// Латиница в нижнем регистре, цифры, `-` и `_`
+    pub name: String,
+    pub version: u32,
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub strategy: TenantStrategy,
+    #[serde(default)]
+    pub evidence: Option<BacktestEvidence>,
+}
+
+impl BundleManifest {
+    fn validate(&self) -> Result<()> {
+        let name_ok = !self.name.is_empty()
+            && self.name.len() <= 64
+            && self.name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+        ensure!(name_ok, "bundle name {:?} must be 1-64 chars of [a-z0-9_-]", self.name);
+        if let Value::Object(params) = &self.strategy.params {
+            ensure!(
+                params.get("entry_model").is_none_or(Value::is_null),
+                "bundle {} references a local entry model file",
+                self.name
+            );
+        }
+        self.strategy.validate().with_context(|| format!("bundle {} strategy", self.name))
+    }
+}
+
+/// Файл бандла
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyBundle {
+    pub format: u32,
+    /// JSON `BundleManifest` - подписанные байты
+    pub manifest: String,
+    /// Ed25519, hex
+    pub public_key: String,
+    pub signature: String,
+}
+
+impl StrategyBundle {
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("read bundle {}", path.display()))?;
+        serde_json::from_slice(&data).with_context(|| format!("parse bundle {}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?).with_context(|| format!("write bundle {}", path.display()))
+    }
+
+    /// Проверяет формат, подпись и манифест. Доверие к ключу - забота вызывающего
+    pub fn verify(&self) -> Result<BundleManifest> {
+        ensure!(self.format == BUNDLE_FORMAT, "unsupported bundle format {}", self.format);
+        let public_key = decode_public_key(&self.public_key)?;
+        let signature = hex::decode(&self.signature).context("bundle signature is not hex")?;
+        UnparsedPublicKey::new(&ED25519, &public_key)
+            .verify(self.manifest.as_bytes(), &signature)
+            .map_err(|_| anyhow!("bundle signature does not match its manifest"))?;
+        let manifest: BundleManifest = serde_json::from_str(&self.manifest).context("bundle manifest")?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+}
+
+fn decode_public_key(key: &str) -> Result<Vec<u8>> {
+    let bytes = hex::decode(key.trim()).context("public key is not hex")?;
+    ensure!(bytes.len() == 32, "Ed25519 public key must be 32 bytes, got {}", bytes.len());
+    Ok(bytes)
+}
+
+/// Ключ автора бандлов
+pub struct BundleSigner {
+    key_pair: Ed25519KeyPair,
+}
+
+impl BundleSigner {
+    /// Новый ключ; PKCS#8 нужно сохранить, чтобы подписывать следующие версии
+    pub fn generate() -> Result<(Self, Zeroizing<Vec<u8>>)> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| anyhow!("generate bundle signing key"))?;
+        let pkcs8 = Zeroizing::new(pkcs8.as_ref().to_vec());
+        Ok((Self::from_pkcs8(&pkcs8)?, pkcs8))
+    }
+
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Self> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8).map_err(|err| anyhow!("bundle signing key: {}", err))?;
+        Ok(Self { key_pair })
+    }
+
+    pub fn public_key(&self) -> String {
+        hex::encode(self.key_pair.public_key().as_ref())
+    }
+
+    pub fn sign(&self, manifest: &BundleManifest) -> Result<StrategyBundle> {
+        manifest.validate()?;
+        let manifest = serde_json::to_string(manifest)?;
+        let signature = self.key_pair.sign(manifest.as_bytes());
+        Ok(StrategyBundle {
+            format: BUNDLE_FORMAT,
+            manifest,
+            public_key: self.public_key(),
+            signature: hex::encode(signature.as_ref()),
+        })
+    }
+}
+
+/// Проверенный бандл в библиотеке
+#[derive(Debug, Clone)]
+pub struct LibraryEntry {
+    pub manifest: BundleManifest,
+    /// Имя доверенного ключа, которым подписан бандл
+    pub signer: String,
+    pub path: PathBuf,
+}
+
+/// Каталог с бандлами и `trusted_keys.json` (имя ключа -> public key hex)
+pub struct BundleLibrary {
+    dir: PathBuf,
+    trusted: BTreeMap<String, String>,
+}
+
+impl BundleLibrary {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("create bundle library {}", dir.display()))?;
+        let keys_path = dir.join(TRUSTED_KEYS_FILE);
+        let trusted = match fs::read(&keys_path) {
+            Ok(data) => serde_json::from_slice(&data).with_context(|| format!("parse {}", keys_path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err).with_context(|| format!("read {}", keys_path.display())),
+        };
+        Ok(Self { dir, trusted })
+    }
+
+    /// Доверять бандлам, подписанным этим ключом
+    pub fn trust(&mut self, label: &str, public_key: &str) -> Result<()> {
+        decode_public_key(public_key)?;
+        self.trusted.insert(label.to_string(), public_key.trim().to_ascii_lowercase());
+        let keys_path = self.dir.join(TRUSTED_KEYS_FILE);
+        fs::write(&keys_path, serde_json::to_vec_pretty(&self.trusted)?)
+            .with_context(|| format!("write {}", keys_path.display()))
+    }
+
+    pub fn untrust(&mut self, label: &str) -> Result<bool> {
+        let removed = self.trusted.remove(label).is_some();
+        let keys_path = self.dir.join(TRUSTED_KEYS_FILE);
+        fs::write(&keys_path, serde_json::to_vec_pretty(&self.trusted)?)
+            .with_context(|| format!("write {}", keys_path.display()))?;
+        Ok(removed)
+    }
+
+    fn signer(&self, public_key: &str) -> Option<&str> {
+        let public_key = public_key.to_ascii_lowercase();
+        self.trusted.iter().find(|(_, key)| **key == public_key).map(|(label, _)| label.as_str())
+    }
+
+    fn load(&self, path: &Path) -> Result<LibraryEntry> {
+        let bundle = StrategyBundle::read(path)?;
+        let manifest = bundle.verify().with_context(|| format!("bundle {}", path.display()))?;
+        let signer = self
+            .signer(&bundle.public_key)
+            .with_context(|| format!("bundle {} is signed by untrusted key {}", path.display(), bundle.public_key))?;
+        Ok(LibraryEntry { manifest, signer: signer.to_string(), path: path.to_path_buf() })
+    }
+
+    /// Проверяет бандл и копирует в библиотеку. Та же версия с другим содержимым -
+    /// ошибка: версия пресета неизменна
+    pub fn import(&self, path: &Path) -> Result<LibraryEntry> {
+        let entry = self.load(path)?;
+        let target = self
+            .dir
+            .join(format!("{}-v{}.{}", entry.manifest.name, entry.manifest.version, BUNDLE_EXTENSION));
+        let data = fs::read(path)?;
+        match fs::read(&target) {
+            Ok(existing) if existing == data => {}
+            Ok(_) => bail!(
+                "bundle {} v{} is already in the library with different content",
+                entry.manifest.name,
+                entry.manifest.version
+            ),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                fs::write(&target, &data).with_context(|| format!("write {}", target.display()))?
+            }
+            Err(err) => return Err(err).with_context(|| format!("read {}", target.display())),
+        }
+        log::info!("imported bundle {} v{} signed by {}", entry.manifest.name, entry.manifest.version, entry.signer);
+        Ok(LibraryEntry { path: target, ..entry })
+    }
+
+    /// Все проверенные бандлы по имени и версии. Битые и недоверенные файлы
+    /// пропускаются с ошибкой в логе
+    pub fn list(&self) -> Result<Vec<LibraryEntry>> {
+        let mut entries = Vec::new();
+        for file in fs::read_dir(&self.dir).with_context(|| format!("read {}", self.dir.display()))? {
+            let path = file?.path();
+            if !path.to_string_lossy().ends_with(BUNDLE_EXTENSION) {
+                continue;
+            }
+            match self.load(&path) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => log::error!("bundle library: {:#}", err),
+            }
+        }
+        entries.sort_by(|a, b| (&a.manifest.name, a.manifest.version).cmp(&(&b.manifest.name, b.manifest.version)));
+        Ok(entries)
+    }
+
+    /// Бандл по имени; без версии - последняя
+    pub fn get(&self, name: &str, version: Option<u32>) -> Result<LibraryEntry> {
+        self.list()?
+            .into_iter()
+            .filter(|entry| entry.manifest.name == name && version.is_none_or(|v| entry.manifest.version == v))
+            .max_by_key(|entry| entry.manifest.version)
+            .with_context(|| match version {
+                Some(version) => format!("no bundle {} v{} in the library", name, version),
+                None => format!("no bundle {} in the library", name),
+            })
+    }
+
+    /// Стратегия из бандла - для `TenantConfig` или `TenantStrategy::adapter`
+    pub fn instantiate(&self, name: &str, version: Option<u32>) -> Result<TenantStrategy> {
+        Ok(self.get(name, version)?.manifest.strategy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::saas::tenants::StrategyKind;
+    use serde_json::json;
+
+    fn manifest(name: &str, version: u32, params: Value) -> BundleManifest {
+        BundleManifest {
+            name: name.to_string(),
+            version,
+            author: "alice".to_string(),
+            description: "deep shots on alts".to_string(),
+            tags: vec!["alts".to_string()],
+            created_at: Utc::now(),
+            strategy: TenantStrategy { kind: StrategyKind::MShot, params },
+            evidence: None,
+        }
+    }
+
+    fn library(name: &str) -> (PathBuf, BundleLibrary) {
+        let dir = std::env::temp_dir().join(format!("bundles-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let library = BundleLibrary::open(dir.join("library")).unwrap();
+        (dir, library)
+    }
+
+    #[test]
+    fn test_import_and_instantiate_latest_version() {
+        let (dir, mut library) = library("import");
+        let (signer, pkcs8) = BundleSigner::generate().unwrap();
+        assert_eq!(BundleSigner::from_pkcs8(&pkcs8).unwrap().public_key(), signer.public_key());
+
+        let v1 = dir.join("deep-v1.json");
+        let v2 = dir.join("deep-v2.json");
+        signer.sign(&manifest("deep", 1, json!({"order_size": 10.0}))).unwrap().write(&v1).unwrap();
+        signer.sign(&manifest("deep", 2, json!({"order_size": 20.0}))).unwrap().write(&v2).unwrap();
+
+        // Подпись верна, но ключу ещё не доверяют
+        let err = library.import(&v1).unwrap_err();
+        assert!(format!("{:#}", err).contains("untrusted key"), "{:#}", err);
+
+        library.trust("alice", &signer.public_key()).unwrap();
+        library.import(&v1).unwrap();
+        library.import(&v2).unwrap();
+        // Повторный импорт того же файла не ошибка
+        library.import(&v2).unwrap();
+
+        let reopened = BundleLibrary::open(dir.join("library")).unwrap();
+        let entries = reopened.list().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.signer == "alice"));
+        let strategy = reopened.instantiate("deep", None).unwrap();
+        assert_eq!(strategy.params["order_size"], json!(20.0));
+        assert!(strategy.adapter().is_ok());
+        assert_eq!(reopened.instantiate("deep", Some(1)).unwrap().params["order_size"], json!(10.0));
+        assert!(reopened.instantiate("deep", Some(3)).is_err());
+
+        // Другой файл под той же версией не подменяет импортированный
+        let forged = dir.join("deep-v2-forged.json");
+        signer.sign(&manifest("deep", 2, json!({"order_size": 30.0}))).unwrap().write(&forged).unwrap();
+        assert!(library.import(&forged).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tampered_and_unsafe_bundles_are_rejected() {
+        let (signer, _) = BundleSigner::generate().unwrap();
+        let mut bundle = signer.sign(&manifest("deep", 1, json!({"order_size": 10.0}))).unwrap();
+        assert!(bundle.verify().is_ok());
+        bundle.manifest = bundle.manifest.replace("10.0", "1000.0");
+        let err = bundle.verify().unwrap_err();
+        assert!(err.to_string().contains("signature"), "{:#}", err);
+
+        assert!(signer.sign(&manifest("deep", 1, json!({"no_such_param": 1}))).is_err());
+        assert!(signer.sign(&manifest("../deep", 1, Value::Null)).is_err());
+        let local_model = json!({"entry_model": {"path": "/home/alice/model.onnx", "threshold": 0.5}});
+        let err = signer.sign(&manifest("deep", 1, local_model)).unwrap_err();
+        assert!(err.to_string().contains("local entry model"), "{:#}", err);
+    }
+}
@@ -1,5 +1,6 @@
 //! SaaS модуль для управления стратегиями пользователей
-//! Визуальный редактор, рейтинги, ИИ рекомендации
+//! Визуальный редактор, рейтинги, ИИ рекомендации, мультитенантный хост, биллинг,
+//! маркетплейс подписанных пресетов, копи-трейдинг
 
 #[cfg(feature = "database")]
 pub mod strategies;
@@ -10,3 +11,15 @@ pub mod ratings;
 #[cfg(feature = "database")]
 pub mod ai_recommendations;
 
+
+pub mod billing;
+#[cfg(feature = "marketplace")]
+pub mod marketplace;
+pub mod signals;
+pub mod tenants;
+
+pub use billing::{BillingProvider, UsageMeter, UsageReport, WebhookBillingProvider};
+#[cfg(feature = "marketplace")]
+pub use marketplace::{BundleLibrary, BundleManifest, BundleSigner, StrategyBundle};
+pub use signals::{CopySignal, FollowDecision, FollowerConfig, SignalFollower, SignalPublisher, SignalSubscriber};
+pub use tenants::{TenantConfig, TenantQuotas, TenantRegistry, TenantScope, TenantStatus};
@@ -0,0 +1,434 @@
+//! Копи-трейдинг: публикация сигналов лидера и повторение у подписчиков
+//!
+//! Лидер публикует исполнения своих ордеров как очищенные сигналы: символ, стратегия,
+//! вход/выход и цена. Размер, id ордеров и аккаунт наружу не уходят. Подписчики
+//! получают сигналы по WebSocket (`/signals/ws`, токен в `Authorization: Bearer`)
+//! и решают сами: свой размер в USDT, свой фильтр символов, проверка свежести
+//! сигнала и проскальзывания против своей цены, лимит позиций и `GlobalRiskManager`.
+//!
+//! Сигналы нумеруются; пропуск номера (переполнение канала у медленного подписчика,
+//! переподключение) считается в `SignalFollower::missed`.
+
+use crate::base_classes::types::Side;
+use crate::config::credentials::Secret;
+use crate::risk::{GlobalRiskManager, RiskAction};
+use crate::runtime::FillEvent;
+use anyhow::{bail, Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::{header::AUTHORIZATION, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalKind {
+    Entry,
+    Exit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CopySignal {
+    pub seq: u64,
+    pub leader: String,
+    pub ts: DateTime<Utc>,
+    pub symbol: String,
+    pub strategy: String,
+    pub kind: SignalKind,
+    /// Цена исполнения у лидера
+    pub price: f64,
+}
+
+pub struct SignalPublisher {
+    leader: String,
+    seq: AtomicU64,
+    tx: broadcast::Sender<CopySignal>,
+    /// SHA-256 токенов подписчиков: сами токены в памяти не держим
+    tokens: Vec<[u8; 32]>,
+}
+
+impl SignalPublisher {
+    /// `capacity` - сколько сигналов ждёт медленного подписчика, дальше он их теряет
+    pub fn new(leader: &str, follower_tokens: &[Secret], capacity: usize) -> Self {
+        assert!(!follower_tokens.is_empty(), "signal publisher needs at least one follower token");
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self {
+            leader: leader.to_string(),
+            seq: AtomicU64::new(0),
+            tx,
+            tokens: follower_tokens.iter().map(|t| token_digest(t.expose())).collect(),
+        }
+    }
+
+    /// Исполнение ордера лидера: покупка - вход, продажа - выход
+    pub fn publish_fill(&self, strategy: &str, fill: &FillEvent, at: DateTime<Utc>) -> CopySignal {
+        let signal = CopySignal {
+            seq: self.seq.fetch_add(1, Ordering::Relaxed) + 1,
+            leader: self.leader.clone(),
+            ts: at,
+            symbol: fill.symbol.as_str().to_string(),
+            strategy: strategy.to_string(),
+            kind: match fill.side {
+                Side::Bid => SignalKind::Entry,
+                Side::Ask => SignalKind::Exit,
+            },
+            price: fill.price,
+        };
+        // Ошибка send - просто нет подписчиков
+        let _ = self.tx.send(signal.clone());
+        signal
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<CopySignal> {
+        self.tx.subscribe()
+    }
+
+    pub fn followers(&self) -> usize {
+        self.tx.receiver_count()
+    }
+
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| s.strip_prefix("Bearer "))
+            .is_some_and(|token| self.tokens.contains(&token_digest(token)))
+    }
+}
+
+fn token_digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// `GET /signals/ws` - поток сигналов лидера
+pub fn signal_routes(publisher: Arc<SignalPublisher>) -> Router {
+    Router::new().route("/signals/ws", get(signals_ws)).with_state(publisher)
+}
+
+async fn signals_ws(
+    State(publisher): State<Arc<SignalPublisher>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if !publisher.authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let rx = publisher.subscribe();
+    ws.on_upgrade(move |socket| stream_signals(socket, rx))
+}
+
+async fn stream_signals(mut socket: WebSocket, mut rx: broadcast::Receiver<CopySignal>) {
+    loop {
+        match rx.recv().await {
+            Ok(signal) => {
+                let text = match serde_json::to_string(&signal) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        log::error!("signal {} not serialized: {}", signal.seq, err);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                log::warn!("signal follower lagged, {} signals dropped", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+        }
+    }
+}
+
+/// Клиент подписчика
+pub struct SignalSubscriber {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl SignalSubscriber {
+    pub async fn connect(url: &str, token: &Secret) -> Result<Self> {
+        let mut request = url.into_client_request().with_context(|| format!("signal url {}", url))?;
+        let auth = HeaderValue::from_str(&format!("Bearer {}", token.expose())).context("signal token")?;
+        request.headers_mut().insert(AUTHORIZATION, auth);
+        let (stream, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .with_context(|| format!("connect to signal feed {}", url))?;
+        Ok(Self { stream })
+    }
+
+    /// Следующий сигнал; `None` - лидер закрыл соединение
+    pub async fn next(&mut self) -> Result<Option<CopySignal>> {
+        use tokio_tungstenite::tungstenite::Message;
+        while let Some(message) = self.stream.next().await {
+            match message.context("signal feed")? {
+                Message::Text(text) => return serde_json::from_str(&text).context("signal").map(Some),
+                Message::Close(_) => return Ok(None),
+                Message::Binary(_) => bail!("unexpected binary message in signal feed"),
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowerConfig {
+    /// Размер входа подписчика, USDT
+    pub order_size_usdt: f64,
+    /// Насколько своя цена может быть хуже цены лидера, %
+    #[serde(default = "default_max_slippage_pct")]
+    pub max_slippage_pct: f64,
+    /// Сигнал старше этого не повторяется
+    #[serde(default = "default_max_signal_age_ms")]
+    pub max_signal_age_ms: i64,
+    #[serde(default = "default_max_positions")]
+    pub max_positions: usize,
+    /// Пусто - все символы / все стратегии лидера
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    #[serde(default)]
+    pub strategies: Vec<String>,
+}
+
+fn default_max_slippage_pct() -> f64 {
+    0.5
+}
+
+fn default_max_signal_age_ms() -> i64 {
+    2_000
+}
+
+fn default_max_positions() -> usize {
+    3
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FollowDecision {
+    /// Лимитная покупка на `usdt` не дороже `limit_price`
+    Enter { symbol: String, limit_price: f64, usdt: f64 },
+    /// Продажа всей своей позиции
+    Exit { symbol: String, size: f64 },
+    Skip(SkipReason),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    Filtered,
+    Stale { age_ms: i64 },
+    NoPrice,
+    Slippage { pct: f64 },
+    AlreadyIn,
+    NotIn,
+    MaxPositions,
+    RiskStop,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    entry_price: f64,
+    size: f64,
+}
+
+/// Решения подписчика по сигналам; исполнение - у вызывающего, который сообщает
+/// результат через `on_entry_filled` / `on_entry_failed` / `on_exit_filled`
+pub struct SignalFollower {
+    config: FollowerConfig,
+    pub risk: GlobalRiskManager,
+    positions: HashMap<String, Position>,
+    pending: HashSet<String>,
+    last_seq: Option<u64>,
+    missed: u64,
+}
+
+impl SignalFollower {
+    pub fn new(config: FollowerConfig, risk: GlobalRiskManager) -> Self {
+        Self { config, risk, positions: HashMap::new(), pending: HashSet::new(), last_seq: None, missed: 0 }
+    }
+
+    /// Сколько сигналов пропущено по разрывам нумерации
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    pub fn open_positions(&self) -> usize {
+        self.positions.len() + self.pending.len()
+    }
+
+    /// `own_price` - своя лучшая цена ask (вход) по символу
+    pub fn on_signal(&mut self, signal: &CopySignal, own_price: Option<f64>, now: DateTime<Utc>) -> FollowDecision {
+        if let Some(last) = self.last_seq
+            && signal.seq > last + 1
+        {
+            let gap = signal.seq - last - 1;
+            self.missed += gap;
+            log::warn!("copy {}: {} signals missed before #{}", signal.leader, gap, signal.seq);
+        }
+        self.last_seq = Some(self.last_seq.map_or(signal.seq, |last| last.max(signal.seq)));
+
+        let decision = self.decide(signal, own_price, now);
+        if let FollowDecision::Skip(reason) = &decision {
+            log::info!("copy {} #{} {} {:?} skipped: {:?}", signal.leader, signal.seq, signal.symbol, signal.kind, reason);
+        }
+        decision
+    }
+
+    fn decide(&mut self, signal: &CopySignal, own_price: Option<f64>, now: DateTime<Utc>) -> FollowDecision {
+        let config = &self.config;
+        if signal.kind == SignalKind::Exit {
+            // Выход повторяется всегда, если есть позиция: свежесть и фильтры не важны
+            return match self.positions.get(&signal.symbol) {
+                Some(position) => FollowDecision::Exit { symbol: signal.symbol.clone(), size: position.size },
+                None => FollowDecision::Skip(SkipReason::NotIn),
+            };
+        }
+        let listed = |list: &[String], value: &str| list.is_empty() || list.iter().any(|v| v == value);
+        if !listed(&config.symbols, &signal.symbol) || !listed(&config.strategies, &signal.strategy) {
+            return FollowDecision::Skip(SkipReason::Filtered);
+        }
+        let age_ms = (now - signal.ts).num_milliseconds();
+        if age_ms > config.max_signal_age_ms {
+            return FollowDecision::Skip(SkipReason::Stale { age_ms });
+        }
+        if self.positions.contains_key(&signal.symbol) || self.pending.contains(&signal.symbol) {
+            return FollowDecision::Skip(SkipReason::AlreadyIn);
+        }
+        if self.open_positions() >= config.max_positions {
+            return FollowDecision::Skip(SkipReason::MaxPositions);
+        }
+        if self.risk.check_stop_conditions() != RiskAction::None {
+            return FollowDecision::Skip(SkipReason::RiskStop);
+        }
+        let Some(price) = own_price.filter(|p| *p > 0.0) else {
+            return FollowDecision::Skip(SkipReason::NoPrice);
+        };
+        let slippage_pct = (price - signal.price) / signal.price * 100.0;
+        if slippage_pct > config.max_slippage_pct {
+            return FollowDecision::Skip(SkipReason::Slippage { pct: slippage_pct });
+        }
+        self.pending.insert(signal.symbol.clone());
+        FollowDecision::Enter {
+            symbol: signal.symbol.clone(),
+            limit_price: signal.price * (1.0 + config.max_slippage_pct / 100.0),
+            usdt: config.order_size_usdt,
+        }
+    }
+
+    pub fn on_entry_filled(&mut self, symbol: &str, price: f64, size: f64) {
+        self.pending.remove(symbol);
+        let position = self.positions.entry(symbol.to_string()).or_insert(Position { entry_price: price, size: 0.0 });
+        let total = position.size + size;
+        position.entry_price = (position.entry_price * position.size + price * size) / total;
+        position.size = total;
+    }
+
+    pub fn on_entry_failed(&mut self, symbol: &str) {
+        self.pending.remove(symbol);
+    }
+
+    /// Закрывает позицию и пишет PnL в риск-менеджер; возвращает PnL, USDT
+    pub fn on_exit_filled(&mut self, symbol: &str, price: f64) -> Option<f64> {
+        let position = self.positions.remove(symbol)?;
+        let pnl = (price - position.entry_price) * position.size;
+        self.risk.record_trade_pnl(pnl);
+        Some(pnl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::symbol::Symbol;
+    use chrono::Duration;
+
+    fn fill(symbol: &str, side: Side, price: f64) -> FillEvent {
+        FillEvent { symbol: Symbol::new(symbol), strategy: 0, side, price, size: 123.0 }
+    }
+
+    fn follower() -> SignalFollower {
+        let config = FollowerConfig {
+            order_size_usdt: 20.0,
+            max_slippage_pct: 0.5,
+            max_signal_age_ms: 2_000,
+            max_positions: 1,
+            symbols: Vec::new(),
+            strategies: vec!["mshot".to_string()],
+        };
+        let mut risk = GlobalRiskManager::new();
+        risk.max_loss_per_trades = Some((5.0, 1));
+        SignalFollower::new(config, risk)
+    }
+
+    #[test]
+    fn test_follower_applies_own_checks() {
+        let publisher = SignalPublisher::new("leader", &[Secret::new("t")], 16);
+        let now = Utc::now();
+        let mut follower = follower();
+
+        let entry = publisher.publish_fill("mshot", &fill("BTC_USDT", Side::Bid, 100.0), now);
+        assert_eq!(entry.kind, SignalKind::Entry);
+        // Без размера лидера
+        assert!(!serde_json::to_string(&entry).unwrap().contains("123"));
+
+        let other = publisher.publish_fill("hook", &fill("ETH_USDT", Side::Bid, 10.0), now);
+        assert_eq!(follower.on_signal(&other, Some(10.0), now), FollowDecision::Skip(SkipReason::Filtered));
+        let stale = now + Duration::seconds(3);
+        assert!(matches!(follower.on_signal(&entry, Some(100.0), stale), FollowDecision::Skip(SkipReason::Stale { .. })));
+        assert!(matches!(
+            follower.on_signal(&entry, Some(101.0), now),
+            FollowDecision::Skip(SkipReason::Slippage { .. })
+        ));
+        match follower.on_signal(&entry, Some(100.2), now) {
+            FollowDecision::Enter { symbol, limit_price, usdt } => {
+                assert_eq!(symbol, "BTC_USDT");
+                assert!((limit_price - 100.5).abs() < 1e-9);
+                assert_eq!(usdt, 20.0);
+            }
+            other => panic!("expected entry, got {:?}", other),
+        }
+        // Ордер ещё не исполнен, но повторного входа нет
+        assert_eq!(follower.on_signal(&entry, Some(100.2), now), FollowDecision::Skip(SkipReason::AlreadyIn));
+        follower.on_entry_filled("BTC_USDT", 100.2, 0.2);
+
+        let exit = publisher.publish_fill("mshot", &fill("BTC_USDT", Side::Ask, 70.0), now);
+        assert_eq!(follower.on_signal(&exit, None, now), FollowDecision::Exit { symbol: "BTC_USDT".to_string(), size: 0.2 });
+        let pnl = follower.on_exit_filled("BTC_USDT", 70.0).unwrap();
+        assert!((pnl + 6.04).abs() < 1e-9);
+
+        // Убыток сессии выше лимита - новые входы не повторяются
+        let entry = publisher.publish_fill("mshot", &fill("BTC_USDT", Side::Bid, 70.0), now);
+        assert_eq!(follower.on_signal(&entry, Some(70.0), now), FollowDecision::Skip(SkipReason::RiskStop));
+        assert_eq!(follower.missed(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_signals_stream_over_websocket() {
+        let publisher = Arc::new(SignalPublisher::new("leader", &[Secret::new("follower-token")], 16));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/signals/ws", listener.local_addr().unwrap());
+        tokio::spawn(axum::serve(listener, signal_routes(publisher.clone())).into_future());
+
+        assert!(SignalSubscriber::connect(&url, &Secret::new("wrong")).await.is_err());
+        let mut subscriber = SignalSubscriber::connect(&url, &Secret::new("follower-token")).await.unwrap();
+        while publisher.followers() == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        let sent = publisher.publish_fill("mshot", &fill("BTC_USDT", Side::Bid, 100.0), Utc::now());
+        assert_eq!(subscriber.next().await.unwrap(), Some(sent));
+    }
+}
@@ -0,0 +1,681 @@
+//! Мультитенантный режим: изолированный инстанс бота на пользователя
+//!
+//! Реестр держит конфиг, квоты и статус каждого тенанта. Запущенный тенант - свой
+//! `ShardedRuntime` (свои потоки, стратегии и роутер ордеров) и свой `CredentialStore`
+//! из собственного keystore, без переменных окружения хоста. Квоты проверяются при
+//! регистрации и смене конфига (стратегии, символы, шарды, размер ордера) и на каждом
+//! запросе control API (запросов в минуту).
+//!
+//! Токены control API - JWT с тенантом и scope'ами. Токен отозванный (по `jti`) или
+//! выданный приостановленному тенанту не проходит.
+//!
+//! С `set_meter` реестр пишет использование в `UsageMeter`: подписанные символы от
+//! старта до остановки инстанса, исполненные сделки, прошедшие через `fill`, и
+//! бэктесты из `record_backtest`.
+
+use super::billing::UsageMeter;
+use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::{with_entry_model, HookAdapter, MShotAdapter, MStrikeAdapter, StrategyAdapter};
+use crate::base_classes::symbol::Symbol;
+use crate::config::credentials::{CredentialStore, Secret};
+use crate::runtime::{
+    FillEvent, OrderRouter, RoutedAction, RuntimeConfig, ShardStats, ShardedRuntime, StrategyFactory, StrategySet,
+};
+use crate::strategy::moon_strategies::{HookConfig, MShotConfig, MStrikeConfig};
+use anyhow::{bail, ensure, Context, Result};
+use axum::extract::{Request, State};
+use axum::http::{header::AUTHORIZATION, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TenantQuotas {
+    pub max_strategies: usize,
+    pub max_symbols: usize,
+    pub max_shards: usize,
+    /// Предел order_size каждой стратегии, USDT
+    pub max_order_usdt: f64,
+    pub api_requests_per_minute: u32,
+}
+
+impl Default for TenantQuotas {
+    fn default() -> Self {
+        Self {
+            max_strategies: 3,
+            max_symbols: 20,
+            max_shards: 1,
+            max_order_usdt: 100.0,
+            api_requests_per_minute: 120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    MShot,
+    MStrike,
+    Hook,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantStrategy {
+    pub kind: StrategyKind,
+    /// Поля конфига стратегии поверх значений по умолчанию
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone)]
+enum StrategyConfig {
+    MShot(MShotConfig),
+    MStrike(MStrikeConfig),
+    Hook(HookConfig),
+}
+
+impl TenantStrategy {
+    /// Проверяет, что параметры ложатся на конфиг стратегии
+    pub fn validate(&self) -> Result<()> {
+        self.resolve().map(|_| ())
+    }
+
+    /// Адаптер стратегии вне тенанта (бэктест, ручной запуск)
+    pub fn adapter(&self) -> Result<Box<dyn StrategyAdapter + Send>> {
+        self.resolve()?.adapter()
+    }
+
+    fn resolve(&self) -> Result<StrategyConfig> {
+        Ok(match self.kind {
+            StrategyKind::MShot => StrategyConfig::MShot(with_overrides(&self.params)?),
+            StrategyKind::MStrike => StrategyConfig::MStrike(with_overrides(&self.params)?),
+            StrategyKind::Hook => StrategyConfig::Hook(with_overrides(&self.params)?),
+        })
+    }
+}
+
+impl StrategyConfig {
+    fn order_size(&self) -> f64 {
+        match self {
+            Self::MShot(config) => config.order_size,
+            Self::MStrike(config) => config.order_size,
+            Self::Hook(config) => config.order_size,
+        }
+    }
+
+    fn adapter(&self) -> Result<Box<dyn StrategyAdapter + Send>> {
+        match self {
+            Self::MShot(config) => with_entry_model(MShotAdapter::new(config.clone()), config.entry_model.as_ref()),
+            Self::MStrike(config) => with_entry_model(MStrikeAdapter::new(config.clone()), config.entry_model.as_ref()),
+            Self::Hook(config) => with_entry_model(HookAdapter::new(config.clone()), config.entry_model.as_ref()),
+        }
+    }
+}
+
+/// Конфиг по умолчанию с полями из `params`; неизвестное поле - ошибка, а не молчаливый пропуск
+fn with_overrides<T: Default + Serialize + DeserializeOwned>(params: &Value) -> Result<T> {
+    let mut config = serde_json::to_value(T::default())?;
+    match params {
+        Value::Null => {}
+        Value::Object(overrides) => {
+            let fields = config.as_object_mut().context("strategy config is not an object")?;
+            for (name, value) in overrides {
+                ensure!(fields.contains_key(name), "unknown strategy param {}", name);
+                fields.insert(name.clone(), value.clone());
+            }
+        }
+        other => bail!("strategy params must be an object, got {}", other),
+    }
+    serde_json::from_value(config).context("strategy params")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Латиница в нижнем регистре, цифры, `-` и `_`
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub quotas: TenantQuotas,
+    pub symbols: Vec<String>,
+    pub strategies: Vec<TenantStrategy>,
+    #[serde(default = "default_shards")]
+    pub shards: usize,
+    /// Keystore тенанта; без него у тенанта нет ключей бирж
+    #[serde(default)]
+    pub keystore: Option<PathBuf>,
+}
+
+fn default_shards() -> usize {
+    1
+}
+
+impl TenantConfig {
+    /// Проверяет конфиг против квот; возвращает конфиги стратегий
+    fn validate(&self) -> Result<Vec<StrategyConfig>> {
+        let id_ok = !self.id.is_empty()
+            && self.id.len() <= 32
+            && self.id.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+        ensure!(id_ok, "tenant id {:?} must be 1-32 chars of [a-z0-9_-]", self.id);
+        let quotas = &self.quotas;
+        ensure!(!self.symbols.is_empty(), "tenant {} has no symbols", self.id);
+        ensure!(
+            self.symbols.len() <= quotas.max_symbols,
+            "tenant {}: {} symbols over quota {}",
+            self.id,
+            self.symbols.len(),
+            quotas.max_symbols
+        );
+        ensure!(
+            self.strategies.len() <= quotas.max_strategies,
+            "tenant {}: {} strategies over quota {}",
+            self.id,
+            self.strategies.len(),
+            quotas.max_strategies
+        );
+        ensure!(
+            self.shards >= 1 && self.shards <= quotas.max_shards,
+            "tenant {}: {} shards outside 1..={}",
+            self.id,
+            self.shards,
+            quotas.max_shards
+        );
+        self.strategies
+            .iter()
+            .map(|strategy| {
+                let config = strategy.resolve().with_context(|| format!("tenant {} {:?}", self.id, strategy.kind))?;
+                ensure!(
+                    config.order_size() <= quotas.max_order_usdt,
+                    "tenant {} {:?}: order_size {} over quota {}",
+                    self.id,
+                    strategy.kind,
+                    config.order_size(),
+                    quotas.max_order_usdt
+                );
+                Ok(config)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TenantStatus {
+    Active,
+    Suspended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TenantScope {
+    /// Статус, PnL, ордера
+    Read,
+    /// Старт/стоп бота, пауза стратегий
+    Control,
+    /// Смена конфига
+    Configure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantClaims {
+    /// Id тенанта
+    pub sub: String,
+    pub scopes: Vec<TenantScope>,
+    pub jti: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+impl TenantClaims {
+    /// Запрос к ресурсам `tenant` со scope `scope`; чужой тенант или нет scope - 403
+    pub fn require(&self, tenant: &str, scope: TenantScope) -> Result<(), StatusCode> {
+        if self.sub == tenant && self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+/// Запущенный инстанс тенанта
+pub struct TenantRuntime {
+    runtime: ShardedRuntime,
+    router: OrderRouter,
+    symbols: HashSet<Symbol>,
+    credentials: Arc<CredentialStore>,
+    started_at: DateTime<Utc>,
+}
+
+impl TenantRuntime {
+    /// Отдаёт тик шардам тенанта, если он подписан на символ
+    pub fn on_tick(&mut self, tick: &TradeTick) -> bool {
+        if !self.symbols.contains(&tick.symbol) {
+            return false;
+        }
+        self.runtime.on_tick(tick.clone());
+        true
+    }
+
+    pub fn router(&mut self) -> &mut OrderRouter {
+        &mut self.router
+    }
+
+    pub fn credentials(&self) -> &Arc<CredentialStore> {
+        &self.credentials
+    }
+
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// Останавливает шарды, разбирая роутер, пока они завершаются (иначе шард с полным
+    /// исходящим кольцом не остановится)
+    fn shutdown(self) -> TenantStopped {
+        let Self { runtime, mut router, .. } = self;
+        let mut unrouted = Vec::new();
+        let stats = thread::scope(|scope| {
+            let shards = scope.spawn(move || runtime.shutdown());
+            while !shards.is_finished() {
+                if router.poll(&mut unrouted, usize::MAX) == 0 {
+                    thread::yield_now();
+                }
+            }
+            shards.join().expect("tenant shard shutdown panicked")
+        });
+        router.poll(&mut unrouted, usize::MAX);
+        TenantStopped { stats, unrouted }
+    }
+}
+
+/// Итог остановки тенанта
+#[derive(Debug)]
+pub struct TenantStopped {
+    pub stats: Vec<ShardStats>,
+    /// Действия стратегий, которые роутер не успел отдать на биржу
+    pub unrouted: Vec<RoutedAction>,
+}
+
+struct Tenant {
+    config: TenantConfig,
+    status: TenantStatus,
+    runtime: Option<TenantRuntime>,
+    /// Начало текущей минуты и число запросов API в ней
+    api_window: (DateTime<Utc>, u32),
+}
+
+pub struct TenantRegistry {
+    tenants: HashMap<String, Tenant>,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    revoked: HashSet<String>,
+    meter: Option<Arc<UsageMeter>>,
+}
+
+impl TenantRegistry {
+    pub fn new(jwt_secret: &str) -> Self {
+        assert!(!jwt_secret.is_empty(), "tenant registry needs a JWT secret");
+        Self {
+            tenants: HashMap::new(),
+            encoding_key: EncodingKey::from_secret(jwt_secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(jwt_secret.as_bytes()),
+            revoked: HashSet::new(),
+            meter: None,
+        }
+    }
+
+    pub fn set_meter(&mut self, meter: Arc<UsageMeter>) {
+        self.meter = Some(meter);
+    }
+
+    pub fn register(&mut self, config: TenantConfig) -> Result<()> {
+        config.validate()?;
+        ensure!(!self.tenants.contains_key(&config.id), "tenant {} already registered", config.id);
+        log::info!("tenant {} registered: {} symbols, {} strategies", config.id, config.symbols.len(), config.strategies.len());
+        self.tenants.insert(
+            config.id.clone(),
+            Tenant {
+                config,
+                status: TenantStatus::Active,
+                runtime: None,
+                api_window: (DateTime::<Utc>::MIN_UTC, 0),
+            },
+        );
+        Ok(())
+    }
+
+    /// Новый конфиг применяется к остановленному тенанту
+    pub fn update_config(&mut self, config: TenantConfig) -> Result<()> {
+        config.validate()?;
+        let tenant = self.tenant_mut(&config.id)?;
+        ensure!(tenant.runtime.is_none(), "tenant {} is running, stop it before reconfiguring", config.id);
+        tenant.config = config;
+        Ok(())
+    }
+
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.tenants.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn config(&self, id: &str) -> Option<&TenantConfig> {
+        self.tenants.get(id).map(|t| &t.config)
+    }
+
+    pub fn status(&self, id: &str) -> Option<TenantStatus> {
+        self.tenants.get(id).map(|t| t.status)
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        self.tenants.get(id).is_some_and(|t| t.runtime.is_some())
+    }
+
+    pub fn runtime_mut(&mut self, id: &str) -> Option<&mut TenantRuntime> {
+        self.tenants.get_mut(id)?.runtime.as_mut()
+    }
+
+    fn tenant_mut(&mut self, id: &str) -> Result<&mut Tenant> {
+        self.tenants.get_mut(id).with_context(|| format!("unknown tenant {}", id))
+    }
+
+    /// Запускает инстанс тенанта. `passphrase` открывает его keystore; стратегии и
+    /// ML-модели собираются сразу, чтобы ошибка конфига всплыла здесь, а не в шарде
+    pub fn start(&mut self, id: &str, passphrase: Option<Secret>) -> Result<()> {
+        let meter = self.meter.clone();
+        let tenant = self.tenant_mut(id)?;
+        ensure!(tenant.status == TenantStatus::Active, "tenant {} is suspended", id);
+        ensure!(tenant.runtime.is_none(), "tenant {} is already running", id);
+        let config = &tenant.config;
+        let strategies = config.validate()?;
+        for strategy in &strategies {
+            strategy.adapter().with_context(|| format!("tenant {}", id))?;
+        }
+        let credentials = match (&config.keystore, passphrase) {
+            (Some(path), Some(passphrase)) => CredentialStore::open_isolated(path, passphrase)
+                .with_context(|| format!("tenant {} keystore", id))?,
+            (Some(path), None) => bail!("tenant {} keystore {} needs a passphrase", id, path.display()),
+            (None, _) => CredentialStore::empty(),
+        };
+
+        let tenant_id = id.to_string();
+        let factory: StrategyFactory = Arc::new(move |symbol: Symbol| -> StrategySet {
+            strategies
+                .iter()
+                .filter_map(|strategy| match strategy.adapter() {
+                    Ok(adapter) => Some(adapter),
+                    Err(err) => {
+                        log::error!("tenant {} {}: strategy not started: {:#}", tenant_id, symbol, err);
+                        None
+                    }
+                })
+                .collect()
+        });
+        let runtime_config = RuntimeConfig { shards: config.shards, ..Default::default() };
+        let symbols = config.symbols.iter().map(|s| Symbol::new(s)).collect();
+        let (runtime, router) = ShardedRuntime::start(runtime_config, factory);
+        let started_at = Utc::now();
+        if let Some(meter) = &meter {
+            meter.set_symbols(id, config.symbols.len(), started_at);
+        }
+        tenant.runtime = Some(TenantRuntime {
+            runtime,
+            router,
+            symbols,
+            credentials: Arc::new(credentials),
+            started_at,
+        });
+        log::info!("tenant {} started", id);
+        Ok(())
+    }
+
+    pub fn stop(&mut self, id: &str) -> Result<TenantStopped> {
+        let runtime = self.tenant_mut(id)?.runtime.take().with_context(|| format!("tenant {} is not running", id))?;
+        let stopped = runtime.shutdown();
+        if let Some(meter) = &self.meter {
+            meter.set_symbols(id, 0, Utc::now());
+        }
+        if !stopped.unrouted.is_empty() {
+            log::warn!("tenant {} stopped with {} unrouted actions", id, stopped.unrouted.len());
+        }
+        log::info!("tenant {} stopped", id);
+        Ok(stopped)
+    }
+
+    /// Останавливает инстанс и закрывает API тенанта до `resume`
+    pub fn suspend(&mut self, id: &str) -> Result<Option<TenantStopped>> {
+        let stopped = if self.is_running(id) { Some(self.stop(id)?) } else { None };
+        self.tenant_mut(id)?.status = TenantStatus::Suspended;
+        log::warn!("tenant {} suspended", id);
+        Ok(stopped)
+    }
+
+    pub fn resume(&mut self, id: &str) -> Result<()> {
+        self.tenant_mut(id)?.status = TenantStatus::Active;
+        Ok(())
+    }
+
+    /// Исполнение ордера тенанта: уходит в его роутер и учитывается как сделка
+    pub fn fill(&mut self, id: &str, fill: FillEvent) -> Result<()> {
+        let runtime = self.runtime_mut(id).with_context(|| format!("tenant {} is not running", id))?;
+        runtime.router().fill(fill);
+        if let Some(meter) = &self.meter {
+            meter.record_trade(id, Utc::now());
+        }
+        Ok(())
+    }
+
+    /// Бэктест, запущенный тенантом, - в учёт использования
+    pub fn record_backtest(&self, id: &str) -> Result<()> {
+        ensure!(self.tenants.contains_key(id), "unknown tenant {}", id);
+        if let Some(meter) = &self.meter {
+            meter.record_backtest(id, Utc::now());
+        }
+        Ok(())
+    }
+
+    /// Раздаёт тик всем запущенным тенантам, подписанным на символ
+    pub fn dispatch(&mut self, tick: &TradeTick) -> usize {
+        self.tenants
+            .values_mut()
+            .filter_map(|t| t.runtime.as_mut())
+            .map(|runtime| runtime.on_tick(tick))
+            .filter(|&delivered| delivered)
+            .count()
+    }
+
+    /// Таймеры рантаймов тенантов; насос зовёт в цикле и когда тиков нет
+    pub fn poll_timers(&mut self) -> usize {
+        self.tenants
+            .values_mut()
+            .filter_map(|t| t.runtime.as_mut())
+            .map(|runtime| runtime.runtime.poll_timers())
+            .filter(|fired| *fired)
+            .count()
+    }
+
+    pub fn issue_token(&self, id: &str, scopes: &[TenantScope], ttl: Duration) -> Result<String> {
+        let tenant = self.tenants.get(id).with_context(|| format!("unknown tenant {}", id))?;
+        ensure!(tenant.status == TenantStatus::Active, "tenant {} is suspended", id);
+        ensure!(!scopes.is_empty(), "token for tenant {} needs at least one scope", id);
+        let mut jti = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut jti);
+        let now = Utc::now();
+        let claims = TenantClaims {
+            sub: id.to_string(),
+            scopes: scopes.to_vec(),
+            jti: jti.iter().map(|b| format!("{:02x}", b)).collect(),
+            iat: now.timestamp(),
+            exp: (now + ttl).timestamp(),
+        };
+        encode(&Header::default(), &claims, &self.encoding_key).context("encode tenant token")
+    }
+
+    pub fn revoke(&mut self, jti: &str) {
+        self.revoked.insert(jti.to_string());
+    }
+
+    /// Проверяет токен, статус тенанта и квоту запросов; считает запрос
+    pub fn authorize(&mut self, token: &str, now: DateTime<Utc>) -> Result<TenantClaims, StatusCode> {
+        let claims = decode::<TenantClaims>(token, &self.decoding_key, &Validation::default())
+            .map_err(|_| StatusCode::UNAUTHORIZED)?
+            .claims;
+        if self.revoked.contains(&claims.jti) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        let tenant = self.tenants.get_mut(&claims.sub).ok_or(StatusCode::FORBIDDEN)?;
+        if tenant.status != TenantStatus::Active {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        let (started, count) = &mut tenant.api_window;
+        if now - *started >= Duration::minutes(1) {
+            *started = now;
+            *count = 0;
+        }
+        if *count >= tenant.config.quotas.api_requests_per_minute {
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+        *count += 1;
+        Ok(claims)
+    }
+}
+
+/// Middleware control API: кладёт `TenantClaims` в extensions; scope и тенант пути
+/// проверяет handler через `TenantClaims::require`
+pub async fn tenant_middleware(
+    State(registry): State<Arc<Mutex<TenantRegistry>>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let claims = registry.lock().expect("tenant registry poisoned").authorize(token, Utc::now())?;
+    request.extensions_mut().insert(claims);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::credentials::{ApiCredentials, CredentialKey, KdfParams, KeystoreFile};
+    use crate::testing::ScenarioBuilder;
+    use serde_json::json;
+
+    fn config(id: &str, symbol: &str) -> TenantConfig {
+        TenantConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            quotas: TenantQuotas::default(),
+            symbols: vec![symbol.to_string()],
+            strategies: vec![TenantStrategy { kind: StrategyKind::Hook, params: json!({ "order_size": 50.0 }) }],
+            shards: 1,
+            keystore: None,
+        }
+    }
+
+    #[test]
+    fn test_config_checked_against_quotas() {
+        let mut registry = TenantRegistry::new("secret");
+        registry.register(config("alice", "BTC_USDT")).unwrap();
+        assert!(registry.register(config("alice", "ETH_USDT")).is_err());
+        assert!(registry.register(config("Bob!", "ETH_USDT")).is_err());
+
+        let oversized = TenantConfig {
+            strategies: vec![TenantStrategy { kind: StrategyKind::MStrike, params: json!({ "order_size": 500.0 }) }],
+            ..config("bob", "ETH_USDT")
+        };
+        assert!(registry.register(oversized).unwrap_err().to_string().contains("over quota"));
+        let typo = TenantConfig {
+            strategies: vec![TenantStrategy { kind: StrategyKind::Hook, params: json!({ "order_sise": 5.0 }) }],
+            ..config("bob", "ETH_USDT")
+        };
+        assert!(format!("{:#}", registry.register(typo).unwrap_err()).contains("unknown strategy param"));
+        assert!(registry.register(TenantConfig { shards: 2, ..config("bob", "ETH_USDT") }).is_err());
+        assert_eq!(registry.ids(), vec!["alice"]);
+    }
+
+    #[test]
+    fn test_tokens_are_scoped_and_rate_limited() {
+        let mut registry = TenantRegistry::new("secret");
+        let mut alice = config("alice", "BTC_USDT");
+        alice.quotas.api_requests_per_minute = 2;
+        registry.register(alice).unwrap();
+        registry.register(config("bob", "ETH_USDT")).unwrap();
+
+        let token = registry.issue_token("alice", &[TenantScope::Read], Duration::hours(1)).unwrap();
+        let now = Utc::now();
+        let claims = registry.authorize(&token, now).unwrap();
+        assert_eq!(claims.require("alice", TenantScope::Read), Ok(()));
+        assert_eq!(claims.require("alice", TenantScope::Control), Err(StatusCode::FORBIDDEN));
+        assert_eq!(claims.require("bob", TenantScope::Read), Err(StatusCode::FORBIDDEN));
+
+        registry.authorize(&token, now).unwrap();
+        assert_eq!(registry.authorize(&token, now).unwrap_err(), StatusCode::TOO_MANY_REQUESTS);
+        registry.authorize(&token, now + Duration::minutes(1)).unwrap();
+
+        let foreign = TenantRegistry::new("other").issue_token("alice", &[TenantScope::Read], Duration::hours(1));
+        assert!(foreign.is_err(), "unknown tenant in another registry");
+        let mut other = TenantRegistry::new("other");
+        other.register(config("alice", "BTC_USDT")).unwrap();
+        let forged = other.issue_token("alice", &[TenantScope::Control], Duration::hours(1)).unwrap();
+        assert_eq!(registry.authorize(&forged, now).unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        let bob = registry.issue_token("bob", &[TenantScope::Control], Duration::hours(1)).unwrap();
+        registry.suspend("bob").unwrap();
+        assert_eq!(registry.authorize(&bob, now).unwrap_err(), StatusCode::FORBIDDEN);
+        registry.resume("bob").unwrap();
+        let jti = registry.authorize(&bob, now).unwrap().jti;
+        registry.revoke(&jti);
+        assert_eq!(registry.authorize(&bob, now).unwrap_err(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_tenant_runtimes_and_credentials_are_isolated() {
+        let dir = std::env::temp_dir().join(format!("tenants-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let keystore = dir.join("alice.json");
+        let keys = HashMap::from([(CredentialKey::main("gate"), ApiCredentials::new("alice-key", "alice-secret"))]);
+        let kdf = KdfParams { m_cost_kib: 64, t_cost: 1, p_cost: 1 };
+        KeystoreFile::encrypt(&keys, "alice-pw", kdf).unwrap().write(&keystore).unwrap();
+
+        let mut registry = TenantRegistry::new("secret");
+        registry.register(TenantConfig { keystore: Some(keystore), ..config("alice", "BTC_USDT") }).unwrap();
+        registry.register(config("bob", "ETH_USDT")).unwrap();
+        assert!(registry.start("alice", None).is_err());
+        registry.start("alice", Some(Secret::new("alice-pw"))).unwrap();
+        registry.start("bob", None).unwrap();
+        assert!(registry.update_config(config("bob", "SOL_USDT")).is_err());
+
+        let alice = registry.runtime_mut("alice").unwrap();
+        assert_eq!(alice.credentials().get("gate", "main").unwrap().api_key, "alice-key");
+        assert!(registry.runtime_mut("bob").unwrap().credentials().get("gate", "main").is_err());
+
+        let btc = ScenarioBuilder::new("BTC_USDT", 100.0).flat(1_000).crash(10.0, 500).build();
+        let sol = ScenarioBuilder::new("SOL_USDT", 100.0).flat(1_000).build();
+        for tick in &btc {
+            assert_eq!(registry.dispatch(tick), 1);
+        }
+        for tick in &sol {
+            assert_eq!(registry.dispatch(tick), 0);
+        }
+
+        let alice = registry.stop("alice").unwrap();
+        assert_eq!(alice.stats.iter().map(|s| s.ticks).sum::<u64>(), btc.len() as u64);
+        assert!(alice.unrouted.iter().all(|a| a.symbol == Symbol::new("BTC_USDT")));
+        assert!(!alice.unrouted.is_empty());
+        let bob = registry.stop("bob").unwrap();
+        assert_eq!(bob.stats.iter().map(|s| s.ticks).sum::<u64>(), 0);
+        assert!(bob.unrouted.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
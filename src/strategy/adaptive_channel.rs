@@ -4,8 +4,9 @@
 //! 3. Extended target - бесконечное оттягивание цели
 
 use std::collections::VecDeque;
+use anyhow::Result;
 use crate::base_classes::types::Side;
-use crate::execution::{QuoteIntent, TimeInForce, Venue, ClientOrderId};
+use crate::execution::{QuoteIntent, TimeInForce, Venue, ClientOrderId, Price, Qty};
 use crate::models::Position;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -192,27 +193,27 @@ impl AdaptiveChannelStrategy {
         window.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b)).into()
     }
 
-    pub fn create_entry_intent(&mut self, symbol: &str, size: f64) -> QuoteIntent {
+    pub fn create_entry_intent(&mut self, symbol: &str, size: f64) -> Result<QuoteIntent> {
         let price = *self.price_history.back().unwrap();
         self.entry_price = Some(price);
         self.highest_price = Some(price);
         self.lowest_price = Some(price);
         
-        QuoteIntent::new(
+        Ok(QuoteIntent::new(
             Venue::Gate,
             symbol.to_string(),
             Side::Bid,
-            price,
-            size,
+            Price::try_from_f64(price)?,
+            Qty::try_from_f64(size)?,
             TimeInForce::Gtc,
             ClientOrderId::new(format!("entry-{}", std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs())),
-        )
+        ))
     }
 
-    pub fn create_exit_intent(&mut self, symbol: &str, size: f64) -> QuoteIntent {
+    pub fn create_exit_intent(&mut self, symbol: &str, size: f64) -> Result<QuoteIntent> {
         let price = *self.price_history.back().unwrap();
         
         // Сбрасываем состояние
@@ -221,18 +222,18 @@ impl AdaptiveChannelStrategy {
         self.highest_price = None;
         self.reversal_detected = false;
         
-        QuoteIntent::new(
+        Ok(QuoteIntent::new(
             Venue::Gate,
             symbol.to_string(),
             Side::Ask,
-            price,
-            size,
+            Price::try_from_f64(price)?,
+            Qty::try_from_f64(size)?,
             TimeInForce::Ioc,
             ClientOrderId::new(format!("exit-{}", std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs())),
-        )
+        ))
     }
 
     pub fn set_position(&mut self, position: Position) {
@@ -7,9 +7,12 @@
 use std::collections::VecDeque;
 use std::time::Instant;
 
+use anyhow::Result;
+
 use crate::base_classes::types::Side;
 use crate::execution::{
-    ClientOrderId, ExecutionReport, OrderStatus, QuoteIntent, TimeInForce, Venue,
+    ClientOrderId, ExecutionReport, OrderStatus, Price, Qty, QuoteIntent, Rounding, TimeInForce,
+    Venue,
 };
 use crate::models::Position;
 use crate::strategy::stop_loss::{check_stop_loss, check_take_profit};
@@ -80,51 +83,55 @@ impl BtcTradingStrategy {
     }
 
     /// Проверка стоп-лосса и тейк-профита
-    pub fn check_position_limits(&mut self, current_price: f64) -> Option<QuoteIntent> {
+    pub fn check_position_limits(&mut self, current_price: f64) -> Result<Option<QuoteIntent>> {
         if let Some(position) = &self.current_position {
             // Проверяем стоп-лосс
             if check_stop_loss(position, current_price) {
-                return Some(self.create_close_order(current_price, position.clone()));
+                return self.create_close_order(current_price, position.clone()).map(Some);
             }
             
             // Проверяем тейк-профит
             if check_take_profit(position, current_price) {
-                return Some(self.create_close_order(current_price, position.clone()));
+                return self.create_close_order(current_price, position.clone()).map(Some);
             }
         }
-        None
+        Ok(None)
     }
 
     /// Генерация торговых сигналов на основе индикаторов
-    pub fn generate_signal(&mut self) -> Option<QuoteIntent> {
-        let price = self.latest_price?;
+    pub fn generate_signal(&mut self) -> Result<Option<QuoteIntent>> {
+        let Some(price) = self.latest_price else {
+            return Ok(None);
+        };
         
         // Проверяем стоп-лосс/тейк-профит сначала
-        if let Some(close_order) = self.check_position_limits(price) {
-            return Some(close_order);
+        if let Some(close_order) = self.check_position_limits(price)? {
+            return Ok(Some(close_order));
         }
 
         // Если уже есть позиция, не открываем новую
         if self.current_position.is_some() {
-            return None;
+            return Ok(None);
         }
 
         // Вычисляем RSI (упрощенная версия)
-        let rsi = self.calculate_simple_rsi()?;
+        let Some(rsi) = self.calculate_simple_rsi() else {
+            return Ok(None);
+        };
         
         // Сигнал на покупку (oversold)
         if rsi < self.config.rsi_oversold {
             let entry_price = price * 0.999;  // Немного ниже рынка для лимитного ордера
-            return Some(self.create_entry_order(Side::Bid, entry_price));
+            return self.create_entry_order(Side::Bid, entry_price).map(Some);
         }
 
         // Сигнал на продажу (overbought) - для шорта
         // if rsi > self.config.rsi_overbought {
         //     let entry_price = price * 1.001;
-        //     return Some(self.create_entry_order(Side::Ask, entry_price));
+        //     return self.create_entry_order(Side::Ask, entry_price).map(Some);
         // }
 
-        None
+        Ok(None)
     }
 
     /// Упрощенный расчет RSI
@@ -155,27 +162,32 @@ impl BtcTradingStrategy {
         Some(rsi)
     }
 
+    fn round_to_tick(&self, price: f64) -> Result<Price> {
+        let tick = Price::try_from_f64(self.config.min_tick)?;
+        Ok(Price::try_from_f64(price)?.round_to(tick.as_decimal(), Rounding::Nearest))
+    }
+
     /// Создание ордера на вход
-    fn create_entry_order(&mut self, side: Side, price: f64) -> QuoteIntent {
+    fn create_entry_order(&mut self, side: Side, price: f64) -> Result<QuoteIntent> {
         self.next_order_id += 1;
         let order_id = format!("btc-{:?}-{}", side, self.next_order_id);
 
         // Округляем цену до min_tick
-        let rounded_price = (price / self.config.min_tick).round() * self.config.min_tick;
+        let rounded_price = self.round_to_tick(price)?;
 
-        QuoteIntent::new(
+        Ok(QuoteIntent::new(
             Venue::Gate,
             self.config.symbol.clone(),
             side,
             rounded_price,
-            self.config.entry_size,
+            Qty::try_from_f64(self.config.entry_size)?,
             TimeInForce::Gtc,
             ClientOrderId::new(order_id),
-        )
+        ))
     }
 
     /// Создание ордера на закрытие позиции
-    fn create_close_order(&mut self, current_price: f64, position: Position) -> QuoteIntent {
+    fn create_close_order(&mut self, current_price: f64, position: Position) -> Result<QuoteIntent> {
         self.next_order_id += 1;
         let order_id = format!("btc-close-{}", self.next_order_id);
 
@@ -186,17 +198,17 @@ impl BtcTradingStrategy {
         };
 
         // Рыночный ордер для закрытия (используем текущую цену)
-        let rounded_price = (current_price / self.config.min_tick).round() * self.config.min_tick;
+        let rounded_price = self.round_to_tick(current_price)?;
 
-        QuoteIntent::new(
+        Ok(QuoteIntent::new(
             Venue::Gate,
             self.config.symbol.clone(),
             side,
             rounded_price,
-            position.amount,
+            Qty::try_from_f64(position.amount)?,
             TimeInForce::Ioc,  // Immediate or Cancel для быстрого закрытия
             ClientOrderId::new(order_id),
-        )
+        ))
     }
 
     /// Обработка исполнения ордера
@@ -9,6 +9,8 @@
 //! 5. Продажа по целям с отпуском цены и подтягиванием стопа в безубыток
 //! 6. Три ордера (как в channel_split стратегии)
 
+use crate::strategy::moon_strategies::numeric::change_pct;
+
 // Простой trait для сброса состояния стратегии
 pub trait StrategyReset {
     fn reset_strategy(&mut self);
@@ -137,13 +139,15 @@ impl EmaReversalStrategy {
 
         // Находим локальный максимум за последние 10 свечей
         let recent_prices = &self.price_history[self.price_history.len().saturating_sub(10)..];
-        let local_high = recent_prices.iter().fold(0.0f64, |a, &b| a.max(b));
+        let local_high = recent_prices.iter().copied().reduce(f64::max)?;
 
-        if local_high <= 0.0 {
-            return None;
-        }
-
-        let dip_pct = ((local_high - current_price) / local_high) * 100.0;
+        let dip_pct = match change_pct(local_high, current_price) {
+            Ok(change) => -change,
+            Err(err) => {
+                log::error!("ema reversal dip: {}", err);
+                return None;
+            }
+        };
 
         // Находим ближайший уровень просадки
         for (i, &level) in self.dip_levels.iter().enumerate() {
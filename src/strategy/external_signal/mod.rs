@@ -0,0 +1,492 @@
+//! Стратегия по внешним сигналам (алерты TradingView и т.п.)
+//!
+//! Вход и ранний выход решает внешний источник: алерт приходит на вебхук
+//! (`webhook`, фича `dashboard`) и кладётся в `ExternalSignalInbox`, откуда его на
+//! ближайшем тике символа забирает `ExternalSignalStrategy`. Всё остальное - как у
+//! своих стратегий: размер ордера из конфига, отсев протухших алертов и ушедшей
+//! цены, тейк-профит, стоп-лосс, безубыток и TimeStop из `moon_strategies::exits`.
+//! Стратегия - обычный `StrategyAdapter`, так что арбитр, риск-менеджер и ML-фильтр
+//! рантайма применяются к внешним входам так же, как к детектам.
+
+#[cfg(feature = "dashboard")]
+pub mod webhook;
+
+use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::{StrategyAction, StrategyAdapter, StrategySnapshot, StrategyView};
+use crate::base_classes::symbol::{normalize, Symbol};
+use crate::strategy::moon_strategies::exits::{BreakevenConfig, BreakevenStop, TimeStopConfig};
+use crate::strategy::moon_strategies::{mshot::Deltas, OrderIntent};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "dashboard")]
+pub use webhook::{webhook_routes, TradingViewAlert};
+
+/// Сколько алертов символа ждут тика; дальше старые вытесняются
+const INBOX_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalAction {
+    Entry,
+    Exit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalSignal {
+    pub symbol: Symbol,
+    pub action: ExternalAction,
+    /// Цена в алерте; None - по цене тика
+    pub price: Option<f64>,
+    pub received_at: DateTime<Utc>,
+    /// Откуда пришёл сигнал (имя алерта/стратегии TradingView) - для логов
+    pub source: String,
+}
+
+/// Очереди алертов по символам между вебхуком и шардами рантайма.
+/// На символ - одна стратегия-потребитель: `take` забирает алерт.
+#[derive(Debug)]
+pub struct ExternalSignalInbox {
+    /// Всего алертов в очередях: тик без алертов не берёт мьютекс
+    pending: AtomicUsize,
+    queues: Mutex<HashMap<Symbol, VecDeque<ExternalSignal>>>,
+}
+
+impl ExternalSignalInbox {
+    /// Принимаются алерты только по этим символам
+    pub fn new(symbols: &[Symbol]) -> Self {
+        Self {
+            pending: AtomicUsize::new(0),
+            queues: Mutex::new(symbols.iter().map(|&s| (s, VecDeque::new())).collect()),
+        }
+    }
+
+    /// Символ из тикера алерта (`BINANCE:BTCUSDT`, `BTCUSDT`, `BTC_USDT`) без интернирования
+    /// неизвестных строк
+    pub fn resolve(&self, ticker: &str) -> Option<Symbol> {
+        let ticker = ticker.rsplit_once(':').map_or(ticker, |(_, t)| t);
+        let ticker = ticker.strip_suffix(".P").unwrap_or(ticker);
+        let canonical = normalize(ticker);
+        let queues = self.queues.lock().expect("signal inbox poisoned");
+        queues.keys().copied().find(|s| s.as_str() == canonical)
+    }
+
+    pub fn push(&self, signal: ExternalSignal) -> Result<()> {
+        let mut queues = self.queues.lock().expect("signal inbox poisoned");
+        let Some(queue) = queues.get_mut(&signal.symbol) else {
+            bail!("external signals for {} are not accepted", signal.symbol);
+        };
+        if queue.len() >= INBOX_CAPACITY {
+            let dropped = queue.pop_front().expect("full queue");
+            log::warn!("external signal inbox {} full, dropped {:?} from {}", dropped.symbol, dropped.action, dropped.source);
+        } else {
+            self.pending.fetch_add(1, Ordering::Release);
+        }
+        queue.push_back(signal);
+        Ok(())
+    }
+
+    pub fn take(&self, symbol: Symbol) -> Option<ExternalSignal> {
+        if self.pending.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        let signal = self.queues.lock().expect("signal inbox poisoned").get_mut(&symbol)?.pop_front()?;
+        self.pending.fetch_sub(1, Ordering::Release);
+        Some(signal)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalSignalConfig {
+    pub order_size: f64,
+    /// Buy на `entry_offset_pct` % ниже цены алерта
+    #[serde(default)]
+    pub entry_offset_pct: f64,
+    /// Алерт старше этого (мс от приёма до тика) отбрасывается
+    pub max_signal_age_ms: u64,
+    /// Цена ушла выше цены алерта больше чем на столько % - вход пропускается
+    pub max_chase_pct: f64,
+    /// Неисполненный buy снимается через столько мс, 0 = держать
+    pub entry_timeout_ms: u64,
+    pub take_profit_pct: f64,
+    /// 0 = без стоп-лосса
+    pub stop_loss_pct: f64,
+    #[serde(default)]
+    pub time_stop: Option<TimeStopConfig>,
+    #[serde(default)]
+    pub breakeven: Option<BreakevenConfig>,
+}
+
+impl Default for ExternalSignalConfig {
+    fn default() -> Self {
+        Self {
+            order_size: 10.0,
+            entry_offset_pct: 0.0,
+            max_signal_age_ms: 5_000,
+            max_chase_pct: 1.0,
+            entry_timeout_ms: 60_000,
+            take_profit_pct: 2.0,
+            stop_loss_pct: 3.0,
+            time_stop: None,
+            breakeven: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct PendingBuy {
+    price: f64,
+    size: f64,
+    placed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExternalSignalState {
+    pending_buy: Option<PendingBuy>,
+    entry_price: Option<f64>,
+    position_size: f64,
+    position_opened_at: Option<DateTime<Utc>>,
+    breakeven_stop: BreakevenStop,
+    /// Выставленный выход (сигнал, стоп, TimeStop или тейк)
+    exit_price: Option<f64>,
+    last_signal_at: Option<DateTime<Utc>>,
+    last_tick_at: Option<DateTime<Utc>>,
+}
+
+/// Read-model для дашборда и тестов
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalSignalView {
+    pub pending_buy: Option<f64>,
+    pub entry_price: Option<f64>,
+    pub position_size: f64,
+    pub position_opened_at: Option<DateTime<Utc>>,
+    pub take_profit: Option<f64>,
+    pub stop_loss: Option<f64>,
+    /// Стоп после переноса в безубыток
+    pub breakeven_stop: Option<f64>,
+    pub time_stop_at: Option<DateTime<Utc>>,
+    pub last_signal_at: Option<DateTime<Utc>>,
+}
+
+pub struct ExternalSignalStrategy {
+    config: ExternalSignalConfig,
+    inbox: Arc<ExternalSignalInbox>,
+    state: ExternalSignalState,
+    /// Расширение стопов устаревшего символа, %; 0 - обычные стопы
+    stop_widen_pct: f64,
+}
+
+impl ExternalSignalStrategy {
+    pub fn new(config: ExternalSignalConfig, inbox: Arc<ExternalSignalInbox>) -> Self {
+        Self { config, inbox, state: ExternalSignalState::default(), stop_widen_pct: 0.0 }
+    }
+
+    fn on_signal(&mut self, signal: ExternalSignal, tick: &TradeTick) -> StrategyAction {
+        self.state.last_signal_at = Some(signal.received_at);
+        let age_ms = (tick.timestamp - signal.received_at).num_milliseconds();
+        if age_ms > self.config.max_signal_age_ms as i64 {
+            log::warn!("{} {:?} from {} dropped: {} ms old", signal.symbol, signal.action, signal.source, age_ms);
+            return StrategyAction::NoAction;
+        }
+        match signal.action {
+            ExternalAction::Entry => {
+                if self.state.entry_price.is_some() || self.state.pending_buy.is_some() {
+                    log::info!("{} entry from {} ignored: already in", signal.symbol, signal.source);
+                    return StrategyAction::NoAction;
+                }
+                let reference = signal.price.unwrap_or(tick.price);
+                let chase_pct = (tick.price - reference) / reference * 100.0;
+                if chase_pct > self.config.max_chase_pct {
+                    log::warn!(
+                        "{} entry from {} skipped: price {:.8} is {:.2}% above alert {:.8}",
+                        signal.symbol,
+                        signal.source,
+                        tick.price,
+                        chase_pct,
+                        reference
+                    );
+                    return StrategyAction::NoAction;
+                }
+                let price = reference.min(tick.price) * (1.0 - self.config.entry_offset_pct / 100.0);
+                let size = self.config.order_size;
+                self.state.pending_buy = Some(PendingBuy { price, size, placed_at: tick.timestamp });
+                StrategyAction::PlaceBuy { price, size }
+            }
+            ExternalAction::Exit => {
+                if self.state.pending_buy.take().is_some() {
+                    return StrategyAction::CancelOrder { order_id: 0 };
+                }
+                if self.state.entry_price.is_none() {
+                    log::info!("{} exit from {} ignored: no position", signal.symbol, signal.source);
+                    return StrategyAction::NoAction;
+                }
+                self.force_exit(tick.best_bid.unwrap_or(tick.price))
+            }
+        }
+    }
+
+    fn force_exit(&mut self, price: f64) -> StrategyAction {
+        self.state.exit_price = Some(price);
+        StrategyAction::PlaceSell { price, size: self.state.position_size }
+    }
+
+    fn take_profit(&self, entry: f64) -> f64 {
+        entry * (1.0 + self.config.take_profit_pct / 100.0)
+    }
+
+    fn stop_loss(&self, entry: f64) -> Option<f64> {
+        let stop_pct = self.config.stop_loss_pct + self.stop_widen_pct;
+        (self.config.stop_loss_pct > 0.0).then(|| entry * (1.0 - stop_pct / 100.0))
+    }
+
+    fn manage_position(&mut self, entry: f64, tick: &TradeTick) -> StrategyAction {
+        if self.state.exit_price.is_some() {
+            return StrategyAction::NoAction;
+        }
+        let price = tick.price;
+        let target = self.take_profit(entry);
+        if price >= target {
+            return self.force_exit(target);
+        }
+        let bid = tick.best_bid.unwrap_or(price);
+        if self.stop_loss(entry).is_some_and(|stop| price <= stop) {
+            return self.force_exit(bid);
+        }
+        if let Some(breakeven) = self.config.breakeven
+            && self.state.breakeven_stop.update(&breakeven, entry, price, self.stop_widen_pct)
+        {
+            return self.force_exit(bid);
+        }
+        if let (Some(time_stop), Some(opened_at)) = (self.config.time_stop, self.state.position_opened_at)
+            && time_stop.expired(opened_at, tick.timestamp)
+        {
+            return self.force_exit(time_stop.exit_price(price, tick.best_bid, target));
+        }
+        StrategyAction::NoAction
+    }
+
+    /// Снять вход, не исполнившийся за `entry_timeout_ms`
+    fn expire_entry(&mut self, now: DateTime<Utc>) -> Option<StrategyAction> {
+        let pending = self.state.pending_buy?;
+        if self.config.entry_timeout_ms == 0
+            || now - pending.placed_at < Duration::milliseconds(self.config.entry_timeout_ms as i64)
+        {
+            return None;
+        }
+        self.state.pending_buy = None;
+        Some(StrategyAction::CancelOrder { order_id: 0 })
+    }
+}
+
+impl StrategyAdapter for ExternalSignalStrategy {
+    fn on_tick(&mut self, tick: &TradeTick, _deltas: &Deltas) -> StrategyAction {
+        self.state.last_tick_at = Some(tick.timestamp);
+        if let Some(signal) = self.inbox.take(tick.symbol) {
+            let action = self.on_signal(signal, tick);
+            if !matches!(action, StrategyAction::NoAction) {
+                return action;
+            }
+        }
+        if let Some(cancel) = self.expire_entry(tick.timestamp) {
+            return cancel;
+        }
+        match self.state.entry_price {
+            Some(entry) => self.manage_position(entry, tick),
+            None => StrategyAction::NoAction,
+        }
+    }
+
+    fn on_timer(&mut self, now: DateTime<Utc>) -> StrategyAction {
+        self.expire_entry(now).unwrap_or(StrategyAction::NoAction)
+    }
+
+    fn get_name(&self) -> &str {
+        "External"
+    }
+
+    fn reset(&mut self) {
+        self.state = ExternalSignalState::default();
+    }
+
+    fn on_buy_filled(&mut self, price: f64, size: f64) -> Option<StrategyAction> {
+        self.state.pending_buy = None;
+        self.state.entry_price = Some(price);
+        self.state.position_size = size;
+        self.state.position_opened_at = Some(self.state.last_tick_at.unwrap_or_else(Utc::now));
+        self.state.breakeven_stop = BreakevenStop::default();
+        self.state.exit_price = None;
+        None
+    }
+
+    fn on_sell_filled(&mut self, _price: f64, _size: f64) {
+        self.state.entry_price = None;
+        self.state.position_size = 0.0;
+        self.state.position_opened_at = None;
+        self.state.exit_price = None;
+    }
+
+    fn on_buy_declined(&mut self) {
+        self.state.pending_buy = None;
+    }
+
+    fn on_stale(&mut self, widen_stops_pct: Option<f64>) {
+        self.stop_widen_pct = widen_stops_pct.unwrap_or(0.0);
+    }
+
+    fn on_book(&mut self, _bids: &[(f64, f64)], _asks: &[(f64, f64)]) {
+        // Выход по стопам считается от best_bid тика, стакан не нужен
+    }
+
+    fn calculate_sell_price(&self, buy_price: f64, _current_price: f64) -> Option<f64> {
+        Some(self.take_profit(buy_price))
+    }
+
+    fn snapshot(&self) -> Result<StrategySnapshot> {
+        StrategySnapshot::new(self.get_name(), &self.state)
+    }
+
+    fn restore(&mut self, snapshot: &StrategySnapshot) -> Result<()> {
+        self.state = snapshot.decode(self.get_name())?;
+        Ok(())
+    }
+
+    fn view(&self) -> StrategyView {
+        let entry = self.state.entry_price;
+        StrategyView::External(ExternalSignalView {
+            pending_buy: self.state.pending_buy.map(|p| p.price),
+            entry_price: entry,
+            position_size: self.state.position_size,
+            position_opened_at: self.state.position_opened_at,
+            take_profit: entry.map(|e| self.take_profit(e)),
+            stop_loss: entry.and_then(|e| self.stop_loss(e)),
+            breakeven_stop: self.state.breakeven_stop.stop_price,
+            time_stop_at: self.config.time_stop.zip(self.state.position_opened_at).and_then(|(t, at)| t.deadline(at)),
+            last_signal_at: self.state.last_signal_at,
+        })
+    }
+
+    fn pending_orders(&self) -> Vec<OrderIntent> {
+        if let Some(entry) = self.state.entry_price {
+            let price = self.state.exit_price.unwrap_or_else(|| self.take_profit(entry));
+            return vec![OrderIntent::sell(price, self.state.position_size)];
+        }
+        self.state.pending_buy.map(|p| OrderIntent::buy(p.price, p.size)).into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::TradeSide;
+
+    fn tick(at: DateTime<Utc>, price: f64) -> TradeTick {
+        TradeTick {
+            timestamp: at,
+            symbol: Symbol::new("SOL_USDT"),
+            price,
+            volume: 10.0,
+            side: TradeSide::Buy,
+            trade_id: String::new(),
+            best_bid: Some(price - 0.01),
+            best_ask: Some(price),
+        }
+    }
+
+    fn signal(action: ExternalAction, price: Option<f64>, at: DateTime<Utc>) -> ExternalSignal {
+        ExternalSignal { symbol: Symbol::new("SOL_USDT"), action, price, received_at: at, source: "tv".to_string() }
+    }
+
+    fn strategy(config: ExternalSignalConfig) -> (Arc<ExternalSignalInbox>, ExternalSignalStrategy) {
+        let inbox = Arc::new(ExternalSignalInbox::new(&[Symbol::new("SOL_USDT")]));
+        (inbox.clone(), ExternalSignalStrategy::new(config, inbox))
+    }
+
+    #[test]
+    fn test_inbox_resolves_tradingview_tickers() {
+        let inbox = ExternalSignalInbox::new(&[Symbol::new("SOL_USDT")]);
+        assert_eq!(inbox.resolve("BINANCE:SOLUSDT"), Some(Symbol::new("SOL_USDT")));
+        assert_eq!(inbox.resolve("SOLUSDT.P"), Some(Symbol::new("SOL_USDT")));
+        assert_eq!(inbox.resolve("DOGEUSDT"), None);
+        assert!(inbox.take(Symbol::new("SOL_USDT")).is_none());
+    }
+
+    #[test]
+    fn test_entry_with_own_size_and_stop_loss() {
+        let config = ExternalSignalConfig { order_size: 25.0, stop_loss_pct: 2.0, ..Default::default() };
+        let (inbox, mut strategy) = strategy(config);
+        let deltas = Deltas::default();
+        let now = Utc::now();
+
+        // Протухший алерт не исполняется
+        inbox.push(signal(ExternalAction::Entry, Some(100.0), now - Duration::seconds(10))).unwrap();
+        assert!(matches!(strategy.on_tick(&tick(now, 100.0), &deltas), StrategyAction::NoAction));
+        // Цена ушла от алерта дальше max_chase_pct
+        inbox.push(signal(ExternalAction::Entry, Some(100.0), now)).unwrap();
+        assert!(matches!(strategy.on_tick(&tick(now, 102.0), &deltas), StrategyAction::NoAction));
+
+        inbox.push(signal(ExternalAction::Entry, Some(100.0), now)).unwrap();
+        match strategy.on_tick(&tick(now, 100.2), &deltas) {
+            StrategyAction::PlaceBuy { price, size } => {
+                assert_eq!(price, 100.0);
+                assert_eq!(size, 25.0);
+            }
+            other => panic!("expected PlaceBuy, got {:?}", other),
+        }
+        assert!(strategy.on_buy_filled(100.0, 25.0).is_none());
+        assert_eq!(strategy.calculate_sell_price(100.0, 100.0), Some(102.0));
+
+        assert!(matches!(strategy.on_tick(&tick(now, 99.0), &deltas), StrategyAction::NoAction));
+        match strategy.on_tick(&tick(now, 97.9), &deltas) {
+            StrategyAction::PlaceSell { price, size } => {
+                assert!((price - 97.89).abs() < 1e-9);
+                assert_eq!(size, 25.0);
+            }
+            other => panic!("expected stop-loss sell, got {:?}", other),
+        }
+        // Выход уже выставлен - без повторов
+        assert!(matches!(strategy.on_tick(&tick(now, 97.0), &deltas), StrategyAction::NoAction));
+        strategy.on_sell_filled(97.89, 25.0);
+        assert!(strategy.pending_orders().is_empty());
+    }
+
+    #[test]
+    fn test_exit_signal_cancels_buy_or_closes_position() {
+        let (inbox, mut strategy) = strategy(ExternalSignalConfig::default());
+        let deltas = Deltas::default();
+        let now = Utc::now();
+
+        inbox.push(signal(ExternalAction::Entry, None, now)).unwrap();
+        assert!(matches!(strategy.on_tick(&tick(now, 50.0), &deltas), StrategyAction::PlaceBuy { .. }));
+        inbox.push(signal(ExternalAction::Exit, None, now)).unwrap();
+        assert!(matches!(strategy.on_tick(&tick(now, 50.0), &deltas), StrategyAction::CancelOrder { order_id: 0 }));
+
+        inbox.push(signal(ExternalAction::Entry, None, now)).unwrap();
+        assert!(matches!(strategy.on_tick(&tick(now, 50.0), &deltas), StrategyAction::PlaceBuy { .. }));
+        strategy.on_buy_filled(50.0, 10.0);
+        let snapshot = strategy.snapshot().unwrap();
+
+        inbox.push(signal(ExternalAction::Exit, None, now)).unwrap();
+        assert!(matches!(strategy.on_tick(&tick(now, 50.5), &deltas), StrategyAction::PlaceSell { size, .. } if size == 10.0));
+
+        let (_, mut restored) = self::strategy(ExternalSignalConfig::default());
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.pending_orders(), vec![OrderIntent::sell(51.0, 10.0)]);
+    }
+
+    #[test]
+    fn test_timer_cancels_stale_entry_without_ticks() {
+        let (inbox, mut strategy) = strategy(ExternalSignalConfig { entry_timeout_ms: 5_000, ..ExternalSignalConfig::default() });
+        let now = Utc::now();
+        inbox.push(signal(ExternalAction::Entry, None, now)).unwrap();
+        assert!(matches!(strategy.on_tick(&tick(now, 50.0), &Deltas::default()), StrategyAction::PlaceBuy { .. }));
+
+        assert!(matches!(strategy.on_timer(now + Duration::seconds(4)), StrategyAction::NoAction));
+        assert!(matches!(strategy.on_timer(now + Duration::seconds(5)), StrategyAction::CancelOrder { order_id: 0 }));
+        assert!(strategy.pending_orders().is_empty());
+        assert!(matches!(strategy.on_timer(now + Duration::seconds(6)), StrategyAction::NoAction));
+    }
+}
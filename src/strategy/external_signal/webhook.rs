@@ -0,0 +1,136 @@
+//! Вебхук для алертов TradingView
+//!
+//! TradingView не умеет заголовки авторизации, поэтому секрет едет в теле алерта
+//! (`passphrase`). Тело - JSON, который пользователь пишет в сообщении алерта:
+//!
+//! ```json
+//! {"passphrase": "...", "ticker": "{{ticker}}", "action": "{{strategy.order.action}}",
+//!  "price": {{close}}, "source": "rsi-dip"}
+//! ```
+//!
+//! `action`: `buy`/`long`/`entry` - вход, `sell`/`exit`/`close`/`flat` - выход.
+//! Content-Type TradingView шлёт `text/plain`, тело разбирается как JSON независимо от него.
+
+use super::{ExternalAction, ExternalSignal, ExternalSignalInbox};
+use crate::config::credentials::Secret;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::Router;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct TradingViewAlert {
+    pub passphrase: String,
+    pub ticker: String,
+    pub action: String,
+    /// Число или строка с числом - в шаблоне алерта `{{close}}` часто берут в кавычки
+    #[serde(default)]
+    pub price: Option<Value>,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl TradingViewAlert {
+    fn action(&self) -> Option<ExternalAction> {
+        match self.action.trim().to_ascii_lowercase().as_str() {
+            "buy" | "long" | "entry" => Some(ExternalAction::Entry),
+            "sell" | "exit" | "close" | "flat" => Some(ExternalAction::Exit),
+            _ => None,
+        }
+    }
+
+    fn price(&self) -> Result<Option<f64>, ()> {
+        let price = match &self.price {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::Number(n)) => n.as_f64(),
+            Some(Value::String(s)) => s.trim().parse().ok(),
+            Some(_) => None,
+        };
+        match price {
+            Some(p) if p.is_finite() && p > 0.0 => Ok(Some(p)),
+            _ => Err(()),
+        }
+    }
+}
+
+struct WebhookState {
+    inbox: Arc<ExternalSignalInbox>,
+    passphrase: [u8; 32],
+}
+
+/// `POST /webhook/tradingview`
+pub fn webhook_routes(inbox: Arc<ExternalSignalInbox>, passphrase: &Secret) -> Router {
+    assert!(!passphrase.expose().is_empty(), "TradingView webhook needs a passphrase");
+    let state = Arc::new(WebhookState { inbox, passphrase: Sha256::digest(passphrase.expose().as_bytes()).into() });
+    Router::new().route("/webhook/tradingview", post(tradingview_alert)).with_state(state)
+}
+
+async fn tradingview_alert(State(state): State<Arc<WebhookState>>, body: String) -> (StatusCode, String) {
+    let alert: TradingViewAlert = match serde_json::from_str(&body) {
+        Ok(alert) => alert,
+        Err(err) => return (StatusCode::BAD_REQUEST, format!("alert is not valid JSON: {}", err)),
+    };
+    let digest: [u8; 32] = Sha256::digest(alert.passphrase.as_bytes()).into();
+    if digest != state.passphrase {
+        log::warn!("TradingView alert for {} rejected: wrong passphrase", alert.ticker);
+        return (StatusCode::UNAUTHORIZED, "wrong passphrase".to_string());
+    }
+    let Some(action) = alert.action() else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, format!("unknown action {:?}", alert.action));
+    };
+    let Ok(price) = alert.price() else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, format!("bad price {:?}", alert.price));
+    };
+    let Some(symbol) = state.inbox.resolve(&alert.ticker) else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, format!("ticker {} is not traded", alert.ticker));
+    };
+    let source = alert.source.unwrap_or_else(|| "tradingview".to_string());
+    log::info!("TradingView {:?} {} from {} at {:?}", action, symbol, source, price);
+    let signal = ExternalSignal { symbol, action, price, received_at: Utc::now(), source };
+    match state.inbox.push(signal) {
+        Ok(()) => (StatusCode::ACCEPTED, "accepted".to_string()),
+        Err(err) => (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::symbol::Symbol;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_alerts_are_authenticated_and_queued() {
+        let symbol = Symbol::new("BTC_USDT");
+        let inbox = Arc::new(ExternalSignalInbox::new(&[symbol]));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/webhook/tradingview", listener.local_addr().unwrap());
+        let routes = webhook_routes(inbox.clone(), &Secret::new("s3cret"));
+        tokio::spawn(axum::serve(listener, routes).into_future());
+
+        let client = reqwest::Client::new();
+        let send = |body: Value| {
+            let request = client.post(&url).header("content-type", "text/plain").body(body.to_string());
+            async move { request.send().await.unwrap().status().as_u16() }
+        };
+        let alert = |passphrase: &str, ticker: &str, action: &str| {
+            json!({"passphrase": passphrase, "ticker": ticker, "action": action, "price": "64000.5", "source": "rsi"})
+        };
+
+        assert_eq!(send(alert("wrong", "BINANCE:BTCUSDT", "buy")).await, 401);
+        assert_eq!(send(alert("s3cret", "BINANCE:BTCUSDT", "hold")).await, 422);
+        assert_eq!(send(alert("s3cret", "BINANCE:ETHUSDT", "buy")).await, 422);
+        assert!(inbox.take(symbol).is_none());
+
+        assert_eq!(send(alert("s3cret", "BINANCE:BTCUSDT", "buy")).await, 202);
+        let signal = inbox.take(symbol).unwrap();
+        assert_eq!(signal.action, ExternalAction::Entry);
+        assert_eq!(signal.price, Some(64000.5));
+        assert_eq!(signal.source, "rsi");
+    }
+}
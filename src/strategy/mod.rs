@@ -16,6 +16,9 @@ pub mod config_parser;
 #[cfg(feature = "gate_exec")]
 pub mod moon_strategies;
 
+#[cfg(feature = "gate_exec")]
+pub mod external_signal;
+
 pub use simple_quote::{QuoteConfig, QuotePlan, ReferenceMeta, SimpleQuoteStrategy};
 pub use btc_strategy::{BtcTradingStrategy, BtcStrategyConfig};
 pub use adaptive_channel::{AdaptiveChannelStrategy, StrategyVariant};
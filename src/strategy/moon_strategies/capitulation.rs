@@ -0,0 +1,80 @@
+//! Капитуляция: прострел вместе с кластером ликвидаций лонгов
+//!
+//! Ровная продажа крупного игрока может продолжаться часами, а каскад ликвидаций
+//! заканчивается, когда выбиты плечи: после него цена обычно возвращается. Фильтр
+//! пускает детект, только если за `window_ms` до него биржа принудительно закрыла
+//! не меньше `min_count` лонгов общим объёмом от `min_volume_pct` % объёма прострела.
+//! Объёмы ликвидаций и сделок в одних единицах биржи (контракты Gate, базовая монета Binance).
+
+use crate::base_classes::liquidations::{LiquidationStats, LiquidationWindow};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CapitulationFilterConfig {
+    /// Окно поиска кластера до детекта (мс)
+    pub window_ms: u64,
+    /// Минимум ликвидаций лонгов в окне
+    pub min_count: u32,
+    /// Объём ликвидаций лонгов в % от объёма прострела (0 = не проверять)
+    #[serde(default)]
+    pub min_volume_pct: f64,
+}
+
+impl Default for CapitulationFilterConfig {
+    fn default() -> Self {
+        Self { window_ms: 60_000, min_count: 3, min_volume_pct: 0.0 }
+    }
+}
+
+impl CapitulationFilterConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.window_ms == 0 {
+            bail!("capitulation_filter.window_ms must be positive");
+        }
+        if self.min_count == 0 && self.min_volume_pct <= 0.0 {
+            bail!("capitulation_filter needs min_count or min_volume_pct, otherwise it passes every strike");
+        }
+        if !(self.min_volume_pct >= 0.0 && self.min_volume_pct.is_finite()) {
+            bail!("capitulation_filter.min_volume_pct {} must be non-negative", self.min_volume_pct);
+        }
+        Ok(())
+    }
+
+    pub fn tracker(&self) -> LiquidationWindow {
+        LiquidationWindow::new(self.window_ms)
+    }
+
+    /// Прострел объёмом `strike_volume` сопровождался кластером ликвидаций лонгов
+    pub fn capitulation(&self, stats: &LiquidationStats, strike_volume: f64) -> bool {
+        stats.long_count >= self.min_count.max(1)
+            && stats.long_qty >= strike_volume * self.min_volume_pct / 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::liquidations::{Liquidation, LiquidationSide};
+
+    #[test]
+    fn test_capitulation_needs_long_liquidation_cluster() {
+        let filter = CapitulationFilterConfig { window_ms: 1_000, min_count: 2, min_volume_pct: 10.0 };
+        let mut window = filter.tracker();
+        let liq = |side, qty, ts_ms| Liquidation { symbol: "ETH_USDT".into(), side, price: 100.0, qty, ts_ms };
+        window.push(liq(LiquidationSide::Long, 4.0, 100));
+        // Ликвидации шортов на прострел не указывают
+        window.push(liq(LiquidationSide::Short, 50.0, 200));
+        assert!(!filter.capitulation(&window.stats(300), 50.0));
+
+        window.push(liq(LiquidationSide::Long, 2.0, 300));
+        assert!(filter.capitulation(&window.stats(300), 50.0));
+        // Мало объёма против прострела
+        assert!(!filter.capitulation(&window.stats(300), 100.0));
+        // Кластер вышел из окна
+        assert!(!filter.capitulation(&window.stats(1_200), 50.0));
+
+        assert!(CapitulationFilterConfig { min_count: 0, ..filter }.validate().is_ok());
+        assert!(CapitulationFilterConfig { min_count: 0, min_volume_pct: 0.0, ..filter }.validate().is_err());
+    }
+}
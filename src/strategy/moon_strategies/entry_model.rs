@@ -0,0 +1,16 @@
+//! ML-фильтр входов: обученная модель оценивает каждый детект перед покупкой
+//!
+//! Модель (ONNX) получает вектор признаков детекта в порядке колонок экспорта
+//! `backtest::features` и возвращает вероятность удачного исхода. Вход с вероятностью
+//! ниже `threshold` пропускается. Инференс собирается с фичей `onnx`.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryModelConfig {
+    /// Путь к .onnx файлу модели
+    pub path: PathBuf,
+    /// Минимальная вероятность удачного исхода для входа (0..1)
+    pub threshold: f64,
+}
@@ -0,0 +1,254 @@
+//! Правила выхода из позиции, общие для Hook и MStrike
+//!
+//! TimeStop: позиция старше `max_age_ms` закрывается по рынку (продажа в бид) или
+//! более близким лимитом. Прострелы, которые не отскочили за минуты, редко
+//! восстанавливаются, а позиция держит капитал.
+//!
+//! Breakeven: когда цена прошла +`trigger_pct` от входа, стоп переносится на вход плюс
+//! комиссии и дальше, если задан `trail_pct`, тянется за максимумом.
+//!
+//! AskWall: если между рынком и целью продажи стоит крупная ask-стена, sell за ней
+//! почти не исполняется - цель переносится на `tick_offset` шагов цены ниже стены.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Как закрывать позицию по таймауту
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum TimeStopExit {
+    /// Продажа в бид (или по последней цене, если стакана нет)
+    #[default]
+    Market,
+    /// Лимит на `offset_pct` % выше текущей цены, но не выше обычной цели
+    Limit { offset_pct: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeStopConfig {
+    /// Максимальный возраст позиции (мс), 0 = выключено
+    pub max_age_ms: u64,
+    #[serde(default)]
+    pub exit: TimeStopExit,
+}
+
+impl TimeStopConfig {
+    /// Момент, после которого позиция, открытая в `opened_at`, закрывается
+    pub fn deadline(&self, opened_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.max_age_ms > 0).then(|| opened_at + Duration::milliseconds(self.max_age_ms as i64))
+    }
+
+    pub fn expired(&self, opened_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.deadline(opened_at).is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Цена выхода по таймауту; `target` - обычная цена продажи стратегии
+    pub fn exit_price(&self, price: f64, best_bid: Option<f64>, target: f64) -> f64 {
+        match self.exit {
+            TimeStopExit::Market => best_bid.unwrap_or(price),
+            TimeStopExit::Limit { offset_pct } => (price * (1.0 + offset_pct / 100.0)).min(target),
+        }
+    }
+}
+
+/// TimeStop по умолчанию и переопределения по имени стратегии (`StrategyAdapter::get_name`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimeStopPolicy {
+    #[serde(default)]
+    pub default: Option<TimeStopConfig>,
+    /// `max_age_ms: 0` в переопределении выключает TimeStop для стратегии
+    #[serde(default)]
+    pub overrides: HashMap<String, TimeStopConfig>,
+}
+
+impl TimeStopPolicy {
+    pub fn for_strategy(&self, name: &str) -> Option<TimeStopConfig> {
+        self.overrides
+            .get(name)
+            .copied()
+            .or(self.default)
+            .filter(|config| config.max_age_ms > 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BreakevenConfig {
+    /// Рост от входа (%), после которого стоп переносится в безубыток
+    pub trigger_pct: f64,
+    /// Комиссии входа и выхода (%), которые стоп должен покрыть
+    pub fee_pct: f64,
+    /// Трейлинг от максимума после переноса (%), 0 = стоп стоит на безубытке
+    #[serde(default)]
+    pub trail_pct: f64,
+}
+
+/// Стоп позиции после переноса в безубыток
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BreakevenStop {
+    /// None, пока цена не дошла до `trigger_pct`
+    pub stop_price: Option<f64>,
+    /// Максимум цены с момента переноса
+    pub peak: f64,
+}
+
+impl BreakevenStop {
+    /// Двигает стоп по цене тика; true, если цена дошла до стопа. `widen_pct` опускает
+    /// срабатывание ниже стопа на столько %, пока поток символа устарел; сам стоп не двигается
+    pub fn update(&mut self, config: &BreakevenConfig, entry: f64, price: f64, widen_pct: f64) -> bool {
+        let stop = match self.stop_price {
+            Some(stop) => stop,
+            None if price >= entry * (1.0 + config.trigger_pct / 100.0) => entry * (1.0 + config.fee_pct / 100.0),
+            None => return false,
+        };
+        self.peak = self.peak.max(price);
+        let stop = if config.trail_pct > 0.0 {
+            stop.max(self.peak * (1.0 - config.trail_pct / 100.0))
+        } else {
+            stop
+        };
+        self.stop_price = Some(stop);
+        price <= stop * (1.0 - widen_pct / 100.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AskWallConfig {
+    /// Уровень - стена, если его объём в `wall_ratio` раз больше медианы видимых уровней
+    pub wall_ratio: f64,
+    /// Стена опережается, только если стоит не дальше `max_distance_pct` % ниже цели
+    pub max_distance_pct: f64,
+    /// Sell ставится на `tick_offset` шагов цены ниже стены
+    pub tick_offset: u32,
+    pub tick_size: f64,
+}
+
+/// Видимые ask-уровни по возрастанию цены и медиана их объёма.
+/// Буферы переиспользуются, обновление стакана не аллоцирует после прогрева.
+#[derive(Debug, Clone, Default)]
+pub struct AskLevels {
+    levels: Vec<(f64, f64)>,
+    sizes: Vec<f64>,
+    median_size: f64,
+}
+
+impl AskLevels {
+    pub fn update(&mut self, asks: &[(f64, f64)]) {
+        self.levels.clear();
+        self.levels.extend_from_slice(asks);
+        self.sizes.clear();
+        self.sizes.extend(asks.iter().map(|&(_, size)| size));
+        self.median_size = if self.sizes.is_empty() {
+            0.0
+        } else {
+            let mid = self.sizes.len() / 2;
+            *self.sizes.select_nth_unstable_by(mid, f64::total_cmp).1
+        };
+    }
+
+    pub fn levels(&self) -> &[(f64, f64)] {
+        &self.levels
+    }
+
+    pub fn median_size(&self) -> f64 {
+        self.median_size
+    }
+}
+
+impl AskWallConfig {
+    /// Ближайшая к рынку стена в зоне `max_distance_pct` ниже цели (цель включительно)
+    pub fn wall_near(&self, target: f64, book: &AskLevels) -> Option<(f64, f64)> {
+        let lowest = target * (1.0 - self.max_distance_pct / 100.0);
+        let min_size = book.median_size() * self.wall_ratio;
+        book.levels()
+            .iter()
+            .copied()
+            .filter(|&(price, _)| price >= lowest && price <= target)
+            .find(|&(_, size)| size > 0.0 && size >= min_size)
+    }
+
+    /// Цена продажи перед стеной; None, если стены у цели нет или цена ушла бы
+    /// на `floor` и ниже (обычно цена входа)
+    pub fn front_run(&self, target: f64, book: &AskLevels, floor: f64) -> Option<f64> {
+        let (wall, _) = self.wall_near(target, book)?;
+        let price = wall - self.tick_offset as f64 * self.tick_size;
+        (price > floor).then_some(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_stop_deadline_and_prices() {
+        let config = TimeStopConfig { max_age_ms: 60_000, exit: TimeStopExit::Market };
+        let opened = Utc::now();
+        assert!(!config.expired(opened, opened + Duration::seconds(59)));
+        assert!(config.expired(opened, opened + Duration::seconds(60)));
+        assert_eq!(config.exit_price(100.0, Some(99.9), 105.0), 99.9);
+
+        let limit = TimeStopConfig { exit: TimeStopExit::Limit { offset_pct: 1.0 }, ..config };
+        assert!((limit.exit_price(100.0, Some(99.9), 105.0) - 101.0).abs() < 1e-9);
+        assert_eq!(limit.exit_price(104.5, None, 105.0), 105.0);
+
+        let off = TimeStopConfig { max_age_ms: 0, ..config };
+        assert!(!off.expired(opened, opened + Duration::days(1)));
+    }
+
+    #[test]
+    fn test_policy_overrides_per_strategy() {
+        let policy = TimeStopPolicy {
+            default: Some(TimeStopConfig { max_age_ms: 300_000, exit: TimeStopExit::Market }),
+            overrides: HashMap::from([
+                ("Hook".to_string(), TimeStopConfig { max_age_ms: 120_000, exit: TimeStopExit::Market }),
+                ("MShot".to_string(), TimeStopConfig { max_age_ms: 0, exit: TimeStopExit::Market }),
+            ]),
+        };
+        assert_eq!(policy.for_strategy("Hook").unwrap().max_age_ms, 120_000);
+        assert_eq!(policy.for_strategy("MStrike").unwrap().max_age_ms, 300_000);
+        assert!(policy.for_strategy("MShot").is_none());
+    }
+
+    #[test]
+    fn test_breakeven_moves_then_trails() {
+        let config = BreakevenConfig { trigger_pct: 2.0, fee_pct: 0.2, trail_pct: 1.0 };
+        let mut stop = BreakevenStop::default();
+        assert!(!stop.update(&config, 100.0, 101.9, 0.0));
+        assert_eq!(stop.stop_price, None);
+
+        assert!(!stop.update(&config, 100.0, 102.0, 0.0));
+        // Трейлинг 1% от 102 = 100.98 выше безубытка 100.2
+        assert!((stop.stop_price.unwrap() - 100.98).abs() < 1e-9);
+        assert!(!stop.update(&config, 100.0, 105.0, 0.0));
+        assert!((stop.stop_price.unwrap() - 103.95).abs() < 1e-9);
+        // Откат не опускает стоп
+        assert!(stop.update(&config, 100.0, 103.9, 0.0));
+        assert!((stop.stop_price.unwrap() - 103.95).abs() < 1e-9);
+
+        let fixed = BreakevenConfig { trail_pct: 0.0, ..config };
+        let mut stop = BreakevenStop::default();
+        stop.update(&fixed, 100.0, 110.0, 0.0);
+        assert!((stop.stop_price.unwrap() - 100.2).abs() < 1e-9);
+        // Расширенный на 1% стоп пропускает 100.1, но не 99.1
+        assert!(!stop.update(&fixed, 100.0, 100.1, 1.0));
+        assert!(stop.update(&fixed, 100.0, 99.1, 1.0));
+        assert!(stop.update(&fixed, 100.0, 100.1, 0.0));
+    }
+
+    #[test]
+    fn test_ask_wall_front_run() {
+        let config = AskWallConfig { wall_ratio: 5.0, max_distance_pct: 1.0, tick_offset: 2, tick_size: 0.01 };
+        let mut book = AskLevels::default();
+        book.update(&[(100.00, 10.0), (100.05, 12.0), (100.10, 9.0), (100.50, 80.0), (100.80, 11.0)]);
+        assert_eq!(book.median_size(), 11.0);
+
+        // Стена 100.50 в пределах 1% ниже цели 101.00
+        let price = config.front_run(101.0, &book, 99.0).unwrap();
+        assert!((price - 100.48).abs() < 1e-9);
+        // Стена за целью не мешает, слишком далёкая - не опережается
+        assert!(config.front_run(100.4, &book, 99.0).is_none());
+        assert!(config.front_run(102.0, &book, 99.0).is_none());
+        // Опережение не уходит ниже входа
+        assert!(config.front_run(101.0, &book, 100.48).is_none());
+    }
+}
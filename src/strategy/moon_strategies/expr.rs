@@ -0,0 +1,227 @@
+//! Выражения в значениях конфига: `= min_notional * 3`, `= max(3, atr_1h * 1.5)`
+//!
+//! Грамматика: числа, имена свойств символа, `+ - * /`, скобки, унарный минус и функции
+//! `min`, `max` (от одного аргумента и больше), `clamp(x, lo, hi)`, `abs`, `round`,
+//! `floor`, `ceil`. Свойства символа (`SymbolProperties`) собирает вызывающий: мин.
+//! нотионал из спецификации контракта, ATR, оборот и т.п.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+
+/// Именованные числовые свойства символа, доступные в выражениях
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolProperties {
+    values: BTreeMap<String, f64>,
+}
+
+impl SymbolProperties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, value: f64) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values.get(name).copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Property(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, char, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+        let expr = parser.sum()?;
+        parser.skip_ws();
+        if parser.pos < parser.chars.len() {
+            bail!("unexpected '{}' at {} in '{}'", parser.chars[parser.pos], parser.pos, text);
+        }
+        Ok(expr)
+    }
+
+    pub fn eval(&self, props: &SymbolProperties) -> Result<f64> {
+        let value = match self {
+            Expr::Number(n) => *n,
+            Expr::Property(name) => props.get(name).ok_or_else(|| anyhow!("unknown symbol property '{}'", name))?,
+            Expr::Neg(inner) => -inner.eval(props)?,
+            Expr::Binary(lhs, op, rhs) => {
+                let (a, b) = (lhs.eval(props)?, rhs.eval(props)?);
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => {
+                        if b == 0.0 {
+                            bail!("division by zero");
+                        }
+                        a / b
+                    }
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|a| a.eval(props)).collect::<Result<Vec<_>>>()?;
+                call(name, &args)?
+            }
+        };
+        if !value.is_finite() {
+            bail!("expression evaluated to {}", value);
+        }
+        Ok(value)
+    }
+}
+
+fn call(name: &str, args: &[f64]) -> Result<f64> {
+    let one = || match args {
+        [x] => Ok(*x),
+        _ => Err(anyhow!("{}() takes one argument, got {}", name, args.len())),
+    };
+    Ok(match name {
+        "min" | "max" if args.is_empty() => bail!("{}() needs at least one argument", name),
+        "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+        "max" => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        "clamp" => match args {
+            [x, lo, hi] if lo <= hi => x.clamp(*lo, *hi),
+            [_, lo, hi] => bail!("clamp() bounds {} > {}", lo, hi),
+            _ => bail!("clamp() takes three arguments, got {}", args.len()),
+        },
+        "abs" => one()?.abs(),
+        "round" => one()?.round(),
+        "floor" => one()?.floor(),
+        "ceil" => one()?.ceil(),
+        _ => bail!("unknown function {}()", name),
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_ws(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.peek() {
+            Some(found) if found == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            found => bail!("expected '{}' at {}, found {:?}", c, self.pos, found),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut lhs = self.product()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.product()?));
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek() == Some('-') {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse().map(Expr::Number).map_err(|_| anyhow!("bad number '{}'", text))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                if self.peek() != Some('(') {
+                    return Ok(Expr::Property(name));
+                }
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() != Some(')') {
+                    loop {
+                        args.push(self.sum()?);
+                        if self.peek() != Some(',') {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                self.expect(')')?;
+                Ok(Expr::Call(name.to_ascii_lowercase(), args))
+            }
+            found => bail!("expected a number, name or '(' at {}, found {:?}", self.pos, found),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expressions_evaluate_against_symbol_properties() {
+        let props = SymbolProperties::new().with("min_notional", 5.0).with("atr_1h", 1.2);
+        let eval = |text: &str| Expr::parse(text).and_then(|e| e.eval(&props));
+        assert_eq!(eval("min_notional * 3").unwrap(), 15.0);
+        assert!((eval("max(3, atr_1h * 1.5)").unwrap() - 3.0).abs() < 1e-12);
+        assert_eq!(eval("-(1 + 2) * 2 - -1").unwrap(), -5.0);
+        assert_eq!(eval("clamp(atr_1h * 10, 2, 8)").unwrap(), 8.0);
+        assert_eq!(eval("round(2.5) + floor(1.9) + ceil(0.1) + abs(-1)").unwrap(), 6.0);
+
+        assert!(eval("volume_24h * 2").unwrap_err().to_string().contains("volume_24h"));
+        assert!(eval("1 / (atr_1h - 1.2)").is_err());
+        assert!(Expr::parse("min(1,").is_err());
+        assert!(Expr::parse("2 * ").is_err());
+        assert!(Expr::parse("3 3").is_err());
+        assert!(eval("median(1, 2)").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Дисбаланс потока ордеров по стороне агрессора (`TradeTick::side`)
+//!
+//! Прострел вниз — это поток рыночных продаж. Пока продавцы не выдохлись, покупка на
+//! «дне» часто оказывается серединой падения. Фильтр пускает вход, только когда за
+//! последние `window_ms` объём агрессивных покупок догнал продажи: дисбаланс
+//! (покупки − продажи) / (покупки + продажи) не ниже `min_imbalance`.
+
+use super::window::{Aggregate, TimeWindow};
+use crate::backtest::market::{TradeSide, TradeTick};
+use anyhow::{bail, Result};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Объёмы агрессивных покупок и продаж за окно
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Flow {
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+}
+
+impl Flow {
+    pub fn total(&self) -> f64 {
+        self.buy_volume + self.sell_volume
+    }
+
+    /// От -1 (только продажи) до 1 (только покупки); 0 без сделок
+    pub fn imbalance(&self) -> f64 {
+        let total = self.total();
+        if total > 0.0 { (self.buy_volume - self.sell_volume) / total } else { 0.0 }
+    }
+}
+
+/// Агрегат окна: объём со знаком, покупки положительные, продажи отрицательные
+#[derive(Debug, Clone, Copy, Default)]
+struct FlowSums(Flow);
+
+impl Aggregate<f64> for FlowSums {
+    #[inline]
+    fn add(&mut self, _seq: u64, signed: &f64) {
+        if *signed >= 0.0 {
+            self.0.buy_volume += signed;
+        } else {
+            self.0.sell_volume -= signed;
+        }
+    }
+    #[inline]
+    fn remove(&mut self, _seq: u64, signed: &f64) {
+        // Накопленная ошибка округления не должна давать отрицательный объём
+        if *signed >= 0.0 {
+            self.0.buy_volume = (self.0.buy_volume - signed).max(0.0);
+        } else {
+            self.0.sell_volume = (self.0.sell_volume + signed).max(0.0);
+        }
+    }
+}
+
+/// Скользящие объёмы по стороне за окно времени
+#[derive(Debug, Clone)]
+pub struct OrderFlow {
+    window: Duration,
+    trades: TimeWindow<f64, FlowSums>,
+}
+
+impl OrderFlow {
+    pub fn new(window: Duration) -> Self {
+        Self { window, trades: TimeWindow::new() }
+    }
+
+    /// Тик; время должно быть неубывающим
+    pub fn push(&mut self, tick: &TradeTick) {
+        let signed = match tick.side {
+            TradeSide::Buy => tick.volume,
+            TradeSide::Sell => -tick.volume,
+        };
+        self.trades.push(tick.timestamp, signed);
+        self.trades.evict_before(tick.timestamp - self.window);
+    }
+
+    pub fn flow(&self) -> Flow {
+        self.trades.aggregate().0
+    }
+}
+
+/// Требование истощения продавцов перед входом в лонг
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FlowFilterConfig {
+    /// Окно подсчёта объёмов (мс)
+    pub window_ms: u64,
+    /// Минимальный дисбаланс покупок, от -1 до 1 (0 = покупки не меньше продаж)
+    pub min_imbalance: f64,
+    /// Минимальный объём за окно, чтобы пара сделок не решала за весь рынок
+    #[serde(default)]
+    pub min_volume: f64,
+    /// MStrike: сколько ждать истощения после детекта (мс), дольше — детект сбрасывается.
+    /// Hook ждёт, пока глубина держится в окне HookTimeFrame
+    pub max_wait_ms: u64,
+}
+
+impl Default for FlowFilterConfig {
+    fn default() -> Self {
+        Self { window_ms: 5_000, min_imbalance: 0.0, min_volume: 0.0, max_wait_ms: 10_000 }
+    }
+}
+
+impl FlowFilterConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.window_ms == 0 {
+            bail!("flow_filter.window_ms must be positive");
+        }
+        if !(-1.0..=1.0).contains(&self.min_imbalance) {
+            bail!("flow_filter.min_imbalance {} must be within -1..1", self.min_imbalance);
+        }
+        if !(self.min_volume >= 0.0 && self.min_volume.is_finite()) {
+            bail!("flow_filter.min_volume {} must be non-negative", self.min_volume);
+        }
+        Ok(())
+    }
+
+    pub fn tracker(&self) -> OrderFlow {
+        OrderFlow::new(Duration::milliseconds(self.window_ms as i64))
+    }
+
+    /// Продавцы выдохлись: объёма достаточно и покупки догнали продажи
+    pub fn exhausted(&self, flow: &Flow) -> bool {
+        flow.total() > 0.0 && flow.total() >= self.min_volume && flow.imbalance() >= self.min_imbalance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_classes::symbol::Symbol;
+    use chrono::{TimeZone, Utc};
+
+    fn tick(ms: i64, side: TradeSide, volume: f64) -> TradeTick {
+        TradeTick {
+            timestamp: Utc.timestamp_millis_opt(ms).unwrap(),
+            symbol: Symbol::new("ETH_USDT"),
+            price: 100.0,
+            volume,
+            side,
+            trade_id: String::new(),
+            best_bid: None,
+            best_ask: None,
+        }
+    }
+
+    #[test]
+    fn test_imbalance_recovers_as_sells_leave_window() {
+        let filter = FlowFilterConfig { window_ms: 1_000, min_imbalance: 0.2, min_volume: 2.0, max_wait_ms: 5_000 };
+        let mut flow = filter.tracker();
+        flow.push(&tick(0, TradeSide::Sell, 3.0));
+        flow.push(&tick(400, TradeSide::Sell, 1.0));
+        flow.push(&tick(800, TradeSide::Buy, 1.0));
+        assert_eq!(flow.flow().imbalance(), -0.6);
+        assert!(!filter.exhausted(&flow.flow()));
+
+        // Первая продажа вышла из окна
+        flow.push(&tick(1_200, TradeSide::Buy, 2.0));
+        assert_eq!(flow.flow(), Flow { buy_volume: 3.0, sell_volume: 1.0 });
+        assert!(filter.exhausted(&flow.flow()));
+
+        // Мало объёма — не решение
+        flow.push(&tick(5_000, TradeSide::Buy, 1.0));
+        assert_eq!(flow.flow().imbalance(), 1.0);
+        assert!(!filter.exhausted(&flow.flow()));
+
+        assert!(FlowFilterConfig { min_imbalance: 1.5, ..filter }.validate().is_err());
+        assert!(FlowFilterConfig { window_ms: 0, ..filter }.validate().is_err());
+    }
+}
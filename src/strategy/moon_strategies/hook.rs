@@ -1,10 +1,21 @@
 //! Hook стратегия - динамический коридор цены
 //! Детектит быстрое падение и выставляет buy-ордер, который движется в коридоре
 
+use super::entry_model::EntryModelConfig;
+use super::exits::{BreakevenConfig, BreakevenStop, TimeStopConfig};
+use super::flow::{FlowFilterConfig, OrderFlow};
+use super::inspect::{CorridorBounds, OrderIntent};
+use super::numeric::{depth_pct, valid_price, valid_volume};
+use super::phase::Phase;
+use super::volume_profile::{VolumeProfile, VolumeProfileConfig};
+use super::window::{Extremes, Sum, TimeWindow};
 use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::WarmupRequirements;
+use crate::base_classes::types::Side;
+use crate::error::{Error, ExecutionError, MarketDataError};
+use crate::execution::TimeInForce;
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookConfig {
@@ -18,12 +29,18 @@ pub struct HookConfig {
     pub hook_price_distance: f64,         // Ширина коридора в % от глубины
     pub hook_price_roll_back: f64,        // Процент отката цены (%)
     pub hook_price_roll_back_max: f64,    // Ограничение роллбека
-    pub hook_roll_back_wait: u64,        // Время ожидания отката (мс)
+    pub hook_roll_back_wait: u64,        // Время ожидания отката до цены RollBack (мс, 0 = buy сразу при детекте)
     
     // Дополнительные фильтры
     pub hook_anti_pump: bool,             // Исключить прострелы после быстрого роста
     pub hook_drop_min: f64,               // Падение цены перед детектом (мин %)
     pub hook_drop_max: f64,               // Падение цены перед детектом (макс %)
+    // Истощение продавцов: детект только когда покупки догнали продажи (None = без фильтра)
+    #[serde(default)]
+    pub flow_filter: Option<FlowFilterConfig>,
+    // Границы коридора прижимаются к узлам профиля объёма внутри коридора (None = как посчитано)
+    #[serde(default)]
+    pub volume_profile: Option<VolumeProfileConfig>,
     
     // Направление
     pub hook_direction: HookDirection,    // Long, Short, Both
@@ -49,6 +66,26 @@ pub struct HookConfig {
     pub hook_repeat_after_sell: bool,
     pub hook_repeat_if_profit: f64,       // % для повтора
     
+    // Time-in-force: коридорный buy обычно GTX, чтобы стоять мейкером
+    #[serde(default)]
+    pub hook_buy_tif: TimeInForce,
+    #[serde(default)]
+    pub hook_sell_tif: TimeInForce,
+    
+    // Повторное взведение детекта
+    #[serde(default)]
+    pub hook_rearm_policy: HookRearmPolicy, // Когда сбрасывать коридор и разрешать новый детект
+    
+    // Выход по возрасту позиции (None = держать до цели)
+    #[serde(default)]
+    pub time_stop: Option<TimeStopConfig>,
+    // Перенос стопа в безубыток после роста (None = без стопа)
+    #[serde(default)]
+    pub breakeven: Option<BreakevenConfig>,
+    // ML-фильтр входов (None = входить по каждому детекту)
+    #[serde(default)]
+    pub entry_model: Option<EntryModelConfig>,
+    
     // Общие параметры
     pub order_size: f64,
     pub buy_modifier: f64,                // Модификатор ширины коридора (отрицательный!)
@@ -63,6 +100,59 @@ pub enum HookDirection {
     Both,
 }
 
+/// Политика повторного взведения детекта после срабатывания
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum HookRearmPolicy {
+    /// Новый детект через HookTimeFrame после предыдущего (коридор не сбрасывается)
+    #[default]
+    TimeFrame,
+    /// Сброс коридора через N секунд после детекта или последней продажи
+    AfterSeconds(f64),
+    /// Сброс коридора когда цена восстановилась на X% от глубины прострела
+    PriceRecovery(f64),
+    /// Только вручную через `HookStrategy::rearm()`
+    Manual,
+}
+
+/// Состояние взведения детекта (для дашборда)
+#[derive(Debug, Clone, Serialize)]
+pub struct HookRearmStatus {
+    pub policy: HookRearmPolicy,
+    pub armed: bool,
+    pub detected_at: Option<DateTime<Utc>>,
+    pub last_sell_at: Option<DateTime<Utc>>,
+    pub corridor_active: bool,
+    /// Цена, при достижении которой сработает PriceRecovery
+    pub recovery_price: Option<f64>,
+    /// Момент, когда сработает AfterSeconds / TimeFrame
+    pub rearm_at: Option<DateTime<Utc>>,
+}
+
+/// Read-model состояния Hook для дашборда и тестов
+#[derive(Debug, Clone, Serialize)]
+pub struct HookView {
+    pub detected: bool,
+    pub detected_at: Option<DateTime<Utc>>,
+    pub detection_depth: Option<f64>,
+    pub strike_min_price: Option<f64>,
+    pub strike_max_price: Option<f64>,
+    pub rollback_price: Option<f64>,
+    /// До какого момента ждём отката к `rollback_price` (HookRollBackWait)
+    pub rollback_wait_until: Option<DateTime<Utc>>,
+    pub corridor: Option<CorridorBounds>,
+    pub initial_buy_price: Option<f64>,
+    pub phase: Phase,
+    pub entry_price: Option<f64>,
+    pub position_size: f64,
+    pub pending_orders: Vec<OrderIntent>,
+    pub repeat_orders: usize,
+    pub position_opened_at: Option<DateTime<Utc>>,
+    /// Когда позиция будет закрыта по TimeStop
+    pub time_stop_at: Option<DateTime<Utc>>,
+    /// Стоп после переноса в безубыток
+    pub stop_price: Option<f64>,
+}
+
 impl Default for HookConfig {
     fn default() -> Self {
         HookConfig {
@@ -77,6 +167,8 @@ impl Default for HookConfig {
             hook_anti_pump: false,
             hook_drop_min: 0.0,
             hook_drop_max: 0.0,
+            flow_filter: None,
+            volume_profile: None,
             hook_direction: HookDirection::Long,
             hook_opposite_order: false,
             hook_interpolate: 0,
@@ -89,6 +181,12 @@ impl Default for HookConfig {
             hook_part_filled_delay: 0,
             hook_repeat_after_sell: false,
             hook_repeat_if_profit: 0.0,
+            hook_buy_tif: TimeInForce::Gtc,
+            hook_sell_tif: TimeInForce::Gtc,
+            hook_rearm_policy: HookRearmPolicy::TimeFrame,
+            time_stop: None,
+            breakeven: None,
+            entry_model: None,
             order_size: 100.0,
             buy_modifier: -3.0,
             use_stop_loss: false,
@@ -97,11 +195,43 @@ impl Default for HookConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+impl HookConfig {
+    /// Проверка значений, которые стратегия не переживёт молча
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.hook_time_frame <= Duration::zero() {
+            anyhow::bail!("hook_time_frame must be positive");
+        }
+        if !(self.hook_detect_depth > 0.0 && self.hook_detect_depth.is_finite()) {
+            anyhow::bail!("hook_detect_depth {} must be positive", self.hook_detect_depth);
+        }
+        if self.hook_detect_depth_max != 0.0 && self.hook_detect_depth_max < self.hook_detect_depth {
+            anyhow::bail!(
+                "hook_detect_depth_max {} is below hook_detect_depth {}",
+                self.hook_detect_depth_max,
+                self.hook_detect_depth
+            );
+        }
+        if self.hook_interpolate > 4 {
+            anyhow::bail!("hook_interpolate {} must be 0-4", self.hook_interpolate);
+        }
+        if !(self.order_size > 0.0 && self.order_size.is_finite()) {
+            anyhow::bail!("order_size {} must be positive", self.order_size);
+        }
+        if let Some(filter) = &self.flow_filter {
+            filter.validate()?;
+        }
+        if let Some(profile) = &self.volume_profile {
+            profile.validate()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookState {
     // Окно для анализа (HookTimeFrame)
-    price_window: VecDeque<(DateTime<Utc>, f64)>, // История цен в окне
-    volume_window: VecDeque<(DateTime<Utc>, f64)>, // История объемов
+    price_window: TimeWindow<f64, Extremes>, // История цен в окне, min/max без пересчета на каждом тике
+    volume_window: TimeWindow<f64, Sum>, // История объемов
     
     // Состояние детекта
     strike_detected: bool,
@@ -110,6 +240,9 @@ pub struct HookState {
     strike_min_price: f64,
     strike_max_price: f64,
     strike_rollback_price: Option<f64>,
+    // Дедлайн ожидания отката после детекта; пока он есть, buy не выставлен
+    #[serde(default)]
+    rollback_deadline: Option<DateTime<Utc>>,
     
     // Дельты на момент детекта (для BuyModifier)
     deltas_at_detection: Option<super::mshot::Deltas>,
@@ -119,16 +252,35 @@ pub struct HookState {
     corridor_lower: Option<f64>,
     initial_buy_price: Option<f64>,
     
+    // Фаза входа; детект (strike_detected) живёт дольше входа - до перевзведения
+    #[serde(default)]
+    phase: Phase,
+    
     // Текущий ордер
     active_order_id: Option<u64>,
+    // Перевзведение пришлось на buy без id: отмена уйдёт, как только биржа его подтвердит
+    #[serde(default)]
+    cancel_pending: bool,
     buy_price: Option<f64>,
     position_size: f64,
     
     // Повторные ордера
     repeat_orders: Vec<RepeatOrderState>,
+    
+    // Время последней продажи (для HookRearmPolicy::AfterSeconds)
+    last_sell_time: Option<DateTime<Utc>>,
+    
+    // Открытие позиции (время тика при филе), стоп безубытка и выставленный
+    // принудительный выход (TimeStop или стоп)
+    #[serde(default)]
+    position_opened_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    breakeven_stop: BreakevenStop,
+    #[serde(default)]
+    forced_exit_price: Option<f64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct RepeatOrderState {
     buy_price: f64,
     sell_price: f64,
@@ -158,34 +310,51 @@ pub enum HookSignal {
     CancelOrder {
         order_id: u64,
     },
+    /// Битый тик (пропущен), испорченное окно (сброшено) или нет нужного состояния позиции
+    Error(Error),
 }
 
 pub struct HookStrategy {
     config: HookConfig,
     state: HookState,
+    // Поток сделок и профиль объёма - рыночные данные, в снимок не входят
+    flow: Option<OrderFlow>,
+    profile: Option<VolumeProfile>,
+    /// Расширение стопов устаревшего символа, %; 0 - обычные стопы
+    stop_widen_pct: f64,
 }
 
 impl HookStrategy {
     pub fn new(config: HookConfig) -> Self {
         Self {
+            flow: config.flow_filter.map(|filter| filter.tracker()),
+            profile: config.volume_profile.map(|profile| profile.profile()),
             config,
+            stop_widen_pct: 0.0,
             state: HookState {
-                price_window: VecDeque::new(),
-                volume_window: VecDeque::new(),
+                price_window: TimeWindow::new(),
+                volume_window: TimeWindow::new(),
                 strike_detected: false,
                 strike_detection_time: None,
                 strike_depth: 0.0,
                 strike_min_price: 0.0,
                 strike_max_price: 0.0,
                 strike_rollback_price: None,
+                rollback_deadline: None,
                 deltas_at_detection: None,
                 corridor_upper: None,
                 corridor_lower: None,
                 initial_buy_price: None,
+                phase: Phase::Idle,
                 active_order_id: None,
+                cancel_pending: false,
                 buy_price: None,
                 position_size: 0.0,
                 repeat_orders: Vec::new(),
+                last_sell_time: None,
+                position_opened_at: None,
+                breakeven_stop: BreakevenStop::default(),
+                forced_exit_price: None,
             },
         }
     }
@@ -200,24 +369,47 @@ impl HookStrategy {
         let current_price = tick.price;
         let volume = tick.volume;
         
+        // Битый тик не должен попасть в окно min/max на весь HookTimeFrame
+        if !(valid_price(current_price) && valid_volume(volume)) {
+            return HookSignal::Error(
+                MarketDataError::InvalidTick {
+                    symbol: tick.symbol.to_string(),
+                    reason: format!("price {} volume {}", current_price, volume),
+                }
+                .into(),
+            );
+        }
+        
         // Обновляем окно данных
         self.update_window(now, current_price, volume);
-        
-        // Если есть позиция - управляем ей
-        if self.state.buy_price.is_some() {
-            return self.manage_position(tick);
+        if let Some(flow) = &mut self.flow {
+            flow.push(tick);
         }
-        
-        // Если есть активный ордер в коридоре - проверяем перестановку
-        if self.state.active_order_id.is_some() && self.state.corridor_upper.is_some() {
-            return self.manage_corridor_order(tick);
+        if let Some(profile) = &mut self.profile {
+            profile.push(now, current_price, volume);
         }
         
-        // Проверяем детект (если не детектировали или прошло достаточно времени)
-        if !self.state.strike_detected || self.can_detect_again(now) {
-            if let Some(signal) = self.detect_hook(tick, deltas) {
-                return signal;
+        match self.state.phase {
+            // Есть позиция - управляем ей
+            Phase::Positioned | Phase::Exiting => return self.manage_position(tick),
+            // Buy стоит в коридоре: перевзведение снимает его, иначе проверяем перестановку
+            Phase::OrderResting if self.state.cancel_pending || self.rearm_due(now, current_price) => {
+                return self.cancel_entry()
             }
+            Phase::OrderResting => return self.manage_corridor_order(tick),
+            // Детект ждёт отката к цене RollBack
+            Phase::Detected if self.state.rollback_deadline.is_some() => return self.await_rollback(tick),
+            Phase::Idle | Phase::Detected => {}
+        }
+        
+        // Проверяем детект (если не детектировали или сработала политика перевзведения)
+        if self.rearm_due(now, current_price) {
+            self.reset_detection();
+        }
+        if (!self.state.strike_detected || self.can_detect_again(now, current_price))
+            && let Some(signal) = self.detect_hook(tick, deltas)
+        {
+            return signal;
         }
         
         HookSignal::NoAction
@@ -225,25 +417,13 @@ impl HookStrategy {
     
     fn update_window(&mut self, timestamp: DateTime<Utc>, price: f64, volume: f64) {
         // Добавляем текущие данные
-        self.state.price_window.push_back((timestamp, price));
-        self.state.volume_window.push_back((timestamp, volume));
+        self.state.price_window.push(timestamp, price);
+        self.state.volume_window.push(timestamp, volume);
         
         // Удаляем старые данные вне HookTimeFrame
         let cutoff_time = timestamp - self.config.hook_time_frame;
-        
-        while let Some(&(time, _)) = self.state.price_window.front() {
-            if time >= cutoff_time {
-                break;
-            }
-            self.state.price_window.pop_front();
-        }
-        
-        while let Some(&(time, _)) = self.state.volume_window.front() {
-            if time >= cutoff_time {
-                break;
-            }
-            self.state.volume_window.pop_front();
-        }
+        self.state.price_window.evict_before(cutoff_time);
+        self.state.volume_window.evict_before(cutoff_time);
     }
     
     fn detect_hook(&mut self, tick: &TradeTick, deltas: &super::mshot::Deltas) -> Option<HookSignal> {
@@ -252,14 +432,23 @@ impl HookStrategy {
         }
         
         let current_price = tick.price;
-        let prices: Vec<f64> = self.state.price_window.iter().map(|(_, p)| *p).collect();
         
-        // Находим максимум и минимум в окне
-        let max_price = prices.iter().fold(0.0f64, |a, &b| a.max(b));
-        let min_price = prices.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        // Максимум и минимум в окне (поддерживаются инкрементально в update_window)
+        let max_price = self.state.price_window.aggregate().max().unwrap_or(current_price);
+        let min_price = self.state.price_window.aggregate().min().unwrap_or(current_price);
         
-        // Вычисляем глубину прострела
-        let depth = ((max_price - min_price) / max_price) * 100.0;
+        // Вычисляем глубину прострела; испорченное окно начинаем заново
+        let depth = match depth_pct(max_price, min_price) {
+            Ok(depth) => depth,
+            Err(err) => {
+                self.state.price_window.clear();
+                self.state.volume_window.clear();
+                return Some(HookSignal::Error(
+                    MarketDataError::InvalidTick { symbol: tick.symbol.to_string(), reason: format!("window depth: {}", err) }
+                        .into(),
+                ));
+            }
+        };
         
         // Проверяем условие детекта
         if depth < self.config.hook_detect_depth {
@@ -281,7 +470,17 @@ impl HookStrategy {
             // TODO: Проверка падения за последние 2 минуты
         }
         
+        // Продавцы ещё давят: ждём, пока глубина в окне продержится до их истощения
+        if let (Some(filter), Some(flow)) = (&self.config.flow_filter, &self.flow)
+            && !filter.exhausted(&flow.flow())
+        {
+            return None;
+        }
+        
         // Детект найден!
+        if let Err(err) = self.enter(Phase::Detected, "detect_hook") {
+            return Some(HookSignal::Error(err.into()));
+        }
         self.state.strike_detected = true;
         self.state.strike_detection_time = Some(tick.timestamp);
         self.state.strike_depth = depth;
@@ -293,6 +492,47 @@ impl HookStrategy {
         let rollback_price = max_price - (depth * self.config.hook_price_roll_back / 100.0) * (max_price / 100.0);
         self.state.strike_rollback_price = Some(rollback_price);
         
+        // HookRollBackWait: buy только после отката, коридор считается в момент отката
+        if self.config.hook_roll_back_wait > 0 {
+            self.state.rollback_deadline =
+                Some(tick.timestamp + Duration::milliseconds(self.config.hook_roll_back_wait as i64));
+            return Some(HookSignal::DetectHook { depth, min_price, max_price });
+        }
+        
+        self.place_entry("detect_hook")
+    }
+    
+    /// Ожидание отката после детекта: цена дошла до RollBack вовремя - выставляем коридорный
+    /// buy, не дошла - вход пропускается, детект остаётся до перевзведения
+    fn await_rollback(&mut self, tick: &TradeTick) -> HookSignal {
+        let (Some(deadline), Some(rollback_price)) = (self.state.rollback_deadline, self.state.strike_rollback_price) else {
+            return Self::missing("strike_rollback_price", "await_rollback");
+        };
+        if tick.timestamp > deadline {
+            self.skip_entry(tick.timestamp);
+            return HookSignal::NoAction;
+        }
+        if tick.price < rollback_price {
+            return HookSignal::NoAction;
+        }
+        self.state.rollback_deadline = None;
+        self.place_entry("await_rollback").unwrap_or(HookSignal::NoAction)
+    }
+    
+    /// Отката не было за HookRollBackWait
+    fn skip_entry(&mut self, now: DateTime<Utc>) {
+        log::debug!(
+            "Hook entry skipped at {}: no rollback to {:?} within {} ms",
+            now,
+            self.state.strike_rollback_price,
+            self.config.hook_roll_back_wait
+        );
+        self.state.rollback_deadline = None;
+        self.state.phase = Phase::Idle;
+    }
+    
+    /// Коридор, размер и buy по зафиксированному детекту
+    fn place_entry(&mut self, context: &'static str) -> Option<HookSignal> {
         // Вычисляем коридор и начальную цену
         self.calculate_corridor();
         
@@ -305,12 +545,17 @@ impl HookStrategy {
         }
         
         // Выставляем ордер
-        let buy_price = self.state.initial_buy_price.unwrap();
+        let Some(buy_price) = self.state.initial_buy_price else {
+            return Some(Self::missing("initial_buy_price", context));
+        };
+        if let Err(err) = self.enter(Phase::OrderResting, context) {
+            return Some(HookSignal::Error(err.into()));
+        }
         
         Some(HookSignal::PlaceBuy {
             price: buy_price,
             size: order_size,
-            reason: format!("Hook detected: depth={:.2}%", depth),
+            reason: format!("Hook detected: depth={:.2}%", self.state.strike_depth),
         })
     }
     
@@ -377,18 +622,30 @@ impl HookStrategy {
             }
         }
         
+        let (upper, lower) = self.snap_to_volume_nodes(upper, lower, initial);
         self.state.corridor_upper = Some(upper);
         self.state.corridor_lower = Some(lower);
         self.state.initial_buy_price = Some(initial);
     }
     
+    /// Нижняя граница поднимается к ближайшему узлу объёма под начальной ценой (поддержка),
+    /// верхняя опускается к ближайшему узлу над ней (сопротивление)
+    fn snap_to_volume_nodes(&self, upper: f64, lower: f64, initial: f64) -> (f64, f64) {
+        let (Some(config), Some(profile)) = (&self.config.volume_profile, &self.profile) else {
+            return (upper, lower);
+        };
+        let lower = profile.highest_node_within(config.node_ratio, lower, initial).unwrap_or(lower);
+        let upper = profile.lowest_node_within(config.node_ratio, initial, upper).unwrap_or(upper);
+        (upper, lower)
+    }
+    
     fn calculate_order_size(&self) -> f64 {
         if self.config.buy_order_reduce == 0 {
             return self.config.order_size;
         }
         
         // Вычисляем средний объем за BuyOrderReduce интервал
-        let total_volume: f64 = self.state.volume_window.iter().map(|(_, v)| *v).sum();
+        let total_volume = self.state.volume_window.aggregate().sum();
         let time_window_ms = self.config.hook_time_frame.num_milliseconds() as f64;
         let avg_volume_per_interval = (total_volume / time_window_ms) * (self.config.buy_order_reduce as f64);
         
@@ -398,18 +655,20 @@ impl HookStrategy {
     
     fn manage_corridor_order(&mut self, tick: &TradeTick) -> HookSignal {
         let current_price = tick.price;
-        let upper = self.state.corridor_upper.unwrap();
-        let lower = self.state.corridor_lower.unwrap();
-        let buy_price = self.state.initial_buy_price.unwrap();
+        let (Some(upper), Some(lower)) = (self.state.corridor_upper, self.state.corridor_lower) else {
+            return Self::missing("corridor_lower", "manage_corridor_order");
+        };
         
         // Проверяем, нужно ли переставить ордер
         if current_price <= lower {
             // Цена упала до нижней границы - переставляем вниз
             let new_price = lower * 0.99; // Немного ниже нижней границы
+            self.state.initial_buy_price = Some(new_price);
             return HookSignal::ReplaceBuy { new_price };
         } else if current_price >= upper {
             // Цена выросла до верхней границы - переставляем вверх
             let new_price = upper * 0.99;
+            self.state.initial_buy_price = Some(new_price);
             return HookSignal::ReplaceBuy { new_price };
         }
         
@@ -418,16 +677,17 @@ impl HookStrategy {
     
     fn manage_position(&mut self, tick: &TradeTick) -> HookSignal {
         let current_price = tick.price;
-        let buy_price = self.state.buy_price.unwrap();
-        let depth = self.state.strike_depth;
+        let Some(buy_price) = self.state.buy_price else {
+            return Self::missing("buy_price", "manage_position");
+        };
+        
+        // Принудительный выход уже выставлен - ждём его фила
+        if self.state.phase == Phase::Exiting {
+            return HookSignal::NoAction;
+        }
         
         // Вычисляем цену продажи
-        let sell_price = if self.config.hook_sell_fixed {
-            let min_price = self.state.strike_min_price;
-            min_price * (1.0 + (depth * self.config.hook_sell_level / 100.0) / 100.0)
-        } else {
-            buy_price * (1.0 + (depth * self.config.hook_sell_level / 100.0) / 100.0)
-        };
+        let sell_price = self.sell_price(buy_price);
         
         if current_price >= sell_price {
             return HookSignal::PlaceSell {
@@ -436,26 +696,354 @@ impl HookStrategy {
             };
         }
         
+        // Стоп безубытка: после роста на trigger_pct позиция не должна уйти в минус
+        if let Some(breakeven) = self.config.breakeven
+            && self.state.breakeven_stop.update(&breakeven, buy_price, current_price, self.stop_widen_pct)
+        {
+            let price = tick.best_bid.unwrap_or(current_price);
+            return self.forced_exit(price);
+        }
+        
+        // TimeStop: позиция не отскочила вовремя
+        if let (Some(time_stop), Some(opened_at)) = (self.config.time_stop, self.state.position_opened_at)
+            && time_stop.expired(opened_at, tick.timestamp)
+        {
+            let price = time_stop.exit_price(current_price, tick.best_bid, sell_price);
+            return self.forced_exit(price);
+        }
+        
         HookSignal::NoAction
     }
     
-    fn can_detect_again(&self, now: DateTime<Utc>) -> bool {
-        if let Some(detection_time) = self.state.strike_detection_time {
-            let elapsed = (now - detection_time).num_milliseconds();
-            elapsed >= self.config.hook_time_frame.num_milliseconds()
+    /// TimeStop или стоп: выход выставляется один раз, дальше ждём его фила
+    fn forced_exit(&mut self, price: f64) -> HookSignal {
+        if let Err(err) = self.enter(Phase::Exiting, "forced_exit") {
+            return HookSignal::Error(err.into());
+        }
+        self.state.forced_exit_price = Some(price);
+        HookSignal::PlaceSell {
+            price,
+            size: self.state.position_size,
+        }
+    }
+    
+    fn time_stop_deadline(&self) -> Option<DateTime<Utc>> {
+        self.config.time_stop?.deadline(self.state.position_opened_at?)
+    }
+    
+    fn sell_price(&self, buy_price: f64) -> f64 {
+        let depth = self.state.strike_depth;
+        if self.config.hook_sell_fixed {
+            let min_price = self.state.strike_min_price;
+            min_price * (1.0 + (depth * self.config.hook_sell_level / 100.0) / 100.0)
         } else {
-            true
+            buy_price * (1.0 + (depth * self.config.hook_sell_level / 100.0) / 100.0)
+        }
+    }
+    
+    /// Таймер рантайма: перевзведение по времени и TimeStop срабатывают и в тихом рынке.
+    /// Цена берётся с последнего тика окна
+    pub fn on_timer(&mut self, now: DateTime<Utc>) -> HookSignal {
+        let Some(&(_, last_price)) = self.state.price_window.back() else {
+            return HookSignal::NoAction;
+        };
+        match self.state.phase {
+            Phase::OrderResting if self.state.cancel_pending || self.rearm_due(now, last_price) => self.cancel_entry(),
+            Phase::Detected if self.state.rollback_deadline.is_some_and(|deadline| now > deadline) => {
+                self.skip_entry(now);
+                HookSignal::NoAction
+            }
+            Phase::Idle | Phase::Detected if self.rearm_due(now, last_price) => {
+                self.reset_detection();
+                HookSignal::NoAction
+            }
+            Phase::Positioned => match (self.config.time_stop, self.state.position_opened_at, self.state.buy_price) {
+                (Some(time_stop), Some(opened_at), Some(buy_price)) if time_stop.expired(opened_at, now) => {
+                    let price = time_stop.exit_price(last_price, None, self.sell_price(buy_price));
+                    self.forced_exit(price)
+                }
+                _ => HookSignal::NoAction,
+            },
+            _ => HookSignal::NoAction,
+        }
+    }
+    
+    /// Политика перевзведения (кроме TimeFrame, где детект просто повторяется) сбрасывает детект
+    fn rearm_due(&self, now: DateTime<Utc>, current_price: f64) -> bool {
+        self.state.strike_detected
+            && self.config.hook_rearm_policy != HookRearmPolicy::TimeFrame
+            && self.can_detect_again(now, current_price)
+    }
+    
+    fn can_detect_again(&self, now: DateTime<Utc>, current_price: f64) -> bool {
+        let detection_time = match self.state.strike_detection_time {
+            Some(t) => t,
+            None => return true,
+        };
+        
+        match self.config.hook_rearm_policy {
+            HookRearmPolicy::TimeFrame => {
+                let elapsed = (now - detection_time).num_milliseconds();
+                elapsed >= self.config.hook_time_frame.num_milliseconds()
+            }
+            HookRearmPolicy::AfterSeconds(_) => {
+                self.rearm_deadline().is_none_or(|deadline| now >= deadline)
+            }
+            HookRearmPolicy::PriceRecovery(_) => {
+                self.recovery_price().is_none_or(|price| current_price >= price)
+            }
+            HookRearmPolicy::Manual => false,
+        }
+    }
+    
+    /// Момент перевзведения для временных политик
+    fn rearm_deadline(&self) -> Option<DateTime<Utc>> {
+        let detection_time = self.state.strike_detection_time?;
+        match self.config.hook_rearm_policy {
+            HookRearmPolicy::TimeFrame => Some(detection_time + self.config.hook_time_frame),
+            HookRearmPolicy::AfterSeconds(secs) => {
+                let anchor = match self.state.last_sell_time {
+                    Some(sell_time) if sell_time > detection_time => sell_time,
+                    _ => detection_time,
+                };
+                Some(anchor + Duration::milliseconds((secs * 1000.0) as i64))
+            }
+            _ => None,
+        }
+    }
+    
+    /// Цена перевзведения для HookRearmPolicy::PriceRecovery
+    fn recovery_price(&self) -> Option<f64> {
+        match self.config.hook_rearm_policy {
+            HookRearmPolicy::PriceRecovery(pct) if self.state.strike_detected => {
+                let min = self.state.strike_min_price;
+                let max = self.state.strike_max_price;
+                Some(min + (max - min) * pct / 100.0)
+            }
+            _ => None,
+        }
+    }
+    
+    /// Ветка требует состояния, которого нет (например, снимок восстановлен не целиком)
+    fn missing(field: &'static str, context: &'static str) -> HookSignal {
+        HookSignal::Error(ExecutionError::MissingState { strategy: "Hook", field, context }.into())
+    }
+    
+    /// Переход фазы; недопустимый не меняет состояние
+    fn enter(&mut self, to: Phase, context: &'static str) -> Result<(), ExecutionError> {
+        self.state.phase.advance(to, "Hook", context)
+    }
+    
+    /// Перевзведение при стоящем buy: детект и коридор сбрасываются, buy снимается.
+    /// Пока биржа не подтвердила buy (`on_buy_placed`), отмена ждёт его id: buy стоит
+    /// без перестановок, а отмена уходит на первом тике или таймере после подтверждения
+    fn cancel_entry(&mut self) -> HookSignal {
+        let Some(order_id) = self.state.active_order_id else {
+            if !self.state.cancel_pending {
+                log::debug!("Hook rearm: buy not acked yet, cancel pending");
+                self.state.cancel_pending = true;
+            }
+            return HookSignal::NoAction;
+        };
+        if let Err(err) = self.enter(Phase::Idle, "cancel_entry") {
+            return HookSignal::Error(err.into());
+        }
+        self.state.active_order_id = None;
+        self.state.cancel_pending = false;
+        self.reset_detection();
+        HookSignal::CancelOrder { order_id }
+    }
+    
+    /// Сброс детекта и коридора (не трогает ордер и открытую позицию)
+    fn reset_detection(&mut self) {
+        if self.state.phase == Phase::Detected {
+            self.state.phase = Phase::Idle;
+        }
+        self.state.strike_detected = false;
+        self.state.strike_detection_time = None;
+        self.state.strike_depth = 0.0;
+        self.state.strike_min_price = 0.0;
+        self.state.strike_max_price = 0.0;
+        self.state.strike_rollback_price = None;
+        self.state.rollback_deadline = None;
+        self.state.deltas_at_detection = None;
+        self.state.corridor_upper = None;
+        self.state.corridor_lower = None;
+        self.state.initial_buy_price = None;
+    }
+    
+    /// Ручное перевзведение детекта (HookRearmPolicy::Manual или из дашборда).
+    /// Игнорируется пока стоит buy или открыта позиция.
+    pub fn rearm(&mut self) -> bool {
+        if !matches!(self.state.phase, Phase::Idle | Phase::Detected) {
+            return false;
+        }
+        self.reset_detection();
+        self.state.active_order_id = None;
+        true
+    }
+    
+    /// Buy принят под `order_id`: по нему снимается стоящий buy при перевзведении.
+    /// Перестановка приходит с новым id; вне OrderResting id не нужен
+    pub fn on_buy_placed(&mut self, order_id: u64) {
+        if self.state.phase == Phase::OrderResting {
+            self.state.active_order_id = Some(order_id);
+        }
+    }
+    
+    /// Buy не ушёл на биржу: коридор снимается, детект остаётся зафиксированным до
+    /// перевзведения по политике, чтобы не войти в тот же прострел позже
+    pub fn on_buy_declined(&mut self) -> Result<(), ExecutionError> {
+        if self.state.phase != Phase::OrderResting {
+            return Err(self.state.phase.rejection(Phase::Idle, "Hook", "on_buy_declined"));
+        }
+        self.enter(Phase::Idle, "on_buy_declined")?;
+        self.state.corridor_upper = None;
+        self.state.corridor_lower = None;
+        self.state.initial_buy_price = None;
+        self.state.active_order_id = None;
+        self.state.cancel_pending = false;
+        Ok(())
+    }
+    
+    /// Готова ли стратегия к новому детекту на момент `now`
+    pub fn is_armed(&self, now: DateTime<Utc>) -> bool {
+        if !matches!(self.state.phase, Phase::Idle | Phase::Detected) {
+            return false;
+        }
+        let last_price = self.state.price_window.back().map(|(_, p)| *p).unwrap_or(0.0);
+        !self.state.strike_detected || self.can_detect_again(now, last_price)
+    }
+    
+    /// Снимок состояния перевзведения для дашборда
+    pub fn rearm_status(&self, now: DateTime<Utc>) -> HookRearmStatus {
+        HookRearmStatus {
+            policy: self.config.hook_rearm_policy,
+            armed: self.is_armed(now),
+            detected_at: self.state.strike_detection_time,
+            last_sell_at: self.state.last_sell_time,
+            corridor_active: self.state.corridor_upper.is_some(),
+            recovery_price: self.recovery_price(),
+            rearm_at: self.rearm_deadline(),
+        }
+    }
+    
+    /// Текущее состояние детекта, коридора и ордеров
+    pub fn view(&self) -> HookView {
+        let detected = self.state.strike_detected;
+        let corridor = match (self.state.corridor_upper, self.state.corridor_lower) {
+            (Some(upper), Some(lower)) => Some(CorridorBounds { upper, lower }),
+            _ => None,
+        };
+        
+        HookView {
+            detected,
+            detected_at: self.state.strike_detection_time,
+            detection_depth: detected.then_some(self.state.strike_depth),
+            strike_min_price: detected.then_some(self.state.strike_min_price),
+            strike_max_price: detected.then_some(self.state.strike_max_price),
+            rollback_price: self.state.strike_rollback_price,
+            rollback_wait_until: self.state.rollback_deadline,
+            corridor,
+            initial_buy_price: self.state.initial_buy_price,
+            phase: self.state.phase,
+            entry_price: self.state.buy_price,
+            position_size: self.state.position_size,
+            pending_orders: self.pending_orders(),
+            repeat_orders: self.state.repeat_orders.len(),
+            position_opened_at: self.state.position_opened_at,
+            time_stop_at: self.time_stop_deadline(),
+            stop_price: self.state.breakeven_stop.stop_price,
+        }
+    }
+    
+    /// Детекту нужно хотя бы две цены в окне HookTimeFrame
+    pub fn warmup_requirements(&self) -> WarmupRequirements {
+        WarmupRequirements { min_ticks: 2, ..WarmupRequirements::default() }
+    }
+    
+    /// Time-in-force ордеров на стороне `side`
+    pub fn time_in_force(&self, side: Side) -> TimeInForce {
+        match side {
+            Side::Bid => self.config.hook_buy_tif,
+            Side::Ask => self.config.hook_sell_tif,
+        }
+    }
+    
+    /// Ордера, которые стратегия держит на рынке
+    pub fn pending_orders(&self) -> Vec<OrderIntent> {
+        match (self.state.phase, self.state.buy_price, self.state.initial_buy_price) {
+            (Phase::Positioned | Phase::Exiting, Some(buy_price), _) => {
+                let price = self.state.forced_exit_price.unwrap_or_else(|| self.sell_price(buy_price));
+                vec![OrderIntent::sell(price, self.state.position_size)]
+            }
+            (Phase::OrderResting, _, Some(price)) => vec![OrderIntent::buy(price, self.calculate_order_size())],
+            _ => Vec::new(),
+        }
+    }
+    
+    /// Поток символа устарел (`Some(%)` расширения стопов) или вернулся (`None`)
+    pub fn set_stop_widening(&mut self, widen_pct: Option<f64>) {
+        self.stop_widen_pct = widen_pct.unwrap_or(0.0);
+    }
+    
+    /// Снимок внутреннего состояния (детект, коридор, позиция, повторы)
+    pub fn snapshot(&self) -> HookState {
+        self.state.clone()
+    }
+    
+    /// Восстановление состояния из снимка (после рестарта)
+    pub fn restore(&mut self, mut state: HookState) {
+        // Снимок без фазы (до её появления): стоящий buy тогда не отслеживался,
+        // поэтому коридор без позиции восстанавливается как детект без входа
+        if state.phase == Phase::Idle {
+            state.phase = match (state.buy_price, state.forced_exit_price) {
+                (Some(_), Some(_)) => Phase::Exiting,
+                (Some(_), None) => Phase::Positioned,
+                _ if state.strike_detected => Phase::Detected,
+                _ => Phase::Idle,
+            };
         }
+        self.state = state;
     }
     
-    pub fn on_buy_filled(&mut self, price: f64, size: f64) {
+    /// Фил без выставленного buy - ошибка, состояние не меняется;
+    /// повторный фил (частичное исполнение) добирает позицию
+    pub fn on_buy_filled(&mut self, price: f64, size: f64) -> Result<(), ExecutionError> {
+        let was = self.state.phase;
+        self.enter(Phase::Positioned, "on_buy_filled")?;
+        // Buy исполнился раньше отмены - снимать больше нечего
+        self.state.cancel_pending = false;
+        if was == Phase::Positioned {
+            let total = self.state.position_size + size;
+            let entry = self.state.buy_price.unwrap_or(price);
+            self.state.buy_price = Some((entry * self.state.position_size + price * size) / total);
+            self.state.position_size = total;
+            return Ok(());
+        }
         self.state.buy_price = Some(price);
         self.state.position_size = size;
-        self.state.active_order_id = Some(0); // TODO: реальный ID
+        self.state.breakeven_stop = BreakevenStop::default();
+        self.state.position_opened_at = Some(self.state.price_window.back().map_or_else(Utc::now, |(t, _)| *t));
+        self.state.forced_exit_price = None;
+        Ok(())
     }
     
-    pub fn on_sell_filled(&mut self) {
-        let buy_price = self.state.buy_price.unwrap();
+    /// Фил продажи без открытой позиции - ошибка: состояние не меняется.
+    /// Частичный фил уменьшает позицию, фаза остаётся
+    pub fn on_sell_filled(&mut self, size: f64) -> Result<(), ExecutionError> {
+        if !self.state.phase.has_position() {
+            return Err(self.state.phase.rejection(Phase::Idle, "Hook", "on_sell_filled"));
+        }
+        let Some(buy_price) = self.state.buy_price else {
+            return Err(ExecutionError::MissingState { strategy: "Hook", field: "buy_price", context: "on_sell_filled" });
+        };
+        let remaining = self.state.position_size - size;
+        if remaining > self.state.position_size * 1e-9 {
+            self.state.position_size = remaining;
+            return Ok(());
+        }
+        self.enter(Phase::Idle, "on_sell_filled")?;
         
         // Проверяем повторный ордер
         if self.config.hook_repeat_after_sell {
@@ -470,7 +1058,12 @@ impl HookStrategy {
         self.state.buy_price = None;
         self.state.position_size = 0.0;
         self.state.active_order_id = None;
-        // Не сбрасываем коридор - он остается активным
+        self.state.position_opened_at = None;
+        self.state.breakeven_stop = BreakevenStop::default();
+        self.state.forced_exit_price = None;
+        self.state.last_sell_time = self.state.price_window.back().map(|(t, _)| *t);
+        // Коридор сбрасывается по HookRearmPolicy (см. can_detect_again)
+        Ok(())
     }
 }
 
@@ -478,12 +1071,15 @@ impl HookStrategy {
 mod tests {
     use super::*;
     use crate::backtest::market::{TradeTick, TradeSide};
+    use crate::base_classes::symbol::Symbol;
+    use crate::strategy::moon_strategies::exits::TimeStopExit;
     use crate::strategy::moon_strategies::mshot::Deltas;
     use chrono::Utc;
 
     #[test]
     fn test_hook_detect_drop() {
         let config = HookConfig {
+            hook_roll_back_wait: 0,
             hook_detect_depth: 5.0, // 5% падение
             hook_time_frame: chrono::Duration::seconds(2),
             ..Default::default()
@@ -496,7 +1092,7 @@ mod tests {
         let ticks = vec![
             TradeTick {
                 timestamp: now,
-                symbol: "BTC_USDT".to_string(),
+                symbol: Symbol::new("BTC_USDT"),
                 price: 100.0,
                 volume: 1.0,
                 side: TradeSide::Buy,
@@ -506,7 +1102,7 @@ mod tests {
             },
             TradeTick {
                 timestamp: now + chrono::Duration::milliseconds(500),
-                symbol: "BTC_USDT".to_string(),
+                symbol: Symbol::new("BTC_USDT"),
                 price: 95.0, // Падение 5%
                 volume: 10.0,
                 side: TradeSide::Sell,
@@ -534,6 +1130,7 @@ mod tests {
         assert!(config.hook_detect_depth > 0.0);
         assert!(config.order_size > 0.0);
         assert!(config.hook_time_frame.num_seconds() > 0);
+        assert_eq!(config.hook_roll_back_wait, 100);
     }
     
     #[test]
@@ -544,4 +1141,283 @@ mod tests {
         assert_eq!(strategy.state.buy_price, None);
         assert_eq!(strategy.state.position_size, 0.0);
     }
+    
+    fn tick_at(base: chrono::DateTime<Utc>, offset_ms: i64, price: f64) -> TradeTick {
+        TradeTick {
+            timestamp: base + chrono::Duration::milliseconds(offset_ms),
+            symbol: Symbol::new("BTC_USDT"),
+            price,
+            volume: 1000.0,
+            side: TradeSide::Sell,
+            trade_id: offset_ms.to_string(),
+            best_bid: Some(price),
+            best_ask: Some(price),
+        }
+    }
+    
+    /// Детект с выставленным buy, который биржа приняла под id 1
+    fn detected_strategy(policy: HookRearmPolicy) -> (HookStrategy, chrono::DateTime<Utc>) {
+        let (mut strategy, now) = unacked_strategy(policy);
+        strategy.on_buy_placed(1);
+        (strategy, now)
+    }
+    
+    /// Детект с buy, подтверждения которого ещё не было
+    fn unacked_strategy(policy: HookRearmPolicy) -> (HookStrategy, chrono::DateTime<Utc>) {
+        let config = HookConfig {
+            hook_roll_back_wait: 0,
+            hook_rearm_policy: policy,
+            buy_order_reduce: 0,
+            ..Default::default()
+        };
+        let mut strategy = HookStrategy::new(config);
+        let now = Utc::now();
+        let deltas = Deltas::default();
+        strategy.on_tick(&tick_at(now, 0, 100.0), &deltas);
+        let signal = strategy.on_tick(&tick_at(now, 500, 90.0), &deltas);
+        assert!(matches!(signal, HookSignal::PlaceBuy { .. }));
+        (strategy, now)
+    }
+    
+    #[test]
+    fn test_flow_filter_delays_detect_until_buyers_return() {
+        let config = HookConfig {
+            hook_roll_back_wait: 0,
+            buy_order_reduce: 0,
+            flow_filter: Some(FlowFilterConfig { min_imbalance: 0.0, ..FlowFilterConfig::default() }),
+            ..Default::default()
+        };
+        let mut strategy = HookStrategy::new(config);
+        let now = Utc::now();
+        let deltas = Deltas::default();
+        strategy.on_tick(&tick_at(now, 0, 100.0), &deltas);
+        // Глубина 10%, но в окне одни продажи
+        assert!(matches!(strategy.on_tick(&tick_at(now, 500, 90.0), &deltas), HookSignal::NoAction));
+        assert!(!strategy.view().detected);
+        
+        let buy = TradeTick { side: TradeSide::Buy, volume: 2500.0, ..tick_at(now, 800, 90.5) };
+        let signal = strategy.on_tick(&buy, &deltas);
+        assert!(matches!(signal, HookSignal::PlaceBuy { .. }), "{:?}", signal);
+    }
+    
+    #[test]
+    fn test_corridor_lower_snaps_to_volume_node() {
+        let config = HookConfig {
+            hook_roll_back_wait: 0,
+            buy_order_reduce: 0,
+            volume_profile: Some(VolumeProfileConfig { lookback_ms: 60_000, bucket_pct: 0.2, node_ratio: 1.5 }),
+            ..Default::default()
+        };
+        let mut strategy = HookStrategy::new(config);
+        let now = Utc::now();
+        let deltas = Deltas::default();
+        // Рынок долго торговался у 91.5
+        for ms in [0, 500, 1_000] {
+            strategy.on_tick(&tick_at(now, ms, 91.5), &deltas);
+        }
+        strategy.on_tick(&tick_at(now, 5_000, 100.0), &deltas);
+        let signal = strategy.on_tick(&tick_at(now, 5_500, 90.0), &deltas);
+        assert!(matches!(signal, HookSignal::PlaceBuy { price, .. } if (price - 92.5).abs() < 1e-9), "{:?}", signal);
+        
+        // Без профиля нижняя граница была бы на минимуме 90
+        let corridor = strategy.view().corridor.unwrap();
+        assert!((corridor.lower - 91.5).abs() / 91.5 < 0.002, "{:?}", corridor);
+        assert_eq!(corridor.upper, 100.0);
+    }
+    
+    #[test]
+    fn test_hook_rearm_manual() {
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::Manual);
+        assert!(!strategy.is_armed(now + chrono::Duration::seconds(60)));
+        
+        // Пока buy стоит, перевзведение его не бросает
+        assert!(!strategy.rearm());
+        strategy.on_buy_declined().unwrap();
+        assert!(strategy.rearm());
+        assert!(strategy.state.corridor_upper.is_none());
+        assert!(strategy.is_armed(now));
+    }
+    
+    #[test]
+    fn test_hook_rearm_after_seconds_resets_corridor() {
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::AfterSeconds(10.0));
+        let deltas = Deltas::default();
+        
+        // До истечения таймера коридор сохраняется
+        strategy.on_tick(&tick_at(now, 5_000, 90.0), &deltas);
+        assert!(strategy.state.corridor_upper.is_some());
+        assert!(!strategy.rearm_status(now + chrono::Duration::seconds(5)).armed);
+        
+        // После истечения - детект сброшен, стоящий buy снят по его id
+        let signal = strategy.on_tick(&tick_at(now, 11_000, 90.0), &deltas);
+        assert!(matches!(signal, HookSignal::CancelOrder { order_id: 1 }), "{:?}", signal);
+        assert_eq!(strategy.view().phase, Phase::Idle);
+        assert!(!strategy.state.strike_detected);
+        assert!(strategy.state.corridor_upper.is_none());
+    }
+    
+    #[test]
+    fn test_hook_rearm_waits_for_buy_order_id() {
+        let (mut strategy, now) = unacked_strategy(HookRearmPolicy::AfterSeconds(10.0));
+        let deltas = Deltas::default();
+        
+        // Биржа ещё не подтвердила buy: отмена ждёт id, buy остаётся стоять
+        let signal = strategy.on_tick(&tick_at(now, 11_000, 90.0), &deltas);
+        assert!(matches!(signal, HookSignal::NoAction), "{:?}", signal);
+        assert!(matches!(strategy.on_timer(now + chrono::Duration::milliseconds(11_200)), HookSignal::NoAction));
+        assert_eq!(strategy.view().phase, Phase::OrderResting);
+        assert!(strategy.state.cancel_pending);
+        assert!(strategy.state.strike_detected);
+        
+        strategy.on_buy_placed(42);
+        let signal = strategy.on_timer(now + chrono::Duration::milliseconds(11_500));
+        assert!(matches!(signal, HookSignal::CancelOrder { order_id: 42 }), "{:?}", signal);
+        assert_eq!(strategy.view().phase, Phase::Idle);
+        assert!(!strategy.state.cancel_pending);
+    }
+    
+    #[test]
+    fn test_pending_cancel_dropped_when_buy_fills_first() {
+        let (mut strategy, now) = unacked_strategy(HookRearmPolicy::AfterSeconds(10.0));
+        let deltas = Deltas::default();
+        assert!(matches!(strategy.on_tick(&tick_at(now, 11_000, 90.0), &deltas), HookSignal::NoAction));
+        assert!(strategy.state.cancel_pending);
+        
+        strategy.on_buy_filled(90.0, 1.0).unwrap();
+        assert!(!strategy.state.cancel_pending);
+        assert_eq!(strategy.view().phase, Phase::Positioned);
+    }
+    
+    #[test]
+    fn test_hook_rearm_price_recovery() {
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::PriceRecovery(50.0));
+        let deltas = Deltas::default();
+        
+        let status = strategy.rearm_status(now);
+        assert!((status.recovery_price.unwrap() - 95.0).abs() < 1e-9);
+        
+        strategy.on_tick(&tick_at(now, 5_000, 93.0), &deltas);
+        assert!(strategy.state.strike_detected);
+        
+        strategy.on_tick(&tick_at(now, 6_000, 96.0), &deltas);
+        assert!(!strategy.state.strike_detected);
+    }
+    
+    #[test]
+    fn test_hook_rearm_blocked_with_position() {
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::Manual);
+        strategy.on_buy_filled(90.0, 1.0).unwrap();
+        assert!(!strategy.rearm());
+        assert!(!strategy.is_armed(now));
+        
+        strategy.on_sell_filled(1.0).unwrap();
+        assert!(strategy.state.last_sell_time.is_some());
+        assert!(strategy.rearm());
+    }
+    
+    #[test]
+    fn test_hook_time_stop_exits_stale_position() {
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::Manual);
+        strategy.config.time_stop = Some(TimeStopConfig {
+            max_age_ms: 60_000,
+            exit: TimeStopExit::Market,
+        });
+        let deltas = Deltas::default();
+        strategy.on_buy_filled(90.0, 1.0).unwrap();
+        let view = strategy.view();
+        assert_eq!(view.time_stop_at, Some(now + chrono::Duration::milliseconds(60_500)));
+        
+        assert!(matches!(strategy.on_tick(&tick_at(now, 30_000, 91.0), &deltas), HookSignal::NoAction));
+        let signal = strategy.on_tick(&tick_at(now, 61_000, 91.0), &deltas);
+        assert!(matches!(signal, HookSignal::PlaceSell { price, size } if price == 91.0 && size == 1.0), "{:?}", signal);
+        // Выход выставлен один раз и виден как ордер стратегии
+        assert!(matches!(strategy.on_tick(&tick_at(now, 62_000, 97.0), &deltas), HookSignal::NoAction));
+        assert_eq!(strategy.pending_orders(), vec![OrderIntent::sell(91.0, 1.0)]);
+        
+        strategy.on_sell_filled(1.0).unwrap();
+        assert!(strategy.view().time_stop_at.is_none());
+    }
+    
+    #[test]
+    fn test_rollback_wait_gates_entry() {
+        let config = HookConfig { hook_roll_back_wait: 300, buy_order_reduce: 0, ..Default::default() };
+        let deltas = Deltas::default();
+        let now = Utc::now();
+        
+        // Откат до RollBack (100 - 10% * 33% = 96.7) вовремя - buy
+        let mut strategy = HookStrategy::new(config.clone());
+        strategy.on_tick(&tick_at(now, 0, 100.0), &deltas);
+        assert!(matches!(strategy.on_tick(&tick_at(now, 500, 90.0), &deltas), HookSignal::DetectHook { .. }));
+        let view = strategy.view();
+        assert_eq!((view.phase, view.pending_orders.len()), (Phase::Detected, 0));
+        assert_eq!(view.rollback_wait_until, Some(now + chrono::Duration::milliseconds(800)));
+        assert!(matches!(strategy.on_tick(&tick_at(now, 600, 95.0), &deltas), HookSignal::NoAction));
+        let signal = strategy.on_tick(&tick_at(now, 700, 97.0), &deltas);
+        assert!(matches!(signal, HookSignal::PlaceBuy { .. }), "{:?}", signal);
+        assert_eq!(strategy.view().phase, Phase::OrderResting);
+        assert!(strategy.view().rollback_wait_until.is_none());
+        
+        // Отката нет - вход пропущен, тот же прострел не торгуется
+        let mut strategy = HookStrategy::new(config);
+        strategy.on_tick(&tick_at(now, 0, 100.0), &deltas);
+        strategy.on_tick(&tick_at(now, 500, 90.0), &deltas);
+        assert!(matches!(strategy.on_tick(&tick_at(now, 900, 91.0), &deltas), HookSignal::NoAction));
+        assert!(matches!(strategy.on_tick(&tick_at(now, 1_000, 97.0), &deltas), HookSignal::NoAction));
+        let view = strategy.view();
+        assert_eq!(view.phase, Phase::Idle);
+        assert!(view.detected && view.pending_orders.is_empty());
+        
+        // В тихом рынке ожидание снимает таймер
+        let mut strategy = HookStrategy::new(HookConfig { hook_roll_back_wait: 300, ..Default::default() });
+        strategy.on_tick(&tick_at(now, 0, 100.0), &deltas);
+        strategy.on_tick(&tick_at(now, 500, 90.0), &deltas);
+        strategy.on_timer(now + chrono::Duration::seconds(1));
+        assert_eq!(strategy.view().phase, Phase::Idle);
+    }
+    
+    #[test]
+    fn test_timer_rearms_and_exits_in_quiet_market() {
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::AfterSeconds(10.0));
+        assert!(matches!(strategy.on_timer(now + chrono::Duration::seconds(5)), HookSignal::NoAction));
+        assert_eq!(strategy.view().phase, Phase::OrderResting);
+        strategy.on_buy_placed(7);
+        // Тиков нет, но стоящий buy снимается по политике
+        let signal = strategy.on_timer(now + chrono::Duration::seconds(11));
+        assert!(matches!(signal, HookSignal::CancelOrder { order_id: 7 }), "{:?}", signal);
+        assert!(!strategy.view().detected);
+        
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::Manual);
+        strategy.config.time_stop = Some(TimeStopConfig { max_age_ms: 60_000, exit: TimeStopExit::Market });
+        strategy.on_buy_filled(90.0, 1.0).unwrap();
+        assert!(matches!(strategy.on_timer(now + chrono::Duration::seconds(30)), HookSignal::NoAction));
+        let signal = strategy.on_timer(now + chrono::Duration::seconds(61));
+        assert!(matches!(signal, HookSignal::PlaceSell { price, size } if price == 90.0 && size == 1.0), "{:?}", signal);
+        assert_eq!(strategy.view().phase, Phase::Exiting);
+    }
+    
+    #[test]
+    fn test_fill_sequences_follow_phases() {
+        let (mut strategy, now) = detected_strategy(HookRearmPolicy::Manual);
+        let deltas = Deltas::default();
+        assert_eq!(strategy.view().phase, Phase::OrderResting);
+        
+        // Продажа раньше покупки: ошибка, buy продолжает стоять в коридоре
+        assert!(matches!(strategy.on_sell_filled(1.0), Err(ExecutionError::IllegalTransition { from: "OrderResting", .. })));
+        let signal = strategy.on_tick(&tick_at(now, 1_000, 89.0), &deltas);
+        assert!(matches!(signal, HookSignal::ReplaceBuy { new_price } if (new_price - 89.1).abs() < 1e-9), "{:?}", signal);
+        assert_eq!(strategy.pending_orders()[0].price, strategy.view().initial_buy_price.unwrap());
+        
+        strategy.on_buy_filled(89.0, 0.5).unwrap();
+        strategy.on_buy_filled(89.2, 0.5).unwrap();
+        assert!((strategy.view().entry_price.unwrap() - 89.1).abs() < 1e-9);
+        assert!(strategy.on_buy_declined().is_err());
+        
+        strategy.on_sell_filled(0.4).unwrap();
+        assert!((strategy.view().position_size - 0.6).abs() < 1e-9);
+        strategy.on_sell_filled(0.6).unwrap();
+        assert_eq!(strategy.view().phase, Phase::Idle);
+        assert!(strategy.on_sell_filled(1.0).is_err());
+        assert!(strategy.on_buy_filled(90.0, 1.0).is_err());
+        assert_eq!(strategy.view().entry_price, None);
+    }
 }
@@ -0,0 +1,36 @@
+//! Read-model структуры для инспекции состояния стратегий
+//! Используются дашбордом и тестами вместо доступа к приватным полям
+
+use crate::base_classes::types::Side;
+use serde::Serialize;
+
+/// Ордер, который стратегия считает выставленным (или собирается выставить)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct OrderIntent {
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+}
+
+impl OrderIntent {
+    pub fn buy(price: f64, size: f64) -> Self {
+        Self { side: Side::Bid, price, size }
+    }
+
+    pub fn sell(price: f64, size: f64) -> Self {
+        Self { side: Side::Ask, price, size }
+    }
+}
+
+/// Границы ценового коридора
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CorridorBounds {
+    pub upper: f64,
+    pub lower: f64,
+}
+
+impl CorridorBounds {
+    pub fn contains(&self, price: f64) -> bool {
+        price >= self.lower && price <= self.upper
+    }
+}
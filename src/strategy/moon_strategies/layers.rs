@@ -0,0 +1,347 @@
+//! Слои конфигурации стратегии: база плюс переопределения по группам и символам
+//!
+//! Вместо копии всего блока конфига на каждый символ задаётся база и только
+//! отличающиеся поля:
+//!
+//! ```yaml
+//! base: { hook_detect_depth: 5.0, order_size: 50.0 }
+//! groups:
+//!   - name: lowcaps
+//!     symbols: [PEPE_USDT, WIF_USDT]
+//!     overrides: { hook_detect_depth: 8.0 }
+//! symbols:
+//!   PEPE_USDT: { hook_detect_depth: 10.0 }
+//! ```
+//!
+//! Порядок применения (каждый следующий слой сильнее): `Default` конфига, `base`, группы в
+//! порядке объявления, переопределение символа. Вложенные объекты сливаются по полям,
+//! `null` сбрасывает поле. Все слои проверяются при загрузке (`validate`): опечатка в
+//! имени поля или недопустимое значение - ошибка старта, а не сюрприз на первом тике.
+//!
+//! Строка, начинающаяся с `=`, - выражение от свойств символа (см. `expr`):
+//! `order_size: "= min_notional * 3"`. Выражения считаются при создании стратегий и
+//! заново по расписанию (`ComputedConfigs::refresh`), когда свойства символа меняются.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+
+use crate::base_classes::symbol::Symbol;
+
+use super::expr::{Expr, SymbolProperties};
+use super::{HookConfig, MShotConfig, MStrikeConfig};
+
+/// Конфиг, который можно собирать из слоёв
+pub trait LayeredConfig: Default + Serialize + DeserializeOwned {
+    /// Проверка собранного конфига
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl LayeredConfig for HookConfig {
+    fn validate(&self) -> Result<()> {
+        HookConfig::validate(self)
+    }
+}
+
+impl LayeredConfig for MShotConfig {}
+
+impl LayeredConfig for MStrikeConfig {
+    fn validate(&self) -> Result<()> {
+        MStrikeConfig::validate(self)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OverrideGroup {
+    pub name: String,
+    pub symbols: Vec<Symbol>,
+    pub overrides: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigLayers {
+    #[serde(default)]
+    pub base: Map<String, Value>,
+    #[serde(default)]
+    pub groups: Vec<OverrideGroup>,
+    #[serde(default)]
+    pub symbols: BTreeMap<Symbol, Map<String, Value>>,
+}
+
+impl ConfigLayers {
+    /// Собирает конфиг символа без свойств: выражения в слоях дадут ошибку
+    pub fn resolve<T: LayeredConfig>(&self, symbol: Symbol) -> Result<T> {
+        self.resolve_with(symbol, &SymbolProperties::default())
+    }
+
+    /// Собирает конфиг символа, вычисляя выражения по его свойствам
+    pub fn resolve_with<T: LayeredConfig>(&self, symbol: Symbol, props: &SymbolProperties) -> Result<T> {
+        let (config, _) = self.resolve_value(symbol, props)?;
+        Ok(config)
+    }
+
+    fn resolve_value<T: LayeredConfig>(&self, symbol: Symbol, props: &SymbolProperties) -> Result<(T, Value)> {
+        let mut merged = self.merged::<T>(Some(symbol))?;
+        evaluate(&mut merged, props, "").with_context(|| format!("config for {}", symbol))?;
+        let config: T = serde_json::from_value(merged.clone()).with_context(|| format!("config for {}", symbol))?;
+        config.validate().with_context(|| format!("config for {}", symbol))?;
+        Ok((config, merged))
+    }
+
+    /// Default конфига со слоями символа (None - только база), выражения не вычислены
+    fn merged<T: LayeredConfig>(&self, symbol: Option<Symbol>) -> Result<Value> {
+        let mut merged = serde_json::to_value(T::default()).context("serialize default config")?;
+        merge(&mut merged, &self.base);
+        if let Some(symbol) = symbol {
+            for group in self.groups.iter().filter(|g| g.symbols.contains(&symbol)) {
+                merge(&mut merged, &group.overrides);
+            }
+            if let Some(overrides) = self.symbols.get(&symbol) {
+                merge(&mut merged, overrides);
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Имена слоёв, которые действуют на символ, от слабого к сильному
+    pub fn layers_for(&self, symbol: Symbol) -> Vec<String> {
+        let mut layers = vec!["base".to_string()];
+        layers.extend(self.groups.iter().filter(|g| g.symbols.contains(&symbol)).map(|g| format!("group {}", g.name)));
+        if self.symbols.contains_key(&symbol) {
+            layers.push(format!("symbol {}", symbol));
+        }
+        layers
+    }
+
+    /// Проверяет имена полей и синтаксис выражений во всех слоях и собирает конфиг
+    /// каждого упомянутого символа. Значения выражений на этом этапе неизвестны, поэтому
+    /// конфиги с выражениями проверяются только на типы, остальные - целиком.
+    pub fn validate<T: LayeredConfig>(&self) -> Result<()> {
+        let default = serde_json::to_value(T::default()).context("serialize default config")?;
+        let Value::Object(known) = &default else {
+            bail!("layered config must serialize to an object");
+        };
+        check_keys(known, &self.base, "base")?;
+        for group in &self.groups {
+            if group.symbols.is_empty() {
+                bail!("override group {} lists no symbols", group.name);
+            }
+            check_keys(known, &group.overrides, &format!("group {}", group.name))?;
+        }
+        for (symbol, overrides) in &self.symbols {
+            check_keys(known, overrides, &format!("symbol {}", symbol))?;
+        }
+        // Базу без символа тоже собираем: её получат все символы без переопределений
+        let mentioned = self.groups.iter().flat_map(|g| g.symbols.iter()).chain(self.symbols.keys());
+        for symbol in std::iter::once(None).chain(mentioned.map(Some)) {
+            let name = symbol.map_or("base config".to_string(), |s| format!("config for {}", s));
+            let mut merged = self.merged::<T>(symbol.copied())?;
+            let computed = placeholders(&mut merged, &default, "").with_context(|| name.clone())?;
+            let config: T = serde_json::from_value(merged).with_context(|| name.clone())?;
+            if !computed {
+                config.validate().with_context(|| name.clone())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Конфиги символов с выражениями и их пересчёт по расписанию
+pub struct ComputedConfigs<T> {
+    layers: ConfigLayers,
+    refresh_ms: u64,
+    last_refresh_ms: Option<u64>,
+    /// Последний собранный конфиг символа (в виде JSON, для сравнения)
+    resolved: HashMap<Symbol, Value>,
+    _config: PhantomData<T>,
+}
+
+impl<T: LayeredConfig> ComputedConfigs<T> {
+    pub fn new(layers: ConfigLayers, refresh_ms: u64) -> Result<Self> {
+        layers.validate::<T>()?;
+        Ok(Self { layers, refresh_ms, last_refresh_ms: None, resolved: HashMap::new(), _config: PhantomData })
+    }
+
+    pub fn layers(&self) -> &ConfigLayers {
+        &self.layers
+    }
+
+    /// Конфиг для создания стратегии символа; запоминается для сравнения при пересчёте
+    pub fn config(&mut self, symbol: Symbol, props: &SymbolProperties) -> Result<T> {
+        let (config, value) = self.layers.resolve_value::<T>(symbol, props)?;
+        self.resolved.insert(symbol, value);
+        Ok(config)
+    }
+
+    /// Пересчитывает выражения символов, если прошло `refresh_ms`. Возвращает символы,
+    /// у которых конфиг изменился: их стратегии надо пересоздать. Если символ не
+    /// пересчитался (нет свойств, ошибка), у него остаётся прежний конфиг.
+    pub fn refresh(&mut self, now_ms: u64, props: impl Fn(Symbol) -> Option<SymbolProperties>) -> Vec<Symbol> {
+        if self.last_refresh_ms.is_some_and(|last| now_ms.saturating_sub(last) < self.refresh_ms) {
+            return Vec::new();
+        }
+        self.last_refresh_ms = Some(now_ms);
+        let mut changed = Vec::new();
+        for (&symbol, previous) in self.resolved.iter_mut() {
+            let Some(props) = props(symbol) else {
+                log::warn!("no properties for {}: keeping its computed config", symbol);
+                continue;
+            };
+            match self.layers.resolve_value::<T>(symbol, &props) {
+                Ok((_, value)) if value != *previous => {
+                    *previous = value;
+                    changed.push(symbol);
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("recomputing config for {} failed, keeping the old one: {:#}", symbol, e),
+            }
+        }
+        changed.sort_by_key(|s| s.to_string());
+        changed
+    }
+}
+
+/// Заменяет строки-выражения их значениями
+fn evaluate(value: &mut Value, props: &SymbolProperties, path: &str) -> Result<()> {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                evaluate(field, props, &join_path(path, key))?;
+            }
+        }
+        Value::String(text) => {
+            if let Some(expr) = text.trim().strip_prefix('=') {
+                let number = Expr::parse(expr)
+                    .and_then(|e| e.eval(props))
+                    .with_context(|| format!("{}: '{}'", path, text))?;
+                *value = Value::from(number);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Проверяет синтаксис выражений и подставляет вместо них значения по умолчанию.
+/// Возвращает true, если выражения были.
+fn placeholders(value: &mut Value, default: &Value, path: &str) -> Result<bool> {
+    let mut found = false;
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                let default = default.get(key.as_str()).unwrap_or(&Value::Null);
+                found |= placeholders(field, default, &join_path(path, key))?;
+            }
+        }
+        Value::String(text) => {
+            if let Some(expr) = text.trim().strip_prefix('=') {
+                Expr::parse(expr).with_context(|| format!("{}: '{}'", path, text))?;
+                *value = if default.is_number() { default.clone() } else { Value::from(1.0) };
+                found = true;
+            }
+        }
+        _ => {}
+    }
+    Ok(found)
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
+}
+
+fn check_keys(known: &Map<String, Value>, layer: &Map<String, Value>, name: &str) -> Result<()> {
+    for key in layer.keys() {
+        if !known.contains_key(key) {
+            return Err(anyhow!("{}: unknown config field '{}'", name, key));
+        }
+    }
+    Ok(())
+}
+
+fn merge(target: &mut Value, layer: &Map<String, Value>) {
+    let Value::Object(target) = target else {
+        return;
+    };
+    for (key, value) in layer {
+        match (target.get_mut(key), value) {
+            (Some(existing @ Value::Object(_)), Value::Object(nested)) => merge(existing, nested),
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYERS: &str = r#"
+base: { hook_detect_depth: 5.0, order_size: 50.0 }
+groups:
+  - name: lowcaps
+    symbols: [PEPE_USDT, WIF_USDT]
+    overrides: { hook_detect_depth: 8.0, hook_sell_level: 60.0 }
+symbols:
+  PEPE_USDT: { hook_detect_depth: 10.0 }
+"#;
+
+    #[test]
+    fn test_symbol_beats_group_beats_base() {
+        let layers: ConfigLayers = serde_yaml::from_str(LAYERS).unwrap();
+        layers.validate::<HookConfig>().unwrap();
+
+        let btc: HookConfig = layers.resolve(Symbol::new("BTC_USDT")).unwrap();
+        let wif: HookConfig = layers.resolve(Symbol::new("WIF_USDT")).unwrap();
+        let pepe: HookConfig = layers.resolve(Symbol::new("PEPE_USDT")).unwrap();
+        assert_eq!((btc.hook_detect_depth, btc.order_size, btc.hook_sell_level), (5.0, 50.0, 75.0));
+        assert_eq!((wif.hook_detect_depth, wif.hook_sell_level), (8.0, 60.0));
+        assert_eq!((pepe.hook_detect_depth, pepe.order_size, pepe.hook_sell_level), (10.0, 50.0, 60.0));
+        assert_eq!(layers.layers_for(Symbol::new("PEPE_USDT")), ["base", "group lowcaps", "symbol PEPE_USDT"]);
+    }
+
+    #[test]
+    fn test_validation_rejects_typos_and_bad_values() {
+        let typo: ConfigLayers = serde_yaml::from_str("symbols: { PEPE_USDT: { hook_detect_dept: 10.0 } }").unwrap();
+        let err = typo.validate::<HookConfig>().unwrap_err();
+        assert!(format!("{:#}", err).contains("hook_detect_dept"), "{:#}", err);
+
+        let negative: ConfigLayers = serde_yaml::from_str("symbols: { PEPE_USDT: { order_size: -1.0 } }").unwrap();
+        assert!(negative.validate::<HookConfig>().is_err());
+        assert!(negative.resolve::<HookConfig>(Symbol::new("BTC_USDT")).is_ok());
+
+        let wrong_type: ConfigLayers = serde_yaml::from_str("base: { hook_anti_pump: 3 }").unwrap();
+        assert!(wrong_type.validate::<HookConfig>().is_err());
+
+        let bad_expr: ConfigLayers = serde_yaml::from_str("base: { order_size: '= min_notional *' }").unwrap();
+        assert!(bad_expr.validate::<HookConfig>().is_err());
+    }
+
+    #[test]
+    fn test_computed_parameters_follow_symbol_properties() {
+        let layers: ConfigLayers = serde_yaml::from_str(
+            "base: { order_size: '= min_notional * 3', hook_detect_depth: '= max(3, atr_1h * 1.5)' }",
+        )
+        .unwrap();
+        let mut configs = ComputedConfigs::<HookConfig>::new(layers, 60_000).unwrap();
+        let (btc, eth) = (Symbol::new("BTC_USDT"), Symbol::new("ETH_USDT"));
+        let props = |atr: f64| SymbolProperties::new().with("min_notional", 5.0).with("atr_1h", atr);
+
+        let config = configs.config(btc, &props(4.0)).unwrap();
+        assert_eq!((config.order_size, config.hook_detect_depth), (15.0, 6.0));
+        configs.config(eth, &props(1.0)).unwrap();
+        assert!(configs.config(eth, &SymbolProperties::new()).is_err());
+
+        assert!(configs.refresh(0, |_| Some(props(1.0))).contains(&btc));
+        // Рано для нового пересчёта
+        assert!(configs.refresh(30_000, |_| Some(props(8.0))).is_empty());
+        assert_eq!(configs.refresh(60_000, |s| (s == btc).then(|| props(8.0))), [btc]);
+        assert_eq!(configs.refresh(120_000, |_| Some(props(8.0))), [eth]);
+        assert!(configs.refresh(180_000, |_| Some(props(8.0))).is_empty());
+    }
+}
@@ -8,12 +8,35 @@ pub mod spread;
 pub mod ema_filter;
 pub mod triggers;
 pub mod sessions;
+pub mod inspect;
+pub mod rolling;
+pub mod exits;
+pub mod entry_model;
+pub mod layers;
+pub mod expr;
+pub mod flow;
+pub mod volume_profile;
+pub mod capitulation;
+pub mod window;
+pub mod numeric;
+pub mod phase;
 
-pub use mshot::{MShotStrategy, MShotConfig, MShotSignal};
-pub use mstrike::{MStrikeStrategy, MStrikeConfig, MStrikeSignal, MStrikeDirection};
-pub use hook::{HookStrategy, HookConfig, HookSignal, HookDirection};
+pub use mshot::{MShotStrategy, MShotConfig, MShotSignal, MShotState, MShotView};
+pub use mstrike::{MStrikeStrategy, MStrikeConfig, MStrikeSignal, MStrikeDirection, MStrikeEmaSource, MStrikeState, MStrikeView};
+pub use hook::{HookStrategy, HookConfig, HookSignal, HookDirection, HookRearmPolicy, HookRearmStatus, HookState, HookView};
 pub use spread::{SpreadStrategy, SpreadConfig, SpreadSignal};
 pub use ema_filter::{EmaFilter, EmaFilterCondition};
 pub use triggers::{TriggerManager, TriggerKey};
 pub use sessions::{SessionManager, SessionState};
-
+pub use inspect::{OrderIntent, CorridorBounds};
+pub use rolling::RollingMinMax;
+pub use exits::{AskLevels, AskWallConfig, BreakevenConfig, BreakevenStop, TimeStopConfig, TimeStopExit, TimeStopPolicy};
+pub use entry_model::EntryModelConfig;
+pub use layers::{ComputedConfigs, ConfigLayers, LayeredConfig, OverrideGroup};
+pub use expr::{Expr, SymbolProperties};
+pub use flow::{Flow, FlowFilterConfig, OrderFlow};
+pub use volume_profile::{VolumeProfile, VolumeProfileConfig};
+pub use capitulation::CapitulationFilterConfig;
+pub use window::{Aggregate, Extremes, Measure, Sum, TimeWindow};
+pub use numeric::NumericError;
+pub use phase::Phase;
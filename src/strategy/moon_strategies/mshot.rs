@@ -1,10 +1,13 @@
 //! MShot стратегия - переставление buy ордеров в коридоре цен
 //! Ловит прострелы и автоматически переставляет ордер при движении цены
 
+use super::entry_model::EntryModelConfig;
+use super::inspect::OrderIntent;
+use super::window::TimeWindow;
 use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::WarmupRequirements;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MShotConfig {
@@ -50,6 +53,10 @@ pub struct MShotConfig {
     pub use_stop_loss: bool,           // Использовать стоп-лосс
     pub use_trailing: bool,            // Использовать трейлинг
     pub use_take_profit: bool,         // Использовать тейк-профит
+    
+    // ML-фильтр входов (None = входить по каждому детекту)
+    #[serde(default)]
+    pub entry_model: Option<EntryModelConfig>,
 }
 
 impl Default for MShotConfig {
@@ -82,11 +89,12 @@ impl Default for MShotConfig {
             use_stop_loss: false,
             use_trailing: false,
             use_take_profit: false,
+            entry_model: None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MShotState {
     active_buy_order: Option<BuyOrderState>,
     last_ask_price: Option<f64>,
@@ -102,10 +110,10 @@ pub struct MShotState {
     delta_btc_5m: f64,
     
     // История цен для расчета
-    price_history: VecDeque<(DateTime<Utc>, f64)>,
+    price_history: TimeWindow<f64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct BuyOrderState {
     price: f64,
     size: f64,
@@ -113,13 +121,23 @@ struct BuyOrderState {
     original_price: f64, // Цена до применения модификаторов
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct RepeatShotState {
     buy_price: f64,
     buy_time: DateTime<Utc>,
     active: bool,
 }
 
+/// Read-model состояния MShot для дашборда и тестов
+#[derive(Debug, Clone, Serialize)]
+pub struct MShotView {
+    pub active_buy_price: Option<f64>,
+    pub active_buy_placed_at: Option<DateTime<Utc>>,
+    pub last_ask_price: Option<f64>,
+    pub active_repeat_shots: usize,
+    pub pending_orders: Vec<OrderIntent>,
+}
+
 #[derive(Debug, Clone)]
 pub enum MShotSignal {
     PlaceBuy { price: f64, size: f64 },
@@ -150,7 +168,7 @@ impl MShotStrategy {
                 delta_market: 0.0,
                 delta_btc: 0.0,
                 delta_btc_5m: 0.0,
-                price_history: VecDeque::new(),
+                price_history: TimeWindow::new(),
             },
         }
     }
@@ -164,10 +182,8 @@ impl MShotStrategy {
         self.update_deltas(deltas);
         
         // Обновляем историю цен
-        self.state.price_history.push_back((now, current_price));
-        if self.state.price_history.len() > 1000 {
-            self.state.price_history.pop_front();
-        }
+        self.state.price_history.push(now, current_price);
+        self.state.price_history.evict_to_len(1000);
         
         // Определяем базовую цену (BID/ASK/Trade)
         let base_price = match self.config.mshot_use_price.as_str() {
@@ -366,6 +382,51 @@ impl MShotStrategy {
         None
     }
     
+    /// Дельты с включёнными модификаторами должны посчитаться по полной истории
+    pub fn warmup_requirements(&self) -> WarmupRequirements {
+        let config = &self.config;
+        WarmupRequirements {
+            min_ticks: 1,
+            min_history_ms: WarmupRequirements::delta_history_ms(&[
+                (config.mshot_add_3h_delta, WarmupRequirements::DELTA_3H_MS),
+                (config.mshot_add_hourly_delta, WarmupRequirements::DELTA_1H_MS),
+                (config.mshot_add_15min_delta, WarmupRequirements::DELTA_15M_MS),
+            ]),
+            indicators: Vec::new(),
+        }
+    }
+    
+    /// Текущее состояние активного ордера и повторных шотов
+    pub fn view(&self) -> MShotView {
+        let order = self.state.active_buy_order.as_ref();
+        MShotView {
+            active_buy_price: order.map(|o| o.price),
+            active_buy_placed_at: order.map(|o| o.placed_at),
+            last_ask_price: self.state.last_ask_price,
+            active_repeat_shots: self.state.repeat_shots.iter().filter(|r| r.active).count(),
+            pending_orders: self.pending_orders(),
+        }
+    }
+    
+    /// Ордера, которые стратегия держит на рынке
+    pub fn pending_orders(&self) -> Vec<OrderIntent> {
+        self.state
+            .active_buy_order
+            .iter()
+            .map(|o| OrderIntent::buy(o.price, o.size))
+            .collect()
+    }
+    
+    /// Снимок внутреннего состояния (активный ордер, повторные шоты, дельты)
+    pub fn snapshot(&self) -> MShotState {
+        self.state.clone()
+    }
+    
+    /// Восстановление состояния из снимка (после рестарта)
+    pub fn restore(&mut self, state: MShotState) {
+        self.state = state;
+    }
+    
     /// Вызывается при исполнении buy ордера
     pub fn on_buy_filled(&mut self, price: f64, size: f64) {
         // Запускаем повторный шот если настроено
@@ -380,6 +441,11 @@ impl MShotStrategy {
         self.state.active_buy_order = None;
     }
     
+    /// Buy не ушёл на биржу: ордера нет, на следующем тике шот ставится заново
+    pub fn on_buy_declined(&mut self) {
+        self.state.active_buy_order = None;
+    }
+    
     /// Вычисление цены продажи
     pub fn calculate_sell_price(&self, buy_price: f64, current_ask: Option<f64>) -> f64 {
         if self.config.mshot_sell_at_last_price {
@@ -396,7 +462,7 @@ impl MShotStrategy {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Deltas {
     pub delta_3h: f64,
     pub delta_hourly: f64,
@@ -404,5 +470,8 @@ pub struct Deltas {
     pub delta_market: f64,
     pub delta_btc: f64,
     pub delta_btc_5m: f64,
+    /// Изменение открытого интереса за 5 минут (%); 0 без данных по OI
+    #[serde(default)]
+    pub delta_oi_5m: f64,
 }
 
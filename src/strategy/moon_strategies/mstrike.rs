@@ -1,10 +1,22 @@
 //! MStrike стратегия - детект прострела с LastBidEMA
 //! Ловит быстрое падение цены и выставляет buy ордер
 
-use crate::backtest::market::TradeTick;
+use super::capitulation::CapitulationFilterConfig;
+use super::entry_model::EntryModelConfig;
+use super::exits::{AskLevels, AskWallConfig, BreakevenConfig, BreakevenStop, TimeStopConfig};
+use super::flow::{FlowFilterConfig, OrderFlow};
+use super::inspect::OrderIntent;
+use super::numeric::{depth_pct, valid_price, valid_volume, NumericError};
+use super::phase::Phase;
+use super::window::TimeWindow;
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::backtest::strategy_adapter::WarmupRequirements;
+use crate::base_classes::liquidations::{Liquidation, LiquidationWindow};
+use crate::base_classes::types::Side;
+use crate::error::{Error, ExecutionError, MarketDataError};
+use crate::execution::TimeInForce;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MStrikeConfig {
@@ -25,12 +37,55 @@ pub struct MStrikeConfig {
     pub mstrike_add_market_delta: f64,   // Добавить % к глубине за каждый % дельты маркета
     pub mstrike_add_btc_delta: f64,      // Добавить % к глубине за каждый % дельты BTC
     
+    // LastBidEMA: период, длина истории и вход (ликвидным парам подходит бид, неликвидным - цена трейда)
+    #[serde(default = "default_ema_period")]
+    pub mstrike_ema_period: usize,       // Период EMA (4 по умолчанию)
+    #[serde(default = "default_bid_history")]
+    pub mstrike_bid_history: usize,      // Сколько последних тиков хранить (не меньше периода)
+    #[serde(default)]
+    pub mstrike_ema_source: MStrikeEmaSource,
+    
     // Направление
     pub mstrike_direction: MStrikeDirection, // Both, OnlyLong, OnlyShort
     
     // MStrikeWaitDip: Ждать пока не появится трейд выше (или ниже для шорта)
     pub mstrike_wait_dip: bool,          // Ждать разворот
     pub mstrike_wait_dip_timeout: u64,   // Таймаут ожидания (мс, макс 10 сек)
+    // Подтверждение разворота; включённые условия должны выполниться все сразу
+    #[serde(default = "default_wait_dip_higher_trades")]
+    pub mstrike_wait_dip_higher_trades: u32, // Сколько трейдов подряд выше предыдущего (1 = любой трейд выше)
+    #[serde(default)]
+    pub mstrike_wait_dip_min_bounce: f64,    // Минимальный отскок от дна ожидания в % (0 = выкл)
+    #[serde(default)]
+    pub mstrike_wait_dip_bid_recovery: f64,  // Объем покупок после детекта в % от объема прострела (0 = выкл)
+    // Отслеживание провала, не доросшего до детекта, бросается через столько мс (0 = без таймаута)
+    #[serde(default = "default_strike_timeout")]
+    pub mstrike_strike_timeout: u64,
+    // Истощение продавцов: вход ждёт, пока дисбаланс покупок не восстановится (None = входить сразу)
+    #[serde(default)]
+    pub flow_filter: Option<FlowFilterConfig>,
+    // Капитуляция: детект только при кластере ликвидаций лонгов (None = без ленты ликвидаций)
+    #[serde(default)]
+    pub capitulation_filter: Option<CapitulationFilterConfig>,
+    
+    // Time-in-force: buy на дне обычно IOC - забрать что есть и не стоять в стакане
+    #[serde(default)]
+    pub mstrike_buy_tif: TimeInForce,
+    #[serde(default)]
+    pub mstrike_sell_tif: TimeInForce,
+    
+    // Выход по возрасту позиции (None = держать до цели)
+    #[serde(default)]
+    pub time_stop: Option<TimeStopConfig>,
+    // Перенос стопа в безубыток после роста (None = без стопа)
+    #[serde(default)]
+    pub breakeven: Option<BreakevenConfig>,
+    // Продажа перед ask-стеной у цели (None = всегда по MStrikeSellLevel)
+    #[serde(default)]
+    pub ask_wall: Option<AskWallConfig>,
+    // ML-фильтр входов (None = входить по каждому детекту)
+    #[serde(default)]
+    pub entry_model: Option<EntryModelConfig>,
     
     // Общие параметры
     pub order_size: f64,                 // Размер ордера
@@ -39,6 +94,55 @@ pub struct MStrikeConfig {
     pub use_take_profit: bool,
 }
 
+impl MStrikeConfig {
+    /// Проверка значений, которые стратегия не переживёт молча
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.mstrike_ema_period == 0 {
+            anyhow::bail!("mstrike_ema_period must be positive");
+        }
+        if self.mstrike_bid_history < self.mstrike_ema_period {
+            anyhow::bail!(
+                "mstrike_bid_history {} is below mstrike_ema_period {}",
+                self.mstrike_bid_history,
+                self.mstrike_ema_period
+            );
+        }
+        if let Some(filter) = &self.flow_filter {
+            filter.validate()?;
+        }
+        if let Some(filter) = &self.capitulation_filter {
+            filter.validate()?;
+        }
+        Ok(())
+    }
+}
+
+fn default_wait_dip_higher_trades() -> u32 {
+    1
+}
+
+fn default_strike_timeout() -> u64 {
+    60_000
+}
+
+fn default_ema_period() -> usize {
+    4
+}
+
+fn default_bid_history() -> usize {
+    10
+}
+
+/// Что подаётся на вход LastBidEMA
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MStrikeEmaSource {
+    /// Лучший бид тика (цена трейда, если бида нет)
+    #[default]
+    Bid,
+    /// Цена последнего трейда: на неликвидной паре бид стоит на месте, пока идут сделки
+    LastTrade,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MStrikeDirection {
     Both,      // В обе стороны симметрично
@@ -60,9 +164,24 @@ impl Default for MStrikeConfig {
             mstrike_add_15min_delta: 0.0,
             mstrike_add_market_delta: 0.0,
             mstrike_add_btc_delta: 0.0,
+            mstrike_ema_period: default_ema_period(),
+            mstrike_bid_history: default_bid_history(),
+            mstrike_ema_source: MStrikeEmaSource::Bid,
             mstrike_direction: MStrikeDirection::Both,
             mstrike_wait_dip: false,
             mstrike_wait_dip_timeout: 10000,
+            mstrike_wait_dip_higher_trades: 1,
+            mstrike_wait_dip_min_bounce: 0.0,
+            mstrike_wait_dip_bid_recovery: 0.0,
+            mstrike_strike_timeout: default_strike_timeout(),
+            flow_filter: None,
+            capitulation_filter: None,
+            mstrike_buy_tif: TimeInForce::Gtc,
+            mstrike_sell_tif: TimeInForce::Gtc,
+            time_stop: None,
+            breakeven: None,
+            ask_wall: None,
+            entry_model: None,
             order_size: 100.0,
             use_stop_loss: false,
             use_trailing: false,
@@ -71,11 +190,11 @@ impl Default for MStrikeConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MStrikeState {
     // LastBidEMA и история
     last_bid_ema: Option<f64>,
-    bid_history: VecDeque<(DateTime<Utc>, f64)>, // История бидов для EMA
+    bid_history: TimeWindow<f64>, // История бидов для EMA
     
     // Состояние детекта
     min_price_during_strike: Option<f64>,    // Минимальная цена во время прострела
@@ -85,6 +204,14 @@ pub struct MStrikeState {
     // Цена до детекта
     price_before_strike: Option<f64>,
     
+    // Глубина последнего детекта (%)
+    #[serde(default)]
+    last_detection_depth: Option<f64>,
+    
+    // Фаза входа; buy_price - цена выставленного buy, после фила - средняя цена входа
+    #[serde(default)]
+    phase: Phase,
+    
     // Текущий ордер
     active_order_id: Option<u64>,
     buy_price: Option<f64>,
@@ -100,6 +227,46 @@ pub struct MStrikeState {
     waiting_for_dip_reversal: bool,
     dip_wait_start: Option<DateTime<Utc>>,
     last_price_before_dip: Option<f64>,
+    #[serde(default)]
+    dip_higher_streak: u32,                   // Трейдов подряд выше предыдущего
+    #[serde(default)]
+    dip_low: Option<f64>,                     // Дно с момента детекта
+    #[serde(default)]
+    dip_buy_volume: f64,                      // Объем покупок с момента детекта
+    
+    // Открытие позиции (время тика при филе), стоп безубытка и выставленный
+    // принудительный выход (TimeStop или стоп)
+    #[serde(default)]
+    position_opened_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    breakeven_stop: BreakevenStop,
+    #[serde(default)]
+    forced_exit_price: Option<f64>,
+}
+
+/// Read-model состояния MStrike для дашборда и тестов
+#[derive(Debug, Clone, Serialize)]
+pub struct MStrikeView {
+    pub last_bid_ema: Option<f64>,
+    pub tracking_strike: bool,
+    pub strike_started_at: Option<DateTime<Utc>>,
+    pub strike_min_price: Option<f64>,
+    pub price_before_strike: Option<f64>,
+    pub strike_volume: f64,
+    pub current_depth: Option<f64>,
+    pub last_detection_depth: Option<f64>,
+    pub effective_depth: f64,
+    pub waiting_for_dip: bool,
+    pub dip_wait_started_at: Option<DateTime<Utc>>,
+    pub phase: Phase,
+    pub entry_price: Option<f64>,
+    pub position_size: f64,
+    pub pending_orders: Vec<OrderIntent>,
+    pub position_opened_at: Option<DateTime<Utc>>,
+    /// Когда позиция будет закрыта по TimeStop
+    pub time_stop_at: Option<DateTime<Utc>>,
+    /// Стоп после переноса в безубыток
+    pub stop_price: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -122,24 +289,37 @@ pub enum MStrikeSignal {
     CancelOrder {
         order_id: u64,
     },
+    /// Битый тик, испорченное состояние прострела или нет нужного состояния позиции
+    Error(Error),
 }
 
 pub struct MStrikeStrategy {
     config: MStrikeConfig,
     state: MStrikeState,
+    // Последний стакан (asks), поток сделок и ликвидации - рыночные данные, в снимок не входят
+    asks: AskLevels,
+    flow: Option<OrderFlow>,
+    liquidations: Option<LiquidationWindow>,
+    /// Расширение стопов устаревшего символа, %; 0 - обычные стопы
+    stop_widen_pct: f64,
 }
 
 impl MStrikeStrategy {
     pub fn new(config: MStrikeConfig) -> Self {
         Self {
+            flow: config.flow_filter.map(|filter| filter.tracker()),
+            liquidations: config.capitulation_filter.map(|filter| filter.tracker()),
             config,
+            stop_widen_pct: 0.0,
             state: MStrikeState {
                 last_bid_ema: None,
-                bid_history: VecDeque::new(),
+                bid_history: TimeWindow::new(),
                 min_price_during_strike: None,
                 strike_start_time: None,
                 strike_volume: 0.0,
                 price_before_strike: None,
+                last_detection_depth: None,
+                phase: Phase::Idle,
                 active_order_id: None,
                 buy_price: None,
                 position_size: 0.0,
@@ -150,7 +330,14 @@ impl MStrikeStrategy {
                 waiting_for_dip_reversal: false,
                 dip_wait_start: None,
                 last_price_before_dip: None,
+                dip_higher_streak: 0,
+                dip_low: None,
+                dip_buy_volume: 0.0,
+                position_opened_at: None,
+                breakeven_stop: BreakevenStop::default(),
+                forced_exit_price: None,
             },
+            asks: AskLevels::default(),
         }
     }
     
@@ -164,23 +351,42 @@ impl MStrikeStrategy {
         let current_price = tick.price;
         let current_bid = tick.best_bid.unwrap_or(current_price);
         
+        // Битый тик не должен попасть ни в EMA бидов, ни в минимум прострела
+        if !(valid_price(current_price) && valid_price(current_bid) && valid_volume(tick.volume)) {
+            return MStrikeSignal::Error(
+                MarketDataError::InvalidTick {
+                    symbol: tick.symbol.to_string(),
+                    reason: format!("price {} bid {} volume {}", current_price, current_bid, tick.volume),
+                }
+                .into(),
+            );
+        }
+        
         // Обновляем дельты
         self.update_deltas(deltas);
         
-        // Обновляем историю бидов
-        self.update_bid_history(now, current_bid);
+        if let Some(flow) = &mut self.flow {
+            flow.push(tick);
+        }
         
-        // Вычисляем LastBidEMA по специальной формуле
-        self.update_last_bid_ema(current_bid);
+        // Обновляем историю бидов (или цен трейдов, см. MStrikeEmaSource)
+        let ema_input = match self.config.mstrike_ema_source {
+            MStrikeEmaSource::Bid => current_bid,
+            MStrikeEmaSource::LastTrade => current_price,
+        };
+        self.update_bid_history(now, ema_input);
         
-        // Если есть активная позиция - управляем ей
-        if self.state.buy_price.is_some() {
-            return self.manage_position(tick);
-        }
+        // Вычисляем LastBidEMA по специальной формуле
+        self.update_last_bid_ema(ema_input);
         
-        // Если ждем разворот (MStrikeWaitDip)
-        if self.state.waiting_for_dip_reversal {
-            return self.check_dip_reversal(tick);
+        match self.state.phase {
+            // Buy стоит - ждём фил или отказ
+            Phase::OrderResting => return MStrikeSignal::NoAction,
+            // Есть позиция - управляем ей
+            Phase::Positioned | Phase::Exiting => return self.manage_position(tick),
+            // Ждем разворот (MStrikeWaitDip)
+            Phase::Detected if self.state.waiting_for_dip_reversal => return self.check_dip_reversal(tick),
+            Phase::Idle | Phase::Detected => {}
         }
         
         // Проверяем детект прострела
@@ -192,41 +398,34 @@ impl MStrikeStrategy {
     }
     
     fn update_bid_history(&mut self, timestamp: DateTime<Utc>, bid: f64) {
-        self.state.bid_history.push_back((timestamp, bid));
+        self.state.bid_history.push(timestamp, bid);
         
-        // Храним только последние 10 тиков для EMA(4)
-        if self.state.bid_history.len() > 10 {
-            self.state.bid_history.pop_front();
-        }
+        // Храним только последние MStrikeBidHistory тиков для EMA
+        self.state.bid_history.evict_to_len(self.config.mstrike_bid_history.max(self.config.mstrike_ema_period));
     }
     
     /// Вычисление LastBidEMA по формуле MoonBot
     /// Если на предпоследнем тике бид меньше чем LastBidEMA, то LastBidEMA = бид на предпоследнем тике
-    /// Если больше - обычное EMA(4)
+    /// Если больше - обычное EMA(MStrikeEmaPeriod)
     fn update_last_bid_ema(&mut self, current_bid: f64) {
-        if self.state.bid_history.len() < 4 {
-            // Недостаточно данных для EMA(4)
+        let period = self.config.mstrike_ema_period.max(1);
+        if self.state.bid_history.len() < period {
+            // Недостаточно данных для EMA
             return;
         }
         
-        let bids: Vec<f64> = self.state.bid_history
-            .iter()
-            .map(|(_, bid)| *bid)
-            .collect();
+        let bids = &self.state.bid_history;
+        let len = bids.len();
         
         // Предпоследний бид (2 секунды назад)
-        let prev_bid = if bids.len() >= 2 {
-            bids[bids.len() - 2]
-        } else {
-            bids[bids.len() - 1]
-        };
+        let prev_bid = len.checked_sub(2).and_then(|idx| bids.get(idx)).map_or(current_bid, |&(_, bid)| bid);
         
-        // Вычисляем EMA(4)
-        let multiplier = 2.0 / (4.0 + 1.0); // 2 / (period + 1)
-        let recent_bids = &bids[bids.len().saturating_sub(4)..];
+        // Вычисляем EMA по последним `period` бидам прямо из истории, без копирования
+        let multiplier = 2.0 / (period as f64 + 1.0);
+        let mut recent_bids = bids.last_n(period).map(|(_, bid)| *bid);
         
-        let mut ema = recent_bids[0];
-        for &bid in recent_bids.iter().skip(1) {
+        let mut ema = recent_bids.next().unwrap_or(current_bid);
+        for bid in recent_bids {
             ema = (bid * multiplier) + (ema * (1.0 - multiplier));
         }
         
@@ -264,27 +463,48 @@ impl MStrikeStrategy {
                 self.state.min_price_during_strike = Some(current_price);
                 self.state.price_before_strike = Some(last_bid_ema);
                 self.state.strike_volume = volume;
-                return None;
             }
+            // Либо только начали отслеживание, либо прострела нет
+            return None;
+        } else if self.strike_abandoned(now, current_price) {
+            // Мелкий провал не дорос до детекта: без сброса его минимум и цена до него
+            // остались бы базой для всех следующих прострелов
+            log::debug!(
+                "MStrike strike tracking from {:?} abandoned at {} (price {})",
+                self.state.price_before_strike,
+                now,
+                current_price
+            );
+            self.reset_strike_state();
+            return None;
         } else {
             // Обновляем минимум
-            let min_price = self.state.min_price_during_strike.unwrap();
-            if current_price < min_price {
+            if self.state.min_price_during_strike.is_some_and(|min_price| current_price < min_price) {
                 self.state.min_price_during_strike = Some(current_price);
                 self.state.strike_volume += volume;
             }
         }
         
-        let min_price = self.state.min_price_during_strike.unwrap();
-        let price_before = self.state.price_before_strike.unwrap();
+        let (Some(min_price), Some(price_before)) = (self.state.min_price_during_strike, self.state.price_before_strike) else {
+            return Some(Self::missing("price_before_strike", "detect_strike"));
+        };
         
         // Вычисляем глубину прострела
-        let depth = ((price_before - min_price) / price_before) * 100.0;
+        let depth = match depth_pct(price_before, min_price) {
+            Ok(depth) => depth,
+            Err(err) => return Some(self.invalid_strike(tick, err)),
+        };
         
         // Проверяем условие детекта
         if depth >= effective_depth {
-            // Проверяем объем
-            if self.state.strike_volume >= self.config.mstrike_volume {
+            // Проверяем объем; без кластера ликвидаций прострел - ровная продажа,
+            // отслеживание продолжается, пока лента не догонит
+            if self.state.strike_volume >= self.config.mstrike_volume && self.capitulation(now) {
+                if let Err(err) = self.enter(Phase::Detected, "detect_strike") {
+                    return Some(MStrikeSignal::Error(err.into()));
+                }
+                self.state.last_detection_depth = Some(depth);
+                
                 // Детект! Логируем информацию
                 let signal = MStrikeSignal::DetectStrike {
                     depth,
@@ -298,11 +518,14 @@ impl MStrikeStrategy {
                     return Some(signal);
                 }
                 
-                // Если нужно ждать разворот (MStrikeWaitDip)
-                if self.config.mstrike_wait_dip {
+                // Если нужно ждать разворот (MStrikeWaitDip) или истощение продавцов
+                if self.config.mstrike_wait_dip || !self.sellers_exhausted() {
                     self.state.waiting_for_dip_reversal = true;
                     self.state.dip_wait_start = Some(now);
                     self.state.last_price_before_dip = Some(current_price);
+                    self.state.dip_higher_streak = 0;
+                    self.state.dip_low = Some(current_price);
+                    self.state.dip_buy_volume = 0.0;
                     return Some(signal);
                 }
                 
@@ -314,6 +537,19 @@ impl MStrikeStrategy {
         None
     }
     
+    /// Провал без детекта бросается, когда цена вернулась к LastBidEMA, от которой он
+    /// начался, или отслеживание длится дольше MStrikeStrikeTimeout
+    fn strike_abandoned(&self, now: DateTime<Utc>, current_price: f64) -> bool {
+        self.state.price_before_strike.is_some_and(|before| current_price >= before) || self.strike_expired(now)
+    }
+    
+    fn strike_expired(&self, now: DateTime<Utc>) -> bool {
+        self.config.mstrike_strike_timeout > 0
+            && self.state.strike_start_time.is_some_and(|start| {
+                (now - start).num_milliseconds().max(0) as u64 > self.config.mstrike_strike_timeout
+            })
+    }
+    
     fn calculate_effective_depth(&self) -> f64 {
         let mut depth = self.config.mstrike_depth;
         
@@ -327,7 +563,9 @@ impl MStrikeStrategy {
     }
     
     fn place_buy_order(&mut self, min_price: f64, depth: f64) -> Option<MStrikeSignal> {
-        let price_before = self.state.price_before_strike.unwrap();
+        let Some(price_before) = self.state.price_before_strike else {
+            return Some(Self::missing("price_before_strike", "place_buy_order"));
+        };
         
         // Вычисляем цену buy ордера
         let buy_price = if self.config.mstrike_buy_relative {
@@ -345,6 +583,9 @@ impl MStrikeStrategy {
             price_before * (1.0 - self.config.mstrike_buy_level / 100.0)
         };
         
+        if let Err(err) = self.enter(Phase::OrderResting, "place_buy_order") {
+            return Some(MStrikeSignal::Error(err.into()));
+        }
         self.state.buy_price = Some(buy_price);
         self.state.position_size = self.config.order_size;
         
@@ -358,9 +599,16 @@ impl MStrikeStrategy {
         })
     }
     
-    fn calculate_sell_price(&self, min_price: f64, depth: f64) -> f64 {
-        let price_before = self.state.price_before_strike.unwrap();
-        
+    /// Цель продажи с учетом ask-стены между рынком и MStrikeSellLevel
+    fn sell_target(&self, min_price: f64, depth: f64) -> f64 {
+        let target = self.calculate_sell_price(min_price, depth);
+        match (self.config.ask_wall, self.state.buy_price) {
+            (Some(ask_wall), Some(buy_price)) => ask_wall.front_run(target, &self.asks, buy_price).unwrap_or(target),
+            _ => target,
+        }
+    }
+    
+        fn calculate_sell_price(&self, min_price: f64, depth: f64) -> f64 {
         // SellLevel - процент от глубины прострела
         let sell_level_price = min_price * (1.0 + (depth * self.config.mstrike_sell_level / 100.0) / 100.0);
         
@@ -372,45 +620,148 @@ impl MStrikeStrategy {
         let current_price = tick.price;
         
         // Проверяем таймаут
-        if let Some(wait_start) = self.state.dip_wait_start {
-            let elapsed = (now - wait_start).num_milliseconds() as u64;
-            if elapsed > self.config.mstrike_wait_dip_timeout {
-                // Таймаут - сбрасываем ожидание
-                self.reset_strike_state();
-                return MStrikeSignal::NoAction;
-            }
+        if self.dip_wait_expired(now) {
+            // Таймаут - сбрасываем ожидание
+            self.reset_strike_state();
+            return MStrikeSignal::NoAction;
         }
         
-        // Проверяем разворот: появился трейд выше предыдущего
+        // Серия трейдов выше предыдущего: трейд ниже обнуляет, равный не меняет
         if let Some(last_price) = self.state.last_price_before_dip {
             if current_price > last_price {
-                // Разворот обнаружен - выставляем ордер
-                self.state.waiting_for_dip_reversal = false;
-                
-                let min_price = self.state.min_price_during_strike.unwrap();
-                let depth = {
-                    let price_before = self.state.price_before_strike.unwrap();
-                    ((price_before - min_price) / price_before) * 100.0
+                self.state.dip_higher_streak += 1;
+            } else if current_price < last_price {
+                self.state.dip_higher_streak = 0;
+            }
+        }
+        self.state.last_price_before_dip = Some(current_price);
+        self.state.dip_low = Some(self.state.dip_low.map_or(current_price, |low| low.min(current_price)));
+        if tick.side == TradeSide::Buy {
+            self.state.dip_buy_volume += tick.volume;
+        }
+        
+        if !self.dip_reversal_confirmed(current_price) {
+            return MStrikeSignal::NoAction;
+        }
+        
+        // Разворот подтвержден - выставляем ордер
+        self.state.waiting_for_dip_reversal = false;
+        
+        let (Some(min_price), Some(price_before)) = (self.state.min_price_during_strike, self.state.price_before_strike) else {
+            return Self::missing("min_price_during_strike", "check_dip_reversal");
+        };
+        let depth = match depth_pct(price_before, min_price) {
+            Ok(depth) => depth,
+            Err(err) => return self.invalid_strike(tick, err),
+        };
+        
+        self.place_buy_order(min_price, depth).unwrap_or(MStrikeSignal::NoAction)
+    }
+    
+    fn dip_wait_expired(&self, now: DateTime<Utc>) -> bool {
+        self.state
+            .dip_wait_start
+            .is_some_and(|wait_start| (now - wait_start).num_milliseconds().max(0) as u64 > self.wait_timeout_ms())
+    }
+    
+    /// Таймер рантайма: таймаут ожидания разворота и TimeStop срабатывают и в тихом
+    /// рынке. Цена выхода считается от последнего бида
+    pub fn on_timer(&mut self, now: DateTime<Utc>) -> MStrikeSignal {
+        match self.state.phase {
+            Phase::Detected if self.state.waiting_for_dip_reversal && self.dip_wait_expired(now) => {
+                self.reset_strike_state();
+                MStrikeSignal::NoAction
+            }
+            Phase::Idle if self.strike_expired(now) => {
+                self.reset_strike_state();
+                MStrikeSignal::NoAction
+            }
+            Phase::Positioned => {
+                let (Some(time_stop), Some(opened_at), Some(&(_, bid))) =
+                    (self.config.time_stop, self.state.position_opened_at, self.state.bid_history.back())
+                else {
+                    return MStrikeSignal::NoAction;
                 };
-                
-                return self.place_buy_order(min_price, depth).unwrap_or(MStrikeSignal::NoAction);
+                if !time_stop.expired(opened_at, now) {
+                    return MStrikeSignal::NoAction;
+                }
+                let (Some(min_price), Some(depth)) = (self.state.min_price_during_strike, self.current_depth()) else {
+                    return Self::missing("min_price_during_strike", "on_timer");
+                };
+                let price = time_stop.exit_price(bid, Some(bid), self.sell_target(min_price, depth));
+                self.forced_exit(price)
             }
+            _ => MStrikeSignal::NoAction,
         }
+    }
+    
+    /// Одиночный аптик часто шум, поэтому кроме серии трейдов выше можно требовать
+    /// отскок от дна и возврат покупок относительно объема прострела. Без MStrikeWaitDip
+    /// ожидание держит только фильтр потока
+    fn dip_reversal_confirmed(&self, current_price: f64) -> bool {
+        let streak_ok = !self.config.mstrike_wait_dip
+            || self.state.dip_higher_streak >= self.config.mstrike_wait_dip_higher_trades.max(1);
         
-        MStrikeSignal::NoAction
+        let bounce_ok = self.config.mstrike_wait_dip_min_bounce <= 0.0
+            || self.state.dip_low.is_some_and(|low| {
+                (current_price - low) / low * 100.0 >= self.config.mstrike_wait_dip_min_bounce
+            });
+        
+        let recovery_ok = self.config.mstrike_wait_dip_bid_recovery <= 0.0
+            || self.state.dip_buy_volume
+                >= self.state.strike_volume * self.config.mstrike_wait_dip_bid_recovery / 100.0;
+        
+        streak_ok && bounce_ok && recovery_ok && self.sellers_exhausted()
+    }
+    
+    /// Фильтр потока не задан или покупки уже догнали продажи
+    fn sellers_exhausted(&self) -> bool {
+        match (&self.config.flow_filter, &self.flow) {
+            (Some(filter), Some(flow)) => filter.exhausted(&flow.flow()),
+            _ => true,
+        }
+    }
+    
+    /// Фильтр капитуляции не задан или прострел сопровождался кластером ликвидаций
+    fn capitulation(&self, now: DateTime<Utc>) -> bool {
+        match (&self.config.capitulation_filter, &self.liquidations) {
+            (Some(filter), Some(liquidations)) => {
+                let stats = liquidations.stats(now.timestamp_millis().max(0) as u64);
+                filter.capitulation(&stats, self.state.strike_volume)
+            }
+            _ => true,
+        }
+    }
+    
+    /// Таймаут ожидания: MStrikeWaitDipTimeout, а если ждём только поток — его max_wait_ms
+    fn wait_timeout_ms(&self) -> u64 {
+        match self.config.flow_filter {
+            Some(filter) if !self.config.mstrike_wait_dip => filter.max_wait_ms,
+            _ => self.config.mstrike_wait_dip_timeout,
+        }
     }
     
     fn manage_position(&mut self, tick: &TradeTick) -> MStrikeSignal {
         let current_price = tick.price;
-        let buy_price = self.state.buy_price.unwrap();
+        let Some(buy_price) = self.state.buy_price else {
+            return Self::missing("buy_price", "manage_position");
+        };
+        
+        // Принудительный выход уже выставлен - ждём его фила
+        if self.state.phase == Phase::Exiting {
+            return MStrikeSignal::NoAction;
+        }
         
         // Вычисляем цену продажи
-        let min_price = self.state.min_price_during_strike.unwrap();
-        let depth = {
-            let price_before = self.state.price_before_strike.unwrap();
-            ((price_before - min_price) / price_before) * 100.0
+        // Позиция открыта: состояние прострела не сбрасываем, только сообщаем
+        let (Some(min_price), Some(price_before)) = (self.state.min_price_during_strike, self.state.price_before_strike) else {
+            return Self::missing("min_price_during_strike", "manage_position");
         };
-        let sell_price = self.calculate_sell_price(min_price, depth);
+        let depth = match depth_pct(price_before, min_price) {
+            Ok(depth) => depth,
+            Err(err) => return Self::corrupt(tick, "position depth", err),
+        };
+        let sell_price = self.sell_target(min_price, depth);
         
         // Проверяем условие продажи
         if current_price >= sell_price {
@@ -420,11 +771,43 @@ impl MStrikeStrategy {
             };
         }
         
-        // TODO: Добавить стоп-лосс и трейлинг
+        // Стоп безубытка: после роста на trigger_pct позиция не должна уйти в минус
+        if let Some(breakeven) = self.config.breakeven
+            && self.state.breakeven_stop.update(&breakeven, buy_price, current_price, self.stop_widen_pct)
+        {
+            let price = tick.best_bid.unwrap_or(current_price);
+            return self.forced_exit(price);
+        }
+        
+        // TimeStop: позиция не отскочила вовремя
+        if let (Some(time_stop), Some(opened_at)) = (self.config.time_stop, self.state.position_opened_at)
+            && time_stop.expired(opened_at, tick.timestamp)
+        {
+            let price = time_stop.exit_price(current_price, tick.best_bid, sell_price);
+            return self.forced_exit(price);
+        }
+        
+        // TODO: Добавить стоп-лосс
         
         MStrikeSignal::NoAction
     }
     
+    /// TimeStop или стоп: выход выставляется один раз, дальше ждём его фила
+    fn forced_exit(&mut self, price: f64) -> MStrikeSignal {
+        if let Err(err) = self.enter(Phase::Exiting, "forced_exit") {
+            return MStrikeSignal::Error(err.into());
+        }
+        self.state.forced_exit_price = Some(price);
+        MStrikeSignal::PlaceSell {
+            price,
+            size: self.state.position_size,
+        }
+    }
+    
+    fn time_stop_deadline(&self) -> Option<DateTime<Utc>> {
+        self.config.time_stop?.deadline(self.state.position_opened_at?)
+    }
+    
     fn update_deltas(&mut self, deltas: &super::mshot::Deltas) {
         self.state.delta_hourly = deltas.delta_hourly;
         self.state.delta_15min = deltas.delta_15min;
@@ -432,7 +815,32 @@ impl MStrikeStrategy {
         self.state.delta_btc = deltas.delta_btc;
     }
     
+    /// Испорченный прострел сбрасывается, отслеживание начинается заново
+    fn invalid_strike(&mut self, tick: &TradeTick, err: NumericError) -> MStrikeSignal {
+        self.reset_strike_state();
+        Self::corrupt(tick, "strike depth", err)
+    }
+    
+    fn corrupt(tick: &TradeTick, what: &str, err: NumericError) -> MStrikeSignal {
+        MStrikeSignal::Error(
+            MarketDataError::InvalidTick { symbol: tick.symbol.to_string(), reason: format!("{}: {}", what, err) }.into(),
+        )
+    }
+    
+    /// Ветка требует состояния, которого нет (например, снимок восстановлен не целиком)
+    fn missing(field: &'static str, context: &'static str) -> MStrikeSignal {
+        MStrikeSignal::Error(ExecutionError::MissingState { strategy: "MStrike", field, context }.into())
+    }
+    
+    /// Переход фазы; недопустимый не меняет состояние
+    fn enter(&mut self, to: Phase, context: &'static str) -> Result<(), ExecutionError> {
+        self.state.phase.advance(to, "MStrike", context)
+    }
+    
+    /// Прострел отработан или испорчен: детект сбрасывается, ордера и позиции уже нет
     fn reset_strike_state(&mut self) {
+        debug_assert!(matches!(self.state.phase, Phase::Idle | Phase::Detected), "strike reset in {}", self.state.phase);
+        self.state.phase = Phase::Idle;
         self.state.min_price_during_strike = None;
         self.state.strike_start_time = None;
         self.state.strike_volume = 0.0;
@@ -440,28 +848,185 @@ impl MStrikeStrategy {
         self.state.waiting_for_dip_reversal = false;
         self.state.dip_wait_start = None;
         self.state.last_price_before_dip = None;
+        self.state.dip_higher_streak = 0;
+        self.state.dip_low = None;
+        self.state.dip_buy_volume = 0.0;
+    }
+    
+    /// Текущее состояние детекта, ожидания разворота и ордеров
+    pub fn view(&self) -> MStrikeView {
+        MStrikeView {
+            last_bid_ema: self.state.last_bid_ema,
+            tracking_strike: self.state.min_price_during_strike.is_some(),
+            strike_started_at: self.state.strike_start_time,
+            strike_min_price: self.state.min_price_during_strike,
+            price_before_strike: self.state.price_before_strike,
+            strike_volume: self.state.strike_volume,
+            current_depth: self.current_depth(),
+            last_detection_depth: self.state.last_detection_depth,
+            effective_depth: self.calculate_effective_depth(),
+            waiting_for_dip: self.state.waiting_for_dip_reversal,
+            dip_wait_started_at: self.state.dip_wait_start,
+            phase: self.state.phase,
+            entry_price: self.state.buy_price,
+            position_size: self.state.position_size,
+            pending_orders: self.pending_orders(),
+            position_opened_at: self.state.position_opened_at,
+            time_stop_at: self.time_stop_deadline(),
+            stop_price: self.state.breakeven_stop.stop_price,
+        }
+    }
+    
+    /// Time-in-force ордеров на стороне `side`
+    /// LastBidEMA по полному периоду и дельты с включёнными модификаторами
+    pub fn warmup_requirements(&self) -> WarmupRequirements {
+        let config = &self.config;
+        WarmupRequirements {
+            min_ticks: config.mstrike_ema_period as u64,
+            min_history_ms: WarmupRequirements::delta_history_ms(&[
+                (config.mstrike_add_hourly_delta, WarmupRequirements::DELTA_1H_MS),
+                (config.mstrike_add_15min_delta, WarmupRequirements::DELTA_15M_MS),
+            ]),
+            indicators: vec!["last_bid_ema"],
+        }
+    }
+    
+    pub fn indicator_ready(&self, indicator: &str) -> bool {
+        match indicator {
+            "last_bid_ema" => self.state.last_bid_ema.is_some(),
+            _ => false,
+        }
     }
     
-    /// Вызывается при исполнении buy ордера
-    pub fn on_buy_filled(&mut self, price: f64, size: f64) {
+    pub fn time_in_force(&self, side: Side) -> TimeInForce {
+        match side {
+            Side::Bid => self.config.mstrike_buy_tif,
+            Side::Ask => self.config.mstrike_sell_tif,
+        }
+    }
+    
+    /// Ордера, которые стратегия держит на рынке
+    pub fn pending_orders(&self) -> Vec<OrderIntent> {
+        if self.state.phase == Phase::OrderResting {
+            return self.state.buy_price.map(|price| OrderIntent::buy(price, self.state.position_size)).into_iter().collect();
+        }
+        match (self.state.buy_price, self.state.min_price_during_strike, self.current_depth()) {
+            (Some(_), Some(min_price), Some(depth)) if self.state.phase.has_position() => {
+                let price = self
+                    .state
+                    .forced_exit_price
+                    .unwrap_or_else(|| self.sell_target(min_price, depth));
+                vec![OrderIntent::sell(price, self.state.position_size)]
+            }
+            _ => Vec::new(),
+        }
+    }
+    
+    fn current_depth(&self) -> Option<f64> {
+        let min_price = self.state.min_price_during_strike?;
+        let price_before = self.state.price_before_strike?;
+        depth_pct(price_before, min_price).ok()
+    }
+    
+    /// Снимок внутреннего состояния (EMA, детект, ожидание разворота, позиция)
+    pub fn snapshot(&self) -> MStrikeState {
+        self.state.clone()
+    }
+    
+    /// Восстановление состояния из снимка (после рестарта)
+    pub fn restore(&mut self, mut state: MStrikeState) {
+        // Снимок без фазы (до её появления): выводим её из полей позиции
+        if state.phase == Phase::Idle {
+            state.phase = match (state.buy_price, state.position_opened_at, state.forced_exit_price) {
+                (Some(_), Some(_), Some(_)) => Phase::Exiting,
+                (Some(_), Some(_), None) => Phase::Positioned,
+                (Some(_), None, _) => Phase::OrderResting,
+                _ if state.waiting_for_dip_reversal => Phase::Detected,
+                _ => Phase::Idle,
+            };
+        }
+        self.state = state;
+    }
+    
+    /// Поток символа устарел (`Some(%)` расширения стопов) или вернулся (`None`)
+    pub fn set_stop_widening(&mut self, widen_pct: Option<f64>) {
+        self.stop_widen_pct = widen_pct.unwrap_or(0.0);
+    }
+    
+    /// Ликвидация по символу стратегии из ленты биржи
+    pub fn on_liquidation(&mut self, liquidation: &Liquidation) {
+        if let Some(liquidations) = &mut self.liquidations {
+            liquidations.push(liquidation.clone());
+        }
+    }
+    
+    /// Обновление стакана: asks по возрастанию цены (top-N уровней)
+    pub fn on_book(&mut self, asks: &[(f64, f64)]) {
+        self.asks.update(asks);
+    }
+    
+    /// Вызывается при исполнении buy ордера. Фил без выставленного buy - ошибка,
+    /// состояние не меняется; повторный фил (частичное исполнение) добирает позицию
+    pub fn on_buy_filled(&mut self, price: f64, size: f64) -> Result<(), ExecutionError> {
+        let was = self.state.phase;
+        self.enter(Phase::Positioned, "on_buy_filled")?;
+        if was == Phase::Positioned {
+            let total = self.state.position_size + size;
+            let entry = self.state.buy_price.unwrap_or(price);
+            self.state.buy_price = Some((entry * self.state.position_size + price * size) / total);
+            self.state.position_size = total;
+            return Ok(());
+        }
         self.state.buy_price = Some(price);
         self.state.position_size = size;
         self.state.active_order_id = Some(0); // TODO: получить реальный ID
+        self.state.breakeven_stop = BreakevenStop::default();
+        self.state.position_opened_at = Some(self.state.bid_history.back().map_or_else(Utc::now, |(t, _)| *t));
+        self.state.forced_exit_price = None;
+        Ok(())
+    }
+    
+    /// Buy не ушёл на биржу: позиции нет, прострел считается отработанным
+    pub fn on_buy_declined(&mut self) -> Result<(), ExecutionError> {
+        if self.state.phase != Phase::OrderResting {
+            return Err(self.state.phase.rejection(Phase::Idle, "MStrike", "on_buy_declined"));
+        }
+        self.enter(Phase::Idle, "on_buy_declined")?;
+        self.state.buy_price = None;
+        self.state.position_size = 0.0;
+        self.state.active_order_id = None;
+        self.reset_strike_state();
+        Ok(())
     }
     
-    /// Вызывается при исполнении sell ордера
-    pub fn on_sell_filled(&mut self) {
+    /// Вызывается при исполнении sell ордера. Фил без позиции - ошибка, состояние
+    /// не меняется; частичный фил уменьшает позицию, фаза остаётся
+    pub fn on_sell_filled(&mut self, size: f64) -> Result<(), ExecutionError> {
+        if !self.state.phase.has_position() {
+            return Err(self.state.phase.rejection(Phase::Idle, "MStrike", "on_sell_filled"));
+        }
+        let remaining = self.state.position_size - size;
+        if remaining > self.state.position_size * 1e-9 {
+            self.state.position_size = remaining;
+            return Ok(());
+        }
+        self.enter(Phase::Idle, "on_sell_filled")?;
         self.state.buy_price = None;
         self.state.position_size = 0.0;
         self.state.active_order_id = None;
+        self.state.position_opened_at = None;
+        self.state.breakeven_stop = BreakevenStop::default();
+        self.state.forced_exit_price = None;
         self.reset_strike_state();
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::backtest::market::{TradeTick, TradeSide};
+    use crate::base_classes::symbol::Symbol;
+    use crate::strategy::moon_strategies::exits::TimeStopExit;
     use crate::strategy::moon_strategies::mshot::Deltas;
     use chrono::Utc;
 
@@ -476,7 +1041,7 @@ mod tests {
         let ticks = vec![
             TradeTick {
                 timestamp: now,
-                symbol: "BTC_USDT".to_string(),
+                symbol: Symbol::new("BTC_USDT"),
                 price: 100.0,
                 volume: 1.0,
                 side: TradeSide::Buy,
@@ -486,7 +1051,7 @@ mod tests {
             },
             TradeTick {
                 timestamp: now + chrono::Duration::try_milliseconds(100).unwrap(),
-                symbol: "BTC_USDT".to_string(),
+                symbol: Symbol::new("BTC_USDT"),
                 price: 95.0, // Прострел на 5%
                 volume: 10.0,
                 side: TradeSide::Sell,
@@ -523,4 +1088,372 @@ mod tests {
         assert_eq!(strategy.state.buy_price, None);
         assert_eq!(strategy.state.position_size, 0.0);
     }
+    
+    #[test]
+    fn test_errors_instead_of_panics_on_bad_input_and_missing_state() {
+        let mut strategy = MStrikeStrategy::new(MStrikeConfig::default());
+        let deltas = Deltas::default();
+        let bad = TradeTick { best_bid: Some(0.0), ..tick_at(0, 100.0, TradeSide::Sell, 1.0) };
+        assert!(matches!(
+            strategy.on_tick(&bad, &deltas),
+            MStrikeSignal::Error(Error::MarketData(MarketDataError::InvalidTick { .. }))
+        ));
+        
+        // Фил покупки без выставленного buy не меняет состояние
+        assert!(matches!(strategy.on_buy_filled(100.0, 1.0), Err(ExecutionError::IllegalTransition { .. })));
+        assert_eq!(strategy.view().phase, Phase::Idle);
+        
+        // Позиция без прострела (например, после неполного восстановления)
+        let mut state = strategy.snapshot();
+        state.phase = Phase::Positioned;
+        state.buy_price = Some(100.0);
+        strategy.restore(state);
+        match strategy.on_tick(&tick_at(10, 100.0, TradeSide::Sell, 1.0), &deltas) {
+            MStrikeSignal::Error(Error::Execution(ExecutionError::MissingState { field, .. })) => {
+                assert_eq!(field, "min_price_during_strike")
+            }
+            other => panic!("expected missing state, got {:?}", other),
+        }
+    }
+    
+    fn tick_at(ms: i64, price: f64, side: TradeSide, volume: f64) -> TradeTick {
+        TradeTick {
+            timestamp: chrono::DateTime::from_timestamp_millis(1_700_000_000_000 + ms).unwrap(),
+            symbol: Symbol::new("BTC_USDT"),
+            price,
+            volume,
+            side,
+            trade_id: ms.to_string(),
+            best_bid: Some(price - 0.1),
+            best_ask: Some(price + 0.1),
+        }
+    }
+    
+    /// Прострел ~2.7% при глубине 2% и включенном ожидании разворота
+    fn strategy_waiting_for_dip(config: MStrikeConfig) -> MStrikeStrategy {
+        let mut strategy = MStrikeStrategy::new(MStrikeConfig {
+            mstrike_depth: 2.0,
+            mstrike_wait_dip: true,
+            ..config
+        });
+        let deltas = Deltas::default();
+        for ms in 0..5 {
+            strategy.on_tick(&tick_at(ms * 100, 100.0, TradeSide::Buy, 1.0), &deltas);
+        }
+        strategy.on_tick(&tick_at(500, 97.0, TradeSide::Sell, 5.0), &deltas);
+        let signal = strategy.on_tick(&tick_at(600, 96.0, TradeSide::Sell, 5.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::DetectStrike { .. }), "{:?}", signal);
+        assert!(strategy.view().waiting_for_dip);
+        strategy
+    }
+    
+    #[test]
+    fn test_ema_period_history_and_source_are_configurable() {
+        // Неликвидная пара: бид стоит, сделки идут ниже
+        let stale_bid = |ms: i64, price: f64| TradeTick { best_bid: Some(100.0), ..tick_at(ms, price, TradeSide::Sell, 1.0) };
+        let deltas = Deltas::default();
+        
+        let mut by_bid = MStrikeStrategy::default();
+        let mut by_trade = MStrikeStrategy::new(MStrikeConfig {
+            mstrike_ema_period: 2,
+            mstrike_bid_history: 3,
+            mstrike_ema_source: MStrikeEmaSource::LastTrade,
+            ..MStrikeConfig::default()
+        });
+        for (i, price) in [99.0, 99.0].into_iter().enumerate() {
+            by_bid.on_tick(&stale_bid(i as i64 * 100, price), &deltas);
+            by_trade.on_tick(&stale_bid(i as i64 * 100, price), &deltas);
+        }
+        // EMA(2) готова после двух тиков, EMA(4) ещё нет
+        assert_eq!(by_trade.view().last_bid_ema, Some(99.0));
+        assert_eq!(by_bid.view().last_bid_ema, None);
+        for ms in 2..6 {
+            by_bid.on_tick(&stale_bid(ms * 100, 99.0), &deltas);
+            by_trade.on_tick(&stale_bid(ms * 100, 99.0), &deltas);
+        }
+        assert_eq!(by_bid.view().last_bid_ema, Some(100.0));
+        assert_eq!(by_trade.view().last_bid_ema, Some(99.0));
+        assert_eq!(by_trade.state.bid_history.len(), 3);
+        
+        let config = MStrikeConfig { mstrike_ema_period: 5, mstrike_bid_history: 4, ..MStrikeConfig::default() };
+        assert!(config.validate().is_err());
+        assert!(MStrikeConfig { mstrike_ema_period: 0, ..MStrikeConfig::default() }.validate().is_err());
+    }
+    
+    #[test]
+    fn test_shallow_dips_do_not_poison_next_strike() {
+        let mut strategy = MStrikeStrategy::new(MStrikeConfig { mstrike_depth: 5.0, ..MStrikeConfig::default() });
+        let deltas = Deltas::default();
+        for ms in 0..5 {
+            strategy.on_tick(&tick_at(ms * 100, 100.0, TradeSide::Buy, 1.0), &deltas);
+        }
+        // Провал на 3% не дорос до детекта, цена вернулась - отслеживание брошено
+        strategy.on_tick(&tick_at(500, 97.0, TradeSide::Sell, 1.0), &deltas);
+        assert!(strategy.view().tracking_strike);
+        strategy.on_tick(&tick_at(600, 100.0, TradeSide::Buy, 1.0), &deltas);
+        assert!(!strategy.view().tracking_strike);
+        
+        // Второй мелкий провал после роста тоже не оставляет следа
+        for ms in 7..12 {
+            strategy.on_tick(&tick_at(ms * 100, 110.0, TradeSide::Buy, 1.0), &deltas);
+        }
+        strategy.on_tick(&tick_at(1_200, 108.0, TradeSide::Sell, 1.0), &deltas);
+        strategy.on_tick(&tick_at(1_300, 110.0, TradeSide::Buy, 1.0), &deltas);
+        assert!(!strategy.view().tracking_strike);
+        
+        // Прострел от нового уровня детектится, хотя старый минимум 97 ниже
+        strategy.on_tick(&tick_at(1_400, 110.0, TradeSide::Buy, 1.0), &deltas);
+        strategy.on_tick(&tick_at(1_500, 104.0, TradeSide::Sell, 1.0), &deltas);
+        let signal = strategy.on_tick(&tick_at(1_600, 100.0, TradeSide::Sell, 1.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceBuy { .. }), "{:?}", signal);
+        assert!(strategy.view().price_before_strike.is_some_and(|before| before > 105.0));
+    }
+    
+    #[test]
+    fn test_strike_tracking_times_out() {
+        let mut strategy = MStrikeStrategy::new(MStrikeConfig {
+            mstrike_depth: 5.0,
+            mstrike_strike_timeout: 1_000,
+            ..MStrikeConfig::default()
+        });
+        let deltas = Deltas::default();
+        for ms in 0..5 {
+            strategy.on_tick(&tick_at(ms * 100, 100.0, TradeSide::Buy, 1.0), &deltas);
+        }
+        strategy.on_tick(&tick_at(500, 98.0, TradeSide::Sell, 1.0), &deltas);
+        let started = strategy.view().strike_started_at.unwrap();
+        strategy.on_tick(&tick_at(1_000, 98.0, TradeSide::Sell, 1.0), &deltas);
+        assert!(strategy.view().tracking_strike);
+        // Тиков нет - провал бросает таймер
+        strategy.on_timer(started + chrono::Duration::milliseconds(1_001));
+        assert!(!strategy.view().tracking_strike);
+        
+        // Тот же таймаут на тике
+        strategy.on_tick(&tick_at(1_600, 97.5, TradeSide::Sell, 1.0), &deltas);
+        assert!(strategy.view().tracking_strike);
+        strategy.on_tick(&tick_at(2_700, 97.5, TradeSide::Sell, 1.0), &deltas);
+        assert!(!strategy.view().tracking_strike);
+    }
+    
+    #[test]
+    fn test_wait_dip_any_higher_trade_by_default() {
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig::default());
+        let deltas = Deltas::default();
+        let signal = strategy.on_tick(&tick_at(700, 96.1, TradeSide::Sell, 0.1), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceBuy { .. }), "{:?}", signal);
+        assert!(!strategy.view().waiting_for_dip);
+    }
+    
+    #[test]
+    fn test_wait_dip_requires_consecutive_higher_trades() {
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig {
+            mstrike_wait_dip_higher_trades: 3,
+            ..MStrikeConfig::default()
+        });
+        let deltas = Deltas::default();
+        // Два аптика, затем трейд ниже обнуляет серию
+        for (ms, price) in [(700, 96.1), (800, 96.2), (900, 95.9), (1000, 96.0), (1100, 96.1)] {
+            let signal = strategy.on_tick(&tick_at(ms, price, TradeSide::Buy, 0.1), &deltas);
+            assert!(matches!(signal, MStrikeSignal::NoAction), "{} -> {:?}", price, signal);
+        }
+        let signal = strategy.on_tick(&tick_at(1200, 96.2, TradeSide::Buy, 0.1), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceBuy { .. }), "{:?}", signal);
+    }
+    
+    #[test]
+    fn test_wait_dip_bounce_and_bid_recovery() {
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig {
+            mstrike_wait_dip_min_bounce: 0.5,
+            mstrike_wait_dip_bid_recovery: 50.0,
+            ..MStrikeConfig::default()
+        });
+        let deltas = Deltas::default();
+        let strike_volume = strategy.view().strike_volume;
+        // Дно ниже детекта: отскок считается от 95.5
+        assert!(matches!(strategy.on_tick(&tick_at(700, 95.5, TradeSide::Sell, 1.0), &deltas), MStrikeSignal::NoAction));
+        // Отскок 0.63%, но покупок мало
+        assert!(matches!(strategy.on_tick(&tick_at(800, 96.1, TradeSide::Buy, 1.0), &deltas), MStrikeSignal::NoAction));
+        // Покупки набрали половину объема прострела
+        let signal = strategy.on_tick(&tick_at(900, 96.2, TradeSide::Buy, strike_volume / 2.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceBuy { .. }), "{:?}", signal);
+        
+        // Без отскока покупки не подтверждают разворот
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig {
+            mstrike_wait_dip_min_bounce: 0.5,
+            ..MStrikeConfig::default()
+        });
+        let signal = strategy.on_tick(&tick_at(700, 96.2, TradeSide::Buy, 100.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::NoAction), "{:?}", signal);
+    }
+    
+    #[test]
+    fn test_flow_filter_waits_for_seller_exhaustion() {
+        let run = || {
+            let mut strategy = MStrikeStrategy::new(MStrikeConfig {
+                mstrike_depth: 2.0,
+                flow_filter: Some(FlowFilterConfig { window_ms: 1_000, min_imbalance: 0.0, min_volume: 0.0, max_wait_ms: 2_000 }),
+                ..MStrikeConfig::default()
+            });
+            let deltas = Deltas::default();
+            for ms in 0..5 {
+                strategy.on_tick(&tick_at(ms * 100, 100.0, TradeSide::Buy, 1.0), &deltas);
+            }
+            strategy.on_tick(&tick_at(500, 97.0, TradeSide::Sell, 5.0), &deltas);
+            // Продажи 10 против покупок 5: вход откладывается
+            let signal = strategy.on_tick(&tick_at(600, 96.0, TradeSide::Sell, 5.0), &deltas);
+            assert!(matches!(signal, MStrikeSignal::DetectStrike { .. }), "{:?}", signal);
+            assert!(strategy.view().waiting_for_dip);
+            strategy
+        };
+        let deltas = Deltas::default();
+        
+        let mut strategy = run();
+        let signal = strategy.on_tick(&tick_at(700, 96.1, TradeSide::Buy, 2.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::NoAction), "{:?}", signal);
+        let signal = strategy.on_tick(&tick_at(800, 96.1, TradeSide::Buy, 4.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceBuy { .. }), "{:?}", signal);
+        
+        // Продавцы не выдохлись за max_wait_ms: детект сброшен
+        let mut strategy = run();
+        let signal = strategy.on_tick(&tick_at(2_700, 95.0, TradeSide::Sell, 1.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::NoAction), "{:?}", signal);
+        assert!(!strategy.view().waiting_for_dip);
+    }
+    
+    #[test]
+    fn test_capitulation_filter_waits_for_liquidation_cluster() {
+        use crate::base_classes::liquidations::LiquidationSide;
+        let mut strategy = MStrikeStrategy::new(MStrikeConfig {
+            mstrike_depth: 2.0,
+            capitulation_filter: Some(CapitulationFilterConfig { window_ms: 1_000, min_count: 2, min_volume_pct: 0.0 }),
+            ..MStrikeConfig::default()
+        });
+        let deltas = Deltas::default();
+        for ms in 0..5 {
+            strategy.on_tick(&tick_at(ms * 100, 100.0, TradeSide::Buy, 1.0), &deltas);
+        }
+        strategy.on_tick(&tick_at(500, 97.0, TradeSide::Sell, 5.0), &deltas);
+        // Прострел есть, но лонги не ликвидировались: ровная продажа
+        let signal = strategy.on_tick(&tick_at(600, 96.0, TradeSide::Sell, 5.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::NoAction), "{:?}", signal);
+        
+        for ts_ms in [1_700_000_000_620, 1_700_000_000_640] {
+            strategy.on_liquidation(&Liquidation {
+                symbol: "BTC_USDT".into(),
+                side: LiquidationSide::Long,
+                price: 96.0,
+                qty: 3.0,
+                ts_ms,
+            });
+        }
+        let signal = strategy.on_tick(&tick_at(700, 96.0, TradeSide::Sell, 1.0), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceBuy { .. }), "{:?}", signal);
+    }
+    
+    #[test]
+    fn test_breakeven_stop_exits_after_giveback() {
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig {
+            breakeven: Some(BreakevenConfig { trigger_pct: 1.0, fee_pct: 0.2, trail_pct: 0.0 }),
+            ..MStrikeConfig::default()
+        });
+        let deltas = Deltas::default();
+        let signal = strategy.on_tick(&tick_at(700, 96.1, TradeSide::Buy, 0.1), &deltas);
+        let MStrikeSignal::PlaceBuy { price, size, .. } = signal else { panic!("{:?}", signal) };
+        strategy.on_buy_filled(price, size).unwrap();
+        
+        // +1% от входа: стоп переносится на вход + комиссии
+        assert!(matches!(strategy.on_tick(&tick_at(800, 97.0, TradeSide::Buy, 0.1), &deltas), MStrikeSignal::NoAction));
+        let stop = strategy.view().stop_price.unwrap();
+        assert!((stop - price * 1.002).abs() < 1e-9);
+        
+        let signal = strategy.on_tick(&tick_at(900, 96.1, TradeSide::Sell, 0.1), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceSell { price, .. } if (price - 96.0).abs() < 1e-9), "{:?}", signal);
+        assert!(matches!(strategy.on_tick(&tick_at(1000, 95.0, TradeSide::Sell, 0.1), &deltas), MStrikeSignal::NoAction));
+        
+        assert_eq!(strategy.view().phase, Phase::Exiting);
+        strategy.on_sell_filled(size).unwrap();
+        assert!(strategy.view().stop_price.is_none());
+    }
+    
+    #[test]
+    fn test_timer_expires_dip_wait_and_time_stop_without_ticks() {
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig {
+            mstrike_wait_dip_timeout: 5_000,
+            ..MStrikeConfig::default()
+        });
+        let at = |ms: i64| tick_at(ms, 0.0, TradeSide::Buy, 0.0).timestamp;
+        // Часы рантайма отстают от биржи - отрицательное ожидание не таймаут
+        assert!(matches!(strategy.on_timer(at(0)), MStrikeSignal::NoAction));
+        assert!(strategy.view().waiting_for_dip);
+        assert!(matches!(strategy.on_timer(at(5_700)), MStrikeSignal::NoAction));
+        assert_eq!(strategy.view().phase, Phase::Idle);
+        
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig {
+            time_stop: Some(TimeStopConfig { max_age_ms: 60_000, exit: TimeStopExit::Market }),
+            ..MStrikeConfig::default()
+        });
+        let signal = strategy.on_tick(&tick_at(700, 96.1, TradeSide::Buy, 0.1), &Deltas::default());
+        let MStrikeSignal::PlaceBuy { price, size, .. } = signal else { panic!("{:?}", signal) };
+        strategy.on_buy_filled(price, size).unwrap();
+        assert!(matches!(strategy.on_timer(at(30_000)), MStrikeSignal::NoAction));
+        // Тиков больше нет: выход по последнему биду
+        let signal = strategy.on_timer(at(61_000));
+        assert!(matches!(signal, MStrikeSignal::PlaceSell { price, .. } if (price - 96.0).abs() < 1e-9), "{:?}", signal);
+        assert_eq!(strategy.view().phase, Phase::Exiting);
+        assert!(matches!(strategy.on_timer(at(62_000)), MStrikeSignal::NoAction));
+    }
+    
+    #[test]
+    fn test_unexpected_fills_do_not_corrupt_state() {
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig::default());
+        let deltas = Deltas::default();
+        assert_eq!(strategy.view().phase, Phase::Detected);
+        assert!(strategy.on_sell_filled(1.0).is_err());
+        assert!(strategy.on_buy_declined().is_err());
+        
+        let signal = strategy.on_tick(&tick_at(700, 96.1, TradeSide::Buy, 0.1), &deltas);
+        let MStrikeSignal::PlaceBuy { price, size, .. } = signal else { panic!("{:?}", signal) };
+        assert_eq!(strategy.pending_orders(), vec![OrderIntent::buy(price, size)]);
+        // Продажа раньше покупки отвергается, buy продолжает стоять без сигналов
+        assert!(matches!(strategy.on_sell_filled(size), Err(ExecutionError::IllegalTransition { context: "on_sell_filled", .. })));
+        assert!(matches!(strategy.on_tick(&tick_at(800, 99.0, TradeSide::Buy, 0.1), &deltas), MStrikeSignal::NoAction));
+        
+        // Buy исполнился двумя частями: средняя цена и полный размер
+        strategy.on_buy_filled(96.0, size / 2.0).unwrap();
+        strategy.on_buy_filled(97.0, size / 2.0).unwrap();
+        let view = strategy.view();
+        assert_eq!((view.phase, view.position_size), (Phase::Positioned, size));
+        assert!((view.entry_price.unwrap() - 96.5).abs() < 1e-9);
+        
+        strategy.on_sell_filled(size / 2.0).unwrap();
+        assert_eq!((strategy.view().phase, strategy.view().position_size), (Phase::Positioned, size / 2.0));
+        strategy.on_sell_filled(size / 2.0).unwrap();
+        assert_eq!(strategy.view().phase, Phase::Idle);
+        assert!(!strategy.view().tracking_strike);
+        
+        // Повторный фил продажи после закрытия
+        assert!(strategy.on_sell_filled(size).is_err());
+        assert!(strategy.on_buy_filled(96.0, size).is_err());
+        assert_eq!(strategy.view().entry_price, None);
+    }
+    
+    #[test]
+    fn test_sell_front_runs_ask_wall() {
+        let mut strategy = strategy_waiting_for_dip(MStrikeConfig {
+            ask_wall: Some(AskWallConfig { wall_ratio: 5.0, max_distance_pct: 1.0, tick_offset: 2, tick_size: 0.01 }),
+            ..MStrikeConfig::default()
+        });
+        let deltas = Deltas::default();
+        let signal = strategy.on_tick(&tick_at(700, 96.1, TradeSide::Buy, 0.1), &deltas);
+        let MStrikeSignal::PlaceBuy { price, size, .. } = signal else { panic!("{:?}", signal) };
+        strategy.on_buy_filled(price, size).unwrap();
+        
+        // Без стакана цель - обычный MStrikeSellLevel выше 97.9
+        assert!(matches!(strategy.on_tick(&tick_at(800, 97.9, TradeSide::Buy, 0.1), &deltas), MStrikeSignal::NoAction));
+        
+        strategy.on_book(&[(97.0, 10.0), (97.5, 12.0), (97.9, 90.0), (98.5, 11.0), (99.0, 9.0)]);
+        assert!((strategy.pending_orders()[0].price - 97.88).abs() < 1e-9);
+        let signal = strategy.on_tick(&tick_at(900, 97.9, TradeSide::Buy, 0.1), &deltas);
+        assert!(matches!(signal, MStrikeSignal::PlaceSell { price, .. } if (price - 97.88).abs() < 1e-9), "{:?}", signal);
+    }
 }
@@ -0,0 +1,92 @@
+//! Защищённая арифметика детекта
+//!
+//! Глубина прострела делится на цену до него. Нулевая, отрицательная или NaN-цена
+//! даёт бесконечную или отрицательную глубину, и детект срабатывает на мусоре.
+//! Функции здесь возвращают ошибку, а стратегия превращает её в явный сигнал
+//! `Error` (`MarketDataError::InvalidTick`) и сбрасывает испорченный детект.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumericError {
+    /// База процента (цена до прострела, максимум окна) не положительна
+    NonPositiveBase(f64),
+    /// Значение NaN или бесконечность
+    NonFinite(f64),
+    /// Минимум выше максимума: окно или состояние детекта испорчено
+    Inverted { high: f64, low: f64 },
+}
+
+impl fmt::Display for NumericError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumericError::NonPositiveBase(base) => write!(f, "non-positive base price {}", base),
+            NumericError::NonFinite(value) => write!(f, "non-finite value {}", value),
+            NumericError::Inverted { high, low } => write!(f, "low {} above high {}", low, high),
+        }
+    }
+}
+
+impl std::error::Error for NumericError {}
+
+/// Цена пригодна для расчёта: конечная и положительная
+#[inline(always)]
+pub fn valid_price(price: f64) -> bool {
+    price > 0.0 && price.is_finite()
+}
+
+/// Объём сделки: конечный и неотрицательный
+#[inline(always)]
+pub fn valid_volume(volume: f64) -> bool {
+    volume >= 0.0 && volume.is_finite()
+}
+
+fn checked_price(price: f64) -> Result<f64, NumericError> {
+    if !price.is_finite() {
+        Err(NumericError::NonFinite(price))
+    } else if price <= 0.0 {
+        Err(NumericError::NonPositiveBase(price))
+    } else {
+        Ok(price)
+    }
+}
+
+/// Глубина падения от `high` до `low`, %
+#[inline]
+pub fn depth_pct(high: f64, low: f64) -> Result<f64, NumericError> {
+    let high = checked_price(high)?;
+    if !low.is_finite() {
+        return Err(NumericError::NonFinite(low));
+    }
+    if low > high {
+        return Err(NumericError::Inverted { high, low });
+    }
+    Ok((high - low) / high * 100.0)
+}
+
+/// Изменение от `from` до `to`, %
+#[inline]
+pub fn change_pct(from: f64, to: f64) -> Result<f64, NumericError> {
+    let from = checked_price(from)?;
+    if !to.is_finite() {
+        return Err(NumericError::NonFinite(to));
+    }
+    Ok((to - from) / from * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_rejects_garbage_instead_of_dividing() {
+        assert_eq!(depth_pct(100.0, 95.0), Ok(5.0));
+        assert_eq!(depth_pct(0.0, 95.0), Err(NumericError::NonPositiveBase(0.0)));
+        assert_eq!(depth_pct(-1.0, -2.0), Err(NumericError::NonPositiveBase(-1.0)));
+        assert!(matches!(depth_pct(f64::NAN, 1.0), Err(NumericError::NonFinite(_))));
+        assert!(matches!(depth_pct(100.0, f64::INFINITY), Err(NumericError::NonFinite(_))));
+        assert_eq!(depth_pct(100.0, 101.0), Err(NumericError::Inverted { high: 100.0, low: 101.0 }));
+        assert_eq!(change_pct(50.0, 55.0), Ok(10.0));
+        assert!(change_pct(0.0, 1.0).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Фаза жизненного цикла входа: Idle → Detected → OrderResting → Positioned → Exiting
+//!
+//! Раньше фаза Hook и MStrike выводилась из набора `Option` (`buy_price`, `active_order_id`,
+//! `forced_exit_price`), и неожиданный фил — продажа без позиции, покупка после отказа —
+//! молча перезаписывал их в несогласованное сочетание. Теперь фаза хранится явно, фил
+//! проверяется по таблице переходов, а недопустимый переход возвращает
+//! `ExecutionError::IllegalTransition` и оставляет состояние как было.
+
+use crate::error::ExecutionError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    /// Нет ни ордера, ни позиции (детект Hook может оставаться зафиксированным до перевзведения)
+    #[default]
+    Idle,
+    /// Детект зафиксирован, вход ещё не выставлен (ожидание разворота, задержка)
+    Detected,
+    /// Buy выставлен, фила ещё не было
+    OrderResting,
+    /// Позиция открыта, стратегия ведёт цель продажи и стопы
+    Positioned,
+    /// Выставлен принудительный выход (TimeStop или стоп), ждём его фила
+    Exiting,
+}
+
+impl Phase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Idle => "Idle",
+            Phase::Detected => "Detected",
+            Phase::OrderResting => "OrderResting",
+            Phase::Positioned => "Positioned",
+            Phase::Exiting => "Exiting",
+        }
+    }
+
+    /// Позиция открыта (частичный выход её не закрывает)
+    pub fn has_position(self) -> bool {
+        matches!(self, Phase::Positioned | Phase::Exiting)
+    }
+
+    /// Таблица переходов. Positioned → Positioned — добор позиции частичным филом buy,
+    /// Detected → Detected — повторный детект до выставления входа
+    pub fn allows(self, to: Phase) -> bool {
+        use Phase::*;
+        matches!(
+            (self, to),
+            (Idle, Detected)
+                | (Detected, Detected)
+                | (Detected, OrderResting)
+                | (Detected, Idle)
+                | (OrderResting, Positioned)
+                | (OrderResting, Idle)
+                | (Positioned, Positioned)
+                | (Positioned, Exiting)
+                | (Positioned, Idle)
+                | (Exiting, Idle)
+        )
+    }
+
+    /// Переход в `to`; недопустимый переход фазу не меняет
+    pub fn advance(&mut self, to: Phase, strategy: &'static str, context: &'static str) -> Result<(), ExecutionError> {
+        if !self.allows(to) {
+            return Err(self.rejection(to, strategy, context));
+        }
+        *self = to;
+        Ok(())
+    }
+
+    /// Ошибка перехода, который таблица разрешает, но событие `context` - нет
+    /// (например, фил продажи в Detected)
+    pub fn rejection(self, to: Phase, strategy: &'static str, context: &'static str) -> ExecutionError {
+        ExecutionError::IllegalTransition { strategy, from: self.as_str(), to: to.as_str(), context }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_illegal_transitions_keep_phase() {
+        let mut phase = Phase::Idle;
+        for to in [Phase::Detected, Phase::OrderResting, Phase::Positioned, Phase::Exiting, Phase::Idle] {
+            phase.advance(to, "Test", "test").unwrap();
+        }
+
+        // Фил продажи без позиции
+        let err = phase.advance(Phase::Idle, "Test", "on_sell_filled").unwrap_err();
+        assert_eq!(err.to_string(), "Test: Idle -> Idle is not allowed in on_sell_filled");
+        assert_eq!(phase, Phase::Idle);
+
+        // Фил покупки после выставленного выхода
+        let mut phase = Phase::Exiting;
+        assert!(phase.advance(Phase::Positioned, "Test", "on_buy_filled").is_err());
+        assert_eq!(phase, Phase::Exiting);
+        assert!(!Phase::Idle.allows(Phase::Positioned));
+        assert!(Phase::Exiting.has_position() && !Phase::OrderResting.has_position());
+    }
+}
@@ -0,0 +1,113 @@
+//! Скользящие минимум/максимум по времени за амортизированное O(1)
+//!
+//! Монотонные деки: в `mins` значения возрастают, в `maxs` убывают, время в обоих растет.
+//! Точки, которые уже не могут стать экстремумом, выбрасываются при вставке, поэтому
+//! min/max окна всегда лежат во фронте. В установившемся режиме аллокаций нет:
+//! VecDeque переиспользует свою емкость.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RollingMinMax {
+    mins: VecDeque<(DateTime<Utc>, f64)>,
+    maxs: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl RollingMinMax {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавить точку; время должно быть неубывающим
+    #[inline]
+    pub fn push(&mut self, timestamp: DateTime<Utc>, value: f64) {
+        while matches!(self.mins.back(), Some(&(_, v)) if v >= value) {
+            self.mins.pop_back();
+        }
+        self.mins.push_back((timestamp, value));
+
+        while matches!(self.maxs.back(), Some(&(_, v)) if v <= value) {
+            self.maxs.pop_back();
+        }
+        self.maxs.push_back((timestamp, value));
+    }
+
+    /// Удалить точки старше `cutoff`
+    #[inline]
+    pub fn evict_before(&mut self, cutoff: DateTime<Utc>) {
+        while matches!(self.mins.front(), Some(&(t, _)) if t < cutoff) {
+            self.mins.pop_front();
+        }
+        while matches!(self.maxs.front(), Some(&(t, _)) if t < cutoff) {
+            self.maxs.pop_front();
+        }
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.mins.front().map(|&(_, v)| v)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.maxs.front().map(|&(_, v)| v)
+    }
+
+    /// Минимум по точкам не старше `cutoff` без изменения состояния
+    pub fn min_since(&self, cutoff: DateTime<Utc>) -> Option<f64> {
+        let idx = self.mins.partition_point(|&(t, _)| t < cutoff);
+        self.mins.get(idx).map(|&(_, v)| v)
+    }
+
+    /// Максимум по точкам не старше `cutoff` без изменения состояния
+    pub fn max_since(&self, cutoff: DateTime<Utc>) -> Option<f64> {
+        let idx = self.maxs.partition_point(|&(t, _)| t < cutoff);
+        self.maxs.get(idx).map(|&(_, v)| v)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mins.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.mins.clear();
+        self.maxs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_matches_naive_window(
+            prices in proptest::collection::vec(0.01f64..1000.0, 1..200),
+            window in 1i64..50,
+        ) {
+            let base = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+            let mut rolling = RollingMinMax::new();
+            for (i, &price) in prices.iter().enumerate() {
+                let now = base + Duration::seconds(i as i64);
+                let cutoff = now - Duration::seconds(window);
+                rolling.push(now, price);
+                rolling.evict_before(cutoff);
+
+                let start = (i as i64 - window).max(0) as usize;
+                let naive = &prices[start..=i];
+                let min = naive.iter().cloned().fold(f64::INFINITY, f64::min);
+                let max = naive.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                prop_assert_eq!(rolling.min(), Some(min));
+                prop_assert_eq!(rolling.max(), Some(max));
+
+                let half = now - Duration::seconds(window / 2);
+                let start = (i as i64 - window / 2).max(0) as usize;
+                let naive = &prices[start..=i];
+                prop_assert_eq!(rolling.min_since(half), Some(naive.iter().cloned().fold(f64::INFINITY, f64::min)));
+                prop_assert_eq!(rolling.max_since(half), Some(naive.iter().cloned().fold(f64::NEG_INFINITY, f64::max)));
+            }
+        }
+    }
+}
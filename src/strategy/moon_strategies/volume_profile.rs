@@ -0,0 +1,168 @@
+//! Профиль объёма: гистограмма объёма по ценовым корзинам за скользящее окно
+//!
+//! Корзины логарифмические, шириной `bucket_pct` %: одна настройка годится и для
+//! BTC, и для монеты за цент. Узел большого объёма (HVN) — корзина, где объём не ниже
+//! `node_ratio` × среднего по непустым корзинам и не меньше соседей; точка контроля
+//! (POC) — корзина с наибольшим объёмом. На таких уровнях рынок долго торговался,
+//! поэтому они держат цену лучше, чем границы, посчитанные от глубины прострела.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VolumeProfileConfig {
+    /// Окно профиля (мс)
+    pub lookback_ms: u64,
+    /// Ширина ценовой корзины (%)
+    pub bucket_pct: f64,
+    /// Во сколько раз объём узла выше среднего по корзинам
+    pub node_ratio: f64,
+}
+
+impl Default for VolumeProfileConfig {
+    fn default() -> Self {
+        Self { lookback_ms: 3_600_000, bucket_pct: 0.2, node_ratio: 1.5 }
+    }
+}
+
+impl VolumeProfileConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.lookback_ms == 0 {
+            bail!("volume_profile.lookback_ms must be positive");
+        }
+        if !(self.bucket_pct > 0.0 && self.bucket_pct.is_finite()) {
+            bail!("volume_profile.bucket_pct {} must be positive", self.bucket_pct);
+        }
+        if !(self.node_ratio > 0.0 && self.node_ratio.is_finite()) {
+            bail!("volume_profile.node_ratio {} must be positive", self.node_ratio);
+        }
+        Ok(())
+    }
+
+    pub fn profile(&self) -> VolumeProfile {
+        VolumeProfile::new(Duration::milliseconds(self.lookback_ms as i64), self.bucket_pct)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VolumeProfile {
+    lookback: Duration,
+    /// ln(1 + bucket_pct / 100)
+    log_step: f64,
+    trades: VecDeque<(DateTime<Utc>, i64, f64)>,
+    buckets: BTreeMap<i64, f64>,
+}
+
+impl VolumeProfile {
+    pub fn new(lookback: Duration, bucket_pct: f64) -> Self {
+        assert!(bucket_pct > 0.0, "bucket_pct must be positive, got {}", bucket_pct);
+        Self {
+            lookback,
+            log_step: (1.0 + bucket_pct / 100.0).ln(),
+            trades: VecDeque::new(),
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Сделка; время должно быть неубывающим
+    pub fn push(&mut self, timestamp: DateTime<Utc>, price: f64, volume: f64) {
+        if !(price > 0.0 && price.is_finite() && volume > 0.0 && volume.is_finite()) {
+            return;
+        }
+        let bucket = self.bucket(price);
+        *self.buckets.entry(bucket).or_default() += volume;
+        self.trades.push_back((timestamp, bucket, volume));
+
+        let cutoff = timestamp - self.lookback;
+        while let Some(&(time, bucket, volume)) = self.trades.front() {
+            if time >= cutoff {
+                break;
+            }
+            self.trades.pop_front();
+            if let Some(total) = self.buckets.get_mut(&bucket) {
+                *total -= volume;
+                // Остаток от округления не должен оставлять пустую корзину в профиле
+                if *total <= volume * 1e-9 {
+                    self.buckets.remove(&bucket);
+                }
+            }
+        }
+    }
+
+    fn bucket(&self, price: f64) -> i64 {
+        (price.ln() / self.log_step).floor() as i64
+    }
+
+    /// Середина корзины (геометрическая)
+    fn bucket_price(&self, bucket: i64) -> f64 {
+        ((bucket as f64 + 0.5) * self.log_step).exp()
+    }
+
+    /// Гистограмма (цена середины корзины, объём) по возрастанию цены
+    pub fn histogram(&self) -> Vec<(f64, f64)> {
+        self.buckets.iter().map(|(&b, &v)| (self.bucket_price(b), v)).collect()
+    }
+
+    /// Точка контроля: цена корзины с наибольшим объёмом
+    pub fn point_of_control(&self) -> Option<f64> {
+        self.buckets
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(&bucket, _)| self.bucket_price(bucket))
+    }
+
+    /// Узлы большого объёма по возрастанию цены
+    pub fn high_volume_nodes(&self, node_ratio: f64) -> Vec<f64> {
+        if self.buckets.is_empty() {
+            return Vec::new();
+        }
+        let mean = self.buckets.values().sum::<f64>() / self.buckets.len() as f64;
+        let volume = |bucket: i64| self.buckets.get(&bucket).copied().unwrap_or(0.0);
+        self.buckets
+            .iter()
+            .filter(|&(&bucket, &v)| v >= mean * node_ratio && v >= volume(bucket - 1) && v >= volume(bucket + 1))
+            .map(|(&bucket, _)| self.bucket_price(bucket))
+            .collect()
+    }
+
+    /// Ближайший узел в диапазоне `[from, to]`, считая от `to` вниз
+    pub fn highest_node_within(&self, node_ratio: f64, from: f64, to: f64) -> Option<f64> {
+        self.high_volume_nodes(node_ratio).into_iter().rev().find(|&p| p >= from && p <= to)
+    }
+
+    /// Ближайший узел в диапазоне `[from, to]`, считая от `from` вверх
+    pub fn lowest_node_within(&self, node_ratio: f64, from: f64, to: f64) -> Option<f64> {
+        self.high_volume_nodes(node_ratio).into_iter().find(|&p| p >= from && p <= to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_nodes_and_point_of_control_follow_the_window() {
+        let at = |ms: i64| Utc.timestamp_millis_opt(ms).unwrap();
+        let mut profile = VolumeProfile::new(Duration::seconds(10), 1.0);
+        // Плотная торговля у 100 и у 95, тонко между ними
+        for (ms, price, volume) in [(0, 100.0, 50.0), (100, 99.0, 2.0), (200, 97.5, 1.0), (300, 96.0, 3.0), (400, 95.0, 30.0)] {
+            profile.push(at(ms), price, volume);
+        }
+        let nodes = profile.high_volume_nodes(1.5);
+        assert_eq!(nodes.len(), 2);
+        assert!((nodes[0] - 95.0).abs() / 95.0 < 0.01 && (nodes[1] - 100.0).abs() / 100.0 < 0.01, "{:?}", nodes);
+        assert!((profile.point_of_control().unwrap() - 100.0).abs() < 1.0);
+        assert_eq!(profile.highest_node_within(1.5, 94.0, 99.0), Some(nodes[0]));
+        assert_eq!(profile.lowest_node_within(1.5, 96.0, 101.0), Some(nodes[1]));
+        assert_eq!(profile.highest_node_within(1.5, 96.0, 99.0), None);
+
+        // Через 10 с узел у 100 выпал из окна
+        profile.push(at(10_050), 95.0, 1.0);
+        assert!((profile.point_of_control().unwrap() - 95.0).abs() < 1.0);
+        assert_eq!(profile.histogram().len(), 4);
+        assert_eq!(profile.histogram().iter().map(|(_, v)| v).sum::<f64>(), 37.0);
+    }
+}
@@ -0,0 +1,327 @@
+//! Окно по времени: точки `(время, значение)` с инкрементальными агрегатами
+//!
+//! Окно само не знает своей длины: владелец вызывает `evict_before` с нужной отсечкой
+//! или `evict_to_len`, поэтому снимок стратегии — просто последовательность точек, как
+//! у прежнего `VecDeque<(DateTime<Utc>, T)>`, а агрегаты пересчитываются при загрузке.
+//! Агрегат видит каждую точку дважды — при вставке и при вытеснении — и держит сумму,
+//! экстремумы или своё (`FlowSums` в `flow.rs`) без прохода по окну. Точка вытесняется
+//! всегда из начала, поэтому агрегату хватает порядкового номера, чтобы её узнать.
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::VecDeque;
+
+/// Числовое значение точки для стандартных агрегатов
+pub trait Measure {
+    fn measure(&self) -> f64;
+}
+
+impl Measure for f64 {
+    #[inline]
+    fn measure(&self) -> f64 {
+        *self
+    }
+}
+
+/// Инкрементальный агрегат окна. `seq` растёт на единицу с каждой вставкой;
+/// `remove` вызывается в том же порядке, что и `add`
+pub trait Aggregate<T>: Default {
+    fn add(&mut self, seq: u64, value: &T);
+    fn remove(&mut self, seq: u64, value: &T);
+}
+
+impl<T> Aggregate<T> for () {
+    #[inline]
+    fn add(&mut self, _seq: u64, _value: &T) {}
+    #[inline]
+    fn remove(&mut self, _seq: u64, _value: &T) {}
+}
+
+impl<T, A: Aggregate<T>, B: Aggregate<T>> Aggregate<T> for (A, B) {
+    #[inline]
+    fn add(&mut self, seq: u64, value: &T) {
+        self.0.add(seq, value);
+        self.1.add(seq, value);
+    }
+    #[inline]
+    fn remove(&mut self, seq: u64, value: &T) {
+        self.0.remove(seq, value);
+        self.1.remove(seq, value);
+    }
+}
+
+/// Сумма и число точек
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Sum {
+    sum: f64,
+    count: usize,
+}
+
+impl Sum {
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+impl<T: Measure> Aggregate<T> for Sum {
+    #[inline]
+    fn add(&mut self, _seq: u64, value: &T) {
+        self.sum += value.measure();
+        self.count += 1;
+    }
+    #[inline]
+    fn remove(&mut self, _seq: u64, value: &T) {
+        self.count -= 1;
+        // Пустое окно сбрасывает накопленную ошибку округления
+        self.sum = if self.count == 0 { 0.0 } else { self.sum - value.measure() };
+    }
+}
+
+/// Минимум и максимум на монотонных деках, амортизированное O(1), как `RollingMinMax`
+#[derive(Debug, Clone, Default)]
+pub struct Extremes {
+    mins: VecDeque<(u64, f64)>,
+    maxs: VecDeque<(u64, f64)>,
+}
+
+impl Extremes {
+    pub fn min(&self) -> Option<f64> {
+        self.mins.front().map(|&(_, v)| v)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.maxs.front().map(|&(_, v)| v)
+    }
+}
+
+impl<T: Measure> Aggregate<T> for Extremes {
+    #[inline]
+    fn add(&mut self, seq: u64, value: &T) {
+        let value = value.measure();
+        while self.mins.back().is_some_and(|&(_, v)| v >= value) {
+            self.mins.pop_back();
+        }
+        self.mins.push_back((seq, value));
+        while self.maxs.back().is_some_and(|&(_, v)| v <= value) {
+            self.maxs.pop_back();
+        }
+        self.maxs.push_back((seq, value));
+    }
+    #[inline]
+    fn remove(&mut self, seq: u64, _value: &T) {
+        if self.mins.front().is_some_and(|&(s, _)| s == seq) {
+            self.mins.pop_front();
+        }
+        if self.maxs.front().is_some_and(|&(s, _)| s == seq) {
+            self.maxs.pop_front();
+        }
+    }
+}
+
+/// Точки с неубывающим временем и агрегат `A` по ним
+#[derive(Debug, Clone)]
+pub struct TimeWindow<T, A = ()> {
+    items: VecDeque<(DateTime<Utc>, T)>,
+    /// Порядковый номер точки в начале окна
+    head: u64,
+    aggregate: A,
+}
+
+impl<T, A: Default> Default for TimeWindow<T, A> {
+    fn default() -> Self {
+        Self { items: VecDeque::new(), head: 0, aggregate: A::default() }
+    }
+}
+
+impl<T, A: Aggregate<T>> TimeWindow<T, A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавить точку; время должно быть неубывающим
+    #[inline]
+    pub fn push(&mut self, timestamp: DateTime<Utc>, value: T) {
+        debug_assert!(self.items.back().is_none_or(|&(t, _)| t <= timestamp), "time window went back in time");
+        self.aggregate.add(self.head + self.items.len() as u64, &value);
+        self.items.push_back((timestamp, value));
+    }
+
+    /// Вытеснить самую старую точку
+    #[inline]
+    pub fn pop_front(&mut self) -> Option<(DateTime<Utc>, T)> {
+        let (timestamp, value) = self.items.pop_front()?;
+        self.aggregate.remove(self.head, &value);
+        self.head += 1;
+        Some((timestamp, value))
+    }
+
+    /// Удалить точки старше `cutoff`; возвращает число удалённых
+    #[inline]
+    pub fn evict_before(&mut self, cutoff: DateTime<Utc>) -> usize {
+        let mut evicted = 0;
+        while self.items.front().is_some_and(|&(t, _)| t < cutoff) {
+            self.pop_front();
+            evicted += 1;
+        }
+        evicted
+    }
+
+    /// Оставить не больше `len` последних точек
+    #[inline]
+    pub fn evict_to_len(&mut self, len: usize) {
+        while self.items.len() > len {
+            self.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+}
+
+impl<T, A> TimeWindow<T, A> {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn front(&self) -> Option<&(DateTime<Utc>, T)> {
+        self.items.front()
+    }
+
+    pub fn back(&self) -> Option<&(DateTime<Utc>, T)> {
+        self.items.back()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&(DateTime<Utc>, T)> {
+        self.items.get(index)
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(DateTime<Utc>, T)> + ExactSizeIterator {
+        self.items.iter()
+    }
+
+    /// Последние `n` точек (или все, если их меньше)
+    pub fn last_n(&self, n: usize) -> impl DoubleEndedIterator<Item = &(DateTime<Utc>, T)> + ExactSizeIterator {
+        self.items.range(self.items.len().saturating_sub(n)..)
+    }
+
+    /// Первая точка не старше `cutoff`, бинарным поиском
+    pub fn first_since(&self, cutoff: DateTime<Utc>) -> Option<&(DateTime<Utc>, T)> {
+        self.items.get(self.items.partition_point(|&(t, _)| t < cutoff))
+    }
+
+    /// Точки не старше `cutoff` без изменения окна
+    pub fn since(&self, cutoff: DateTime<Utc>) -> impl DoubleEndedIterator<Item = &(DateTime<Utc>, T)> + ExactSizeIterator {
+        self.items.range(self.items.partition_point(|&(t, _)| t < cutoff)..)
+    }
+}
+
+impl<T: Measure, A> TimeWindow<T, A> {
+    /// Сумма за O(n); для горячего пути — агрегат `Sum`
+    pub fn sum(&self) -> f64 {
+        self.items.iter().map(|(_, v)| v.measure()).sum()
+    }
+}
+
+/// Снимок — последовательность точек, как у `VecDeque<(DateTime<Utc>, T)>`
+impl<T: Serialize, A> Serialize for TimeWindow<T, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.items.iter())
+    }
+}
+
+impl<'de, T: DeserializeOwned, A: Aggregate<T>> Deserialize<'de> for TimeWindow<T, A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = VecDeque::<(DateTime<Utc>, T)>::deserialize(deserializer)?;
+        if items.iter().zip(items.iter().skip(1)).any(|(a, b)| a.0 > b.0) {
+            return Err(serde::de::Error::custom("time window points are not in time order"));
+        }
+        let mut window = Self::new();
+        for (timestamp, value) in items {
+            window.push(timestamp, value);
+        }
+        Ok(window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use proptest::prelude::*;
+
+    type Stats = TimeWindow<f64, (Sum, Extremes)>;
+
+    proptest! {
+        #[test]
+        fn prop_aggregates_match_naive_window(
+            points in proptest::collection::vec((0i64..3, -1000.0f64..1000.0), 1..200),
+            span in 1i64..40,
+            max_len in 1usize..60,
+        ) {
+            let base = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+            let mut window = Stats::new();
+            let mut naive: VecDeque<(DateTime<Utc>, f64)> = VecDeque::new();
+            let mut now = base;
+            for (step, value) in points {
+                // Шаг 0 даёт точки с одинаковым временем
+                now += Duration::seconds(step);
+                window.push(now, value);
+                window.evict_before(now - Duration::seconds(span));
+                window.evict_to_len(max_len);
+
+                naive.push_back((now, value));
+                naive.retain(|&(t, _)| t >= now - Duration::seconds(span));
+                while naive.len() > max_len {
+                    naive.pop_front();
+                }
+
+                let values: Vec<f64> = naive.iter().map(|&(_, v)| v).collect();
+                let (sum, extremes) = window.aggregate();
+                prop_assert_eq!(window.len(), values.len());
+                prop_assert_eq!(sum.count(), values.len());
+                prop_assert!((sum.sum() - values.iter().sum::<f64>()).abs() < 1e-6);
+                prop_assert_eq!(extremes.min(), values.iter().cloned().reduce(f64::min));
+                prop_assert_eq!(extremes.max(), values.iter().cloned().reduce(f64::max));
+                prop_assert!(window.iter().map(|&(_, v)| v).eq(values.iter().cloned()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_snapshot_is_a_plain_sequence() {
+        let at = |s: i64| Utc.timestamp_opt(1_700_000_000 + s, 0).unwrap();
+        let mut window = Stats::new();
+        for (s, v) in [(0, 3.0), (1, 1.0), (2, 2.0)] {
+            window.push(at(s), v);
+        }
+        let json = serde_json::to_string(&window).unwrap();
+        let plain: VecDeque<(DateTime<Utc>, f64)> = window.iter().cloned().collect();
+        assert_eq!(json, serde_json::to_string(&plain).unwrap());
+
+        let restored: Stats = serde_json::from_str(&json).unwrap();
+        assert_eq!((restored.aggregate().0.mean(), restored.aggregate().1.max()), (Some(2.0), Some(3.0)));
+        assert_eq!(restored.first_since(at(1)).map(|&(_, v)| v), Some(1.0));
+        assert_eq!(restored.last_n(2).map(|&(_, v)| v).collect::<Vec<_>>(), [1.0, 2.0]);
+
+        let shuffled = serde_json::to_string(&[(at(1), 1.0), (at(0), 3.0)]).unwrap();
+        assert!(serde_json::from_str::<Stats>(&shuffled).is_err());
+    }
+}
@@ -2,11 +2,13 @@
 
 use std::time::{Duration, Instant};
 
+use anyhow::Result;
 use serde::Deserialize;
 
 use crate::base_classes::types::Side;
 use crate::execution::{
-    ClientOrderId, ExecutionReport, OrderStatus, QuoteIntent, TimeInForce, Venue,
+    ClientOrderId, ClientOrderIdGenerator, ExecutionReport, OrderStatus, Price, PricePrecision, Qty, QuoteIntent,
+    Rounding, TimeInForce, Venue,
 };
 
 const DEFAULT_REPRICE_BPS: f64 = 2.0;
@@ -64,7 +66,10 @@ pub struct ReferenceMeta {
 
 pub struct SimpleQuoteStrategy {
     config: QuoteConfig,
-    next_id: u64,
+    precision: PricePrecision,
+    // Раздельные генераторы: `-b-`/`-s-` в id - сторона для InventoryTracker
+    bid_ids: ClientOrderIdGenerator,
+    ask_ids: ClientOrderIdGenerator,
     last_reference: Option<f64>,
     last_refresh_at: Option<Instant>,
     active_orders: Vec<ClientOrderId>,
@@ -77,9 +82,14 @@ pub struct SimpleQuoteStrategy {
 
 impl SimpleQuoteStrategy {
     pub fn new(config: QuoteConfig) -> Self {
+        // lot_size = 0: размер уходит как есть, биржевой контракт считает шлюз
+        let precision = PricePrecision::from_f64(config.min_tick.max(1e-8), 0.0)
+            .expect("quote config min_tick must be finite");
         Self {
             config,
-            next_id: 0,
+            precision,
+            bid_ids: ClientOrderIdGenerator::new("b").expect("static client id tag"),
+            ask_ids: ClientOrderIdGenerator::new("s").expect("static client id tag"),
             last_reference: None,
             last_refresh_at: None,
             active_orders: Vec::new(),
@@ -122,19 +132,21 @@ impl SimpleQuoteStrategy {
         cancels
     }
 
-    pub fn plan_quotes(&mut self, now: Instant) -> Option<QuotePlan> {
-        let price = self.latest_price?;
+    pub fn plan_quotes(&mut self, now: Instant) -> Result<Option<QuotePlan>> {
+        let Some(price) = self.latest_price else {
+            return Ok(None);
+        };
 
         if !self.needs_requote {
-            return None;
+            return Ok(None);
         }
 
         if !self.cancel_buffer_elapsed(now) {
-            return None;
+            return Ok(None);
         }
 
         if !self.active_orders.is_empty() && !self.debounce_elapsed(now) {
-            return None;
+            return Ok(None);
         }
 
         let has_uncancelled_live_orders = self
@@ -142,18 +154,18 @@ impl SimpleQuoteStrategy {
             .iter()
             .any(|id| !self.pending_cancels.iter().any(|pending| pending == id));
         if has_uncancelled_live_orders {
-            return None;
+            return Ok(None);
         }
 
-        let intents = self.build_intents(price);
+        let intents = self.build_intents(price)?;
 
-        Some(QuotePlan {
+        Ok(Some(QuotePlan {
             reference_price: price,
             cancels: Vec::new(),
             intents,
             planned_at: now,
             reference_meta: self.latest_meta.clone(),
-        })
+        }))
     }
 
     pub fn commit_plan(&mut self, plan: &QuotePlan) {
@@ -177,7 +189,10 @@ impl SimpleQuoteStrategy {
 
     pub fn handle_report(&mut self, report: &ExecutionReport) {
         match report.status {
-            OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Rejected => {
+            OrderStatus::Filled
+            | OrderStatus::Canceled
+            | OrderStatus::PartiallyCanceled
+            | OrderStatus::Rejected => {
                 self.pending_cancels
                     .retain(|id| id != &report.client_order_id);
                 self.active_orders
@@ -217,7 +232,7 @@ impl SimpleQuoteStrategy {
         }
     }
 
-    fn build_intents(&mut self, mid: f64) -> Vec<QuoteIntent> {
+    fn build_intents(&mut self, mid: f64) -> Result<Vec<QuoteIntent>> {
         let mut spread = mid * self.config.spread_bps / 10_000.0;
         if spread < self.config.min_tick {
             spread = self.config.min_tick;
@@ -229,21 +244,21 @@ impl SimpleQuoteStrategy {
             self.config.venue,
             self.config.symbol.clone(),
             Side::Bid,
-            bid_px,
-            self.config.size,
+            Price::try_from_f64(bid_px)?.round_to(self.precision.tick_size, Rounding::Down),
+            Qty::try_from_f64(self.config.size)?,
             TimeInForce::PostOnly,
-            self.next_client_id("B"),
+            self.bid_ids.next_id()?,
         );
         let ask = QuoteIntent::new(
             self.config.venue,
             self.config.symbol.clone(),
             Side::Ask,
-            ask_px,
-            self.config.size,
+            Price::try_from_f64(ask_px)?.round_to(self.precision.tick_size, Rounding::Up),
+            Qty::try_from_f64(self.config.size)?,
             TimeInForce::PostOnly,
-            self.next_client_id("S"),
+            self.ask_ids.next_id()?,
         );
-        vec![bid, ask]
+        Ok(vec![bid, ask])
     }
 
     fn quote_levels(&self, mid: f64, half_spread: f64) -> (f64, f64) {
@@ -260,15 +275,6 @@ impl SimpleQuoteStrategy {
         (bid, ask)
     }
 
-    fn next_client_id(&mut self, side_tag: &str) -> ClientOrderId {
-        self.next_id = self.next_id.wrapping_add(1);
-        ClientOrderId::new(format!(
-            "t-gate-{}-{}",
-            side_tag.to_lowercase(),
-            self.next_id
-        ))
-    }
-
     fn debounce_duration(&self) -> Duration {
         Duration::from_millis(self.config.debounce_ms.max(1))
     }
@@ -0,0 +1,376 @@
+//! Инъекция сетевых сбоев для chaos-тестов и paper-режима
+//!
+//! `ChaosGateway` оборачивает любой `ExecutionGateway`: теряет часть ack, дублирует
+//! execution reports и придерживает отчёты с исполнением. `ChaosStream` делает то же
+//! с user-data стримом и вдобавок рвёт его: после обрыва часть событий теряется, а
+//! потребитель получает `StreamGap`, как от настоящего переподключения. Случайность
+//! детерминирована сидом, поэтому упавший прогон воспроизводится.
+
+use crate::execution::gateway::{ExecutionGateway, GatewayCapabilities};
+use crate::execution::money::{Price, Qty};
+use crate::execution::stops::{ServerTrigger, TriggerId, TriggerOrder};
+use crate::execution::types::{ClientOrderId, ExecutionReport, OrderAck, QuoteIntent};
+use crate::execution::user_stream::UserEvent;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone)]
+pub struct FaultConfig {
+    pub seed: u64,
+    /// Доля ack, которые теряются по дороге (заявка при этом ушла), 0..1
+    pub drop_ack: f64,
+    /// Доля отчётов/событий, приходящих дважды, 0..1
+    pub duplicate: f64,
+    /// Задержка отчётов и событий с исполнением
+    pub fill_delay: Duration,
+    /// Стрим рвётся на каждом N-м событии
+    pub disconnect_every: Option<u64>,
+    /// Сколько событий теряется за время обрыва
+    pub outage_events: u64,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            drop_ack: 0.0,
+            duplicate: 0.0,
+            fill_delay: Duration::ZERO,
+            disconnect_every: None,
+            outage_events: 0,
+        }
+    }
+}
+
+/// Что именно было сломано за прогон
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultStats {
+    pub dropped_acks: u64,
+    pub duplicated: u64,
+    pub delayed: u64,
+    pub disconnects: u64,
+    pub lost_events: u64,
+}
+
+/// xorshift64*: без внешних зависимостей и одинаковый на всех платформах
+#[derive(Debug, Clone)]
+struct FaultRng(u64);
+
+impl FaultRng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        if p <= 0.0 {
+            return false;
+        }
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let x = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        ((x >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+struct GatewayFaults {
+    rng: FaultRng,
+    held: VecDeque<(Instant, ExecutionReport)>,
+    stats: FaultStats,
+}
+
+pub struct ChaosGateway<G> {
+    inner: G,
+    config: FaultConfig,
+    faults: Mutex<GatewayFaults>,
+}
+
+impl<G: ExecutionGateway> ChaosGateway<G> {
+    pub fn new(inner: G, config: FaultConfig) -> Self {
+        let faults = GatewayFaults { rng: FaultRng::new(config.seed), held: VecDeque::new(), stats: FaultStats::default() };
+        Self { inner, config, faults: Mutex::new(faults) }
+    }
+
+    pub fn stats(&self) -> FaultStats {
+        self.faults.lock().unwrap().stats
+    }
+
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<G: ExecutionGateway> ExecutionGateway for ChaosGateway<G> {
+    fn capabilities(&self) -> GatewayCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn submit(&self, intents: &[QuoteIntent]) -> Result<Vec<OrderAck>> {
+        let acks = self.inner.submit(intents).await?;
+        let mut faults = self.faults.lock().unwrap();
+        let drop_ack = self.config.drop_ack;
+        Ok(acks
+            .into_iter()
+            .filter(|_| {
+                let lost = faults.rng.chance(drop_ack);
+                faults.stats.dropped_acks += lost as u64;
+                !lost
+            })
+            .collect())
+    }
+
+    async fn cancel_batch(&self, ids: &[ClientOrderId]) -> Result<()> {
+        self.inner.cancel_batch(ids).await
+    }
+
+    async fn poll_reports(&self) -> Result<Vec<ExecutionReport>> {
+        let reports = self.inner.poll_reports().await?;
+        let now = Instant::now();
+        let mut faults = self.faults.lock().unwrap();
+        let mut out = Vec::with_capacity(reports.len());
+        while faults.held.front().is_some_and(|(due, _)| *due <= now) {
+            let (_, report) = faults.held.pop_front().unwrap();
+            out.push(report);
+        }
+        for report in reports {
+            if report.filled_qty > 0.0 && !self.config.fill_delay.is_zero() {
+                faults.stats.delayed += 1;
+                faults.held.push_back((now + self.config.fill_delay, report));
+                continue;
+            }
+            if faults.rng.chance(self.config.duplicate) {
+                faults.stats.duplicated += 1;
+                out.push(report.clone());
+            }
+            out.push(report);
+        }
+        Ok(out)
+    }
+
+    // Остальное проходит без сбоев: иначе обёртка объявляет возможности, которых не даёт
+    async fn amend(&self, original: &QuoteIntent, price: Price, size: Qty) -> Result<OrderAck> {
+        self.inner.amend(original, price, size).await
+    }
+
+    async fn query_by_client_id(&self, id: &ClientOrderId) -> Result<Option<ExecutionReport>> {
+        self.inner.query_by_client_id(id).await
+    }
+
+    async fn place_trigger(&self, order: &TriggerOrder) -> Result<TriggerId> {
+        self.inner.place_trigger(order).await
+    }
+
+    async fn cancel_trigger(&self, id: &TriggerId) -> Result<()> {
+        self.inner.cancel_trigger(id).await
+    }
+
+    async fn list_triggers(&self, symbol: &str) -> Result<Vec<ServerTrigger>> {
+        self.inner.list_triggers(symbol).await
+    }
+}
+
+fn carries_fill(event: &UserEvent) -> bool {
+    match event {
+        UserEvent::Order(update) => update.filled > 0.0,
+        UserEvent::Trade(_) => true,
+        _ => false,
+    }
+}
+
+/// Сбои user-data стрима; синхронный фильтр, удобный в тестах без рантайма
+pub struct ChaosStream {
+    config: FaultConfig,
+    rng: FaultRng,
+    held: VecDeque<(Instant, UserEvent)>,
+    seen: u64,
+    /// Сколько событий ещё потеряется в текущем обрыве
+    outage_left: u64,
+    stats: FaultStats,
+}
+
+impl ChaosStream {
+    pub fn new(config: FaultConfig) -> Self {
+        Self {
+            rng: FaultRng::new(config.seed),
+            config,
+            held: VecDeque::new(),
+            seen: 0,
+            outage_left: 0,
+            stats: FaultStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> FaultStats {
+        self.stats
+    }
+
+    /// Событие от биржи -> то, что увидит потребитель (возможно, ничего или больше одного)
+    pub fn apply(&mut self, event: UserEvent, now: Instant) -> Vec<UserEvent> {
+        let mut out = self.release(now);
+        self.seen += 1;
+        if self.config.disconnect_every.is_some_and(|every| every > 0 && self.seen.is_multiple_of(every)) {
+            self.stats.disconnects += 1;
+            self.outage_left = self.config.outage_events;
+        }
+        if self.outage_left > 0 {
+            self.outage_left -= 1;
+            self.stats.lost_events += 1;
+            if self.outage_left == 0 {
+                out.push(UserEvent::StreamGap { reconnects: self.stats.disconnects });
+            }
+            return out;
+        }
+        if carries_fill(&event) && !self.config.fill_delay.is_zero() {
+            self.stats.delayed += 1;
+            self.held.push_back((now + self.config.fill_delay, event));
+            return out;
+        }
+        if self.rng.chance(self.config.duplicate) {
+            self.stats.duplicated += 1;
+            out.push(event.clone());
+        }
+        out.push(event);
+        out
+    }
+
+    /// Придержанные события, срок которых вышел
+    pub fn release(&mut self, now: Instant) -> Vec<UserEvent> {
+        let mut out = Vec::new();
+        while self.held.front().is_some_and(|(due, _)| *due <= now) {
+            out.push(self.held.pop_front().unwrap().1);
+        }
+        out
+    }
+
+    /// Ретранслятор для paper-режима: читает настоящий стрим и отдаёт испорченный.
+    /// Придержанные события досылаются по таймеру, даже если биржа молчит.
+    pub fn spawn_relay(mut self, mut input: mpsc::UnboundedReceiver<UserEvent>) -> mpsc::UnboundedReceiver<UserEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(Duration::from_millis(10));
+            loop {
+                let events = tokio::select! {
+                    event = input.recv() => match event {
+                        Some(event) => self.apply(event, Instant::now()),
+                        None => return,
+                    },
+                    _ = timer.tick() => self.release(Instant::now()),
+                };
+                for event in events {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::strategy_adapter::{HookAdapter, StrategyAction};
+    use crate::base_classes::symbol::Symbol;
+    use crate::base_classes::types::Side;
+    use crate::execution::user_stream::OrderUpdate;
+    use crate::execution::{DryRunGateway, ExchangeOrderId, OrderStatus, TimeInForce, Venue};
+    use crate::execution::money::{Price, Qty};
+    use crate::runtime::{OrderState, RuntimeConfig, ShardedRuntime, StrategyFactory, StrategySet};
+    use crate::strategy::moon_strategies::HookConfig;
+    use crate::testing::ScenarioBuilder;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_gateway_drops_acks_deterministically() {
+        let config = FaultConfig { seed: 7, drop_ack: 0.5, ..FaultConfig::default() };
+        let intents: Vec<QuoteIntent> = (0..200)
+            .map(|i| {
+                QuoteIntent::new(
+                    Venue::Gate,
+                    "BTC_USDT",
+                    Side::Bid,
+                    Price::from_f64(100.0),
+                    Qty::from_f64(1.0),
+                    TimeInForce::Gtc,
+                    ClientOrderId::new(format!("c-{}", i)),
+                )
+            })
+            .collect();
+        let first = ChaosGateway::new(DryRunGateway::new(), config.clone());
+        let second = ChaosGateway::new(DryRunGateway::new(), config);
+        let acks = first.submit(&intents).await.unwrap();
+        assert_eq!(acks.len() as u64 + first.stats().dropped_acks, 200);
+        assert!((60..140).contains(&acks.len()), "{}", acks.len());
+        assert_eq!(second.submit(&intents).await.unwrap().len(), acks.len());
+    }
+
+    /// Дубли, задержка и обрыв стрима не дают двойного фила в шард
+    #[test]
+    fn test_router_survives_degraded_user_stream() {
+        let factory: StrategyFactory = Arc::new(|_symbol: Symbol| -> StrategySet {
+            vec![Box::new(HookAdapter::new(HookConfig { hook_roll_back_wait: 0, ..Default::default() }))]
+        });
+        let (mut runtime, mut router) = ShardedRuntime::start(RuntimeConfig { shards: 1, ..Default::default() }, factory);
+        for tick in ScenarioBuilder::new("ETH_USDT", 100.0).flat(1_000).crash(10.0, 500).build() {
+            runtime.on_tick(tick);
+        }
+        let mut actions = Vec::new();
+        let buy = loop {
+            router.poll(&mut actions, usize::MAX);
+            if let Some(buy) = actions.iter().find(|a| matches!(a.action, StrategyAction::PlaceBuy { .. })) {
+                break buy.clone();
+            }
+            std::thread::yield_now();
+        };
+        let StrategyAction::PlaceBuy { price, size } = buy.action else { unreachable!() };
+        let id = ClientOrderId::new("chaos-1");
+        router.submitted(id.clone(), &buy);
+
+        let config = FaultConfig {
+            duplicate: 1.0,
+            fill_delay: Duration::from_millis(50),
+            disconnect_every: Some(4),
+            outage_events: 1,
+            ..FaultConfig::default()
+        };
+        let mut stream = ChaosStream::new(config);
+        let update = |status, filled| {
+            UserEvent::Order(OrderUpdate {
+                symbol: buy.symbol,
+                client_order_id: Some(id.clone()),
+                exchange_order_id: ExchangeOrderId("1".to_string()),
+                status,
+                side: Side::Bid,
+                price,
+                size,
+                filled,
+                avg_fill_price: Some(price),
+                ts_ms: 0,
+            })
+        };
+        let start = Instant::now();
+        let mut delivered = Vec::new();
+        delivered.extend(stream.apply(update(OrderStatus::New, 0.0), start));
+        delivered.extend(stream.apply(update(OrderStatus::PartiallyFilled, size / 2.0), start));
+        delivered.extend(stream.apply(update(OrderStatus::Filled, size), start));
+        // Четвёртое событие попадает в обрыв
+        delivered.extend(stream.apply(update(OrderStatus::Filled, size), start));
+        delivered.extend(stream.release(start + Duration::from_millis(60)));
+        let stats = stream.stats();
+        assert_eq!((stats.duplicated, stats.delayed, stats.disconnects, stats.lost_events), (1, 2, 1, 1));
+
+        for event in delivered {
+            router.on_user_event(event);
+        }
+        assert_eq!(router.orders().get(&id).unwrap().state, OrderState::Filled);
+        assert_eq!(router.stream_gaps(), 1);
+        let shard_stats = runtime.shutdown();
+        assert_eq!(shard_stats[0].fills, 2, "partial and remainder, no duplicates");
+    }
+}
@@ -0,0 +1,391 @@
+//! Симуляция стратегия + риск + исполнение на скриптованных тиках
+//!
+//! Исполнение детерминированное: лимитный buy исполняется полностью по своей цене,
+//! когда трейд проходит на уровне ордера или ниже (sell - на уровне или выше).
+//! Ордер, выставленный на тике, может исполниться только со следующего тика.
+
+use crate::backtest::market::TradeTick;
+use crate::backtest::strategy_adapter::{StrategyAction, StrategyAdapter};
+use crate::base_classes::types::Side;
+use crate::error::Error;
+use crate::risk::{GlobalRiskManager, RiskAction};
+use crate::strategy::moon_strategies::mshot::Deltas;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimOrderStatus {
+    Resting,
+    Filled,
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimOrder {
+    pub id: u64,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+    pub placed_at: DateTime<Utc>,
+    pub filled_at: Option<DateTime<Utc>>,
+    pub status: SimOrderStatus,
+}
+
+/// Прогон стратегии через сценарий
+pub struct SimulationHarness {
+    strategy: Box<dyn StrategyAdapter>,
+    risk: GlobalRiskManager,
+    deltas: Deltas,
+    orders: Vec<SimOrder>,
+    position: f64,
+    position_cost: f64,
+    realized_pnl: f64,
+    round_trips: usize,
+    blocked_by_risk: usize,
+    ignored_actions: Vec<StrategyAction>,
+    detections: Vec<String>,
+    errors: Vec<Error>,
+    last_price: f64,
+}
+
+impl SimulationHarness {
+    pub fn new<S: StrategyAdapter + 'static>(strategy: S) -> Self {
+        Self {
+            strategy: Box::new(strategy),
+            risk: GlobalRiskManager::new(),
+            deltas: Deltas::default(),
+            orders: Vec::new(),
+            position: 0.0,
+            position_cost: 0.0,
+            realized_pnl: 0.0,
+            round_trips: 0,
+            blocked_by_risk: 0,
+            ignored_actions: Vec::new(),
+            errors: Vec::new(),
+            detections: Vec::new(),
+            last_price: 0.0,
+        }
+    }
+
+    /// Риск-менеджер, блокирующий новые входы при StopTrading
+    pub fn with_risk(mut self, risk: GlobalRiskManager) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// Фиксированные дельты, подаваемые стратегии на каждом тике
+    pub fn with_deltas(mut self, deltas: Deltas) -> Self {
+        self.deltas = deltas;
+        self
+    }
+
+    pub fn strategy(&self) -> &dyn StrategyAdapter {
+        self.strategy.as_ref()
+    }
+
+    pub fn run(mut self, ticks: &[TradeTick]) -> SimulationReport {
+        for tick in ticks {
+            self.step(tick);
+        }
+        SimulationReport {
+            strategy: self.strategy.get_name().to_string(),
+            orders: self.orders,
+            realized_pnl: self.realized_pnl,
+            open_position: self.position,
+            unrealized_pnl: self.position * self.last_price - self.position_cost,
+            round_trips: self.round_trips,
+            blocked_by_risk: self.blocked_by_risk,
+            ignored_actions: self.ignored_actions,
+            detections: self.detections,
+            errors: self.errors,
+        }
+    }
+
+    fn step(&mut self, tick: &TradeTick) {
+        self.last_price = tick.price;
+        self.match_orders(tick);
+        let action = self.strategy.on_tick(tick, &self.deltas);
+        self.apply(action, tick.timestamp);
+    }
+
+    fn match_orders(&mut self, tick: &TradeTick) {
+        let mut fills = Vec::new();
+        for order in self.orders.iter_mut().filter(|o| o.status == SimOrderStatus::Resting) {
+            let crosses = match order.side {
+                Side::Bid => tick.price <= order.price,
+                Side::Ask => tick.price >= order.price,
+            };
+            if crosses {
+                order.status = SimOrderStatus::Filled;
+                order.filled_at = Some(tick.timestamp);
+                fills.push((order.side, order.price, order.size));
+            }
+        }
+
+        for (side, price, size) in fills {
+            match side {
+                Side::Bid => {
+                    self.position += size;
+                    self.position_cost += price * size;
+                    if let Some(action) = self.strategy.on_buy_filled(price, size) {
+                        self.apply(action, tick.timestamp);
+                    }
+                }
+                Side::Ask => {
+                    let avg_cost = self.position_cost / self.position;
+                    let pnl = (price - avg_cost) * size;
+                    self.position -= size;
+                    self.position_cost -= avg_cost * size;
+                    self.realized_pnl += pnl;
+                    self.risk.record_trade_pnl(pnl);
+                    if self.position <= f64::EPSILON {
+                        self.position = 0.0;
+                        self.position_cost = 0.0;
+                        self.round_trips += 1;
+                    }
+                    self.strategy.on_sell_filled(price, size);
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, action: StrategyAction, now: DateTime<Utc>) {
+        match action {
+            StrategyAction::NoAction => {}
+            StrategyAction::DetectSignal { message } => self.detections.push(message),
+            StrategyAction::Error(err) => self.errors.push(err),
+            StrategyAction::PlaceBuy { price, size } => {
+                if self.risk.check_stop_conditions() == RiskAction::StopTrading {
+                    self.blocked_by_risk += 1;
+                    self.strategy.on_buy_declined();
+                    return;
+                }
+                if self.resting(Side::Bid).is_some() {
+                    self.ignored_actions.push(StrategyAction::PlaceBuy { price, size });
+                    return;
+                }
+                self.place(Side::Bid, price, size, now);
+            }
+            StrategyAction::ReplaceBuy { new_price } => match self.resting(Side::Bid) {
+                Some(idx) => {
+                    self.orders[idx].price = new_price;
+                    self.orders[idx].placed_at = now;
+                }
+                None => self.ignored_actions.push(StrategyAction::ReplaceBuy { new_price }),
+            },
+            StrategyAction::PlaceSell { price, size } => {
+                // Без позиции продавать нечего; повторный сигнал двигает уже стоящий sell
+                if self.position <= 0.0 {
+                    self.ignored_actions.push(StrategyAction::PlaceSell { price, size });
+                    return;
+                }
+                match self.resting(Side::Ask) {
+                    Some(idx) => self.orders[idx].price = price,
+                    None => self.place(Side::Ask, price, size.min(self.position), now),
+                }
+            }
+            StrategyAction::CancelOrder { order_id } => {
+                let order = self
+                    .orders
+                    .iter_mut()
+                    .find(|o| o.status == SimOrderStatus::Resting && (order_id == 0 || o.id == order_id));
+                match order {
+                    Some(order) => order.status = SimOrderStatus::Cancelled,
+                    None => self.ignored_actions.push(StrategyAction::CancelOrder { order_id }),
+                }
+            }
+        }
+    }
+
+    fn resting(&self, side: Side) -> Option<usize> {
+        self.orders
+            .iter()
+            .position(|o| o.side == side && o.status == SimOrderStatus::Resting)
+    }
+
+    fn place(&mut self, side: Side, price: f64, size: f64, now: DateTime<Utc>) {
+        let id = self.orders.len() as u64 + 1;
+        self.orders.push(SimOrder {
+            id,
+            side,
+            price,
+            size,
+            placed_at: now,
+            filled_at: None,
+            status: SimOrderStatus::Resting,
+        });
+        self.strategy.on_order_placed(side, id);
+    }
+}
+
+/// Итог прогона с assert-хелперами
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub strategy: String,
+    pub orders: Vec<SimOrder>,
+    pub realized_pnl: f64,
+    pub open_position: f64,
+    pub unrealized_pnl: f64,
+    pub round_trips: usize,
+    pub blocked_by_risk: usize,
+    pub ignored_actions: Vec<StrategyAction>,
+    pub detections: Vec<String>,
+    /// Ошибки стратегии (`StrategyAction::Error`)
+    pub errors: Vec<Error>,
+}
+
+impl SimulationReport {
+    pub fn buys(&self) -> impl Iterator<Item = &SimOrder> {
+        self.orders.iter().filter(|o| o.side == Side::Bid)
+    }
+
+    pub fn sells(&self) -> impl Iterator<Item = &SimOrder> {
+        self.orders.iter().filter(|o| o.side == Side::Ask)
+    }
+
+    pub fn filled(&self) -> impl Iterator<Item = &SimOrder> {
+        self.orders.iter().filter(|o| o.status == SimOrderStatus::Filled)
+    }
+
+    /// Хотя бы один buy выставлен в диапазоне цен [low, high]
+    pub fn assert_buy_placed_between(&self, low: f64, high: f64) -> &Self {
+        assert!(
+            self.buys().any(|o| o.price >= low && o.price <= high),
+            "{}: expected a buy in [{}, {}], got buys {:?}",
+            self.strategy,
+            low,
+            high,
+            self.buys().map(|o| o.price).collect::<Vec<_>>()
+        );
+        self
+    }
+
+    /// Стратегия не выставила ни одного buy
+    pub fn assert_no_entries(&self) -> &Self {
+        assert_eq!(
+            self.buys().count(),
+            0,
+            "{}: expected no entries, got {:?}",
+            self.strategy,
+            self.buys().collect::<Vec<_>>()
+        );
+        self
+    }
+
+    pub fn assert_round_trips(&self, expected: usize) -> &Self {
+        assert_eq!(
+            self.round_trips, expected,
+            "{}: round trips mismatch, orders: {:?}",
+            self.strategy, self.orders
+        );
+        self
+    }
+
+    pub fn assert_profit(&self) -> &Self {
+        assert!(
+            self.realized_pnl > 0.0,
+            "{}: expected realized profit, got {:.8}",
+            self.strategy,
+            self.realized_pnl
+        );
+        self
+    }
+
+    pub fn assert_pnl_between(&self, low: f64, high: f64) -> &Self {
+        assert!(
+            self.realized_pnl >= low && self.realized_pnl <= high,
+            "{}: realized pnl {:.8} outside [{}, {}]",
+            self.strategy,
+            self.realized_pnl,
+            low,
+            high
+        );
+        self
+    }
+
+    /// Позиция закрыта и не осталось стоящих ордеров
+    pub fn assert_flat(&self) -> &Self {
+        assert_eq!(self.open_position, 0.0, "{}: position still open", self.strategy);
+        let resting: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|o| o.status == SimOrderStatus::Resting)
+            .collect();
+        assert!(resting.is_empty(), "{}: resting orders left: {:?}", self.strategy, resting);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::strategy_adapter::{HookAdapter, MStrikeAdapter};
+    use crate::strategy::moon_strategies::HookConfig;
+    use crate::testing::ScenarioBuilder;
+
+    /// Hook без ожидания отката: buy уходит на тике детекта
+    fn hook() -> HookAdapter {
+        HookAdapter::new(HookConfig { hook_roll_back_wait: 0, ..Default::default() })
+    }
+
+    #[test]
+    fn test_hook_buys_crash_and_sells_recovery() {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .flat(1_000)
+            .crash(10.0, 500)
+            .flat(500)
+            .ramp_to(100.0, 6_000)
+            .flat(500)
+            .build();
+
+        SimulationHarness::new(hook())
+            .run(&ticks)
+            .assert_buy_placed_between(90.0, 100.0)
+            .assert_round_trips(1)
+            .assert_profit()
+            .assert_flat();
+    }
+
+    #[test]
+    fn test_hook_ignores_choppy_range() {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .choppy(1.0, 20_000, 2_000)
+            .build();
+
+        SimulationHarness::new(hook())
+            .run(&ticks)
+            .assert_no_entries()
+            .assert_pnl_between(0.0, 0.0);
+    }
+
+    #[test]
+    fn test_mstrike_enters_on_gap_down() {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .flat(1_000)
+            .gap(-20.0)
+            .flat(500)
+            .build();
+
+        // LastBidEMA уже включает бид гэпа, поэтому эффективная глубина ~13%
+        let report = SimulationHarness::new(MStrikeAdapter::default()).run(&ticks);
+        report.assert_buy_placed_between(79.0, 81.0);
+        assert_eq!(report.open_position, report.buys().next().unwrap().size);
+    }
+
+    #[test]
+    fn test_risk_stop_blocks_entries() {
+        let mut risk = GlobalRiskManager::new();
+        risk.max_loss_per_trades = Some((10.0, 0));
+        risk.record_trade_pnl(-50.0);
+
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .flat(1_000)
+            .crash(10.0, 500)
+            .build();
+
+        let report = SimulationHarness::new(hook())
+            .with_risk(risk)
+            .run(&ticks);
+        report.assert_no_entries();
+        assert!(report.blocked_by_risk > 0);
+    }
+}
@@ -0,0 +1,233 @@
+//! Инварианты риск-математики
+//!
+//! Проверки возвращают `InvariantViolation` с описанием, а не паникуют, чтобы их можно было
+//! вызывать из property-тестов, симуляций и runtime-ассертов.
+
+use crate::risk::{LiquidationControl, PanicSellManager};
+use crate::strategy::moon_strategies::{CorridorBounds, HookView};
+use std::fmt;
+
+/// Допуск на погрешность f64 при сравнении цен и объемов
+const EPS: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvariantViolation {
+    pub invariant: &'static str,
+    pub details: String,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invariant '{}' violated: {}", self.invariant, self.details)
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+pub type InvariantResult = Result<(), InvariantViolation>;
+
+fn violation(invariant: &'static str, details: String) -> InvariantResult {
+    Err(InvariantViolation { invariant, details })
+}
+
+/// Цена ликвидации ниже входа для long и выше входа для short, и всегда неотрицательна
+pub fn liquidation_price_side(
+    control: &LiquidationControl,
+    position_size: f64,
+    entry_price: f64,
+    leverage: f64,
+) -> InvariantResult {
+    let liq = control.calculate_liquidation_price(position_size, entry_price, 0.0, leverage);
+    if !liq.is_finite() || liq < 0.0 {
+        return violation(
+            "liquidation_price_side",
+            format!("liquidation price {} for entry {} x{}", liq, entry_price, leverage),
+        );
+    }
+    let wrong_side = if position_size > 0.0 { liq >= entry_price } else { liq <= entry_price };
+    if wrong_side {
+        return violation(
+            "liquidation_price_side",
+            format!(
+                "{} position: liquidation {} vs entry {} at x{}",
+                if position_size > 0.0 { "long" } else { "short" },
+                liq,
+                entry_price,
+                leverage
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// Цена паник-продажи положительна и не ниже `floor`
+pub fn panic_price_above_floor(
+    manager: &PanicSellManager,
+    buy_price: f64,
+    floor: f64,
+) -> InvariantResult {
+    let price = manager.calculate_panic_price(buy_price);
+    if !price.is_finite() || price <= 0.0 || price + EPS < floor {
+        return violation(
+            "panic_price_above_floor",
+            format!("panic price {} for buy {} below floor {}", price, buy_price, floor),
+        );
+    }
+    Ok(())
+}
+
+/// Номинал позиции не превышает balance × leverage
+pub fn order_within_leverage(
+    size: f64,
+    price: f64,
+    balance: f64,
+    leverage: f64,
+) -> InvariantResult {
+    let notional = size.abs() * price;
+    let limit = balance * leverage;
+    if notional > limit * (1.0 + EPS) {
+        return violation(
+            "order_within_leverage",
+            format!("notional {} exceeds balance {} x{} = {}", notional, balance, leverage, limit),
+        );
+    }
+    Ok(())
+}
+
+/// Верхняя граница коридора не ниже нижней
+pub fn corridor_ordered(corridor: &CorridorBounds) -> InvariantResult {
+    if !(corridor.upper.is_finite() && corridor.lower.is_finite()) || corridor.upper < corridor.lower {
+        return violation(
+            "corridor_ordered",
+            format!("upper {} < lower {}", corridor.upper, corridor.lower),
+        );
+    }
+    Ok(())
+}
+
+/// Согласованность состояния Hook: коридор упорядочен, глубина детекта положительна,
+/// цена входа есть ровно в фазах с позицией
+pub fn hook_view_consistent(view: &HookView) -> InvariantResult {
+    if let Some(corridor) = &view.corridor {
+        corridor_ordered(corridor)?;
+    }
+    if let (Some(min), Some(max)) = (view.strike_min_price, view.strike_max_price)
+        && min > max
+    {
+        return violation("hook_view_consistent", format!("strike min {} > max {}", min, max));
+    }
+    if let Some(depth) = view.detection_depth
+        && !(depth > 0.0 && depth <= 100.0)
+    {
+        return violation("hook_view_consistent", format!("detection depth {}", depth));
+    }
+    if view.phase.has_position() != view.entry_price.is_some() {
+        return violation(
+            "hook_view_consistent",
+            format!("phase {} with entry price {:?}", view.phase, view.entry_price),
+        );
+    }
+    if view.position_size < 0.0 {
+        return violation(
+            "hook_view_consistent",
+            format!("negative position size {}", view.position_size),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::market::{TradeSide, TradeTick};
+    use crate::base_classes::symbol::Symbol;
+    use crate::strategy::moon_strategies::mshot::Deltas;
+    use crate::strategy::moon_strategies::{HookConfig, HookStrategy};
+    use chrono::{Duration, TimeZone, Utc};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_liquidation_price_on_correct_side(
+            entry in 1e-6f64..1e6,
+            leverage in 1u32..=125,
+            mmr in 0.0f64..0.5,
+            size in 1e-6f64..1e6,
+            is_long in any::<bool>(),
+        ) {
+            let control = LiquidationControl::new(true, 125, mmr, 20.0);
+            let position = if is_long { size } else { -size };
+            prop_assert!(liquidation_price_side(&control, position, entry, leverage as f64).is_ok());
+        }
+
+        #[test]
+        fn prop_panic_price_never_below_floor(
+            buy in 1e-6f64..1e6,
+            drop_to in 0.9f64..1.1,
+            spread in 0.0f64..0.05,
+        ) {
+            let manager = PanicSellManager::new(true, drop_to, spread, Some(5.0), None);
+            let floor = buy * 0.9 * 0.95;
+            prop_assert!(panic_price_above_floor(&manager, buy, floor).is_ok());
+        }
+
+        #[test]
+        fn prop_can_open_position_respects_leverage(
+            size in 0.0f64..1e4,
+            current in 0.0f64..1e4,
+            price in 1e-3f64..1e5,
+            balance in 1.0f64..1e6,
+            leverage in 1u32..=125,
+        ) {
+            let control = LiquidationControl::default();
+            let leverage = leverage as f64;
+            if control.can_open_position(size, price, balance, leverage, current) {
+                prop_assert!(order_within_leverage(size + current, price, balance, leverage).is_ok());
+            }
+        }
+
+        #[test]
+        fn prop_hook_corridor_ordered(
+            crash_pct in 5.5f64..60.0,
+            interpolate in 0u8..=4,
+            initial_price in 0.0f64..100.0,
+            distance in 0.0f64..100.0,
+            roll_back in 0.0f64..100.0,
+        ) {
+            let config = HookConfig {
+                hook_interpolate: interpolate,
+                hook_initial_price: initial_price,
+                hook_price_distance: distance,
+                hook_price_roll_back: roll_back,
+                buy_order_reduce: 0,
+                ..Default::default()
+            };
+            let mut strategy = HookStrategy::new(config);
+            let base = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+            let deltas = Deltas::default();
+            for (i, price) in [100.0, 100.0 - crash_pct].iter().enumerate() {
+                let tick = TradeTick {
+                    timestamp: base + Duration::milliseconds(i as i64 * 500),
+                    symbol: Symbol::new("BTC_USDT"),
+                    price: *price,
+                    volume: 1.0,
+                    side: TradeSide::Sell,
+                    trade_id: i.to_string(),
+                    best_bid: Some(*price),
+                    best_ask: Some(*price),
+                };
+                strategy.on_tick(&tick, &deltas);
+            }
+            let view = strategy.view();
+            prop_assert!(view.detected);
+            prop_assert!(hook_view_consistent(&view).is_ok(), "{:?}", view);
+        }
+    }
+
+    #[test]
+    fn test_violation_reports_details() {
+        let err = corridor_ordered(&CorridorBounds { upper: 1.0, lower: 2.0 }).unwrap_err();
+        assert_eq!(err.invariant, "corridor_ordered");
+        assert!(err.to_string().contains("upper 1"));
+    }
+}
@@ -0,0 +1,14 @@
+//! Утилиты для тестов: сценарии рынка, синтетические стресс-потоки, симуляция стратегий end-to-end,
+//! инварианты риск-математики, инъекция сетевых сбоев
+
+pub mod scenarios;
+pub mod harness;
+pub mod invariants;
+pub mod chaos;
+pub mod synthetic;
+
+pub use scenarios::ScenarioBuilder;
+pub use harness::{SimulationHarness, SimulationReport, SimOrder, SimOrderStatus};
+pub use invariants::{InvariantResult, InvariantViolation};
+pub use chaos::{ChaosGateway, ChaosStream, FaultConfig, FaultStats};
+pub use synthetic::{SyntheticConfig, SyntheticEvent, SyntheticGenerator};
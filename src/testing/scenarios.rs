@@ -0,0 +1,183 @@
+//! Скриптованные последовательности тиков (обвал, памп-дамп, пила, гэп)
+//! Детерминированы: одинаковый сценарий всегда дает одинаковые тики
+
+use crate::backtest::market::{TradeSide, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// Построитель последовательности тиков
+///
+/// Все движения задаются в % от текущей цены и длительности в мс;
+/// тики генерируются с шагом `step_ms`.
+#[derive(Debug, Clone)]
+pub struct ScenarioBuilder {
+    symbol: Symbol,
+    price: f64,
+    time: DateTime<Utc>,
+    step_ms: i64,
+    volume: f64,
+    spread_pct: f64,
+    ticks: Vec<TradeTick>,
+}
+
+impl ScenarioBuilder {
+    pub fn new(symbol: &str, start_price: f64) -> Self {
+        Self {
+            symbol: Symbol::new(symbol),
+            price: start_price,
+            time: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            step_ms: 100,
+            volume: 1000.0,
+            spread_pct: 0.0,
+            ticks: Vec::new(),
+        }
+    }
+
+    /// Интервал между тиками (мс)
+    pub fn step_ms(mut self, step_ms: i64) -> Self {
+        assert!(step_ms > 0, "step_ms must be positive, got {}", step_ms);
+        self.step_ms = step_ms;
+        self
+    }
+
+    /// Объем каждого тика
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Спред best_bid/best_ask вокруг цены трейда (%)
+    pub fn spread_pct(mut self, spread_pct: f64) -> Self {
+        self.spread_pct = spread_pct;
+        self
+    }
+
+    /// Цена стоит на месте `duration_ms`
+    pub fn flat(mut self, duration_ms: i64) -> Self {
+        let price = self.price;
+        for _ in 0..self.steps(duration_ms) {
+            self.push(price);
+        }
+        self
+    }
+
+    /// Линейное движение к цене `target` за `duration_ms`
+    pub fn ramp_to(mut self, target: f64, duration_ms: i64) -> Self {
+        let start = self.price;
+        let steps = self.steps(duration_ms);
+        for i in 1..=steps {
+            self.push(start + (target - start) * i as f64 / steps as f64);
+        }
+        self
+    }
+
+    /// Обвал на `pct`% за `duration_ms`
+    pub fn crash(self, pct: f64, duration_ms: i64) -> Self {
+        let target = self.price * (1.0 - pct / 100.0);
+        self.ramp_to(target, duration_ms)
+    }
+
+    /// Рост на `pct`% за `duration_ms`
+    pub fn pump(self, pct: f64, duration_ms: i64) -> Self {
+        let target = self.price * (1.0 + pct / 100.0);
+        self.ramp_to(target, duration_ms)
+    }
+
+    /// Памп на `pump_pct`%, затем дамп на `dump_pct`% (от вершины), каждый за половину `duration_ms`
+    pub fn pump_then_dump(self, pump_pct: f64, dump_pct: f64, duration_ms: i64) -> Self {
+        let half = duration_ms / 2;
+        self.pump(pump_pct, half).crash(dump_pct, duration_ms - half)
+    }
+
+    /// Пила вокруг текущей цены с амплитудой `amplitude_pct`% и периодом `period_ms`
+    pub fn choppy(mut self, amplitude_pct: f64, duration_ms: i64, period_ms: i64) -> Self {
+        let center = self.price;
+        let amplitude = center * amplitude_pct / 100.0;
+        let period_steps = self.steps(period_ms).max(2);
+        for i in 1..=self.steps(duration_ms) {
+            // Треугольная волна: 0 -> +A -> 0 -> -A -> 0
+            let phase = (i % period_steps) as f64 / period_steps as f64;
+            let wave = if phase < 0.25 {
+                phase * 4.0
+            } else if phase < 0.75 {
+                2.0 - phase * 4.0
+            } else {
+                phase * 4.0 - 4.0
+            };
+            self.push(center + amplitude * wave);
+        }
+        self
+    }
+
+    /// Мгновенный гэп на `pct`% (отрицательный - вниз) одним тиком
+    pub fn gap(mut self, pct: f64) -> Self {
+        let target = self.price * (1.0 + pct / 100.0);
+        self.push(target);
+        self
+    }
+
+    /// Текущая (последняя) цена сценария
+    pub fn last_price(&self) -> f64 {
+        self.price
+    }
+
+    pub fn build(self) -> Vec<TradeTick> {
+        self.ticks
+    }
+
+    fn steps(&self, duration_ms: i64) -> usize {
+        assert!(duration_ms >= 0, "duration must be non-negative, got {}", duration_ms);
+        (duration_ms / self.step_ms).max(1) as usize
+    }
+
+    fn push(&mut self, price: f64) {
+        assert!(price > 0.0, "scenario produced non-positive price {}", price);
+        let side = if price < self.price { TradeSide::Sell } else { TradeSide::Buy };
+        self.time += Duration::milliseconds(self.step_ms);
+        let half_spread = price * self.spread_pct / 200.0;
+        self.ticks.push(TradeTick {
+            timestamp: self.time,
+            symbol: self.symbol,
+            price,
+            volume: self.volume,
+            side,
+            trade_id: self.ticks.len().to_string(),
+            best_bid: Some(price - half_spread),
+            best_ask: Some(price + half_spread),
+        });
+        self.price = price;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_reaches_target() {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .flat(1_000)
+            .crash(10.0, 500)
+            .build();
+        assert_eq!(ticks.len(), 15);
+        assert!((ticks.last().unwrap().price - 90.0).abs() < 1e-9);
+        assert!(ticks.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+    }
+
+    #[test]
+    fn test_choppy_stays_in_range() {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0)
+            .choppy(1.0, 10_000, 1_000)
+            .build();
+        assert!(ticks.iter().all(|t| t.price >= 99.0 - 1e-9 && t.price <= 101.0 + 1e-9));
+        assert!(ticks.iter().any(|t| t.price > 100.5));
+        assert!(ticks.iter().any(|t| t.price < 99.5));
+    }
+
+    #[test]
+    fn test_gap_is_single_tick() {
+        let ticks = ScenarioBuilder::new("BTC_USDT", 100.0).flat(200).gap(-20.0).build();
+        assert_eq!(ticks.len(), 3);
+        assert!((ticks[2].price - 80.0).abs() < 1e-9);
+    }
+}
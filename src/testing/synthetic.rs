@@ -0,0 +1,324 @@
+//! Синтетические потоки тиков для стресс-тестов
+//!
+//! В отличие от `ScenarioBuilder`, здесь рынок случайный, но с управляемыми свойствами:
+//! кластеризация волатильности (GARCH(1,1)), скачки цены как в jump-diffusion,
+//! спуфинговые прострелы одним тиком и спред, который расширяется вместе с волатильностью
+//! и после скачков. Так стратегии и риск-менеджеры проверяются на сценариях, которых
+//! нет в истории. Случайность детерминирована сидом: упавший прогон воспроизводится.
+
+use crate::backtest::market::{TradeSide, TradeStream, TradeTick};
+use crate::base_classes::symbol::Symbol;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticConfig {
+    pub seed: u64,
+    pub step_ms: i64,
+    /// Объём тика в спокойном рынке; растёт с величиной движения
+    pub volume: f64,
+    /// Долгосрочная волатильность одного шага, %
+    pub volatility_pct: f64,
+    /// GARCH(1,1): вес последнего шока и память волатильности; `alpha + beta < 1`,
+    /// оба нуля — волатильность постоянная
+    pub garch_alpha: f64,
+    pub garch_beta: f64,
+    /// Скачки: частота (пуассоновская), средний размер и разброс, %
+    pub jumps_per_hour: f64,
+    pub jump_mean_pct: f64,
+    pub jump_std_pct: f64,
+    /// Прострелы: одиночный тик на `wick_pct`% в случайную сторону, цена не сдвигается
+    pub wicks_per_hour: f64,
+    pub wick_pct: f64,
+    /// Спред в спокойном рынке, %
+    pub spread_pct: f64,
+    /// Спред ∝ (текущая волатильность / долгосрочная) в этой степени
+    pub spread_vol_elasticity: f64,
+    /// Спред сразу после скачка умножается на это и возвращается за `jump_spread_decay_ms`
+    pub jump_spread_mult: f64,
+    pub jump_spread_decay_ms: i64,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            step_ms: 100,
+            volume: 1000.0,
+            volatility_pct: 0.02,
+            garch_alpha: 0.0,
+            garch_beta: 0.0,
+            jumps_per_hour: 0.0,
+            jump_mean_pct: -5.0,
+            jump_std_pct: 2.0,
+            wicks_per_hour: 0.0,
+            wick_pct: 3.0,
+            spread_pct: 0.02,
+            spread_vol_elasticity: 1.0,
+            jump_spread_mult: 10.0,
+            jump_spread_decay_ms: 30_000,
+        }
+    }
+}
+
+impl SyntheticConfig {
+    /// `calm`, `clustered`, `crashes`, `spoofing` или `stress` (всё сразу)
+    pub fn preset(name: &str) -> Result<Self> {
+        let calm = Self::default();
+        let clustered = Self { garch_alpha: 0.1, garch_beta: 0.85, ..calm.clone() };
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "calm" => calm,
+            "clustered" => clustered,
+            "crashes" => Self { jumps_per_hour: 2.0, ..clustered },
+            "spoofing" => Self { wicks_per_hour: 20.0, ..calm },
+            "stress" => Self { jumps_per_hour: 2.0, wicks_per_hour: 20.0, spread_vol_elasticity: 1.5, ..clustered },
+            other => bail!("unknown preset '{}', expected calm, clustered, crashes, spoofing or stress", other),
+        })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.step_ms <= 0 {
+            bail!("step_ms must be positive, got {}", self.step_ms);
+        }
+        if !(self.volume > 0.0 && self.volume.is_finite()) {
+            bail!("volume must be positive, got {}", self.volume);
+        }
+        if !(self.volatility_pct >= 0.0 && self.spread_pct >= 0.0) {
+            bail!("volatility_pct and spread_pct must not be negative");
+        }
+        if self.garch_alpha < 0.0 || self.garch_beta < 0.0 || self.garch_alpha + self.garch_beta >= 1.0 {
+            bail!(
+                "GARCH needs alpha, beta >= 0 and alpha + beta < 1, got {} + {}",
+                self.garch_alpha,
+                self.garch_beta
+            );
+        }
+        if self.jumps_per_hour < 0.0 || self.wicks_per_hour < 0.0 || self.jump_std_pct < 0.0 {
+            bail!("event rates and jump_std_pct must not be negative");
+        }
+        if self.jump_mean_pct.abs() + 4.0 * self.jump_std_pct >= 100.0 || self.wick_pct >= 100.0 {
+            bail!("jumps and wicks must stay well below 100%");
+        }
+        Ok(())
+    }
+}
+
+/// Что генератор вставил в поток, для проверок вокруг событий
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntheticEvent {
+    /// Скачок цены на `pct`% на тике `index`
+    Jump { index: usize, pct: f64 },
+    /// Прострел на `pct`% одним тиком `index`
+    Wick { index: usize, pct: f64 },
+}
+
+/// Генератор синтетического потока
+#[derive(Debug, Clone)]
+pub struct SyntheticGenerator {
+    config: SyntheticConfig,
+    symbol: Symbol,
+    price: f64,
+    time: DateTime<Utc>,
+    rng: SynthRng,
+    /// Текущая дисперсия шага (доли, не %)
+    variance: f64,
+    last_return: f64,
+    /// Время последнего скачка: от него затухает расширение спреда
+    last_jump: Option<DateTime<Utc>>,
+    ticks: Vec<TradeTick>,
+    events: Vec<SyntheticEvent>,
+}
+
+impl SyntheticGenerator {
+    pub fn new(symbol: &str, start_price: f64, config: SyntheticConfig) -> Result<Self> {
+        config.validate()?;
+        if !(start_price > 0.0 && start_price.is_finite()) {
+            bail!("start price must be positive, got {}", start_price);
+        }
+        let variance = (config.volatility_pct / 100.0).powi(2);
+        Ok(Self {
+            rng: SynthRng::new(config.seed),
+            symbol: Symbol::new(symbol),
+            price: start_price,
+            time: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            variance,
+            last_return: 0.0,
+            last_jump: None,
+            ticks: Vec::new(),
+            events: Vec::new(),
+            config,
+        })
+    }
+
+    /// Дописать `duration_ms` рынка
+    pub fn run(mut self, duration_ms: i64) -> Self {
+        let steps = (duration_ms / self.config.step_ms).max(0);
+        let long_run = (self.config.volatility_pct / 100.0).powi(2);
+        let per_step_hours = self.config.step_ms as f64 / 3_600_000.0;
+        let (alpha, beta) = (self.config.garch_alpha, self.config.garch_beta);
+        for _ in 0..steps {
+            self.time += Duration::milliseconds(self.config.step_ms);
+            if alpha + beta > 0.0 {
+                self.variance = long_run * (1.0 - alpha - beta) + alpha * self.last_return.powi(2) + beta * self.variance;
+            }
+            let mut ret = self.variance.sqrt() * self.rng.normal();
+            if self.rng.chance(self.config.jumps_per_hour * per_step_hours) {
+                let pct = self.config.jump_mean_pct + self.config.jump_std_pct * self.rng.normal();
+                // Лог-доходность скачка: цена остаётся положительной при любом знаке
+                ret += (1.0 + pct / 100.0).max(0.01).ln();
+                self.events.push(SyntheticEvent::Jump { index: self.ticks.len(), pct });
+                self.last_jump = Some(self.time);
+            }
+            self.last_return = ret;
+            let price = self.price * ret.exp();
+            let volume = self.config.volume * (1.0 + ret.abs() / long_run.sqrt().max(1e-12)).min(50.0);
+            self.push(price, volume);
+            self.price = price;
+
+            if self.rng.chance(self.config.wicks_per_hour * per_step_hours) {
+                let pct = if self.rng.chance(0.5) { self.config.wick_pct } else { -self.config.wick_pct };
+                self.events.push(SyntheticEvent::Wick { index: self.ticks.len(), pct });
+                self.push(self.price * (1.0 + pct / 100.0), self.config.volume * 0.05);
+            }
+        }
+        self
+    }
+
+    pub fn events(&self) -> &[SyntheticEvent] {
+        &self.events
+    }
+
+    pub fn build(self) -> (Vec<TradeTick>, Vec<SyntheticEvent>) {
+        (self.ticks, self.events)
+    }
+
+    pub fn build_stream(self) -> TradeStream {
+        TradeStream::new(self.symbol, self.ticks)
+    }
+
+    fn spread_pct(&self) -> f64 {
+        let long_run = self.config.volatility_pct / 100.0;
+        let vol_ratio = if long_run > 0.0 { self.variance.sqrt() / long_run } else { 1.0 };
+        let mut spread = self.config.spread_pct * vol_ratio.powf(self.config.spread_vol_elasticity);
+        if let Some(at) = self.last_jump {
+            let since = (self.time - at).num_milliseconds() as f64;
+            let decay = (1.0 - since / self.config.jump_spread_decay_ms.max(1) as f64).max(0.0);
+            spread *= 1.0 + (self.config.jump_spread_mult - 1.0).max(0.0) * decay;
+        }
+        spread
+    }
+
+    fn push(&mut self, price: f64, volume: f64) {
+        let side = match self.ticks.last() {
+            Some(prev) if price < prev.price => TradeSide::Sell,
+            _ => TradeSide::Buy,
+        };
+        let half_spread = price * self.spread_pct() / 200.0;
+        self.ticks.push(TradeTick {
+            timestamp: self.time,
+            symbol: self.symbol,
+            price,
+            volume,
+            side,
+            trade_id: self.ticks.len().to_string(),
+            best_bid: Some(price - half_spread),
+            best_ask: Some(price + half_spread),
+        });
+    }
+}
+
+/// xorshift64*: одинаковый на всех платформах и версиях зависимостей
+#[derive(Debug, Clone)]
+struct SynthRng(u64);
+
+impl SynthRng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// Равномерно в [0, 1)
+    fn uniform(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let x = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        p > 0.0 && self.uniform() < p
+    }
+
+    /// Стандартное нормальное (Бокс — Мюллер)
+    fn normal(&mut self) -> f64 {
+        let u = self.uniform().max(f64::MIN_POSITIVE);
+        let v = self.uniform();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn returns(ticks: &[TradeTick]) -> Vec<f64> {
+        ticks.windows(2).map(|w| (w[1].price / w[0].price).ln()).collect()
+    }
+
+    /// Автокорреляция квадратов доходностей с лагом 1
+    fn squared_autocorr(r: &[f64]) -> f64 {
+        let sq: Vec<f64> = r.iter().map(|x| x * x).collect();
+        let mean = sq.iter().sum::<f64>() / sq.len() as f64;
+        let var: f64 = sq.iter().map(|x| (x - mean).powi(2)).sum();
+        let cov: f64 = sq.windows(2).map(|w| (w[0] - mean) * (w[1] - mean)).sum();
+        cov / var
+    }
+
+    #[test]
+    fn test_same_seed_same_stream_and_garch_clusters() {
+        let hour = 3_600_000;
+        let generate = |config: SyntheticConfig| SyntheticGenerator::new("ETH_USDT", 100.0, config).unwrap().run(hour).build().0;
+        let calm = generate(SyntheticConfig::preset("calm").unwrap());
+        assert_eq!(calm.len(), 36_000);
+        let prices = |ticks: &[TradeTick]| ticks.iter().map(|t| t.price).collect::<Vec<_>>();
+        assert_eq!(prices(&calm), prices(&generate(SyntheticConfig::preset("calm").unwrap())));
+        let reseeded = SyntheticConfig { seed: 2, ..SyntheticConfig::preset("calm").unwrap() };
+        assert_ne!(prices(&calm), prices(&generate(reseeded)));
+
+        let clustered = generate(SyntheticConfig::preset("clustered").unwrap());
+        assert!(squared_autocorr(&returns(&calm)).abs() < 0.03);
+        assert!(squared_autocorr(&returns(&clustered)) > 0.05);
+        assert!(clustered.iter().all(|t| t.best_bid.unwrap() <= t.price && t.price <= t.best_ask.unwrap()));
+    }
+
+    #[test]
+    fn test_jumps_widen_spread_and_wicks_snap_back() {
+        let config = SyntheticConfig { jumps_per_hour: 30.0, wicks_per_hour: 30.0, ..SyntheticConfig::default() };
+        let (ticks, events) = SyntheticGenerator::new("ETH_USDT", 100.0, config).unwrap().run(3_600_000).build();
+        let spread = |t: &TradeTick| (t.best_ask.unwrap() - t.best_bid.unwrap()) / t.price * 100.0;
+        let (mut jumps, mut wicks) = (0, 0);
+        for event in &events {
+            match *event {
+                SyntheticEvent::Jump { index, pct } if index > 0 => {
+                    jumps += 1;
+                    let moved = (ticks[index].price / ticks[index - 1].price - 1.0) * 100.0;
+                    assert!((moved - pct).abs() < 0.5, "jump {}% moved {}%", pct, moved);
+                    assert!(spread(&ticks[index]) > 5.0 * spread(&ticks[index - 1]).min(0.02));
+                }
+                SyntheticEvent::Wick { index, pct } if index + 1 < ticks.len() => {
+                    wicks += 1;
+                    let away = (ticks[index].price / ticks[index - 1].price - 1.0) * 100.0;
+                    assert!((away - pct).abs() < 1e-9);
+                    let back = (ticks[index + 1].price / ticks[index - 1].price - 1.0) * 100.0;
+                    assert!(back.abs() < pct.abs() / 2.0);
+                }
+                _ => {}
+            }
+        }
+        assert!(jumps > 10 && wicks > 10, "{} jumps, {} wicks", jumps, wicks);
+
+        let bad = SyntheticConfig { garch_alpha: 0.2, garch_beta: 0.8, ..SyntheticConfig::default() };
+        assert!(SyntheticGenerator::new("ETH_USDT", 100.0, bad).is_err());
+        assert!(SyntheticConfig::preset("tsunami").is_err());
+    }
+}